@@ -0,0 +1,20 @@
+//! Feed arbitrary bytes to the parsers of the tape label, the binary header and the trace
+//! header. Run with `cargo fuzz run headers` from the root of the repository.
+#![no_main]
+use giga_segy_core::{BinHeader, SegySettings, TapeLabel, TraceHeader};
+use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TRACE_HEADER_LEN};
+use giga_segy_in::convert_headers::{HeaderFromBytes, TraceHeaderFromBytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let settings = SegySettings::default();
+    let _ = TapeLabel::from_bytes(&data[..data.len().min(TAPE_LABEL_LEN)], &settings);
+    let _ = TapeLabel::from_bytes(data, &settings);
+
+    // The binary header is followed by a trace header, which is parsed with it.
+    let (bin_bytes, trace_bytes) = data.split_at(data.len().min(BIN_HEADER_LEN));
+    if let Ok(bin_header) = BinHeader::from_bytes(bin_bytes, &settings) {
+        let trace_bytes = &trace_bytes[..trace_bytes.len().min(TRACE_HEADER_LEN)];
+        let _ = TraceHeader::from_bytes(trace_bytes, &bin_header, &settings, 0);
+    }
+});
@@ -14,7 +14,8 @@ pub type BitConverter = fn(&[u8]) -> Result<f32, TryFromSliceError>;
 ///
 /// The converter should be chosen once per trace (or better still once per file) for efficiency.
 /// Importantly the `le` argument determines whether the bytes converted are assumed to be little endian
-/// or big endian.
+/// or big endian. This also applies to IBM floats, which were always read as big endian before
+/// version 0.5.
 /// ```
 /// # use giga_segy_core::bitconverter::converter_chooser;
 /// # use giga_segy_core::enums::SampleFormatCode;
@@ -27,6 +28,14 @@ pub type BitConverter = fn(&[u8]) -> Result<f32, TryFromSliceError>;
 /// ```
 pub fn converter_chooser(format: SampleFormatCode, le: bool) -> Result<BitConverter, RsgError> {
     let f = match format {
+        SampleFormatCode::IbmFloat32 if le => {
+            fn x(input: &[u8]) -> Result<f32, TryFromSliceError> {
+                let mut bytes: [u8; 4] = input.try_into()?;
+                bytes.reverse();
+                Ok(f32::from(F32::from_be_bytes(bytes)))
+            }
+            x
+        }
         SampleFormatCode::IbmFloat32 => {
             fn x(input: &[u8]) -> Result<f32, TryFromSliceError> {
                 Ok(f32::from(F32::from_be_bytes(input.try_into()?)))
@@ -162,15 +171,73 @@ pub fn converter_chooser(format: SampleFormatCode, le: bool) -> Result<BitConver
                 msg: "Parsing of 24-bit integers is not implemented.".to_string(),
             });
         }
+        // NB: This format is obsolete and is only read on a best-effort basis for legacy files.
+        SampleFormatCode::FixPoint32 if le => {
+            fn x(input: &[u8]) -> Result<f32, TryFromSliceError> {
+                Ok(fix_point_32_to_f32(i32::from_le_bytes(input.try_into()?)))
+            }
+            x
+        }
         SampleFormatCode::FixPoint32 => {
-            return Err(RsgError::BitConversionError {
-                msg: "FixPoint32 are obsolete.".to_string(),
-            });
+            fn x(input: &[u8]) -> Result<f32, TryFromSliceError> {
+                Ok(fix_point_32_to_f32(i32::from_be_bytes(input.try_into()?)))
+            }
+            x
         }
     };
     Ok(f)
 }
 
+/// Interpret the bits of a 32-bit fixed point sample (sample format code 4) as a signed number
+/// with 16 integer and 16 fractional bits.
+///
+/// This format is obsolete and was used in different ways by old recording systems, so this is
+/// a best-effort interpretation for legacy files. It is not possible to write it.
+fn fix_point_32_to_f32(bits: i32) -> f32 {
+    (bits as f64 / 65536.0) as f32
+}
+
+/// Decode the bytes of samples in the given format to f32 values. Unlike the converters of
+/// [`converter_chooser`], this also reads 24-bit integers.
+/// ```
+/// # use giga_segy_core::bitconverter::decode_samples;
+/// # use giga_segy_core::enums::SampleFormatCode;
+/// let bytes = [0xFF, 0xFF, 0xFE, 0x00, 0x01, 0x00];
+/// let samples = decode_samples(&bytes, SampleFormatCode::Int24, false).unwrap();
+/// assert_eq!(samples, vec![-2., 256.]);
+/// ```
+pub fn decode_samples(
+    bytes: &[u8],
+    format: SampleFormatCode,
+    le: bool,
+) -> Result<Vec<f32>, RsgError> {
+    let samples = bytes.chunks_exact(format.datum_byte_length());
+    match format {
+        SampleFormatCode::Int24 | SampleFormatCode::UInt24 => Ok(samples
+            .map(|s| {
+                let bytes = if le {
+                    [0, s[2], s[1], s[0]]
+                } else {
+                    [0, s[0], s[1], s[2]]
+                };
+                let value = u32::from_be_bytes(bytes);
+                if format == SampleFormatCode::Int24 {
+                    // Shift the sign bit into place and back to extend it.
+                    ((value << 8) as i32 >> 8) as f32
+                } else {
+                    value as f32
+                }
+            })
+            .collect()),
+        _ => {
+            let converter = converter_chooser(format, le)?;
+            samples
+                .map(|s| converter(s).map_err(RsgError::from))
+                .collect()
+        }
+    }
+}
+
 /// A helper function to convert ascii null terminated to string.
 ///
 /// This function assumes that the string is ascii and will truncate it at the first null byte.
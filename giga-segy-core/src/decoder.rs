@@ -0,0 +1,140 @@
+//! This module contains the [`TraceDecoder`] trait, which lets the samples of a proprietary
+//! sample format be read like those of the standard formats.
+//!
+//! A decoder is registered for a sample format code with
+//! [`crate::SegySettings::register_custom_decoder`]. Then `giga-segy-in` uses it to work out the
+//! length of the traces and to convert their samples to f32 values, instead of the built-in
+//! conversions.
+use crate::errors::RsgError;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The size of a sample of a custom sample format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatumLen {
+    /// Each sample takes up the given number of bytes.
+    Bytes(usize),
+    /// The samples are packed with the given number of bits each, so that a sample may start
+    /// in the middle of a byte. The last byte of a trace is padded if needed.
+    Bits(usize),
+}
+
+impl DatumLen {
+    /// Get the number of bytes which `n_samples` samples take up.
+    /// ```
+    /// use giga_segy_core::decoder::DatumLen;
+    ///
+    /// assert_eq!(DatumLen::Bytes(2).byte_len(5), 10);
+    /// assert_eq!(DatumLen::Bits(12).byte_len(5), 8);
+    /// ```
+    pub fn byte_len(self, n_samples: usize) -> usize {
+        match self {
+            DatumLen::Bytes(n) => n_samples * n,
+            DatumLen::Bits(n) => (n_samples * n + 7) / 8,
+        }
+    }
+
+    /// Get the number of whole samples in `byte_len` bytes.
+    pub fn sample_count(self, byte_len: usize) -> usize {
+        match self {
+            DatumLen::Bytes(0) | DatumLen::Bits(0) => 0,
+            DatumLen::Bytes(n) => byte_len / n,
+            DatumLen::Bits(n) => byte_len * 8 / n,
+        }
+    }
+}
+
+/// Converts the samples of a custom sample format to f32 values.
+///
+/// NB: Decoders are shared between threads (eg. when traces are read in parallel), so they
+/// must be `Send` and `Sync`.
+/// ```
+/// use giga_segy_core::decoder::{DatumLen, TraceDecoder};
+/// use giga_segy_core::RsgError;
+///
+/// /// Samples which are stored as single bytes with the sign bit flipped.
+/// struct OffsetBinary;
+///
+/// impl TraceDecoder for OffsetBinary {
+///     fn datum_byte_length(&self) -> DatumLen {
+///         DatumLen::Bytes(1)
+///     }
+///
+///     fn decode(
+///         &self,
+///         bytes: &[u8],
+///         n_samples: usize,
+///         _le: bool,
+///         out: &mut Vec<f32>,
+///     ) -> Result<(), RsgError> {
+///         out.extend(bytes[..n_samples].iter().map(|b| *b as f32 - 128.));
+///         Ok(())
+///     }
+/// }
+///
+/// let mut out = Vec::new();
+/// OffsetBinary.decode(&[0, 128, 255], 3, false, &mut out).unwrap();
+/// assert_eq!(out, vec![-128., 0., 127.]);
+/// ```
+pub trait TraceDecoder: Send + Sync {
+    /// Get the size of a sample.
+    fn datum_byte_length(&self) -> DatumLen;
+
+    /// Convert the `n_samples` samples in `bytes` (the data of a whole trace, with `le` giving
+    /// the byte order of the file) to f32 values, and append them to `out`.
+    ///
+    /// The samples are not scaled afterwards with the trace weighting factor of the trace
+    /// header, but the user gain of the settings is applied.
+    fn decode(
+        &self,
+        bytes: &[u8],
+        n_samples: usize,
+        le: bool,
+        out: &mut Vec<f32>,
+    ) -> Result<(), RsgError>;
+}
+
+/// The custom decoders of a [`crate::SegySettings`], by sample format code.
+///
+/// Two tables are equal if they have the same decoders (not just equal ones) for the same
+/// codes.
+#[derive(Clone, Default)]
+pub struct CustomDecoders(BTreeMap<u16, Arc<dyn TraceDecoder>>);
+
+impl CustomDecoders {
+    pub(crate) fn insert(&mut self, code: u16, decoder: Arc<dyn TraceDecoder>) {
+        self.0.insert(code, decoder);
+    }
+
+    /// Get the decoder for a sample format code.
+    pub fn get(&self, code: u16) -> Option<&Arc<dyn TraceDecoder>> {
+        self.0.get(&code)
+    }
+
+    /// Check whether there are no decoders.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate through the sample format codes which have a decoder.
+    pub fn codes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.0.keys().copied()
+    }
+}
+
+impl std::fmt::Debug for CustomDecoders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.codes()).finish()
+    }
+}
+
+impl PartialEq for CustomDecoders {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(other.0.iter()).all(|((a, da), (b, db))| {
+                // Only the data pointers are compared, since the vtables may be duplicated.
+                a == b && Arc::as_ptr(da) as *const u8 == Arc::as_ptr(db) as *const u8
+            })
+    }
+}
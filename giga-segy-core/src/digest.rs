@@ -0,0 +1,111 @@
+//! This module contains the hash function which is used to compute digests of trace data, so
+//! that the integrity of the data can be checked trace by trace (eg. after a file has been
+//! transferred), independently of the headers.
+//!
+//! The hash is XXH64 with a seed of zero, which is fast and well known, so digests can also be
+//! computed with other tools.
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+/// Compute the XXH64 hash (with a seed of zero) of a slice of bytes.
+/// ```
+/// use giga_segy_core::digest::digest_bytes;
+///
+/// assert_eq!(digest_bytes(b""), 0xEF46_DB37_51D8_E999);
+/// assert_eq!(digest_bytes(b"abc"), 0x44BC_2CF5_AD77_0999);
+/// assert_eq!(
+///     digest_bytes(b"Nobody inspects the spammish repetition"),
+///     0xFBCE_A83C_8A37_8BF1
+/// );
+/// ```
+pub fn digest_bytes(bytes: &[u8]) -> u64 {
+    let len = bytes.len() as u64;
+    let mut stripes = bytes.chunks_exact(32);
+    let mut hash = if bytes.len() >= 32 {
+        let mut acc = [
+            PRIME_1.wrapping_add(PRIME_2),
+            PRIME_2,
+            0,
+            0u64.wrapping_sub(PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (a, lane) in acc.iter_mut().zip(stripe.chunks_exact(8)) {
+                *a = round(*a, read_u64(lane));
+            }
+        }
+        let mut hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        for a in acc.iter() {
+            hash = merge_round(hash, *a);
+        }
+        hash
+    } else {
+        PRIME_5
+    };
+    hash = hash.wrapping_add(len);
+
+    let mut rest = stripes.remainder();
+    while rest.len() >= 8 {
+        hash ^= round(0, read_u64(&rest[..8]));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(PRIME_1)
+            .wrapping_add(PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        let lane = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as u64;
+        hash ^= lane.wrapping_mul(PRIME_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(PRIME_2)
+            .wrapping_add(PRIME_3);
+        rest = &rest[4..];
+    }
+    for b in rest.iter() {
+        hash ^= (*b as u64).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^= hash >> 32;
+    hash
+}
+
+/// Combine the digests of the traces of a file (in the order of the traces) into a single
+/// digest. This is the digest of the little endian bytes of all the trace digests.
+pub fn combine_digests<I: IntoIterator<Item = u64>>(digests: I) -> u64 {
+    let bytes = digests
+        .into_iter()
+        .flat_map(|d| d.to_le_bytes())
+        .collect::<Vec<u8>>();
+    digest_bytes(&bytes)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut lane = [0; 8];
+    lane.copy_from_slice(bytes);
+    u64::from_le_bytes(lane)
+}
+
+fn round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME_1)
+}
+
+fn merge_round(hash: u64, acc: u64) -> u64 {
+    (hash ^ round(0, acc))
+        .wrapping_mul(PRIME_1)
+        .wrapping_add(PRIME_4)
+}
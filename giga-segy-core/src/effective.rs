@@ -0,0 +1,176 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This module contains the [`EffectiveParams`], which record the values that were actually
+//! used to interpret a file when it was read, after the overrides of the [`SegySettings`] were
+//! applied, and where each of them came from.
+use crate::enums::{EndianDetection, MeasurementSystem, SampleFormatCode, TraceIdCode};
+use crate::settings::SegySettings;
+use crate::{BinHeader, BIN_HEADER_LEN};
+use crate::{CDPX_BYTE_LOCATION, CDPY_BYTE_LOCATION};
+use crate::{CROSSLINE_BYTE_LOCATION, INLINE_BYTE_LOCATION};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Where a value which was used to interpret a file came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Provenance {
+    /// The value was given by the file (eg. by the binary header or the trace headers).
+    File,
+    /// The value was given by the settings.
+    Override,
+    /// The file did not give the value (or it was not trusted), so it was guessed or the
+    /// default of the standard was used.
+    Fallback,
+}
+
+/// A value which was used to interpret a file, and where it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Resolved<T> {
+    pub value: T,
+    pub source: Provenance,
+}
+
+impl<T> Resolved<T> {
+    fn new(value: T, source: Provenance) -> Self {
+        Resolved { value, source }
+    }
+}
+
+/// The values which were used to interpret a file when it was read, after the overrides of the
+/// [`SegySettings`] were applied.
+///
+/// Values which are read from every trace header unless they are overridden (such as the
+/// coordinate scaling) are `None` if they were not overridden.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EffectiveParams {
+    /// Whether the file was read as little endian.
+    pub is_le: Resolved<bool>,
+    /// The format of the samples. This is a [`Provenance::Fallback`] if the format of the binary
+    /// header was replaced because of the size of the file (see
+    /// [`SegySettings::set_trust_file_size`]), and a [`Provenance::Override`] if the samples are
+    /// read with a custom decoder (see [`SegySettings::register_custom_decoder`]).
+    pub sample_format: Resolved<SampleFormatCode>,
+    /// The format of the coordinates in the trace headers. This comes from the file if it was
+    /// recorded in the extended text headers.
+    pub coordinate_format: Resolved<SampleFormatCode>,
+    /// The units of the coordinates.
+    pub coordinate_units: Resolved<MeasurementSystem>,
+    /// The coordinate scalar, or `None` if the coordinate scalar of each trace header was used
+    /// (see [`SegySettings::set_override_coordinate_scaling`]).
+    pub coordinate_scaling: Resolved<Option<f64>>,
+    /// The units of the depths in the trace headers, or `None` if the units of the trace
+    /// headers were used.
+    pub trace_depth_units: Resolved<Option<MeasurementSystem>>,
+    /// The trace identification code, or `None` if the code of each trace header was used.
+    pub trace_id_code: Resolved<Option<TraceIdCode>>,
+    /// The sample interval in microseconds (or millimetres, or thousandths of a foot), or zero
+    /// if it is not known.
+    pub sample_interval: Resolved<f64>,
+    /// The number of samples of each trace. If the traces do not have a fixed length (see
+    /// [`Self::sample_count_per_trace`]), this is the number of samples of the binary header,
+    /// and the number of samples of each trace comes from its trace header. An override is
+    /// the largest number of samples which is read from each trace.
+    pub no_samples: Resolved<usize>,
+    /// Whether the number of samples was read from each trace header, because the traces do
+    /// not have a fixed length.
+    pub sample_count_per_trace: bool,
+    /// The position of the inline number in the trace header. The standard position is a
+    /// [`Provenance::Fallback`].
+    pub inline_no_bidx: Resolved<usize>,
+    /// The position of the crossline number in the trace header.
+    pub crossline_no_bidx: Resolved<usize>,
+    /// The position of the X coordinate of the ensemble in the trace header.
+    pub x_ensemble_bidx: Resolved<usize>,
+    /// The position of the Y coordinate of the ensemble in the trace header.
+    pub y_ensemble_bidx: Resolved<usize>,
+}
+
+impl EffectiveParams {
+    /// Record how the parameters were resolved when a file was read with `settings`, where
+    /// `bin_header` is the binary header as it was used, `raw_bin_header` its bytes in the file
+    /// and `coordinate_format_in_file` whether the coordinate format of the settings was taken
+    /// from the extended text headers.
+    pub fn resolve(
+        settings: &SegySettings,
+        bin_header: &BinHeader,
+        raw_bin_header: &[u8; BIN_HEADER_LEN],
+        coordinate_format_in_file: bool,
+    ) -> Self {
+        use Provenance::*;
+        let overridden = |o: bool| if o { Override } else { File };
+        let flag_given = matches!(raw_bin_header[96..100], [1, 2, 3, 4] | [4, 3, 2, 1]);
+        let le_source = match settings.get_endianness_detection() {
+            _ if settings.get_override_to_le().is_some() => Override,
+            EndianDetection::ForceLe | EndianDetection::ForceBe => Override,
+            _ if flag_given => File,
+            _ => Fallback,
+        };
+
+        let sample_format = bin_header.sample_format_code;
+        let raw_format = bin_header.raw_sample_format_code;
+        let sample_format_source = if settings.get_override_trace_format().is_some()
+            || settings.get_custom_decoder(raw_format).is_some()
+        {
+            Override
+        } else if sample_format as u16 == raw_format {
+            File
+        } else {
+            Fallback
+        };
+
+        let coordinate_format = match settings.get_override_coordinate_format() {
+            Some(f) if coordinate_format_in_file => Resolved::new(f, File),
+            Some(f) => Resolved::new(f, Override),
+            None => Resolved::new(SampleFormatCode::Int32, Fallback),
+        };
+
+        let sample_interval = match settings.get_override_sample_interval() {
+            Some(interval) => Resolved::new(interval, Override),
+            None => match bin_header.sample_interval_us() {
+                interval if interval > 0. => Resolved::new(interval, File),
+                interval => Resolved::new(interval, Fallback),
+            },
+        };
+
+        let no_samples = match settings.get_override_dim_z() {
+            Some(dim_z) => Resolved::new(dim_z as usize, Override),
+            None => Resolved::new(bin_header.sample_count(), File),
+        };
+
+        let bidx = |bidx: usize, standard: usize| {
+            Resolved::new(bidx, if bidx == standard { Fallback } else { Override })
+        };
+
+        EffectiveParams {
+            is_le: Resolved::new(bin_header.binary_flag_direction_is_le, le_source),
+            sample_format: Resolved::new(sample_format, sample_format_source),
+            coordinate_format,
+            coordinate_units: Resolved::new(
+                bin_header.measurement_system,
+                overridden(settings.get_override_coordinate_units().is_some()),
+            ),
+            coordinate_scaling: Resolved::new(
+                settings.get_override_coordinate_scaling(),
+                overridden(settings.get_override_coordinate_scaling().is_some()),
+            ),
+            trace_depth_units: Resolved::new(
+                settings.get_override_trace_depth_units(),
+                overridden(settings.get_override_trace_depth_units().is_some()),
+            ),
+            trace_id_code: Resolved::new(
+                settings.get_override_trace_id_code(),
+                overridden(settings.get_override_trace_id_code().is_some()),
+            ),
+            sample_interval,
+            no_samples,
+            sample_count_per_trace: !bin_header.fixed_length_trace_flag.yes(),
+            inline_no_bidx: bidx(settings.get_inline_no_bidx(), INLINE_BYTE_LOCATION),
+            crossline_no_bidx: bidx(settings.get_crossline_no_bidx(), CROSSLINE_BYTE_LOCATION),
+            x_ensemble_bidx: bidx(settings.get_x_ensemble_bidx(), CDPX_BYTE_LOCATION),
+            y_ensemble_bidx: bidx(settings.get_y_ensemble_bidx(), CDPY_BYTE_LOCATION),
+        }
+    }
+}
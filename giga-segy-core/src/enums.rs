@@ -19,11 +19,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::errors::*;
 
-/// Choose which of the header lines to count traces by.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+/// Choose which of the header lines to count traces by (see
+/// [`crate::SegySettings::set_order_trace_by`]) or to sort them by (see
+/// [`crate::SegySettings::set_sort_traces_by`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OrderTraceBy {
+    /// The position of the trace in the file.
     Default = 1,
     TraceSequenceOnLine = 2,
     TraceSequenceInFile = 3,
@@ -32,15 +35,168 @@ pub enum OrderTraceBy {
     TraceNoInEnsemble = 6,
 }
 
+impl OrderTraceBy {
+    /// Get the value of the trace header which is counted by, or `None` for
+    /// [`OrderTraceBy::Default`].
+    pub fn header_value(self, header: &crate::TraceHeader) -> Option<i32> {
+        match self {
+            OrderTraceBy::Default => None,
+            OrderTraceBy::TraceSequenceOnLine => Some(header.trace_sequence_on_line),
+            OrderTraceBy::TraceSequenceInFile => Some(header.trace_sequence_in_file),
+            OrderTraceBy::FieldRecordNo => Some(header.field_record_no),
+            OrderTraceBy::TraceNo => Some(header.trace_no),
+            OrderTraceBy::TraceNoInEnsemble => Some(header.trace_no_in_ensemble),
+        }
+    }
+}
+
+/// Choose how the byte order of a file is determined when it is read.
+///
+/// NB: An override set with [`crate::SegySettings::set_override_to_le`] always takes precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EndianDetection {
+    /// The file is little endian only if bytes 3297-3300 of the binary header are `1, 2, 3, 4`.
+    TrustFlag,
+    /// Bytes 3297-3300 of the binary header are used if they are `1, 2, 3, 4` or `4, 3, 2, 1`.
+    /// Otherwise the sample format code, sample count and sample interval are read in both byte
+    /// orders and the order in which they make the most sense is used. In case of a tie the file
+    /// is assumed to be big endian.
+    Heuristic,
+    /// The file is always read as little endian.
+    ForceLe,
+    /// The file is always read as big endian.
+    ForceBe,
+}
+
+/// Choose how the text header of a file is decoded when it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TextEncoding {
+    /// The header is read as ASCII if all of its bytes are printable ASCII characters, and as
+    /// EBCDIC otherwise. The text header of a file which is read with this setting is recorded
+    /// as [`TextEncoding::Ascii`] or [`TextEncoding::Ebcdic`], depending on which was used.
+    Auto,
+    /// The bytes are read as they are. Bytes which are not valid UTF-8 are replaced with
+    /// `U+FFFD`.
+    Ascii,
+    /// The bytes are converted from EBCDIC to ASCII. Bytes which have no ASCII equivalent are
+    /// replaced with `U+FFFD`.
+    Ebcdic,
+    /// Each byte is read as the character with the same code point (ie. as Latin-1), so that no
+    /// byte is lost and the header is not cut off at a zero byte.
+    Raw,
+}
+
+/// How the lines (card images) of a text header were found when it was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TextHeaderLayout {
+    /// The header is split into cards of eighty characters each, as in the standard. Any stray
+    /// carriage returns or line feeds are replaced with spaces, so the cards keep their width.
+    FixedWidth,
+    /// The lines of the header end with line feeds (or carriage returns and line feeds), as in
+    /// some headers which were edited by hand. The header is split at the line feeds, and each
+    /// line is padded with spaces or cut off to eighty characters.
+    LineTerminated,
+}
+
+/// Choose what happens when a coordinate in a trace header cannot be converted to an integer
+/// (eg. because the coordinate format is overridden to [`SampleFormatCode::Float32`] and the
+/// bytes are NaN).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CoordErrorPolicy {
+    /// Opening the file fails with [`crate::RsgError::CoordinateParse`].
+    Fail,
+    /// The coordinate is set to zero and a warning is recorded.
+    ZeroAndWarn,
+    /// The trace is left out and a warning is recorded.
+    SkipTrace,
+}
+
+/// Choose which traces are kept when a file is read, depending on whether they are auxiliary
+/// traces (see [`TraceIdCode::is_auxiliary`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AuxPolicy {
+    /// All traces are kept.
+    Include,
+    /// The auxiliary traces are left out.
+    Exclude,
+    /// Only the auxiliary traces are kept.
+    Only,
+}
+
+/// Choose how much of a file is brought into memory when it is mapped (see
+/// [`crate::SegySettings::set_map_population`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MapPopulation {
+    /// The pages of the file are read when they are first accessed.
+    Lazy,
+    /// The pages of the file are read when it is mapped (as far as the platform supports it).
+    Populate,
+    /// The pages of the file are read when it is mapped, and locked in memory so that they are
+    /// never paged out.
+    Lock,
+}
+
+/// Where the inline and crossline numbers of the traces of a file come from when it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineNumberingSource {
+    /// The line numbers are read from the trace headers at the given byte indices.
+    TraceHeaders {
+        inline_no_bidx: usize,
+        crossline_no_bidx: usize,
+    },
+    /// The line numbers are derived from a custom crossline count (see
+    /// [`crate::SegySettings::set_override_dim_x`]) and the count of each trace (see
+    /// [`crate::SegySettings::set_order_trace_by`]). The values in the trace headers are kept
+    /// with each trace (see [`crate::Trace::original_inline_no`]).
+    Synthesized {
+        dim_x: i32,
+        order_trace_by: OrderTraceBy,
+    },
+}
+
+/// The domain and unit of the vertical axis of a file, along with the sample interval in that
+/// unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VerticalAxis {
+    /// Time, with the sample interval in milliseconds.
+    TimeMs { interval: f64 },
+    /// Depth, with the sample interval in metres.
+    DepthMeters { interval: f64 },
+    /// Depth, with the sample interval in feet.
+    DepthFeet { interval: f64 },
+    /// The vertical axis could not be determined.
+    Unknown,
+}
+
+impl VerticalAxis {
+    /// Get the sample interval in milliseconds, metres or feet, if the axis is known.
+    pub fn interval(self) -> Option<f64> {
+        match self {
+            Self::TimeMs { interval }
+            | Self::DepthMeters { interval }
+            | Self::DepthFeet { interval } => Some(interval),
+            Self::Unknown => None,
+        }
+    }
+}
+
 /// From bytes 3225-3226  (25-26) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SampleFormatCode {
     IbmFloat32 = 1,
     Int32 = 2,
     Int16 = 3,
-    FixPoint32 = 4, //Obsolete.
+    FixPoint32 = 4, //Obsolete. Read as 16.16 fixed point, but not written.
     Float32 = 5,
     Float64 = 6,
     Int24 = 7,
@@ -87,10 +243,15 @@ impl SampleFormatCode {
             Self::UInt8 => 1,
         }
     }
+
+    /// Check whether the samples are integers (or fixed point numbers), rather than floats.
+    pub fn is_integer(self) -> bool {
+        !matches!(self, Self::IbmFloat32 | Self::Float32 | Self::Float64)
+    }
 }
 
 /// From bytes 3229-3230 (29-30) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TraceSortingCode {
@@ -116,7 +277,7 @@ impl TraceSortingCode {
 }
 
 /// From bytes 3239-3240 (39-40) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SweepTypeCode {
@@ -137,7 +298,7 @@ impl SweepTypeCode {
 
 /// From bytes 3247-3248 (47-48) of the binary header.
 /// Also in bytes 139-140 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TaperType {
@@ -156,7 +317,7 @@ impl TaperType {
 }
 
 /// 3249-3250 (49-50) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CorrelatedDataTraces {
@@ -174,7 +335,7 @@ impl CorrelatedDataTraces {
 }
 
 /// From bytes 3251-3252 (51-52) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BinaryGainRecovered {
@@ -192,7 +353,7 @@ impl BinaryGainRecovered {
 }
 
 /// From bytes 3253-3254 (53-54) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AmplitudeRecoveryMethod {
@@ -212,7 +373,7 @@ impl AmplitudeRecoveryMethod {
 }
 
 /// From bytes 3255-3256 (55-56) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MeasurementSystem {
@@ -230,7 +391,7 @@ impl MeasurementSystem {
 }
 
 /// From bytes 3257-3258 (57-58) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ImpulseSignalPolarity {
@@ -248,7 +409,7 @@ impl ImpulseSignalPolarity {
 }
 
 /// From bytes 3259-3260 (59-60) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VibratoryPolarityCode {
@@ -272,7 +433,7 @@ impl VibratoryPolarityCode {
 }
 
 /// From bytes 3503-3504 (303-304) of the binary header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FixedLengthTraces {
@@ -301,7 +462,7 @@ impl FixedLengthTraces {
 
 /// From bytes 3511-3512 (311-312) of the binary header.
 /// Alternatively bytes 167-168 of a standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TimeBasisCode {
@@ -322,7 +483,7 @@ impl TimeBasisCode {
 }
 
 /// From bytes 29-30 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TraceIdCode {
@@ -377,10 +538,42 @@ impl TraceIdCode {
     pub fn new(source: i16) -> Self {
         Self::from_i16(source).unwrap_or(Self::Invalid)
     }
+
+    /// Check whether this code stands for seismic data recorded in time (including the
+    /// components of multicomponent and vibrator data).
+    pub fn is_time_domain(self) -> bool {
+        let code = self as i16;
+        self == Self::TimeDomainSeismic
+            || (Self::SeismicPressureSensor as i16..=Self::VibratorReference as i16).contains(&code)
+    }
+
+    /// Check whether this code stands for seismic data in depth.
+    pub fn is_depth_domain(self) -> bool {
+        self == Self::DepthDomainSeismic
+    }
+
+    /// Check whether this code stands for an auxiliary channel which is recorded along with
+    /// the seismic data of a field record: A time break, uphole, sweep, timing, water break or
+    /// gun signature, or one of the vibrator signals (codes 4-10 and 18-21).
+    ///
+    /// Dead and dummy traces, and traces with an unknown or invalid code, are not auxiliary.
+    /// ```
+    /// use giga_segy_core::enums::TraceIdCode;
+    ///
+    /// assert!(TraceIdCode::Sweep.is_auxiliary());
+    /// assert!(TraceIdCode::VibratorBaseplate.is_auxiliary());
+    /// assert!(!TraceIdCode::TimeDomainSeismic.is_auxiliary());
+    /// assert!(!TraceIdCode::Dead.is_auxiliary());
+    /// ```
+    pub fn is_auxiliary(self) -> bool {
+        let code = self as i16;
+        (Self::TimeBreak as i16..=Self::FarFieldGunSig as i16).contains(&code)
+            || (Self::VibratorReactionMass as i16..=Self::VibratorReference as i16).contains(&code)
+    }
 }
 
 /// From bytes 35-36 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DataUse {
@@ -398,7 +591,7 @@ impl DataUse {
 }
 
 /// From bytes 89-90 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CoordinateUnits {
@@ -418,7 +611,7 @@ impl CoordinateUnits {
 }
 
 ///From bytes 119-120 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GainType {
@@ -437,7 +630,7 @@ impl GainType {
 }
 
 // From bytes 125-126 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Correlated {
@@ -455,7 +648,7 @@ impl Correlated {
 }
 
 /// From bytes 133-134 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SweepType {
@@ -479,7 +672,7 @@ impl SweepType {
 // Time Basis code is covered above. (but is found in bytes 157-158 of the STH)
 
 /// Found in bytes 179-180 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OverTravel {
@@ -497,7 +690,7 @@ impl OverTravel {
 }
 
 /// Found in bytes 203-204 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TraceValueUnit {
@@ -523,7 +716,7 @@ impl TraceValueUnit {
 }
 
 /// Found in bytes 211-212 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransductionUnits {
@@ -549,7 +742,7 @@ impl TransductionUnits {
 }
 
 /// Found in bytes 217-218 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SourceType {
@@ -574,7 +767,7 @@ impl SourceType {
 }
 
 /// Found in bytes 231-232 of the standard trace header.
-#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SourceMeasurementUnit {
@@ -1,7 +1,12 @@
 use crate::enums::SampleFormatCode;
 
 /// Basic Error types.
+///
+/// Each variant has a numeric code (see [`RsgError::code`]) which does not change, so that
+/// errors can be told apart without relying on their messages (eg. through a C FFI). New
+/// variants may be added in minor releases, so matches must have a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum RsgError {
     /// TryFromSliceError from the std library.
     TryFromSlice(std::array::TryFromSliceError),
@@ -21,12 +26,22 @@ pub enum RsgError {
         float: f32,
         format: SampleFormatCode,
     },
-    /// SEG-Y is too short for a different reason..
-    IncompleteTrace,
+    /// A coordinate of a trace header could not be converted to an integer. This contains the
+    /// index of the trace, the position of the coordinate in the trace header and its bytes.
+    CoordinateParse {
+        trace: usize,
+        offset: usize,
+        bytes: [u8; 4],
+    },
+    /// SEG-Y is too short for a different reason. This contains the index of the trace which
+    /// is incomplete.
+    IncompleteTrace { trace: usize },
     /// Trace not found.
     TraceNotFound { i: usize },
     /// Trace point out of bounds.
     TracePointOutOfBounds { idx: usize },
+    /// There are no traces with the given inline or crossline number.
+    LineNotFound { line: i32 },
     /// SEG-Y is too short.
     SEGYTooShort,
     /// Your SEG-Y is too short.
@@ -43,9 +58,18 @@ pub enum RsgError {
     BitConversionError { msg: String },
     /// An error caused by an invalid header.
     InvalidHeader { msg: String },
-    /// When the data vector length exceeds 65535 data points.
-    LongDataVector { l_data: usize },
+    /// The data vector has more samples than the headers of the file can count. This contains
+    /// the limit (`max`) and the `reason` for it: 65535 samples before SEG-Y rev 2, and
+    /// [`u32::MAX`] with the extended sample counts of SEG-Y rev 2.
+    LongDataVector {
+        l_data: usize,
+        max: usize,
+        reason: &'static str,
+    },
     /// Thrown when the data vector length does not match that declared in headers.
+    ///
+    /// NB: `giga-segy-out` now returns [`RsgError::FixedLengthMismatch`] or
+    /// [`RsgError::TraceSampleCountMismatch`] instead.
     BadDataVector {
         l_data: u16,
         l_bin: u16,
@@ -53,8 +77,91 @@ pub enum RsgError {
     },
     /// Enum creation error.
     ParseEnum { f: String, code: u16 },
+    /// A header field has a value which cannot be written to a file.
+    HeaderFieldEncode { field: &'static str },
+    /// The size of the trace data does not fit the declared sample format, but it would fit
+    /// another one.
+    FormatSizeMismatch {
+        declared: SampleFormatCode,
+        implied_by_size: SampleFormatCode,
+    },
+    /// More traces were added to a file than the binary header can count.
+    TooManyTraces { max: usize },
+    /// The operation was stopped by a progress callback.
+    Cancelled,
     /// Map file error (this is just a `std::io` error when mapping files).
     MapFile(std::io::Error),
+    /// The binary header declares fixed length traces, but the data of a trace has a different
+    /// number of samples. This contains the index of the trace.
+    FixedLengthMismatch {
+        trace: usize,
+        l_data: usize,
+        l_bin: u32,
+    },
+    /// The data of a trace does not have the number of samples given in its trace header, in a
+    /// file with variable length traces. This contains the index of the trace.
+    TraceSampleCountMismatch {
+        trace: usize,
+        l_data: usize,
+        l_trace: u16,
+    },
+    /// The file (of `len` bytes) cannot be mapped to memory, because it is larger than the
+    /// address space of the platform (eg. on 32-bit targets), or than the mapping budget of the
+    /// settings (see [`crate::SegySettings::set_mapping_budget`]).
+    FileTooLargeForPlatform { len: u64 },
+    /// The string which was given to be written between the header and the data of a trace does
+    /// not fill the trace header extensions which the binary header declares (`expected` bytes).
+    /// This contains the index of the trace.
+    TraceExtensionLength {
+        trace: usize,
+        len: usize,
+        expected: usize,
+    },
+    /// The data of a trace starts with `text_len` bytes of text and the traces after it could not
+    /// be found, which suggests that there is a payload between the trace headers and the data
+    /// (see [`crate::SegySettings::set_trace_prefix_bytes`]). This contains the index of the
+    /// trace.
+    TextInTraceData { trace: usize, text_len: usize },
+    /// A trace cannot be written to the cell `[i, j]` of a regular grid, because the cell is
+    /// outside of the grid or already has a trace.
+    InvalidCell { cell: [usize; 2], msg: String },
+    /// `count` cells of a regular grid have no trace when the grid is finished. This contains
+    /// the first of them.
+    MissingCells { count: usize, first: [usize; 2] },
+    /// The sample with index `idx` of a trace (with the value `value` after scaling) cannot be
+    /// represented by the numeric type it was requested as.
+    SampleConversion { idx: usize, value: f64 },
+    /// Reading the data would need `bytes` bytes of memory, which is more than the `budget`
+    /// which was given for it.
+    AllocationOverBudget { bytes: usize, budget: usize },
+    /// The trace with index `trace` has `samples` samples, while the traces before it have
+    /// `expected`, and the traces were to be read as a matrix without padding.
+    RaggedTraces {
+        trace: usize,
+        samples: usize,
+        expected: usize,
+    },
+    /// The binary header gives a SEG-Y revision (`major.minor`) which is not supported, and the
+    /// settings ask for the revision to be checked (see
+    /// [`crate::SegySettings::set_strict_revision`]).
+    UnsupportedRevision { major: u8, minor: u8 },
+    /// The time window (in milliseconds) which was to be read from the trace with index
+    /// `trace` is not within the times of its samples (`extent`), and the settings ask for
+    /// such windows to be rejected (see [`crate::SegySettings::set_strict_time_window`]).
+    TimeWindowOutOfBounds {
+        trace: usize,
+        window: [f64; 2],
+        extent: [f64; 2],
+    },
+    /// The file which was to be written already exists, and it was not to be overwritten.
+    OutputExists { path: std::path::PathBuf },
+    /// The binary header gives more extended text headers (`count`) than can be in the file,
+    /// or than the settings allow (`max` is the smaller of the two, see
+    /// [`crate::SegySettings::set_max_extended_headers`]).
+    ImplausibleExtendedHeaderCount { count: u32, max: u32 },
+    /// The inline and crossline numbers of the traces span a grid with `cells` cells, which is
+    /// more than the `max` that is allocated for a slice or section of the survey.
+    GridTooLarge { cells: u64, max: u64 },
     #[cfg(feature = "to_json")]
     /// Serialisation/Deserialisation error.
     SerdeError(serde_json::Error),
@@ -85,21 +192,204 @@ impl std::fmt::Display for RsgError {
             SEGYSettingsError { msg } => write!(fmt, "Error in settings: {}", msg),
             FileTooShort => write!(fmt, "File is too short to be SEG-Y"),
             FloatConversion { float, format } => write!(fmt, "Could not convert {} to {}.", float, format),
-            IncompleteTrace => write!(fmt, "Last trace incomplete: File may be corrupt."),
+            CoordinateParse { trace, offset, bytes } => write!(fmt, "Could not convert the coordinate in bytes {}-{} of trace {} ({:02X?}) to an integer.", offset + 1, offset + 4, trace, bytes),
+            IncompleteTrace { trace } => write!(fmt, "Last trace (no. {}) incomplete: File may be corrupt.", trace),
             TraceNotFound { i } => write!(fmt, "Trace  no. {} not found.", i),
             TracePointOutOfBounds { idx } => write!(fmt, "Error getting trace: Idx ({}) trace point is out of bounds.", idx),
+            LineNotFound { line } => write!(fmt, "No traces found for line no. {}.", line),
             SEGYTooShort => write!(fmt, "Mapped file is too short to be a SEG-Y file, or too many Extended Text Headers are counted"),
             ShortSEGY { a, b } => write!(fmt, "Error getting trace: SEG-Y Mapping is too short (is {}-bytes, needs to be {}-bytes)", a, b),
             TraceDivisibility { a, b, format } => write!(fmt, "Error getting trace: data binary length ({}) not divisible by datum length ({}-bit ({}))", a, b, format),
             TraceHeaderLength { l } => write!(fmt, "Trace header length should be 240 but is {}", l),
             BitConversionError { msg } => write!(fmt, "Bit conversion failed: {}", msg),
             InvalidHeader { msg } => write!(fmt, "Invalid header: {}", msg),
-            LongDataVector { l_data } => write!(fmt, "Data vector has {} points, but max length is 65535.", l_data),
+            LongDataVector { l_data, max, reason } => write!(fmt, "Data vector has {} points, but max length is {}, since {}.", l_data, max, reason),
             BadDataVector { l_data, l_bin, l_trace } => write!(fmt, "Data length is {}, but was declared as {} (binary header) or {} (trace header).", l_data, l_bin, l_trace),
             ParseEnum { f, code } => write!(fmt, "Could not parse source ({}) to {}.", code, f),
+            HeaderFieldEncode { field } => write!(fmt, "Header field `{}` has a value which cannot be written.", field),
+            FormatSizeMismatch { declared, implied_by_size } => write!(fmt, "The sample format is declared as {}, but the file size implies {}.", declared, implied_by_size),
+            TooManyTraces { max } => write!(fmt, "A file can only have {} traces unless the extended trace count is enabled.", max),
+            Cancelled => write!(fmt, "The operation was cancelled."),
             MapFile(e) => write!(fmt, "Could not create file map: {}", e),
+            FixedLengthMismatch { trace, l_data, l_bin } => write!(fmt, "Trace {} has {} samples, but the binary header declares fixed length traces with {} samples.", trace, l_data, l_bin),
+            TraceSampleCountMismatch { trace, l_data, l_trace } => write!(fmt, "Trace {} has {} samples, but its trace header declares {}.", trace, l_data, l_trace),
+            FileTooLargeForPlatform { len } => write!(fmt, "The file is too large ({} bytes) to be mapped to memory on this platform.", len),
+            TraceExtensionLength { trace, len, expected } => write!(fmt, "The extended header of trace {} has {} bytes, but the trace header extensions of the binary header take {} bytes.", trace, len, expected),
+            TextInTraceData { trace, text_len } => write!(fmt, "The data of trace {} starts with {} bytes of text, and the traces after it could not be found. The file was probably written with an extended header for each trace which is not declared in the binary header, so it may be read with `SegySettings::set_trace_prefix_bytes`.", trace, text_len),
+            InvalidCell { cell, msg } => write!(fmt, "Cannot write a trace to the cell ({}, {}) of the grid: {}", cell[0], cell[1], msg),
+            MissingCells { count, first } => write!(fmt, "{} cells of the grid have no trace, starting with ({}, {}).", count, first[0], first[1]),
+            SampleConversion { idx, value } => write!(fmt, "Sample {} of the trace ({}) cannot be converted to the requested type.", idx, value),
+            AllocationOverBudget { bytes, budget } => write!(fmt, "Reading the data needs {} bytes of memory, but the budget is {} bytes.", bytes, budget),
+            RaggedTraces { trace, samples, expected } => write!(fmt, "Trace {} has {} samples, but the traces before it have {}.", trace, samples, expected),
+            UnsupportedRevision { major, minor } => write!(fmt, "SEG-Y revision {}.{} is not supported.", major, minor),
+            TimeWindowOutOfBounds { trace, window, extent } => write!(fmt, "The time window from {} ms to {} ms is not within trace {}, which runs from {} ms to {} ms.", window[0], window[1], trace, extent[0], extent[1]),
+            OutputExists { path } => write!(fmt, "The output file {} already exists.", path.display()),
+            ImplausibleExtendedHeaderCount { count, max } => write!(fmt, "The binary header gives {} extended text headers, but there can be no more than {}.", count, max),
+            GridTooLarge { cells, max } => write!(fmt, "The grid of inline and crossline numbers has {} cells, but there can be no more than {}.", cells, max),
+        }
+    }
+}
+
+impl RsgError {
+    /// Get the numeric code of the error. The codes are stable, so they can be used instead of
+    /// the messages to tell errors apart (eg. through a C FFI):
+    ///
+    /// | Code | Variant |
+    /// |------|---------|
+    /// | 1 | [`RsgError::TryFromSlice`] |
+    /// | 2 | [`RsgError::TryFromUtf8`] |
+    /// | 3 | [`RsgError::StdIoError`] |
+    /// | 4 | [`RsgError::BinHeaderLength`] |
+    /// | 5 | [`RsgError::SEGYSettingsError`] |
+    /// | 6 | [`RsgError::FileTooShort`] |
+    /// | 7 | [`RsgError::FloatConversion`] |
+    /// | 8 | [`RsgError::CoordinateParse`] |
+    /// | 9 | [`RsgError::IncompleteTrace`] |
+    /// | 10 | [`RsgError::TraceNotFound`] |
+    /// | 11 | [`RsgError::TracePointOutOfBounds`] |
+    /// | 12 | [`RsgError::LineNotFound`] |
+    /// | 13 | [`RsgError::SEGYTooShort`] |
+    /// | 14 | [`RsgError::ShortSEGY`] |
+    /// | 15 | [`RsgError::TraceDivisibility`] |
+    /// | 16 | [`RsgError::TraceHeaderLength`] |
+    /// | 17 | [`RsgError::BitConversionError`] |
+    /// | 18 | [`RsgError::InvalidHeader`] |
+    /// | 19 | [`RsgError::LongDataVector`] |
+    /// | 20 | [`RsgError::BadDataVector`] |
+    /// | 21 | [`RsgError::ParseEnum`] |
+    /// | 22 | [`RsgError::HeaderFieldEncode`] |
+    /// | 23 | [`RsgError::FormatSizeMismatch`] |
+    /// | 24 | [`RsgError::TooManyTraces`] |
+    /// | 25 | [`RsgError::Cancelled`] |
+    /// | 26 | [`RsgError::MapFile`] |
+    /// | 27 | `RsgError::SerdeError` (with the `to_json` feature) |
+    /// | 28 | [`RsgError::FixedLengthMismatch`] |
+    /// | 29 | [`RsgError::TraceSampleCountMismatch`] |
+    /// | 30 | [`RsgError::FileTooLargeForPlatform`] |
+    /// | 31 | [`RsgError::TraceExtensionLength`] |
+    /// | 32 | [`RsgError::TextInTraceData`] |
+    /// | 33 | [`RsgError::InvalidCell`] |
+    /// | 34 | [`RsgError::MissingCells`] |
+    /// | 35 | [`RsgError::SampleConversion`] |
+    /// | 36 | [`RsgError::AllocationOverBudget`] |
+    /// | 37 | [`RsgError::RaggedTraces`] |
+    /// | 38 | [`RsgError::UnsupportedRevision`] |
+    /// | 39 | [`RsgError::TimeWindowOutOfBounds`] |
+    /// | 40 | [`RsgError::OutputExists`] |
+    /// | 41 | [`RsgError::ImplausibleExtendedHeaderCount`] |
+    /// | 42 | [`RsgError::GridTooLarge`] |
+    ///
+    /// Codes of variants which are removed are not reused.
+    pub fn code(&self) -> u32 {
+        use self::RsgError::*;
+        match self {
+            TryFromSlice(_) => 1,
+            TryFromUtf8(_) => 2,
+            StdIoError(_) => 3,
+            BinHeaderLength { .. } => 4,
+            SEGYSettingsError { .. } => 5,
+            FileTooShort => 6,
+            FloatConversion { .. } => 7,
+            CoordinateParse { .. } => 8,
+            IncompleteTrace { .. } => 9,
+            TraceNotFound { .. } => 10,
+            TracePointOutOfBounds { .. } => 11,
+            LineNotFound { .. } => 12,
+            SEGYTooShort => 13,
+            ShortSEGY { .. } => 14,
+            TraceDivisibility { .. } => 15,
+            TraceHeaderLength { .. } => 16,
+            BitConversionError { .. } => 17,
+            InvalidHeader { .. } => 18,
+            LongDataVector { .. } => 19,
+            BadDataVector { .. } => 20,
+            ParseEnum { .. } => 21,
+            HeaderFieldEncode { .. } => 22,
+            FormatSizeMismatch { .. } => 23,
+            TooManyTraces { .. } => 24,
+            Cancelled => 25,
+            MapFile(_) => 26,
+            #[cfg(feature = "to_json")]
+            SerdeError(_) => 27,
+            FixedLengthMismatch { .. } => 28,
+            TraceSampleCountMismatch { .. } => 29,
+            FileTooLargeForPlatform { .. } => 30,
+            TraceExtensionLength { .. } => 31,
+            TextInTraceData { .. } => 32,
+            InvalidCell { .. } => 33,
+            MissingCells { .. } => 34,
+            SampleConversion { .. } => 35,
+            AllocationOverBudget { .. } => 36,
+            RaggedTraces { .. } => 37,
+            UnsupportedRevision { .. } => 38,
+            TimeWindowOutOfBounds { .. } => 39,
+            OutputExists { .. } => 40,
+            ImplausibleExtendedHeaderCount { .. } => 41,
+            GridTooLarge { .. } => 42,
         }
     }
+
+    /// Get the index of the trace that the error is about, for the errors which have one.
+    pub fn trace_index(&self) -> Option<usize> {
+        use self::RsgError::*;
+        match self {
+            CoordinateParse { trace, .. }
+            | IncompleteTrace { trace }
+            | FixedLengthMismatch { trace, .. }
+            | TraceSampleCountMismatch { trace, .. }
+            | TraceExtensionLength { trace, .. }
+            | TextInTraceData { trace, .. }
+            | RaggedTraces { trace, .. }
+            | TimeWindowOutOfBounds { trace, .. } => Some(*trace),
+            TraceNotFound { i } => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+impl From<RsgError> for std::io::Error {
+    /// IO errors are unwrapped, while other errors are wrapped in an IO error of a fitting kind
+    /// (eg. [`std::io::ErrorKind::UnexpectedEof`] for files which are too short).
+    fn from(e: RsgError) -> Self {
+        use self::RsgError::*;
+        use std::io::ErrorKind;
+        let kind = match e {
+            StdIoError(e) | MapFile(e) => return e,
+            FileTooShort | SEGYTooShort | ShortSEGY { .. } | IncompleteTrace { .. } => {
+                ErrorKind::UnexpectedEof
+            }
+            TraceNotFound { .. } | LineNotFound { .. } => ErrorKind::NotFound,
+            SEGYSettingsError { .. }
+            | TracePointOutOfBounds { .. }
+            | TimeWindowOutOfBounds { .. }
+            | FixedLengthMismatch { .. }
+            | TraceSampleCountMismatch { .. }
+            | TraceExtensionLength { .. }
+            | InvalidCell { .. }
+            | MissingCells { .. } => ErrorKind::InvalidInput,
+            TryFromSlice(_)
+            | TryFromUtf8(_)
+            | BinHeaderLength { .. }
+            | CoordinateParse { .. }
+            | TraceDivisibility { .. }
+            | TraceHeaderLength { .. }
+            | InvalidHeader { .. }
+            | ParseEnum { .. }
+            | FormatSizeMismatch { .. }
+            | TextInTraceData { .. }
+            | SampleConversion { .. }
+            | RaggedTraces { .. }
+            | UnsupportedRevision { .. }
+            | ImplausibleExtendedHeaderCount { .. }
+            | GridTooLarge { .. } => ErrorKind::InvalidData,
+            // Not `Interrupted`, which callers such as `Read::read_exact` retry.
+            Cancelled => ErrorKind::Other,
+            OutputExists { .. } => ErrorKind::AlreadyExists,
+            FileTooLargeForPlatform { .. } | AllocationOverBudget { .. } => ErrorKind::OutOfMemory,
+            _ => ErrorKind::Other,
+        };
+        std::io::Error::new(kind, e)
+    }
 }
 
 impl From<RsgError> for String {
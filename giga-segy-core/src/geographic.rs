@@ -0,0 +1,102 @@
+//! This module interprets the source and receiver group coordinates of trace headers whose
+//! [`CoordinateUnits`] are geographic (seconds of arc, decimal degrees or degrees, minutes and
+//! seconds), as described in SEG-Y rev 2 for bytes 89-90 of the trace header.
+//!
+//! NB: The coordinates of the trace header are kept as they are in the file. The functions of
+//! this module only give another view of them.
+use crate::enums::CoordinateUnits;
+use crate::TraceHeader;
+
+/// A geographic position in decimal degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct GeoPosition {
+    /// The longitude (from the X coordinate), positive to the east.
+    pub longitude: f64,
+    /// The latitude (from the Y coordinate), positive to the north.
+    pub latitude: f64,
+}
+
+impl TraceHeader {
+    /// Apply the coordinate scalar (bytes 71-72) to a coordinate of the trace header. A positive
+    /// scalar is a multiplier, a negative one a divisor, and zero is taken as one.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let header = TraceHeader::new_2d(123_456, 10, -100);
+    /// assert_eq!(header.apply_coordinate_scalar(header.x_ensemble), 1234.56);
+    /// ```
+    pub fn apply_coordinate_scalar(&self, value: i32) -> f64 {
+        match self.coordinate_scalar {
+            s if s > 0 => value as f64 * s as f64,
+            s if s < 0 => value as f64 / -(s as f64),
+            _ => value as f64,
+        }
+    }
+
+    /// Get the position of the source in decimal degrees, if the coordinate units of the trace
+    /// header are geographic.
+    ///
+    /// The scaled coordinates are read as seconds of arc, decimal degrees, or degrees, minutes
+    /// and seconds packed as `±DDDMMSS.ss` (eg. `-0431245.00` with a scalar of `-100` for
+    /// 43°12'45" west), depending on the coordinate units. `None` is returned for lengths,
+    /// unspecified or invalid units, for minutes or seconds of 60 or more, and for positions
+    /// outside of ±180° longitude and ±90° latitude.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_core::enums::CoordinateUnits;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let mut header = TraceHeader::new_2d(0, 0, -100);
+    /// header.coordinate_units = CoordinateUnits::DegreesMinutesSeconds;
+    /// header.source_x = 10452250;
+    /// header.source_y = 59545020;
+    /// let position = header.geographic_source_position().unwrap();
+    /// assert!((position.longitude - 10.75625).abs() < 1e-9);
+    /// assert!((position.latitude - (59. + 54. / 60. + 50.2 / 3600.)).abs() < 1e-9);
+    /// ```
+    pub fn geographic_source_position(&self) -> Option<GeoPosition> {
+        self.geographic_position(self.source_x, self.source_y)
+    }
+
+    /// Get the position of the receiver group in decimal degrees, if the coordinate units of
+    /// the trace header are geographic. See [`TraceHeader::geographic_source_position`].
+    pub fn geographic_group_position(&self) -> Option<GeoPosition> {
+        self.geographic_position(self.receiver_group_x, self.receiver_group_y)
+    }
+
+    fn geographic_position(&self, x: i32, y: i32) -> Option<GeoPosition> {
+        let to_degrees = |c: i32| {
+            let c = self.apply_coordinate_scalar(c);
+            match self.coordinate_units {
+                CoordinateUnits::SecondsOfArc => Some(c / 3600.),
+                CoordinateUnits::DegreesDecimal => Some(c),
+                CoordinateUnits::DegreesMinutesSeconds => unpack_dms(c),
+                _ => None,
+            }
+        };
+        let position = GeoPosition {
+            longitude: to_degrees(x)?,
+            latitude: to_degrees(y)?,
+        };
+        if position.longitude.abs() <= 180. && position.latitude.abs() <= 90. {
+            Some(position)
+        } else {
+            None
+        }
+    }
+}
+
+/// Unpack degrees, minutes and seconds from the `±DDDMMSS.ss` convention to decimal degrees.
+fn unpack_dms(packed: f64) -> Option<f64> {
+    // Round away the error of the scalar (eg. 0.01 is not exact), to a thousandth of a second.
+    let packed_abs = (packed.abs() * 1000.).round() / 1000.;
+    let degrees = (packed_abs / 10_000.).floor();
+    let minutes = ((packed_abs - degrees * 10_000.) / 100.).floor();
+    let seconds = packed_abs - degrees * 10_000. - minutes * 100.;
+    if minutes >= 60. || seconds >= 60. {
+        return None;
+    }
+    Some((degrees + minutes / 60. + seconds / 3600.).copysign(packed))
+}
@@ -0,0 +1,100 @@
+//! This module contains the [`SegyHandle`] trait, which gives read-only access to the headers
+//! and traces of a SEG-Y file, regardless of whether it is being read or written.
+use crate::{BinHeader, SegyMetadata, TapeLabel, Trace};
+use std::borrow::Cow;
+
+/// The read-only surface shared by the `SegyFile` of `giga_segy_in` and the `SegyFile` of
+/// `giga_segy_out`, so that code which only inspects the headers and traces of a file needs to
+/// be written once.
+///
+/// Only [`SegyHandle::get_metadata`], [`SegyHandle::trace_count`] and [`SegyHandle::get_trace`]
+/// need to be implemented. The traces are given as [`Cow`]s, since a file which is read may not
+/// keep its trace headers in memory (eg. when they are stored compactly), and then each trace is
+/// put together when it is asked for.
+/// ```
+/// use giga_segy_core::SegyHandle;
+///
+/// /// Get the number of traces and the inline range of a file.
+/// fn summarize(h: &impl SegyHandle) -> (usize, Option<[i32; 2]>) {
+///     let inlines = h.traces_iter().map(|t| t.get_header().inline_no);
+///     let range = inlines.fold(None, |r: Option<[i32; 2]>, i| match r {
+///         Some([min, max]) => Some([min.min(i), max.max(i)]),
+///         None => Some([i, i]),
+///     });
+///     (h.trace_count(), range)
+/// }
+/// ```
+pub trait SegyHandle {
+    /// The type of the settings that the file was opened or created with.
+    type Settings;
+
+    /// Get the metadata of the file (the headers and the settings).
+    fn get_metadata(&self) -> &SegyMetadata<Self::Settings>;
+
+    /// Get the settings that the file was opened or created with.
+    fn get_settings(&self) -> &Self::Settings {
+        self.get_metadata().get_settings()
+    }
+
+    /// Get the tape label, if the file has one.
+    fn get_tape_label(&self) -> &Option<TapeLabel> {
+        self.get_metadata().get_tape_label()
+    }
+
+    /// Get the text header.
+    fn get_text_header(&self) -> &str {
+        self.get_metadata().get_text_header()
+    }
+
+    /// Get the extended text headers.
+    fn get_extended_headers(&self) -> &[String] {
+        self.get_metadata().get_extended_headers()
+    }
+
+    /// Get the binary header.
+    fn get_bin_header(&self) -> &BinHeader {
+        self.get_metadata().get_bin_header()
+    }
+
+    /// Get the number of traces (or of those written so far).
+    fn trace_count(&self) -> usize;
+
+    /// Get a trace by its index. An out of bounds index returns a `None`.
+    fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>>;
+
+    /// Iterate through the traces.
+    fn traces_iter(&self) -> TracesIter<'_, Self> {
+        TracesIter {
+            handle: self,
+            next: 0,
+        }
+    }
+}
+
+/// An iterator through the traces of a [`SegyHandle`], in the order of the file (see
+/// [`SegyHandle::traces_iter`]).
+#[derive(Debug)]
+pub struct TracesIter<'a, H: SegyHandle + ?Sized> {
+    handle: &'a H,
+    next: usize,
+}
+
+impl<'a, H: SegyHandle + ?Sized> Iterator for TracesIter<'a, H> {
+    type Item = Cow<'a, Trace>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // A trace which cannot be put together is skipped, as with an index that is out of
+        // bounds.
+        while self.next < self.handle.trace_count() {
+            self.next += 1;
+            if let Some(trace) = self.handle.get_trace(self.next - 1) {
+                return Some(trace);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.handle.trace_count().saturating_sub(self.next)))
+    }
+}
@@ -2,16 +2,17 @@
 //! used for better interpreting the file in the parser.
 use crate::bitconverter::ascii_bytes_to_string;
 use crate::enums::*;
-#[cfg(feature = "to_json")]
 use crate::RsgError;
 use crate::SegySettings;
+use crate::{BIN_HEADER_LEN, TEXT_HEADER_LEN};
 
 use encoding8::ebcdic::to_ascii;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 /// This structure represents a parsed binary trace header for a single trace of a SEG-Y file..
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TraceHeader {
@@ -168,8 +169,12 @@ pub struct TraceHeader {
     pub crossline_no: i32,
     /// Bytes 197 - 200 (196..200) of the trace header.
     pub shot_point_no: i32,
-    /// Bytes 201 - 202 (200..202) of the trace header.
-    pub shot_point_scalar: u16,
+    /// Bytes 201 - 202 (200..202) of the trace header. Like the coordinate scalar, this is a
+    /// multiplier if it is positive and a divisor if it is negative (see
+    /// [`TraceHeader::scaled_shot_point`]).
+    ///
+    /// NB: This was a `u16` before version 0.5, which read negative scalars as large multipliers.
+    pub shot_point_scalar: i16,
     /// Bytes 203 - 204 (202..204) of the trace header.
     pub trace_value_measurement_unit: TraceValueUnit,
     /// Bytes 205 - 208 (204..208) of the trace header.
@@ -196,15 +201,18 @@ pub struct TraceHeader {
     pub source_measurement_exponent: u16,
     /// Bytes 231 - 232 (230..232) of the trace header.
     pub source_measurement_unit: SourceMeasurementUnit,
-    /// Bytes 233 - 230 (232..230) of the trace header.
+    /// Bytes 233 - 240 (232..240) of the trace header, in the order of the file.
+    ///
+    /// NB: Older versions of this crate reversed these bytes when reading and writing big
+    /// endian files. [`TraceHeader::get_trace_name`] turns such names around again.
     pub trace_name: [u8; 8],
 }
 
 /// This structure represents a parsed binary header for a SEG-Y file.
 ///
 /// It should be noted that while the binary header of a SEG-Y file is 400 bytes long
-/// and contains approximately 45 fields, this structure uses about 29 of these fields
-/// and rearranges then into 32 fields for convenience.
+/// and contains approximately 45 fields, this structure uses about 32 of these fields
+/// and rearranges then into 35 fields for convenience.
 #[derive(Debug, Clone, PartialEq)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -229,6 +237,12 @@ pub struct BinHeader {
     pub no_samples_original: u16,
     /// Bytes 3225 - 3226 of the SEG-Y file, (24..26) of the binary header.
     pub sample_format_code: SampleFormatCode,
+    /// Bytes 3225 - 3226 of the SEG-Y file, (24..26) of the binary header, as they are in the
+    /// file. This differs from [`BinHeader::sample_format_code`] if the sample format was
+    /// overridden with [`SegySettings::set_override_trace_format`], in which case it need not
+    /// be a valid code at all.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub raw_sample_format_code: u16,
     /// Bytes 3227 - 3228 of the SEG-Y file, (26..28) of the binary header.
     pub ensemble_fold: u16,
     /// Bytes 3229 - 3230 of the SEG-Y file, (28..30) of the binary header.
@@ -263,15 +277,70 @@ pub struct BinHeader {
     pub impulse_signal_polarity: ImpulseSignalPolarity,
     /// Bytes 3259 - 3260 of the SEG-Y file, (58..60) of the binary header.
     pub vibratory_polarity_code: VibratoryPolarityCode,
+    /// Bytes 3261 - 3264 of the SEG-Y file, (60..64) of the binary header. The number of data
+    /// traces per ensemble (SEG-Y rev 2), or zero if it is not given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_no_traces: u32,
+    /// Bytes 3265 - 3268 of the SEG-Y file, (64..68) of the binary header. The number of
+    /// auxiliary traces per ensemble (SEG-Y rev 2), or zero if it is not given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_no_aux_traces: u32,
+    /// Bytes 3269 - 3272 of the SEG-Y file, (68..72) of the binary header. The number of
+    /// samples per data trace (SEG-Y rev 2), or zero if it is not given. If it is given, it
+    /// takes precedence over [`BinHeader::no_samples`] (see [`BinHeader::sample_count`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_no_samples: u32,
+    /// Bytes 3273 - 3280 of the SEG-Y file, (72..80) of the binary header. The sample interval
+    /// as an IEEE double (SEG-Y rev 2), or zero if it is not given. If it is given, it takes
+    /// precedence over [`BinHeader::sample_interval`] (see [`BinHeader::sample_interval_us`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_sample_interval: f64,
+    /// Bytes 3281 - 3288 of the SEG-Y file, (80..88) of the binary header. The sample interval
+    /// of the original field recording as an IEEE double (SEG-Y rev 2), or zero if it is not
+    /// given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_sample_interval_original: f64,
+    /// Bytes 3289 - 3292 of the SEG-Y file, (88..92) of the binary header. The number of
+    /// samples per data trace in the original field recording (SEG-Y rev 2), or zero if it is
+    /// not given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_no_samples_original: u32,
+    /// Bytes 3293 - 3296 of the SEG-Y file, (92..96) of the binary header. The ensemble fold
+    /// (SEG-Y rev 2), or zero if it is not given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended_ensemble_fold: u32,
     /// Bytes 3501 - 3502 of the SEG-Y file, (300..302) of the binary header.
     /// Combines minor and major revision code.
     pub segy_revision_number: [u8; 2],
     /// Bytes 3503 - 3504 of the SEG-Y file, (302..304) of the binary header.
     pub fixed_length_trace_flag: FixedLengthTraces,
-    /// Bytes 3505 - 3506 of the SEG-Y file, (304..306) of the binary header.
+    /// Bytes 3505 - 3506 of the SEG-Y file, (304..306) of the binary header. The number of 3200
+    /// byte extended text headers which follow the binary header. This is a two byte field, so
+    /// it cannot be more than 65535 when the header is written.
     pub extended_header_count: u32,
+    /// Bytes 3507 - 3510 of the SEG-Y file, (306..310) of the binary header. The number of
+    /// additional 240 byte trace headers (trace header extensions) which follow each trace
+    /// header.
+    ///
+    /// NB: The standard allows this to be a maximum, with a varying number of extensions per
+    /// trace. This library only supports files where every trace has this many extensions.
+    pub max_additional_trace_headers: u32,
     /// Bytes 3511 - 3512 of the SEG-Y file, (310..312) of the binary header.
     pub time_basis_code: TimeBasisCode,
+    /// Bytes 3513 - 3520 of the SEG-Y file, (312..320) of the binary header. The number of
+    /// traces in the file (SEG-Y rev 2), or zero if it is not given.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_traces_in_file: u64,
+    /// Bytes 3521 - 3528 of the SEG-Y file, (320..328) of the binary header. The byte offset of
+    /// the first trace header from the start of the text header (SEG-Y rev 2), or zero if it is
+    /// not given. If this is given, it takes precedence over the offset implied by the number of
+    /// extended text headers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub first_trace_offset: u64,
+    /// Bytes 3529 - 3532 of the SEG-Y file, (328..332) of the binary header. The number of 3200
+    /// byte data trailer stanzas after the last trace (SEG-Y rev 2).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_trailer_stanzas: i32,
     /// Determined from bytes 3297 - 3300 of the SEG-Y file, (96..100) of the binary header.
     /// This library does not support all variants, only complete LE or complete BE.
     ///
@@ -290,31 +359,33 @@ pub struct TapeLabel {
     pub storage_unit_seq_no: [u8; 4],
     /// Bytes 5-9 (4..9)
     pub segy_revision_no: [u8; 5],
-    /// Storage Unit Type 1-15 (9..15) b"RECORD"
+    /// Storage Unit Type 10-15 (9..15) b"RECORD"
     pub storage_unit_structure: [u8; 6],
     /// Binding Edition 16-19 (15..19) (b"BXXX")
     pub binding_number: [u8; 4],
     /// Max Block Size: 20-29 (19..29) Stored as character bytes. Needs to be parsed.
     pub max_block_size: u32,
-    /// Producing Organisation code: 29-39. Stored as character bytes.
+    /// Producing Organisation code: 30-39 (29..39). Stored as character bytes.
     pub producing_organisation_code: [u8; 10],
     /// Creation Date: 40-50 (39..50).
     pub creation_date: [u8; 11],
     /// Serial Number: 51-62 (50..62).
     pub serial_number: [u8; 12],
-    /// Reserved: 63-68 (62..68).?
-    /// Storage Set Identifier:
-    /// External Label Name: 69-80 (68..80)
+    // Bytes 63-68 (62..68) are reserved.
+    /// Storage Set Identifier or External Label Name: 69-80 (68..80)
     pub external_label: [u8; 12],
-    /// Recording Entity Name (81-104 (80..104).
+    /// Recording Entity Name: 81-104 (80..104).
     pub recording_entity: [u8; 24],
     /// User Defined: 105-118 (104..118)
     pub extra: [u8; 14],
-    // The last ten bytes are reserved.
+    // Bytes 119-128 (118..128) are reserved.
 }
 
 /// This is a rust readable version of the [`TapeLabel`] structure, which can be generated after the file
 /// has been read, but is not stored.
+///
+/// The fields of the tape label are padded with spaces, which are removed in the readable
+/// version. It can be converted back with [`ReadableTapeLabel::to_tape_label`].
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ReadableTapeLabel {
@@ -335,17 +406,17 @@ impl TapeLabel {
     /// Converts the C compatible [`TapeLabel`] to a rust compatible [`ReadableTapeLabel`]
     pub fn to_readable(&self) -> ReadableTapeLabel {
         ReadableTapeLabel {
-            storage_unit_seq_no: ascii_bytes_to_string(&self.storage_unit_seq_no),
-            segy_revision_no: ascii_bytes_to_string(&self.segy_revision_no),
-            storage_unit_structure: ascii_bytes_to_string(&self.storage_unit_structure),
-            binding_number: ascii_bytes_to_string(&self.binding_number),
+            storage_unit_seq_no: label_field_to_string(&self.storage_unit_seq_no),
+            segy_revision_no: label_field_to_string(&self.segy_revision_no),
+            storage_unit_structure: label_field_to_string(&self.storage_unit_structure),
+            binding_number: label_field_to_string(&self.binding_number),
             max_block_size: self.max_block_size,
-            producing_organisation_code: ascii_bytes_to_string(&self.producing_organisation_code),
-            creation_date: ascii_bytes_to_string(&self.creation_date),
-            serial_number: ascii_bytes_to_string(&self.serial_number),
-            external_label: ascii_bytes_to_string(&self.external_label),
-            recording_entity: ascii_bytes_to_string(&self.recording_entity),
-            extra: ascii_bytes_to_string(&self.extra),
+            producing_organisation_code: label_field_to_string(&self.producing_organisation_code),
+            creation_date: label_field_to_string(&self.creation_date),
+            serial_number: label_field_to_string(&self.serial_number),
+            external_label: label_field_to_string(&self.external_label),
+            recording_entity: label_field_to_string(&self.recording_entity),
+            extra: label_field_to_string(&self.extra),
         }
     }
 
@@ -355,14 +426,225 @@ impl TapeLabel {
     }
 }
 
+impl ReadableTapeLabel {
+    /// Converts the [`ReadableTapeLabel`] to a [`TapeLabel`] which can be written, padding each
+    /// field with spaces.
+    ///
+    /// An [`RsgError::InvalidHeader`] naming the field is returned if a field is longer than
+    /// its place in the tape label, or if it is not ASCII.
+    /// ```
+    /// use giga_segy_core::ReadableTapeLabel;
+    ///
+    /// let readable = ReadableTapeLabel {
+    ///     storage_unit_seq_no: String::from("1"),
+    ///     segy_revision_no: String::from("SY2.0"),
+    ///     storage_unit_structure: String::from("RECORD"),
+    ///     binding_number: String::from("BXXX"),
+    ///     max_block_size: 65536,
+    ///     producing_organisation_code: String::from("GIGA"),
+    ///     creation_date: String::from("08-SEP-2022"),
+    ///     serial_number: String::from("42"),
+    ///     external_label: String::from("SURVEY"),
+    ///     recording_entity: String::from("Vessel"),
+    ///     extra: String::new(),
+    /// };
+    /// let label = readable.to_tape_label().unwrap();
+    /// assert_eq!(&label.storage_unit_seq_no, b"1   ");
+    /// assert_eq!(label.to_readable(), readable);
+    ///
+    /// let too_long = ReadableTapeLabel {
+    ///     binding_number: String::from("B1234"),
+    ///     ..readable
+    /// };
+    /// assert!(too_long.to_tape_label().unwrap_err().to_string().contains("binding_number"));
+    /// ```
+    pub fn to_tape_label(&self) -> Result<TapeLabel, RsgError> {
+        Ok(TapeLabel {
+            storage_unit_seq_no: pad_label_field("storage_unit_seq_no", &self.storage_unit_seq_no)?,
+            segy_revision_no: pad_label_field("segy_revision_no", &self.segy_revision_no)?,
+            storage_unit_structure: pad_label_field(
+                "storage_unit_structure",
+                &self.storage_unit_structure,
+            )?,
+            binding_number: pad_label_field("binding_number", &self.binding_number)?,
+            max_block_size: self.max_block_size,
+            producing_organisation_code: pad_label_field(
+                "producing_organisation_code",
+                &self.producing_organisation_code,
+            )?,
+            creation_date: pad_label_field("creation_date", &self.creation_date)?,
+            serial_number: pad_label_field("serial_number", &self.serial_number)?,
+            external_label: pad_label_field("external_label", &self.external_label)?,
+            recording_entity: pad_label_field("recording_entity", &self.recording_entity)?,
+            extra: pad_label_field("extra", &self.extra)?,
+        })
+    }
+}
+
+/// Converts a field of the tape label to a string, without the padding.
+fn label_field_to_string(bytes: &[u8]) -> String {
+    ascii_bytes_to_string(bytes).trim_end().to_string()
+}
+
+/// Pads a field of the tape label with spaces to its length in the tape label.
+fn pad_label_field<const N: usize>(name: &str, value: &str) -> Result<[u8; N], RsgError> {
+    if !value.is_ascii() {
+        let msg = format!("Invalid TapeLabel: {} is not ASCII: {:?}", name, value);
+        return Err(RsgError::InvalidHeader { msg });
+    }
+    if value.len() > N {
+        let msg = format!(
+            "Invalid TapeLabel: {} is too long: {} bytes (at most {})",
+            name,
+            value.len(),
+            N
+        );
+        return Err(RsgError::InvalidHeader { msg });
+    }
+    let mut field = [b' '; N];
+    field[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(field)
+}
+
 impl BinHeader {
+    /// Apply the sample count override of the settings, if any. A count which does not fit in
+    /// [`BinHeader::no_samples`] is kept in [`BinHeader::extended_no_samples`] instead.
     pub fn adjust_sample_count(&mut self, settings: &SegySettings) {
         if let Some(dim_z) = settings.override_dim_z {
-            self.no_samples = dim_z as u16;
+            match u16::try_from(dim_z) {
+                Ok(no_samples) => {
+                    self.no_samples = no_samples;
+                    self.extended_no_samples = 0;
+                }
+                Err(_) => {
+                    self.no_samples = 0;
+                    self.extended_no_samples = dim_z as u32;
+                }
+            }
             self.fixed_length_trace_flag = FixedLengthTraces::Yes;
         }
     }
 
+    /// Get the position of the first trace header relative to the start of the text header,
+    /// for a file with `extended_header_count` extended text headers.
+    ///
+    /// This is [`BinHeader::first_trace_offset`] if it is given, and otherwise the first trace
+    /// header directly follows the extended text headers. An offset which would place the first
+    /// trace within the headers is ignored.
+    /// ```
+    /// use giga_segy_core::BinHeader;
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    ///
+    /// let mut bin_header = BinHeader::new(0, 4000, 50, SampleFormatCode::Float32);
+    /// assert_eq!(bin_header.get_first_trace_offset(1), 6800);
+    /// bin_header.first_trace_offset = 8192;
+    /// assert_eq!(bin_header.get_first_trace_offset(1), 8192);
+    /// bin_header.first_trace_offset = 3600;
+    /// assert_eq!(bin_header.get_first_trace_offset(1), 6800);
+    /// ```
+    pub fn get_first_trace_offset(&self, extended_header_count: usize) -> usize {
+        let default_start = TEXT_HEADER_LEN * (extended_header_count + 1) + BIN_HEADER_LEN;
+        match usize::try_from(self.first_trace_offset) {
+            Ok(offset) if offset >= default_start => offset,
+            _ => default_start,
+        }
+    }
+
+    /// Get the sample interval (in microseconds for time data). This is the
+    /// [`BinHeader::extended_sample_interval`] if the header is SEG-Y rev 2 or later and it is
+    /// given, and otherwise [`BinHeader::sample_interval`].
+    pub fn sample_interval_us(&self) -> f64 {
+        let extended = self.extended_sample_interval;
+        if self.segy_revision_number[0] >= 2 && extended.is_finite() && extended > 0. {
+            extended
+        } else {
+            self.sample_interval as f64
+        }
+    }
+
+    /// Get the number of samples per data trace. This is the [`BinHeader::extended_no_samples`]
+    /// if it is given and the header is SEG-Y rev 2 or later (or [`BinHeader::no_samples`] is
+    /// zero, as after a sample count override which does not fit in it), and otherwise
+    /// [`BinHeader::no_samples`].
+    pub fn sample_count(&self) -> usize {
+        let extended = self.segy_revision_number[0] >= 2 || self.no_samples == 0;
+        if extended && self.extended_no_samples > 0 {
+            self.extended_no_samples as usize
+        } else {
+            self.no_samples as usize
+        }
+    }
+
+    /// Check whether the SEG-Y revision of the header is one that this crate supports: Any
+    /// revision 0.x (before the revision number was introduced, most files leave it at zero),
+    /// 1.0 or 2.0.
+    /// ```
+    /// use giga_segy_core::BinHeader;
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    ///
+    /// let mut bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    /// assert!(bin_header.revision_supported());
+    /// bin_header.segy_revision_number = [3, 0];
+    /// assert!(!bin_header.revision_supported());
+    /// ```
+    pub fn revision_supported(&self) -> bool {
+        matches!(self.segy_revision_number, [0, _] | [1, 0] | [2, 0])
+    }
+
+    /// Set the sample interval (in microseconds for time data).
+    ///
+    /// An interval which is a whole number and fits in a `u16` (at most 65535 microseconds) is
+    /// written to [`BinHeader::sample_interval`], and the extended sample interval is cleared.
+    /// Any other interval is written to [`BinHeader::extended_sample_interval`], which requires
+    /// the header to be SEG-Y rev 2 or later. In that case [`BinHeader::sample_interval`] is
+    /// set to the rounded interval if it fits, and to zero otherwise, for readers which do not
+    /// know the extended field.
+    ///
+    /// An error is returned (and nothing is changed) if the interval is negative or not finite,
+    /// or if it needs the extended field and the header is of an older revision.
+    /// ```
+    /// use giga_segy_core::BinHeader;
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    ///
+    /// let mut bin_header = BinHeader::new(0, 0, 50, SampleFormatCode::Float32);
+    /// bin_header.try_set_sample_interval_us(100_000.).unwrap();
+    /// assert_eq!(bin_header.sample_interval, 0);
+    /// assert_eq!(bin_header.sample_interval_us(), 100_000.);
+    ///
+    /// bin_header.segy_revision_number = [1, 0];
+    /// assert!(bin_header.try_set_sample_interval_us(100_000.).is_err());
+    /// ```
+    pub fn try_set_sample_interval_us(&mut self, interval: f64) -> Result<(), RsgError> {
+        if !interval.is_finite() || interval < 0. {
+            let msg = format!("Invalid sample interval: {}", interval);
+            return Err(RsgError::InvalidHeader { msg });
+        }
+        if interval.fract() == 0. && interval <= u16::MAX as f64 {
+            self.sample_interval = interval as u16;
+            self.extended_sample_interval = 0.;
+            return Ok(());
+        }
+        if self.segy_revision_number[0] < 2 {
+            let msg = format!(
+                "The sample interval {} needs the extended sample interval of SEG-Y rev 2, \
+                but the binary header is of revision {}.{}",
+                interval, self.segy_revision_number[0], self.segy_revision_number[1]
+            );
+            return Err(RsgError::InvalidHeader { msg });
+        }
+        let rounded = interval.round();
+        self.sample_interval = if rounded <= u16::MAX as f64 {
+            rounded as u16
+        } else {
+            0
+        };
+        self.extended_sample_interval = interval;
+        Ok(())
+    }
+
     #[cfg(feature = "to_json")]
     pub fn to_json(&self) -> Result<String, RsgError> {
         serde_json::to_string(&self).map_err(RsgError::SerdeError)
@@ -370,27 +652,65 @@ impl BinHeader {
 }
 
 impl TraceHeader {
+    /// Apply the sample count override of the settings, if any. A count which does not fit in
+    /// [`TraceHeader::no_samples_in_trace`] leaves it at zero, as in SEG-Y rev 2 (the count is
+    /// then given by [`BinHeader::sample_count`]).
     pub fn adjust_sample_count(&mut self, settings: &SegySettings) {
         if let Some(dim_z) = settings.override_dim_z {
-            self.no_samples_in_trace = dim_z as u16;
+            self.no_samples_in_trace = u16::try_from(dim_z).unwrap_or(0);
         }
     }
 
     /// This gets the trace name as a String.
+    ///
+    /// The name should start with "SEG" (in ASCII or EBCDIC), or just be blank. A name which
+    /// ends with a reversed "SEG" was written to a big endian file by an older version of this
+    /// crate (see [`TraceHeader::trace_name`]), so it is turned around.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let mut header = TraceHeader::new_2d(0, 0, 1);
+    /// header.trace_name = *b"SEG00001";
+    /// assert_eq!(header.get_trace_name(), "SEG00001");
+    ///
+    /// header.trace_name = *b"10000GES";
+    /// assert_eq!(header.get_trace_name(), "SEG00001");
+    /// ```
     pub fn get_trace_name(&self) -> String {
+        let mut name = self.trace_name;
+        if name.ends_with(b"GES") || name.ends_with(&[0xC7, 0xC5, 0xE2]) {
+            name.reverse();
+        }
         // Trace name should start with "SEG", or just be blank.
-        let is_ascii = self.trace_name[0] == b'S';
+        let is_ascii = name[0] == b'S';
         if is_ascii {
-            return ascii_bytes_to_string(&self.trace_name);
+            return ascii_bytes_to_string(&name);
         }
-        let name = self
-            .trace_name
-            .iter()
-            .map(|c| to_ascii(*c))
-            .collect::<Vec<_>>();
+        let name = name.iter().map(|c| to_ascii(*c)).collect::<Vec<_>>();
         ascii_bytes_to_string(&name)
     }
 
+    /// Get the shot point number (bytes 197-200) with the shot point scalar (bytes 201-202)
+    /// applied. A positive scalar is a multiplier, a negative one a divisor, and zero is taken
+    /// as one.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let mut header = TraceHeader::new_2d(0, 0, 1);
+    /// header.shot_point_no = 1015;
+    /// header.shot_point_scalar = -10;
+    /// assert_eq!(header.scaled_shot_point(), 101.5);
+    /// ```
+    pub fn scaled_shot_point(&self) -> f64 {
+        match self.shot_point_scalar {
+            s if s > 0 => self.shot_point_no as f64 * s as f64,
+            s if s < 0 => self.shot_point_no as f64 / -(s as f64),
+            _ => self.shot_point_no as f64,
+        }
+    }
+
     #[cfg(feature = "to_json")]
     pub fn to_json(&self) -> Result<String, RsgError> {
         serde_json::to_string(&self).map_err(RsgError::SerdeError)
@@ -407,15 +727,25 @@ binary flag direction is le: {}
 binary gain recovered: {:?}
 correlated traces: {:?}
 ensemble fold: {}
+extended ensemble fold: {}
 extended header count: {}
+extended no aux traces: {}
+extended no samples original: {}
+extended no samples: {}
+extended no traces: {}
+extended sample interval original: {}
+first trace offset: {}
 fixed length trace flag: {:?}
 impulse signal polarity: {:?}
 job id: {:?}
 line number: {:?}
+max additional trace headers: {}
 measurement system:{:?}
 no aux traces: {}
 no samples original: {}
 no samples: {}
+no trailer stanzas: {}
+no traces in file: {}
 no traces: {}
 reel number: {}
 sample format code: {:?}
@@ -439,20 +769,30 @@ vibratory polarity code: {:?}",
             b.binary_gain_recovered,
             b.correlated_traces,
             b.ensemble_fold,
+            b.extended_ensemble_fold,
             b.extended_header_count,
+            b.extended_no_aux_traces,
+            b.extended_no_samples_original,
+            b.extended_no_samples,
+            b.extended_no_traces,
+            b.extended_sample_interval_original,
+            b.first_trace_offset,
             b.fixed_length_trace_flag,
             b.impulse_signal_polarity,
             b.job_id,
             b.line_number,
+            b.max_additional_trace_headers,
             b.measurement_system,
             b.no_aux_traces,
             b.no_samples_original,
             b.no_samples,
+            b.no_trailer_stanzas,
+            b.no_traces_in_file,
             b.no_traces,
             b.reel_number,
             b.sample_format_code,
             b.sample_interval_original,
-            b.sample_interval,
+            b.sample_interval_us(),
             b.segy_revision_number[0],
             b.segy_revision_number[1],
             b.sorting_code,
@@ -553,6 +893,7 @@ inline no: {}
 crossline no: {}
 shot point no: {}
 shot point scalar: {}
+scaled shot point: {}
 trace value measurement unit: {:?}
 transduction constant mantissa: {}
 transduction constant power: {}
@@ -644,6 +985,7 @@ trace name: {}",
             t.crossline_no,
             t.shot_point_no,
             t.shot_point_scalar,
+            t.scaled_shot_point(),
             t.trace_value_measurement_unit,
             t.transduction_constant_mantissa,
             t.transduction_constant_power,
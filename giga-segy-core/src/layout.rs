@@ -0,0 +1,415 @@
+//! This module describes where each field of the [`BinHeader`](crate::BinHeader) and the
+//! [`TraceHeader`] is found in the headers of a SEG-Y file, so that tools can display this
+//! (eg. "field X lives at bytes A-B") without repeating the standard.
+//!
+//! The positions are those of the standard. NB: The positions of the inline and crossline
+//! numbers and of the ensemble coordinates can be changed with the [`crate::SegySettings`].
+use crate::enums::*;
+use crate::{BinHeader, TraceHeader};
+use num::ToPrimitive;
+
+use std::fmt;
+use std::ops::Range;
+
+/// How a header field is encoded in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    I16,
+    U16,
+    I32,
+    U32,
+    U64,
+    /// An IEEE double.
+    F64,
+    /// A code which is parsed to one of the enums of [`crate::enums`].
+    Enum,
+    /// A coordinate or distance, which is four bytes long and encoded in the coordinate format
+    /// (an `i32` by default, see [`crate::SegySettings::set_override_coordinate_format`]).
+    Coordinate,
+    /// Raw bytes (eg. the SEG-Y revision or the trace name).
+    Bytes,
+}
+
+/// The name, position and encoding of a header field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// The name of the field in the header structure.
+    pub name: &'static str,
+    /// The position of the field in the header (counting from zero).
+    pub range: Range<usize>,
+    /// How the field is encoded.
+    pub kind: FieldKind,
+}
+
+/// The value of a trace header field, as returned by [`TraceHeader::get_field_by_name`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldValue {
+    I32(i32),
+    U16(u16),
+    I16(i16),
+    /// The numeric code of an enum.
+    Enum(i32),
+}
+
+/// Get the layout of the binary header, in the order of the fields in the file.
+pub const fn bin_header_layout() -> &'static [FieldSpec] {
+    BIN_HEADER_LAYOUT
+}
+
+/// Get the layout of the trace header, in the order of the fields in the file.
+/// ```
+/// use giga_segy_core::layout::{trace_header_layout, FieldKind};
+///
+/// let inline = trace_header_layout().iter().find(|f| f.name == "inline_no").unwrap();
+/// assert_eq!(inline.range, 188..192);
+/// assert_eq!(inline.kind, FieldKind::I32);
+/// ```
+pub const fn trace_header_layout() -> &'static [FieldSpec] {
+    TRACE_HEADER_LAYOUT
+}
+
+/// The fields of the trace header whose byte index can be set in the settings (eg. with
+/// [`crate::SegySettings::set_inline_no_bidx`]), in the order of the byte indices which are
+/// given to [`find_bidx_collisions`].
+pub const RELOCATABLE_FIELDS: [&str; 4] = ["inline_no", "crossline_no", "x_ensemble", "y_ensemble"];
+
+/// A field of the trace header whose byte index overlaps other fields, as found by
+/// [`find_bidx_collisions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidxCollision {
+    /// The name of the field whose byte index is set (one of [`RELOCATABLE_FIELDS`]).
+    pub field: &'static str,
+    /// The position of the field in the trace header (counting from zero).
+    pub range: Range<usize>,
+    /// The names of the fields which are overlapped, in the order of the trace header. These
+    /// are the other fields of the standard, and the other fields whose byte index is set.
+    pub overlaps: Vec<&'static str>,
+}
+
+impl fmt::Display for BidxCollision {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} at bytes {}-{} overlaps {}",
+            self.field,
+            self.range.start + 1,
+            self.range.end,
+            self.overlaps.join(", ")
+        )
+    }
+}
+
+/// Find the fields of the trace header which are overlapped when the fields of
+/// [`RELOCATABLE_FIELDS`] are placed at the given byte indices. When a file is written, these
+/// would be overwritten, and when it is read, they are parsed from the same bytes.
+///
+/// NB: The standard positions of the relocatable fields themselves do not count, since these
+/// are only used if a field is not moved.
+/// ```
+/// use giga_segy_core::layout::find_bidx_collisions;
+///
+/// // The standard positions.
+/// assert!(find_bidx_collisions([188, 192, 180, 184]).is_empty());
+/// // The inline and crossline numbers can swap places.
+/// assert!(find_bidx_collisions([192, 188, 180, 184]).is_empty());
+///
+/// // The X coordinate of the ensemble on top of the X coordinate of the source.
+/// let collisions = find_bidx_collisions([188, 192, 72, 184]);
+/// assert_eq!(collisions.len(), 1);
+/// assert_eq!(collisions[0].field, "x_ensemble");
+/// assert_eq!(collisions[0].overlaps, vec!["source_x"]);
+/// assert_eq!(collisions[0].to_string(), "x_ensemble at bytes 73-76 overlaps source_x");
+/// ```
+pub fn find_bidx_collisions(byte_indices: [usize; 4]) -> Vec<BidxCollision> {
+    let ranges = byte_indices
+        .iter()
+        .map(|b| *b..(*b + 4))
+        .collect::<Vec<_>>();
+    let overlap = |a: &Range<usize>, b: &Range<usize>| a.start < b.end && b.start < a.end;
+
+    let mut collisions = Vec::new();
+    for (k, range) in ranges.iter().enumerate() {
+        let mut overlaps = trace_header_layout()
+            .iter()
+            .filter(|f| !RELOCATABLE_FIELDS.contains(&f.name) && overlap(&f.range, range))
+            .map(|f| (f.range.start, f.name))
+            .collect::<Vec<_>>();
+        for (j, other) in ranges.iter().enumerate() {
+            if j != k && overlap(other, range) {
+                overlaps.push((other.start, RELOCATABLE_FIELDS[j]));
+            }
+        }
+        if !overlaps.is_empty() {
+            overlaps.sort_unstable();
+            collisions.push(BidxCollision {
+                field: RELOCATABLE_FIELDS[k],
+                range: range.clone(),
+                overlaps: overlaps.into_iter().map(|(_, name)| name).collect(),
+            });
+        }
+    }
+    collisions
+}
+
+/// Builds a static table of [`FieldSpec`]s.
+macro_rules! field_table {
+    ($table:ident; $($name:ident: $range:expr => $kind:ident $(($enum:ident))?),+ $(,)?) => {
+        const $table: &[FieldSpec] = &[
+            $(FieldSpec {
+                name: stringify!($name),
+                range: $range,
+                kind: FieldKind::$kind,
+            }),+
+        ];
+    };
+}
+
+/// Converts a field of a header to a [`FieldValue`]. Enums which have no valid code give
+/// `None`, and so do raw bytes.
+macro_rules! field_value {
+    ($v:expr, I32) => {
+        Some(FieldValue::I32($v))
+    };
+    ($v:expr, Coordinate) => {
+        Some(FieldValue::I32($v))
+    };
+    ($v:expr, U16) => {
+        Some(FieldValue::U16($v))
+    };
+    ($v:expr, I16) => {
+        Some(FieldValue::I16($v))
+    };
+    ($v:expr, Enum, $enum:ident) => {
+        if $v == $enum::Invalid {
+            None
+        } else {
+            $v.to_i32().map(FieldValue::Enum)
+        }
+    };
+    ($v:expr, Bytes) => {
+        None
+    };
+}
+
+/// Builds both the layout of the trace header and [`TraceHeader::get_field_by_name`] from the
+/// same list, so that they cannot disagree.
+macro_rules! trace_header_fields {
+    ($($name:ident: $range:expr => $kind:ident $(($enum:ident))?),+ $(,)?) => {
+        field_table!(TRACE_HEADER_LAYOUT; $($name: $range => $kind $(($enum))?),+);
+
+        impl TraceHeader {
+            /// Get the value of a field by its name (as in [`trace_header_layout`]), or `None`
+            /// if there is no such field. Enums without a valid code and `trace_name` also give
+            /// `None`.
+            /// ```
+            /// use giga_segy_core::layout::FieldValue;
+            /// use giga_segy_core::TraceHeader;
+            /// use giga_segy_out::create_headers::CreateTraceHeader;
+            ///
+            /// let header = TraceHeader::new_3d(1000, 2000, 12, 34, 1);
+            /// assert_eq!(header.get_field_by_name("inline_no"), Some(FieldValue::I32(12)));
+            /// assert_eq!(header.get_field_by_name("no_such_field"), None);
+            /// ```
+            pub fn get_field_by_name(&self, name: &str) -> Option<FieldValue> {
+                match name {
+                    $(stringify!($name) => field_value!(self.$name, $kind $(, $enum)?),)+
+                    _ => None,
+                }
+            }
+
+            /// Get the value of a field by its name (as in [`trace_header_layout`]) in `Debug`
+            /// form, or `None` if there is no such field. Unlike [`Self::get_field_by_name`],
+            /// this gives a value for every field.
+            /// ```
+            /// use giga_segy_core::TraceHeader;
+            /// use giga_segy_out::create_headers::CreateTraceHeader;
+            ///
+            /// let header = TraceHeader::new_3d(1000, 2000, 12, 34, 1);
+            /// assert_eq!(header.format_field_by_name("inline_no").unwrap(), "12");
+            /// let name = header.format_field_by_name("trace_name").unwrap();
+            /// assert_eq!(name, "[0, 0, 0, 0, 0, 0, 0, 0]");
+            /// ```
+            pub fn format_field_by_name(&self, name: &str) -> Option<String> {
+                match name {
+                    $(stringify!($name) => Some(format!("{:?}", self.$name)),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/// Builds both the layout of the binary header and [`BinHeader::format_field_by_name`] from
+/// the same list. The fields after the semicolon are those which are not in the layout,
+/// because they are read from the same bytes as another field.
+macro_rules! bin_header_fields {
+    ($($name:ident: $range:expr => $kind:ident),+ $(,)?; $($extra:ident),* $(,)?) => {
+        field_table!(BIN_HEADER_LAYOUT; $($name: $range => $kind),+);
+
+        /// The fields of the binary header which are not in [`bin_header_layout`], because they
+        /// are read from the same bytes as another field.
+        pub const BIN_HEADER_EXTRA_FIELDS: &[&str] = &[$(stringify!($extra)),*];
+
+        impl BinHeader {
+            /// Get the value of a field by its name (as in [`bin_header_layout`] or
+            /// [`BIN_HEADER_EXTRA_FIELDS`]) in `Debug` form, or `None` if there is no such field.
+            /// ```
+            /// use giga_segy_core::enums::SampleFormatCode;
+            /// use giga_segy_core::BinHeader;
+            /// use giga_segy_out::create_headers::CreateBinHeader;
+            ///
+            /// let header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+            /// assert_eq!(header.format_field_by_name("sample_interval").unwrap(), "4000");
+            /// assert_eq!(header.format_field_by_name("no_such_field"), None);
+            /// ```
+            pub fn format_field_by_name(&self, name: &str) -> Option<String> {
+                match name {
+                    $(stringify!($name) => Some(format!("{:?}", self.$name)),)+
+                    $(stringify!($extra) => Some(format!("{:?}", self.$extra)),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+bin_header_fields!(
+    job_id: 0..4 => U32,
+    line_number: 4..8 => U32,
+    reel_number: 8..12 => U32,
+    no_traces: 12..14 => U16,
+    no_aux_traces: 14..16 => U16,
+    sample_interval: 16..18 => U16,
+    sample_interval_original: 18..20 => U16,
+    no_samples: 20..22 => U16,
+    no_samples_original: 22..24 => U16,
+    sample_format_code: 24..26 => Enum,
+    ensemble_fold: 26..28 => U16,
+    sorting_code: 28..30 => Enum,
+    vertical_sum: 30..32 => U16,
+    sweep_frequency_start: 32..34 => U16,
+    sweep_frequency_end: 34..36 => U16,
+    sweep_length: 36..38 => U16,
+    sweep_type: 38..40 => Enum,
+    sweep_channel_trace_no: 40..42 => U16,
+    sweep_taper_at_start: 42..44 => U16,
+    sweep_taper_at_end: 44..46 => U16,
+    taper_type: 46..48 => Enum,
+    correlated_traces: 48..50 => Enum,
+    binary_gain_recovered: 50..52 => Enum,
+    amplitude_recovery_method: 52..54 => Enum,
+    measurement_system: 54..56 => Enum,
+    impulse_signal_polarity: 56..58 => Enum,
+    vibratory_polarity_code: 58..60 => Enum,
+    extended_no_traces: 60..64 => U32,
+    extended_no_aux_traces: 64..68 => U32,
+    extended_no_samples: 68..72 => U32,
+    extended_sample_interval: 72..80 => F64,
+    extended_sample_interval_original: 80..88 => F64,
+    extended_no_samples_original: 88..92 => U32,
+    extended_ensemble_fold: 92..96 => U32,
+    binary_flag_direction_is_le: 96..100 => Bytes,
+    segy_revision_number: 300..302 => Bytes,
+    fixed_length_trace_flag: 302..304 => Enum,
+    extended_header_count: 304..306 => U16,
+    max_additional_trace_headers: 306..310 => U32,
+    time_basis_code: 310..312 => Enum,
+    no_traces_in_file: 312..320 => U64,
+    first_trace_offset: 320..328 => U64,
+    no_trailer_stanzas: 328..332 => I32;
+    raw_sample_format_code,
+);
+
+trace_header_fields!(
+    trace_sequence_on_line: 0..4 => I32,
+    trace_sequence_in_file: 4..8 => I32,
+    field_record_no: 8..12 => I32,
+    trace_no: 12..16 => I32,
+    energy_source_point_no: 16..20 => I32,
+    ensemble_no: 20..24 => I32,
+    trace_no_in_ensemble: 24..28 => I32,
+    trace_identification_code: 28..30 => Enum(TraceIdCode),
+    no_v_summed_traces: 30..32 => U16,
+    no_h_stacked_traces: 32..34 => U16,
+    data_use: 34..36 => Enum(DataUse),
+    source_to_receiver_distance: 36..40 => Coordinate,
+    elevation_of_receiver_group: 40..44 => Coordinate,
+    surface_elevation_of_source: 44..48 => Coordinate,
+    source_depth: 48..52 => Coordinate,
+    datum_elevation_of_receiver_group: 52..56 => Coordinate,
+    datum_elevation_of_source: 56..60 => Coordinate,
+    water_column_height_at_source: 60..64 => Coordinate,
+    water_column_height_at_group: 64..68 => Coordinate,
+    elevation_scalar: 68..70 => I16,
+    coordinate_scalar: 70..72 => I16,
+    source_x: 72..76 => Coordinate,
+    source_y: 76..80 => Coordinate,
+    receiver_group_x: 80..84 => Coordinate,
+    receiver_group_y: 84..88 => Coordinate,
+    coordinate_units: 88..90 => Enum(CoordinateUnits),
+    weathing_velocity: 90..92 => U16,
+    sub_weathering_velocity: 92..94 => U16,
+    uphole_time_at_source: 94..96 => U16,
+    uphole_time_at_group: 96..98 => U16,
+    source_static_correction: 98..100 => U16,
+    group_static_correction: 100..102 => U16,
+    total_static_applied: 102..104 => U16,
+    lag_time_a: 104..106 => U16,
+    lag_time_b: 106..108 => U16,
+    delay_recording_time: 108..110 => U16,
+    mute_time_start: 110..112 => U16,
+    mute_time_end: 112..114 => U16,
+    no_samples_in_trace: 114..116 => U16,
+    sample_interval_of_trace: 116..118 => U16,
+    gain_type: 118..120 => Enum(GainType),
+    instrument_gain_constant: 120..122 => U16,
+    instrument_initial_gain: 122..124 => U16,
+    correlated: 124..126 => Enum(Correlated),
+    sweep_frequency_at_start: 126..128 => U16,
+    sweep_frequency_at_end: 128..130 => U16,
+    sweep_length: 130..132 => U16,
+    sweep_type: 132..134 => Enum(SweepType),
+    sweep_trace_taper_length_at_start: 134..136 => U16,
+    sweep_trace_taper_length_at_end: 136..138 => U16,
+    taper_type: 138..140 => Enum(TaperType),
+    alias_filter_frequency: 140..142 => U16,
+    alias_filter_slope: 142..144 => U16,
+    notch_filter_frequency: 144..146 => U16,
+    notch_filter_slope: 146..148 => U16,
+    low_cut_frequency: 148..150 => U16,
+    high_cut_frequency: 150..152 => U16,
+    low_cut_slope: 152..154 => U16,
+    high_cut_slope: 154..156 => U16,
+    year_recorded: 156..158 => U16,
+    day_of_year: 158..160 => U16,
+    hour_of_day: 160..162 => U16,
+    minute_of_hour: 162..164 => U16,
+    second_of_minute: 164..166 => U16,
+    time_base_code: 166..168 => Enum(TimeBasisCode),
+    trace_weighting_factor: 168..170 => U16,
+    geophone_group_number_roll_pos1: 170..172 => U16,
+    geophone_group_number_first_trace_orig_field: 172..174 => U16,
+    geophone_group_number_last_trace_orig_field: 174..176 => U16,
+    gap_size: 176..178 => U16,
+    over_travel: 178..180 => Enum(OverTravel),
+    x_ensemble: 180..184 => Coordinate,
+    y_ensemble: 184..188 => Coordinate,
+    inline_no: 188..192 => I32,
+    crossline_no: 192..196 => I32,
+    shot_point_no: 196..200 => I32,
+    shot_point_scalar: 200..202 => I16,
+    trace_value_measurement_unit: 202..204 => Enum(TraceValueUnit),
+    transduction_constant_mantissa: 204..208 => I32,
+    transduction_constant_power: 208..210 => U16,
+    transduction_units: 210..212 => Enum(TransductionUnits),
+    trace_identifier: 212..214 => U16,
+    time_scalar_trace_header: 214..216 => U16,
+    source_type: 216..218 => Enum(SourceType),
+    source_energy_direction_v: 218..220 => U16,
+    source_energy_direction_il: 220..222 => U16,
+    source_energy_direction_xl: 222..224 => U16,
+    source_measurement_mantissa: 224..228 => I32,
+    source_measurement_exponent: 228..230 => U16,
+    source_measurement_unit: 230..232 => Enum(SourceMeasurementUnit),
+    trace_name: 232..240 => Bytes,
+);
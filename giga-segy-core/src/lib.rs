@@ -12,42 +12,79 @@ extern crate serde;
 extern crate serde_json;
 
 pub mod bitconverter;
+pub mod decoder;
+pub mod digest;
+pub mod effective;
 pub mod enums;
 pub mod errors;
+pub mod geographic;
+pub mod handle;
 pub mod header_structs;
+pub mod layout;
+pub mod progress;
+pub mod recording_time;
 pub mod settings;
+pub mod standard_locations;
+pub mod stanza;
 #[cfg(test)]
 mod tests;
+pub mod transform;
 
 pub use errors::RsgError;
 
+pub use effective::{EffectiveParams, Provenance};
 pub use enums::*;
+pub use geographic::GeoPosition;
+pub use handle::{SegyHandle, TracesIter};
 pub use header_structs::*;
-pub use settings::SegySettings;
+pub use progress::{ProgressPhase, SegyProgress};
+pub use recording_time::RecordingTime;
+pub use settings::{HeaderProfile, SegySettings};
+pub use transform::AffineTransform;
+
+use std::convert::TryFrom;
 
 pub const TAPE_LABEL_LEN: usize = 128;
 pub const TEXT_HEADER_LEN: usize = 3200;
 pub const BIN_HEADER_LEN: usize = 400;
 pub const TRACE_HEADER_LEN: usize = 240;
-pub const INLINE_BYTE_LOCATION: usize = 188;
-pub const CROSSLINE_BYTE_LOCATION: usize = 192;
-pub const CDPX_BYTE_LOCATION: usize = 180;
-pub const CDPY_BYTE_LOCATION: usize = 184;
+/// The byte index (counting from zero) of the number of samples of a trace in its first trace
+/// header extension (SEG-Y rev 2). This is used if the trace has too many samples for
+/// [`TraceHeader::no_samples_in_trace`], which is then zero.
+pub const EXTENDED_SAMPLE_COUNT_BYTE_LOCATION: usize = 136;
+pub const INLINE_BYTE_LOCATION: usize = standard_locations::INLINE_NO;
+pub const CROSSLINE_BYTE_LOCATION: usize = standard_locations::CROSSLINE_NO;
+pub const CDPX_BYTE_LOCATION: usize = standard_locations::X_ENSEMBLE;
+pub const CDPY_BYTE_LOCATION: usize = standard_locations::Y_ENSEMBLE;
 
 /// This structure represents a SEG-Y trace.
 ///
 /// The Header is parsed and stored in the structure, the data is stored
 /// in a memory map and referenced here as start and end indices.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Trace {
     /// A parsed trace header which contains the trace metadata.
     pub(crate) trace_header: TraceHeader,
-    /// Starting byte of the trace on the map.
-    pub(crate) trace_start_byte: usize,
-    /// Length of the trace in bytes on the map.
-    pub(crate) trace_byte_len: usize,
+    /// Starting byte of the trace on the map (or in the file).
+    pub(crate) trace_start_byte: u64,
+    /// Length of the trace in bytes on the map (or in the file).
+    pub(crate) trace_byte_len: u64,
+    /// The number of 240 byte trace header extensions between the trace header and the data.
+    pub(crate) extension_count: usize,
+    /// The number of bytes of a non-standard payload between the trace header extensions and
+    /// the data (see [`SegySettings::set_trace_prefix_bytes`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) prefix_len: usize,
+    /// The inline number in the trace header, if the inline number of the trace header was
+    /// replaced by a synthesized one when it was read.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) original_inline_no: Option<i32>,
+    /// The crossline number in the trace header, if the crossline number of the trace header
+    /// was replaced by a synthesized one when it was read.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) original_crossline_no: Option<i32>,
 }
 
 /// This structure contains all of the metadata for opening a SEG-Y file.
@@ -55,12 +92,40 @@ pub struct Trace {
 /// Different implementations of [`SegyMetadata`] can then be made, depending on what type `S` is
 /// used for the settings. In general [`SegyMetadata`] is used internally by `giga_segy_input`
 /// and `giga_segy_output`, but may also prove suitable for uses elsewhere.
+///
+/// NB: The metadata cannot be `Eq`, since the binary header has floating point fields.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SegyMetadata<S> {
     pub tape_label: Option<TapeLabel>,
     pub text_header: String,
     pub extended_headers: Vec<String>,
     pub bin_header: BinHeader,
     pub settings: S,
+    /// The binary header as it was read from a file, if it was. This keeps the bytes which do
+    /// not belong to any field of [`BinHeader`] (eg. the unassigned bytes 3261-3500), so that
+    /// they can be preserved when the headers are written again.
+    pub raw_bin_header: Option<[u8; BIN_HEADER_LEN]>,
+    /// The encoding with which the text header was decoded, if it was read from a file (see
+    /// [`SegySettings::set_text_header_encoding`]).
+    pub text_header_encoding: Option<TextEncoding>,
+    /// How the lines of the text header were found, if it was read from a file.
+    pub text_header_layout: Option<TextHeaderLayout>,
+    /// The position of the text header in the file, if it was read from one. This is zero,
+    /// the length of the tape label, or the number of bytes which were skipped before it (see
+    /// [`SegySettings::set_scan_for_text_header`]).
+    pub text_header_offset: Option<usize>,
+    /// The values which were used to interpret the file, if it was read from one.
+    pub effective_params: Option<EffectiveParams>,
+}
+
+/// The headers and settings of a [`SegyMetadata`], as given by [`SegyMetadata::into_parts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegyMetadataParts<S> {
+    pub tape_label: Option<TapeLabel>,
+    pub text_header: String,
+    pub extended_headers: Vec<String>,
+    pub bin_header: BinHeader,
+    pub settings: S,
 }
 
 impl Trace {
@@ -87,25 +152,150 @@ impl Trace {
     /// assert_eq!(tr.len(), 100 * 4);
     /// ```
     pub fn new(trace_header: TraceHeader, data_start: usize, data_len: usize) -> Self {
+        Trace::new_u64(trace_header, data_start as u64, data_len as u64)
+    }
+
+    /// Construct a new "trace" in the same way as [`Trace::new`], with byte locations which
+    /// need not fit in a `usize` (eg. for a file which is written on a 32-bit target).
+    /// ```
+    /// use giga_segy_core::{Trace, TraceHeader};
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let tr = Trace::new_u64(TraceHeader::default(), 6_000_000_000, 400);
+    /// assert_eq!(tr.get_start_u64(), 6_000_000_000);
+    /// assert_eq!(tr.end_u64(), 6_000_000_400);
+    /// assert_eq!(tr.len(), 400);
+    /// ```
+    pub fn new_u64(trace_header: TraceHeader, data_start: u64, data_len: u64) -> Self {
         Trace {
             trace_header,
             trace_start_byte: data_start,
             trace_byte_len: data_len,
+            extension_count: 0,
+            prefix_len: 0,
+            original_inline_no: None,
+            original_crossline_no: None,
+        }
+    }
+
+    /// Construct a new "trace" in the same way as [`Trace::new`], for a trace which has
+    /// `extension_count` trace header extensions (of 240 bytes each) between the trace header
+    /// and the trace data. NB: `data_start` is still the start of the data itself.
+    /// ```
+    /// use giga_segy_core::{Trace, TraceHeader};
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let tr = Trace::new_with_extensions(TraceHeader::default(), 4080, 400, 1);
+    /// assert_eq!(tr.extension_count(), 1);
+    /// assert_eq!(tr.extension_range(), 3840..4080);
+    /// assert_eq!(tr.data_range(), 4080..4480);
+    /// ```
+    pub fn new_with_extensions(
+        trace_header: TraceHeader,
+        data_start: usize,
+        data_len: usize,
+        extension_count: usize,
+    ) -> Self {
+        Trace {
+            extension_count,
+            ..Trace::new(trace_header, data_start, data_len)
+        }
+    }
+
+    /// Set the number of bytes of a non-standard payload which sit between the trace header
+    /// extensions and the data (see [`SegySettings::set_trace_prefix_bytes`]).
+    /// NB: `data_start` is still the start of the data itself.
+    /// ```
+    /// use giga_segy_core::{Trace, TraceHeader};
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let tr = Trace::new_with_extensions(TraceHeader::default(), 4100, 400, 1).with_prefix_len(20);
+    /// assert_eq!(tr.prefix_len(), 20);
+    /// assert_eq!(tr.extension_range(), 3840..4080);
+    /// assert_eq!(tr.data_range(), 4100..4500);
+    /// ```
+    pub fn with_prefix_len(self, prefix_len: usize) -> Self {
+        Trace { prefix_len, ..self }
+    }
+
+    /// Record the inline and crossline numbers which were in the trace header, when the ones
+    /// of the parsed header were synthesized (eg. by `giga-segy-in` from
+    /// [`SegySettings::set_override_dim_x`]).
+    /// ```
+    /// use giga_segy_core::{Trace, TraceHeader};
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let tr = Trace::new(TraceHeader::new_3d(0, 0, 3, 4, 1), 3840, 400);
+    /// assert!(!tr.line_numbers_synthesized());
+    /// assert_eq!(tr.original_inline_no(), None);
+    ///
+    /// let tr = tr.with_original_line_numbers(1200, 650);
+    /// assert!(tr.line_numbers_synthesized());
+    /// assert_eq!(tr.get_header().inline_no, 3);
+    /// assert_eq!(tr.original_inline_no(), Some(1200));
+    /// assert_eq!(tr.original_crossline_no(), Some(650));
+    /// ```
+    pub fn with_original_line_numbers(self, inline_no: i32, crossline_no: i32) -> Self {
+        Trace {
+            original_inline_no: Some(inline_no),
+            original_crossline_no: Some(crossline_no),
+            ..self
         }
     }
 
+    /// Check whether the inline and crossline numbers of the trace header were synthesized
+    /// when it was read, rather than taken from the trace header in the file.
+    pub fn line_numbers_synthesized(&self) -> bool {
+        self.original_inline_no.is_some() || self.original_crossline_no.is_some()
+    }
+
+    /// Get the inline number in the trace header in the file, if it was replaced by a
+    /// synthesized one (see [`Self::line_numbers_synthesized`]).
+    pub fn original_inline_no(&self) -> Option<i32> {
+        self.original_inline_no
+    }
+
+    /// Get the crossline number in the trace header in the file, if it was replaced by a
+    /// synthesized one (see [`Self::line_numbers_synthesized`]).
+    pub fn original_crossline_no(&self) -> Option<i32> {
+        self.original_crossline_no
+    }
+
     /// Get a reference to the trace header.
     pub fn get_header(&self) -> &TraceHeader {
         &self.trace_header
     }
 
+    /// Get a mutable reference to the trace header.
+    ///
+    /// NB: This only changes the parsed header. It does not change the file, nor the byte
+    /// range of the trace data, and the lookups of a SEG-Y file are not updated.
+    pub fn header_mut(&mut self) -> &mut TraceHeader {
+        &mut self.trace_header
+    }
+
     /// Get the starting byte of the trace data.
+    ///
+    /// NB: If the position does not fit in a `usize` (which can only happen for traces created
+    /// with [`Trace::new_u64`] on targets where `usize` is smaller than 64 bits), this saturates
+    /// at `usize::MAX`. The same is true of the other functions which return byte positions or
+    /// lengths as a `usize`. See [`Trace::get_start_u64`].
     pub fn get_start(&self) -> usize {
+        saturating_usize(self.trace_start_byte)
+    }
+
+    /// Get the starting byte of the trace data as a `u64`.
+    pub fn get_start_u64(&self) -> u64 {
         self.trace_start_byte
     }
 
     /// Get the length of the data in bytes.
     pub fn len(&self) -> usize {
+        saturating_usize(self.trace_byte_len)
+    }
+
+    /// Get the length of the data in bytes as a `u64`.
+    pub fn len_u64(&self) -> u64 {
         self.trace_byte_len
     }
 
@@ -113,6 +303,60 @@ impl Trace {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Get the number of trace header extensions of the trace.
+    pub fn extension_count(&self) -> usize {
+        self.extension_count
+    }
+
+    /// Get the number of bytes of a non-standard payload between the trace header extensions
+    /// and the data.
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// Get the range of bytes which contain the trace header extensions (which directly
+    /// precede the trace data, or the prefix if there is one). The range is empty if there are
+    /// no extensions.
+    pub fn extension_range(&self) -> std::ops::Range<usize> {
+        let end = self.get_start() - self.prefix_len;
+        (end - self.extension_count * TRACE_HEADER_LEN)..end
+    }
+
+    /// Get the byte after the last byte of the trace data.
+    pub fn end(&self) -> usize {
+        saturating_usize(self.end_u64())
+    }
+
+    /// Get the byte after the last byte of the trace data as a `u64`.
+    pub fn end_u64(&self) -> u64 {
+        self.trace_start_byte + self.trace_byte_len
+    }
+
+    /// Get the range of bytes which contain the trace data.
+    pub fn data_range(&self) -> std::ops::Range<usize> {
+        self.get_start()..self.end()
+    }
+
+    /// Get the number of samples in the trace data for a given sample format. If the length of
+    /// the data is not a multiple of the size of a sample, the incomplete sample is not counted.
+    /// ```
+    /// use giga_segy_core::{Trace, TraceHeader};
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let tr = Trace::new(TraceHeader::default(), 3600, 402);
+    /// assert_eq!(tr.sample_count(SampleFormatCode::Float32), 100);
+    /// assert_eq!(tr.sample_count(SampleFormatCode::Int16), 201);
+    /// ```
+    pub fn sample_count(&self, format: SampleFormatCode) -> usize {
+        saturating_usize(self.trace_byte_len / format.datum_byte_length() as u64)
+    }
+}
+
+/// Convert a byte position to a `usize`, saturating if it does not fit.
+fn saturating_usize(x: u64) -> usize {
+    usize::try_from(x).unwrap_or(usize::MAX)
 }
 
 impl<S> SegyMetadata<S> {
@@ -129,6 +373,11 @@ impl<S> SegyMetadata<S> {
             extended_headers,
             bin_header,
             settings,
+            raw_bin_header: None,
+            text_header_encoding: None,
+            text_header_layout: None,
+            text_header_offset: None,
+            effective_params: None,
         }
     }
 
@@ -160,13 +409,82 @@ impl<S> SegyMetadata<S> {
     /// Get the text header as collection of short substrings. This function
     /// clones the content of the text header.
     pub fn get_text_header_lines(&self) -> Vec<String> {
-        self.text_header
-            .chars()
-            .collect::<Vec<char>>()
-            .as_slice()
-            .chunks(80)
-            .map(|c| c.iter().collect::<String>())
-            .collect::<Vec<String>>()
+        self.text_header_lines().map(String::from).collect()
+    }
+
+    /// Iterate over the lines (card images) of the text header without copying them. Each line
+    /// is eighty characters long, except perhaps the last one.
+    ///
+    /// NB: The text header is normally ASCII, so each line is also eighty bytes long. Should the
+    /// header contain multibyte characters, lines are still split after eighty characters.
+    pub fn text_header_lines(&self) -> impl Iterator<Item = &str> {
+        let mut rest = self.text_header.as_str();
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let split = if rest.len() <= 80 {
+                rest.len()
+            } else if rest.as_bytes()[..80].is_ascii() {
+                80
+            } else {
+                rest.char_indices().nth(80).map_or(rest.len(), |(i, _)| i)
+            };
+            let (line, remainder) = rest.split_at(split);
+            rest = remainder;
+            Some(line)
+        })
+    }
+
+    /// Get line `n` of the text header, counting from one (as in `C 1`, `C 2` etc.). Returns
+    /// `None` if there is no such line.
+    pub fn line(&self, n: usize) -> Option<&str> {
+        self.text_header_lines().nth(n.checked_sub(1)?)
+    }
+
+    /// Get the lines of the text header with the trailing spaces of each line removed. Leading
+    /// spaces (eg. for indentation) are kept, and there are still as many lines as with
+    /// [`Self::get_text_header_lines`].
+    pub fn get_text_header_lines_trimmed(&self) -> Vec<String> {
+        self.text_header_lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    }
+
+    /// Get the text header as its lines with the trailing spaces removed (see
+    /// [`Self::get_text_header_lines_trimmed`]), separated by line feeds. Empty lines at the
+    /// end of the header are dropped.
+    /// ```
+    /// use giga_segy_core::SegyMetadata;
+    /// use giga_segy_core::BinHeader;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    /// use giga_segy_core::enums::SampleFormatCode;
+    ///
+    /// let bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    /// let text_header = format!("{:<80}{:<80}{:<3040}", "C 1 CLIENT: GIGA", "C 2   INDENTED", "");
+    /// let metadata = SegyMetadata::new(None, text_header, vec![], bin_header, ());
+    /// assert_eq!(metadata.get_text_header_trimmed(), "C 1 CLIENT: GIGA\nC 2   INDENTED");
+    /// ```
+    pub fn get_text_header_trimmed(&self) -> String {
+        let mut lines = self.get_text_header_lines_trimmed();
+        while matches!(lines.last(), Some(line) if line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// Check whether the text header says nothing: Every line is either blank or only has the
+    /// card number (eg. `C 1` or `C01`), as in a header which was written from an empty
+    /// template. This can be used to find files which are missing their documentation.
+    pub fn text_header_is_blank(&self) -> bool {
+        self.text_header_lines().all(|line| {
+            let line = line.trim();
+            let number = match line.strip_prefix('C') {
+                Some(rest) => rest.trim_start(),
+                None => return line.is_empty(),
+            };
+            number.len() <= 2 && number.bytes().all(|c| c.is_ascii_digit())
+        })
     }
 
     /// Get the binary header.
@@ -174,28 +492,158 @@ impl<S> SegyMetadata<S> {
         &self.bin_header
     }
 
+    /// Get the binary header as it was read from a file, if it was.
+    pub fn get_raw_bin_header(&self) -> Option<&[u8; BIN_HEADER_LEN]> {
+        self.raw_bin_header.as_ref()
+    }
+
+    /// Get the encoding with which the text header was decoded, if it was read from a file.
+    pub fn get_text_header_encoding(&self) -> Option<TextEncoding> {
+        self.text_header_encoding
+    }
+
+    /// Get how the lines of the text header were found, if it was read from a file.
+    pub fn get_text_header_layout(&self) -> Option<TextHeaderLayout> {
+        self.text_header_layout
+    }
+
+    /// Get the position of the text header in the file, if it was read from one.
+    pub fn get_text_header_offset(&self) -> Option<usize> {
+        self.text_header_offset
+    }
+
+    /// Get the values which were used to interpret the file, if it was read from one.
+    pub fn get_effective_params(&self) -> Option<&EffectiveParams> {
+        self.effective_params.as_ref()
+    }
+
     /// This function gets the Tape Label in a rust compatible format.
     pub fn get_readable_tape_label(&self) -> Option<ReadableTapeLabel> {
         self.tape_label.as_ref().map(|l| l.to_readable())
     }
 
-    /// This function gets all the fields of [`SegyMetadata`] and discards the instance. Used to get all
-    /// data in an efficient manner.
-    /// NB: The internal mapping is discarded in the process.
-    pub fn deconstruct(self) -> (Option<TapeLabel>, String, Vec<String>, BinHeader, S) {
+    /// This function gets the headers and the settings of [`SegyMetadata`] and discards the
+    /// instance. Used to get all data in an efficient manner.
+    /// NB: The raw binary header and the other details of how the file was read are discarded.
+    /// ```
+    /// use giga_segy_core::{BinHeader, SegyMetadata};
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    ///
+    /// let bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    /// let metadata = SegyMetadata::new(None, "C 1".to_string(), vec![], bin_header, ());
+    /// let parts = metadata.into_parts();
+    /// assert_eq!(parts.text_header, "C 1");
+    /// assert!(parts.extended_headers.is_empty());
+    /// ```
+    pub fn into_parts(self) -> SegyMetadataParts<S> {
         let SegyMetadata {
             tape_label,
             text_header,
             extended_headers,
             bin_header,
             settings,
+            ..
         } = self;
-        (
+        SegyMetadataParts {
             tape_label,
             text_header,
             extended_headers,
             bin_header,
             settings,
+        }
+    }
+
+    /// This function gets all the fields of [`SegyMetadata`] and discards the instance, in the
+    /// same way as [`Self::into_parts`], but as a tuple.
+    #[deprecated(note = "Use `into_parts`, which names the parts.")]
+    pub fn deconstruct(self) -> (Option<TapeLabel>, String, Vec<String>, BinHeader, S) {
+        let parts = self.into_parts();
+        (
+            parts.tape_label,
+            parts.text_header,
+            parts.extended_headers,
+            parts.bin_header,
+            parts.settings,
         )
     }
+
+    /// Take the text header out of the metadata, leaving an empty string in its place.
+    ///
+    /// NB: `giga_segy_in::SegyFile` does not give mutable access to its metadata, so this can
+    /// not leave an open file with a missing text header.
+    pub fn take_text_header(&mut self) -> String {
+        std::mem::take(&mut self.text_header)
+    }
+
+    /// Take the extended headers out of the metadata, leaving an empty list in their place.
+    ///
+    /// NB: As with [`SegyMetadata::take_text_header`], this can not be done to the metadata of
+    /// an open `giga_segy_in::SegyFile`.
+    pub fn take_extended_headers(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.extended_headers)
+    }
+
+    /// Get the binary header and discard everything else.
+    pub fn into_bin_header(self) -> BinHeader {
+        self.bin_header
+    }
+
+    /// Convert the settings to another type, keeping the headers as they are.
+    pub fn map_settings<T, F: FnOnce(S) -> T>(self, f: F) -> SegyMetadata<T> {
+        let SegyMetadata {
+            tape_label,
+            text_header,
+            extended_headers,
+            bin_header,
+            settings,
+            raw_bin_header,
+            text_header_encoding,
+            text_header_layout,
+            text_header_offset,
+            effective_params,
+        } = self;
+        SegyMetadata {
+            tape_label,
+            text_header,
+            extended_headers,
+            bin_header,
+            settings: f(settings),
+            raw_bin_header,
+            text_header_encoding,
+            text_header_layout,
+            text_header_offset,
+            effective_params,
+        }
+    }
+}
+
+#[cfg(feature = "to_json")]
+impl<S: serde::Serialize> SegyMetadata<S> {
+    /// Export the metadata as JSON: The tape label (as a [`ReadableTapeLabel`]), the text header
+    /// with the trailing spaces of its lines removed (see
+    /// [`SegyMetadata::get_text_header_trimmed`]), the extended headers, the binary header and
+    /// the settings. The text header as it is (with all of its padding) is included as
+    /// `raw_text_header` if `include_raw_text_header` is set.
+    pub fn to_json(&self, include_raw_text_header: bool) -> Result<String, RsgError> {
+        #[derive(serde::Serialize)]
+        struct MetadataJson<'a, S> {
+            tape_label: Option<ReadableTapeLabel>,
+            text_header: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            raw_text_header: Option<&'a str>,
+            extended_headers: &'a [String],
+            bin_header: &'a BinHeader,
+            settings: &'a S,
+        }
+        let json = MetadataJson {
+            tape_label: self.get_readable_tape_label(),
+            text_header: self.get_text_header_trimmed(),
+            raw_text_header: Some(self.get_text_header()).filter(|_| include_raw_text_header),
+            extended_headers: &self.extended_headers,
+            bin_header: &self.bin_header,
+            settings: &self.settings,
+        };
+        serde_json::to_string(&json).map_err(RsgError::SerdeError)
+    }
 }
@@ -0,0 +1,35 @@
+//! This module contains the [`SegyProgress`] structure, which is used to report the progress of
+//! long running operations (eg. opening a large file) to a callback.
+//!
+//! Such callbacks return a [`std::ops::ControlFlow`]. If they return `ControlFlow::Break(())`,
+//! the operation is stopped and [`crate::RsgError::Cancelled`] is returned.
+
+/// The stage of an operation that is being reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressPhase {
+    /// The tape label, text header, binary header and extended headers are read.
+    Headers,
+    /// The trace headers are scanned.
+    TraceScan,
+    /// Traces are written to a new file.
+    Writing,
+}
+
+/// A report on the progress of an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegyProgress {
+    /// The current stage of the operation.
+    pub phase: ProgressPhase,
+    /// The number of traces processed so far.
+    pub traces_done: usize,
+    /// The total number of traces, if it is known.
+    pub traces_total: Option<usize>,
+    /// The number of bytes processed so far.
+    pub bytes_done: usize,
+    /// The total number of bytes, if it is known.
+    pub bytes_total: Option<usize>,
+}
+
+/// The number of traces between two progress reports. Reports are only made at this coarse
+/// granularity so that the overhead of the callback is negligible.
+pub const PROGRESS_INTERVAL: usize = 256;
@@ -0,0 +1,161 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This module puts together the time at which a trace was recorded from bytes 157-168 of its
+//! trace header (year, day of the year, hour, minute, second and time basis code).
+//!
+//! NB: The fields of the trace header are kept as they are in the file. The functions of this
+//! module only give another view of them.
+use crate::enums::TimeBasisCode;
+use crate::TraceHeader;
+
+/// The number of days before the first of each month in a year which is not a leap year.
+const DAYS_BEFORE_MONTH: [u16; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+/// The time at which a trace was recorded, as given by its trace header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecordingTime {
+    /// The year, with four digits.
+    pub year: u16,
+    /// The month, from 1 to 12.
+    pub month: u8,
+    /// The day of the month, from 1 to 31.
+    pub day: u8,
+    /// The hour, from 0 to 23.
+    pub hour: u8,
+    /// The minute, from 0 to 59.
+    pub minute: u8,
+    /// The second, from 0 to 59.
+    pub second: u8,
+    /// What the time is relative to (eg. local time or UTC).
+    pub basis: TimeBasisCode,
+}
+
+impl RecordingTime {
+    /// Put the time together from the fields of a trace header, where `day_of_year` counts from
+    /// 1 for the first of January.
+    ///
+    /// A year below 100 is taken to have two digits: Years up to 30 are in the 2000s, and later
+    /// ones in the 1900s (eg. 30 is 2030 and 31 is 1931). `None` is returned if all of the
+    /// fields (apart from the basis) are zero, which is how most files leave them, or if any of
+    /// them is out of range, such as day 366 of a year which is not a leap year.
+    /// ```
+    /// use giga_segy_core::enums::TimeBasisCode;
+    /// use giga_segy_core::RecordingTime;
+    ///
+    /// let time = RecordingTime::new(96, 60, 13, 5, 0, TimeBasisCode::CoordinatedUTC).unwrap();
+    /// assert_eq!((time.year, time.month, time.day), (1996, 2, 29));
+    /// ```
+    pub fn new(
+        year: u16,
+        day_of_year: u16,
+        hour: u16,
+        minute: u16,
+        second: u16,
+        basis: TimeBasisCode,
+    ) -> Option<Self> {
+        if [year, day_of_year, hour, minute, second]
+            .iter()
+            .all(|v| *v == 0)
+        {
+            return None;
+        }
+        let year = match year {
+            0..=30 => 2000 + year,
+            31..=99 => 1900 + year,
+            _ => year,
+        };
+        let (month, day) = month_and_day(year, day_of_year)?;
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        Some(RecordingTime {
+            year,
+            month,
+            day,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            basis,
+        })
+    }
+
+    /// Get the number of seconds since 1970-01-01 00:00:00 UTC. This is only given if the time
+    /// basis is GMT or UTC, since the offset of local time is not known, and GPS time and other
+    /// bases differ from UTC.
+    /// ```
+    /// use giga_segy_core::enums::TimeBasisCode;
+    /// use giga_segy_core::RecordingTime;
+    ///
+    /// let time = RecordingTime::new(2000, 1, 0, 0, 1, TimeBasisCode::GreenwichGMT).unwrap();
+    /// assert_eq!(time.to_unix_seconds(), Some(946_684_801));
+    ///
+    /// let time = RecordingTime::new(2000, 1, 0, 0, 1, TimeBasisCode::Local).unwrap();
+    /// assert_eq!(time.to_unix_seconds(), None);
+    /// ```
+    pub fn to_unix_seconds(&self) -> Option<i64> {
+        match self.basis {
+            TimeBasisCode::GreenwichGMT | TimeBasisCode::CoordinatedUTC => {}
+            _ => return None,
+        }
+        let days = days_since_epoch(self.year, self.month, self.day);
+        let seconds = self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        Some(days * 86_400 + seconds)
+    }
+}
+
+impl TraceHeader {
+    /// Get the time at which the trace was recorded (see [`RecordingTime::new`]), or `None` if
+    /// the trace header does not give it.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_core::enums::TimeBasisCode;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let mut header = TraceHeader::new_2d(0, 0, 1);
+    /// assert_eq!(header.recording_datetime(), None);
+    ///
+    /// header.year_recorded = 2023;
+    /// header.day_of_year = 32;
+    /// header.hour_of_day = 12;
+    /// header.time_base_code = TimeBasisCode::CoordinatedUTC;
+    /// let time = header.recording_datetime().unwrap();
+    /// assert_eq!((time.month, time.day, time.hour), (2, 1, 12));
+    /// ```
+    pub fn recording_datetime(&self) -> Option<RecordingTime> {
+        RecordingTime::new(
+            self.year_recorded,
+            self.day_of_year,
+            self.hour_of_day,
+            self.minute_of_hour,
+            self.second_of_minute,
+            self.time_base_code,
+        )
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Turn the day of the year (counting from 1) into the month and the day of the month.
+fn month_and_day(year: u16, day_of_year: u16) -> Option<(u8, u8)> {
+    let leap = is_leap_year(year) as u16;
+    if day_of_year == 0 || day_of_year > 365 + leap {
+        return None;
+    }
+    let days_before = |month: usize| DAYS_BEFORE_MONTH[month] + if month >= 2 { leap } else { 0 };
+    let month = (0..12).rev().find(|m| days_before(*m) < day_of_year)?;
+    Some((month as u8 + 1, (day_of_year - days_before(month)) as u8))
+}
+
+/// Count the days from 1970-01-01 to a date of the proleptic Gregorian calendar.
+fn days_since_epoch(year: u16, month: u8, day: u8) -> i64 {
+    // Count the years from March, so that the leap day is at the end of the year.
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
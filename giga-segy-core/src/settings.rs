@@ -4,8 +4,15 @@
 //! NB: It should be noted that since few files are in keeping with the proper SEG-Y format, this
 //! is necessary. On the other hand, using this functionality can easily cause incorrect writing
 //! or parsing of SEG-Y files and should therefore be done with care.
-use crate::enums::{MeasurementSystem, OrderTraceBy, SampleFormatCode, TraceIdCode};
+use crate::decoder::{CustomDecoders, TraceDecoder};
+use crate::enums::{
+    AuxPolicy, CoordErrorPolicy, EndianDetection, LineNumberingSource, MapPopulation,
+    MeasurementSystem, OrderTraceBy, SampleFormatCode, TextEncoding, TraceIdCode,
+};
 use crate::errors::*;
+use crate::layout::{find_bidx_collisions, BidxCollision};
+use crate::standard_locations;
+use crate::transform::AffineTransform;
 use crate::{
     CDPX_BYTE_LOCATION, CDPY_BYTE_LOCATION, CROSSLINE_BYTE_LOCATION, INLINE_BYTE_LOCATION,
     TRACE_HEADER_LEN,
@@ -13,8 +20,11 @@ use crate::{
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use std::sync::Arc;
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 /// This structure holds a list of various settings to be imported for the custom reading of
 /// byte locations of various variables in the headers and other things when interpreting a SEG-Y file.
 ///
@@ -23,18 +33,21 @@ use serde::{Deserialize, Serialize};
 /// and in other cases the value of one field may influence the value of another field.
 /// Therefore using setter and getter functions is generally safer.
 pub struct SegySettings {
-    /// An enum which determines what traces are ordered by.
+    /// An enum which determines what traces are counted by when the inline and crossline
+    /// numbers are derived from custom dimensions.
     pub(crate) order_trace_by: OrderTraceBy,
     /// Should the endianness be overwridden to LE?
     pub(crate) override_to_le: Option<bool>,
+    /// How the endianness is determined if it is not overridden.
+    pub(crate) endianness_detection: EndianDetection,
     /// Should trace format be overwridden?
     pub(crate) override_trace_format: Option<SampleFormatCode>,
     /// Reads trace header coordinates as f32 instead of i32.
     pub(crate) override_coordinate_format: Option<SampleFormatCode>,
     /// A chance to override the z_axis domain by changing the id code of the traces.
     pub(crate) override_trace_id_code: Option<TraceIdCode>,
-    /// A chance to override z-axis unit ONLY. NB: Not used in crate. Provided for consuming
-    /// libraries and applications.
+    /// A chance to override z-axis unit ONLY. NB: Only used to determine the vertical axis of
+    /// a file. Otherwise provided for consuming libraries and applications.
     pub(crate) override_trace_depth_units: Option<MeasurementSystem>,
     /// A chance to override xy-axis units ONLY.
     pub(crate) override_coordinate_units: Option<MeasurementSystem>,
@@ -66,8 +79,85 @@ pub struct SegySettings {
     pub(crate) override_u: Option<[f64; 3]>,
     /// Sets a custom v unit vector.
     pub(crate) override_v: Option<[f64; 3]>,
-    /// Sets a custom w unit vector.
+    /// A sample interval which is used if neither the trace headers nor the binary header have one.
     pub(crate) override_sample_interval: Option<f64>,
+    /// Whether the text header is brought into card image form when writing.
+    pub(crate) strict_text_header: bool,
+    /// Whether the sample format implied by the file size is used if it disagrees with the
+    /// declared sample format.
+    pub(crate) trust_file_size: bool,
+    /// Whether muted samples are set to NaN instead of zero when reading muted trace data.
+    pub(crate) mute_with_nan: bool,
+    /// What happens when a coordinate in a trace header cannot be converted to an integer.
+    pub(crate) on_coordinate_parse_error: CoordErrorPolicy,
+    /// Whether more than 65535 traces may be written, with the number of traces in the
+    /// extended field of the binary header.
+    pub(crate) extended_trace_count: bool,
+    /// Whether the sequence numbers of trace headers are filled in when writing, if they are
+    /// left at zero.
+    pub(crate) auto_sequence: bool,
+    /// Whether a trace header without a sample count is an error when writing a file with
+    /// variable length traces, rather than being filled in.
+    pub(crate) strict_sample_count: bool,
+    /// Whether only a few values of each trace header are kept in memory when reading, with
+    /// the full header being parsed again when it is needed.
+    pub(crate) compact_headers: bool,
+    /// The largest file (in bytes) which is mapped to memory when reading, if there is a limit
+    /// beyond the address space of the platform.
+    pub(crate) mapping_budget: Option<u64>,
+    /// Whether the values which are the same in many trace headers are stored only once when
+    /// reading.
+    pub(crate) dedup_headers: bool,
+    /// Whether files may be written with byte indices which overlap other fields of the trace
+    /// header.
+    pub(crate) allow_bidx_collisions: bool,
+    /// Whether the samples of integer formats are scaled with the trace weighting factor of the
+    /// trace header when they are read as floats.
+    pub(crate) apply_trace_weighting: bool,
+    /// A factor by which all samples are multiplied when they are read as floats.
+    pub(crate) user_gain: f32,
+    /// The number of bytes of a non-standard payload between each trace header (and its
+    /// extensions) and the trace data, which are skipped when reading.
+    pub(crate) trace_prefix_bytes: usize,
+    /// How the text header is decoded when reading.
+    pub(crate) text_header_encoding: TextEncoding,
+    /// The value of the trace headers by which the traces are sorted when reading.
+    pub(crate) sort_traces_by: OrderTraceBy,
+    /// The transform which is applied to the ensemble, source and receiver group coordinates
+    /// when reading.
+    pub(crate) coordinate_transform: Option<AffineTransform>,
+    /// Which traces are kept when reading, depending on whether they are auxiliary traces.
+    pub(crate) aux_trace_policy: AuxPolicy,
+    /// The largest window (in bytes) of the file which is mapped to memory at once when
+    /// reading, or zero if the whole file is mapped.
+    pub(crate) max_map_window: usize,
+    /// Whether a file whose binary header gives a SEG-Y revision which is not supported is
+    /// rejected when reading.
+    pub(crate) strict_revision: bool,
+    /// Whether the samples are read in the opposite byte order to the headers.
+    pub(crate) swap_data_byte_order: bool,
+    /// Whether a time window which is not within a trace is rejected, rather than clamped to
+    /// the trace.
+    pub(crate) strict_time_window: bool,
+    /// How many bytes at the start of a file are searched for the text header when reading,
+    /// or zero if the file must start with it (or with a tape label).
+    pub(crate) scan_for_text_header: usize,
+    /// The largest number of extended text headers which the binary header may give.
+    pub(crate) max_extended_headers: u32,
+    /// Whether the extended text headers are detected if the binary header gives an
+    /// implausible number of them.
+    pub(crate) detect_extended_headers: bool,
+    /// Whether a card which records how the file was written is added to the text header when
+    /// a file is finalized.
+    pub(crate) append_provenance_card: bool,
+    /// How much of a file is brought into memory when it is mapped.
+    pub(crate) map_population: MapPopulation,
+    /// The size of the largest file which is locked in memory, if there is a limit.
+    pub(crate) lock_budget: Option<u64>,
+    /// The decoders for custom sample formats. These cannot be serialized, so they have to be
+    /// registered again after the settings are deserialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) custom_decoders: CustomDecoders,
 }
 
 impl Default for SegySettings {
@@ -76,6 +166,7 @@ impl Default for SegySettings {
     fn default() -> Self {
         SegySettings {
             override_to_le: None,
+            endianness_detection: EndianDetection::TrustFlag,
             override_trace_format: None,
             override_coordinate_format: None,
             override_coordinate_scaling: None,
@@ -97,6 +188,35 @@ impl Default for SegySettings {
             override_v: None,
             override_sample_interval: None,
             order_trace_by: OrderTraceBy::Default,
+            strict_text_header: false,
+            trust_file_size: false,
+            mute_with_nan: false,
+            on_coordinate_parse_error: CoordErrorPolicy::Fail,
+            extended_trace_count: false,
+            auto_sequence: false,
+            strict_sample_count: false,
+            compact_headers: false,
+            mapping_budget: None,
+            dedup_headers: false,
+            allow_bidx_collisions: false,
+            apply_trace_weighting: false,
+            user_gain: 1.0,
+            trace_prefix_bytes: 0,
+            text_header_encoding: TextEncoding::Auto,
+            sort_traces_by: OrderTraceBy::Default,
+            coordinate_transform: None,
+            aux_trace_policy: AuxPolicy::Include,
+            max_map_window: 0,
+            strict_revision: false,
+            swap_data_byte_order: false,
+            strict_time_window: false,
+            scan_for_text_header: 0,
+            max_extended_headers: 1000,
+            detect_extended_headers: false,
+            append_provenance_card: false,
+            map_population: MapPopulation::Lazy,
+            lock_budget: None,
+            custom_decoders: CustomDecoders::default(),
         }
     }
 }
@@ -107,16 +227,38 @@ impl SegySettings {
         serde_json::to_string(&self).map_err(|e| e.to_string())
     }
 
-    /// A function to set the order_trace_by`
+    /// Sets the value of the trace headers by which the traces are counted when their inline
+    /// and crossline numbers are derived from a custom crossline count (see
+    /// [`SegySettings::set_override_dim_x`]). The trace with a count of `n` is given the inline
+    /// number `n / dim_x` and the crossline number `n % dim_x`. By default
+    /// ([`OrderTraceBy::Default`]) this is the position of the trace in the file.
+    ///
+    /// NB: This does not change the order of the traces (see
+    /// [`SegySettings::set_sort_traces_by`]). If two traces have the same count, they are given
+    /// the same inline and crossline numbers, and a warning is recorded by `giga-segy-in`
+    /// (see `giga_segy_in::SegyFile::get_warnings`). Both traces are kept, but only the later
+    /// one can be found by its inline and crossline numbers.
     pub fn set_order_trace_by(&mut self, order: OrderTraceBy) {
         self.order_trace_by = order;
     }
 
     /// Sets the endianness to LE if true and BE if false
+    ///
+    /// When reading, this overrides the endianness detected from the binary header. When writing
+    /// with `giga-segy-out`, this determines the endianness of the whole file.
     pub fn set_override_to_le(&mut self, le: bool) {
         self.override_to_le = Some(le);
     }
 
+    /// Sets how the endianness of a file is determined when reading it. This has no effect if
+    /// the endianness is overridden with [`SegySettings::set_override_to_le`].
+    ///
+    /// The endianness which was used can be found in the `binary_flag_direction_is_le` field
+    /// of the binary header of the parsed file.
+    pub fn set_endianness_detection(&mut self, detection: EndianDetection) {
+        self.endianness_detection = detection;
+    }
+
     /// Sets the trace format to the input.
     pub fn set_override_trace_format(&mut self, format: SampleFormatCode) {
         self.override_trace_format = Some(format);
@@ -127,8 +269,10 @@ impl SegySettings {
         self.override_trace_id_code = Some(domain);
     }
 
-    /// Sets the trace format to the input.
-    /// NB: Not used in crate. Provided for consuming libraries and applications.
+    /// Sets the units of the vertical axis of depth data.
+    /// NB: Only used by `giga_segy_in::SegyFile::vertical_axis` (where it takes precedence over
+    /// the measurement system of the binary header). Otherwise provided for consuming libraries
+    /// and applications.
     pub fn set_override_trace_depth_units(&mut self, units: MeasurementSystem) {
         self.override_trace_depth_units = Some(units);
     }
@@ -165,15 +309,8 @@ impl SegySettings {
         &mut self,
         format: SampleFormatCode,
     ) -> Result<(), RsgError> {
-        use SampleFormatCode::*;
-        match format {
-            IbmFloat32 | Float32 | UInt32 | Int32 => self.override_coordinate_format = Some(format),
-            _ => {
-                return Err(RsgError::BitConversionError {
-                    msg: format!("Coordinate format must be 4-byte. {:?} is not", format),
-                })
-            }
-        }
+        check_coordinate_format(format)?;
+        self.override_coordinate_format = Some(format);
         Ok(())
     }
 
@@ -270,11 +407,491 @@ impl SegySettings {
         Ok(())
     }
 
+    /// Sets the byte indices of the ensemble (CDP) coordinates to those of the source
+    /// coordinates, so that `source_x` and `source_y` are read into `x_ensemble` and
+    /// `y_ensemble` (eg. for shot gathers, where the ensemble coordinates are often not set).
+    ///
+    /// NB: The fields overlap (see [`Self::check_bidx_collisions`]), so `giga-segy-in` adds a
+    /// warning when a file is read with these settings, and `giga-segy-out` writes the ensemble
+    /// coordinates over the source coordinates if this is allowed.
+    /// ```
+    /// # use giga_segy_core::settings::*;
+    /// use giga_segy_core::standard_locations::{SOURCE_X, SOURCE_Y};
+    ///
+    /// let mut settings = SegySettings::default();
+    /// settings.set_ensemble_xy_to_source_xy();
+    /// assert_eq!(settings.get_x_ensemble_bidx(), SOURCE_X);
+    /// assert_eq!(settings.get_y_ensemble_bidx(), SOURCE_Y);
+    /// ```
+    pub fn set_ensemble_xy_to_source_xy(&mut self) {
+        self.x_ensemble_bidx = standard_locations::SOURCE_X;
+        self.y_ensemble_bidx = standard_locations::SOURCE_Y;
+    }
+
+    /// Sets the byte indices of the ensemble (CDP) coordinates to those of the receiver group
+    /// coordinates. See [`Self::set_ensemble_xy_to_source_xy`].
+    pub fn set_ensemble_xy_to_receiver_xy(&mut self) {
+        self.x_ensemble_bidx = standard_locations::RECEIVER_GROUP_X;
+        self.y_ensemble_bidx = standard_locations::RECEIVER_GROUP_Y;
+    }
+
+    /// Sets the byte indices of the inline and crossline numbers and of the ensemble (CDP)
+    /// coordinates together, as given by a [`HeaderProfile`].
+    ///
+    /// An error is returned (and nothing is changed) if a byte index would lead to reading past
+    /// the end of the trace header, or if two of the fields would overlap.
+    /// ```
+    /// # use giga_segy_core::settings::*;
+    /// let mut settings = SegySettings::default();
+    /// settings.apply_profile(HeaderProfile::PetrelLegacy).unwrap();
+    /// assert_eq!(settings.get_inline_no_bidx(), 8);
+    /// assert_eq!(settings.get_crossline_no_bidx(), 20);
+    ///
+    /// let overlapping = HeaderProfile::Custom {
+    ///     inline: 188,
+    ///     crossline: 190,
+    ///     cdp_x: 180,
+    ///     cdp_y: 184,
+    /// };
+    /// assert!(settings.apply_profile(overlapping).is_err());
+    /// assert_eq!(settings.get_crossline_no_bidx(), 20);
+    /// ```
+    pub fn apply_profile(&mut self, profile: HeaderProfile) -> Result<(), RsgError> {
+        let indices = profile.byte_indices();
+        let error = |msg: String| Err(RsgError::SEGYSettingsError { msg });
+        for (i, bidx) in indices.iter().enumerate() {
+            if *bidx > TRACE_HEADER_LEN - 4 {
+                return error(format!(
+                    "The byte index {} of the {} is past the end of the trace header.",
+                    bidx,
+                    HeaderProfile::FIELD_NAMES[i]
+                ));
+            }
+            for (j, other) in indices.iter().enumerate().skip(i + 1) {
+                if bidx.max(other) - bidx.min(other) < 4 {
+                    return error(format!(
+                        "The {} (byte index {}) and the {} (byte index {}) overlap.",
+                        HeaderProfile::FIELD_NAMES[i],
+                        bidx,
+                        HeaderProfile::FIELD_NAMES[j],
+                        other
+                    ));
+                }
+            }
+        }
+        let [inline, crossline, cdp_x, cdp_y] = indices;
+        self.inline_no_bidx = inline;
+        self.crossline_no_bidx = crossline;
+        self.x_ensemble_bidx = cdp_x;
+        self.y_ensemble_bidx = cdp_y;
+        Ok(())
+    }
+
+    /// Find the fields of the trace header which are overlapped by the inline and crossline
+    /// numbers and the ensemble coordinates at the byte indices of the settings (see
+    /// [`find_bidx_collisions`]). This is empty for the standard positions.
+    ///
+    /// `giga-segy-out` refuses to write a file with such settings, unless this is allowed with
+    /// [`Self::set_allow_bidx_collisions`]. `giga-segy-in` reads the file anyway, but adds a
+    /// warning for each collision.
+    /// ```
+    /// # use giga_segy_core::settings::*;
+    /// let mut settings = SegySettings::default();
+    /// assert!(settings.check_bidx_collisions().is_empty());
+    ///
+    /// settings.set_x_ensemble_bidx(72).unwrap();
+    /// let collisions = settings.check_bidx_collisions();
+    /// assert_eq!(collisions[0].field, "x_ensemble");
+    /// assert_eq!(collisions[0].overlaps, vec!["source_x"]);
+    /// ```
+    pub fn check_bidx_collisions(&self) -> Vec<BidxCollision> {
+        find_bidx_collisions([
+            self.inline_no_bidx,
+            self.crossline_no_bidx,
+            self.x_ensemble_bidx,
+            self.y_ensemble_bidx,
+        ])
+    }
+
     /// Sets the trace data step by (skip-1) value to the input value.
+    ///
+    /// If this is larger than one, only every `step`-th sample of each trace is read, starting
+    /// with the first. This applies in the same way to every accessor of the trace data, both
+    /// as f32 values and as raw bytes, so a trace with `n` samples always gives `n / step`
+    /// samples, rounded up. Likewise, the sample indices given to the accessors of single
+    /// samples (and to time slices) count only the samples that are read, so that index `k`
+    /// is sample `k * step` of the trace. The exceptions are the digests of the trace data,
+    /// which always cover the whole trace.
     pub fn set_step_by(&mut self, step: usize) {
         self.step_by = step;
     }
 
+    /// Sets whether the text header is brought into card image form (forty lines of eighty
+    /// ASCII characters) when a file is created with `giga-segy-out`. If this is set, invalid
+    /// text headers cause an error instead of being written as they are.
+    pub fn set_strict_text_header(&mut self, strict: bool) {
+        self.strict_text_header = strict;
+    }
+
+    /// Sets whether the file size is trusted over the declared sample format when reading.
+    ///
+    /// If the size of the trace data in a file is a multiple (or a fraction) of 2, 4 or 8 of
+    /// the size implied by the binary header, the declared sample format is most likely wrong.
+    /// By default this causes an [`RsgError::FormatSizeMismatch`] error. If this is set, the
+    /// sample format implied by the file size is used instead.
+    ///
+    /// NB: This has no effect if the trace format is overridden.
+    pub fn set_trust_file_size(&mut self, trust: bool) {
+        self.trust_file_size = trust;
+    }
+
+    /// Sets whether muted samples are set to NaN (instead of zero) when the mute times of the
+    /// trace headers are applied (eg. with `giga_segy_in::SegyFile::get_trace_data_as_f32_muted`).
+    pub fn set_mute_with_nan(&mut self, nan: bool) {
+        self.mute_with_nan = nan;
+    }
+
+    /// Sets whether the samples are scaled with the trace weighting factor of the trace header
+    /// when they are read as floats (eg. with `giga_segy_in::SegyFile::get_trace_data_as_f32`).
+    ///
+    /// As in the standard, a weighting factor of `N` means that the least significant bit is
+    /// worth `2^-N`, so the samples are multiplied by `2^-N`. `N` is read as a signed number,
+    /// so that samples which were scaled down to fit in their format (as with
+    /// `giga_segy_out::SegyFile::add_trace_scaled`) are scaled up again. This is only done for
+    /// integer (and fixed point) sample formats. The accessors which return the bytes of the
+    /// samples are not affected.
+    pub fn set_apply_trace_weighting(&mut self, apply: bool) {
+        self.apply_trace_weighting = apply;
+    }
+
+    /// Sets a gain by which all samples are multiplied when they are read as floats (eg. for a
+    /// quick normalization), regardless of the sample format. This is applied on top of the
+    /// trace weighting (see [`Self::set_apply_trace_weighting`]), and the accessors which
+    /// return the bytes of the samples are not affected. The default is `1.0`.
+    ///
+    /// NB: A gain which is not finite is refused by [`Self::validate`].
+    pub fn set_user_gain(&mut self, gain: f32) {
+        self.user_gain = gain;
+    }
+
+    /// Sets the number of bytes which are skipped between each trace header (and its trace
+    /// header extensions) and the trace data when reading. This is not part of the SEG-Y
+    /// standard, but older versions of `giga-segy-out` wrote the `extended_header` of each trace
+    /// between its header and data without declaring it as trace header extensions. Such files
+    /// can be read by setting this to the length of that string. The default is `0`.
+    ///
+    /// NB: `giga-segy-in` returns an [`crate::errors::RsgError::TextInTraceData`] if it finds
+    /// text at the start of the trace data which suggests that this needs to be set.
+    pub fn set_trace_prefix_bytes(&mut self, len: usize) {
+        self.trace_prefix_bytes = len;
+    }
+
+    /// Sets the value of the trace headers by which the traces are sorted when a file is read
+    /// with `giga-segy-in`, so that the trace with index `0` is the one with the smallest value.
+    /// By default ([`OrderTraceBy::Default`]) the traces are kept in the order of the file.
+    ///
+    /// The sort is stable: Traces with the same value are kept in the order of the file, and a
+    /// warning is recorded for them (see `giga_segy_in::SegyFile::get_warnings`).
+    pub fn set_sort_traces_by(&mut self, order: OrderTraceBy) {
+        self.sort_traces_by = order;
+    }
+
+    /// Sets the transform which `giga-segy-in` applies to the coordinates of the ensemble, the
+    /// source and the receiver group when it parses a trace header, or removes it with `None`.
+    ///
+    /// Each pair of coordinates is scaled with the coordinate scalar of the trace header (or
+    /// [`Self::set_override_coordinate_scaling`]), transformed, and stored again with the same
+    /// scalar, rounded to the nearest integer. So the precision of the transformed coordinates
+    /// is that of the scalar. If a transformed coordinate does not fit, this is handled like a
+    /// coordinate which cannot be parsed (see [`Self::set_on_coordinate_parse_error`]).
+    ///
+    /// The untransformed values can still be read with
+    /// `giga_segy_in::SegyFile::load_untransformed_trace_header`. The transform is not used when
+    /// writing.
+    /// ```
+    /// # use giga_segy_core::settings::*;
+    /// use giga_segy_core::AffineTransform;
+    ///
+    /// let mut settings = SegySettings::default();
+    /// settings.set_coordinate_transform(Some(AffineTransform::translation(500_000., 0.)));
+    /// assert_eq!(
+    ///     settings.get_coordinate_transform().unwrap().apply(1., 2.),
+    ///     (500_001., 2.)
+    /// );
+    /// ```
+    pub fn set_coordinate_transform(&mut self, transform: Option<AffineTransform>) {
+        self.coordinate_transform = transform;
+    }
+
+    /// Sets which traces are kept when a file is read with `giga-segy-in`, so that the
+    /// auxiliary traces of field records (eg. time breaks or sweeps) can be separated from the
+    /// seismic traces. A trace is auxiliary if its trace identification code is (see
+    /// [`TraceIdCode::is_auxiliary`]). The number of auxiliary traces per record in the binary
+    /// header is not used to find them, since it is often wrong.
+    ///
+    /// By default ([`AuxPolicy::Include`]) all traces are kept, and the indices of the auxiliary
+    /// traces can be had with `giga_segy_in::SegyFile::aux_trace_indices`.
+    pub fn set_aux_trace_policy(&mut self, policy: AuxPolicy) {
+        self.aux_trace_policy = policy;
+    }
+
+    /// Sets how the text header is decoded when a file is read with `giga-segy-in`. By default
+    /// ([`TextEncoding::Auto`]) it is read as ASCII if it only contains printable ASCII
+    /// characters, and as EBCDIC otherwise.
+    ///
+    /// NB: The extended text headers are always decoded as with [`TextEncoding::Auto`]. The
+    /// bytes of the text header can be had with `giga_segy_in::SegyFile::get_text_header_bytes`
+    /// for other encodings.
+    pub fn set_text_header_encoding(&mut self, encoding: TextEncoding) {
+        self.text_header_encoding = encoding;
+    }
+
+    /// Sets what happens when a coordinate in a trace header cannot be converted to an integer.
+    /// This can only happen if the coordinate format is overridden to a floating point format
+    /// (see [`SegySettings::set_override_coordinate_format`]).
+    ///
+    /// By default opening the file fails. Otherwise the coordinate is set to zero or the trace
+    /// is left out, and a warning is recorded (see `giga_segy_in::SegyFile::get_warnings`).
+    pub fn set_on_coordinate_parse_error(&mut self, policy: CoordErrorPolicy) {
+        self.on_coordinate_parse_error = policy;
+    }
+
+    /// Sets whether more than 65535 traces may be written to a file with `giga-segy-out`.
+    ///
+    /// The number of traces in the binary header (bytes 3213-3214) is only two bytes long. If
+    /// this is set and more traces are written, it is set to zero and the number of traces is
+    /// written to the extended number of data traces (bytes 3261-3264) of SEG-Y rev 2 instead.
+    /// Otherwise trying to write more traces causes an [`RsgError::TooManyTraces`] error.
+    pub fn set_extended_trace_count(&mut self, extended: bool) {
+        self.extended_trace_count = extended;
+    }
+
+    /// Sets whether the sequence numbers of the trace headers are filled in when traces are
+    /// written with `giga-segy-out`.
+    ///
+    /// If this is set, `trace_sequence_on_line`, `trace_sequence_in_file` and `trace_no` are set
+    /// to the position of the trace in the file, counting from one as in the SEG-Y standard,
+    /// if they are zero. Values which are not zero are always kept.
+    pub fn set_auto_sequence(&mut self, auto_sequence: bool) {
+        self.auto_sequence = auto_sequence;
+    }
+
+    /// Sets whether a card which records how the file was written is added to the text header
+    /// when a file which is written with `giga-segy-out` is finalized.
+    ///
+    /// If this is set, the last blank card of the text header (searching from C40 backwards)
+    /// is replaced by eg. `C39 written by giga-segy-out v0.5.0 on 2024-05-01, format 5, 120
+    /// traces`. If there is no blank card, the text header is left as it is (see
+    /// `giga_segy_out::SegyFile::provenance_card`).
+    pub fn set_append_provenance_card(&mut self, append: bool) {
+        self.append_provenance_card = append;
+    }
+
+    /// Sets whether the sample count of the trace headers is checked strictly when traces are
+    /// written with `giga-segy-out`.
+    ///
+    /// If the binary header does not declare fixed length traces, readers rely on the number of
+    /// samples in each trace header (bytes 115-116) to find the next trace. Thus it must match
+    /// the number of samples that are written. By default it is filled in if it is zero. If
+    /// this is set, a zero is an error ([`RsgError::TraceSampleCountMismatch`]) like any other
+    /// wrong number.
+    pub fn set_strict_sample_count(&mut self, strict: bool) {
+        self.strict_sample_count = strict;
+    }
+
+    /// Sets whether the trace headers are stored compactly when a file is read with the
+    /// `SegyFile` of `giga-segy-in`.
+    ///
+    /// Normally every trace header is parsed into a [`crate::TraceHeader`] which is kept in
+    /// memory, which takes several gigabytes for a file with millions of traces. If this is
+    /// set, only the inline and crossline numbers, the ensemble coordinates and the sample
+    /// count of each trace are kept (see `giga_segy_in::TraceKey`), and the full header is
+    /// parsed again from the file when it is needed (see `giga_segy_in::SegyFile::load_trace`).
+    ///
+    /// NB: In this mode, the functions which give traces (such as `get_trace` and
+    /// `traces_iter`) give owned traces, whose headers are parsed when they are asked for. This
+    /// also applies to the `AsyncSegyFile`, which reads each header with a short blocking read.
+    pub fn set_compact_headers(&mut self, compact: bool) {
+        self.compact_headers = compact;
+    }
+
+    /// Sets the size (in bytes) of the largest file that the `SegyFile` of `giga-segy-in` maps
+    /// to memory. Larger files are refused with [`RsgError::FileTooLargeForPlatform`], as are
+    /// files which are larger than `usize::MAX` regardless of this setting.
+    ///
+    /// On 32-bit targets, a file must fit into the address space of the process to be mapped,
+    /// which is usually a lot less than 4 GB. This allows such files to be refused up front,
+    /// rather than with an opaque error from the memory map. They can still be read with the
+    /// `AsyncSegyFile` of `giga-segy-in`, which does not map the file (as long as its size fits
+    /// in a `usize`).
+    pub fn set_mapping_budget(&mut self, budget: Option<u64>) {
+        self.mapping_budget = budget;
+    }
+
+    /// Sets the size (in bytes) of the largest part of a file that the `SegyFile` of
+    /// `giga-segy-in` maps to memory at once. By default (zero) the whole file is mapped in one
+    /// go, which is the fastest, but fails for very large files on systems with strict memory
+    /// overcommit settings and on some filesystems.
+    ///
+    /// Otherwise the beginning of the file (with the text and binary headers) stays mapped, and
+    /// the rest is mapped in windows of this size when it is needed, with the few windows which
+    /// were used most recently kept mapped. A trace which does not fit into a window gets a
+    /// larger one. Bytes which are given out of a window (such as by
+    /// `giga_segy_in::SegyFile::get_trace_header_bytes`) are copied, so no more than these few
+    /// windows stay mapped.
+    ///
+    /// NB: The mapping budget (see [`Self::set_mapping_budget`]) still applies to the size of
+    /// the whole file.
+    pub fn set_max_map_window(&mut self, bytes: usize) {
+        self.max_map_window = bytes;
+    }
+
+    /// Sets how much of a file the `SegyFile` of `giga-segy-in` brings into memory when it maps
+    /// the file. By default ([`MapPopulation::Lazy`]) the pages of the file are only read when
+    /// they are first accessed, which is best if only parts of the file are read.
+    ///
+    /// With [`MapPopulation::Populate`], the whole file is read when it is mapped (with
+    /// `MAP_POPULATE` on Linux and `MADV_WILLNEED` on other Unix platforms), so that the time
+    /// of the first access to any trace is bounded. [`MapPopulation::Lock`] also locks the file
+    /// in memory (with `mlock`), so that it is never paged out. If the file cannot be locked
+    /// (eg. because it is larger than the lock budget, see [`Self::set_lock_budget`], or than
+    /// the limit of the process, `RLIMIT_MEMLOCK`), it is only populated, and a warning is
+    /// recorded (see `giga_segy_in::SegyFile::get_warnings`).
+    ///
+    /// NB: On platforms which do not support this, the file is mapped as with
+    /// [`MapPopulation::Lazy`]. Files which are mapped in windows (see
+    /// [`Self::set_max_map_window`]) are populated window by window, and only the beginning of
+    /// the file, which stays mapped, is locked.
+    pub fn set_map_population(&mut self, population: MapPopulation) {
+        self.map_population = population;
+    }
+
+    /// Sets the size (in bytes) of the largest file that the `SegyFile` of `giga-segy-in` locks
+    /// in memory with [`MapPopulation::Lock`] (see [`Self::set_map_population`]). Larger files
+    /// are only populated. By default there is no limit but that of the platform.
+    pub fn set_lock_budget(&mut self, budget: Option<u64>) {
+        self.lock_budget = budget;
+    }
+
+    /// Sets whether a file is rejected when it is read with the `SegyFile` of `giga-segy-in`
+    /// if its binary header gives a SEG-Y revision which is not supported (see
+    /// [`BinHeader::revision_supported`]), such as the draft of revision 3. Then an
+    /// [`RsgError::UnsupportedRevision`] is returned.
+    ///
+    /// By default such files are read as well as possible. Revision bytes which are printable
+    /// ASCII characters (such as "1." for revision 1.0 written as text) cannot be meant as a
+    /// revision number, so they are read as revision 0.0 with a warning.
+    ///
+    /// [`BinHeader::revision_supported`]: crate::BinHeader::revision_supported
+    pub fn set_strict_revision(&mut self, strict: bool) {
+        self.strict_revision = strict;
+    }
+
+    /// Sets whether the samples are read in the opposite byte order to the headers.
+    ///
+    /// This is meant for files where only the trace data was written with the wrong byte order
+    /// (eg. IBM floats which were byte swapped by mistake), while the headers are fine, so
+    /// that [`Self::set_override_to_le`] cannot be used. Such files can be recognized with
+    /// `giga_segy_in::SegyFile::detect_float_byte_order_issue`.
+    ///
+    /// NB: This only applies to the samples as numbers, so the bytes of the samples (eg. as
+    /// read by `giga_segy_in::SegyFile::get_trace_data_as_bytes`) are those of the file.
+    pub fn set_swap_data_byte_order(&mut self, swap: bool) {
+        self.swap_data_byte_order = swap;
+    }
+
+    /// Sets whether a time window which is not within a trace is rejected when the samples in
+    /// the window are read (see `giga_segy_in::SegyFile::get_trace_data_window_ms`). Then an
+    /// [`RsgError::TimeWindowOutOfBounds`] is returned.
+    ///
+    /// By default the window is clamped to the times of the samples of the trace, so that a
+    /// window which is entirely before or after the trace gives no samples.
+    pub fn set_strict_time_window(&mut self, strict: bool) {
+        self.strict_time_window = strict;
+    }
+
+    /// Sets how many bytes at the start of a file the `SegyFile` of `giga-segy-in` searches for
+    /// the text header, for files which have something else in front of it (eg. the header of
+    /// a transfer or archive format). By default (zero) the text header must be at the start of
+    /// the file, or after a tape label.
+    ///
+    /// Otherwise the text header is taken to start at the first position up to `max_scan_bytes`
+    /// where it looks like a text header: It starts with a `C` and is all text, in ASCII or
+    /// EBCDIC. The headers and traces are then read from there on, and the position is given
+    /// by `giga_segy_in::SegyFile::get_text_header_offset`. If no text header is found, an
+    /// [`RsgError::InvalidHeader`] is returned.
+    ///
+    /// NB: This also applies to files which do start with the text header, so a file whose text
+    /// header does not start with a `C` or has other bytes than text cannot be read with this
+    /// setting.
+    pub fn set_scan_for_text_header(&mut self, max_scan_bytes: usize) {
+        self.scan_for_text_header = max_scan_bytes;
+    }
+
+    /// Sets the largest number of extended text headers which the binary header may give when
+    /// a file is read with `giga-segy-in` (1000 by default). A count which is larger than this,
+    /// or than the number of headers which fit into the file, is taken to be corrupt (eg. two
+    /// spaces which were read as a number), and an [`RsgError::ImplausibleExtendedHeaderCount`]
+    /// is returned, unless the headers are to be detected instead (see
+    /// [`Self::set_detect_extended_headers`]).
+    pub fn set_max_extended_headers(&mut self, max: u32) {
+        self.max_extended_headers = max;
+    }
+
+    /// Sets whether the extended text headers are detected when the binary header gives an
+    /// implausible number of them (see [`Self::set_max_extended_headers`]), instead of
+    /// returning an error.
+    ///
+    /// The headers are then counted as the blocks of 3200 bytes after the binary header which
+    /// consist of text (in ASCII or EBCDIC), and the count of the binary header is replaced.
+    /// A warning is recorded by `giga-segy-in` (see `giga_segy_in::SegyFile::get_warnings`).
+    pub fn set_detect_extended_headers(&mut self, detect: bool) {
+        self.detect_extended_headers = detect;
+    }
+
+    /// Registers a decoder for the samples of files whose binary header gives the sample
+    /// format code `code`, replacing any decoder which was registered for it before.
+    ///
+    /// The `SegyFile` of `giga-segy-in` then uses the decoder to work out the length of the
+    /// traces and to read their samples as f32 values (and as other number types, which are
+    /// converted from them), instead of the built-in conversion. This also works for the
+    /// standard codes, and for codes which are not valid otherwise. For the latter, the
+    /// [`crate::BinHeader::sample_format_code`] of the file is `UInt8` as a stand-in, while the
+    /// code is kept in [`crate::BinHeader::raw_sample_format_code`].
+    ///
+    /// NB: The decoder is not used if the trace format is overridden (see
+    /// [`Self::set_override_trace_format`]). The raw bytes of the samples can still be read, but
+    /// not every `step_by`-th sample if the samples are packed (see
+    /// [`crate::decoder::DatumLen::Bits`]).
+    pub fn register_custom_decoder(&mut self, code: u16, decoder: Arc<dyn TraceDecoder>) {
+        self.custom_decoders.insert(code, decoder);
+    }
+
+    /// Sets whether the parts of the trace headers which rarely change are stored only once
+    /// when a file is read with the `SegyFile` of `giga-segy-in`.
+    ///
+    /// In many files, the trace headers only differ in their line and sequence numbers and
+    /// coordinates, while everything else (sample count, filters, sweep parameters, units...)
+    /// is the same for every trace. If this is set, the values which usually vary are kept for
+    /// each trace, and the rest of each header is kept once for each distinct set of values.
+    /// The full header is put back together when it is needed (see
+    /// `giga_segy_in::SegyFile::load_trace`), without reading the file again.
+    ///
+    /// NB: As with [`Self::set_compact_headers`], the functions which give traces give owned
+    /// traces in this mode, for the `AsyncSegyFile` as well. The two modes cannot be used
+    /// together (see [`Self::validate`]).
+    pub fn set_dedup_headers(&mut self, dedup: bool) {
+        self.dedup_headers = dedup;
+    }
+
+    /// Sets whether a file may be written by `giga-segy-out` when the byte indices of the
+    /// settings overlap other fields of the trace header (see [`Self::check_bidx_collisions`]).
+    /// The overlapped fields are then overwritten with the inline and crossline numbers or the
+    /// ensemble coordinates.
+    pub fn set_allow_bidx_collisions(&mut self, allow: bool) {
+        self.allow_bidx_collisions = allow;
+    }
+
     /// Sets the mininmum and maximum inline value to the input values.
     pub fn set_inlne_min_max(&mut self, min_max: [i32; 2]) {
         self.inline_min_max = Some(min_max);
@@ -293,10 +910,12 @@ impl SegySettings {
     /// Sets the override for inline count.
     ///
     /// While this function takes an [`i32`] value as an argument, it will throw an error if the
-    /// value is negative. Furthermore, both [`Self::set_override_dim_x`] and [`Self::set_override_dim_y`]
-    /// also set `crossline_min_max` and `inline_min_max` respectively. Since the minimum
-    /// is set to zero in this case, it is strongly recommended that the user check and override this value
-    /// manually with [`Self::set_inlne_min_max`] or [`Self::set_crossline_min_max`] as appropriate.
+    /// value is negative. Furthermore, if `crossline_min_max` and `inline_min_max` have not been set,
+    /// [`Self::set_override_dim_x`] and [`Self::set_override_dim_y`] respectively set them to start
+    /// from zero. Since the minimum is set to zero in this case, it is strongly recommended that the
+    /// user check and override this value manually with [`Self::set_inlne_min_max`] or
+    /// [`Self::set_crossline_min_max`] as appropriate. A minimum and maximum which was already set
+    /// is kept, so the order in which these functions are called does not matter.
     /// ```
     /// # use giga_segy_core::settings::*;
     /// let mut settings = SegySettings::default();
@@ -318,9 +937,9 @@ impl SegySettings {
             })
         } else {
             self.override_dim_x = Some(dim_x);
-            // These must be overriden, or limiters will use the original inline/crossline numbers
+            // These must be set, or limiters will use the original inline/crossline numbers
             // for cutoffs of out of bounds traces.
-            self.crossline_min_max = Some([0, dim_x - 1]);
+            self.crossline_min_max.get_or_insert([0, dim_x - 1]);
             Ok(())
         }
     }
@@ -348,9 +967,9 @@ impl SegySettings {
             })
         } else {
             self.override_dim_y = Some(dim_y);
-            // These must be overriden, or limiters will use the original inline/crossline numbers
+            // These must be set, or limiters will use the original inline/crossline numbers
             // for cutoffs of out of bounds traces.
-            self.inline_min_max = Some([0, dim_y - 1]);
+            self.inline_min_max.get_or_insert([0, dim_y - 1]);
             Ok(())
         }
     }
@@ -367,6 +986,12 @@ impl SegySettings {
         }
     }
 
+    /// Removes the override for sample count (see [`Self::set_override_dim_z`]), so that the
+    /// traces are read with the sample count of their headers.
+    pub fn clear_override_dim_z(&mut self) {
+        self.override_dim_z = None;
+    }
+
     /// Set the physical `u` vector to something other than what is found in headers.
     pub fn set_override_u(&mut self, u: [f64; 3]) {
         self.override_u = Some(u)
@@ -378,6 +1003,10 @@ impl SegySettings {
     }
 
     /// Set an override for the physical sample interval (NB: one dimensionsal by definition.)
+    ///
+    /// This is given in the same units as the sample interval in the headers (ie. microseconds
+    /// for time data). NB: It is only used as a fallback if neither the trace header nor the
+    /// binary header have a sample interval.
     pub fn set_override_sample_interval(&mut self, t: f64) {
         self.override_sample_interval = Some(t);
     }
@@ -387,6 +1016,11 @@ impl SegySettings {
         self.override_to_le
     }
 
+    /// Get the way in which the endianness is determined when reading.
+    pub fn get_endianness_detection(&self) -> EndianDetection {
+        self.endianness_detection
+    }
+
     /// Get the trace format override if any.
     pub fn get_override_trace_format(&self) -> Option<SampleFormatCode> {
         self.override_trace_format
@@ -403,7 +1037,6 @@ impl SegySettings {
     }
 
     /// Gets the depth units override value.
-    /// NB: Not used in crate. Provided for consuming libraries and applications.
     pub fn get_override_trace_depth_units(&self) -> Option<MeasurementSystem> {
         self.override_trace_depth_units
     }
@@ -443,6 +1076,99 @@ impl SegySettings {
         self.step_by
     }
 
+    /// Gets whether the text header is brought into card image form when writing.
+    pub fn get_strict_text_header(&self) -> bool {
+        self.strict_text_header
+    }
+
+    /// Gets whether the file size is trusted over the declared sample format when reading.
+    pub fn get_trust_file_size(&self) -> bool {
+        self.trust_file_size
+    }
+
+    /// Gets whether muted samples are set to NaN instead of zero.
+    pub fn get_mute_with_nan(&self) -> bool {
+        self.mute_with_nan
+    }
+
+    /// Gets whether the samples are scaled with the trace weighting factor when they are read.
+    pub fn get_apply_trace_weighting(&self) -> bool {
+        self.apply_trace_weighting
+    }
+
+    /// Gets the gain by which all samples are multiplied when they are read as floats.
+    pub fn get_user_gain(&self) -> f32 {
+        self.user_gain
+    }
+
+    /// Gets the number of bytes which are skipped between each trace header and the trace data.
+    pub fn get_trace_prefix_bytes(&self) -> usize {
+        self.trace_prefix_bytes
+    }
+
+    /// Gets how the text header is decoded when reading.
+    pub fn get_text_header_encoding(&self) -> TextEncoding {
+        self.text_header_encoding
+    }
+
+    /// Gets what happens when a coordinate in a trace header cannot be converted to an integer.
+    pub fn get_on_coordinate_parse_error(&self) -> CoordErrorPolicy {
+        self.on_coordinate_parse_error
+    }
+
+    /// Gets whether more than 65535 traces may be written to a file.
+    pub fn get_extended_trace_count(&self) -> bool {
+        self.extended_trace_count
+    }
+
+    /// Gets whether the sequence numbers of the trace headers are filled in when writing.
+    pub fn get_auto_sequence(&self) -> bool {
+        self.auto_sequence
+    }
+
+    /// Gets whether a card which records how the file was written is added to the text header
+    /// when a file is finalized.
+    pub fn get_append_provenance_card(&self) -> bool {
+        self.append_provenance_card
+    }
+
+    /// Gets whether the sample count of the trace headers is checked strictly when writing.
+    pub fn get_strict_sample_count(&self) -> bool {
+        self.strict_sample_count
+    }
+
+    /// Gets whether the trace headers are stored compactly when reading.
+    pub fn get_compact_headers(&self) -> bool {
+        self.compact_headers
+    }
+
+    /// Gets the size of the largest file which is mapped to memory when reading, if any.
+    pub fn get_mapping_budget(&self) -> Option<u64> {
+        self.mapping_budget
+    }
+
+    /// Gets how much of a file is brought into memory when it is mapped.
+    pub fn get_map_population(&self) -> MapPopulation {
+        self.map_population
+    }
+
+    /// Gets the size of the largest file which is locked in memory, if there is a limit.
+    pub fn get_lock_budget(&self) -> Option<u64> {
+        self.lock_budget
+    }
+
+    /// Gets whether the rarely changing parts of the trace headers are stored only once when
+    /// reading.
+    pub fn get_dedup_headers(&self) -> bool {
+        self.dedup_headers
+    }
+
+    /// Gets whether files may be written with byte indices which overlap other fields of the
+    /// trace header.
+    pub fn get_allow_bidx_collisions(&self) -> bool {
+        self.allow_bidx_collisions
+    }
+
     /// Get the mininmum and maximum inline value, if any.
     pub fn get_inlne_min_max(&self) -> Option<[i32; 2]> {
         self.inline_min_max
@@ -508,6 +1234,107 @@ impl SegySettings {
         self.order_trace_by
     }
 
+    /// Gets the value of the trace headers by which the traces are sorted when reading.
+    pub fn get_sort_traces_by(&self) -> OrderTraceBy {
+        self.sort_traces_by
+    }
+
+    /// Gets the transform which is applied to the coordinates when reading, if there is one.
+    pub fn get_coordinate_transform(&self) -> Option<AffineTransform> {
+        self.coordinate_transform
+    }
+
+    /// Gets which traces are kept when reading, depending on whether they are auxiliary traces.
+    pub fn get_aux_trace_policy(&self) -> AuxPolicy {
+        self.aux_trace_policy
+    }
+
+    /// Gets the size of the largest window of a file which is mapped to memory at once when
+    /// reading, or zero if the whole file is mapped.
+    pub fn get_max_map_window(&self) -> usize {
+        self.max_map_window
+    }
+
+    /// Gets whether files with a SEG-Y revision which is not supported are rejected when
+    /// reading.
+    pub fn get_strict_revision(&self) -> bool {
+        self.strict_revision
+    }
+
+    /// Gets whether the samples are read in the opposite byte order to the headers.
+    pub fn get_swap_data_byte_order(&self) -> bool {
+        self.swap_data_byte_order
+    }
+
+    /// Gets whether a time window which is not within a trace is rejected when reading.
+    pub fn get_strict_time_window(&self) -> bool {
+        self.strict_time_window
+    }
+
+    /// Gets how many bytes at the start of a file are searched for the text header when
+    /// reading, or zero if they are not searched.
+    pub fn get_scan_for_text_header(&self) -> usize {
+        self.scan_for_text_header
+    }
+
+    /// Gets the largest number of extended text headers which the binary header may give.
+    pub fn get_max_extended_headers(&self) -> u32 {
+        self.max_extended_headers
+    }
+
+    /// Gets whether the extended text headers are detected if the binary header gives an
+    /// implausible number of them.
+    pub fn get_detect_extended_headers(&self) -> bool {
+        self.detect_extended_headers
+    }
+
+    /// Gets the decoder which was registered for a sample format code.
+    pub fn get_custom_decoder(&self, code: u16) -> Option<&Arc<dyn TraceDecoder>> {
+        self.custom_decoders.get(code)
+    }
+
+    /// Gets the decoders for custom sample formats.
+    pub fn get_custom_decoders(&self) -> &CustomDecoders {
+        &self.custom_decoders
+    }
+
+    /// Get where the inline and crossline numbers of the traces come from when a file is read
+    /// with these settings.
+    /// ```
+    /// # use giga_segy_core::settings::SegySettings;
+    /// use giga_segy_core::enums::{LineNumberingSource, OrderTraceBy};
+    ///
+    /// let mut settings = SegySettings::default();
+    /// assert_eq!(
+    ///     settings.line_numbering_source(),
+    ///     LineNumberingSource::TraceHeaders {
+    ///         inline_no_bidx: 188,
+    ///         crossline_no_bidx: 192,
+    ///     }
+    /// );
+    ///
+    /// settings.set_override_dim_x(50).unwrap();
+    /// assert_eq!(
+    ///     settings.line_numbering_source(),
+    ///     LineNumberingSource::Synthesized {
+    ///         dim_x: 50,
+    ///         order_trace_by: OrderTraceBy::Default,
+    ///     }
+    /// );
+    /// ```
+    pub fn line_numbering_source(&self) -> LineNumberingSource {
+        match self.override_dim_x {
+            Some(dim_x) => LineNumberingSource::Synthesized {
+                dim_x,
+                order_trace_by: self.order_trace_by,
+            },
+            None => LineNumberingSource::TraceHeaders {
+                inline_no_bidx: self.inline_no_bidx,
+                crossline_no_bidx: self.crossline_no_bidx,
+            },
+        }
+    }
+
     /// Check whether a given inline and crossline number will be in bounds
     /// according to the options. If no inline/crossline min-max is set, the
     /// return is [`true`].
@@ -537,4 +1364,170 @@ impl SegySettings {
         };
         inline_ok && crossline_ok
     }
+
+    /// Check that the settings are consistent with each other. An error is returned if
+    /// `step_by` is zero, if a minimum is larger than the corresponding maximum, if a
+    /// custom dimension is smaller than the span of the corresponding minimum and maximum, if
+    /// the coordinate format is not four bytes long (see [`check_coordinate_format`]), if the
+    /// user gain is not finite, or if the trace headers are to be both compact and deduplicated.
+    ///
+    /// The values which the setters check are checked again, since settings which were
+    /// deserialized did not go through them: An error is also returned if a byte index goes
+    /// past the end of the trace header, if a custom dimension is negative, or if the custom
+    /// crossline count is zero.
+    /// ```
+    /// # use giga_segy_core::settings::SegySettings;
+    /// let mut settings = SegySettings::default();
+    /// assert!(settings.validate().is_ok());
+    ///
+    /// settings.set_crossline_min_max([1, 100]);
+    /// settings.set_override_dim_x(50).unwrap();
+    /// assert!(settings.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), RsgError> {
+        let error = |msg: String| Err(RsgError::SEGYSettingsError { msg });
+        if self.step_by == 0 {
+            return error("`step_by` must be larger than zero.".to_string());
+        }
+        if let Some(format) = self.override_coordinate_format {
+            check_coordinate_format(format)?;
+        }
+        if !self.user_gain.is_finite() {
+            return error(format!("The user gain ({}) is not finite.", self.user_gain));
+        }
+        if self.compact_headers && self.dedup_headers {
+            return error("The trace headers cannot be both compact and deduplicated.".to_string());
+        }
+        let bidxs = [
+            ("inline number", self.inline_no_bidx),
+            ("crossline number", self.crossline_no_bidx),
+            ("X ensemble coordinate", self.x_ensemble_bidx),
+            ("Y ensemble coordinate", self.y_ensemble_bidx),
+        ];
+        for (name, bidx) in bidxs.iter() {
+            if *bidx > TRACE_HEADER_LEN - 4 {
+                return error(format!(
+                    "The byte index of the {} ({}) is past the end of the trace header.",
+                    name, bidx
+                ));
+            }
+        }
+        let dims = [
+            ("crossline", self.override_dim_x),
+            ("inline", self.override_dim_y),
+            ("sample", self.override_dim_z),
+        ];
+        for (name, dim) in dims.iter() {
+            match dim {
+                Some(dim) if *dim < 0 => {
+                    return error(format!("The custom {} count ({}) is negative.", name, dim));
+                }
+                _ => {}
+            }
+        }
+        if self.override_dim_x == Some(0) {
+            return error("The custom crossline count must not be zero.".to_string());
+        }
+
+        let checks = [
+            ("inline", self.inline_min_max, self.override_dim_y),
+            ("crossline", self.crossline_min_max, self.override_dim_x),
+        ];
+        for (name, min_max, dim) in checks.iter() {
+            if let Some([min, max]) = min_max {
+                if min > max {
+                    return error(format!(
+                        "The minimum {} ({}) is larger than the maximum ({}).",
+                        name, min, max
+                    ));
+                }
+                let span = *max as i64 - *min as i64 + 1;
+                match dim {
+                    Some(dim) if (*dim as i64) < span => {
+                        return error(format!(
+                            "The custom {} count ({}) is smaller than the span of {} numbers ({}).",
+                            name, dim, name, span
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The positions of the inline and crossline numbers and of the ensemble (CDP) coordinates in
+/// the trace headers, as written by different software (see [`SegySettings::apply_profile`]).
+///
+/// The positions below are given counting from one, as in the standard, while the byte indices
+/// of the settings count from zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum HeaderProfile {
+    /// The positions of SEG-Y rev 1 and 2: the inline number at byte 189, the crossline number
+    /// at byte 193 and the CDP coordinates at bytes 181 and 185.
+    Standard,
+    /// The inline number in the field record number (byte 9) and the crossline number in the
+    /// ensemble number (byte 21), as in older Petrel exports. The CDP coordinates are at the
+    /// standard positions.
+    PetrelLegacy,
+    /// The inline number in the trace sequence number within the file (byte 5) and the
+    /// crossline number in the field record number (byte 9), as in older SeisWorks exports.
+    /// The CDP coordinates are at the standard positions.
+    SeisworksLegacy,
+    /// Any other positions, given as byte indices counting from zero (as with
+    /// [`SegySettings::set_inline_no_bidx`]).
+    Custom {
+        inline: usize,
+        crossline: usize,
+        cdp_x: usize,
+        cdp_y: usize,
+    },
+}
+
+impl HeaderProfile {
+    /// The profiles with fixed positions, in the order in which they are preferred.
+    pub const PRESETS: [HeaderProfile; 3] = [
+        HeaderProfile::Standard,
+        HeaderProfile::PetrelLegacy,
+        HeaderProfile::SeisworksLegacy,
+    ];
+
+    /// The names of the fields, in the order of [`HeaderProfile::byte_indices`].
+    const FIELD_NAMES: [&'static str; 4] = ["inline number", "crossline number", "CDP X", "CDP Y"];
+
+    /// Get the byte indices (counting from zero) of the inline number, the crossline number and
+    /// the X and Y coordinates of the CDP, in that order.
+    pub fn byte_indices(&self) -> [usize; 4] {
+        let standard = [
+            INLINE_BYTE_LOCATION,
+            CROSSLINE_BYTE_LOCATION,
+            CDPX_BYTE_LOCATION,
+            CDPY_BYTE_LOCATION,
+        ];
+        match *self {
+            HeaderProfile::Standard => standard,
+            HeaderProfile::PetrelLegacy => [8, 20, standard[2], standard[3]],
+            HeaderProfile::SeisworksLegacy => [4, 8, standard[2], standard[3]],
+            HeaderProfile::Custom {
+                inline,
+                crossline,
+                cdp_x,
+                cdp_y,
+            } => [inline, crossline, cdp_x, cdp_y],
+        }
+    }
+}
+
+/// Check that a format can be used for the coordinates of the trace headers, which is the case
+/// if it is four bytes long (see [`SegySettings::set_override_coordinate_format`]).
+pub fn check_coordinate_format(format: SampleFormatCode) -> Result<(), RsgError> {
+    use SampleFormatCode::*;
+    match format {
+        IbmFloat32 | Float32 | UInt32 | Int32 => Ok(()),
+        _ => Err(RsgError::BitConversionError {
+            msg: format!("Coordinate format must be 4-byte. {:?} is not", format),
+        }),
+    }
 }
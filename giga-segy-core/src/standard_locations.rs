@@ -0,0 +1,152 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This module contains the byte index (counting from zero) of each field of the
+//! [`TraceHeader`](crate::TraceHeader) in the standard, for use with the settings which move
+//! a field to other bytes (eg. [`crate::SegySettings::set_x_ensemble_bidx`]).
+//!
+//! The constants are taken from the layout of [`crate::layout::trace_header_layout`] when the
+//! crate is compiled, so they always agree with it.
+//! ```
+//! use giga_segy_core::standard_locations::*;
+//! use giga_segy_core::SegySettings;
+//!
+//! assert_eq!(SOURCE_X, 72);
+//! assert_eq!(INLINE_NO, giga_segy_core::INLINE_BYTE_LOCATION);
+//!
+//! let mut settings = SegySettings::default();
+//! settings.set_inline_no_bidx(SHOT_POINT_NO).unwrap();
+//! assert_eq!(settings.get_inline_no_bidx(), 196);
+//! ```
+use crate::layout::trace_header_layout;
+
+/// Find the byte index of a field of the trace header by its name. This panics (and thus fails
+/// to compile when it is used for a constant) if there is no such field.
+const fn byte_index(name: &str) -> usize {
+    let layout = trace_header_layout();
+    let mut i = 0;
+    while i < layout.len() {
+        if str_eq(layout[i].name, name) {
+            return layout[i].range.start;
+        }
+        i += 1;
+    }
+    panic!("There is no such field in the trace header layout.");
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Defines a constant with the byte index of each of the given fields.
+macro_rules! standard_locations {
+    ($($constant:ident: $field:ident),+ $(,)?) => {
+        $(
+            #[doc = concat!("The byte index of `", stringify!($field), "` in the standard.")]
+            pub const $constant: usize = byte_index(stringify!($field));
+        )+
+    };
+}
+
+standard_locations!(
+    TRACE_SEQUENCE_ON_LINE: trace_sequence_on_line,
+    TRACE_SEQUENCE_IN_FILE: trace_sequence_in_file,
+    FIELD_RECORD_NO: field_record_no,
+    TRACE_NO: trace_no,
+    ENERGY_SOURCE_POINT_NO: energy_source_point_no,
+    ENSEMBLE_NO: ensemble_no,
+    TRACE_NO_IN_ENSEMBLE: trace_no_in_ensemble,
+    TRACE_IDENTIFICATION_CODE: trace_identification_code,
+    NO_V_SUMMED_TRACES: no_v_summed_traces,
+    NO_H_STACKED_TRACES: no_h_stacked_traces,
+    DATA_USE: data_use,
+    SOURCE_TO_RECEIVER_DISTANCE: source_to_receiver_distance,
+    ELEVATION_OF_RECEIVER_GROUP: elevation_of_receiver_group,
+    SURFACE_ELEVATION_OF_SOURCE: surface_elevation_of_source,
+    SOURCE_DEPTH: source_depth,
+    DATUM_ELEVATION_OF_RECEIVER_GROUP: datum_elevation_of_receiver_group,
+    DATUM_ELEVATION_OF_SOURCE: datum_elevation_of_source,
+    WATER_COLUMN_HEIGHT_AT_SOURCE: water_column_height_at_source,
+    WATER_COLUMN_HEIGHT_AT_GROUP: water_column_height_at_group,
+    ELEVATION_SCALAR: elevation_scalar,
+    COORDINATE_SCALAR: coordinate_scalar,
+    SOURCE_X: source_x,
+    SOURCE_Y: source_y,
+    RECEIVER_GROUP_X: receiver_group_x,
+    RECEIVER_GROUP_Y: receiver_group_y,
+    COORDINATE_UNITS: coordinate_units,
+    WEATHING_VELOCITY: weathing_velocity,
+    SUB_WEATHERING_VELOCITY: sub_weathering_velocity,
+    UPHOLE_TIME_AT_SOURCE: uphole_time_at_source,
+    UPHOLE_TIME_AT_GROUP: uphole_time_at_group,
+    SOURCE_STATIC_CORRECTION: source_static_correction,
+    GROUP_STATIC_CORRECTION: group_static_correction,
+    TOTAL_STATIC_APPLIED: total_static_applied,
+    LAG_TIME_A: lag_time_a,
+    LAG_TIME_B: lag_time_b,
+    DELAY_RECORDING_TIME: delay_recording_time,
+    MUTE_TIME_START: mute_time_start,
+    MUTE_TIME_END: mute_time_end,
+    NO_SAMPLES_IN_TRACE: no_samples_in_trace,
+    SAMPLE_INTERVAL_OF_TRACE: sample_interval_of_trace,
+    GAIN_TYPE: gain_type,
+    INSTRUMENT_GAIN_CONSTANT: instrument_gain_constant,
+    INSTRUMENT_INITIAL_GAIN: instrument_initial_gain,
+    CORRELATED: correlated,
+    SWEEP_FREQUENCY_AT_START: sweep_frequency_at_start,
+    SWEEP_FREQUENCY_AT_END: sweep_frequency_at_end,
+    SWEEP_LENGTH: sweep_length,
+    SWEEP_TYPE: sweep_type,
+    SWEEP_TRACE_TAPER_LENGTH_AT_START: sweep_trace_taper_length_at_start,
+    SWEEP_TRACE_TAPER_LENGTH_AT_END: sweep_trace_taper_length_at_end,
+    TAPER_TYPE: taper_type,
+    ALIAS_FILTER_FREQUENCY: alias_filter_frequency,
+    ALIAS_FILTER_SLOPE: alias_filter_slope,
+    NOTCH_FILTER_FREQUENCY: notch_filter_frequency,
+    NOTCH_FILTER_SLOPE: notch_filter_slope,
+    LOW_CUT_FREQUENCY: low_cut_frequency,
+    HIGH_CUT_FREQUENCY: high_cut_frequency,
+    LOW_CUT_SLOPE: low_cut_slope,
+    HIGH_CUT_SLOPE: high_cut_slope,
+    YEAR_RECORDED: year_recorded,
+    DAY_OF_YEAR: day_of_year,
+    HOUR_OF_DAY: hour_of_day,
+    MINUTE_OF_HOUR: minute_of_hour,
+    SECOND_OF_MINUTE: second_of_minute,
+    TIME_BASE_CODE: time_base_code,
+    TRACE_WEIGHTING_FACTOR: trace_weighting_factor,
+    GEOPHONE_GROUP_NUMBER_ROLL_POS1: geophone_group_number_roll_pos1,
+    GEOPHONE_GROUP_NUMBER_FIRST_TRACE_ORIG_FIELD: geophone_group_number_first_trace_orig_field,
+    GEOPHONE_GROUP_NUMBER_LAST_TRACE_ORIG_FIELD: geophone_group_number_last_trace_orig_field,
+    GAP_SIZE: gap_size,
+    OVER_TRAVEL: over_travel,
+    X_ENSEMBLE: x_ensemble,
+    Y_ENSEMBLE: y_ensemble,
+    INLINE_NO: inline_no,
+    CROSSLINE_NO: crossline_no,
+    SHOT_POINT_NO: shot_point_no,
+    SHOT_POINT_SCALAR: shot_point_scalar,
+    TRACE_VALUE_MEASUREMENT_UNIT: trace_value_measurement_unit,
+    TRANSDUCTION_CONSTANT_MANTISSA: transduction_constant_mantissa,
+    TRANSDUCTION_CONSTANT_POWER: transduction_constant_power,
+    TRANSDUCTION_UNITS: transduction_units,
+    TRACE_IDENTIFIER: trace_identifier,
+    TIME_SCALAR_TRACE_HEADER: time_scalar_trace_header,
+    SOURCE_TYPE: source_type,
+    SOURCE_ENERGY_DIRECTION_V: source_energy_direction_v,
+    SOURCE_ENERGY_DIRECTION_IL: source_energy_direction_il,
+    SOURCE_ENERGY_DIRECTION_XL: source_energy_direction_xl,
+    SOURCE_MEASUREMENT_MANTISSA: source_measurement_mantissa,
+    SOURCE_MEASUREMENT_EXPONENT: source_measurement_exponent,
+    SOURCE_MEASUREMENT_UNIT: source_measurement_unit,
+    TRACE_NAME: trace_name,
+);
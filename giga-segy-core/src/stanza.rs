@@ -0,0 +1,242 @@
+//! This module deals with the stanzas of extended text headers, as described in SEG-Y rev 2.
+//!
+//! A stanza starts with a line that gives its name in double brackets, such as
+//! `((SEG: Location Data ver 1.0))`, which is followed by lines of the form `key = value`.
+//! A stanza may be continued in the next extended header, and the stanzas end with the
+//! `((SEG: EndText))` stanza. Each extended header is 3200 characters long, which are read as
+//! forty card images of eighty characters (or as lines, if the header contains line breaks).
+use crate::enums::SampleFormatCode;
+use crate::settings::check_coordinate_format;
+use crate::TEXT_HEADER_LEN;
+
+/// The length of a line (card image) of a text header.
+const CARD_LEN: usize = 80;
+
+/// The name of the stanza that marks the end of the extended text headers.
+const END_TEXT: &str = "SEG: EndText";
+
+/// The name of the stanza which records the format of the coordinates of the trace headers, if
+/// it is not the default (see [`crate::SegySettings::set_override_coordinate_format`]).
+pub const COORDINATE_FORMAT_STANZA: &str = "giga-segy: Coordinate Format ver 1.0";
+
+/// The key of the sample format code in the [`COORDINATE_FORMAT_STANZA`].
+const COORDINATE_FORMAT_KEY: &str = "Sample format code";
+
+/// A stanza of the extended text headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Stanza {
+    /// The name of the stanza without the brackets (eg. `SEG: Location Data ver 1.0`).
+    pub name: String,
+    /// The `key = value` pairs of the stanza in the order in which they appear.
+    pub entries: Vec<(String, String)>,
+}
+
+impl Stanza {
+    /// Create a stanza with a name and no entries.
+    pub fn new(name: &str) -> Self {
+        Stanza {
+            name: name.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Get the value of the first entry with the given key, if there is one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Write the stanza as extended text headers, which are padded to 3200 characters with
+    /// spaces. The name of the stanza is written on the first card and every entry starts on a
+    /// card of its own, so that [`parse_extended_stanzas`] gives back the same stanza.
+    ///
+    /// Entries which do not fit on a card are continued on the following cards, split at
+    /// spaces, so a series of spaces is read back as a single space (and words which are longer
+    /// than a card are read back with a space where they were split). A continuation card
+    /// which contains a `=` is read as an entry of its own, so long values should not contain
+    /// `=`.
+    /// ```
+    /// use giga_segy_core::stanza::{parse_extended_stanzas, Stanza};
+    ///
+    /// let mut stanza = Stanza::new("SEG: Location Data ver 1.0");
+    /// stanza.entries.push(("Location type".to_string(), "Local grid".to_string()));
+    ///
+    /// let records = stanza.to_header_records();
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].len(), 3200);
+    /// assert_eq!(parse_extended_stanzas(&records), vec![stanza]);
+    /// ```
+    pub fn to_header_records(&self) -> Vec<String> {
+        let mut cards = vec![format!("(({}))", self.name)];
+        for (key, value) in self.entries.iter() {
+            wrap_card(&format!("{} = {}", key, value), &mut cards);
+        }
+        cards
+            .chunks(TEXT_HEADER_LEN / CARD_LEN)
+            .map(|record| {
+                let record = record
+                    .iter()
+                    .map(|card| format!("{:<1$}", card, CARD_LEN))
+                    .collect::<String>();
+                format!("{:<1$}", record, TEXT_HEADER_LEN)
+            })
+            .collect()
+    }
+
+    /// Add a line of the stanza, which is either a new entry or the continuation of the last.
+    fn push_line(&mut self, line: &str) {
+        if let Some((key, value)) = line.split_once('=') {
+            self.entries
+                .push((key.trim().to_string(), value.trim().to_string()));
+        } else if let Some((_, value)) = self.entries.last_mut() {
+            if !value.is_empty() {
+                value.push(' ');
+            }
+            value.push_str(line);
+        }
+    }
+}
+
+/// Parse the stanzas of the extended text headers (eg. those of
+/// [`crate::SegyMetadata::extended_headers`]).
+///
+/// Stanzas which span several headers are joined, and lines without a `=` are taken as the
+/// continuation of the value of the previous entry. Free-form headers (without any stanza
+/// names or `key = value` lines) are ignored, as is anything before the first stanza and after
+/// the `((SEG: EndText))` stanza. The end stanza itself is not returned.
+/// ```
+/// use giga_segy_core::stanza::parse_extended_stanzas;
+///
+/// let header = format!(
+///     "{:<80}{:<80}{:<3040}",
+///     "((SEG: Observer Notes))", "Weather = Calm", "((SEG: EndText))"
+/// );
+/// let stanzas = parse_extended_stanzas(&[header]);
+/// assert_eq!(stanzas.len(), 1);
+/// assert_eq!(stanzas[0].name, "SEG: Observer Notes");
+/// assert_eq!(stanzas[0].get("Weather"), Some("Calm"));
+/// ```
+pub fn parse_extended_stanzas(headers: &[String]) -> Vec<Stanza> {
+    let mut stanzas = Vec::new();
+    let mut current: Option<Stanza> = None;
+    for header in headers {
+        let lines = header_lines(header);
+        // A free-form header ends the stanza before it.
+        if !lines
+            .iter()
+            .any(|l| stanza_name(l).is_some() || l.contains('='))
+        {
+            stanzas.extend(current.take());
+            continue;
+        }
+
+        for line in lines.iter().filter(|l| !l.is_empty()) {
+            if let Some(name) = stanza_name(line) {
+                stanzas.extend(current.take());
+                if name.eq_ignore_ascii_case(END_TEXT) {
+                    return stanzas;
+                }
+                current = Some(Stanza::new(name));
+            } else if let Some(stanza) = current.as_mut() {
+                stanza.push_line(line);
+            }
+        }
+    }
+    stanzas.extend(current);
+    stanzas
+}
+
+/// Create the [`COORDINATE_FORMAT_STANZA`] for a format of the coordinates.
+/// ```
+/// use giga_segy_core::enums::SampleFormatCode;
+/// use giga_segy_core::stanza::*;
+///
+/// let stanza = coordinate_format_stanza(SampleFormatCode::Float32);
+/// assert_eq!(stanza.get("Sample format code"), Some("5"));
+/// assert_eq!(
+///     find_coordinate_format(&[stanza]),
+///     Some(SampleFormatCode::Float32)
+/// );
+/// ```
+pub fn coordinate_format_stanza(format: SampleFormatCode) -> Stanza {
+    let mut stanza = Stanza::new(COORDINATE_FORMAT_STANZA);
+    stanza.entries.push((
+        COORDINATE_FORMAT_KEY.to_string(),
+        (format as u16).to_string(),
+    ));
+    stanza
+        .entries
+        .push(("Sample format".to_string(), format!("{:?}", format)));
+    stanza
+}
+
+/// Get the format of the coordinates from the [`COORDINATE_FORMAT_STANZA`], if there is one
+/// and it gives a valid format which is four bytes long.
+pub fn find_coordinate_format(stanzas: &[Stanza]) -> Option<SampleFormatCode> {
+    let stanza = stanzas
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(COORDINATE_FORMAT_STANZA))?;
+    let code = stanza.get(COORDINATE_FORMAT_KEY)?.parse::<u16>().ok()?;
+    SampleFormatCode::new(code)
+        .ok()
+        .filter(|f| check_coordinate_format(*f).is_ok())
+}
+
+/// Split an extended header into lines, which are cards of eighty characters unless the
+/// header contains line breaks. The lines are trimmed.
+fn header_lines(header: &str) -> Vec<String> {
+    let trim = |l: &str| {
+        l.trim_matches(|c: char| c.is_whitespace() || c == '\0')
+            .to_string()
+    };
+    if header.contains('\n') {
+        header.lines().map(trim).collect()
+    } else {
+        let chars = header.chars().collect::<Vec<_>>();
+        chars
+            .chunks(CARD_LEN)
+            .map(|card| trim(&card.iter().collect::<String>()))
+            .collect()
+    }
+}
+
+/// Get the name of a stanza from a line such as `((SEG: EndText))`, if it is one.
+fn stanza_name(line: &str) -> Option<&str> {
+    line.strip_prefix("((")
+        .and_then(|l| l.strip_suffix("))"))
+        .map(str::trim)
+}
+
+/// Add a line to the cards, continuing it on further cards (split at spaces) if it is longer
+/// than a card.
+fn wrap_card(line: &str, cards: &mut Vec<String>) {
+    if line.chars().count() <= CARD_LEN {
+        cards.push(line.to_string());
+        return;
+    }
+    let mut card = String::new();
+    for word in line.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+        // Words which are too long for a card are split wherever the card ends.
+        while !word.is_empty() {
+            let card_len = card.chars().count();
+            let space = if card_len == 0 { 0 } else { 1 };
+            if card_len + space + word.len() <= CARD_LEN {
+                if space == 1 {
+                    card.push(' ');
+                }
+                card.extend(word.drain(..));
+            } else if card_len == 0 {
+                card.extend(word.drain(..CARD_LEN));
+            } else {
+                cards.push(std::mem::take(&mut card));
+            }
+        }
+    }
+    if !card.is_empty() {
+        cards.push(card);
+    }
+}
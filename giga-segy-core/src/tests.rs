@@ -419,6 +419,36 @@ mod enums {
         assert_eq!(Invalid, SourceMeasurementUnit::new(-8));
         assert_eq!(Invalid, SourceMeasurementUnit::new(80));
     }
+
+    #[test]
+    fn enums_in_hash_set() {
+        use std::collections::HashSet;
+
+        let formats = [
+            SampleFormatCode::IbmFloat32,
+            SampleFormatCode::Float32,
+            SampleFormatCode::IbmFloat32,
+        ]
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+        assert_eq!(formats.len(), 2);
+        assert!(formats.contains(&SampleFormatCode::Float32));
+        assert!(!formats.contains(&SampleFormatCode::Int16));
+
+        let units = [MeasurementSystem::Meters, MeasurementSystem::Feet]
+            .iter()
+            .copied()
+            .collect::<HashSet<_>>();
+        assert!(units.contains(&MeasurementSystem::Feet));
+
+        let source = LineNumberingSource::Synthesized {
+            dim_x: 10,
+            order_trace_by: OrderTraceBy::Default,
+        };
+        let sources = std::iter::once(source).collect::<HashSet<_>>();
+        assert!(sources.contains(&source));
+    }
 }
 
 mod bitconverter {
@@ -546,6 +576,81 @@ mod bitconverter {
             );
         }
     }
+
+    #[test]
+    fn test_fix_point_32() {
+        use crate::enums::SampleFormatCode::FixPoint32;
+
+        let cases: [(u32, f32); 7] = [
+            (0x0000_0000, 0.0),
+            (0x0001_0000, 1.0),
+            (0x0001_8000, 1.5),
+            (0xFFFF_8000, -0.5),
+            (0xFFFF_0000, -1.0),
+            (0x0000_0001, 1.0 / 65536.0),
+            (0x7FFF_0000, 32767.0),
+        ];
+        for (bits, expected) in cases.iter() {
+            assert_eq!(
+                converter_chooser(FixPoint32, false).unwrap()(&bits.to_be_bytes()).unwrap(),
+                *expected
+            );
+            assert_eq!(
+                converter_chooser(FixPoint32, true).unwrap()(&bits.to_le_bytes()).unwrap(),
+                *expected
+            );
+        }
+        assert_eq!(
+            converter_chooser(FixPoint32, false).unwrap()(&[0x80, 0, 0, 0]).unwrap(),
+            -32768.0
+        );
+    }
+
+    #[test]
+    fn test_ibm_float_byte_order() {
+        use crate::enums::SampleFormatCode::IbmFloat32;
+
+        let cases: [(u32, f32); 3] = [(0x4110_0000, 1.0), (0xC276_A000, -118.625), (0, 0.0)];
+        for (bits, expected) in cases.iter() {
+            assert_eq!(
+                converter_chooser(IbmFloat32, false).unwrap()(&bits.to_be_bytes()).unwrap(),
+                *expected
+            );
+            assert_eq!(
+                converter_chooser(IbmFloat32, true).unwrap()(&bits.to_le_bytes()).unwrap(),
+                *expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_24_bit_samples() {
+        use crate::enums::SampleFormatCode::{IbmFloat32, Int24, UInt24};
+
+        let be = [
+            0x00, 0x00, 0x01, 0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF, 0x80, 0x00, 0x00,
+        ];
+        let le = be
+            .chunks_exact(3)
+            .flat_map(|s| [s[2], s[1], s[0]])
+            .collect::<Vec<u8>>();
+        for (bytes, is_le) in [(&be[..], false), (&le[..], true)] {
+            assert_eq!(
+                decode_samples(bytes, Int24, is_le).unwrap(),
+                vec![1., -1., 8_388_607., -8_388_608.]
+            );
+            assert_eq!(
+                decode_samples(bytes, UInt24, is_le).unwrap(),
+                vec![1., 16_777_215., 8_388_607., 8_388_608.]
+            );
+        }
+        // The other formats are decoded as by `converter_chooser`.
+        let bytes = [0xC2, 0x76, 0xA0, 0x00, 0x41, 0x10, 0x00, 0x00];
+        assert_eq!(
+            decode_samples(&bytes, IbmFloat32, false).unwrap(),
+            vec![-118.625, 1.]
+        );
+    }
 }
 
 mod settings {
@@ -568,6 +673,7 @@ mod settings {
     fn test_default() {
         let expected = SegySettings {
             override_to_le: None,
+            endianness_detection: EndianDetection::TrustFlag,
             override_trace_format: None,
             override_coordinate_format: None,
             override_coordinate_scaling: None,
@@ -589,6 +695,35 @@ mod settings {
             override_v: None,
             override_sample_interval: None,
             order_trace_by: OrderTraceBy::Default,
+            strict_text_header: false,
+            trust_file_size: false,
+            mute_with_nan: false,
+            on_coordinate_parse_error: CoordErrorPolicy::Fail,
+            extended_trace_count: false,
+            auto_sequence: false,
+            strict_sample_count: false,
+            compact_headers: false,
+            mapping_budget: None,
+            dedup_headers: false,
+            allow_bidx_collisions: false,
+            apply_trace_weighting: false,
+            user_gain: 1.0,
+            trace_prefix_bytes: 0,
+            text_header_encoding: TextEncoding::Auto,
+            sort_traces_by: OrderTraceBy::Default,
+            coordinate_transform: None,
+            aux_trace_policy: AuxPolicy::Include,
+            max_map_window: 0,
+            strict_revision: false,
+            swap_data_byte_order: false,
+            strict_time_window: false,
+            scan_for_text_header: 0,
+            max_extended_headers: 1000,
+            detect_extended_headers: false,
+            append_provenance_card: false,
+            map_population: MapPopulation::Lazy,
+            lock_budget: None,
+            custom_decoders: Default::default(),
         };
         assert_eq!(SegySettings::default(), expected);
     }
@@ -626,6 +761,17 @@ mod settings {
         );
     }
 
+    #[test]
+    fn test_endianness_detection() {
+        test_set_get!(
+            set_endianness_detection,
+            get_endianness_detection,
+            endianness_detection,
+            EndianDetection::Heuristic,
+            EndianDetection::Heuristic
+        );
+    }
+
     #[test]
     fn test_override_sample_format() {
         test_set_get!(
@@ -777,6 +923,337 @@ mod settings {
         test_set_get!(set_step_by, get_step_by, step_by, 34, 34);
     }
 
+    #[test]
+    fn test_strict_text_header() {
+        test_set_get!(
+            set_strict_text_header,
+            get_strict_text_header,
+            strict_text_header,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_trust_file_size() {
+        test_set_get!(
+            set_trust_file_size,
+            get_trust_file_size,
+            trust_file_size,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_mute_with_nan() {
+        test_set_get!(
+            set_mute_with_nan,
+            get_mute_with_nan,
+            mute_with_nan,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_apply_trace_weighting() {
+        test_set_get!(
+            set_apply_trace_weighting,
+            get_apply_trace_weighting,
+            apply_trace_weighting,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_user_gain() {
+        test_set_get!(set_user_gain, get_user_gain, user_gain, 0.5, 0.5);
+    }
+
+    #[test]
+    fn test_trace_prefix_bytes() {
+        test_set_get!(
+            set_trace_prefix_bytes,
+            get_trace_prefix_bytes,
+            trace_prefix_bytes,
+            40,
+            40
+        );
+    }
+
+    #[test]
+    fn test_sort_traces_by() {
+        test_set_get!(
+            set_sort_traces_by,
+            get_sort_traces_by,
+            sort_traces_by,
+            OrderTraceBy::TraceNo,
+            OrderTraceBy::TraceNo
+        );
+    }
+
+    #[test]
+    fn test_coordinate_transform() {
+        let transform = crate::AffineTransform::new([[0., -1., 10.], [1., 0., 20.]]);
+        test_set_get!(
+            set_coordinate_transform,
+            get_coordinate_transform,
+            coordinate_transform,
+            Some(transform),
+            Some(transform)
+        );
+    }
+
+    #[test]
+    fn test_aux_trace_policy() {
+        test_set_get!(
+            set_aux_trace_policy,
+            get_aux_trace_policy,
+            aux_trace_policy,
+            AuxPolicy::Only,
+            AuxPolicy::Only
+        );
+    }
+
+    #[test]
+    fn test_map_population() {
+        test_set_get!(
+            set_map_population,
+            get_map_population,
+            map_population,
+            MapPopulation::Lock,
+            MapPopulation::Lock
+        );
+    }
+
+    #[test]
+    fn test_lock_budget() {
+        test_set_get!(
+            set_lock_budget,
+            get_lock_budget,
+            lock_budget,
+            Some(4096),
+            Some(4096)
+        );
+    }
+
+    #[test]
+    fn test_max_map_window() {
+        test_set_get!(
+            set_max_map_window,
+            get_max_map_window,
+            max_map_window,
+            1 << 20,
+            1 << 20
+        );
+    }
+
+    #[test]
+    fn test_strict_revision() {
+        test_set_get!(
+            set_strict_revision,
+            get_strict_revision,
+            strict_revision,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_strict_time_window() {
+        test_set_get!(
+            set_strict_time_window,
+            get_strict_time_window,
+            strict_time_window,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_scan_for_text_header() {
+        test_set_get!(
+            set_scan_for_text_header,
+            get_scan_for_text_header,
+            scan_for_text_header,
+            4096,
+            4096
+        );
+    }
+
+    #[test]
+    fn test_append_provenance_card() {
+        test_set_get!(
+            set_append_provenance_card,
+            get_append_provenance_card,
+            append_provenance_card,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_max_extended_headers() {
+        test_set_get!(
+            set_max_extended_headers,
+            get_max_extended_headers,
+            max_extended_headers,
+            10,
+            10
+        );
+    }
+
+    #[test]
+    fn test_detect_extended_headers() {
+        test_set_get!(
+            set_detect_extended_headers,
+            get_detect_extended_headers,
+            detect_extended_headers,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_text_header_encoding() {
+        test_set_get!(
+            set_text_header_encoding,
+            get_text_header_encoding,
+            text_header_encoding,
+            TextEncoding::Raw,
+            TextEncoding::Raw
+        );
+    }
+
+    #[test]
+    fn test_on_coordinate_parse_error() {
+        test_set_get!(
+            set_on_coordinate_parse_error,
+            get_on_coordinate_parse_error,
+            on_coordinate_parse_error,
+            CoordErrorPolicy::SkipTrace,
+            CoordErrorPolicy::SkipTrace
+        );
+    }
+
+    #[test]
+    fn test_extended_trace_count() {
+        test_set_get!(
+            set_extended_trace_count,
+            get_extended_trace_count,
+            extended_trace_count,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_auto_sequence() {
+        test_set_get!(
+            set_auto_sequence,
+            get_auto_sequence,
+            auto_sequence,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_strict_sample_count() {
+        test_set_get!(
+            set_strict_sample_count,
+            get_strict_sample_count,
+            strict_sample_count,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_compact_headers() {
+        test_set_get!(
+            set_compact_headers,
+            get_compact_headers,
+            compact_headers,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_mapping_budget() {
+        test_set_get!(
+            set_mapping_budget,
+            get_mapping_budget,
+            mapping_budget,
+            Some(1 << 30),
+            Some(1 << 30)
+        );
+    }
+
+    #[test]
+    fn test_dedup_headers() {
+        test_set_get!(
+            set_dedup_headers,
+            get_dedup_headers,
+            dedup_headers,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_allow_bidx_collisions() {
+        test_set_get!(
+            set_allow_bidx_collisions,
+            get_allow_bidx_collisions,
+            allow_bidx_collisions,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_check_bidx_collisions() {
+        let mut settings = SegySettings::default();
+        assert!(settings.check_bidx_collisions().is_empty());
+        settings
+            .apply_profile(HeaderProfile::SeisworksLegacy)
+            .unwrap();
+        let collisions = settings.check_bidx_collisions();
+        assert!(!collisions.is_empty());
+        assert!(collisions.iter().all(|c| !c.overlaps.is_empty()));
+
+        // The ensemble coordinates over the source coordinates and each other.
+        let mut settings = SegySettings::default();
+        settings.set_x_ensemble_bidx(72).unwrap();
+        settings.set_y_ensemble_bidx(74).unwrap();
+        let collisions = settings.check_bidx_collisions();
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(collisions[0].field, "x_ensemble");
+        assert_eq!(collisions[0].range, 72..76);
+        assert_eq!(collisions[0].overlaps, vec!["source_x", "y_ensemble"]);
+        assert_eq!(collisions[1].field, "y_ensemble");
+        assert_eq!(
+            collisions[1].overlaps,
+            vec!["source_x", "x_ensemble", "source_y"]
+        );
+        assert_eq!(
+            collisions[1].to_string(),
+            "y_ensemble at bytes 75-78 overlaps source_x, x_ensemble, source_y"
+        );
+
+        // Shuffled among the standard positions of the four fields.
+        let mut settings = SegySettings::default();
+        settings.set_inline_no_bidx(180).unwrap();
+        settings.set_crossline_no_bidx(184).unwrap();
+        settings.set_x_ensemble_bidx(188).unwrap();
+        settings.set_y_ensemble_bidx(192).unwrap();
+        assert!(settings.check_bidx_collisions().is_empty());
+    }
+
     #[test]
     fn test_inlne_min_max() {
         test_set_get!(
@@ -834,7 +1311,175 @@ mod settings {
         default.set_override_dim_z(44).expect("Valid");
         assert_eq!(default.override_dim_z, Some(44));
         assert_eq!(default.get_override_dim_z(), Some(44));
-        assert_eq!(default.inline_min_max, None)
+        assert_eq!(default.inline_min_max, None);
+
+        default.clear_override_dim_z();
+        assert_eq!(default.override_dim_z, None);
+        assert_eq!(default.get_override_dim_z(), None);
+    }
+
+    #[test]
+    fn test_override_dim_and_min_max_order() {
+        let mut a = SegySettings::default();
+        a.set_override_dim_x(50).expect("Valid");
+        a.set_override_dim_y(20).expect("Valid");
+        a.set_crossline_min_max([10, 59]);
+        a.set_inlne_min_max([5, 24]);
+
+        let mut b = SegySettings::default();
+        b.set_crossline_min_max([10, 59]);
+        b.set_inlne_min_max([5, 24]);
+        b.set_override_dim_x(50).expect("Valid");
+        b.set_override_dim_y(20).expect("Valid");
+
+        assert_eq!(a, b);
+        assert_eq!(a.get_crossline_min_max(), Some([10, 59]));
+        assert_eq!(a.get_inlne_min_max(), Some([5, 24]));
+        assert!(a.validate().is_ok());
+    }
+
+    #[cfg(feature = "to_json")]
+    #[test]
+    fn test_deserialize_older_settings() {
+        // Settings which were serialized before some fields existed can still be read.
+        let json = SegySettings::default().to_json().unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("endianness_detection");
+        fields.remove("strict_text_header");
+        fields.remove("trust_file_size");
+        fields.remove("mute_with_nan");
+        fields.remove("on_coordinate_parse_error");
+        fields.remove("extended_trace_count");
+        fields.remove("auto_sequence");
+        fields.remove("strict_sample_count");
+        fields.remove("compact_headers");
+        fields.remove("mapping_budget");
+        fields.remove("dedup_headers");
+        fields.remove("allow_bidx_collisions");
+        fields.remove("apply_trace_weighting");
+        fields.remove("user_gain");
+        fields.remove("trace_prefix_bytes");
+        fields.remove("text_header_encoding");
+        fields.remove("sort_traces_by");
+        fields.remove("coordinate_transform");
+        fields.remove("aux_trace_policy");
+        fields.remove("max_map_window");
+        fields.insert("step_by".to_string(), serde_json::json!(3));
+        let settings: SegySettings = serde_json::from_value(value).unwrap();
+
+        let mut expected = SegySettings::default();
+        expected.set_step_by(3);
+        assert_eq!(settings, expected);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SegySettings::default().validate().is_ok());
+
+        let mut settings = SegySettings::default();
+        settings.set_step_by(0);
+        assert!(settings.validate().is_err());
+
+        let mut settings = SegySettings::default();
+        settings.set_inlne_min_max([10, 9]);
+        assert!(settings.validate().is_err());
+        settings.set_inlne_min_max([10, 10]);
+        assert!(settings.validate().is_ok());
+
+        let mut settings = SegySettings::default();
+        settings.set_inlne_min_max([1, 30]);
+        settings.set_override_dim_y(29).expect("Valid");
+        assert!(settings.validate().is_err());
+        settings.set_override_dim_y(30).expect("Valid");
+        assert!(settings.validate().is_ok());
+
+        let mut settings = SegySettings::default();
+        settings.set_crossline_min_max([i32::MIN, i32::MAX]);
+        settings.set_override_dim_x(i32::MAX).expect("Valid");
+        assert!(settings.validate().is_err());
+
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(true);
+        settings.set_dedup_headers(true);
+        assert!(settings.validate().is_err());
+        settings.set_compact_headers(false);
+        assert!(settings.validate().is_ok());
+
+        let mut settings = SegySettings::default();
+        settings.set_user_gain(f32::INFINITY);
+        assert!(settings.validate().is_err());
+        settings.set_user_gain(-2.0);
+        assert!(settings.validate().is_ok());
+
+        // Eg. settings which were deserialized.
+        let mut settings = SegySettings {
+            override_coordinate_format: Some(crate::SampleFormatCode::Float64),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        settings.override_coordinate_format = Some(crate::SampleFormatCode::IbmFloat32);
+        assert!(settings.validate().is_ok());
+        settings.crossline_no_bidx = TRACE_HEADER_LEN - 3;
+        assert!(settings.validate().is_err());
+        settings.crossline_no_bidx = TRACE_HEADER_LEN - 4;
+        assert!(settings.validate().is_ok());
+        settings.override_dim_z = Some(-1);
+        assert!(settings.validate().is_err());
+        settings.override_dim_z = None;
+        settings.override_dim_x = Some(0);
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_set_ensemble_xy() {
+        let mut settings = SegySettings::default();
+        settings.set_ensemble_xy_to_source_xy();
+        assert_eq!(settings.get_x_ensemble_bidx(), 72);
+        assert_eq!(settings.get_y_ensemble_bidx(), 76);
+        assert_eq!(settings.get_inline_no_bidx(), INLINE_BYTE_LOCATION);
+        assert_eq!(settings.get_crossline_no_bidx(), CROSSLINE_BYTE_LOCATION);
+        let collisions = settings.check_bidx_collisions();
+        assert_eq!(collisions.len(), 2);
+        assert_eq!(collisions[0].overlaps, vec!["source_x"]);
+        assert_eq!(collisions[1].overlaps, vec!["source_y"]);
+
+        settings.set_ensemble_xy_to_receiver_xy();
+        assert_eq!(settings.get_x_ensemble_bidx(), 80);
+        assert_eq!(settings.get_y_ensemble_bidx(), 84);
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut settings = SegySettings::default();
+        settings.apply_profile(HeaderProfile::PetrelLegacy).unwrap();
+        assert_eq!(settings.get_inline_no_bidx(), 8);
+        assert_eq!(settings.get_crossline_no_bidx(), 20);
+        settings.apply_profile(HeaderProfile::Standard).unwrap();
+        assert_eq!(settings, SegySettings::default());
+
+        for profile in HeaderProfile::PRESETS.iter() {
+            let mut settings = SegySettings::default();
+            assert!(settings.apply_profile(*profile).is_ok());
+        }
+
+        // Overlapping fields or fields past the end of the header are refused.
+        let mut settings = SegySettings::default();
+        let overlapping = HeaderProfile::Custom {
+            inline: 8,
+            crossline: 10,
+            cdp_x: 180,
+            cdp_y: 184,
+        };
+        assert!(settings.apply_profile(overlapping).is_err());
+        let past_end = HeaderProfile::Custom {
+            inline: 8,
+            crossline: 20,
+            cdp_x: 180,
+            cdp_y: TRACE_HEADER_LEN - 2,
+        };
+        assert!(settings.apply_profile(past_end).is_err());
+        assert_eq!(settings, SegySettings::default());
     }
 
     #[test]
@@ -890,4 +1535,604 @@ mod settings {
             Some(-999.)
         );
     }
+
+    #[test]
+    fn test_swap_data_byte_order() {
+        test_set_get!(
+            set_swap_data_byte_order,
+            get_swap_data_byte_order,
+            swap_data_byte_order,
+            true,
+            true
+        );
+    }
+
+    #[test]
+    fn test_register_custom_decoder() {
+        use crate::decoder::{DatumLen, TraceDecoder};
+        use crate::errors::RsgError;
+        use std::sync::Arc;
+
+        struct Nibbles;
+
+        impl TraceDecoder for Nibbles {
+            fn datum_byte_length(&self) -> DatumLen {
+                DatumLen::Bits(4)
+            }
+
+            fn decode(
+                &self,
+                _: &[u8],
+                _: usize,
+                _: bool,
+                _: &mut Vec<f32>,
+            ) -> Result<(), RsgError> {
+                Ok(())
+            }
+        }
+
+        let decoder: Arc<dyn TraceDecoder> = Arc::new(Nibbles);
+        let mut settings = SegySettings::default();
+        assert!(settings.get_custom_decoders().is_empty());
+        settings.register_custom_decoder(300, decoder.clone());
+        assert!(settings.get_custom_decoder(300).is_some());
+        assert!(settings.get_custom_decoder(5).is_none());
+        assert_eq!(
+            settings.get_custom_decoders().codes().collect::<Vec<_>>(),
+            vec![300]
+        );
+        assert_eq!(format!("{:?}", settings.get_custom_decoders()), "{300}");
+
+        // Settings are only equal if they share the decoders.
+        let mut other = SegySettings::default();
+        assert_ne!(settings, other);
+        other.register_custom_decoder(300, decoder);
+        assert_eq!(settings, other);
+        other.register_custom_decoder(300, Arc::new(Nibbles));
+        assert_ne!(settings, other);
+    }
+}
+
+mod errors {
+    use crate::enums::SampleFormatCode;
+    use crate::errors::RsgError;
+    use std::convert::TryFrom;
+    use std::io::{Error, ErrorKind};
+
+    fn all_errors() -> Vec<(u32, RsgError)> {
+        let format = SampleFormatCode::Float32;
+        let errors = vec![
+            (
+                1,
+                RsgError::TryFromSlice(<[u8; 4]>::try_from(&[0u8][..]).unwrap_err()),
+            ),
+            (
+                2,
+                RsgError::TryFromUtf8(String::from_utf8(vec![0xff]).unwrap_err()),
+            ),
+            (
+                3,
+                RsgError::StdIoError(Error::new(ErrorKind::PermissionDenied, "no")),
+            ),
+            (4, RsgError::BinHeaderLength { l: 399 }),
+            (
+                5,
+                RsgError::SEGYSettingsError {
+                    msg: "bad".to_string(),
+                },
+            ),
+            (6, RsgError::FileTooShort),
+            (7, RsgError::FloatConversion { float: 1.5, format }),
+            (
+                8,
+                RsgError::CoordinateParse {
+                    trace: 3,
+                    offset: 72,
+                    bytes: [0xff; 4],
+                },
+            ),
+            (9, RsgError::IncompleteTrace { trace: 4 }),
+            (10, RsgError::TraceNotFound { i: 5 }),
+            (11, RsgError::TracePointOutOfBounds { idx: 6 }),
+            (12, RsgError::LineNotFound { line: 7 }),
+            (13, RsgError::SEGYTooShort),
+            (14, RsgError::ShortSEGY { a: 1, b: 2 }),
+            (15, RsgError::TraceDivisibility { a: 5, b: 4, format }),
+            (16, RsgError::TraceHeaderLength { l: 239 }),
+            (
+                17,
+                RsgError::BitConversionError {
+                    msg: "bad".to_string(),
+                },
+            ),
+            (
+                18,
+                RsgError::InvalidHeader {
+                    msg: "bad".to_string(),
+                },
+            ),
+            (
+                19,
+                RsgError::LongDataVector {
+                    l_data: 70000,
+                    max: 65535,
+                    reason: "the file is not SEG-Y rev 2",
+                },
+            ),
+            (
+                20,
+                RsgError::BadDataVector {
+                    l_data: 1,
+                    l_bin: 2,
+                    l_trace: 3,
+                },
+            ),
+            (
+                21,
+                RsgError::ParseEnum {
+                    f: "SampleFormatCode".to_string(),
+                    code: 99,
+                },
+            ),
+            (22, RsgError::HeaderFieldEncode { field: "source_x" }),
+            (
+                23,
+                RsgError::FormatSizeMismatch {
+                    declared: format,
+                    implied_by_size: SampleFormatCode::Int16,
+                },
+            ),
+            (24, RsgError::TooManyTraces { max: 65535 }),
+            (25, RsgError::Cancelled),
+            (
+                26,
+                RsgError::MapFile(Error::new(ErrorKind::OutOfMemory, "no")),
+            ),
+            (
+                28,
+                RsgError::FixedLengthMismatch {
+                    trace: 8,
+                    l_data: 10,
+                    l_bin: 20,
+                },
+            ),
+            (
+                29,
+                RsgError::TraceSampleCountMismatch {
+                    trace: 9,
+                    l_data: 10,
+                    l_trace: 20,
+                },
+            ),
+            (30, RsgError::FileTooLargeForPlatform { len: 1 << 33 }),
+            (
+                31,
+                RsgError::TraceExtensionLength {
+                    trace: 10,
+                    len: 12,
+                    expected: 240,
+                },
+            ),
+            (
+                32,
+                RsgError::TextInTraceData {
+                    trace: 0,
+                    text_len: 40,
+                },
+            ),
+            (
+                33,
+                RsgError::InvalidCell {
+                    cell: [1, 2],
+                    msg: "bad".to_string(),
+                },
+            ),
+            (
+                34,
+                RsgError::MissingCells {
+                    count: 3,
+                    first: [0, 1],
+                },
+            ),
+            (35, RsgError::SampleConversion { idx: 4, value: 1e6 }),
+            (
+                36,
+                RsgError::AllocationOverBudget {
+                    bytes: 4000,
+                    budget: 1000,
+                },
+            ),
+            (
+                37,
+                RsgError::RaggedTraces {
+                    trace: 11,
+                    samples: 10,
+                    expected: 20,
+                },
+            ),
+            (38, RsgError::UnsupportedRevision { major: 3, minor: 0 }),
+            (
+                39,
+                RsgError::TimeWindowOutOfBounds {
+                    trace: 12,
+                    window: [0., 10.],
+                    extent: [4., 40.],
+                },
+            ),
+            (
+                40,
+                RsgError::OutputExists {
+                    path: "out.sgy".into(),
+                },
+            ),
+            (
+                41,
+                RsgError::ImplausibleExtendedHeaderCount {
+                    count: 8224,
+                    max: 2,
+                },
+            ),
+            (
+                42,
+                RsgError::GridTooLarge {
+                    cells: 1 << 40,
+                    max: 1 << 28,
+                },
+            ),
+        ];
+        #[cfg(feature = "to_json")]
+        let errors = {
+            let mut errors = errors;
+            errors.push((
+                27,
+                RsgError::SerdeError(serde_json::from_str::<u8>("x").unwrap_err()),
+            ));
+            errors
+        };
+        errors
+    }
+
+    #[test]
+    fn error_codes_are_stable() {
+        for (code, e) in all_errors() {
+            assert_eq!(e.code(), code, "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn error_trace_index() {
+        let indices = all_errors()
+            .into_iter()
+            .filter_map(|(code, e)| e.trace_index().map(|i| (code, i)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            indices,
+            vec![
+                (8, 3),
+                (9, 4),
+                (10, 5),
+                (28, 8),
+                (29, 9),
+                (31, 10),
+                (32, 0),
+                (37, 11),
+                (39, 12)
+            ]
+        );
+    }
+
+    #[test]
+    fn error_into_io_error() {
+        let kinds = all_errors()
+            .into_iter()
+            .map(|(code, e)| (code, Error::from(e).kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds[2], (3, ErrorKind::PermissionDenied));
+        assert_eq!(kinds[5], (6, ErrorKind::UnexpectedEof));
+        assert_eq!(kinds[8], (9, ErrorKind::UnexpectedEof));
+        assert_eq!(kinds[9], (10, ErrorKind::NotFound));
+        assert_eq!(kinds[4], (5, ErrorKind::InvalidInput));
+        assert_eq!(kinds[17], (18, ErrorKind::InvalidData));
+        assert_eq!(kinds[24], (25, ErrorKind::Other));
+        assert_eq!(kinds[25], (26, ErrorKind::OutOfMemory));
+        assert_eq!(kinds[18], (19, ErrorKind::Other));
+        assert_eq!(kinds[26], (28, ErrorKind::InvalidInput));
+        assert_eq!(kinds[27], (29, ErrorKind::InvalidInput));
+        assert_eq!(kinds[28], (30, ErrorKind::OutOfMemory));
+        assert_eq!(kinds[29], (31, ErrorKind::InvalidInput));
+        assert_eq!(kinds[30], (32, ErrorKind::InvalidData));
+        assert_eq!(kinds[31], (33, ErrorKind::InvalidInput));
+        assert_eq!(kinds[32], (34, ErrorKind::InvalidInput));
+        assert_eq!(kinds[33], (35, ErrorKind::InvalidData));
+        assert_eq!(kinds[34], (36, ErrorKind::OutOfMemory));
+        assert_eq!(kinds[35], (37, ErrorKind::InvalidData));
+        assert_eq!(kinds[36], (38, ErrorKind::InvalidData));
+        assert_eq!(kinds[37], (39, ErrorKind::InvalidInput));
+        assert_eq!(kinds[38], (40, ErrorKind::AlreadyExists));
+        assert_eq!(kinds[39], (41, ErrorKind::InvalidData));
+        assert_eq!(kinds[40], (42, ErrorKind::InvalidData));
+
+        let e = Error::from(RsgError::TraceNotFound { i: 5 });
+        assert_eq!(e.to_string(), RsgError::TraceNotFound { i: 5 }.to_string());
+
+        let e = Error::from(RsgError::Cancelled);
+        let inner = e.get_ref().unwrap().downcast_ref::<RsgError>();
+        assert!(matches!(inner, Some(RsgError::Cancelled)));
+    }
+}
+
+mod layout {
+    use crate::layout::*;
+    use crate::{BIN_HEADER_LEN, TRACE_HEADER_LEN};
+
+    #[test]
+    fn trace_header_layout_is_contiguous() {
+        let layout = trace_header_layout();
+        assert_eq!(layout[0].range.start, 0);
+        assert_eq!(layout.last().unwrap().range.end, TRACE_HEADER_LEN);
+        for pair in layout.windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start, "{}", pair[1].name);
+        }
+    }
+
+    #[test]
+    fn bin_header_layout_does_not_overlap() {
+        let layout = bin_header_layout();
+        assert!(layout.iter().all(|f| f.range.start < f.range.end));
+        assert!(layout.last().unwrap().range.end <= BIN_HEADER_LEN);
+
+        // Everything else in the binary header is unassigned.
+        let mut gaps = Vec::new();
+        let mut end = 0;
+        for field in layout {
+            assert!(field.range.start >= end, "{}", field.name);
+            if field.range.start > end {
+                gaps.push(end..field.range.start);
+            }
+            end = field.range.end;
+        }
+        gaps.push(end..BIN_HEADER_LEN);
+        assert_eq!(gaps, vec![100..300, 332..400]);
+    }
+
+    #[test]
+    fn standard_locations_match_layout() {
+        use crate::standard_locations::*;
+        let layout = trace_header_layout();
+        let start = |name: &str| layout.iter().find(|f| f.name == name).unwrap().range.start;
+        assert_eq!(TRACE_SEQUENCE_ON_LINE, 0);
+        assert_eq!(
+            SOURCE_TO_RECEIVER_DISTANCE,
+            start("source_to_receiver_distance")
+        );
+        assert_eq!(SOURCE_X, start("source_x"));
+        assert_eq!(RECEIVER_GROUP_Y, start("receiver_group_y"));
+        assert_eq!(SHOT_POINT_NO, 196);
+        assert_eq!(TRACE_NAME, 232);
+    }
+
+    #[test]
+    fn field_names_are_unique() {
+        for layout in [trace_header_layout(), bin_header_layout()].iter() {
+            let mut names = layout.iter().map(|f| f.name).collect::<Vec<_>>();
+            names.sort_unstable();
+            names.dedup();
+            assert_eq!(names.len(), layout.len());
+        }
+    }
+}
+
+mod stanza {
+    use crate::stanza::*;
+    use crate::TEXT_HEADER_LEN;
+
+    /// Build an extended header from card images.
+    fn record(cards: &[String]) -> String {
+        let record = cards
+            .iter()
+            .map(|c| format!("{:<80}", c))
+            .collect::<String>();
+        assert!(record.len() <= TEXT_HEADER_LEN);
+        format!("{:<3200}", record)
+    }
+
+    /// A free-form header, then two stanzas of which the first crosses into the next header.
+    fn fixture() -> Vec<String> {
+        let mut location = vec!["((SEG: Location Data ver 1.0))".to_string()];
+        location.extend((0..39).map(|i| format!("Key {:02} = Value {}", i, i)));
+
+        let mut second = (39..42)
+            .map(|i| format!("Key {:02} = Value {}", i, i))
+            .collect::<Vec<_>>();
+        second.push(String::new());
+        second.push("((Company: Acquisition Notes))".to_string());
+        second.push("Vessel = Research Vessel".to_string());
+        second.push("Remarks = Strong currents".to_string());
+        second.push("  in the northern part of the survey".to_string());
+        second.push("((SEG: EndText))".to_string());
+        second.push("Ignored = Yes".to_string());
+
+        vec![
+            record(&["C 1 Free-form notes on the survey.".to_string()]),
+            record(&location),
+            record(&second),
+            record(&["((Ignored stanza))".to_string(), "Key = Value".to_string()]),
+        ]
+    }
+
+    #[test]
+    fn parse_stanzas_across_records() {
+        let stanzas = parse_extended_stanzas(&fixture());
+        assert_eq!(stanzas.len(), 2);
+
+        assert_eq!(stanzas[0].name, "SEG: Location Data ver 1.0");
+        assert_eq!(stanzas[0].entries.len(), 42);
+        for (i, (key, value)) in stanzas[0].entries.iter().enumerate() {
+            assert_eq!(key, &format!("Key {:02}", i));
+            assert_eq!(value, &format!("Value {}", i));
+        }
+
+        assert_eq!(stanzas[1].name, "Company: Acquisition Notes");
+        assert_eq!(stanzas[1].get("Vessel"), Some("Research Vessel"));
+        assert_eq!(
+            stanzas[1].get("Remarks"),
+            Some("Strong currents in the northern part of the survey")
+        );
+        assert_eq!(stanzas[1].entries.len(), 2);
+    }
+
+    #[test]
+    fn parse_stanzas_with_line_breaks() {
+        let header = "((SEG: Observer Notes))\r\nWeather = Calm\r\nSea state=2\n";
+        let stanzas = parse_extended_stanzas(&[header.to_string()]);
+        assert_eq!(stanzas.len(), 1);
+        assert_eq!(
+            stanzas[0].entries,
+            [
+                ("Weather".to_string(), "Calm".to_string()),
+                ("Sea state".to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn free_form_header_ends_stanza() {
+        let headers = [
+            record(&["((Notes))".to_string(), "A = B".to_string()]),
+            record(&["Just some text.".to_string()]),
+        ];
+        let stanzas = parse_extended_stanzas(&headers);
+        assert_eq!(stanzas.len(), 1);
+        assert_eq!(stanzas[0].entries, [("A".to_string(), "B".to_string())]);
+        assert!(parse_extended_stanzas(&[]).is_empty());
+    }
+
+    #[test]
+    fn stanza_to_header_records() {
+        let stanzas = parse_extended_stanzas(&fixture());
+        let records = stanzas
+            .iter()
+            .flat_map(|s| s.to_header_records())
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| r.len() == TEXT_HEADER_LEN));
+        assert_eq!(
+            &records[0][..80],
+            format!("{:<80}", "((SEG: Location Data ver 1.0))")
+        );
+        assert_eq!(&records[1][..80], format!("{:<80}", "Key 39 = Value 39"));
+        assert_eq!(parse_extended_stanzas(&records), stanzas);
+    }
+
+    #[test]
+    fn stanza_to_header_records_long_entry() {
+        let mut stanza = Stanza::new("Notes");
+        let long = (0..30).map(|i| format!("word{}", i)).collect::<Vec<_>>();
+        stanza.entries.push(("Text".to_string(), long.join(" ")));
+        stanza.entries.push(("Code".to_string(), "x".repeat(100)));
+
+        let records = stanza.to_header_records();
+        assert_eq!(records.len(), 1);
+        let cards = records[0]
+            .as_bytes()
+            .chunks(80)
+            .map(|c| std::str::from_utf8(c).unwrap().trim_end())
+            .collect::<Vec<_>>();
+        assert!(cards[1].starts_with("Text = word0 word1"));
+        assert!(cards[2].starts_with("word"));
+
+        let parsed = parse_extended_stanzas(&records);
+        assert_eq!(parsed[0].entries[0], stanza.entries[0]);
+        let code = format!("{} {}", "x".repeat(80), "x".repeat(20));
+        assert_eq!(parsed[0].get("Code"), Some(code.as_str()));
+    }
+}
+
+mod recording_time {
+    use crate::enums::TimeBasisCode;
+    use crate::RecordingTime;
+
+    fn date(year: u16, day_of_year: u16) -> Option<(u16, u8, u8)> {
+        RecordingTime::new(year, day_of_year, 0, 0, 0, TimeBasisCode::Unspecified)
+            .map(|t| (t.year, t.month, t.day))
+    }
+
+    #[test]
+    fn day_of_year_to_date() {
+        assert_eq!(date(2023, 1), Some((2023, 1, 1)));
+        assert_eq!(date(2023, 59), Some((2023, 2, 28)));
+        assert_eq!(date(2023, 60), Some((2023, 3, 1)));
+        assert_eq!(date(2023, 365), Some((2023, 12, 31)));
+        assert_eq!(date(2023, 366), None);
+        assert_eq!(date(2023, 0), None);
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(date(2024, 60), Some((2024, 2, 29)));
+        assert_eq!(date(2024, 61), Some((2024, 3, 1)));
+        assert_eq!(date(2024, 366), Some((2024, 12, 31)));
+        assert_eq!(date(2000, 366), Some((2000, 12, 31)));
+        // Centuries are only leap years if they divide by 400.
+        assert_eq!(date(1900, 60), Some((1900, 3, 1)));
+        assert_eq!(date(1900, 366), None);
+        assert_eq!(date(2024, 367), None);
+    }
+
+    #[test]
+    fn two_digit_years() {
+        assert_eq!(date(0, 1), Some((2000, 1, 1)));
+        assert_eq!(date(30, 1), Some((2030, 1, 1)));
+        assert_eq!(date(31, 1), Some((1931, 1, 1)));
+        assert_eq!(date(99, 1), Some((1999, 1, 1)));
+        assert_eq!(date(100, 1), Some((100, 1, 1)));
+        // Year 0 is read as 2000, which is a leap year.
+        assert_eq!(date(0, 366), Some((2000, 12, 31)));
+    }
+
+    #[test]
+    fn zeroed_and_invalid_times() {
+        assert_eq!(
+            RecordingTime::new(0, 0, 0, 0, 0, TimeBasisCode::Local),
+            None
+        );
+        let utc = TimeBasisCode::CoordinatedUTC;
+        assert_eq!(RecordingTime::new(2023, 1, 24, 0, 0, utc), None);
+        assert_eq!(RecordingTime::new(2023, 1, 0, 60, 0, utc), None);
+        assert_eq!(RecordingTime::new(2023, 1, 0, 0, 60, utc), None);
+        let time = RecordingTime::new(2023, 1, 23, 59, 59, utc).unwrap();
+        assert_eq!((time.hour, time.minute, time.second), (23, 59, 59));
+    }
+
+    #[test]
+    fn unix_seconds() {
+        let at = |year, day, basis| {
+            RecordingTime::new(year, day, 0, 0, 0, basis)
+                .unwrap()
+                .to_unix_seconds()
+        };
+        assert_eq!(at(1970, 1, TimeBasisCode::GreenwichGMT), Some(0));
+        assert_eq!(at(1969, 365, TimeBasisCode::GreenwichGMT), Some(-86_400));
+        // 2024-12-31, the 366th day of a leap year.
+        assert_eq!(
+            at(24, 366, TimeBasisCode::CoordinatedUTC),
+            Some(1_735_603_200)
+        );
+        // 2100-03-01, after a century which is not a leap year.
+        assert_eq!(
+            at(2100, 60, TimeBasisCode::CoordinatedUTC),
+            Some(4_107_542_400)
+        );
+        for basis in [
+            TimeBasisCode::Unspecified,
+            TimeBasisCode::Local,
+            TimeBasisCode::Other,
+            TimeBasisCode::GlobalGPS,
+        ] {
+            assert_eq!(at(2024, 1, basis), None);
+        }
+
+        let time = RecordingTime::new(2024, 60, 13, 14, 15, TimeBasisCode::GreenwichGMT).unwrap();
+        assert_eq!(time.to_unix_seconds(), Some(1_709_212_455));
+    }
 }
@@ -0,0 +1,70 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This module contains the [`AffineTransform`] which `giga-segy-in` applies to the coordinates
+//! of the trace headers when a file is read (see
+//! [`crate::SegySettings::set_coordinate_transform`]), eg. to turn local grid coordinates into
+//! UTM coordinates.
+use crate::TraceHeader;
+
+/// A two dimensional affine transform of coordinates, given as the matrix
+/// `[[a, b, tx], [c, d, ty]]`, which maps `(x, y)` to `(a * x + b * y + tx, c * x + d * y + ty)`.
+/// ```
+/// use giga_segy_core::AffineTransform;
+///
+/// let shift = AffineTransform::translation(500_000., 6_000_000.);
+/// assert_eq!(shift.apply(10., 20.), (500_010., 6_000_020.));
+///
+/// // A rotation by 90 degrees anticlockwise, with a scale of two.
+/// let rotate = AffineTransform::new([[0., -2., 0.], [2., 0., 0.]]);
+/// assert_eq!(rotate.apply(1., 3.), (-6., 2.));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct AffineTransform {
+    /// The matrix `[[a, b, tx], [c, d, ty]]`.
+    pub matrix: [[f64; 3]; 2],
+}
+
+impl AffineTransform {
+    /// Create a transform from the matrix `[[a, b, tx], [c, d, ty]]`.
+    pub fn new(matrix: [[f64; 3]; 2]) -> Self {
+        AffineTransform { matrix }
+    }
+
+    /// Create a transform which only shifts the coordinates.
+    pub fn translation(tx: f64, ty: f64) -> Self {
+        Self::new([[1., 0., tx], [0., 1., ty]])
+    }
+
+    /// Apply the transform to a pair of coordinates.
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [[a, b, tx], [c, d, ty]] = self.matrix;
+        (a * x + b * y + tx, c * x + d * y + ty)
+    }
+}
+
+impl TraceHeader {
+    /// Turn a coordinate back into the integer which gives it when the coordinate scalar is
+    /// applied (see [`TraceHeader::apply_coordinate_scalar`]), rounded to the nearest integer.
+    /// `None` is returned if this does not fit in an `i32`.
+    /// ```
+    /// use giga_segy_core::TraceHeader;
+    /// use giga_segy_out::create_headers::CreateTraceHeader;
+    ///
+    /// let header = TraceHeader::new_2d(0, 0, -100);
+    /// assert_eq!(header.remove_coordinate_scalar(1234.567), Some(123_457));
+    /// assert_eq!(header.remove_coordinate_scalar(1e9), None);
+    /// ```
+    pub fn remove_coordinate_scalar(&self, value: f64) -> Option<i32> {
+        let unscaled = match self.coordinate_scalar {
+            s if s > 0 => value / s as f64,
+            s if s < 0 => value * -(s as f64),
+            _ => value,
+        }
+        .round();
+        if unscaled >= i32::MIN as f64 && unscaled <= i32::MAX as f64 {
+            Some(unscaled as i32)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! Benchmarks for opening a synthetic file with full and with deduplicated trace headers. The
+//! memory which is used for the traces in each case is printed before the benchmarks are run.
+use criterion::{criterion_group, criterion_main, Criterion};
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use giga_segy_in::SegyFile;
+use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+use std::path::Path;
+
+const LINES: i32 = 200;
+const SAMPLES: u16 = 10;
+
+/// Write a file with a trace for each of `LINES` by `LINES` inlines and crosslines, whose
+/// headers only differ in the line numbers.
+fn write_file(path: &Path) {
+    let bin_header = BinHeader::new(0, 4000, SAMPLES, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        path,
+        SegySettings::default(),
+        "C 1 A benchmark.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for il in 1..=LINES {
+        for xl in 1..=LINES {
+            let header = TraceHeader::new_3d(0, 0, il, xl, 1);
+            file.add_trace(header, None, vec![0.; SAMPLES as usize])
+                .expect("Could not add trace.");
+        }
+    }
+}
+
+fn settings(dedup: bool) -> SegySettings {
+    let mut settings = SegySettings::default();
+    settings.set_dedup_headers(dedup);
+    settings
+}
+
+fn dedup_headers(c: &mut Criterion) {
+    let dir = tempfile::tempdir().expect("Couldn't get tempdir.");
+    let path = dir.path().join("bench.sgy");
+    write_file(&path);
+    let path = path.to_str().unwrap();
+
+    for dedup in [false, true] {
+        let segy = SegyFile::open(path, settings(dedup)).expect("Could not open file.");
+        println!(
+            "{} traces, dedup_headers = {}: {} bytes for the traces.",
+            segy.trace_count(),
+            dedup,
+            segy.trace_memory_size()
+        );
+    }
+
+    let mut group = c.benchmark_group("open");
+    for (name, dedup) in [("full_headers", false), ("dedup_headers", true)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                SegyFile::open(path, settings(dedup))
+                    .expect("Could not open file.")
+                    .trace_count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, dedup_headers);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! Benchmarks for reading the samples of all traces of a synthetic file as f32 values, for the
+//! sample formats which have a fast path and for one which is read with the generic converter.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use giga_segy_in::SegyFile;
+use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+use std::path::Path;
+
+const SAMPLES: usize = 2_000;
+const TRACES: usize = 1_000;
+
+/// Write a file with `TRACES` traces of `SAMPLES` samples each.
+fn write_file(path: &Path, format: SampleFormatCode, le: bool) {
+    let mut bin_header = BinHeader::new(0, 4000, SAMPLES as u16, format);
+    bin_header.binary_flag_direction_is_le = le;
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        path,
+        SegySettings::default(),
+        "C 1 A benchmark.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    let data = (0..SAMPLES).map(|s| (s % 1000) as f32).collect::<Vec<_>>();
+    for i in 0..TRACES {
+        let header = TraceHeader::new_3d(0, 0, 1, i as i32, 1);
+        file.add_trace(header, None, data.clone())
+            .expect("Could not add trace.");
+    }
+}
+
+fn bench_format(c: &mut Criterion, format: SampleFormatCode, le: bool) {
+    let dir = tempfile::tempdir().expect("Couldn't get tempdir.");
+    let path = dir.path().join("bench.sgy");
+    write_file(&path, format, le);
+    let segy = SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open file.");
+
+    let mut group = c.benchmark_group("get_trace_data_as_f32");
+    group.throughput(Throughput::Elements((SAMPLES * TRACES) as u64));
+    let name = format!("{:?}/{}", format, if le { "le" } else { "be" });
+    group.bench_function(name, |b| {
+        b.iter(|| {
+            (0..segy.trace_count())
+                .map(|i| segy.get_trace_data_as_f32(i).expect("Readable.")[SAMPLES - 1])
+                .sum::<f32>()
+        })
+    });
+    group.finish();
+}
+
+fn read_data(c: &mut Criterion) {
+    bench_format(c, SampleFormatCode::Float32, false);
+    bench_format(c, SampleFormatCode::Float32, true);
+    bench_format(c, SampleFormatCode::Int16, false);
+    // This format is still read with the generic converter.
+    bench_format(c, SampleFormatCode::UInt16, false);
+}
+
+criterion_group!(benches, read_data);
+criterion_main!(benches);
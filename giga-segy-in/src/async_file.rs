@@ -0,0 +1,416 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows a SEG-Y file to be opened and read asynchronously with `tokio`.
+//!
+//! Unlike [`crate::SegyFile`], the file is not mapped to memory. Instead the headers and trace
+//! data are read with positional reads when they are needed, so that worker threads are not
+//! blocked by page faults. Once the bytes are read, they are parsed in the same way as for the
+//! memory mapped file.
+use giga_segy_core::enums::{LineNumberingSource, TextEncoding, TextHeaderLayout};
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, EffectiveParams, SegyHandle, SegyMetadata, SegySettings};
+use giga_segy_core::{TapeLabel, Trace, TracesIter};
+use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TEXT_HEADER_LEN};
+
+use futures_util::stream::{self, Stream};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::compact::CompactTrace;
+use crate::dedup::DedupTraces;
+use crate::line_index::LineKey;
+use crate::memory_map::{self, FileHeaders, HeaderStorage, TraceScanner};
+use crate::read_data::{
+    decimate_trace_bytes, decode_trace_data_as_f32, decode_trace_data_converted,
+};
+
+/// A structure which represents a SEG-Y file that is read asynchronously.
+///
+/// The headers are parsed when the file is opened, while the trace data is only read from
+/// the file when it is requested. The trace headers are kept in the same way as for a
+/// [`crate::SegyFile`], so they can also be stored compactly or deduplicated (see
+/// [`SegySettings::set_compact_headers`] and [`SegySettings::set_dedup_headers`]).
+///
+/// ```no_run
+/// # async fn run() -> Result<(), giga_segy_in::RsgError> {
+/// use giga_segy_in::AsyncSegyFile;
+///
+/// let file = AsyncSegyFile::open("MyFavouriteSEGYDataset.sgy", Default::default()).await?;
+/// let data = file.get_trace_data_as_f32(0).await?;
+/// println!("First trace: {:?}", data);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncSegyFile {
+    pub(crate) metadata: SegyMetadata<SegySettings>,
+    pub(crate) traces: Vec<Trace>,
+    /// The traces without their headers, if the headers are stored compactly. Otherwise this
+    /// is empty.
+    compact_traces: Vec<CompactTrace>,
+    /// The traces with deduplicated headers, if the headers are deduplicated. Otherwise this
+    /// is empty.
+    dedup_traces: DedupTraces,
+    /// This is here to speed up the lookup of traces. The inline and crossline
+    /// numbers act as the key, and the index of the trace is retrieved.
+    pub(crate) lookup: HashMap<LineKey, usize>,
+    /// The indices of the auxiliary traces, if they were kept with the other traces.
+    aux_traces: Vec<usize>,
+    /// Anything that was not quite right in the file, but could be dealt with when opening it.
+    pub(crate) warnings: Vec<String>,
+    /// The file, which is only read with positional reads so that several reads can share it.
+    file: Arc<File>,
+    file_len: usize,
+}
+
+impl AsyncSegyFile {
+    /// Attempts to open a SEG-Y file stored in a given location, with a given set of settings.
+    /// This is the asynchronous equivalent of [`crate::SegyFile::open`].
+    pub async fn open(file_name: &str, mut settings: SegySettings) -> Result<Self, RsgError> {
+        settings.validate()?;
+        let file = tokio::fs::File::open(file_name)
+            .await
+            .map_err(RsgError::MapFile)?;
+        let file_len = file.metadata().await.map_err(RsgError::MapFile)?.len();
+        let file = Arc::new(file.into_std().await);
+        // The file is not mapped, so the mapping budget does not apply.
+        let file_len = memory_map::check_file_fits(file_len, None)?;
+
+        // The tape label, text header and binary header come first, after anything which is in
+        // front of them if the text header is searched for.
+        let max_scan = settings.get_scan_for_text_header();
+        let head_len = file_len
+            .min((TAPE_LABEL_LEN + TEXT_HEADER_LEN + BIN_HEADER_LEN).saturating_add(max_scan));
+        let mut head = read_at(&file, 0, head_len).await?;
+        let offset = memory_map::find_headers_start(&head, &settings)?;
+        head.drain(..offset);
+        let headers_len = file_len - offset;
+        memory_map::check_file_length(&head, headers_len)?;
+
+        // Then come the extended headers, if there are any. Only as many as can be there are
+        // read, which is enough to detect them if the count is implausible.
+        let needed = FileHeaders::needed_len(&head, headers_len, &settings)?;
+        if needed > head.len() {
+            let rest = read_at(&file, offset + head.len(), needed - head.len()).await?;
+            head.extend_from_slice(&rest);
+        }
+        let mut warnings = Vec::new();
+        let mut headers = FileHeaders::parse(&head, headers_len, &mut settings, &mut warnings)?;
+
+        // And finally the trace headers, which are read one by one.
+        let bin_header = &mut headers.bin_header;
+        let extended_header_count = headers.extended_headers.len();
+        memory_map::check_before_scan(
+            &head,
+            headers_len,
+            bin_header,
+            extended_header_count,
+            &settings,
+            &mut warnings,
+        )?;
+        let mut scanner = TraceScanner::new(
+            &head,
+            offset,
+            file_len,
+            bin_header,
+            extended_header_count,
+            &settings,
+            HeaderStorage::from_settings(&settings),
+        )?;
+        let mut scanned = Ok(());
+        while let Some(b_range) = scanner.next_header_range() {
+            let bytes = read_at(&file, b_range.start, b_range.len()).await?;
+            scanned = scanner.push_header(&bytes, bin_header, &settings);
+            if scanned.is_err() {
+                break;
+            }
+        }
+        if scanned.is_err() || scanner.stopped_early() {
+            if let Some(range) = scanner.first_data_range() {
+                let bytes = read_at(&file, range.start, range.len()).await?;
+                memory_map::check_text_in_data(&bytes)?;
+            }
+        }
+        scanned?;
+        let (scanned, scan_warnings) = scanner.finish(bin_header, &settings);
+        warnings.extend(scan_warnings);
+
+        let label_len = headers.tape_label.as_ref().map_or(0, |_| TAPE_LABEL_LEN);
+        let metadata = headers.into_metadata(settings, offset + label_len);
+        Ok(AsyncSegyFile {
+            metadata,
+            lookup: scanned.lookup(),
+            traces: scanned.full,
+            compact_traces: scanned.compact,
+            dedup_traces: scanned.dedup,
+            aux_traces: scanned.aux,
+            warnings,
+            file,
+            file_len,
+        })
+    }
+
+    /// Get a reference to the [`SegySettings`] which were used when opening the file.
+    pub fn get_settings(&self) -> &SegySettings {
+        self.metadata.get_settings()
+    }
+
+    /// Get a reference to the tape label from file if it has one.
+    pub fn get_tape_label(&self) -> &Option<TapeLabel> {
+        self.metadata.get_tape_label()
+    }
+
+    /// Get the text header of the file.
+    pub fn get_text_header(&self) -> &str {
+        self.metadata.get_text_header()
+    }
+
+    /// Get the encoding with which the text header was decoded (see
+    /// [`crate::SegyFile::get_text_header_encoding`]).
+    pub fn get_text_header_encoding(&self) -> TextEncoding {
+        self.metadata
+            .get_text_header_encoding()
+            .expect("Set when the file is opened.")
+    }
+
+    /// Get how the lines of the text header were found (see
+    /// [`crate::SegyFile::get_text_header_layout`]).
+    pub fn get_text_header_layout(&self) -> TextHeaderLayout {
+        self.metadata
+            .get_text_header_layout()
+            .expect("Set when the file is opened.")
+    }
+
+    /// Get the values which were used to interpret the file (see
+    /// [`crate::SegyFile::effective_parameters`]).
+    pub fn effective_parameters(&self) -> &EffectiveParams {
+        self.metadata
+            .get_effective_params()
+            .expect("Set when the file is opened.")
+    }
+
+    /// Get a reference to the extended headers.
+    pub fn get_extended_headers(&self) -> &[String] {
+        self.metadata.get_extended_headers()
+    }
+
+    /// Get the warnings about problems with the file which were dealt with when opening it.
+    /// See [`crate::SegyFile::get_warnings`].
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Get the binary header.
+    pub fn get_bin_header(&self) -> &BinHeader {
+        self.metadata.get_bin_header()
+    }
+
+    /// Get the number of readable traces in the SEG-Y file.
+    pub fn trace_count(&self) -> usize {
+        let settings = self.get_settings();
+        if settings.get_compact_headers() {
+            self.compact_traces.len()
+        } else if settings.get_dedup_headers() {
+            self.dedup_traces.len()
+        } else {
+            self.traces.len()
+        }
+    }
+
+    /// Check whether the file has no traces (see [`crate::SegyFile::is_empty`]).
+    pub fn is_empty(&self) -> bool {
+        self.trace_count() == 0
+    }
+
+    /// Get where the inline and crossline numbers of the traces come from (see
+    /// [`crate::SegyFile::line_numbering_source`]).
+    pub fn line_numbering_source(&self) -> LineNumberingSource {
+        self.get_settings().line_numbering_source()
+    }
+
+    /// Get the indices of the auxiliary traces (see [`crate::SegyFile::aux_trace_indices`]).
+    pub fn aux_trace_indices(&self) -> &[usize] {
+        &self.aux_traces
+    }
+
+    /// Get the trace with a given index in the same way as [`crate::SegyFile::load_trace`].
+    ///
+    /// NB: If the trace headers are stored compactly, the trace header is read from the file.
+    /// This is a short positional read, but it blocks until it is done.
+    pub fn load_trace(&self, i: usize) -> Result<Cow<'_, Trace>, RsgError> {
+        let settings = self.get_settings();
+        if settings.get_dedup_headers() {
+            return self
+                .dedup_traces
+                .get(i)
+                .map(Cow::Owned)
+                .ok_or(RsgError::TraceNotFound { i });
+        }
+        if !settings.get_compact_headers() {
+            return self
+                .traces
+                .get(i)
+                .map(Cow::Borrowed)
+                .ok_or(RsgError::TraceNotFound { i });
+        }
+        let trace = self
+            .compact_traces
+            .get(i)
+            .ok_or(RsgError::TraceNotFound { i })?;
+        let range = trace.header_range(self.get_bin_header(), settings);
+        let bytes = read_exact_at(&self.file, range.start, range.len())?;
+        let full = trace.load(&bytes, i, self.get_bin_header(), settings)?;
+        Ok(Cow::Owned(full))
+    }
+
+    /// Get a certain trace by the order in which it appears in the SEG-Y file (see
+    /// [`Self::load_trace`]). An out of bounds index returns a `None`.
+    pub fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        self.load_trace(i).ok()
+    }
+
+    /// Get a certain trace by inline and crossline number using the lookup.
+    pub fn get_trace_by_xline_inline(&self, xline: i32, inline: i32) -> Option<Cow<'_, Trace>> {
+        let index = self.lookup.get(&LineKey::new(inline, xline))?;
+        self.get_trace(*index)
+    }
+
+    /// Iterate through the traces.
+    pub fn traces_iter(&self) -> TracesIter<'_, Self> {
+        SegyHandle::traces_iter(self)
+    }
+
+    /// Gets the trace data for a trace with a given index as a [`Vec<f32>`]. This is the
+    /// asynchronous equivalent of [`crate::SegyFile::get_trace_data_as_f32`].
+    pub async fn get_trace_data_as_f32(&self, i: usize) -> Result<Vec<f32>, RsgError> {
+        let trace = self.load_trace(i)?;
+        self.get_trace_data_as_f32_from_trace(&trace).await
+    }
+
+    /// Retrives the trace data for a given [`Trace`] from the same [`AsyncSegyFile`] as
+    /// a [`Vec<f32>`].
+    pub async fn get_trace_data_as_f32_from_trace(&self, t: &Trace) -> Result<Vec<f32>, RsgError> {
+        let raw_data = self.read_trace_data(t).await?;
+        decode_trace_data_as_f32(
+            &raw_data,
+            t.get_header(),
+            self.get_bin_header(),
+            self.get_settings(),
+        )
+    }
+
+    /// Gets the trace data for a trace with a given index converted to any primitive number
+    /// type. This is the asynchronous equivalent of [`crate::SegyFile::get_trace_data_as`].
+    pub async fn get_trace_data_as<T: num::FromPrimitive>(
+        &self,
+        i: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<T>, RsgError> {
+        let trace = self.load_trace(i)?;
+        let raw_data = self.read_trace_data(&trace).await?;
+        decode_trace_data_converted(
+            &raw_data,
+            trace.get_header(),
+            self.get_bin_header(),
+            self.get_settings(),
+            scale,
+        )
+    }
+
+    /// Retrives the unprocessed bytes of the trace data for a given [`Trace`]. As with
+    /// [`crate::SegyFile::get_trace_data_as_bytes_from_trace`], only the bytes of every
+    /// `step_by`-th sample are returned (see [`SegySettings::set_step_by`]).
+    pub async fn get_trace_data_as_bytes_from_trace(&self, t: &Trace) -> Result<Vec<u8>, RsgError> {
+        let raw_data = self.read_trace_data(t).await?;
+        let settings = self.get_settings();
+        decimate_trace_bytes(&raw_data, self.get_bin_header(), settings)
+    }
+
+    /// Read all bytes of the data of a trace from the file.
+    async fn read_trace_data(&self, t: &Trace) -> Result<Vec<u8>, RsgError> {
+        let len = t.len();
+        let start = t.get_start();
+
+        // Sanity check.
+        if self.file_len < len + start {
+            return Err(RsgError::ShortSEGY {
+                a: self.file_len,
+                b: len + start,
+            });
+        }
+
+        read_at(&self.file, start, len).await
+    }
+
+    /// Get a stream which yields each trace in the order of appearance in the file, together
+    /// with its data as a [`Vec<f32>`]. The data of each trace is only read when the stream
+    /// is polled.
+    pub fn traces_stream(
+        &self,
+    ) -> impl Stream<Item = Result<(Cow<'_, Trace>, Vec<f32>), RsgError>> + '_ {
+        stream::unfold(0, move |i| async move {
+            if i >= self.trace_count() {
+                return None;
+            }
+            let data = match self.load_trace(i) {
+                Ok(trace) => self
+                    .get_trace_data_as_f32_from_trace(&trace)
+                    .await
+                    .map(|data| (trace, data)),
+                Err(e) => Err(e),
+            };
+            Some((data, i + 1))
+        })
+    }
+}
+
+impl SegyHandle for AsyncSegyFile {
+    type Settings = SegySettings;
+
+    fn get_metadata(&self) -> &SegyMetadata<SegySettings> {
+        &self.metadata
+    }
+
+    fn trace_count(&self) -> usize {
+        AsyncSegyFile::trace_count(self)
+    }
+
+    fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        AsyncSegyFile::get_trace(self, i)
+    }
+}
+
+/// Read `len` bytes from the file, starting at the byte `start`, on a thread on which blocking
+/// is allowed.
+async fn read_at(file: &Arc<File>, start: usize, len: usize) -> Result<Vec<u8>, RsgError> {
+    let file = Arc::clone(file);
+    match tokio::task::spawn_blocking(move || read_exact_at(&file, start, len)).await {
+        Ok(read) => read,
+        // A blocking task is never cancelled, so it can only fail if the read panicked.
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Read `len` bytes from the file, starting at the byte `start`, without moving the cursor of
+/// the file, so that several reads can be done at once.
+fn read_exact_at(file: &File, start: usize, len: usize) -> Result<Vec<u8>, RsgError> {
+    let mut buf = vec![0; len];
+    #[cfg(unix)]
+    std::os::unix::fs::FileExt::read_exact_at(file, &mut buf, start as u64)?;
+    #[cfg(windows)]
+    {
+        use std::io::ErrorKind;
+        use std::os::windows::fs::FileExt;
+
+        let mut done = 0;
+        while done < len {
+            match file.seek_read(&mut buf[done..], (start + done) as u64) {
+                Ok(0) => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+                Ok(n) => done += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    Ok(buf)
+}
@@ -0,0 +1,100 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the compact storage of the traces of a file, which is used instead
+//! of a full [`Trace`] for each trace if [`SegySettings::set_compact_headers`] is set.
+//!
+//! [`SegySettings::set_compact_headers`]: giga_segy_core::SegySettings::set_compact_headers
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, Trace, TraceHeader, TRACE_HEADER_LEN};
+
+use crate::convert_headers::{keep_original_line_numbers, parse_trace_header};
+
+/// The values of a trace header which are kept for every trace, even if the trace headers are
+/// stored compactly. These are enough to look traces up by their line numbers or position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceKey {
+    /// The inline number of the trace.
+    pub inline_no: i32,
+    /// The crossline number of the trace.
+    pub crossline_no: i32,
+    /// The X coordinate of the ensemble (CDP) position of the trace.
+    pub x_ensemble: i32,
+    /// The Y coordinate of the ensemble (CDP) position of the trace.
+    pub y_ensemble: i32,
+    /// The scalar of the coordinates (after the override of the settings, if any).
+    pub coordinate_scalar: i16,
+    /// The number of samples in the trace, as given in the trace header.
+    pub no_samples_in_trace: u16,
+}
+
+impl TraceKey {
+    /// Take the key values from a trace header.
+    pub fn from_header(header: &TraceHeader) -> Self {
+        TraceKey {
+            inline_no: header.inline_no,
+            crossline_no: header.crossline_no,
+            x_ensemble: header.x_ensemble,
+            y_ensemble: header.y_ensemble,
+            coordinate_scalar: header.coordinate_scalar,
+            no_samples_in_trace: header.no_samples_in_trace,
+        }
+    }
+}
+
+/// A trace without its trace header, which is parsed again from the file when it is needed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CompactTrace {
+    pub(crate) key: TraceKey,
+    /// The starting byte of the trace data.
+    pub(crate) start: usize,
+    /// The length of the trace data in bytes.
+    pub(crate) len: usize,
+}
+
+impl CompactTrace {
+    pub(crate) fn new(trace: &Trace) -> Self {
+        CompactTrace {
+            key: TraceKey::from_header(trace.get_header()),
+            start: trace.get_start(),
+            len: trace.len(),
+        }
+    }
+    /// Get the position of the trace header in the file, which comes before the trace header
+    /// extensions and the prefix (see [`SegySettings::set_trace_prefix_bytes`]).
+    pub(crate) fn header_range(
+        &self,
+        bin_header: &BinHeader,
+        settings: &SegySettings,
+    ) -> std::ops::Range<usize> {
+        let extension_count = bin_header.max_additional_trace_headers as usize;
+        let end =
+            self.start - settings.get_trace_prefix_bytes() - TRACE_HEADER_LEN * extension_count;
+        (end - TRACE_HEADER_LEN)..end
+    }
+
+    /// Put the trace with index `i` back together from the bytes of its trace header, which are
+    /// parsed in the same way as when the file was opened. The index may differ from the one
+    /// that was used then (eg. if the traces were sorted), so the inline and crossline numbers,
+    /// which may be derived from it, are taken from the key.
+    pub(crate) fn load(
+        &self,
+        bytes: &[u8],
+        i: usize,
+        bin_header: &BinHeader,
+        settings: &SegySettings,
+    ) -> Result<Trace, RsgError> {
+        let (mut header, _) = parse_trace_header(bytes, bin_header, settings, i)?;
+        header.adjust_sample_count(settings);
+        header.inline_no = self.key.inline_no;
+        header.crossline_no = self.key.crossline_no;
+        let trace = Trace::new_with_extensions(
+            header,
+            self.start,
+            self.len,
+            bin_header.max_additional_trace_headers as usize,
+        )
+        .with_prefix_len(settings.get_trace_prefix_bytes());
+        Ok(keep_original_line_numbers(
+            trace, bytes, bin_header, settings,
+        ))
+    }
+}
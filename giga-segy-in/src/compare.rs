@@ -0,0 +1,233 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows two SEG-Y files to be compared, header field by header field and
+//! sample by sample. This is mostly useful for checking the output of one SEG-Y writer
+//! against that of another.
+use giga_segy_core::errors::*;
+use giga_segy_core::layout::{bin_header_layout, trace_header_layout, BIN_HEADER_EXTRA_FIELDS};
+use giga_segy_core::{BinHeader, TraceHeader};
+
+use crate::SegyFile;
+
+/// The options for [`diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffOptions {
+    /// The largest absolute difference between two samples which is still considered equal.
+    /// The default is `0.0`.
+    pub tolerance: f32,
+    /// The names of the binary header fields which are not compared (eg. `"job_id"`), as in
+    /// [`bin_header_layout`] and [`BIN_HEADER_EXTRA_FIELDS`]. Other names are an error.
+    pub ignore_bin_header_fields: Vec<String>,
+    /// The names of the trace header fields which are not compared (eg. `"year_recorded"`), as
+    /// in [`trace_header_layout`]. Other names are an error.
+    pub ignore_trace_header_fields: Vec<String>,
+    /// The maximum number of traces for which header mismatches are recorded. The default is
+    /// 100.
+    pub max_trace_header_mismatches: usize,
+    /// Whether the trace data is compared. The default is `true`.
+    pub compare_data: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            tolerance: 0.0,
+            ignore_bin_header_fields: Vec::new(),
+            ignore_trace_header_fields: Vec::new(),
+            max_trace_header_mismatches: 100,
+            compare_data: true,
+        }
+    }
+}
+
+/// A header field which has a different value in the two files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The value of the field in the first file (in `Debug` form).
+    pub a: String,
+    /// The value of the field in the second file (in `Debug` form).
+    pub b: String,
+}
+
+/// The trace header fields which differ for a trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceHeaderMismatch {
+    /// The index of the trace.
+    pub trace: usize,
+    /// The fields which differ.
+    pub fields: Vec<FieldMismatch>,
+}
+
+/// The difference between the data of a trace in the two files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceDataMismatch {
+    /// The index of the trace.
+    pub trace: usize,
+    /// The number of samples in the first and second file.
+    pub sample_counts: [usize; 2],
+    /// The largest absolute difference between two samples (in the samples that both traces
+    /// have). If only one of the samples is NaN, the difference is infinite.
+    pub max_diff: f32,
+}
+
+/// The differences between two SEG-Y files. See [`diff`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SegyDiff {
+    /// The binary header fields which differ.
+    pub bin_header: Vec<FieldMismatch>,
+    /// The number of traces in each file, if it differs.
+    pub trace_count: Option<[usize; 2]>,
+    /// The traces for which the trace headers differ.
+    pub trace_headers: Vec<TraceHeaderMismatch>,
+    /// Whether there were more trace header mismatches than could be recorded.
+    pub trace_headers_truncated: bool,
+    /// The traces for which the data differs by more than the tolerance.
+    pub trace_data: Vec<TraceDataMismatch>,
+}
+
+impl SegyDiff {
+    /// Check whether no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.bin_header.is_empty()
+            && self.trace_count.is_none()
+            && self.trace_headers.is_empty()
+            && !self.trace_headers_truncated
+            && self.trace_data.is_empty()
+    }
+}
+
+/// Compare two SEG-Y files.
+///
+/// The binary headers are compared field by field, then the traces are compared in the order
+/// in which they appear in the files: first the trace headers (field by field) and then the
+/// data. If the files have a different number of traces, only the traces that both files
+/// have are compared.
+/// ```
+/// use std::env::var;
+/// use std::path::PathBuf;
+/// use giga_segy_in::SegyFile;
+/// use giga_segy_in::compare::{diff, DiffOptions};
+///
+/// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// root.pop();
+/// let name = root.join("testdata").join("DutchMiniHead.sgy");
+///
+/// let a = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+/// let b = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+/// assert!(diff(&a, &b, DiffOptions::default()).unwrap().is_empty());
+/// ```
+pub fn diff(a: &SegyFile, b: &SegyFile, opts: DiffOptions) -> Result<SegyDiff, RsgError> {
+    check_ignored("binary", bin_header_fields, &opts.ignore_bin_header_fields)?;
+    check_ignored(
+        "trace",
+        trace_header_fields,
+        &opts.ignore_trace_header_fields,
+    )?;
+
+    let mut result = SegyDiff {
+        bin_header: diff_fields(
+            bin_header_fields(),
+            a.get_bin_header(),
+            b.get_bin_header(),
+            BinHeader::format_field_by_name,
+            &opts.ignore_bin_header_fields,
+        ),
+        ..Default::default()
+    };
+    if a.trace_count() != b.trace_count() {
+        result.trace_count = Some([a.trace_count(), b.trace_count()]);
+    }
+
+    for i in 0..a.trace_count().min(b.trace_count()) {
+        let (ta, tb) = (a.load_trace(i)?, b.load_trace(i)?);
+        let fields = diff_fields(
+            trace_header_fields(),
+            ta.get_header(),
+            tb.get_header(),
+            TraceHeader::format_field_by_name,
+            &opts.ignore_trace_header_fields,
+        );
+        if !fields.is_empty() {
+            if result.trace_headers.len() < opts.max_trace_header_mismatches {
+                result
+                    .trace_headers
+                    .push(TraceHeaderMismatch { trace: i, fields });
+            } else {
+                result.trace_headers_truncated = true;
+            }
+        }
+
+        if opts.compare_data {
+            let da = a.get_trace_data_as_f32_from_trace(&ta)?;
+            let db = b.get_trace_data_as_f32_from_trace(&tb)?;
+            let max_diff = da
+                .iter()
+                .zip(db.iter())
+                .map(|(x, y)| match (x.is_nan(), y.is_nan()) {
+                    (true, true) => 0.0,
+                    (false, false) => (x - y).abs(),
+                    _ => f32::INFINITY,
+                })
+                .fold(0.0, f32::max);
+            if max_diff > opts.tolerance || da.len() != db.len() {
+                result.trace_data.push(TraceDataMismatch {
+                    trace: i,
+                    sample_counts: [da.len(), db.len()],
+                    max_diff,
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The names of the fields of the binary header, as in [`bin_header_layout`] and
+/// [`BIN_HEADER_EXTRA_FIELDS`].
+fn bin_header_fields() -> impl Iterator<Item = &'static str> {
+    bin_header_layout()
+        .iter()
+        .map(|f| f.name)
+        .chain(BIN_HEADER_EXTRA_FIELDS.iter().copied())
+}
+
+/// The names of the fields of the trace header, as in [`trace_header_layout`].
+fn trace_header_fields() -> impl Iterator<Item = &'static str> {
+    trace_header_layout().iter().map(|f| f.name)
+}
+
+/// Check that each field which is to be ignored is one of the `fields` of the header.
+fn check_ignored<I: Iterator<Item = &'static str>>(
+    header: &str,
+    fields: fn() -> I,
+    ignore: &[String],
+) -> Result<(), RsgError> {
+    match ignore.iter().find(|name| !fields().any(|f| f == *name)) {
+        Some(name) => {
+            let msg = format!("The {} header has no field {:?} to ignore.", header, name);
+            Err(RsgError::SEGYSettingsError { msg })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Compares the given fields of two headers, using `format` to get the value of a field, and
+/// returns the names and values of those that differ (and are not ignored).
+fn diff_fields<H, I>(
+    fields: I,
+    a: &H,
+    b: &H,
+    format: fn(&H, &str) -> Option<String>,
+    ignore: &[String],
+) -> Vec<FieldMismatch>
+where
+    I: Iterator<Item = &'static str>,
+{
+    fields
+        .filter(|f| !ignore.iter().any(|i| i == f))
+        .filter_map(|field| match (format(a, field), format(b, field)) {
+            (Some(a), Some(b)) if a != b => Some(FieldMismatch { field, a, b }),
+            _ => None,
+        })
+        .collect()
+}
@@ -5,11 +5,12 @@ use giga_segy_core::bitconverter::{converter_chooser, BitConverter};
 use giga_segy_core::enums::*;
 use giga_segy_core::errors::*;
 use giga_segy_core::SegySettings;
-use giga_segy_core::{BinHeader, TapeLabel, TraceHeader};
+use giga_segy_core::{BinHeader, TapeLabel, Trace, TraceHeader};
 
 use num::FromPrimitive;
 
 use std::convert::TryInto;
+use std::ops::Range;
 
 /// This trait allows a header to be created from bytes, potentially using [`SegySettings`]
 /// to guide the creation process.
@@ -39,8 +40,15 @@ pub trait TraceHeaderFromBytes: Sized {
 impl HeaderFromBytes for TapeLabel {
     /// This is always ASCII bytes so we are not too concerned with translating them.
     fn from_bytes(bytes: &[u8], _settings: &SegySettings) -> Result<Self, RsgError> {
+        if bytes.len() != crate::TAPE_LABEL_LEN {
+            let msg = format!("Invalid TapeLabel: length is {} bytes", bytes.len());
+            return Err(RsgError::InvalidHeader { msg });
+        }
+        // Bytes 63-68 and 119-128 are reserved and are not kept.
+        // The number may be padded with spaces or zero bytes.
         let max_block_size: u32 = match String::from_utf8(bytes[19..29].to_vec())
             .map_err(RsgError::TryFromUtf8)?
+            .trim_matches(|c: char| c == ' ' || c == '\0')
             .parse()
         {
             Ok(n) => n,
@@ -48,22 +56,53 @@ impl HeaderFromBytes for TapeLabel {
         };
 
         let res = TapeLabel {
-            storage_unit_seq_no: bytes[0..4].try_into().unwrap(),
-            segy_revision_no: bytes[4..9].try_into().unwrap(),
-            storage_unit_structure: bytes[9..15].try_into().unwrap(),
-            binding_number: bytes[15..19].try_into().unwrap(),
+            storage_unit_seq_no: bytes[0..4].try_into()?,
+            segy_revision_no: bytes[4..9].try_into()?,
+            storage_unit_structure: bytes[9..15].try_into()?,
+            binding_number: bytes[15..19].try_into()?,
             max_block_size,
-            producing_organisation_code: bytes[29..39].try_into().unwrap(),
-            creation_date: bytes[39..50].try_into().unwrap(),
-            serial_number: bytes[50..62].try_into().unwrap(),
-            external_label: bytes[62..68].try_into().unwrap(),
-            recording_entity: bytes[80..104].try_into().unwrap(),
-            extra: bytes[104..118].try_into().unwrap(),
+            producing_organisation_code: bytes[29..39].try_into()?,
+            creation_date: bytes[39..50].try_into()?,
+            serial_number: bytes[50..62].try_into()?,
+            external_label: bytes[68..80].try_into()?,
+            recording_entity: bytes[80..104].try_into()?,
+            extra: bytes[104..118].try_into()?,
         };
         Ok(res)
     }
 }
 
+/// Guess whether a binary header is little endian. The flag in bytes 96-100 is used if it is
+/// set, otherwise the sample format code, sample interval and sample count are checked for
+/// plausibility in both byte orders. Big endian wins a tie, since it is the standard.
+pub(crate) fn guess_le(bytes: &[u8]) -> bool {
+    match bytes[96..100] {
+        [1, 2, 3, 4] => return true,
+        [4, 3, 2, 1] => return false,
+        _ => {}
+    }
+
+    let score = |from_bytes: fn([u8; 2]) -> u16| {
+        let sample_interval = from_bytes(bytes[16..18].try_into().unwrap());
+        let no_samples = from_bytes(bytes[20..22].try_into().unwrap());
+        let format = from_bytes(bytes[24..26].try_into().unwrap());
+
+        // The format code is the most reliable indicator, so it counts double.
+        let mut score = 0;
+        if SampleFormatCode::new(format).is_ok() {
+            score += 2;
+        }
+        if (1..=i16::MAX as u16).contains(&sample_interval) {
+            score += 1;
+        }
+        if (1..=i16::MAX as u16).contains(&no_samples) {
+            score += 1;
+        }
+        score
+    };
+    score(u16::from_le_bytes) > score(u16::from_be_bytes)
+}
+
 impl HeaderFromBytes for BinHeader {
     fn from_bytes(bytes: &[u8], settings: &SegySettings) -> Result<Self, RsgError> {
         // Binary header should be 400 bytes long. If it is not, we Houston has a problem.
@@ -75,7 +114,12 @@ impl HeaderFromBytes for BinHeader {
         let le = if let Some(le) = settings.get_override_to_le() {
             le
         } else {
-            bytes[96..100] == [1, 2, 3, 4]
+            match settings.get_endianness_detection() {
+                EndianDetection::TrustFlag => bytes[96..100] == [1, 2, 3, 4],
+                EndianDetection::Heuristic => guess_le(bytes),
+                EndianDetection::ForceLe => true,
+                EndianDetection::ForceBe => false,
+            }
         };
 
         // Use the `bonary_flag_direction` to determine how to interpret bytes.
@@ -97,11 +141,31 @@ impl HeaderFromBytes for BinHeader {
             u32::from_be_bytes
         };
 
-        // Set sample format code, using override if one is set.
+        let i32_from_bytes = if le {
+            i32::from_le_bytes
+        } else {
+            i32::from_be_bytes
+        };
+
+        let u64_from_bytes = if le {
+            u64::from_le_bytes
+        } else {
+            u64::from_be_bytes
+        };
+
+        // Set sample format code, using override if one is set. The code in the file is only
+        // parsed if there is no override, so that a corrupt code can be worked around. A code
+        // with a custom decoder stands in as `UInt8` if it is not a standard one.
+        let raw_sample_format_code = u16_from_bytes(bytes[24..26].try_into().unwrap());
         let sample_format_code = if let Some(code) = settings.get_override_trace_format() {
             code
+        } else if settings
+            .get_custom_decoder(raw_sample_format_code)
+            .is_some()
+        {
+            SampleFormatCode::new(raw_sample_format_code).unwrap_or(SampleFormatCode::UInt8)
         } else {
-            SampleFormatCode::new(u16_from_bytes(bytes[24..26].try_into().unwrap()))?
+            SampleFormatCode::new(raw_sample_format_code)?
         };
 
         let time_basis_code =
@@ -128,8 +192,13 @@ impl HeaderFromBytes for BinHeader {
         let correlated_traces =
             CorrelatedDataTraces::new(u16_from_bytes(bytes[48..50].try_into().unwrap()));
 
+        // If the sample format is overridden, the binary header is not trusted, so an invalid
+        // fixed length trace flag is treated as variable length. See `override_warnings`.
         let fixed_length_trace_flag =
-            FixedLengthTraces::new(u16_from_bytes(bytes[302..304].try_into().unwrap()))?;
+            match FixedLengthTraces::new(u16_from_bytes(bytes[302..304].try_into().unwrap())) {
+                Err(_) if settings.get_override_trace_format().is_some() => FixedLengthTraces::No,
+                flag => flag?,
+            };
 
         let header = BinHeader {
             job_id: u32_from_bytes(bytes[0..4].try_into().unwrap()),
@@ -142,6 +211,7 @@ impl HeaderFromBytes for BinHeader {
             no_samples: u16_from_bytes(bytes[20..22].try_into().unwrap()),
             no_samples_original: u16_from_bytes(bytes[22..24].try_into().unwrap()),
             sample_format_code,
+            raw_sample_format_code,
             ensemble_fold: u16_from_bytes(bytes[26..28].try_into().unwrap()),
             sorting_code: TraceSortingCode::new(i16_from_bytes(bytes[28..30].try_into().unwrap())),
             vertical_sum: u16_from_bytes(bytes[30..32].try_into().unwrap()),
@@ -159,10 +229,25 @@ impl HeaderFromBytes for BinHeader {
             measurement_system,
             impulse_signal_polarity,
             vibratory_polarity_code,
+            extended_no_traces: u32_from_bytes(bytes[60..64].try_into().unwrap()),
+            extended_no_aux_traces: u32_from_bytes(bytes[64..68].try_into().unwrap()),
+            extended_no_samples: u32_from_bytes(bytes[68..72].try_into().unwrap()),
+            extended_sample_interval: f64::from_bits(u64_from_bytes(
+                bytes[72..80].try_into().unwrap(),
+            )),
+            extended_sample_interval_original: f64::from_bits(u64_from_bytes(
+                bytes[80..88].try_into().unwrap(),
+            )),
+            extended_no_samples_original: u32_from_bytes(bytes[88..92].try_into().unwrap()),
+            extended_ensemble_fold: u32_from_bytes(bytes[92..96].try_into().unwrap()),
             segy_revision_number: [bytes[300], bytes[301]],
             fixed_length_trace_flag,
-            extended_header_count: u32_from_bytes(bytes[306..310].try_into().unwrap()),
+            extended_header_count: u16_from_bytes(bytes[304..306].try_into().unwrap()) as u32,
+            max_additional_trace_headers: u32_from_bytes(bytes[306..310].try_into().unwrap()),
             time_basis_code,
+            no_traces_in_file: u64_from_bytes(bytes[312..320].try_into().unwrap()),
+            first_trace_offset: u64_from_bytes(bytes[320..328].try_into().unwrap()),
+            no_trailer_stanzas: i32_from_bytes(bytes[328..332].try_into().unwrap()),
             binary_flag_direction_is_le: le,
         };
 
@@ -173,229 +258,371 @@ impl HeaderFromBytes for BinHeader {
 impl TraceHeaderFromBytes for TraceHeader {
     /// When making a `TraceHeader` we use the data from the `BinHeader` to determine whether or
     /// not
+    ///
+    /// If a coordinate cannot be converted to an integer, this depends on
+    /// [`SegySettings::get_on_coordinate_parse_error`]: Either an error is returned, or the
+    /// coordinate is set to zero. Since a single header cannot be skipped, an error is also
+    /// returned for [`CoordErrorPolicy::SkipTrace`].
     fn from_bytes(
         bytes: &[u8],
         bin_header: &BinHeader,
         settings: &SegySettings,
         idx: usize,
     ) -> Result<Self, RsgError> {
-        // Binary header should be 400 bytes long. If it is not, we Houston has a problem.
-        if bytes.len() != crate::TRACE_HEADER_LEN {
-            return Err(RsgError::TraceHeaderLength { l: bytes.len() });
+        let (header, bad_coordinates) = parse_trace_header(bytes, bin_header, settings, idx)?;
+        match bad_coordinates.into_iter().next() {
+            Some(e) if settings.get_on_coordinate_parse_error() == CoordErrorPolicy::SkipTrace => {
+                Err(e)
+            }
+            _ => Ok(header),
         }
+    }
+}
 
-        let use_le = bin_header.binary_flag_direction_is_le;
-
-        let inline_no_rng = settings.get_inline_no_bidx()..(4 + settings.get_inline_no_bidx());
-        let xline_no_rng = settings.get_crossline_no_bidx()..(4 + settings.get_crossline_no_bidx());
-        let x_ensemble_rng = settings.get_x_ensemble_bidx()..(4 + settings.get_x_ensemble_bidx());
-        let y_ensemble_rng = settings.get_y_ensemble_bidx()..(4 + settings.get_y_ensemble_bidx());
-
-        let u16_from_bytes = if use_le {
-            u16::from_le_bytes
-        } else {
-            u16::from_be_bytes
-        };
-
-        let i16_from_bytes = if use_le {
-            i16::from_le_bytes
-        } else {
-            i16::from_be_bytes
-        };
-
-        let trace_name = if use_le {
-            bytes[232..240].try_into().unwrap()
-        } else {
-            bytes[232..240].iter().cloned().rev().collect::<Vec<_>>()[..]
-                .try_into()
-                .unwrap()
-        };
-
-        let i32_from_bytes = if use_le {
-            i32::from_le_bytes
-        } else {
-            i32::from_be_bytes
-        };
-
-        // A little bit convoluted because of types.
-        let coordinate_format =
-            if let Some(coord_override) = settings.get_override_coordinate_format() {
-                coord_override
-            } else {
-                // Default coordinate format is Int32.
-                SampleFormatCode::Int32
-            };
-
-        let coordinate_parser: BitConverter = converter_chooser(coordinate_format, use_le)?;
-
-        let coord_parser = |x: [u8; 4]| {
-            let float: f32 = coordinate_parser(&x)?;
-            i32::from_f32(float).ok_or(RsgError::FloatConversion {
-                float,
-                format: coordinate_format,
-            })
-        };
-
-        // Make coordinate scalar, using override if one is set.
-        let coordinate_scalar = if let Some(scaling) = settings.get_override_coordinate_scaling() {
-            scaling as i16 // This is valid because `set_override_coordinate_scaling` is checked.
-        } else {
-            i16_from_bytes(bytes[70..72].try_into().unwrap())
-        };
+/// Read the inline and crossline numbers of a trace header of 240 bytes at the byte indices
+/// of the settings, even if they are synthesized when the header is parsed.
+fn header_line_numbers(bytes: &[u8], bin_header: &BinHeader, settings: &SegySettings) -> [i32; 2] {
+    let i32_from_bytes = if bin_header.binary_flag_direction_is_le {
+        i32::from_le_bytes
+    } else {
+        i32::from_be_bytes
+    };
+    [
+        settings.get_inline_no_bidx(),
+        settings.get_crossline_no_bidx(),
+    ]
+    .map(|bidx| i32_from_bytes(bytes[bidx..(bidx + 4)].try_into().unwrap()))
+}
 
-        let source_measurement_unit =
-            SourceMeasurementUnit::new(i16_from_bytes(bytes[230..232].try_into().unwrap()));
-        let source_type = SourceType::new(i16_from_bytes(bytes[216..218].try_into().unwrap()));
-        let trace_value_measurement_unit =
-            TraceValueUnit::new(i16_from_bytes(bytes[202..204].try_into().unwrap()));
-        let transduction_units =
-            TransductionUnits::new(i16_from_bytes(bytes[210..212].try_into().unwrap()));
-        let over_travel = OverTravel::new(u16_from_bytes(bytes[178..180].try_into().unwrap()));
-        let time_base_code =
-            TimeBasisCode::new(u16_from_bytes(bytes[166..168].try_into().unwrap()));
-        let taper_type = TaperType::new(u16_from_bytes(bytes[138..140].try_into().unwrap()));
-        let sweep_type = SweepType::new(u16_from_bytes(bytes[132..134].try_into().unwrap()));
-        let correlated = Correlated::new(u16_from_bytes(bytes[124..126].try_into().unwrap()));
-        let gain_type = GainType::new(u16_from_bytes(bytes[118..120].try_into().unwrap()));
-        let coordinate_units =
-            CoordinateUnits::new(u16_from_bytes(bytes[88..90].try_into().unwrap()));
-        let data_use = DataUse::new(u16_from_bytes(bytes[34..36].try_into().unwrap()));
-        let trace_identification_code = if let Some(id) = settings.get_override_trace_id_code() {
-            id
-        } else {
-            TraceIdCode::new(i16_from_bytes(bytes[28..30].try_into().unwrap()))
-        };
+/// Record the inline and crossline numbers of the trace header `bytes` with a trace if its
+/// line numbers are synthesized (see [`Trace::with_original_line_numbers`]).
+pub(crate) fn keep_original_line_numbers(
+    trace: Trace,
+    bytes: &[u8],
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> Trace {
+    if settings.get_override_dim_x().is_none() {
+        return trace;
+    }
+    let [inline_no, crossline_no] = header_line_numbers(bytes, bin_header, settings);
+    trace.with_original_line_numbers(inline_no, crossline_no)
+}
 
-        let trace_sequence_on_line = i32_from_bytes(bytes[0..4].try_into().unwrap());
-        let trace_sequence_in_file = i32_from_bytes(bytes[4..8].try_into().unwrap());
-        let field_record_no = i32_from_bytes(bytes[8..12].try_into().unwrap());
-        let trace_no = i32_from_bytes(bytes[12..16].try_into().unwrap());
-        let trace_no_in_ensemble = i32_from_bytes(bytes[24..28].try_into().unwrap());
-
-        let idx = match settings.get_order_trace_by() {
-            OrderTraceBy::Default => idx,
-            OrderTraceBy::TraceSequenceOnLine => trace_sequence_on_line as usize,
-            OrderTraceBy::TraceSequenceInFile => trace_sequence_in_file as usize,
-            OrderTraceBy::FieldRecordNo => field_record_no as usize,
-            OrderTraceBy::TraceNo => trace_no as usize,
-            OrderTraceBy::TraceNoInEnsemble => trace_no_in_ensemble as usize,
-        };
+/// Check the settings which the parsing of a trace header relies on, since settings which were
+/// not made with the setters (eg. deserialized ones) may not have been validated (see
+/// [`SegySettings::validate`]).
+fn check_trace_header_settings(settings: &SegySettings) -> Result<(), RsgError> {
+    let bidxs = [
+        settings.get_inline_no_bidx(),
+        settings.get_crossline_no_bidx(),
+        settings.get_x_ensemble_bidx(),
+        settings.get_y_ensemble_bidx(),
+    ];
+    if let Some(bidx) = bidxs.iter().find(|b| **b > crate::TRACE_HEADER_LEN - 4) {
+        let msg = format!(
+            "The byte index {} is past the end of the trace header.",
+            bidx
+        );
+        return Err(RsgError::SEGYSettingsError { msg });
+    }
+    if matches!(settings.get_override_dim_x(), Some(x) if x <= 0) {
+        let msg = "The custom crossline count must be larger than zero.".to_string();
+        return Err(RsgError::SEGYSettingsError { msg });
+    }
+    Ok(())
+}
 
-        // If dimensions are customised, set them manually, otherwise, read them from the header.
-        // NB, setting customised x on its own doesn't make much sense and can lead to strange
-        // results. However, sometimes it may be required.
-        let (inline_no, crossline_no) =
-            match (settings.get_override_dim_x(), settings.get_override_dim_y()) {
-                (Some(x), _) => {
-                    let i_no = (idx / x as usize) as i32;
-                    let x_no = (idx % x as usize) as i32;
-                    (i_no, x_no)
-                }
-                (None, _) => {
-                    let i_no = i32_from_bytes(bytes[inline_no_rng].try_into().unwrap());
-                    let x_no = i32_from_bytes(bytes[xline_no_rng].try_into().unwrap());
-                    (i_no, x_no)
+/// Parse a trace header, as in [`TraceHeaderFromBytes::from_bytes`].
+///
+/// Unless the settings ask for this to fail, coordinates which cannot be converted to integers
+/// are set to zero, and an [`RsgError::CoordinateParse`] for each of them is returned along with
+/// the header, so that the caller can decide what to do with the trace.
+pub(crate) fn parse_trace_header(
+    bytes: &[u8],
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    idx: usize,
+) -> Result<(TraceHeader, Vec<RsgError>), RsgError> {
+    // Binary header should be 400 bytes long. If it is not, we Houston has a problem.
+    if bytes.len() != crate::TRACE_HEADER_LEN {
+        return Err(RsgError::TraceHeaderLength { l: bytes.len() });
+    }
+    check_trace_header_settings(settings)?;
+
+    let use_le = bin_header.binary_flag_direction_is_le;
+
+    let x_ensemble_rng = settings.get_x_ensemble_bidx()..(4 + settings.get_x_ensemble_bidx());
+    let y_ensemble_rng = settings.get_y_ensemble_bidx()..(4 + settings.get_y_ensemble_bidx());
+
+    let u16_from_bytes = if use_le {
+        u16::from_le_bytes
+    } else {
+        u16::from_be_bytes
+    };
+
+    let i16_from_bytes = if use_le {
+        i16::from_le_bytes
+    } else {
+        i16::from_be_bytes
+    };
+
+    // The trace name is a string of characters, so it is read in the order of the file
+    // whatever the byte order of the numbers.
+    let trace_name = bytes[232..240].try_into().unwrap();
+
+    let i32_from_bytes = if use_le {
+        i32::from_le_bytes
+    } else {
+        i32::from_be_bytes
+    };
+
+    // A little bit convoluted because of types.
+    let coordinate_format = if let Some(coord_override) = settings.get_override_coordinate_format()
+    {
+        coord_override
+    } else {
+        // Default coordinate format is Int32.
+        SampleFormatCode::Int32
+    };
+
+    let coordinate_parser: BitConverter = converter_chooser(coordinate_format, use_le)?;
+
+    let policy = settings.get_on_coordinate_parse_error();
+    let mut bad_coordinates = Vec::new();
+    let mut coord_parser = |range: Range<usize>| {
+        let raw: [u8; 4] = bytes[range.clone()].try_into().unwrap();
+        let float: f32 = coordinate_parser(&raw)?;
+        match i32::from_f32(float) {
+            Some(coordinate) => Ok(coordinate),
+            None => {
+                let e = RsgError::CoordinateParse {
+                    trace: idx,
+                    offset: range.start,
+                    bytes: raw,
+                };
+                if policy == CoordErrorPolicy::Fail {
+                    return Err(e);
                 }
-            };
-
-        let traceheader = TraceHeader {
-            trace_sequence_on_line,
-            trace_sequence_in_file,
-            field_record_no,
-            trace_no,
-            energy_source_point_no: i32_from_bytes(bytes[16..20].try_into().unwrap()),
-            ensemble_no: i32_from_bytes(bytes[20..24].try_into().unwrap()),
-            trace_no_in_ensemble,
-            trace_identification_code,
-            no_v_summed_traces: u16_from_bytes(bytes[30..32].try_into().unwrap()),
-            no_h_stacked_traces: u16_from_bytes(bytes[32..34].try_into().unwrap()),
-            data_use,
-            source_to_receiver_distance: coord_parser(bytes[36..40].try_into().unwrap())?,
-            elevation_of_receiver_group: coord_parser(bytes[40..44].try_into().unwrap())?,
-            surface_elevation_of_source: coord_parser(bytes[44..48].try_into().unwrap())?,
-            source_depth: coord_parser(bytes[48..52].try_into().unwrap())?,
-            datum_elevation_of_receiver_group: coord_parser(bytes[52..56].try_into().unwrap())?,
-            datum_elevation_of_source: coord_parser(bytes[56..60].try_into().unwrap())?,
-            water_column_height_at_source: coord_parser(bytes[60..64].try_into().unwrap())?,
-            water_column_height_at_group: coord_parser(bytes[64..68].try_into().unwrap())?,
-            elevation_scalar: i16_from_bytes(bytes[68..70].try_into().unwrap()),
-            coordinate_scalar,
-            source_x: coord_parser(bytes[72..76].try_into().unwrap())?,
-            source_y: coord_parser(bytes[76..80].try_into().unwrap())?,
-            receiver_group_x: coord_parser(bytes[80..84].try_into().unwrap())?,
-            receiver_group_y: coord_parser(bytes[84..88].try_into().unwrap())?,
-            coordinate_units,
-            weathing_velocity: u16_from_bytes(bytes[90..92].try_into().unwrap()),
-            sub_weathering_velocity: u16_from_bytes(bytes[92..94].try_into().unwrap()),
-            uphole_time_at_source: u16_from_bytes(bytes[94..96].try_into().unwrap()),
-            uphole_time_at_group: u16_from_bytes(bytes[96..98].try_into().unwrap()),
-            source_static_correction: u16_from_bytes(bytes[98..100].try_into().unwrap()),
-            group_static_correction: u16_from_bytes(bytes[100..102].try_into().unwrap()),
-            total_static_applied: u16_from_bytes(bytes[102..104].try_into().unwrap()),
-            lag_time_a: u16_from_bytes(bytes[104..106].try_into().unwrap()),
-            lag_time_b: u16_from_bytes(bytes[106..108].try_into().unwrap()),
-            delay_recording_time: u16_from_bytes(bytes[108..110].try_into().unwrap()),
-            mute_time_start: u16_from_bytes(bytes[110..112].try_into().unwrap()),
-            mute_time_end: u16_from_bytes(bytes[112..114].try_into().unwrap()),
-            no_samples_in_trace: u16_from_bytes(bytes[114..116].try_into().unwrap()),
-            sample_interval_of_trace: u16_from_bytes(bytes[116..118].try_into().unwrap()),
-            gain_type,
-            instrument_gain_constant: u16_from_bytes(bytes[120..122].try_into().unwrap()),
-            instrument_initial_gain: u16_from_bytes(bytes[122..124].try_into().unwrap()),
-            correlated,
-            sweep_frequency_at_start: u16_from_bytes(bytes[126..128].try_into().unwrap()),
-            sweep_frequency_at_end: u16_from_bytes(bytes[128..130].try_into().unwrap()),
-            sweep_length: u16_from_bytes(bytes[130..132].try_into().unwrap()),
-            sweep_type,
-            sweep_trace_taper_length_at_start: u16_from_bytes(bytes[134..136].try_into().unwrap()),
-            sweep_trace_taper_length_at_end: u16_from_bytes(bytes[136..138].try_into().unwrap()),
-            taper_type,
-            alias_filter_frequency: u16_from_bytes(bytes[140..142].try_into().unwrap()),
-            alias_filter_slope: u16_from_bytes(bytes[142..144].try_into().unwrap()),
-            notch_filter_frequency: u16_from_bytes(bytes[144..146].try_into().unwrap()),
-            notch_filter_slope: u16_from_bytes(bytes[146..148].try_into().unwrap()),
-            low_cut_frequency: u16_from_bytes(bytes[148..150].try_into().unwrap()),
-            high_cut_frequency: u16_from_bytes(bytes[150..152].try_into().unwrap()),
-            low_cut_slope: u16_from_bytes(bytes[152..154].try_into().unwrap()),
-            high_cut_slope: u16_from_bytes(bytes[154..156].try_into().unwrap()),
-            year_recorded: u16_from_bytes(bytes[156..158].try_into().unwrap()),
-            day_of_year: u16_from_bytes(bytes[158..160].try_into().unwrap()),
-            hour_of_day: u16_from_bytes(bytes[160..162].try_into().unwrap()),
-            minute_of_hour: u16_from_bytes(bytes[162..164].try_into().unwrap()),
-            second_of_minute: u16_from_bytes(bytes[164..166].try_into().unwrap()),
-            time_base_code,
-            trace_weighting_factor: u16_from_bytes(bytes[168..170].try_into().unwrap()),
-            geophone_group_number_roll_pos1: u16_from_bytes(bytes[170..172].try_into().unwrap()),
-            geophone_group_number_first_trace_orig_field: u16_from_bytes(
-                bytes[172..174].try_into().unwrap(),
-            ),
-            geophone_group_number_last_trace_orig_field: u16_from_bytes(
-                bytes[174..176].try_into().unwrap(),
-            ),
-            gap_size: u16_from_bytes(bytes[176..178].try_into().unwrap()),
-            over_travel,
-            x_ensemble: coord_parser(bytes[x_ensemble_rng].try_into().unwrap())?,
-            y_ensemble: coord_parser(bytes[y_ensemble_rng].try_into().unwrap())?,
-            inline_no,
-            crossline_no,
-            shot_point_no: i32_from_bytes(bytes[196..200].try_into().unwrap()),
-            shot_point_scalar: u16_from_bytes(bytes[200..202].try_into().unwrap()),
-            trace_value_measurement_unit,
-            transduction_constant_mantissa: i32_from_bytes(bytes[204..208].try_into().unwrap()),
-            transduction_constant_power: u16_from_bytes(bytes[208..210].try_into().unwrap()),
-            transduction_units,
-            trace_identifier: u16_from_bytes(bytes[212..214].try_into().unwrap()),
-            time_scalar_trace_header: u16_from_bytes(bytes[214..216].try_into().unwrap()),
-            source_type,
-            source_energy_direction_v: u16_from_bytes(bytes[218..220].try_into().unwrap()),
-            source_energy_direction_il: u16_from_bytes(bytes[220..222].try_into().unwrap()),
-            source_energy_direction_xl: u16_from_bytes(bytes[222..224].try_into().unwrap()),
-            source_measurement_mantissa: i32_from_bytes(bytes[224..228].try_into().unwrap()),
-            source_measurement_exponent: u16_from_bytes(bytes[228..230].try_into().unwrap()),
-            source_measurement_unit,
-            trace_name,
+                bad_coordinates.push(e);
+                Ok(0)
+            }
+        }
+    };
+
+    // Make coordinate scalar, using override if one is set.
+    let coordinate_scalar = if let Some(scaling) = settings.get_override_coordinate_scaling() {
+        scaling as i16 // This is valid because `set_override_coordinate_scaling` is checked.
+    } else {
+        i16_from_bytes(bytes[70..72].try_into().unwrap())
+    };
+
+    let source_measurement_unit =
+        SourceMeasurementUnit::new(i16_from_bytes(bytes[230..232].try_into().unwrap()));
+    let source_type = SourceType::new(i16_from_bytes(bytes[216..218].try_into().unwrap()));
+    let trace_value_measurement_unit =
+        TraceValueUnit::new(i16_from_bytes(bytes[202..204].try_into().unwrap()));
+    let transduction_units =
+        TransductionUnits::new(i16_from_bytes(bytes[210..212].try_into().unwrap()));
+    let over_travel = OverTravel::new(u16_from_bytes(bytes[178..180].try_into().unwrap()));
+    let time_base_code = TimeBasisCode::new(u16_from_bytes(bytes[166..168].try_into().unwrap()));
+    let taper_type = TaperType::new(u16_from_bytes(bytes[138..140].try_into().unwrap()));
+    let sweep_type = SweepType::new(u16_from_bytes(bytes[132..134].try_into().unwrap()));
+    let correlated = Correlated::new(u16_from_bytes(bytes[124..126].try_into().unwrap()));
+    let gain_type = GainType::new(u16_from_bytes(bytes[118..120].try_into().unwrap()));
+    let coordinate_units = CoordinateUnits::new(u16_from_bytes(bytes[88..90].try_into().unwrap()));
+    let data_use = DataUse::new(u16_from_bytes(bytes[34..36].try_into().unwrap()));
+    let trace_identification_code = if let Some(id) = settings.get_override_trace_id_code() {
+        id
+    } else {
+        TraceIdCode::new(i16_from_bytes(bytes[28..30].try_into().unwrap()))
+    };
+
+    let trace_sequence_on_line = i32_from_bytes(bytes[0..4].try_into().unwrap());
+    let trace_sequence_in_file = i32_from_bytes(bytes[4..8].try_into().unwrap());
+    let field_record_no = i32_from_bytes(bytes[8..12].try_into().unwrap());
+    let trace_no = i32_from_bytes(bytes[12..16].try_into().unwrap());
+    let trace_no_in_ensemble = i32_from_bytes(bytes[24..28].try_into().unwrap());
+
+    let idx = match settings.get_order_trace_by() {
+        OrderTraceBy::Default => idx,
+        OrderTraceBy::TraceSequenceOnLine => trace_sequence_on_line as usize,
+        OrderTraceBy::TraceSequenceInFile => trace_sequence_in_file as usize,
+        OrderTraceBy::FieldRecordNo => field_record_no as usize,
+        OrderTraceBy::TraceNo => trace_no as usize,
+        OrderTraceBy::TraceNoInEnsemble => trace_no_in_ensemble as usize,
+    };
+
+    // If dimensions are customised, set them manually, otherwise, read them from the header.
+    // NB, setting customised x on its own doesn't make much sense and can lead to strange
+    // results. However, sometimes it may be required.
+    let (inline_no, crossline_no) =
+        match (settings.get_override_dim_x(), settings.get_override_dim_y()) {
+            (Some(x), _) => {
+                let i_no = (idx / x as usize) as i32;
+                let x_no = (idx % x as usize) as i32;
+                (i_no, x_no)
+            }
+            (None, _) => {
+                let [i_no, x_no] = header_line_numbers(bytes, bin_header, settings);
+                (i_no, x_no)
+            }
         };
 
-        Ok(traceheader)
+    let mut traceheader = TraceHeader {
+        trace_sequence_on_line,
+        trace_sequence_in_file,
+        field_record_no,
+        trace_no,
+        energy_source_point_no: i32_from_bytes(bytes[16..20].try_into().unwrap()),
+        ensemble_no: i32_from_bytes(bytes[20..24].try_into().unwrap()),
+        trace_no_in_ensemble,
+        trace_identification_code,
+        no_v_summed_traces: u16_from_bytes(bytes[30..32].try_into().unwrap()),
+        no_h_stacked_traces: u16_from_bytes(bytes[32..34].try_into().unwrap()),
+        data_use,
+        source_to_receiver_distance: coord_parser(36..40)?,
+        elevation_of_receiver_group: coord_parser(40..44)?,
+        surface_elevation_of_source: coord_parser(44..48)?,
+        source_depth: coord_parser(48..52)?,
+        datum_elevation_of_receiver_group: coord_parser(52..56)?,
+        datum_elevation_of_source: coord_parser(56..60)?,
+        water_column_height_at_source: coord_parser(60..64)?,
+        water_column_height_at_group: coord_parser(64..68)?,
+        elevation_scalar: i16_from_bytes(bytes[68..70].try_into().unwrap()),
+        coordinate_scalar,
+        source_x: coord_parser(72..76)?,
+        source_y: coord_parser(76..80)?,
+        receiver_group_x: coord_parser(80..84)?,
+        receiver_group_y: coord_parser(84..88)?,
+        coordinate_units,
+        weathing_velocity: u16_from_bytes(bytes[90..92].try_into().unwrap()),
+        sub_weathering_velocity: u16_from_bytes(bytes[92..94].try_into().unwrap()),
+        uphole_time_at_source: u16_from_bytes(bytes[94..96].try_into().unwrap()),
+        uphole_time_at_group: u16_from_bytes(bytes[96..98].try_into().unwrap()),
+        source_static_correction: u16_from_bytes(bytes[98..100].try_into().unwrap()),
+        group_static_correction: u16_from_bytes(bytes[100..102].try_into().unwrap()),
+        total_static_applied: u16_from_bytes(bytes[102..104].try_into().unwrap()),
+        lag_time_a: u16_from_bytes(bytes[104..106].try_into().unwrap()),
+        lag_time_b: u16_from_bytes(bytes[106..108].try_into().unwrap()),
+        delay_recording_time: u16_from_bytes(bytes[108..110].try_into().unwrap()),
+        mute_time_start: u16_from_bytes(bytes[110..112].try_into().unwrap()),
+        mute_time_end: u16_from_bytes(bytes[112..114].try_into().unwrap()),
+        no_samples_in_trace: u16_from_bytes(bytes[114..116].try_into().unwrap()),
+        sample_interval_of_trace: u16_from_bytes(bytes[116..118].try_into().unwrap()),
+        gain_type,
+        instrument_gain_constant: u16_from_bytes(bytes[120..122].try_into().unwrap()),
+        instrument_initial_gain: u16_from_bytes(bytes[122..124].try_into().unwrap()),
+        correlated,
+        sweep_frequency_at_start: u16_from_bytes(bytes[126..128].try_into().unwrap()),
+        sweep_frequency_at_end: u16_from_bytes(bytes[128..130].try_into().unwrap()),
+        sweep_length: u16_from_bytes(bytes[130..132].try_into().unwrap()),
+        sweep_type,
+        sweep_trace_taper_length_at_start: u16_from_bytes(bytes[134..136].try_into().unwrap()),
+        sweep_trace_taper_length_at_end: u16_from_bytes(bytes[136..138].try_into().unwrap()),
+        taper_type,
+        alias_filter_frequency: u16_from_bytes(bytes[140..142].try_into().unwrap()),
+        alias_filter_slope: u16_from_bytes(bytes[142..144].try_into().unwrap()),
+        notch_filter_frequency: u16_from_bytes(bytes[144..146].try_into().unwrap()),
+        notch_filter_slope: u16_from_bytes(bytes[146..148].try_into().unwrap()),
+        low_cut_frequency: u16_from_bytes(bytes[148..150].try_into().unwrap()),
+        high_cut_frequency: u16_from_bytes(bytes[150..152].try_into().unwrap()),
+        low_cut_slope: u16_from_bytes(bytes[152..154].try_into().unwrap()),
+        high_cut_slope: u16_from_bytes(bytes[154..156].try_into().unwrap()),
+        year_recorded: u16_from_bytes(bytes[156..158].try_into().unwrap()),
+        day_of_year: u16_from_bytes(bytes[158..160].try_into().unwrap()),
+        hour_of_day: u16_from_bytes(bytes[160..162].try_into().unwrap()),
+        minute_of_hour: u16_from_bytes(bytes[162..164].try_into().unwrap()),
+        second_of_minute: u16_from_bytes(bytes[164..166].try_into().unwrap()),
+        time_base_code,
+        trace_weighting_factor: u16_from_bytes(bytes[168..170].try_into().unwrap()),
+        geophone_group_number_roll_pos1: u16_from_bytes(bytes[170..172].try_into().unwrap()),
+        geophone_group_number_first_trace_orig_field: u16_from_bytes(
+            bytes[172..174].try_into().unwrap(),
+        ),
+        geophone_group_number_last_trace_orig_field: u16_from_bytes(
+            bytes[174..176].try_into().unwrap(),
+        ),
+        gap_size: u16_from_bytes(bytes[176..178].try_into().unwrap()),
+        over_travel,
+        x_ensemble: coord_parser(x_ensemble_rng)?,
+        y_ensemble: coord_parser(y_ensemble_rng)?,
+        inline_no,
+        crossline_no,
+        shot_point_no: i32_from_bytes(bytes[196..200].try_into().unwrap()),
+        shot_point_scalar: i16_from_bytes(bytes[200..202].try_into().unwrap()),
+        trace_value_measurement_unit,
+        transduction_constant_mantissa: i32_from_bytes(bytes[204..208].try_into().unwrap()),
+        transduction_constant_power: u16_from_bytes(bytes[208..210].try_into().unwrap()),
+        transduction_units,
+        trace_identifier: u16_from_bytes(bytes[212..214].try_into().unwrap()),
+        time_scalar_trace_header: u16_from_bytes(bytes[214..216].try_into().unwrap()),
+        source_type,
+        source_energy_direction_v: u16_from_bytes(bytes[218..220].try_into().unwrap()),
+        source_energy_direction_il: u16_from_bytes(bytes[220..222].try_into().unwrap()),
+        source_energy_direction_xl: u16_from_bytes(bytes[222..224].try_into().unwrap()),
+        source_measurement_mantissa: i32_from_bytes(bytes[224..228].try_into().unwrap()),
+        source_measurement_exponent: u16_from_bytes(bytes[228..230].try_into().unwrap()),
+        source_measurement_unit,
+        trace_name,
+    };
+
+    if let Some(transform) = settings.get_coordinate_transform() {
+        let h = &mut traceheader;
+        let pairs = [
+            [
+                settings.get_x_ensemble_bidx(),
+                settings.get_y_ensemble_bidx(),
+            ],
+            [72, 76],
+            [80, 84],
+        ];
+        let mut coordinates = [
+            [h.x_ensemble, h.y_ensemble],
+            [h.source_x, h.source_y],
+            [h.receiver_group_x, h.receiver_group_y],
+        ];
+        for (offsets, pair) in pairs.iter().zip(coordinates.iter_mut()) {
+            // A coordinate which could not be parsed stays as it is.
+            let unparsed = |o: &usize| {
+                bad_coordinates
+                    .iter()
+                    .any(|e| matches!(e, RsgError::CoordinateParse { offset, .. } if offset == o))
+            };
+            if offsets.iter().any(unparsed) {
+                continue;
+            }
+            let (x, y) = transform.apply(
+                h.apply_coordinate_scalar(pair[0]),
+                h.apply_coordinate_scalar(pair[1]),
+            );
+            for ((c, value), offset) in pair.iter_mut().zip([x, y]).zip(offsets) {
+                *c = match h.remove_coordinate_scalar(value) {
+                    Some(c) => c,
+                    None => {
+                        let e = RsgError::CoordinateParse {
+                            trace: idx,
+                            offset: *offset,
+                            bytes: bytes[*offset..(offset + 4)].try_into().unwrap(),
+                        };
+                        if policy == CoordErrorPolicy::Fail {
+                            return Err(e);
+                        }
+                        bad_coordinates.push(e);
+                        0
+                    }
+                };
+            }
+        }
+        let [[x_ensemble, y_ensemble], [source_x, source_y], [receiver_group_x, receiver_group_y]] =
+            coordinates;
+        h.x_ensemble = x_ensemble;
+        h.y_ensemble = y_ensemble;
+        h.source_x = source_x;
+        h.source_y = source_y;
+        h.receiver_group_x = receiver_group_x;
+        h.receiver_group_y = receiver_group_y;
     }
+
+    Ok((traceheader, bad_coordinates))
 }
@@ -0,0 +1,204 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the deduplicated storage of the traces of a file, which is used
+//! instead of a full [`Trace`] for each trace if [`SegySettings::set_dedup_headers`] is set.
+//!
+//! Each trace header is split into the values which usually differ from trace to trace (see
+//! [`HotHeader`]), which are kept for every trace, and the rest, which is kept once for each
+//! distinct set of values.
+//!
+//! [`SegySettings::set_dedup_headers`]: giga_segy_core::SegySettings::set_dedup_headers
+use crate::compact::TraceKey;
+use giga_segy_core::{Trace, TraceHeader};
+
+use std::collections::HashMap;
+use std::mem::take;
+
+/// The values of a trace header which are kept for every trace when the trace headers are
+/// deduplicated: The sequence and line numbers and the coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HotHeader {
+    trace_sequence_on_line: i32,
+    trace_sequence_in_file: i32,
+    field_record_no: i32,
+    trace_no: i32,
+    energy_source_point_no: i32,
+    ensemble_no: i32,
+    trace_no_in_ensemble: i32,
+    source_to_receiver_distance: i32,
+    source_x: i32,
+    source_y: i32,
+    receiver_group_x: i32,
+    receiver_group_y: i32,
+    x_ensemble: i32,
+    y_ensemble: i32,
+    inline_no: i32,
+    crossline_no: i32,
+    shot_point_no: i32,
+}
+
+impl HotHeader {
+    /// Take the hot values out of a trace header, which leaves zeros in their place.
+    fn take_from(header: &mut TraceHeader) -> Self {
+        HotHeader {
+            trace_sequence_on_line: take(&mut header.trace_sequence_on_line),
+            trace_sequence_in_file: take(&mut header.trace_sequence_in_file),
+            field_record_no: take(&mut header.field_record_no),
+            trace_no: take(&mut header.trace_no),
+            energy_source_point_no: take(&mut header.energy_source_point_no),
+            ensemble_no: take(&mut header.ensemble_no),
+            trace_no_in_ensemble: take(&mut header.trace_no_in_ensemble),
+            source_to_receiver_distance: take(&mut header.source_to_receiver_distance),
+            source_x: take(&mut header.source_x),
+            source_y: take(&mut header.source_y),
+            receiver_group_x: take(&mut header.receiver_group_x),
+            receiver_group_y: take(&mut header.receiver_group_y),
+            x_ensemble: take(&mut header.x_ensemble),
+            y_ensemble: take(&mut header.y_ensemble),
+            inline_no: take(&mut header.inline_no),
+            crossline_no: take(&mut header.crossline_no),
+            shot_point_no: take(&mut header.shot_point_no),
+        }
+    }
+
+    /// Put the hot values back into a trace header.
+    fn put_into(&self, header: &mut TraceHeader) {
+        header.trace_sequence_on_line = self.trace_sequence_on_line;
+        header.trace_sequence_in_file = self.trace_sequence_in_file;
+        header.field_record_no = self.field_record_no;
+        header.trace_no = self.trace_no;
+        header.energy_source_point_no = self.energy_source_point_no;
+        header.ensemble_no = self.ensemble_no;
+        header.trace_no_in_ensemble = self.trace_no_in_ensemble;
+        header.source_to_receiver_distance = self.source_to_receiver_distance;
+        header.source_x = self.source_x;
+        header.source_y = self.source_y;
+        header.receiver_group_x = self.receiver_group_x;
+        header.receiver_group_y = self.receiver_group_y;
+        header.x_ensemble = self.x_ensemble;
+        header.y_ensemble = self.y_ensemble;
+        header.inline_no = self.inline_no;
+        header.crossline_no = self.crossline_no;
+        header.shot_point_no = self.shot_point_no;
+    }
+}
+
+/// A trace with the hot values of its header and the index of the rest of its header.
+#[derive(Debug, Clone, PartialEq)]
+struct DedupTrace {
+    hot: HotHeader,
+    cold: usize,
+    /// The inline and crossline numbers in the trace header, if they were synthesized.
+    original: Option<[i32; 2]>,
+    /// The starting byte of the trace data.
+    start: usize,
+    /// The length of the trace data in bytes.
+    len: usize,
+}
+
+/// The traces of a file with deduplicated trace headers.
+#[derive(Debug, Default)]
+pub(crate) struct DedupTraces {
+    traces: Vec<DedupTrace>,
+    /// The distinct trace headers with the hot values set to zero.
+    cold: Vec<TraceHeader>,
+    /// The index of each header in `cold`. This is only needed while traces are added.
+    index: HashMap<TraceHeader, usize>,
+    extension_count: usize,
+    prefix_len: usize,
+}
+
+impl DedupTraces {
+    pub(crate) fn new(extension_count: usize, prefix_len: usize) -> Self {
+        DedupTraces {
+            extension_count,
+            prefix_len,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push(&mut self, trace: Trace) {
+        let (start, len) = (trace.get_start(), trace.len());
+        let original = trace
+            .original_inline_no()
+            .zip(trace.original_crossline_no())
+            .map(|(inline_no, crossline_no)| [inline_no, crossline_no]);
+        let mut header = trace.get_header().clone();
+        let hot = HotHeader::take_from(&mut header);
+        let cold = match self.index.get(&header) {
+            Some(cold) => *cold,
+            None => {
+                let cold = self.cold.len();
+                self.index.insert(header.clone(), cold);
+                self.cold.push(header);
+                cold
+            }
+        };
+        self.traces.push(DedupTrace {
+            hot,
+            cold,
+            original,
+            start,
+            len,
+        });
+    }
+
+    /// Sort the traces by the key with the same index (see [`crate::memory_map::sort_by_keys`]).
+    pub(crate) fn sort_by_keys(&mut self, keys: &[i32]) {
+        crate::memory_map::sort_by_keys(&mut self.traces, keys);
+    }
+
+    /// Stop adding traces, which frees the index of the distinct headers.
+    pub(crate) fn finish(&mut self) {
+        self.index = HashMap::new();
+        self.traces.shrink_to_fit();
+        self.cold.shrink_to_fit();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.traces.len()
+    }
+
+    /// Get the number of distinct trace headers, apart from the hot values.
+    pub(crate) fn distinct_count(&self) -> usize {
+        self.cold.len()
+    }
+
+    /// Put the trace with a given index back together.
+    pub(crate) fn get(&self, i: usize) -> Option<Trace> {
+        let trace = self.traces.get(i)?;
+        let mut header = self.cold[trace.cold].clone();
+        trace.hot.put_into(&mut header);
+        let full = Trace::new_with_extensions(header, trace.start, trace.len, self.extension_count)
+            .with_prefix_len(self.prefix_len);
+        Some(match trace.original {
+            Some([inline_no, crossline_no]) => {
+                full.with_original_line_numbers(inline_no, crossline_no)
+            }
+            None => full,
+        })
+    }
+
+    /// Get the start and length of the data of a trace.
+    pub(crate) fn data_position(&self, i: usize) -> Option<[usize; 2]> {
+        self.traces.get(i).map(|t| [t.start, t.len])
+    }
+
+    pub(crate) fn key(&self, i: usize) -> Option<TraceKey> {
+        let trace = self.traces.get(i)?;
+        Some(TraceKey {
+            inline_no: trace.hot.inline_no,
+            crossline_no: trace.hot.crossline_no,
+            x_ensemble: trace.hot.x_ensemble,
+            y_ensemble: trace.hot.y_ensemble,
+            coordinate_scalar: self.cold[trace.cold].coordinate_scalar,
+            no_samples_in_trace: self.cold[trace.cold].no_samples_in_trace,
+        })
+    }
+
+    /// Get the number of bytes which are used to store the traces (not counting the index,
+    /// which is freed by [`Self::finish`]).
+    pub(crate) fn memory_size(&self) -> usize {
+        self.traces.capacity() * std::mem::size_of::<DedupTrace>()
+            + self.cold.capacity() * std::mem::size_of::<TraceHeader>()
+    }
+}
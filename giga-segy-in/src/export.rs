@@ -0,0 +1,151 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the types which describe the flat export of the samples of a file
+//! (see [`crate::SegyFile::export_flat_f32`]), the header of the NumPy `.npy` format, and the
+//! [`TraceMatrix`] which holds all traces of a file in memory.
+#[cfg(feature = "npy")]
+use std::io::Write;
+
+/// The order in which [`crate::SegyFile::export_flat_f32`] writes the traces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeOrder {
+    /// The traces are written in the order in which they appear in the file.
+    TraceMajor,
+    /// The traces are arranged on a grid of inline and crossline numbers using the lookup, with
+    /// the inline as the slowest and the sample as the fastest axis. Grid cells for which there
+    /// is no trace are filled with NaN.
+    Grid,
+}
+
+/// The shape of the data which was written by [`crate::SegyFile::export_flat_f32`].
+///
+/// Each trace is given as many samples as the longest trace in the file (after `step_by`),
+/// and shorter traces are filled up with NaN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CubeShape {
+    /// The traces in the order of the file (see [`CubeOrder::TraceMajor`]).
+    Traces {
+        /// The number of traces.
+        traces: usize,
+        /// The number of samples per trace.
+        samples: usize,
+    },
+    /// The traces on the grid of the survey (see [`CubeOrder::Grid`]).
+    Grid {
+        /// The minimum and maximum inline numbers.
+        inline_range: [i32; 2],
+        /// The difference in inline number between two neighbouring inlines of the grid.
+        inline_step: i32,
+        /// The minimum and maximum crossline numbers.
+        crossline_range: [i32; 2],
+        /// The difference in crossline number between two neighbouring crosslines of the grid.
+        crossline_step: i32,
+        /// The number of inlines.
+        inlines: usize,
+        /// The number of crosslines.
+        crosslines: usize,
+        /// The number of samples per trace.
+        samples: usize,
+    },
+}
+
+impl CubeShape {
+    /// Get the length of each axis, from the slowest to the fastest: `[traces, samples]` or
+    /// `[inlines, crosslines, samples]`.
+    pub fn dims(&self) -> Vec<usize> {
+        match self {
+            CubeShape::Traces { traces, samples } => vec![*traces, *samples],
+            CubeShape::Grid {
+                inlines,
+                crosslines,
+                samples,
+                ..
+            } => vec![*inlines, *crosslines, *samples],
+        }
+    }
+
+    /// Get the total number of values.
+    pub fn value_count(&self) -> usize {
+        self.dims().iter().product()
+    }
+}
+
+/// The samples of all traces of a file in one contiguous row-major buffer, with one row for
+/// each trace and one column for each sample (see [`crate::SegyFile::read_all_traces_f32`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceMatrix {
+    data: Vec<f32>,
+    rows: usize,
+    columns: usize,
+}
+
+impl TraceMatrix {
+    pub(crate) fn new(data: Vec<f32>, rows: usize, columns: usize) -> Self {
+        debug_assert_eq!(data.len(), rows * columns);
+        TraceMatrix {
+            data,
+            rows,
+            columns,
+        }
+    }
+
+    /// Get the number of rows (traces) and columns (samples): `[traces, samples]`.
+    pub fn shape(&self) -> [usize; 2] {
+        [self.rows, self.columns]
+    }
+
+    /// Get the distance between neighbouring values along each axis of [`Self::shape`], in
+    /// values (not bytes).
+    pub fn strides(&self) -> [usize; 2] {
+        [self.columns, 1]
+    }
+
+    /// Get all values, row by row.
+    pub fn as_slice(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Get the samples of the trace with a given index, or `None` if it is out of bounds.
+    pub fn row(&self, i: usize) -> Option<&[f32]> {
+        if i < self.rows {
+            Some(&self.data[(i * self.columns)..((i + 1) * self.columns)])
+        } else {
+            None
+        }
+    }
+
+    /// Take the values out of the matrix, row by row.
+    pub fn into_vec(self) -> Vec<f32> {
+        self.data
+    }
+
+    /// Convert the matrix into a two dimensional [`ndarray::Array2`] without copying the data.
+    #[cfg(feature = "ndarray")]
+    pub fn into_ndarray(self) -> ndarray::Array2<f32> {
+        ndarray::Array2::from_shape_vec((self.rows, self.columns), self.data)
+            .expect("The shape matches the data.")
+    }
+}
+
+/// Write the header of a version 1.0 `.npy` file for little endian f32 values of the given
+/// shape. The header is padded with spaces so that the data starts at a multiple of 64 bytes.
+#[cfg(feature = "npy")]
+pub(crate) fn write_npy_header<W: Write>(out: &mut W, shape: &CubeShape) -> std::io::Result<()> {
+    let dims = shape
+        .dims()
+        .iter()
+        .map(|d| format!("{}, ", d))
+        .collect::<String>();
+    let mut dict = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}), }}",
+        dims.trim_end()
+    );
+    // The magic string, the version and the length of the header take 10 bytes, and the
+    // header ends with a newline.
+    let len = 10 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - len % 64) % 64));
+    dict.push('\n');
+
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    out.write_all(&(dict.len() as u16).to_le_bytes())?;
+    out.write_all(dict.as_bytes())
+}
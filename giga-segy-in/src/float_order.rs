@@ -0,0 +1,172 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`FloatOrderDiagnosis`], which tells whether the float samples
+//! of a file were written with the wrong byte order (see
+//! [`crate::SegyFile::detect_float_byte_order_issue`]).
+use giga_segy_core::bitconverter::converter_chooser;
+use giga_segy_core::enums::SampleFormatCode;
+
+use std::convert::TryInto;
+
+/// The smallest and largest magnitude of a sample which is taken to be a plausible amplitude.
+const PLAUSIBLE_BAND: [f32; 2] = [1e-6, 1e8];
+/// How much larger the fraction of plausible samples must be in one byte order than in the
+/// other for that byte order to be chosen.
+const MARGIN: f64 = 0.25;
+
+/// Which byte order of the samples looks right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatOrder {
+    /// The samples look right in the byte order that they are read in.
+    AsRead,
+    /// The samples look right in the opposite byte order, so they should be read with
+    /// [`giga_segy_core::SegySettings::set_swap_data_byte_order`] (or without it, if it is
+    /// already set).
+    Swapped,
+    /// Neither byte order looks clearly better, eg. because the samples are all zero.
+    Undecided,
+    /// The samples are not floats (or they are read with a custom decoder), so nothing was
+    /// checked.
+    NotApplicable,
+}
+
+/// Statistics of the samples of a file, when they are decoded in one byte order.
+///
+/// The fractions are of the samples which are not zero, since zero is the same in both byte
+/// orders.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SampleStats {
+    /// The number of samples which are not zero.
+    pub count: usize,
+    /// The fraction of samples whose magnitude is in the range of plausible amplitudes (from
+    /// 1e-6 to 1e8).
+    pub plausible: f64,
+    /// The fraction of samples which are NaN or infinite (or too large for an f32, for IBM
+    /// floats).
+    pub non_finite: f64,
+    /// The fraction of samples which are subnormal (or not normalized, for IBM floats).
+    pub denormal: f64,
+}
+
+/// The result of [`crate::SegyFile::detect_float_byte_order_issue`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatOrderDiagnosis {
+    /// The sample format which was checked.
+    pub format: SampleFormatCode,
+    /// The number of traces whose samples were checked.
+    pub traces: usize,
+    /// The statistics of the samples in the byte order that they are read in.
+    pub as_read: SampleStats,
+    /// The statistics of the samples in the opposite byte order.
+    pub swapped: SampleStats,
+    /// Which byte order looks right.
+    pub verdict: FloatOrder,
+}
+
+impl FloatOrderDiagnosis {
+    /// The diagnosis for samples which cannot be checked.
+    pub(crate) fn not_applicable(format: SampleFormatCode) -> Self {
+        FloatOrderDiagnosis {
+            format,
+            traces: 0,
+            as_read: SampleStats::default(),
+            swapped: SampleStats::default(),
+            verdict: FloatOrder::NotApplicable,
+        }
+    }
+
+    /// Check the samples of some traces, which are read as little endian if `le` is true.
+    /// The format must be `Float32` or `IbmFloat32`.
+    pub(crate) fn diagnose<'a, I: Iterator<Item = &'a [u8]>>(
+        format: SampleFormatCode,
+        le: bool,
+        traces: I,
+    ) -> Self {
+        let mut counts = [Counts::default(); 2];
+        let mut n_traces = 0;
+        for data in traces {
+            n_traces += 1;
+            for sample in data.chunks_exact(4) {
+                let be = u32::from_be_bytes(sample.try_into().unwrap());
+                let bits = if le { be.swap_bytes() } else { be };
+                counts[0].add(format, bits);
+                counts[1].add(format, bits.swap_bytes());
+            }
+        }
+        let [as_read, swapped] = [counts[0].stats(), counts[1].stats()];
+        let verdict = if as_read.plausible >= swapped.plausible + MARGIN {
+            FloatOrder::AsRead
+        } else if swapped.plausible >= as_read.plausible + MARGIN {
+            FloatOrder::Swapped
+        } else {
+            FloatOrder::Undecided
+        };
+        FloatOrderDiagnosis {
+            format,
+            traces: n_traces,
+            as_read,
+            swapped,
+            verdict,
+        }
+    }
+
+    /// Check whether the samples look like they were written with the wrong byte order.
+    pub fn is_swapped(&self) -> bool {
+        self.verdict == FloatOrder::Swapped
+    }
+}
+
+/// The number of samples of each kind, in one byte order.
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    count: usize,
+    plausible: usize,
+    non_finite: usize,
+    denormal: usize,
+}
+
+impl Counts {
+    /// Count a sample, given as the bits of a big endian number.
+    fn add(&mut self, format: SampleFormatCode, bits: u32) {
+        let (value, denormal) = match format {
+            SampleFormatCode::IbmFloat32 => {
+                if bits & 0x00FF_FFFF == 0 {
+                    return;
+                }
+                let converter = converter_chooser(format, false).unwrap();
+                let value = converter(&bits.to_be_bytes()).unwrap();
+                (value, bits & 0x00F0_0000 == 0)
+            }
+            _ => {
+                if bits & 0x7FFF_FFFF == 0 {
+                    return;
+                }
+                let value = f32::from_bits(bits);
+                (value, value.is_subnormal())
+            }
+        };
+        self.count += 1;
+        if !value.is_finite() {
+            self.non_finite += 1;
+        } else if denormal {
+            self.denormal += 1;
+        } else if (PLAUSIBLE_BAND[0]..=PLAUSIBLE_BAND[1]).contains(&value.abs()) {
+            self.plausible += 1;
+        }
+    }
+
+    fn stats(&self) -> SampleStats {
+        let fraction = |n: usize| {
+            if self.count == 0 {
+                0.
+            } else {
+                n as f64 / self.count as f64
+            }
+        };
+        SampleStats {
+            count: self.count,
+            plausible: fraction(self.plausible),
+            non_finite: fraction(self.non_finite),
+            denormal: fraction(self.denormal),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`GridAxes`], which map the inline and crossline numbers of a
+//! survey onto the indices of a regular grid (see [`crate::SegyFile::grid_axes`]).
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The largest fraction of the line numbers of an axis which may fall off the grid when the
+/// increment is inferred. Such line numbers are taken to be outliers and ignored.
+const MAX_OUTLIER_FRACTION: f64 = 0.1;
+
+/// The line numbers along one axis of a regular grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridAxis {
+    /// The line number at index zero.
+    pub origin: i32,
+    /// The difference in line number between neighbouring indices.
+    pub increment: i32,
+    /// The number of indices, from the first to the last line number on the grid.
+    pub len: usize,
+    /// The number of line numbers which do not fall onto the grid, and were ignored when the
+    /// increment was inferred.
+    pub outliers: usize,
+}
+
+impl GridAxis {
+    /// Infer the axis from a set of line numbers. The increment is the greatest common divisor
+    /// of the distances between the line numbers, but a larger increment is taken if no more
+    /// than a tenth of the line numbers fall off it. The origin is the smallest line number on
+    /// the grid.
+    pub(crate) fn infer<I: Iterator<Item = i32>>(lines: I) -> Self {
+        let mut lines = lines.map(i64::from).collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.dedup();
+        if lines.is_empty() {
+            return GridAxis {
+                origin: 0,
+                increment: 1,
+                len: 0,
+                outliers: 0,
+            };
+        }
+
+        let deltas = lines.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        let all = deltas.iter().copied().fold(0, gcd).max(1);
+        // Outliers make the common divisor smaller, so the increment is looked for among the
+        // divisors of the most common distance, from the largest down.
+        let mut counts = HashMap::new();
+        for d in deltas.iter() {
+            *counts.entry(*d).or_insert(0usize) += 1;
+        }
+        let common = counts
+            .into_iter()
+            .max_by_key(|(d, n)| (*n, -*d))
+            .map(|(d, _)| d)
+            .unwrap_or(1);
+        let max_outliers = (lines.len() as f64 * MAX_OUTLIER_FRACTION) as usize;
+        let (increment, residue) = divisors(common)
+            .into_iter()
+            .filter(|k| *k > all && i32::try_from(*k).is_ok())
+            .find_map(|k| {
+                let (residue, on_grid) = largest_residue_class(&lines, k);
+                (lines.len() - on_grid <= max_outliers).then_some((k, residue))
+            })
+            .unwrap_or((all, lines[0].rem_euclid(all)));
+        let increment = i32::try_from(increment).unwrap_or(1);
+
+        let step = increment as i64;
+        let on_grid = lines
+            .iter()
+            .copied()
+            .filter(|l| l.rem_euclid(step) == residue.rem_euclid(step))
+            .collect::<Vec<_>>();
+        let origin = on_grid[0];
+        let last = on_grid[on_grid.len() - 1];
+        GridAxis {
+            origin: origin as i32,
+            increment,
+            len: ((last - origin) / step) as usize + 1,
+            outliers: lines.len() - on_grid.len(),
+        }
+    }
+
+    /// Get the index of a line number, or `None` if it is not on the grid.
+    pub fn index_of(&self, line: i32) -> Option<usize> {
+        let offset = line as i64 - self.origin as i64;
+        if offset < 0 || offset % self.increment as i64 != 0 {
+            return None;
+        }
+        let index = usize::try_from(offset / self.increment as i64).ok()?;
+        (index < self.len).then_some(index)
+    }
+
+    /// Get the line number at an index. Indices beyond the end of the axis give the line
+    /// numbers which would follow it (saturating at [`i32::MAX`] or [`i32::MIN`]).
+    pub fn line_number(&self, index: usize) -> i32 {
+        let line = self.origin as i128 + index as i128 * self.increment as i128;
+        line.clamp(i32::MIN as i128, i32::MAX as i128) as i32
+    }
+}
+
+/// The inline and crossline axes of the regular grid of a survey, as given by
+/// [`crate::SegyFile::grid_axes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridAxes {
+    pub inline: GridAxis,
+    pub crossline: GridAxis,
+}
+
+/// Find the residue modulo `k` which most line numbers have, and how many have it. Ties go to
+/// the residue of the smaller line number.
+fn largest_residue_class(lines: &[i64], k: i64) -> (i64, usize) {
+    let mut counts = HashMap::new();
+    for l in lines {
+        *counts.entry(l.rem_euclid(k)).or_insert(0usize) += 1;
+    }
+    lines
+        .iter()
+        .map(|l| l.rem_euclid(k))
+        .fold((0, 0), |best, r| {
+            let n = counts[&r];
+            if n > best.1 {
+                (r, n)
+            } else {
+                best
+            }
+        })
+}
+
+/// Get the divisors of a positive number, from the largest down.
+fn divisors(n: i64) -> Vec<i64> {
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    let mut i = 1;
+    while i * i <= n {
+        if n % i == 0 {
+            small.push(i);
+            if i * i != n {
+                large.push(n / i);
+            }
+        }
+        i += 1;
+    }
+    large.into_iter().chain(small.into_iter().rev()).collect()
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -33,7 +33,7 @@
 //! // Of course there are more organised ways of doing this, but for a demo, this will do.
 //! for trace in file.traces_iter() {
 //!     println!("Trace header: {}", trace.get_header());
-//!     let data:Vec<f32> = file.get_trace_data_as_f32_from_trace(trace).unwrap();
+//!     let data:Vec<f32> = file.get_trace_data_as_f32_from_trace(&trace).unwrap();
 //!     if data.len() > 9 {
 //!         println!("Data: {:?}", &data[0..10]);
 //!     } else {
@@ -46,25 +46,76 @@
 //! with the clever use of `giga_segy_in` and `giga_segy_out`, we do not recommend this.
 #![allow(clippy::derive_partial_eq_without_eq)]
 extern crate encoding8;
+#[cfg(test)]
+extern crate giga_segy_out;
 extern crate memmap2;
 extern crate num;
+#[cfg(test)]
+extern crate tempfile;
 
+#[cfg(feature = "async")]
+pub mod async_file;
+mod compact;
+pub mod compare;
 pub mod convert_headers;
+mod dedup;
+pub mod export;
+mod float_order;
+mod grid;
+mod line_geometry;
+mod line_index;
 pub mod memory_map;
+mod positions;
+mod profile;
+#[cfg(test)]
+mod prop_tests;
+mod quick_check;
 pub mod read_data;
+pub mod slices;
+#[cfg(feature = "su")]
+pub mod su;
 #[cfg(test)]
 mod tests;
 
-use memory_map::MappedSegY;
+use compact::CompactTrace;
+use convert_headers::parse_trace_header;
+use dedup::DedupTraces;
+use enums::TextEncoding;
+use line_index::LineIndex;
+use memory_map::{lock, FileHeaders, MapSlice, MappedSegY, ScannedTraces};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryInto;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
 
 pub use giga_segy_core::enums;
 pub use giga_segy_core::errors::*;
 pub use giga_segy_core::header_structs::*;
-pub use giga_segy_core::{SegyMetadata, SegySettings, Trace};
+pub use giga_segy_core::stanza::Stanza;
+pub use giga_segy_core::{
+    EffectiveParams, GeoPosition, HeaderProfile, ProgressPhase, Provenance, SegyHandle,
+    SegyMetadata, SegyProgress, SegySettings, Trace, TracesIter,
+};
+
+#[cfg(feature = "async")]
+pub use async_file::AsyncSegyFile;
+pub use compact::TraceKey;
+pub use export::{CubeOrder, CubeShape, TraceMatrix};
+pub use float_order::{FloatOrder, FloatOrderDiagnosis, SampleStats};
+pub use grid::{GridAxes, GridAxis};
+pub use line_geometry::LineGeometry;
+pub use line_index::LineKey;
+pub use positions::TracePosition;
+pub use quick_check::{quick_check, QuickCheckReport};
+pub use slices::{LineKind, Section, TimeSlice};
 
 use giga_segy_core::*;
 
+/// A list of data points that could not be read, given as the position of the requested point
+/// in the input and the reason for the failure.
+pub type SampleFailures = Vec<(usize, RsgError)>;
+
 /// A structure which represents a mapped SEG-Y file.
 ///
 /// The structure contains:
@@ -79,20 +130,86 @@ use giga_segy_core::*;
 ///
 /// It should be noted that while the trace headers are parsed, the trace data itself
 /// is contained in the memory map.
+///
+/// If the file was opened with [`SegySettings::set_compact_headers`], the parsed trace headers
+/// are not kept. Then the functions which give traces ([`Self::get_trace`],
+/// [`Self::traces_iter`] and the like, and those of [`SegyHandle`]) parse the trace header
+/// from the file each time (see [`Self::load_trace`]), so the traces are owned rather than
+/// borrowed. All functions which take the index of a trace work in the same way in both modes.
+/// The same goes for files opened with [`SegySettings::set_dedup_headers`], where the trace
+/// header is put back together from the shared and per-trace values.
+///
+/// [`SegyFile`] is [`Send`] and [`Sync`], so the data of its traces can be read from several
+/// threads at the same time (eg. with a thread pool). The memory map is read only, and the
+/// only state which is changed after opening the file is the index of [`Self::get_nearest_trace`]
+/// and [`Self::get_traces_in_rect`] and the grid of [`Self::grid_axes`], which are built once
+/// behind mutexes.
+///
+/// Since the whole file is mapped to memory, it must fit into the address space of the process.
+/// Files which are larger than `usize::MAX` (or than [`SegySettings::set_mapping_budget`]) are
+/// refused with [`RsgError::FileTooLargeForPlatform`]. On 32-bit targets such files can be read
+/// with the `AsyncSegyFile` (with the `async` feature), which reads the headers and trace data
+/// from the file when they are needed instead of mapping it, as long as the file is smaller
+/// than 4 GB.
+///
+/// By default the whole file is mapped at once. For very large files, this can be capped with
+/// [`SegySettings::set_max_map_window`], so that the file is mapped in windows instead.
 pub struct SegyFile {
     pub(crate) metadata: SegyMetadata<SegySettings>,
     pub(crate) traces: Vec<Trace>,
-    /// This is here to speed up the lookup of traces. The crossline and inline
+    /// The traces without their headers, if the headers are stored compactly. Otherwise this
+    /// is empty.
+    compact_traces: Vec<CompactTrace>,
+    /// The traces with deduplicated headers, if the headers are deduplicated. Otherwise this
+    /// is empty.
+    dedup_traces: DedupTraces,
+    /// This is here to speed up the lookup of traces. The inline and crossline
     /// numbers act as the key, and the index of the trace is retrieved.
-    pub(crate) lookup: HashMap<[i32; 2], usize>,
+    pub(crate) lookup: HashMap<LineKey, usize>,
+    /// The traces sorted by inline and crossline number, for nearest neighbour and range
+    /// queries. This is only built when it is first needed.
+    line_index: Mutex<Option<Arc<LineIndex>>>,
+    /// The regular grid of the inline and crossline numbers. This is only inferred when it is
+    /// first needed.
+    grid: Mutex<Option<GridAxes>>,
+    /// The indices of the auxiliary traces, if they were kept with the other traces.
+    aux_traces: Vec<usize>,
+    /// Anything that was not quite right in the file, but could be dealt with when opening it.
+    pub(crate) warnings: Vec<String>,
     data: MappedSegY,
 }
 
+/// The headers and traces of a [`SegyFile`], as given by [`SegyFile::into_parts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegyFileParts {
+    pub tape_label: Option<TapeLabel>,
+    pub text_header: String,
+    pub extended_headers: Vec<String>,
+    pub bin_header: BinHeader,
+    pub traces: Vec<Trace>,
+    /// The index of each trace by its inline and crossline numbers (see
+    /// [`SegyFile::into_lookup`]), if it was asked for.
+    pub lookup: Option<HashMap<LineKey, usize>>,
+}
+
+// Make sure that `SegyFile` stays `Send` and `Sync`.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SegyFile>();
+    assert_send_sync::<MappedSegY>();
+    #[cfg(feature = "async")]
+    assert_send_sync::<AsyncSegyFile>();
+};
+
 impl SegyFile {
     /// Attempts to open a SEG-Y file stored in a given location, with a given set of settings.
     /// This creates an instance of [`SegyFile`] which can then be used as a handle to get headers
     /// and data.
     ///
+    /// The settings are checked with [`SegySettings::validate`] before the file is opened.
+    /// If they do not give the coordinate format, but the extended text headers record it (as
+    /// `giga_segy_out` does), it is added to the settings (see [`Self::coordinate_encoding`]).
+    ///
     /// ```
     /// use std::env::var;
     /// use std::path::PathBuf;
@@ -105,37 +222,118 @@ impl SegyFile {
     /// assert!(file.is_ok());
     /// ```
     pub fn open(file_name: &str, settings: SegySettings) -> Result<Self, RsgError> {
-        let data = MappedSegY::new(file_name)?;
-        let tape_label = data.get_tape_label(&settings)?;
-        let text_header = data.get_text_header()?;
-        let mut bin_header = data.get_bin_header(&settings)?;
-        let extended_headers = data.get_extended_text_headers(bin_header.extended_header_count)?;
-        let traces =
-            data.get_metadata_for_traces(&mut bin_header, extended_headers.len(), &settings)?;
-        let lookup = traces
-            .iter()
-            .enumerate()
-            .map(|(i, trace)| {
-                let header = trace.get_header();
-                ([header.crossline_no, header.inline_no], i)
-            })
-            .collect::<HashMap<[i32; 2], usize>>();
-
-        let metadata = SegyMetadata::new(
-            tape_label,
-            text_header,
-            extended_headers,
-            bin_header,
-            settings,
-        );
+        Self::open_with_progress(file_name, settings, |_| ControlFlow::Continue(()))
+    }
+
+    /// This works in the same way as [`SegyFile::open`], but reports the progress to a callback,
+    /// once after the headers are read and then every few hundred traces while the trace
+    /// headers are scanned. If the callback returns `ControlFlow::Break(())`, opening the file
+    /// is stopped and [`RsgError::Cancelled`] is returned.
+    /// ```
+    /// use std::env::var;
+    /// use std::ops::ControlFlow;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{RsgError, SegyFile};
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let mut reports = 0;
+    /// let file = SegyFile::open_with_progress(name.to_str().unwrap(), Default::default(), |p| {
+    ///     reports += 1;
+    ///     assert!(p.bytes_done <= p.bytes_total.unwrap());
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert!(file.is_ok());
+    /// assert!(reports > 1);
+    ///
+    /// let res = SegyFile::open_with_progress(name.to_str().unwrap(), Default::default(), |_| {
+    ///     ControlFlow::Break(())
+    /// });
+    /// assert!(matches!(res, Err(RsgError::Cancelled)));
+    /// ```
+    pub fn open_with_progress<F>(
+        file_name: &str,
+        mut settings: SegySettings,
+        mut progress: F,
+    ) -> Result<Self, RsgError>
+    where
+        F: FnMut(SegyProgress) -> ControlFlow<()>,
+    {
+        settings.validate()?;
+        let data = MappedSegY::new(file_name, &settings)?;
+        let mut warnings = Vec::new();
+        warnings.extend(data.map_warning().map(String::from));
+        let file_len = data.len();
+        let headers_start = data.headers_start();
+        let headers_len = file_len - headers_start;
+        // The extended headers may go beyond the head of a file which is mapped in windows.
+        let needed = FileHeaders::needed_len(data.head(), headers_len, &settings)?;
+        let mut headers = FileHeaders::parse(
+            &data.get(headers_start..(headers_start + needed))?,
+            headers_len,
+            &mut settings,
+            &mut warnings,
+        )?;
 
-        let file = SegyFile {
+        let label_len = headers.tape_label.as_ref().map_or(0, |_| TAPE_LABEL_LEN);
+        let text_header_offset = headers_start + label_len;
+        let head_len = text_header_offset
+            + headers
+                .bin_header
+                .get_first_trace_offset(headers.extended_headers.len());
+        let report = SegyProgress {
+            phase: ProgressPhase::Headers,
+            traces_done: 0,
+            traces_total: None,
+            bytes_done: head_len.min(file_len),
+            bytes_total: Some(file_len),
+        };
+        if progress(report).is_break() {
+            return Err(RsgError::Cancelled);
+        }
+
+        let scanned = data.get_metadata_for_traces(
+            &mut headers.bin_header,
+            headers.extended_headers.len(),
+            &settings,
+            &mut progress,
+            &mut warnings,
+        )?;
+        let metadata = headers.into_metadata(settings, text_header_offset);
+        Ok(Self::from_scanned(data, metadata, scanned, warnings))
+    }
+
+    /// Put together a [`SegyFile`] from the traces which were found in the memory map.
+    pub(crate) fn from_scanned(
+        data: MappedSegY,
+        metadata: SegyMetadata<SegySettings>,
+        scanned: ScannedTraces,
+        warnings: Vec<String>,
+    ) -> Self {
+        let lookup = scanned.lookup();
+        SegyFile {
             metadata,
-            traces,
+            traces: scanned.full,
+            compact_traces: scanned.compact,
+            dedup_traces: scanned.dedup,
             lookup,
+            line_index: Mutex::new(None),
+            grid: Mutex::new(None),
+            aux_traces: scanned.aux,
+            warnings,
             data,
-        };
-        Ok(file)
+        }
+    }
+
+    /// Get the format in which the coordinates of the trace headers are read. This is the
+    /// override of the settings (which may have been taken from the extended text headers, see
+    /// [`Self::open`]), or `Int32` as in the standard.
+    pub fn coordinate_encoding(&self) -> enums::SampleFormatCode {
+        self.get_settings()
+            .get_override_coordinate_format()
+            .unwrap_or(enums::SampleFormatCode::Int32)
     }
 
     /// Get a reference to the [`SegySettings`] which were used when opening the file in this
@@ -208,6 +406,65 @@ impl SegyFile {
         self.metadata.get_text_header()
     }
 
+    /// Get the encoding with which the text header was decoded. This is never
+    /// [`TextEncoding::Auto`], since the encoding which was detected is given instead (see
+    /// [`SegySettings::set_text_header_encoding`]).
+    pub fn get_text_header_encoding(&self) -> TextEncoding {
+        self.metadata
+            .get_text_header_encoding()
+            .expect("Set when the file is opened.")
+    }
+
+    /// Get how the lines (card images) of the text header were found. A header whose lines
+    /// end with line feeds (eg. one which was edited by hand) is split at them, so that
+    /// [`Self::get_text_header_lines`] still gives one line of eighty characters for each card
+    /// (see [`enums::TextHeaderLayout`]).
+    ///
+    /// NB: The text header is always written with fixed width cards by `giga_segy_out`. With
+    /// [`TextEncoding::Raw`] the header is kept exactly as it is, so this is always
+    /// [`enums::TextHeaderLayout::FixedWidth`].
+    pub fn get_text_header_layout(&self) -> enums::TextHeaderLayout {
+        self.metadata
+            .get_text_header_layout()
+            .expect("Set when the file is opened.")
+    }
+
+    /// Get the position of the text header in the file. This is zero, or the length of the tape
+    /// label if the file has one, unless the text header was searched for and found after
+    /// something else (see [`SegySettings::set_scan_for_text_header`]).
+    ///
+    /// NB: This is zero for a file without a text header (eg. a Seismic Unix file).
+    pub fn get_text_header_offset(&self) -> usize {
+        self.metadata.get_text_header_offset().unwrap_or(0)
+    }
+
+    /// Get the values which were actually used to interpret the file, after the overrides of
+    /// the settings were applied, and where each of them came from (the file, the settings, or
+    /// a fallback such as the default of the standard).
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{Provenance, SegyFile, SegySettings};
+    /// use giga_segy_in::enums::SampleFormatCode;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let mut settings = SegySettings::default();
+    /// settings.set_override_coordinate_format(SampleFormatCode::Float32).unwrap();
+    /// let file = SegyFile::open(name.to_str().unwrap(), settings).unwrap();
+    /// let params = file.effective_parameters();
+    /// assert_eq!(params.sample_format.source, Provenance::File);
+    /// assert_eq!(params.coordinate_format.value, SampleFormatCode::Float32);
+    /// assert_eq!(params.coordinate_format.source, Provenance::Override);
+    /// ```
+    pub fn effective_parameters(&self) -> &EffectiveParams {
+        self.metadata
+            .get_effective_params()
+            .expect("Set when the file is opened.")
+    }
+
     /// Get an iterator over the extended headers.
     pub fn extended_headers_iter(&self) -> std::slice::Iter<String> {
         self.metadata.extended_headers_iter()
@@ -218,6 +475,13 @@ impl SegyFile {
         self.metadata.get_extended_headers()
     }
 
+    /// Parse the stanzas of the extended headers, such as `((SEG: Location Data ver 1.0))` in
+    /// SEG-Y rev 2 (see [`giga_segy_core::stanza::parse_extended_stanzas`]). Extended headers
+    /// which are not made up of stanzas are ignored.
+    pub fn extended_stanzas(&self) -> Vec<Stanza> {
+        stanza::parse_extended_stanzas(self.get_extended_headers())
+    }
+
     /// Get the text header as a collection of short substrings.
     ///
     /// This function splits the header into 80 character long substrings
@@ -246,6 +510,214 @@ impl SegyFile {
         self.metadata.get_text_header_lines()
     }
 
+    /// Iterate over the lines (card images) of the text header without copying them.
+    pub fn text_header_lines(&self) -> impl Iterator<Item = &str> {
+        self.metadata.text_header_lines()
+    }
+
+    /// Get line `n` of the text header, counting from one (as in `C 1`, `C 2` etc.).
+    pub fn text_header_line(&self, n: usize) -> Option<&str> {
+        self.metadata.line(n)
+    }
+
+    /// Get the lines of the text header with their trailing spaces removed (see
+    /// [`SegyMetadata::get_text_header_lines_trimmed`]).
+    pub fn get_text_header_lines_trimmed(&self) -> Vec<String> {
+        self.metadata.get_text_header_lines_trimmed()
+    }
+
+    /// Get the text header as its trimmed lines, separated by line feeds (see
+    /// [`SegyMetadata::get_text_header_trimmed`]).
+    pub fn get_text_header_trimmed(&self) -> String {
+        self.metadata.get_text_header_trimmed()
+    }
+
+    /// Check whether the text header is blank, or only has the card numbers of its lines (see
+    /// [`SegyMetadata::text_header_is_blank`]).
+    pub fn text_header_is_blank(&self) -> bool {
+        self.metadata.text_header_is_blank()
+    }
+
+    /// Get the bytes of the text header exactly as they are in the file (ie. usually in EBCDIC).
+    pub fn get_text_header_bytes(&self) -> &[u8] {
+        let start = self.head_start();
+        &self.data.head()[start..(start + TEXT_HEADER_LEN)]
+    }
+
+    /// Get the bytes of the binary header exactly as they are in the file, including those which
+    /// are not parsed into [`BinHeader`].
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    /// assert_eq!(file.get_text_header_bytes().len(), 3200);
+    /// assert_eq!(file.get_bin_header_bytes().len(), 400);
+    /// assert_eq!(file.get_trace_header_bytes(0).unwrap().len(), 240);
+    /// ```
+    pub fn get_bin_header_bytes(&self) -> &[u8] {
+        let start = self.head_start() + TEXT_HEADER_LEN;
+        &self.data.head()[start..(start + BIN_HEADER_LEN)]
+    }
+
+    /// Get the bytes of the trace header of the trace with a given index exactly as they are in
+    /// the file. The trace header extensions are not included (see
+    /// [`Self::get_trace_extension_bytes`]).
+    ///
+    /// The bytes are borrowed from the memory map, unless the file is mapped in windows (see
+    /// [`SegySettings::set_max_map_window`]) and they are not in its head. Then they are copied,
+    /// so that the window need not stay mapped.
+    pub fn get_trace_header_bytes(&self, i: usize) -> Result<Cow<'_, [u8]>, RsgError> {
+        Ok(self.trace_header_bytes(i)?.into_cow())
+    }
+
+    /// Get the bytes of a trace header in the same way as [`Self::get_trace_header_bytes`],
+    /// but without copying them out of a window.
+    fn trace_header_bytes(&self, i: usize) -> Result<MapSlice<'_>, RsgError> {
+        self.data.get(self.trace_header_range(i)?)
+    }
+
+    fn trace_header_range(&self, i: usize) -> Result<std::ops::Range<usize>, RsgError> {
+        let end = self
+            .extension_range(i)
+            .ok_or(RsgError::TraceNotFound { i })?
+            .start;
+        Ok((end - TRACE_HEADER_LEN)..end)
+    }
+
+    /// Get the position of the trace header extensions of a trace, which end where the data
+    /// (or the prefix, see [`SegySettings::set_trace_prefix_bytes`]) begins. This works without
+    /// the trace header, so that it can be used in all modes.
+    fn extension_range(&self, i: usize) -> Option<std::ops::Range<usize>> {
+        let [start, _] = self.data_position(i)?;
+        let end = start - self.get_settings().get_trace_prefix_bytes();
+        let extension_count = self.get_bin_header().max_additional_trace_headers as usize;
+        Some((end - TRACE_HEADER_LEN * extension_count)..end)
+    }
+
+    /// Get the start and length of the data of a trace.
+    fn data_position(&self, i: usize) -> Option<[usize; 2]> {
+        if self.has_compact_headers() {
+            self.compact_traces.get(i).map(|t| [t.start, t.len])
+        } else if self.has_dedup_headers() {
+            self.dedup_traces.data_position(i)
+        } else {
+            self.traces.get(i).map(|t| [t.get_start(), t.len()])
+        }
+    }
+
+    /// Get the start and length of the data of a trace and the factor by which its samples are
+    /// multiplied (see [`read_data::sample_scale`]). The trace header is only loaded if it is
+    /// needed for the trace weighting.
+    fn sample_position(&self, i: usize) -> Option<(usize, usize, f32)> {
+        let [start, len] = self.data_position(i)?;
+        let (bin_header, settings) = (self.get_bin_header(), self.get_settings());
+        let scale = if settings.get_apply_trace_weighting() {
+            read_data::sample_scale(self.load_trace(i).ok()?.get_header(), bin_header, settings)
+        } else {
+            settings.get_user_gain()
+        };
+        Some((start, len, scale))
+    }
+
+    /// The start of the text header, which is after the tape label, if there is one.
+    fn head_start(&self) -> usize {
+        self.get_tape_label().as_ref().map_or(0, |_| TAPE_LABEL_LEN)
+    }
+
+    /// Guess where the inline and crossline numbers are in the trace headers, by trying each of
+    /// [`HeaderProfile::PRESETS`] on the first `max_traces` traces. This is meant to suggest a
+    /// profile for [`SegySettings::apply_profile`] if a file does not look right.
+    ///
+    /// A profile is preferred if it gives pairs of line numbers which are unique, sorted and
+    /// fill a grid. Profiles which give line numbers that are zero, negative or very large are
+    /// not considered, and `None` is returned if this rules out all of them (or if the file has
+    /// no traces). If several profiles are equally plausible, the first is returned.
+    ///
+    /// NB: The line numbers are read directly from the trace headers, so the settings that the
+    /// file was opened with do not matter.
+    pub fn detect_header_profile(&self, max_traces: usize) -> Option<HeaderProfile> {
+        let headers = (0..self.trace_count().min(max_traces))
+            .map(|i| self.trace_header_bytes(i))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let le = self.get_bin_header().binary_flag_direction_is_le;
+        let i32_at = |header: &[u8], bidx: usize| {
+            let bytes = header[bidx..(bidx + 4)].try_into().unwrap();
+            if le {
+                i32::from_le_bytes(bytes)
+            } else {
+                i32::from_be_bytes(bytes)
+            }
+        };
+
+        let mut best: Option<(f64, HeaderProfile)> = None;
+        for profile in HeaderProfile::PRESETS.iter() {
+            let [inline, crossline, _, _] = profile.byte_indices();
+            let lines = headers
+                .iter()
+                .map(|h| [i32_at(h, inline), i32_at(h, crossline)])
+                .collect::<Vec<_>>();
+            if let Some(score) = profile::line_score(&lines) {
+                if !matches!(best, Some((b, _)) if b >= score) {
+                    best = Some((score, *profile));
+                }
+            }
+        }
+        best.map(|(_, profile)| profile)
+    }
+
+    /// Check whether the float samples of the file were written with the wrong byte order, by
+    /// decoding the samples of up to `sample_traces` traces (spread evenly through the file) in
+    /// both byte orders.
+    ///
+    /// Samples which were written with the wrong byte order can usually still be decoded, but
+    /// their values are absurdly large or small, or not numbers. So the byte order in which more
+    /// of the samples have a plausible amplitude is chosen. If the samples need to be swapped,
+    /// the file can be read with [`SegySettings::set_swap_data_byte_order`], which swaps the
+    /// samples without affecting the headers.
+    ///
+    /// Only `Float32` and `IbmFloat32` samples are checked (taking the trace format override
+    /// into account). For other formats, or samples which are read with a custom decoder, the
+    /// verdict is [`FloatOrder::NotApplicable`]. Traces whose data cannot be read are skipped.
+    pub fn detect_float_byte_order_issue(&self, sample_traces: usize) -> FloatOrderDiagnosis {
+        let (bin_header, settings) = (self.get_bin_header(), self.get_settings());
+        let format = settings
+            .get_override_trace_format()
+            .unwrap_or(bin_header.sample_format_code);
+        let is_float = matches!(
+            format,
+            enums::SampleFormatCode::Float32 | enums::SampleFormatCode::IbmFloat32
+        );
+        if !is_float || read_data::custom_decoder(bin_header, settings).is_some() {
+            return FloatOrderDiagnosis::not_applicable(format);
+        }
+
+        let (n, k) = (self.trace_count(), sample_traces.min(self.trace_count()));
+        let data = (0..k)
+            .filter_map(|j| {
+                let [start, len] = self.data_position(j * n / k)?;
+                self.data.get(start..(start + len)).ok()
+            })
+            .collect::<Vec<_>>();
+        FloatOrderDiagnosis::diagnose(
+            format,
+            read_data::data_is_le(bin_header, settings),
+            data.iter().map(|d| &d[..]),
+        )
+    }
+
+    /// Get the warnings about problems with the file which were dealt with when opening it
+    /// (eg. a sample format which was replaced because the file size was trusted).
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
     /// Get the binary header.
     pub fn get_bin_header(&self) -> &BinHeader {
         self.metadata.get_bin_header()
@@ -253,30 +725,574 @@ impl SegyFile {
 
     /// Get the number of readable traces in the SEG-Y file.
     pub fn trace_count(&self) -> usize {
-        self.traces.len()
+        if self.has_compact_headers() {
+            self.compact_traces.len()
+        } else if self.has_dedup_headers() {
+            self.dedup_traces.len()
+        } else {
+            self.traces.len()
+        }
     }
 
-    /// Get the reference to a certain trace by the order in which it appears in the
-    /// SEG-Y file. An out of bounds index returns a `None`.
-    pub fn get_trace(&self, i: usize) -> Option<&Trace> {
-        self.traces.get(i)
+    /// Check whether the file has no readable traces. A file with valid headers and no traces
+    /// (eg. a placeholder) can be opened, and then every trace index is out of range, the
+    /// minimum and maximum helpers return `None` and the iterators are empty.
+    pub fn is_empty(&self) -> bool {
+        self.trace_count() == 0
     }
 
-    /// Get the reference to a certain trace by inline and crossline number using the lookup.
-    /// This function should be used rather than iterating over traces retrieved by [`Self::get_trace`].
-    pub fn get_trace_by_xline_inline(&self, xline: i32, inline: i32) -> Option<&Trace> {
-        if let Some(index) = self.lookup.get(&[xline, inline]) {
-            self.get_trace(*index)
+    /// Get the indices of the auxiliary traces (eg. time breaks or sweeps, see
+    /// [`enums::TraceIdCode::is_auxiliary`]), in ascending order. These are only kept along
+    /// with the other traces with [`AuxPolicy::Include`](enums::AuxPolicy::Include), which is
+    /// the default (see [`SegySettings::set_aux_trace_policy`]). Otherwise this is empty.
+    pub fn aux_trace_indices(&self) -> &[usize] {
+        &self.aux_traces
+    }
+
+    /// Get where the inline and crossline numbers of the traces come from (see
+    /// [`SegySettings::line_numbering_source`]). If they are synthesized, the values in the
+    /// trace headers are kept with each trace (see [`Trace::original_inline_no`] and
+    /// [`Trace::original_crossline_no`]).
+    pub fn line_numbering_source(&self) -> enums::LineNumberingSource {
+        self.get_settings().line_numbering_source()
+    }
+
+    /// Check whether the trace headers are stored compactly (see
+    /// [`SegySettings::set_compact_headers`]).
+    pub fn has_compact_headers(&self) -> bool {
+        self.get_settings().get_compact_headers()
+    }
+
+    /// Check whether the trace headers are deduplicated (see
+    /// [`SegySettings::set_dedup_headers`]).
+    pub fn has_dedup_headers(&self) -> bool {
+        self.get_settings().get_dedup_headers()
+    }
+
+    /// Get the number of distinct trace headers, apart from the values which are kept for each
+    /// trace, if the trace headers are deduplicated. Otherwise `None` is returned.
+    pub fn distinct_header_count(&self) -> Option<usize> {
+        if self.has_dedup_headers() {
+            Some(self.dedup_traces.distinct_count())
         } else {
             None
         }
     }
 
+    /// Get the number of bytes which are used to keep the traces in memory, not counting the
+    /// lookups. This shows how much is saved with [`SegySettings::set_compact_headers`] or
+    /// [`SegySettings::set_dedup_headers`].
+    pub fn trace_memory_size(&self) -> usize {
+        self.traces.capacity() * std::mem::size_of::<Trace>()
+            + self.compact_traces.capacity() * std::mem::size_of::<CompactTrace>()
+            + self.dedup_traces.memory_size()
+    }
+
+    /// Get the key values (line numbers, ensemble coordinates and sample count) of the trace
+    /// header of the trace with a given index. This works whether or not the trace headers are
+    /// stored compactly. An out of bounds index returns a `None`.
+    pub fn get_trace_key(&self, i: usize) -> Option<TraceKey> {
+        if self.has_compact_headers() {
+            self.compact_traces.get(i).map(|t| t.key)
+        } else if self.has_dedup_headers() {
+            self.dedup_traces.key(i)
+        } else {
+            self.traces
+                .get(i)
+                .map(|t| TraceKey::from_header(t.get_header()))
+        }
+    }
+
+    /// Iterate through the key values of all traces.
+    fn trace_keys(&self) -> impl Iterator<Item = TraceKey> + '_ {
+        (0..self.trace_count()).filter_map(move |i| self.get_trace_key(i))
+    }
+
+    /// Get the trace with a given index, whether or not the trace headers are stored compactly.
+    /// If they are, the trace header is parsed from the file. If they are deduplicated, the
+    /// trace header is put back together, and otherwise the trace is borrowed. If the trace is
+    /// not found, an error is returned.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{SegyFile, SegySettings};
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let mut settings = SegySettings::default();
+    /// settings.set_compact_headers(true);
+    /// let file = SegyFile::open(name.to_str().unwrap(), settings).unwrap();
+    /// let trace = file.load_trace(0).unwrap();
+    /// assert_eq!(trace.get_header().inline_no, file.get_trace_key(0).unwrap().inline_no);
+    /// ```
+    pub fn load_trace(&self, i: usize) -> Result<Cow<'_, Trace>, RsgError> {
+        if self.has_dedup_headers() {
+            return self
+                .dedup_traces
+                .get(i)
+                .map(Cow::Owned)
+                .ok_or(RsgError::TraceNotFound { i });
+        }
+        if !self.has_compact_headers() {
+            return self
+                .traces
+                .get(i)
+                .map(Cow::Borrowed)
+                .ok_or(RsgError::TraceNotFound { i });
+        }
+        let trace = self
+            .compact_traces
+            .get(i)
+            .ok_or(RsgError::TraceNotFound { i })?;
+        let (bin_header, settings) = (self.get_bin_header(), self.get_settings());
+        let bytes = self.data.get(trace.header_range(bin_header, settings))?;
+        let full = trace.load(&bytes, i, bin_header, settings)?;
+        Ok(Cow::Owned(full))
+    }
+
+    /// Get the trace header of the trace with a given index, parsed without the coordinate
+    /// transform of the settings (see [`SegySettings::set_coordinate_transform`]). Apart from
+    /// the coordinates, this is the same as the header of [`Self::load_trace`].
+    pub fn load_untransformed_trace_header(&self, i: usize) -> Result<TraceHeader, RsgError> {
+        let mut header = self.load_trace(i)?.get_header().clone();
+        let mut settings = self.get_settings().clone();
+        settings.set_coordinate_transform(None);
+        let (raw, _) = parse_trace_header(
+            &self.trace_header_bytes(i)?,
+            self.get_bin_header(),
+            &settings,
+            i,
+        )?;
+        header.x_ensemble = raw.x_ensemble;
+        header.y_ensemble = raw.y_ensemble;
+        header.source_x = raw.source_x;
+        header.source_y = raw.source_y;
+        header.receiver_group_x = raw.receiver_group_x;
+        header.receiver_group_y = raw.receiver_group_y;
+        Ok(header)
+    }
+
+    /// Get the number of samples which are returned when reading the trace with a given index
+    /// (eg. with [`Self::get_trace_data_as_f32`]). This takes the sample format override and
+    /// the `step_by` setting into account. An out of bounds index returns a `None`.
+    pub fn trace_sample_count(&self, i: usize) -> Option<usize> {
+        let [_, len] = self.data_position(i)?;
+        let settings = self.get_settings();
+        let datum_len = read_data::datum_len(self.get_bin_header(), settings);
+        // This mirrors the way that samples are skipped when reading.
+        Some(
+            (0..datum_len.sample_count(len))
+                .step_by(settings.get_step_by())
+                .len(),
+        )
+    }
+
+    /// Get the sample interval of the trace with a given index, as given in the headers (ie. in
+    /// microseconds, or in millimetres for depth data).
+    ///
+    /// The sample interval of the trace header is used if it is set, then that of the binary
+    /// header, and finally the override in the settings (see
+    /// [`SegySettings::set_override_sample_interval`]). If none of these are set, or the index is
+    /// out of bounds and the binary header has no sample interval either, `0.0` is returned.
+    ///
+    /// NB: This does not take the `step_by` setting into account.
+    pub fn effective_sample_interval(&self, i: usize) -> f64 {
+        self.sample_interval_of(self.load_trace(i).ok().as_deref())
+    }
+
+    /// The inner function of [`Self::effective_sample_interval`].
+    fn sample_interval_of(&self, trace: Option<&Trace>) -> f64 {
+        let from_trace = trace.map_or(0, |t| t.get_header().sample_interval_of_trace);
+        if from_trace != 0 {
+            from_trace as f64
+        } else if self.get_bin_header().sample_interval_us() != 0. {
+            self.get_bin_header().sample_interval_us()
+        } else {
+            self.get_settings()
+                .get_override_sample_interval()
+                .unwrap_or(0.0)
+        }
+    }
+
+    /// Get the times (in milliseconds) of the samples that are returned when reading the trace
+    /// with a given index (eg. with [`Self::get_trace_data_as_f32`]).
+    ///
+    /// The first sample is at the `delay_recording_time` of the trace header, and the samples
+    /// are spaced by [`Self::effective_sample_interval`] multiplied by `step_by`. An error is
+    /// returned if the index is out of bounds or no sample interval is known.
+    pub fn time_axis(&self, i: usize) -> Result<Vec<f64>, RsgError> {
+        let (start, step, count) = self.trace_timing(i)?;
+        Ok((0..count).map(|k| start + k as f64 * step).collect())
+    }
+
+    /// Get the times (in milliseconds) of the first and the last sample that are returned when
+    /// reading the trace with a given index, as given by [`Self::time_axis`]. For a trace without
+    /// samples, both are the `delay_recording_time` of the trace header.
+    ///
+    /// An error is returned if the index is out of bounds or no sample interval is known.
+    pub fn trace_time_range(&self, i: usize) -> Result<(f64, f64), RsgError> {
+        let (start, step, count) = self.trace_timing(i)?;
+        Ok((start, start + count.saturating_sub(1) as f64 * step))
+    }
+
+    /// Get the time of the first sample of a trace, the time between the samples that are read
+    /// (both in milliseconds) and the number of samples that are read.
+    fn trace_timing(&self, i: usize) -> Result<(f64, f64, usize), RsgError> {
+        let trace = self.load_trace(i)?;
+        let count = self
+            .trace_sample_count(i)
+            .ok_or(RsgError::TraceNotFound { i })?;
+        let interval = self.effective_sample_interval(i);
+        if interval <= 0.0 {
+            return Err(RsgError::InvalidHeader {
+                msg: format!("No sample interval is known for trace {}", i),
+            });
+        }
+
+        let start = trace.get_header().delay_recording_time as f64;
+        let step = interval * self.get_settings().get_step_by() as f64 / 1000.0;
+        Ok((start, step, count))
+    }
+
+    /// Get the domain and unit of the vertical axis, along with the sample interval in that
+    /// unit (see [`Self::effective_sample_interval`]).
+    ///
+    /// If the trace identification code is overridden in the settings, it decides whether the
+    /// data is in time or depth. Otherwise the data is in time (or depth) if more than half of
+    /// all traces have a time domain (or depth domain) code, and the axis is unknown if neither
+    /// of them dominates (eg. if most traces are dead). See [`Self::trace_id_code_counts`].
+    ///
+    /// The unit of depth data is taken from [`SegySettings::get_override_trace_depth_units`]
+    /// if it is set, and from the measurement system of the binary header otherwise. If the
+    /// measurement system is not specified, metres are assumed.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{SegyFile, SegySettings};
+    /// use giga_segy_in::enums::{TraceIdCode, VerticalAxis};
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// // The traces of this file do not have a trace identification code.
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    /// assert_eq!(file.vertical_axis(), VerticalAxis::Unknown);
+    ///
+    /// let mut settings = SegySettings::default();
+    /// settings.set_override_trace_id_code(TraceIdCode::TimeDomainSeismic);
+    /// let file = SegyFile::open(name.to_str().unwrap(), settings).unwrap();
+    /// assert_eq!(file.vertical_axis(), VerticalAxis::TimeMs { interval: 4.0 });
+    /// ```
+    pub fn vertical_axis(&self) -> VerticalAxis {
+        let settings = self.get_settings();
+        let (time, depth) = match settings.get_override_trace_id_code() {
+            Some(code) => (code.is_time_domain(), code.is_depth_domain()),
+            None => {
+                let counts = self.trace_id_code_counts();
+                let count = |f: fn(TraceIdCode) -> bool| {
+                    counts
+                        .iter()
+                        .filter(|(code, _)| f(*code))
+                        .map(|(_, n)| n)
+                        .sum::<usize>()
+                };
+                let half = self.trace_count() / 2;
+                (
+                    count(TraceIdCode::is_time_domain) > half,
+                    count(TraceIdCode::is_depth_domain) > half,
+                )
+            }
+        };
+
+        let interval = self.effective_sample_interval(0) / 1000.0;
+        if time {
+            VerticalAxis::TimeMs { interval }
+        } else if depth {
+            let units = settings
+                .get_override_trace_depth_units()
+                .unwrap_or(self.get_bin_header().measurement_system);
+            match units {
+                MeasurementSystem::Feet => VerticalAxis::DepthFeet { interval },
+                _ => VerticalAxis::DepthMeters { interval },
+            }
+        } else {
+            VerticalAxis::Unknown
+        }
+    }
+
+    /// Get the number of traces with each trace identification code, in the order in which the
+    /// codes first appear in the file.
+    pub fn trace_id_code_counts(&self) -> Vec<(TraceIdCode, usize)> {
+        let mut counts: Vec<(TraceIdCode, usize)> = Vec::new();
+        for i in 0..self.trace_count() {
+            let code = match self.load_trace(i) {
+                Ok(trace) => trace.get_header().trace_identification_code,
+                Err(_) => continue,
+            };
+            match counts.iter_mut().find(|(c, _)| *c == code) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((code, 1)),
+            }
+        }
+        counts
+    }
+
+    /// Get a certain trace by the order in which it appears in the SEG-Y file. An out of bounds
+    /// index returns a `None`. The trace is borrowed, unless the trace headers are stored
+    /// compactly or deduplicated, in which case it is put together with [`Self::load_trace`]
+    /// (which should be used instead to get the reason why a trace cannot be read).
+    pub fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        self.load_trace(i).ok()
+    }
+
+    /// Get a certain trace by inline and crossline number using the lookup, in the same way as
+    /// with [`Self::get_trace`]. This function should be used rather than iterating over traces
+    /// retrieved by [`Self::get_trace`].
+    pub fn get_trace_by_xline_inline(&self, xline: i32, inline: i32) -> Option<Cow<'_, Trace>> {
+        let index = self.lookup.get(&LineKey::new(inline, xline))?;
+        self.get_trace(*index)
+    }
+
+    /// Check whether there is a trace with the given inline and crossline numbers.
+    pub fn contains_line(&self, inline: i32, crossline: i32) -> bool {
+        self.lookup.contains_key(&LineKey::new(inline, crossline))
+    }
+
+    /// Iterate through the inline and crossline numbers of the traces (in no particular order).
+    /// If several traces have the same line numbers, they are only given once.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    /// for key in file.lines_iter() {
+    ///     assert!(file.contains_line(key.inline, key.crossline));
+    ///     let trace = file.get_trace_by_xline_inline(key.crossline, key.inline).unwrap();
+    ///     assert_eq!(trace.get_header().inline_no, key.inline);
+    /// }
+    /// ```
+    pub fn lines_iter(&self) -> impl Iterator<Item = LineKey> + '_ {
+        self.lookup.keys().copied()
+    }
+
+    /// Get the trace which is closest to the given crossline and inline numbers, along with its
+    /// squared distance in line numbers (`d_inline² + d_crossline²`, which is zero for an exact
+    /// match and saturates at [`i64::MAX`]). This is useful for decimated or irregular surveys,
+    /// where the exact pair of line numbers may not exist. If several traces are equally close,
+    /// the one that comes first in the file is returned. `None` is only returned if the file has
+    /// no traces (or if the trace header cannot be read, see [`Self::load_trace`]).
+    ///
+    /// As with [`Self::get_trace_by_xline_inline`], only one trace is considered for each pair
+    /// of line numbers. The sorted index that is used is built on the first call to this
+    /// function or to [`Self::get_traces_in_rect`]. The trace is obtained with
+    /// [`Self::load_trace`], so this works however the trace headers are stored.
+    pub fn get_nearest_trace(&self, xline: i32, inline: i32) -> Option<(Cow<'_, Trace>, i64)> {
+        let (i, distance) = self.line_index().nearest(inline, xline)?;
+        Some((self.load_trace(i).ok()?, distance))
+    }
+
+    /// Get the indices of the traces whose inline and crossline numbers are within the given
+    /// (inclusive) ranges, in the order in which they appear in the file.
+    ///
+    /// As with [`Self::get_trace_by_xline_inline`], only one trace is considered for each pair
+    /// of line numbers.
+    pub fn get_traces_in_rect(&self, inline_range: [i32; 2], xline_range: [i32; 2]) -> Vec<usize> {
+        self.line_index().in_rect(inline_range, xline_range)
+    }
+
+    /// Get the regular grid onto which the inline and crossline numbers of the traces fall,
+    /// eg. for surveys whose inlines are numbered from 1001 in steps of two.
+    ///
+    /// The increment of each axis is the greatest common divisor of the distances between the
+    /// line numbers, unless a few line numbers (no more than a tenth of them) are off a larger
+    /// increment. Such line numbers are counted as [`GridAxis::outliers`] and have no grid
+    /// index, so the increments should be checked if this is not expected. The grid is inferred
+    /// on the first call to this function or to the functions which use it.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    /// let grid = file.grid_axes();
+    /// for key in file.lines_iter() {
+    ///     let (i_idx, x_idx) = file.grid_index_of(key.inline, key.crossline).unwrap();
+    ///     assert!(i_idx < grid.inline.len && x_idx < grid.crossline.len);
+    ///     assert_eq!(file.line_numbers_of(i_idx, x_idx), (key.inline, key.crossline));
+    /// }
+    /// ```
+    pub fn grid_axes(&self) -> GridAxes {
+        *lock(&self.grid).get_or_insert_with(|| GridAxes {
+            inline: GridAxis::infer(self.lookup.keys().map(|k| k.inline)),
+            crossline: GridAxis::infer(self.lookup.keys().map(|k| k.crossline)),
+        })
+    }
+
+    /// Get the inline and crossline indices of a pair of line numbers on the grid of
+    /// [`Self::grid_axes`]. `None` is returned if either line number is not on the grid,
+    /// whether or not there is a trace with these line numbers.
+    pub fn grid_index_of(&self, inline: i32, crossline: i32) -> Option<(usize, usize)> {
+        let grid = self.grid_axes();
+        Some((
+            grid.inline.index_of(inline)?,
+            grid.crossline.index_of(crossline)?,
+        ))
+    }
+
+    /// Get the inline and crossline numbers at a pair of indices on the grid of
+    /// [`Self::grid_axes`]. This is the inverse of [`Self::grid_index_of`]. Indices beyond the
+    /// grid give the line numbers which would follow it.
+    pub fn line_numbers_of(&self, i_idx: usize, x_idx: usize) -> (i32, i32) {
+        let grid = self.grid_axes();
+        (
+            grid.inline.line_number(i_idx),
+            grid.crossline.line_number(x_idx),
+        )
+    }
+
+    /// Get the trace at a pair of inline and crossline indices on the grid of
+    /// [`Self::grid_axes`], as with [`Self::get_trace_by_xline_inline`]. `None` is returned if
+    /// the indices are beyond the grid or there is no trace there. The trace is obtained with
+    /// [`Self::load_trace`], so this works however the trace headers are stored.
+    pub fn get_trace_by_grid_index(&self, i_idx: usize, x_idx: usize) -> Option<Cow<'_, Trace>> {
+        let grid = self.grid_axes();
+        if i_idx >= grid.inline.len || x_idx >= grid.crossline.len {
+            return None;
+        }
+        let (inline, crossline) = self.line_numbers_of(i_idx, x_idx);
+        let i = self.lookup.get(&LineKey::new(inline, crossline))?;
+        self.load_trace(*i).ok()
+    }
+
+    /// Fit a straight line through the ensemble (CDP) coordinates of the traces, scaled with
+    /// their coordinate scalars. This is meant for 2D lines (especially crooked ones), which
+    /// have no meaningful inline and crossline numbers. See [`Self::order_traces_by_line_distance`].
+    ///
+    /// `None` is returned if there are fewer than two traces, if all traces are at the same
+    /// position, or if a trace header cannot be parsed (when the headers are stored compactly).
+    pub fn line_geometry(&self) -> Option<LineGeometry> {
+        LineGeometry::fit(&self.ensemble_points()?)
+    }
+
+    /// Get the indices of the traces, sorted by the distance along the line of their ensemble
+    /// coordinates when these are projected onto the line of [`Self::line_geometry`]. Traces
+    /// at the same distance are kept in the order in which they appear in the file.
+    ///
+    /// If there is no such line, the indices are returned in the order of the file.
+    pub fn order_traces_by_line_distance(&self) -> Vec<usize> {
+        let mut order = (0..self.trace_count()).collect::<Vec<_>>();
+        let points = match self.ensemble_points() {
+            Some(points) => points,
+            None => return order,
+        };
+        if let Some(geometry) = LineGeometry::fit(&points) {
+            let distances = points
+                .iter()
+                .map(|p| geometry.distance(*p))
+                .collect::<Vec<_>>();
+            order.sort_by(|a, b| distances[*a].total_cmp(&distances[*b]));
+        }
+        order
+    }
+
+    /// Iterate through the line numbers and the ensemble coordinates of the traces, in the order
+    /// of the file. The coordinates are scaled with the coordinate scalar of each trace (or its
+    /// override, see [`SegySettings::set_override_coordinate_scaling`]), and they are
+    /// transformed if the settings give a coordinate transform.
+    ///
+    /// This only uses the values which are kept for every trace (see [`Self::get_trace_key`]), so
+    /// no trace headers are parsed or copied, even if they are stored compactly.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{SegyFile, SegySettings};
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), SegySettings::default()).unwrap();
+    /// for p in file.positions_iter().take(3) {
+    ///     println!("{}: ({}, {}) at {}, {}", p.index, p.inline, p.crossline, p.x, p.y);
+    /// }
+    /// ```
+    pub fn positions_iter(&self) -> impl Iterator<Item = TracePosition> + '_ {
+        (0..self.trace_count())
+            .filter_map(move |i| Some(TracePosition::new(i, self.get_trace_key(i)?)))
+    }
+
+    /// Get the positions of all traces (see [`Self::positions_iter`]).
+    pub fn positions_to_vec(&self) -> Vec<TracePosition> {
+        self.positions_iter().collect()
+    }
+
+    /// Get the bounding box of the ensemble coordinates of the traces, scaled as with
+    /// [`Self::positions_iter`], as `[x_min, y_min, x_max, y_max]`. `None` is returned if the
+    /// file has no traces.
+    pub fn positions_bounding_box(&self) -> Option<[f64; 4]> {
+        positions::bounding_box(self.positions_iter())
+    }
+
+    /// Get the position of the source of a trace in decimal degrees, if the coordinate units of
+    /// its trace header are geographic (see [`TraceHeader::geographic_source_position`]).
+    ///
+    /// `None` is returned if the trace does not exist or its header cannot be parsed, if the
+    /// units are not geographic, or if the coordinate units have been overridden with
+    /// [`SegySettings::set_override_coordinate_units`], since the coordinates are then lengths.
+    /// The raw coordinates remain available in the trace header.
+    pub fn geographic_source_position(&self, i: usize) -> Option<GeoPosition> {
+        if self
+            .get_settings()
+            .get_override_coordinate_units()
+            .is_some()
+        {
+            return None;
+        }
+        self.load_trace(i)
+            .ok()?
+            .get_header()
+            .geographic_source_position()
+    }
+
+    /// Get the scaled ensemble coordinates of every trace, or `None` if a trace header cannot
+    /// be loaded.
+    fn ensemble_points(&self) -> Option<Vec<[f64; 2]>> {
+        (0..self.trace_count())
+            .map(|i| {
+                let trace = self.load_trace(i).ok()?;
+                Some(line_geometry::scaled_ensemble(trace.get_header()))
+            })
+            .collect()
+    }
+
+    fn line_index(&self) -> Arc<LineIndex> {
+        lock(&self.line_index)
+            .get_or_insert_with(|| Arc::new(LineIndex::new(&self.lookup)))
+            .clone()
+    }
+
     /// Gets the trace data for a trace with a given index as a [`Vec<f32>`]. In this case, if
     /// the index is out of bounds, an error is returned to reflect that there was a failure
     /// to retrieve the data. If the data is not in an [`f32`] format, it is converted to [`f32`]
     /// with potential loss of precision.
     ///
+    /// The samples are scaled with the trace weighting factor and the user gain, if these are
+    /// set in the settings (see [`SegySettings::set_apply_trace_weighting`] and
+    /// [`SegySettings::set_user_gain`]). This applies to all functions which read samples as
+    /// [`f32`], but not to those which read the bytes.
+    ///
     /// ```
     /// use std::env::var;
     /// use std::path::PathBuf;
@@ -297,26 +1313,71 @@ impl SegyFile {
     /// assert!(matches!(no_this_fails, RsgError::TraceNotFound { i } if i == 999_999_999));
     /// ```
     pub fn get_trace_data_as_f32(&self, i: usize) -> Result<Vec<f32>, RsgError> {
-        let trace = &self.get_trace(i).ok_or(RsgError::TraceNotFound { i })?;
+        let trace = self.load_trace(i)?;
 
         crate::read_data::get_trace_data_as_f32(
             &self.data,
-            trace,
+            &trace,
             self.get_bin_header(),
             self.get_settings(),
         )
     }
 
+    /// Get the trace data for a given index converted to any primitive number type `T`, eg.
+    /// [`i16`] to store the samples compactly, or [`f64`] to keep all of the precision of a 64
+    /// bit format.
+    ///
+    /// The samples are multiplied by the trace weighting factor and user gain, as with
+    /// [`Self::get_trace_data_as_f32`], and then by `scale` if it is given. Integer samples are
+    /// converted exactly if there is nothing to multiply them by, and every other sample is
+    /// converted from an [`f64`], so that values are truncated towards zero for integer types.
+    /// If a sample does not fit in `T`, an [`RsgError::SampleConversion`] with its index is
+    /// returned. As with the other functions, only every `step_by`-th sample is returned (see
+    /// [`SegySettings::set_step_by`]).
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// let data = file.get_trace_data_as::<f64>(3, None).unwrap();
+    /// let expected = file.get_trace_data_as_f32(3).unwrap();
+    /// assert_eq!(data, expected.iter().map(|v| *v as f64).collect::<Vec<_>>());
+    /// ```
+    pub fn get_trace_data_as<T: num::FromPrimitive>(
+        &self,
+        i: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<T>, RsgError> {
+        let trace = self.load_trace(i)?;
+
+        crate::read_data::get_trace_data_converted(
+            &self.data,
+            &trace,
+            self.get_bin_header(),
+            self.get_settings(),
+            scale,
+        )
+    }
+
     /// Get the trace for a given index as a [`Vec<u8>`]. This is useful if there
     /// is concern for precision loss, or the file contains an unusual data type (eg paired data).
     ///
-    /// As with [`Self::get_trace_data_as_f32`], if the trace is not found, an error is returned.
+    /// As with [`Self::get_trace_data_as_f32`], if the trace is not found, an error is returned,
+    /// and only the bytes of every `step_by`-th sample are returned (see
+    /// [`SegySettings::set_step_by`]). The bytes are those of the file, so the trace weighting
+    /// factor and user gain are not applied.
     pub fn get_trace_data_as_bytes(&self, i: usize) -> Result<Vec<u8>, RsgError> {
-        let trace = &self.get_trace(i).ok_or(RsgError::TraceNotFound { i })?;
+        let trace = self.load_trace(i)?;
 
         crate::read_data::get_trace_data_as_bytes_unprocessed(
             &self.data,
-            trace,
+            &trace,
             self.get_bin_header(),
             self.get_settings(),
         )
@@ -334,6 +1395,153 @@ impl SegyFile {
         )
     }
 
+    /// Get the samples of the trace with a given index whose times (see [`Self::time_axis`]) are
+    /// from `start_ms` to `end_ms` (inclusive) as a [`Vec<f32>`]. Only the bytes of these
+    /// samples are decoded, unless they are read with a custom decoder.
+    ///
+    /// By default the window is clamped to the samples of the trace, so that a window which is
+    /// entirely before the `delay_recording_time` of the trace header (or after its last sample)
+    /// gives no samples. If the settings ask for it, an [`RsgError::TimeWindowOutOfBounds`] is
+    /// returned instead (see [`SegySettings::set_strict_time_window`]). An error is also returned
+    /// if `start_ms` is after `end_ms`, or if no sample interval is known.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// // The samples are 4 ms apart, starting at 0 ms.
+    /// let window = file.get_trace_data_window_ms(3, 8., 20.).unwrap();
+    /// assert_eq!(window, file.get_trace_data_as_f32(3).unwrap()[2..=5]);
+    /// ```
+    pub fn get_trace_data_window_ms(
+        &self,
+        i: usize,
+        start_ms: f64,
+        end_ms: f64,
+    ) -> Result<Vec<f32>, RsgError> {
+        if start_ms.is_nan() || end_ms.is_nan() || start_ms > end_ms {
+            return Err(RsgError::SEGYSettingsError {
+                msg: format!("Invalid time window: {} ms to {} ms", start_ms, end_ms),
+            });
+        }
+        let (first, step, count) = self.trace_timing(i)?;
+        // The window is turned into sample positions, with a little tolerance so that a window
+        // which ends on the time of a sample includes it despite rounding errors.
+        let tolerance = 1e-9;
+        let from = (start_ms - first) / step;
+        let to = (end_ms - first) / step;
+        if self.get_settings().get_strict_time_window()
+            && (count == 0 || from < -tolerance || to > (count - 1) as f64 + tolerance)
+        {
+            return Err(RsgError::TimeWindowOutOfBounds {
+                trace: i,
+                window: [start_ms, end_ms],
+                extent: [first, first + count.saturating_sub(1) as f64 * step],
+            });
+        }
+
+        let from = (from - tolerance).ceil().max(0.);
+        let to = (to + tolerance).floor();
+        if count == 0 || to < from {
+            return Ok(Vec::new());
+        }
+        let range = from as usize..(to as usize).min(count - 1) + 1;
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let trace = self.load_trace(i)?;
+        crate::read_data::get_trace_data_window_as_f32(
+            &self.data,
+            &trace,
+            self.get_bin_header(),
+            self.get_settings(),
+            range,
+        )
+    }
+
+    /// Get the trace data for a given index as a [`Vec<f32>`], with the mute of the trace header
+    /// applied. See [`Self::get_trace_data_as_f32_muted_from_trace`].
+    pub fn get_trace_data_as_f32_muted(&self, i: usize) -> Result<Vec<f32>, RsgError> {
+        let trace = self.load_trace(i)?;
+        self.get_trace_data_as_f32_muted_from_trace(&trace)
+    }
+
+    /// Retrieves the trace data for a given [`Trace`] in the same way as
+    /// [`Self::get_trace_data_as_f32_from_trace`], but the samples between `mute_time_start`
+    /// (inclusive) and `mute_time_end` (exclusive) of the trace header are set to zero, or to
+    /// NaN if [`SegySettings::get_mute_with_nan`] is set.
+    ///
+    /// The times of the samples are calculated as in [`Self::time_axis`]. If both mute times
+    /// are zero, the trace is not muted. If the end of the mute is after the end of the trace,
+    /// everything from the start of the mute onwards is muted. An error is returned if the trace
+    /// is muted, but no sample interval is known.
+    pub fn get_trace_data_as_f32_muted_from_trace(&self, t: &Trace) -> Result<Vec<f32>, RsgError> {
+        let mut data = self.get_trace_data_as_f32_from_trace(t)?;
+        let header = t.get_header();
+        if header.mute_time_start == 0 && header.mute_time_end == 0 {
+            return Ok(data);
+        }
+
+        let interval = self.sample_interval_of(Some(t));
+        if interval <= 0.0 {
+            return Err(RsgError::InvalidHeader {
+                msg: "No sample interval is known to apply the mute".to_string(),
+            });
+        }
+        let settings = self.get_settings();
+        let start = header.delay_recording_time as f64;
+        let step = interval * settings.get_step_by() as f64 / 1000.0;
+        let mute = header.mute_time_start as f64..header.mute_time_end as f64;
+        let fill = if settings.get_mute_with_nan() {
+            f32::NAN
+        } else {
+            0.0
+        };
+        for (k, sample) in data.iter_mut().enumerate() {
+            if mute.contains(&(start + k as f64 * step)) {
+                *sample = fill;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Get the raw bytes of the trace header extensions (see
+    /// [`BinHeader::max_additional_trace_headers`]) of the trace with a given index. The slice
+    /// is empty if the trace has no extensions. An out of bounds index returns a `None`. The
+    /// bytes are borrowed or copied in the same way as with [`Self::get_trace_header_bytes`].
+    pub fn get_trace_extension_bytes(&self, i: usize) -> Option<Cow<'_, [u8]>> {
+        let bytes = self.data.get(self.extension_range(i)?).ok()?;
+        Some(bytes.into_cow())
+    }
+
+    /// Get a digest (see [`giga_segy_core::digest::digest_bytes`]) of the raw bytes of the
+    /// data of the trace with a given index. The headers of the trace are not included, so the
+    /// digest does not change if they are edited. If the trace is not found, an error is
+    /// returned.
+    ///
+    /// NB: The whole trace is used, regardless of [`SegySettings::get_step_by`].
+    pub fn trace_data_digest(&self, i: usize) -> Result<u64, RsgError> {
+        let [start, len] = self.data_position(i).ok_or(RsgError::TraceNotFound { i })?;
+        let bytes = self.data.get(start..(start + len))?;
+        Ok(digest::digest_bytes(&bytes))
+    }
+
+    /// Get a digest of the data of all traces, which combines the digests of each trace (see
+    /// [`SegyFile::trace_data_digest`]) in the order of the traces with
+    /// [`giga_segy_core::digest::combine_digests`].
+    pub fn file_data_digest(&self) -> Result<u64, RsgError> {
+        let digests = (0..self.trace_count())
+            .map(|i| self.trace_data_digest(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(digest::combine_digests(digests))
+    }
+
     /// Retrives the trace data for a given [`Trace`] from the same [`SegyFile`] as a [`Vec<u8>`].
     /// If one is already holding a reference to a trace, this function should be preferred
     /// over [`Self::get_trace_data_as_bytes`].
@@ -349,7 +1557,11 @@ impl SegyFile {
     /// This function tries to get a data point at a particular index in the trace recording
     /// from a particular trace. Returns it as an unprocessed byte slice as an owned [`Vec<u8>`].
     /// This function is less efficient than [`Self::get_trace_data_as_bytes_from_trace`], so if multiple
-    /// data points are needed, then usually that function should be preferred.  
+    /// data points are needed, then usually that function should be preferred.
+    ///
+    /// The index is that of the data point in the output of
+    /// [`Self::get_trace_data_as_bytes_from_trace`], so it counts only every `step_by`-th
+    /// sample (see [`SegySettings::set_step_by`]).
     pub fn get_trace_data_point_as_bytes_from_trace(
         &self,
         t: &Trace,
@@ -368,6 +1580,10 @@ impl SegyFile {
     /// from a particular trace, returning it as a [`f32`] value.
     /// This function is less efficient than [`Self::get_trace_data_as_f32_from_trace`], so if multiple
     /// data points are needed, then usually that function should be preferred.
+    ///
+    /// The index is that of the data point in the output of
+    /// [`Self::get_trace_data_as_f32_from_trace`], so it counts only every `step_by`-th
+    /// sample (see [`SegySettings::set_step_by`]).
     /// ```
     /// use std::env::var;
     /// use std::path::PathBuf;
@@ -382,10 +1598,10 @@ impl SegyFile {
     ///
     /// let third_trace = file.get_trace(3).unwrap();
     /// let data_vec_4 = file.get_trace_data_as_f32(3).unwrap();
-    /// let third_value = file.get_trace_data_point_as_f32_from_trace(third_trace, 2).unwrap();
+    /// let third_value = file.get_trace_data_point_as_f32_from_trace(&third_trace, 2).unwrap();
     /// assert_eq!(third_value, data_vec_4[2]);
     ///
-    /// let no_this_fails = file.get_trace_data_point_as_f32_from_trace(third_trace, 999_999_999);
+    /// let no_this_fails = file.get_trace_data_point_as_f32_from_trace(&third_trace, 999_999_999);
     /// assert!(matches!(
     ///     no_this_fails.unwrap_err(),
     ///     RsgError::TracePointOutOfBounds { idx: 999_999_999 }
@@ -405,9 +1621,408 @@ impl SegyFile {
         )
     }
 
-    /// Iterate through the traces.
-    pub fn traces_iter(&self) -> std::slice::Iter<Trace> {
-        self.traces.iter()
+    /// This function gets one data point from each of a number of traces, given as pairs of
+    /// `(trace_index, sample_index)`, returning them as [`f32`] values in the same order as the
+    /// input. This is much more efficient than calling
+    /// [`Self::get_trace_data_point_as_f32_from_trace`] for each pair (eg when extracting a
+    /// horizon), since the converter is only chosen once and the data is read in the order in
+    /// which it appears in the file.
+    ///
+    /// As with [`Self::get_trace_data_point_as_f32_from_trace`], the sample indices count only
+    /// every `step_by`-th sample. If any of the pairs are out of range an error is returned. See
+    /// [`Self::get_samples_at_or_nan`] for a more forgiving version.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// let samples = file.get_samples_at(&[(3, 2), (0, 5)]).unwrap();
+    /// assert_eq!(samples[0], file.get_trace_data_as_f32(3).unwrap()[2]);
+    /// assert_eq!(samples[1], file.get_trace_data_as_f32(0).unwrap()[5]);
+    /// ```
+    pub fn get_samples_at(&self, indices: &[(usize, usize)]) -> Result<Vec<f32>, RsgError> {
+        crate::read_data::get_samples_at(
+            &self.data,
+            |i| self.sample_position(i),
+            self.get_bin_header(),
+            self.get_settings(),
+            indices,
+            false,
+        )
+        .map(|(data, _)| data)
+    }
+
+    /// This function works like [`Self::get_samples_at`], but the values for out of range pairs
+    /// are set to NaN instead of returning an error. The failures are returned as a second
+    /// output, in the form of the position of the pair in `indices` and the reason for failure.
+    pub fn get_samples_at_or_nan(
+        &self,
+        indices: &[(usize, usize)],
+    ) -> Result<(Vec<f32>, SampleFailures), RsgError> {
+        crate::read_data::get_samples_at(
+            &self.data,
+            |i| self.sample_position(i),
+            self.get_bin_header(),
+            self.get_settings(),
+            indices,
+            true,
+        )
+    }
+
+    /// Extract a horizontal slice (eg a time slice) at a given sample index from all traces,
+    /// arranged on a grid of inline and crossline numbers using the lookup. See [`TimeSlice`]
+    /// for the layout of the data.
+    ///
+    /// If the sample index is out of range for any trace, an error is returned, unless
+    /// `fill_missing_with_nan` is set, in which case the value for that trace is NaN. If the
+    /// grid would have more than 2^28 cells, [`RsgError::GridTooLarge`] is returned.
+    pub fn extract_time_slice(
+        &self,
+        sample_idx: usize,
+        fill_missing_with_nan: bool,
+    ) -> Result<TimeSlice, RsgError> {
+        let (inline_range, inline_step) = slices::line_axis(self.lookup.keys().map(|k| k.inline));
+        let (crossline_range, crossline_step) =
+            slices::line_axis(self.lookup.keys().map(|k| k.crossline));
+        let columns = slices::axis_len(crossline_range, crossline_step, self.lookup.is_empty());
+        let rows = slices::axis_len(inline_range, inline_step, self.lookup.is_empty());
+        let cell_count = slices::grid_cells(rows, columns)?;
+
+        // Find the position of each trace on the grid.
+        let mut cells = Vec::with_capacity(self.lookup.len());
+        let mut indices = Vec::with_capacity(self.lookup.len());
+        for (key, i) in self.lookup.iter() {
+            let row = slices::axis_position(key.inline, inline_range, inline_step);
+            let column = slices::axis_position(key.crossline, crossline_range, crossline_step);
+            if let (Some(row), Some(column)) = (row, column) {
+                cells.push(row * columns + column);
+                indices.push((*i, sample_idx));
+            }
+        }
+
+        let samples = if fill_missing_with_nan {
+            self.get_samples_at_or_nan(&indices)?.0
+        } else {
+            self.get_samples_at(&indices)?
+        };
+
+        let mut data = vec![f32::NAN; cell_count];
+        for (cell, v) in cells.into_iter().zip(samples) {
+            data[cell] = v;
+        }
+
+        Ok(TimeSlice {
+            sample_idx,
+            inline_range,
+            inline_step,
+            crossline_range,
+            crossline_step,
+            data,
+        })
+    }
+
+    /// Get a vertical section along the inline with the given number. The traces are ordered by
+    /// crossline number, and there is a slot for each crossline on the grid of the survey.
+    ///
+    /// Slots for which there is no trace are left empty, unless `fill_missing_with_nan` is set,
+    /// in which case they have as many NaN values as the longest trace in the section.
+    /// If there are no traces on the inline, [`RsgError::LineNotFound`] is returned, and if
+    /// there would be more than 2^28 slots, [`RsgError::GridTooLarge`] is returned.
+    pub fn extract_inline_section(
+        &self,
+        inline_no: i32,
+        fill_missing_with_nan: bool,
+    ) -> Result<Section, RsgError> {
+        self.extract_section(LineKind::Inline, inline_no, fill_missing_with_nan)
+    }
+
+    /// Get a vertical section along the crossline with the given number. This works in the same
+    /// way as [`Self::extract_inline_section`], but the traces are ordered by inline number.
+    pub fn extract_crossline_section(
+        &self,
+        crossline_no: i32,
+        fill_missing_with_nan: bool,
+    ) -> Result<Section, RsgError> {
+        self.extract_section(LineKind::Crossline, crossline_no, fill_missing_with_nan)
+    }
+
+    fn extract_section(
+        &self,
+        kind: LineKind,
+        line_no: i32,
+        fill_missing_with_nan: bool,
+    ) -> Result<Section, RsgError> {
+        // The number of the line itself, and the position along it.
+        let split = |k: &LineKey| match kind {
+            LineKind::Inline => (k.inline, k.crossline),
+            LineKind::Crossline => (k.crossline, k.inline),
+        };
+        let (range, step) = slices::line_axis(self.lookup.keys().map(|k| split(k).1));
+        let slots = slices::grid_cells(slices::axis_len(range, step, false), 1)?;
+
+        let mut members = self
+            .lookup
+            .iter()
+            .map(|(k, i)| (split(k), *i))
+            .filter(|((this, _), _)| *this == line_no)
+            .map(|((_, other), i)| (other, i))
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            return Err(RsgError::LineNotFound { line: line_no });
+        }
+        members.sort_unstable();
+
+        let data = crate::read_data::get_traces_data_as_f32(
+            &self.data,
+            members
+                .iter()
+                .map(|(_, i)| self.load_trace(*i))
+                .collect::<Result<Vec<_>, _>>()?,
+            self.get_bin_header(),
+            self.get_settings(),
+        )?;
+        let missing_len = if fill_missing_with_nan {
+            data.iter().map(|d| d.len()).max().unwrap_or(0)
+        } else {
+            0
+        };
+
+        let line_numbers = (0..slots)
+            .map(|n| slices::axis_line(n, range, step))
+            .collect();
+        let mut traces = vec![Vec::new(); slots];
+        for ((n, _), d) in members.into_iter().zip(data) {
+            if let Some(slot) = slices::axis_position(n, range, step) {
+                traces[slot] = d;
+            }
+        }
+        for t in traces.iter_mut().filter(|t| t.is_empty()) {
+            t.resize(missing_len, f32::NAN);
+        }
+
+        Ok(Section {
+            kind,
+            line_no,
+            line_numbers,
+            traces,
+            sample_interval: self.get_bin_header().sample_interval,
+        })
+    }
+
+    /// Get the shape of the data which [`Self::export_flat_f32`] writes in the given order.
+    pub fn cube_shape(&self, order: CubeOrder) -> CubeShape {
+        let samples = (0..self.trace_count())
+            .filter_map(|i| self.trace_sample_count(i))
+            .max()
+            .unwrap_or(0);
+        match order {
+            CubeOrder::TraceMajor => CubeShape::Traces {
+                traces: self.trace_count(),
+                samples,
+            },
+            CubeOrder::Grid => {
+                let (inline_range, inline_step) =
+                    slices::line_axis(self.lookup.keys().map(|k| k.inline));
+                let (crossline_range, crossline_step) =
+                    slices::line_axis(self.lookup.keys().map(|k| k.crossline));
+                CubeShape::Grid {
+                    inline_range,
+                    inline_step,
+                    crossline_range,
+                    crossline_step,
+                    inlines: slices::axis_len(inline_range, inline_step, self.lookup.is_empty()),
+                    crosslines: slices::axis_len(
+                        crossline_range,
+                        crossline_step,
+                        self.lookup.is_empty(),
+                    ),
+                    samples,
+                }
+            }
+        }
+    }
+
+    /// Write the samples of all traces to `out` as little endian f32 values without any header,
+    /// so that they can be read as a flat array (eg. with `numpy.fromfile`), and return the
+    /// shape of the data (see [`CubeShape`]).
+    ///
+    /// The samples are read as with [`Self::get_trace_data_as_f32`], so the `step_by`, trace
+    /// weighting and gain settings apply. The traces are written one at a time, so `out` should
+    /// be buffered if it is a file.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::{CubeOrder, SegyFile};
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// let mut out = Vec::new();
+    /// let shape = file.export_flat_f32(&mut out, CubeOrder::TraceMajor).unwrap();
+    /// assert_eq!(out.len(), shape.value_count() * 4);
+    /// let first = f32::from_le_bytes([out[0], out[1], out[2], out[3]]);
+    /// assert_eq!(first, file.get_trace_data_as_f32(0).unwrap()[0]);
+    /// ```
+    pub fn export_flat_f32(
+        &self,
+        mut out: impl std::io::Write,
+        order: CubeOrder,
+    ) -> Result<CubeShape, RsgError> {
+        let shape = self.cube_shape(order);
+        self.write_cube(&mut out, &shape)?;
+        Ok(shape)
+    }
+
+    /// Write the samples of all traces to `out` as a NumPy `.npy` file, so that they can be
+    /// loaded with `numpy.load`. This works like [`Self::export_flat_f32`], but the data is
+    /// preceded by a header which gives its type and shape.
+    #[cfg(feature = "npy")]
+    pub fn export_npy(
+        &self,
+        mut out: impl std::io::Write,
+        order: CubeOrder,
+    ) -> Result<CubeShape, RsgError> {
+        let shape = self.cube_shape(order);
+        export::write_npy_header(&mut out, &shape)?;
+        self.write_cube(&mut out, &shape)?;
+        Ok(shape)
+    }
+
+    /// Read the samples of all traces into one [`TraceMatrix`] with a row for each trace, as
+    /// with [`Self::get_trace_data_as_f32`] (so the `step_by`, trace weighting and gain settings
+    /// apply).
+    ///
+    /// The rows are as long as the longest trace. If the traces do not all have the same number
+    /// of samples, the shorter ones are filled up with NaN if `pad_with_nan` is set, and an
+    /// [`RsgError::RaggedTraces`] is returned otherwise. An [`RsgError::AllocationOverBudget`]
+    /// is returned before anything is read if the matrix would take more than `max_bytes`.
+    ///
+    /// With the `rayon` feature, the traces are decoded in parallel.
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// let matrix = file.read_all_traces_f32(1 << 30, false).unwrap();
+    /// assert_eq!(matrix.shape()[0], file.trace_count());
+    /// assert_eq!(matrix.row(1).unwrap(), &file.get_trace_data_as_f32(1).unwrap()[..]);
+    /// ```
+    pub fn read_all_traces_f32(
+        &self,
+        max_bytes: usize,
+        pad_with_nan: bool,
+    ) -> Result<TraceMatrix, RsgError> {
+        let rows = self.trace_count();
+        let counts = (0..rows)
+            .map(|i| self.trace_sample_count(i).unwrap_or(0))
+            .collect::<Vec<_>>();
+        let columns = counts.iter().copied().max().unwrap_or(0);
+        if !pad_with_nan {
+            if let Some(trace) = counts.iter().position(|c| *c != counts[0]) {
+                return Err(RsgError::RaggedTraces {
+                    trace,
+                    samples: counts[trace],
+                    expected: counts[0],
+                });
+            }
+        }
+        let bytes = rows
+            .checked_mul(columns)
+            .and_then(|values| values.checked_mul(std::mem::size_of::<f32>()))
+            .unwrap_or(usize::MAX);
+        if bytes > max_bytes {
+            return Err(RsgError::AllocationOverBudget {
+                bytes,
+                budget: max_bytes,
+            });
+        }
+        if columns == 0 {
+            return Ok(TraceMatrix::new(Vec::new(), rows, columns));
+        }
+
+        let mut data = vec![f32::NAN; rows * columns];
+        let fill_row = |(i, row): (usize, &mut [f32])| -> Result<(), RsgError> {
+            let trace = self.get_trace_data_as_f32(i)?;
+            let len = trace.len().min(columns);
+            row[..len].copy_from_slice(&trace[..len]);
+            Ok(())
+        };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            data.par_chunks_mut(columns)
+                .enumerate()
+                .try_for_each(fill_row)?;
+        }
+        #[cfg(not(feature = "rayon"))]
+        data.chunks_mut(columns)
+            .enumerate()
+            .try_for_each(fill_row)?;
+        Ok(TraceMatrix::new(data, rows, columns))
+    }
+
+    /// The inner function of [`Self::export_flat_f32`].
+    fn write_cube<W: std::io::Write>(
+        &self,
+        out: &mut W,
+        shape: &CubeShape,
+    ) -> Result<(), RsgError> {
+        let mut bytes = Vec::new();
+        let mut write_trace = |i: Option<usize>, samples: usize| -> Result<(), RsgError> {
+            let mut data = match i {
+                Some(i) => self.get_trace_data_as_f32(i)?,
+                None => Vec::new(),
+            };
+            data.resize(samples, f32::NAN);
+            bytes.clear();
+            bytes.extend(data.iter().flat_map(|v| v.to_le_bytes()));
+            out.write_all(&bytes)?;
+            Ok(())
+        };
+        match *shape {
+            CubeShape::Traces { traces, samples } => {
+                for i in 0..traces {
+                    write_trace(Some(i), samples)?;
+                }
+            }
+            CubeShape::Grid {
+                inline_range,
+                inline_step,
+                crossline_range,
+                crossline_step,
+                inlines,
+                crosslines,
+                samples,
+            } => {
+                for row in 0..inlines {
+                    for column in 0..crosslines {
+                        let inline = slices::axis_line(row, inline_range, inline_step);
+                        let xline = slices::axis_line(column, crossline_range, crossline_step);
+                        let key = LineKey::new(inline, xline);
+                        write_trace(self.lookup.get(&key).copied(), samples)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterate through the traces, which are given in the same way as with [`Self::get_trace`].
+    pub fn traces_iter(&self) -> TracesIter<'_, Self> {
+        SegyHandle::traces_iter(self)
     }
 
     /// Get the indices for the traces with the minimum and maximum values for the crossline
@@ -434,12 +2049,12 @@ impl SegyFile {
         let min = self
             .lookup
             .iter()
-            .min_by_key(|(t, _i)| t[0])
+            .min_by_key(|(t, _i)| t.crossline)
             .map(|(_t, i)| i);
         let max = self
             .lookup
             .iter()
-            .max_by_key(|(t, _i)| t[0])
+            .max_by_key(|(t, _i)| t.crossline)
             .map(|(_t, i)| i);
         match (min, max) {
             (Some(min), Some(max)) => Some([*min, *max]),
@@ -453,12 +2068,12 @@ impl SegyFile {
         let min = self
             .lookup
             .iter()
-            .min_by_key(|(t, _i)| t[1])
+            .min_by_key(|(t, _i)| t.inline)
             .map(|(_t, i)| i);
         let max = self
             .lookup
             .iter()
-            .max_by_key(|(t, _i)| t[1])
+            .max_by_key(|(t, _i)| t.inline)
             .map(|(_t, i)| i);
         match (min, max) {
             (Some(min), Some(max)) => Some([*min, *max]),
@@ -470,17 +2085,15 @@ impl SegyFile {
     /// number. The traces can then be retrieved with [`Self::get_trace`].
     pub fn get_trace_idx_for_x_ensemble_min_max(&self) -> Option<[usize; 2]> {
         let min = self
-            .traces
-            .iter()
+            .trace_keys()
             .enumerate()
-            .min_by_key(|(_i, t)| t.get_header().x_ensemble)
-            .map(|(i, _t)| i);
+            .min_by_key(|(_i, k)| k.x_ensemble)
+            .map(|(i, _k)| i);
         let max = self
-            .traces
-            .iter()
+            .trace_keys()
             .enumerate()
-            .max_by_key(|(_i, t)| t.get_header().x_ensemble)
-            .map(|(i, _t)| i);
+            .max_by_key(|(_i, k)| k.x_ensemble)
+            .map(|(i, _k)| i);
         match (min, max) {
             (Some(min), Some(max)) => Some([min, max]),
             _ => None,
@@ -491,28 +2104,83 @@ impl SegyFile {
     /// number. The traces can then be retrieved with [`Self::get_trace`].
     pub fn get_trace_idx_for_y_ensemble_min_max(&self) -> Option<[usize; 2]> {
         let min = self
-            .traces
-            .iter()
+            .trace_keys()
             .enumerate()
-            .min_by_key(|(_i, t)| t.get_header().y_ensemble)
-            .map(|(i, _t)| i);
+            .min_by_key(|(_i, k)| k.y_ensemble)
+            .map(|(i, _k)| i);
         let max = self
-            .traces
-            .iter()
+            .trace_keys()
             .enumerate()
-            .max_by_key(|(_i, t)| t.get_header().y_ensemble)
-            .map(|(i, _t)| i);
+            .max_by_key(|(_i, k)| k.y_ensemble)
+            .map(|(i, _k)| i);
         match (min, max) {
             (Some(min), Some(max)) => Some([min, max]),
             _ => None,
         }
     }
 
+    /// This function consumes the instance of [`SegyFile`] and returns its metadata, including
+    /// the settings and the raw binary header. This can be used to write a derived file with
+    /// the same headers (eg. with `giga_segy_out::SegyFile::create_file_from_metadata`).
+    ///
+    /// NB: As with [`SegyFile::into_parts`], the internal mapping is discarded.
+    pub fn into_metadata(self) -> SegyMetadata<SegySettings> {
+        self.metadata
+    }
+
+    /// This function consumes the instance of [`SegyFile`] and returns its lookup, which gives
+    /// the index of a trace for its inline and crossline numbers (see [`Self::lines_iter`]).
+    ///
+    /// NB: As with [`SegyFile::into_parts`], the internal mapping is discarded.
+    pub fn into_lookup(self) -> HashMap<LineKey, usize> {
+        self.lookup
+    }
+
     /// This function consumes the instance of [`SegyFile`] returning all
-    /// metadata and header data in an efficient manner.
+    /// metadata and header data in an efficient manner. The lookup of the traces by their line
+    /// numbers is only kept if `with_lookup` is set.
     ///
     /// NB: The internal mapping is discarded in the process, so once this function is called,
-    /// internal trace data can no longer be accessed.
+    /// internal trace data can no longer be accessed. The traces are only given if their
+    /// headers are kept in full (see [`SegySettings::set_compact_headers`] and
+    /// [`SegySettings::set_dedup_headers`]).
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_in::SegyFile;
+    ///
+    /// let mut name = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// name.pop();
+    /// name.push("testdata");
+    /// name.push("DutchMiniHead.sgy");
+    ///
+    /// let file = SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    /// let trace_count = file.trace_count();
+    /// let parts = file.into_parts(true);
+    /// assert_eq!(parts.traces.len(), trace_count);
+    /// assert_eq!(parts.lookup.map(|l| l.len()), Some(trace_count));
+    /// ```
+    pub fn into_parts(self, with_lookup: bool) -> SegyFileParts {
+        let SegyFile {
+            metadata,
+            traces,
+            lookup,
+            ..
+        } = self;
+        let parts = metadata.into_parts();
+        SegyFileParts {
+            tape_label: parts.tape_label,
+            text_header: parts.text_header,
+            extended_headers: parts.extended_headers,
+            bin_header: parts.bin_header,
+            traces,
+            lookup: if with_lookup { Some(lookup) } else { None },
+        }
+    }
+
+    /// This function consumes the instance of [`SegyFile`] in the same way as
+    /// [`Self::into_parts`], but returns the parts (without the lookup) as a tuple.
+    #[deprecated(note = "Use `into_parts`, which names the parts.")]
     pub fn deconstruct(
         self,
     ) -> (
@@ -522,16 +2190,29 @@ impl SegyFile {
         BinHeader,
         Vec<Trace>,
     ) {
-        let SegyFile {
-            metadata, traces, ..
-        } = self;
-        let (tape_label, text_header, extended_headers, bin_header, _) = metadata.deconstruct();
+        let parts = self.into_parts(false);
         (
-            tape_label,
-            text_header,
-            extended_headers,
-            bin_header,
-            traces,
+            parts.tape_label,
+            parts.text_header,
+            parts.extended_headers,
+            parts.bin_header,
+            parts.traces,
         )
     }
 }
+
+impl SegyHandle for SegyFile {
+    type Settings = SegySettings;
+
+    fn get_metadata(&self) -> &SegyMetadata<SegySettings> {
+        &self.metadata
+    }
+
+    fn trace_count(&self) -> usize {
+        SegyFile::trace_count(self)
+    }
+
+    fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        SegyFile::get_trace(self, i)
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the straight line which is fitted through the ensemble coordinates
+//! of the traces of a 2D line, so that the traces of a crooked line can be ordered by their
+//! distance along it (see [`crate::SegyFile::order_traces_by_line_distance`]).
+use giga_segy_core::TraceHeader;
+
+/// The straight line which best fits the ensemble (CDP) coordinates of the traces of a file,
+/// as returned by [`crate::SegyFile::line_geometry`].
+///
+/// The coordinates are scaled with the coordinate scalar of each trace header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineGeometry {
+    /// The point on the line where the traces start, which is the projection of the trace that
+    /// is furthest back along the line.
+    pub origin: [f64; 2],
+    /// The direction of the line as a unit vector. It points from the first trace in the file
+    /// towards the last.
+    pub direction: [f64; 2],
+    /// The distance along the line from the first trace to the last (after projection).
+    pub length: f64,
+}
+
+impl LineGeometry {
+    /// Get the distance of the projection of a point along the line from the [`Self::origin`].
+    pub fn distance(&self, point: [f64; 2]) -> f64 {
+        (point[0] - self.origin[0]) * self.direction[0]
+            + (point[1] - self.origin[1]) * self.direction[1]
+    }
+
+    /// Fit a line through the points (in the order of the traces) with a principal component
+    /// analysis. This gives `None` if there are fewer than two points, if all points are the
+    /// same or if a point is not finite.
+    pub(crate) fn fit(points: &[[f64; 2]]) -> Option<Self> {
+        if points.len() < 2 || points.iter().flatten().any(|c| !c.is_finite()) {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean = [
+            points.iter().map(|p| p[0]).sum::<f64>() / n,
+            points.iter().map(|p| p[1]).sum::<f64>() / n,
+        ];
+        let (mut sxx, mut syy, mut sxy) = (0., 0., 0.);
+        for p in points {
+            let (dx, dy) = (p[0] - mean[0], p[1] - mean[1]);
+            sxx += dx * dx;
+            syy += dy * dy;
+            sxy += dx * dy;
+        }
+        if sxx + syy == 0. {
+            return None;
+        }
+        let angle = 0.5 * (2. * sxy).atan2(sxx - syy);
+        let axis = [angle.cos(), angle.sin()];
+
+        // Orient the line so that it runs from the first trace towards the last.
+        let along = |p: &[f64; 2]| (p[0] - mean[0]) * axis[0] + (p[1] - mean[1]) * axis[1];
+        let (first, last) = (along(&points[0]), along(&points[points.len() - 1]));
+        let sign = if last < first { -1. } else { 1. };
+        let direction = [axis[0] * sign, axis[1] * sign];
+
+        let projected = points.iter().map(|p| along(p) * sign).collect::<Vec<_>>();
+        let min = projected.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = projected.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Some(LineGeometry {
+            origin: [mean[0] + min * direction[0], mean[1] + min * direction[1]],
+            direction,
+            length: max - min,
+        })
+    }
+}
+
+/// Get the ensemble coordinates of a trace header, scaled with its coordinate scalar.
+pub(crate) fn scaled_ensemble(header: &TraceHeader) -> [f64; 2] {
+    [
+        header.apply_coordinate_scalar(header.x_ensemble),
+        header.apply_coordinate_scalar(header.y_ensemble),
+    ]
+}
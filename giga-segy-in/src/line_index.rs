@@ -0,0 +1,130 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`LineKey`] by which the traces of a file are looked up, and
+//! the sorted index of inline and crossline numbers which is used for nearest neighbour and
+//! range queries on the traces of a file.
+use giga_segy_core::TraceHeader;
+
+use std::collections::{BTreeMap, HashMap};
+
+/// The inline and crossline numbers of a trace, which are the key of the lookup of a file (see
+/// [`crate::SegyFile::lines_iter`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LineKey {
+    pub inline: i32,
+    pub crossline: i32,
+}
+
+impl LineKey {
+    pub fn new(inline: i32, crossline: i32) -> Self {
+        LineKey { inline, crossline }
+    }
+
+    /// Get the inline and crossline numbers of a trace header.
+    pub fn from_header(header: &TraceHeader) -> Self {
+        Self::new(header.inline_no, header.crossline_no)
+    }
+}
+
+/// The traces of a file, sorted by inline and then crossline number.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LineIndex {
+    traces: BTreeMap<(i32, i32), usize>,
+}
+
+impl LineIndex {
+    /// Build the index from the lookup of a file.
+    pub(crate) fn new(lookup: &HashMap<LineKey, usize>) -> Self {
+        let traces = lookup
+            .iter()
+            .map(|(key, i)| ((key.inline, key.crossline), *i))
+            .collect();
+        LineIndex { traces }
+    }
+
+    /// Find the trace which is closest to the given inline and crossline numbers, and return
+    /// its index along with the squared distance in line numbers. Ties go to the trace which
+    /// comes first in the file.
+    pub(crate) fn nearest(&self, inline: i32, xline: i32) -> Option<(usize, i64)> {
+        let mut best: Option<(i64, usize)> = None;
+
+        // Walk through the inlines outwards from the target, in both directions, until they
+        // are further away than the best trace so far.
+        let mut up = self.next_inline_from(inline);
+        while let Some(il) = up {
+            if matches!(best, Some((d, _)) if squared(il, inline) > d) {
+                break;
+            }
+            best = self.nearest_on_inline(il, inline, xline, best);
+            up = il.checked_add(1).and_then(|il| self.next_inline_from(il));
+        }
+        let mut down = self.previous_inline_before(inline);
+        while let Some(il) = down {
+            if matches!(best, Some((d, _)) if squared(il, inline) > d) {
+                break;
+            }
+            best = self.nearest_on_inline(il, inline, xline, best);
+            down = self.previous_inline_before(il);
+        }
+        best.map(|(d, i)| (i, d))
+    }
+
+    /// Get the indices of the traces within the given (inclusive) ranges of inline and
+    /// crossline numbers, in the order in which they appear in the file.
+    pub(crate) fn in_rect(&self, inline_range: [i32; 2], xline_range: [i32; 2]) -> Vec<usize> {
+        let mut found = Vec::new();
+        if inline_range[0] > inline_range[1] || xline_range[0] > xline_range[1] {
+            return found;
+        }
+        // Jump from inline to inline, so that crosslines outside the range are not visited.
+        let mut next = self.next_inline_from(inline_range[0]);
+        while let Some(il) = next.filter(|il| *il <= inline_range[1]) {
+            let row = self
+                .traces
+                .range((il, xline_range[0])..=(il, xline_range[1]));
+            found.extend(row.map(|(_, i)| *i));
+            next = il.checked_add(1).and_then(|il| self.next_inline_from(il));
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// Get the smallest inline number which is at least `inline`.
+    fn next_inline_from(&self, inline: i32) -> Option<i32> {
+        self.traces
+            .range((inline, i32::MIN)..)
+            .next()
+            .map(|((il, _), _)| *il)
+    }
+
+    /// Get the largest inline number which is smaller than `inline`.
+    fn previous_inline_before(&self, inline: i32) -> Option<i32> {
+        self.traces
+            .range(..(inline, i32::MIN))
+            .next_back()
+            .map(|((il, _), _)| *il)
+    }
+
+    /// Check the traces on inline `il` which are closest to `xline` on either side against the
+    /// best trace so far, which is given as its squared distance and index.
+    fn nearest_on_inline(
+        &self,
+        il: i32,
+        inline: i32,
+        xline: i32,
+        mut best: Option<(i64, usize)>,
+    ) -> Option<(i64, usize)> {
+        let above = self.traces.range((il, xline)..=(il, i32::MAX)).next();
+        let below = self.traces.range((il, i32::MIN)..(il, xline)).next_back();
+        for ((_, xl), i) in above.into_iter().chain(below) {
+            let candidate = (squared(il, inline).saturating_add(squared(*xl, xline)), *i);
+            best = Some(best.map_or(candidate, |b| b.min(candidate)));
+        }
+        best
+    }
+}
+
+/// The squared difference of two line numbers.
+fn squared(a: i32, b: i32) -> i64 {
+    let d = a as i64 - b as i64;
+    d.saturating_mul(d)
+}
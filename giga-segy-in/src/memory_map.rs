@@ -1,253 +1,1488 @@
 //! This submodule exists to map the file as a "memory map" and then allow reading of the data.
+use giga_segy_core::decoder::DatumLen;
+use giga_segy_core::enums::{
+    AuxPolicy, CoordErrorPolicy, FixedLengthTraces, MapPopulation, OrderTraceBy, SampleFormatCode,
+    TextEncoding, TextHeaderLayout,
+};
 use giga_segy_core::errors::*;
-use giga_segy_core::{BinHeader, TapeLabel, TraceHeader};
-use giga_segy_core::{SegySettings, Trace};
+use giga_segy_core::progress::{ProgressPhase, SegyProgress, PROGRESS_INTERVAL};
+use giga_segy_core::EXTENDED_SAMPLE_COUNT_BYTE_LOCATION;
+use giga_segy_core::{stanza, EffectiveParams, SegyMetadata, SegySettings, Trace, TraceHeader};
+use giga_segy_core::{BinHeader, TapeLabel};
 use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TEXT_HEADER_LEN, TRACE_HEADER_LEN};
 
-use super::convert_headers::{HeaderFromBytes, TraceHeaderFromBytes};
+use super::convert_headers::{keep_original_line_numbers, parse_trace_header, HeaderFromBytes};
+use crate::compact::{CompactTrace, TraceKey};
+use crate::dedup::DedupTraces;
+use crate::line_index::LineKey;
+use crate::read_data::{custom_decoder, datum_len};
 
 use encoding8::ebcdic::to_ascii;
+#[cfg(unix)]
+use memmap2::Advice;
 use memmap2::{Mmap, MmapOptions};
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::ops::{ControlFlow, Deref, Range};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The number of characters of text at the start of the trace data from which
+/// [`check_text_in_data`] assumes that there is a payload before the data.
+pub(crate) const MIN_TEXT_IN_DATA: usize = 16;
+
+/// The number of windows which are kept mapped (apart from the head) when a file is mapped in
+/// windows (see [`SegySettings::set_max_map_window`]).
+pub(crate) const CACHED_WINDOWS: usize = 4;
+
+/// The number of bytes which are always in the head of a file which is mapped in windows, so
+/// that the text and binary headers can be read from it.
+const MIN_HEAD_LEN: usize = TAPE_LABEL_LEN + TEXT_HEADER_LEN + BIN_HEADER_LEN;
+
 /// This structure represents a memory map with an underlying SEG-Y file handle.
+///
+/// The whole file is mapped at once, unless the settings cap the size of a mapping (see
+/// [`SegySettings::set_max_map_window`]). Then only the head of the file stays mapped, and the
+/// rest is mapped in windows when it is needed.
 pub struct MappedSegY {
-    pub(crate) map: Mmap,
-    _file: std::fs::File,
+    /// The whole file, or its first window if it is mapped in windows.
+    head: Mmap,
+    /// The windows of the rest of the file, if it is mapped in windows.
+    windows: Option<Windows>,
+    len: usize,
+    /// The position of the text header (or the tape label), which is not zero if something
+    /// else was found in front of it (see [`SegySettings::set_scan_for_text_header`]).
+    offset: usize,
+    file: std::fs::File,
+    /// Why the file could not be locked in memory, if the settings asked for it (see
+    /// [`SegySettings::set_map_population`]).
+    map_warning: Option<String>,
 }
 
-impl MappedSegY {
-    /// Create a mapped Seg-Y structure with a Memory map and underlying file handle.
-    pub(crate) fn new(file_name: &str) -> Result<MappedSegY, RsgError> {
-        // Map the file.
-        let (map, file) = map_file_to_memory(file_name)?;
+/// The windows of a file which is mapped in windows.
+struct Windows {
+    size: usize,
+    /// Whether the windows are populated when they are mapped.
+    populate: bool,
+    /// The windows which were used most recently, with the most recent one last.
+    recent: Mutex<Vec<Arc<Window>>>,
+}
 
-        // Perform sanity check to make sure file is big enough to be SEG-Y.
-        let too_short_with_label = has_label(&map)?
-            && (map.len() <= TEXT_HEADER_LEN + BIN_HEADER_LEN + TRACE_HEADER_LEN + TAPE_LABEL_LEN);
+/// A mapped part of a file, starting at the byte `start`.
+pub(crate) struct Window {
+    start: usize,
+    map: Mmap,
+}
 
-        let too_short_without_label =
-            !has_label(&map)? && (map.len() <= TEXT_HEADER_LEN + BIN_HEADER_LEN + TRACE_HEADER_LEN);
+impl Window {
+    fn contains(&self, range: &Range<usize>) -> bool {
+        range.start >= self.start && range.end <= self.start + self.map.len()
+    }
 
-        if too_short_with_label || too_short_without_label {
-            return Err(RsgError::FileTooShort);
+    fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self.map[(range.start - self.start)..(range.end - self.start)]
+    }
+}
+
+impl Windows {
+    /// Get a window which contains `range`, which is mapped from the start of the range if none
+    /// of the recent windows contains it. Since the traces are usually read in order, this
+    /// makes the windows slide through the file.
+    fn window_for(
+        &self,
+        file: &std::fs::File,
+        range: &Range<usize>,
+        file_len: usize,
+    ) -> Result<Arc<Window>, RsgError> {
+        let mut recent = lock(&self.recent);
+        if let Some(i) = recent.iter().rposition(|w| w.contains(range)) {
+            let window = recent.remove(i);
+            recent.push(window.clone());
+            return Ok(window);
         }
-        // Return Mapping.
-        Ok(MappedSegY { map, _file: file })
+        let len = self.size.max(range.len()).min(file_len - range.start);
+        let mut options = MmapOptions::new();
+        options.offset(range.start as u64).len(len);
+        if self.populate {
+            options.populate();
+        }
+        let map = unsafe { options.map(file).map_err(RsgError::MapFile)? };
+        let window = Arc::new(Window {
+            start: range.start,
+            map,
+        });
+        if recent.len() >= CACHED_WINDOWS {
+            recent.remove(0);
+        }
+        recent.push(window.clone());
+        Ok(window)
     }
+}
+
+/// Some bytes of a [`MappedSegY`], which are either borrowed from its head or kept in a window.
+pub(crate) enum MapSlice<'a> {
+    Borrowed(&'a [u8]),
+    Window(Arc<Window>, Range<usize>),
+}
 
-    /// Determine whether we have a label or not.
-    fn has_label(&self) -> Result<bool, RsgError> {
-        has_label(&self.map)
+impl<'a> MapSlice<'a> {
+    /// Turn the bytes into a [`Cow`], which borrows them from the head of the file, and copies
+    /// them out of a window (so that the window need not stay mapped).
+    pub(crate) fn into_cow(self) -> Cow<'a, [u8]> {
+        match self {
+            MapSlice::Borrowed(bytes) => Cow::Borrowed(bytes),
+            MapSlice::Window(window, range) => Cow::Owned(window.slice(range).to_vec()),
+        }
     }
+}
 
-    /// Get the bytes of a label.
-    pub(crate) fn get_tape_label(
-        &self,
+impl Deref for MapSlice<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            MapSlice::Borrowed(bytes) => bytes,
+            MapSlice::Window(window, range) => window.slice(range.clone()),
+        }
+    }
+}
+
+/// Lock a mutex, which is fine even if another thread panicked while holding it, since the
+/// values behind it (the windows of a file, or the caches of a [`crate::SegyFile`]) are never
+/// left half changed.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl MappedSegY {
+    /// Create a mapped Seg-Y structure with a Memory map and underlying file handle, unless the
+    /// file is larger than the mapping budget (see [`SegySettings::set_mapping_budget`]). The
+    /// file is mapped in windows if the settings ask for it.
+    pub(crate) fn new(file_name: &str, settings: &SegySettings) -> Result<MappedSegY, RsgError> {
+        // Map the file.
+        let mut segy = Self::map(file_name, settings)?;
+        segy.offset = find_headers_start(&segy.head, settings)?;
+
+        // Perform sanity check to make sure file is big enough to be SEG-Y.
+        check_file_length(segy.head(), segy.len() - segy.offset)?;
+
+        // Return Mapping.
+        Ok(segy)
+    }
+
+    /// Create a mapped structure for a file which consists only of traces (such as a Seismic
+    /// Unix file), so that it only has to be as long as a trace header.
+    #[cfg(feature = "su")]
+    pub(crate) fn new_headerless(
+        file_name: &str,
         settings: &SegySettings,
-    ) -> Result<Option<TapeLabel>, RsgError> {
-        if self.has_label()? {
-            let label = TapeLabel::from_bytes(&self.map[0..TAPE_LABEL_LEN], settings)?;
-            Ok(Some(label))
-        } else {
-            Ok(None)
+    ) -> Result<MappedSegY, RsgError> {
+        let segy = Self::map(file_name, settings)?;
+        if segy.len() < TRACE_HEADER_LEN {
+            return Err(RsgError::FileTooShort);
         }
+        Ok(segy)
     }
 
-    /// This function gets the bytes corresponding to the text header and attempts to parse them
-    /// into a string. It is OK for rust, but less helpful for C.
-    pub(crate) fn get_text_header(&self) -> Result<String, RsgError> {
-        let start = start_byte(&self.map, 0, TAPE_LABEL_LEN)?;
+    fn map(file_name: &str, settings: &SegySettings) -> Result<MappedSegY, RsgError> {
+        let window = settings.get_max_map_window();
+        #[cfg(test)]
+        let window = test_map_window(window);
+        let population = settings.get_map_population();
+        let (head, file, len) = map_file_to_memory(
+            file_name,
+            settings.get_mapping_budget(),
+            window,
+            MIN_HEAD_LEN.saturating_add(settings.get_scan_for_text_header()),
+            population,
+        )?;
+        let map_warning = apply_population(&head, population, settings.get_lock_budget());
+        let windows = if head.len() < len {
+            Some(Windows {
+                size: window,
+                populate: population != MapPopulation::Lazy,
+                recent: Mutex::new(Vec::with_capacity(CACHED_WINDOWS)),
+            })
+        } else {
+            None
+        };
+        Ok(MappedSegY {
+            head,
+            windows,
+            len,
+            offset: 0,
+            file,
+            map_warning,
+        })
+    }
 
-        // Convert to the right sort of encoding.
-        let header_bytes = convert_bytes_to_ascii(&self.map, start, TEXT_HEADER_LEN);
-        Ok(String::from_utf8_lossy(&header_bytes).to_string())
+    /// Get the reason why the file could not be locked in memory, if the settings asked for it
+    /// (see [`SegySettings::set_map_population`]).
+    pub(crate) fn map_warning(&self) -> Option<&str> {
+        self.map_warning.as_deref()
     }
 
-    /// Attempts to get the bytes corresponding to the binary
-    pub(crate) fn get_bin_header(&self, settings: &SegySettings) -> Result<BinHeader, RsgError> {
-        let start = start_byte(&self.map, TEXT_HEADER_LEN, TAPE_LABEL_LEN)?;
-        BinHeader::from_bytes(&self.map[start..(start + BIN_HEADER_LEN)], settings)
+    /// Get the length of the file.
+    pub(crate) fn len(&self) -> usize {
+        self.len
     }
 
-    /// This attempts to get extended text headers. NB: Needs an input of how many headers there are.
-    /// NB: We require a parsed binary header or foreknowledge of some kind which tells us just
-    /// how many headers we have.
-    /// NB2: It is possible, albeit unlikely, that this function will return an `Ok(stuff)` even
-    /// if it goes past the end of the
-    pub(crate) fn get_extended_text_headers(&self, count: u32) -> Result<Vec<String>, RsgError> {
-        // Shortcut the process if we have nothing to give.
-        if count == 0 {
-            return Ok(Vec::with_capacity(0));
-        }
+    /// Get the beginning of the file from the tape label or text header on, which contains at
+    /// least the text and binary headers (if the file is long enough), or the rest of the file
+    /// if it is not mapped in windows.
+    pub(crate) fn head(&self) -> &[u8] {
+        &self.head[self.offset..]
+    }
 
-        let default_start = TEXT_HEADER_LEN + BIN_HEADER_LEN;
-        let start_byte = start_byte(&self.map, default_start, TAPE_LABEL_LEN)?;
-        let count = count as usize;
+    /// Get the position of the tape label or text header in the file (see [`Self::head`]).
+    pub(crate) fn headers_start(&self) -> usize {
+        self.offset
+    }
 
-        // A sanity check to shortcut us if the file is too short.
-        if self.map.len() < start_byte + count * TEXT_HEADER_LEN {
-            return Err(RsgError::SEGYTooShort);
+    /// Get a range of bytes of the file, which is mapped if it is not already. An
+    /// [`RsgError::ShortSEGY`] is returned if the range goes past the end of the file.
+    pub(crate) fn get(&self, range: Range<usize>) -> Result<MapSlice<'_>, RsgError> {
+        if range.end > self.len || range.start > range.end {
+            return Err(RsgError::ShortSEGY {
+                a: self.len,
+                b: range.end,
+            });
         }
-
-        let mut extra_headers = Vec::with_capacity(count);
-        for i in 0..count {
-            let start = start_byte + i * TEXT_HEADER_LEN;
-            let header_bytes = convert_bytes_to_ascii(&self.map, start, TEXT_HEADER_LEN);
-            let header = String::from_utf8_lossy(&header_bytes).to_string();
-            extra_headers.push(header);
+        match self.windows {
+            Some(ref windows) if range.end > self.head.len() => {
+                let window = windows.window_for(&self.file, &range, self.len)?;
+                Ok(MapSlice::Window(window, range))
+            }
+            _ => Ok(MapSlice::Borrowed(&self.head[range])),
         }
+    }
 
-        Ok(extra_headers)
+    /// Get the number of windows which are mapped apart from the head, which is never more than
+    /// [`CACHED_WINDOWS`] (plus those which are still held as a [`MapSlice`]).
+    #[cfg(test)]
+    pub(crate) fn mapped_window_count(&self) -> usize {
+        self.windows.as_ref().map_or(0, |w| lock(&w.recent).len())
     }
 
     /// This function retrieves the metadata for the headers, which includes the trace headers
     /// and the start and end point of each trace in the form of a `Trace` instance.
-    /// The `extended_header_count` should come from the actual extended headers.
+    /// The `extended_header_count` should come from the actual extended headers. The traces
+    /// are kept as given by [`HeaderStorage::from_settings`].
+    ///
+    /// Before the scan, the binary header is checked (see [`check_before_scan`]), so the sample
+    /// format of `bin_header` may be replaced.
+    /// Any problems that are dealt with (such as this) are added to `warnings`.
+    ///
+    /// The progress of the scan is reported to a callback every [`PROGRESS_INTERVAL`] traces
+    /// (and at the end). If the callback returns `ControlFlow::Break`, the scan is stopped with
+    /// [`RsgError::Cancelled`].
     pub(crate) fn get_metadata_for_traces(
         &self,
         bin_header: &mut BinHeader,
         extended_header_count: usize,
         settings: &SegySettings,
-    ) -> Result<Vec<Trace>, RsgError> {
-        // If all traces have the same length, our task is quite easy. In theory.
-        let datum_size = bin_header.sample_format_code.datum_byte_length();
-        let default_start = TEXT_HEADER_LEN * (extended_header_count + 1) + BIN_HEADER_LEN;
-        let start_byte = start_byte(&self.map, default_start, TAPE_LABEL_LEN)?;
-
-        let mut traces: Vec<Trace> = Vec::new();
-        let mut last_header_err = None;
-        let max_trace_length = settings.get_max_trace_length_by_override_dimensions();
-        let max_trace_count = settings.get_max_trace_count_by_override_dimensions();
+        progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+        warnings: &mut Vec<String>,
+    ) -> Result<ScannedTraces, RsgError> {
+        check_before_scan(
+            self.head(),
+            self.len - self.offset,
+            bin_header,
+            extended_header_count,
+            settings,
+            warnings,
+        )?;
+        self.scan_traces(
+            bin_header,
+            extended_header_count,
+            None,
+            settings,
+            progress,
+            warnings,
+        )
+    }
+
+    /// This works in the same way as [`Self::get_metadata_for_traces`] for a file which consists
+    /// only of traces (such as a Seismic Unix file), so that the first trace header is at the
+    /// start of the file. The `bin_header` stands in for the headers which the file does not
+    /// have.
+    #[cfg(feature = "su")]
+    pub(crate) fn get_metadata_for_headerless_traces(
+        &self,
+        bin_header: &mut BinHeader,
+        settings: &SegySettings,
+        progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+        warnings: &mut Vec<String>,
+    ) -> Result<ScannedTraces, RsgError> {
+        bidx_warnings(settings, warnings);
+        self.scan_traces(bin_header, 0, Some(0), settings, progress, warnings)
+    }
+
+    /// Scan all of the trace headers of the file, starting at `first_header` if it is given
+    /// instead of after the headers.
+    fn scan_traces(
+        &self,
+        bin_header: &mut BinHeader,
+        extended_header_count: usize,
+        first_header: Option<usize>,
+        settings: &SegySettings,
+        progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+        warnings: &mut Vec<String>,
+    ) -> Result<ScannedTraces, RsgError> {
+        let mut scanner = TraceScanner::new(
+            self.head(),
+            self.offset,
+            self.len,
+            bin_header,
+            extended_header_count,
+            settings,
+            HeaderStorage::from_settings(settings),
+        )?;
+        if let Some(first_header) = first_header {
+            scanner.start_at(first_header);
+        }
+        let mut scan = || {
+            while let Some(b_range) = scanner.next_header_range() {
+                if scanner.i % PROGRESS_INTERVAL == 0 {
+                    scanner.report(progress)?;
+                }
+                scanner.push_header(&self.get(b_range)?, bin_header, settings)?;
+            }
+            scanner.report(progress)
+        };
+        let scanned = scan();
+        if scanned.is_err() || scanner.stopped_early() {
+            if let Some(range) = scanner.first_data_range() {
+                check_text_in_data(&self.get(range)?)?;
+            }
+        }
+        scanned?;
+        let (traces, scan_warnings) = scanner.finish(bin_header, settings);
+        warnings.extend(scan_warnings);
+        Ok(traces)
+    }
+}
+
+/// The headers of a SEG-Y file which come before the traces, as they are parsed when the file
+/// is opened. This is shared by [`crate::SegyFile`] and the `AsyncSegyFile`, which only differ
+/// in how they get the bytes of the file.
+pub(crate) struct FileHeaders {
+    pub(crate) tape_label: Option<TapeLabel>,
+    pub(crate) text_header: String,
+    pub(crate) text_header_encoding: TextEncoding,
+    pub(crate) text_header_layout: TextHeaderLayout,
+    pub(crate) bin_header: BinHeader,
+    pub(crate) raw_bin_header: [u8; BIN_HEADER_LEN],
+    pub(crate) extended_headers: Vec<String>,
+    /// Whether the coordinate format was taken from the extended text headers.
+    pub(crate) coordinate_format_in_file: bool,
+}
+
+impl FileHeaders {
+    /// Get the number of bytes from the start of `head` which [`Self::parse`] needs: The tape
+    /// label, text and binary headers, and as many extended text headers as the binary header
+    /// gives, but no more than can be there. `head` must hold at least the tape label, text and
+    /// binary headers, and the headers are `headers_len` bytes long from its start.
+    pub(crate) fn needed_len(
+        head: &[u8],
+        headers_len: usize,
+        settings: &SegySettings,
+    ) -> Result<usize, RsgError> {
+        let bin_header = get_bin_header(head, settings)?;
+        let max = max_extended_header_count(head, headers_len, settings)?;
+        let count = bin_header.extended_header_count.min(max) as usize;
+        Ok(headers_len.min(MIN_HEAD_LEN.saturating_add(count.saturating_mul(TEXT_HEADER_LEN))))
+    }
+
+    /// Parse the headers from `head`, which must be at least [`Self::needed_len`] bytes long
+    /// (or as long as the headers). If the extended text headers give the coordinate format,
+    /// it is added to the settings (see [`detect_coordinate_format`]), and any problems which
+    /// are dealt with are added to `warnings`.
+    pub(crate) fn parse(
+        head: &[u8],
+        headers_len: usize,
+        settings: &mut SegySettings,
+        warnings: &mut Vec<String>,
+    ) -> Result<Self, RsgError> {
+        let tape_label = get_tape_label(head, settings)?;
+        let (text_header, text_header_encoding, text_header_layout) =
+            get_text_header(head, settings)?;
+        let mut bin_header = get_bin_header(head, settings)?;
+        check_extended_header_count(head, headers_len, &mut bin_header, settings, warnings)?;
+        let extended_headers = get_extended_text_headers(head, bin_header.extended_header_count)?;
+        let coordinate_format_in_file = detect_coordinate_format(&extended_headers, settings)?;
+        Ok(FileHeaders {
+            tape_label,
+            text_header,
+            text_header_encoding,
+            text_header_layout,
+            bin_header,
+            raw_bin_header: get_raw_bin_header(head)?,
+            extended_headers,
+            coordinate_format_in_file,
+        })
+    }
+
+    /// Put together the metadata of a file whose text header is at `text_header_offset`, once
+    /// its traces were scanned (which may adjust the binary header).
+    pub(crate) fn into_metadata(
+        self,
+        settings: SegySettings,
+        text_header_offset: usize,
+    ) -> SegyMetadata<SegySettings> {
+        let mut metadata = SegyMetadata::new(
+            self.tape_label,
+            self.text_header,
+            self.extended_headers,
+            self.bin_header,
+            settings,
+        );
+        metadata.effective_params = Some(EffectiveParams::resolve(
+            &metadata.settings,
+            &metadata.bin_header,
+            &self.raw_bin_header,
+            self.coordinate_format_in_file,
+        ));
+        metadata.raw_bin_header = Some(self.raw_bin_header);
+        metadata.text_header_encoding = Some(self.text_header_encoding);
+        metadata.text_header_layout = Some(self.text_header_layout);
+        metadata.text_header_offset = Some(text_header_offset);
+        metadata
+    }
+}
+
+/// How the traces which are found by a [`TraceScanner`] are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HeaderStorage {
+    /// Every trace is kept with its full trace header.
+    Full,
+    /// The trace headers are not kept (see [`SegySettings::set_compact_headers`]).
+    Compact,
+    /// The trace headers are deduplicated (see [`SegySettings::set_dedup_headers`]).
+    Dedup,
+}
+
+impl HeaderStorage {
+    pub(crate) fn from_settings(settings: &SegySettings) -> Self {
+        if settings.get_compact_headers() {
+            HeaderStorage::Compact
+        } else if settings.get_dedup_headers() {
+            HeaderStorage::Dedup
+        } else {
+            HeaderStorage::Full
+        }
+    }
+}
+
+/// The traces found by a [`TraceScanner`]. Only the list which belongs to the
+/// [`HeaderStorage`] of the scanner is filled.
+#[derive(Debug, Default)]
+pub(crate) struct ScannedTraces {
+    pub(crate) full: Vec<Trace>,
+    pub(crate) compact: Vec<CompactTrace>,
+    pub(crate) dedup: DedupTraces,
+    /// The indices of the auxiliary traces, if they are kept along with the other traces (see
+    /// [`SegySettings::set_aux_trace_policy`]).
+    pub(crate) aux: Vec<usize>,
+}
+
+impl ScannedTraces {
+    /// Get the index of each trace by its inline and crossline numbers, from the list which
+    /// belongs to the [`HeaderStorage`].
+    pub(crate) fn lookup(&self) -> HashMap<LineKey, usize> {
+        let dedup = &self.dedup;
+        let keys = self
+            .full
+            .iter()
+            .map(|trace| TraceKey::from_header(trace.get_header()))
+            .chain(self.compact.iter().map(|trace| trace.key))
+            .chain((0..dedup.len()).filter_map(|i| dedup.key(i)));
+        keys.enumerate()
+            .map(|(i, key)| (LineKey::new(key.inline_no, key.crossline_no), i))
+            .collect()
+    }
+}
+
+/// This structure walks through the trace headers of a SEG-Y file one by one.
+///
+/// It does not hold the bytes of the file itself. Instead it gives the byte range of the next
+/// trace header to be parsed, so that the same logic can be used both for a memory map and
+/// for files which are read piece by piece (eg asynchronously).
+pub(crate) struct TraceScanner {
+    file_len: usize,
+    pointer: usize,
+    i: usize,
+    datum_len: DatumLen,
+    extension_count: usize,
+    prefix_len: usize,
+    first_header: usize,
+    block_byte_length: Option<usize>,
+    /// Whether the first trace header extension is read along with each trace header, for the
+    /// sample counts which do not fit in the trace header (SEG-Y rev 2).
+    extended_sample_counts: bool,
+    max_trace_length: Option<usize>,
+    max_trace_count: usize,
+    last_header_err: Option<RsgError>,
+    finished: bool,
+    storage: HeaderStorage,
+    traces: ScannedTraces,
+    warnings: Vec<String>,
+    /// The value by which the traces are sorted (see [`SegySettings::set_sort_traces_by`]).
+    sort_by: OrderTraceBy,
+    /// The value of `sort_by` of each trace.
+    sort_keys: Vec<i32>,
+    /// The inline and crossline numbers which have been derived so far, if they are derived
+    /// from a custom dimension and a value other than the position of the trace.
+    derived_cells: Option<HashSet<[i32; 2]>>,
+    /// The number of traces whose derived inline and crossline numbers were already taken.
+    duplicate_cells: usize,
+    /// Which traces are kept, depending on whether they are auxiliary traces.
+    aux_policy: AuxPolicy,
+    /// Whether each trace which is kept is an auxiliary trace, if all traces are kept.
+    aux_flags: Vec<bool>,
+    /// The number of auxiliary traces which were found, whether they were kept or not.
+    aux_count: usize,
+}
+
+impl TraceScanner {
+    /// Create a new scanner for a file of `file_len` bytes. `head` must contain at least the
+    /// first `TAPE_LABEL_LEN + 1` bytes of the headers, which start at `headers_start` in the
+    /// file. The traces are kept as given by `storage`.
+    pub(crate) fn new(
+        head: &[u8],
+        headers_start: usize,
+        file_len: usize,
+        bin_header: &BinHeader,
+        extended_header_count: usize,
+        settings: &SegySettings,
+        storage: HeaderStorage,
+    ) -> Result<Self, RsgError> {
+        let datum_len = datum_len(bin_header, settings);
+        // Any trace header extensions (and the prefix) sit between the trace header and the data.
+        let extension_count = bin_header.max_additional_trace_headers as usize;
+        let prefix_len = settings.get_trace_prefix_bytes();
+        let header_len = TRACE_HEADER_LEN * (extension_count + 1) + prefix_len;
+        let default_start = bin_header.get_first_trace_offset(extended_header_count);
         // If the traces have the same length then this is fairly easy and we just iterate through
         // blocks.
-        if bin_header.fixed_length_trace_flag.yes() {
-            // Get the length of each block.
-            let block_byte_length = datum_size * bin_header.no_samples as usize + TRACE_HEADER_LEN;
-            let trace_byte_length = datum_size * bin_header.no_samples as usize;
+        let block_byte_length = if bin_header.fixed_length_trace_flag.yes() {
+            Some(datum_len.byte_len(bin_header.sample_count()) + header_len)
+        } else {
+            None
+        };
+        let extended_sample_counts = block_byte_length.is_none()
+            && bin_header.segy_revision_number[0] >= 2
+            && extension_count > 0;
+        let first_header = headers_start + start_byte(head, default_start, TAPE_LABEL_LEN)?;
+        Ok(TraceScanner {
+            file_len,
+            pointer: first_header,
+            i: 0,
+            datum_len,
+            extension_count,
+            prefix_len,
+            first_header,
+            block_byte_length,
+            extended_sample_counts,
+            max_trace_length: settings.get_max_trace_length_by_override_dimensions(),
+            max_trace_count: settings.get_max_trace_count_by_override_dimensions(),
+            last_header_err: None,
+            finished: false,
+            storage,
+            traces: ScannedTraces {
+                dedup: DedupTraces::new(extension_count, prefix_len),
+                ..Default::default()
+            },
+            warnings: Vec::new(),
+            sort_by: settings.get_sort_traces_by(),
+            sort_keys: Vec::new(),
+            derived_cells: match (settings.get_override_dim_x(), settings.get_order_trace_by()) {
+                (Some(_), order) if order != OrderTraceBy::Default => Some(HashSet::new()),
+                _ => None,
+            },
+            duplicate_cells: 0,
+            aux_policy: settings.get_aux_trace_policy(),
+            aux_flags: Vec::new(),
+            aux_count: 0,
+        })
+    }
+
+    /// Start the scan at `first_header` instead of after the headers.
+    fn start_at(&mut self, first_header: usize) {
+        self.pointer = first_header;
+        self.first_header = first_header;
+    }
+
+    /// Get the byte range of the next trace header, or `None` if there are no more traces.
+    /// This includes the first trace header extension if it may hold the sample count.
+    pub(crate) fn next_header_range(&self) -> Option<std::ops::Range<usize>> {
+        if self.finished || self.i >= self.max_trace_count {
+            return None;
+        }
+        let header_len = if self.extended_sample_counts {
+            2 * TRACE_HEADER_LEN
+        } else {
+            TRACE_HEADER_LEN
+        };
+        let needed = self.block_byte_length.unwrap_or(header_len);
+        // Check that we have enough space to read the header (and, for fixed length traces,
+        // the data). Finish if we're too close to the end.
+        if self.file_len < self.pointer + needed {
+            return None;
+        }
+        Some(self.pointer..(self.pointer + header_len))
+    }
+
+    /// Parse the bytes of the trace header given by [`TraceScanner::next_header_range`]
+    /// and move on to the next trace.
+    pub(crate) fn push_header(
+        &mut self,
+        bytes: &[u8],
+        bin_header: &BinHeader,
+        settings: &SegySettings,
+    ) -> Result<(), RsgError> {
+        let i = self.i;
+        let (bytes, extension) = bytes.split_at(TRACE_HEADER_LEN.min(bytes.len()));
+        // If all traces have the same length, our task is quite easy. In theory.
+        if let Some(block_byte_length) = self.block_byte_length {
+            let header_len = self.header_len();
+            let trace_byte_length = block_byte_length - header_len;
             // This is needed to set a "fake" byte length used purely for reading the data,
             // if we wish to truncate all traces.
-            let trace_apparent_byte_length = if let Some(l) = max_trace_length {
-                datum_size * l
+            let trace_apparent_byte_length = if let Some(l) = self.max_trace_length {
+                self.datum_len.byte_len(l)
             } else {
                 trace_byte_length
             };
 
-            'regular: for (i, ch) in self.map[start_byte..].chunks(block_byte_length).enumerate() {
-                // Preliminary san check.
-                if (ch.len() < block_byte_length) || (i >= max_trace_count) {
-                    break 'regular;
-                }
-
-                let start = start_byte + i * block_byte_length;
-                let b_range = start..(start + TRACE_HEADER_LEN);
-                // It is possible that we already have a collection of valid traces followed by
-                // something else. In this case we may get an error here, instead of an "end of data"
-                // clause. Thus if traces are not empty, an invalid header is interpreted as an
-                // end of trace data statement. Otherwise, it'sjust an error.
-                match TraceHeader::from_bytes(&self.map[b_range], bin_header, settings, i) {
-                    Ok(mut t) => {
-                        // If sample count is not adjusted, we will not truncate the record.
-                        t.adjust_sample_count(settings);
-                        // check the inline and crossline number, and if they're outside of our
-                        // optional range, discard them.
-                        if settings.trace_in_bounds(t.inline_no, t.crossline_no) {
-                            let trace =
-                                Trace::new(t, start + TRACE_HEADER_LEN, trace_apparent_byte_length);
-                            traces.push(trace);
-                        }
+            // It is possible that we already have a collection of valid traces followed by
+            // something else. In this case we may get an error here, instead of an "end of data"
+            // clause. Thus if traces are not empty, an invalid header is interpreted as an
+            // end of trace data statement. Otherwise, it'sjust an error.
+            match parse_trace_header(bytes, bin_header, settings, i) {
+                Ok((mut t, bad_coordinates)) => {
+                    // If sample count is not adjusted, we will not truncate the record.
+                    t.adjust_sample_count(settings);
+                    let keep = self.keep_trace(bad_coordinates, settings) && self.keep_aux(&t);
+                    // check the inline and crossline number, and if they're outside of our
+                    // optional range, discard them.
+                    if keep && settings.trace_in_bounds(t.inline_no, t.crossline_no) {
+                        let start = self.pointer + header_len;
+                        let trace = Trace::new_with_extensions(
+                            t,
+                            start,
+                            trace_apparent_byte_length,
+                            self.extension_count,
+                        )
+                        .with_prefix_len(self.prefix_len);
+                        self.push_trace(keep_original_line_numbers(
+                            trace, bytes, bin_header, settings,
+                        ));
                     }
-                    Err(e) if (i == 0) || last_header_err.is_some() => return Err(e),
-                    Err(e) => last_header_err = Some(e),
-                };
-            }
+                }
+                // A coordinate that could not be converted is not a sign that the data is over.
+                Err(e @ RsgError::CoordinateParse { .. }) => return Err(e),
+                Err(e) if (i == 0) || self.last_header_err.is_some() => return Err(e),
+                Err(e) => self.last_header_err = Some(e),
+            };
+            self.pointer += block_byte_length;
+            self.i += 1;
         // If the trace length is variable, we must parse one header at a time, then grab some
         // bytes for the trace. Then rinse and repeat.
         } else {
-            // TODO: This branch.
-            let mut pointer = start_byte;
-            let mut i = 0;
-            'irregular: while i < max_trace_count {
-                // Check that we have enough space to read header. Finish if we're too close to the end.
+            match parse_trace_header(bytes, bin_header, settings, i) {
+                Ok((mut t, bad_coordinates)) => {
+                    let keep = self.keep_trace(bad_coordinates, settings) && self.keep_aux(&t);
+                    let no_samples = match t.no_samples_in_trace {
+                        0 => extended_sample_count(extension, bin_header),
+                        no_samples => no_samples as usize,
+                    };
+                    let trace_byte_length = self.datum_len.byte_len(no_samples);
+                    let header_len = self.header_len();
+
+                    // Sample count can be adjusted here. NB: Must be done after `trace_byte_length`
+                    // is calculated or the byte lengths will be wrong.
+                    t.adjust_sample_count(settings);
+
+                    // If the file is shorter than the total block length, it means we have
+                    // a good header, but an incomplete trace, so the file is corrupt. For
+                    // Now this is probably best producing an error.
+                    if self.file_len < header_len + trace_byte_length {
+                        return Err(RsgError::IncompleteTrace { trace: i });
+                    }
+                    // check the inline and crossline number, and if they're outside of our
+                    // optional range, discard them.
+                    // NB: The pointer MUST be incremented, even if the trace is discarded,
+                    // otherwise we would never proceed to the next trace.
+                    if keep && settings.trace_in_bounds(t.inline_no, t.crossline_no) {
+                        // This makes a "fake" byte length if we are truncating all traces for
+                        // reading.
+                        let trace_apparent_byte_length = match self.max_trace_length {
+                            Some(l) if self.datum_len.byte_len(l) < trace_byte_length => {
+                                self.datum_len.byte_len(l)
+                            }
+                            _ => trace_byte_length,
+                        };
+                        // make the trace.
+                        let trace = Trace::new_with_extensions(
+                            t,
+                            self.pointer + header_len,
+                            trace_apparent_byte_length,
+                            self.extension_count,
+                        )
+                        .with_prefix_len(self.prefix_len);
+                        self.push_trace(keep_original_line_numbers(
+                            trace, bytes, bin_header, settings,
+                        ));
+                    }
+                    // Increment pointer.
+                    self.pointer += header_len + trace_byte_length;
+                    self.i += 1;
+                }
+                // Since length is derived from a header, if one header is invalid, then the
+                // recording is over. A "corrupt" header at this stage is not considered
+                // an error. This is because we have no way of knowing if we have got to the end
+                // of the data or not. (ie it is for the user to decide.)
                 // If the last header was invalid, but we still have space, we have probably got to
                 // the end of the data and have some extended headers or corrupt records. Either
                 // way, we're done.
-                if self.map.len() < pointer + TRACE_HEADER_LEN || last_header_err.is_some() {
-                    break;
-                }
-                // Get the trace headers
-                let b_range = pointer..(pointer + TRACE_HEADER_LEN);
-                match TraceHeader::from_bytes(&self.map[b_range], bin_header, settings, i) {
-                    Ok(mut t) => {
-                        let trace_byte_length = datum_size * t.no_samples_in_trace as usize;
-
-                        // Sample count can be adjusted here. NB: Must be done after `trace_byte_length`
-                        // is calculated or the byte lengths will be wrong.
-                        t.adjust_sample_count(settings);
-
-                        // If the file is shorter than the total block length, it means we have
-                        // a good header, but an incomplete trace, so the file is corrupt. For
-                        // Now this is probably best producing an error.
-                        if self.map.len() < TRACE_HEADER_LEN + trace_byte_length {
-                            return Err(RsgError::IncompleteTrace);
-                        }
-                        // check the inline and crossline number, and if they're outside of our
-                        // optional range, discard them.
-                        // NB: The pointer MUST be incremented, even if the trace is discarded,
-                        // otherwise we would never proceed to the next trace.
-                        if settings.trace_in_bounds(t.inline_no, t.crossline_no) {
-                            // This makes a "fake" byte length if we are truncating all traces for
-                            // reading.
-                            let trace_apparent_byte_length = if let Some(l) = max_trace_length {
-                                if l * datum_size < trace_byte_length {
-                                    l * datum_size
-                                } else {
-                                    trace_byte_length
-                                }
-                            } else {
-                                trace_byte_length
-                            };
-                            // make the trace.
-                            let trace = Trace::new(
-                                t,
-                                pointer + TRACE_HEADER_LEN,
-                                trace_apparent_byte_length,
-                            );
-                            traces.push(trace);
-                        }
-                        // Increment pointer.
-                        pointer += TRACE_HEADER_LEN + trace_byte_length;
-                        i += 1;
-                    }
-                    // Since length is derived from a header, if one header is invalid, then the
-                    // recording is over. A "corrupt" header at this stage is not considered
-                    // an error. This is because we have no way of knowing if we have got to the end
-                    // of the data or not. (ie it is for the user to decide.)
-                    Err(_e) => break 'irregular,
-                };
+                Err(e @ RsgError::CoordinateParse { .. }) => return Err(e),
+                Err(_e) => self.finished = true,
+            };
+        }
+        Ok(())
+    }
+
+    /// Report how far the scan has got to a progress callback.
+    pub(crate) fn report(
+        &self,
+        progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+    ) -> Result<(), RsgError> {
+        let report = SegyProgress {
+            phase: ProgressPhase::TraceScan,
+            traces_done: self.i,
+            traces_total: None,
+            bytes_done: self.pointer.min(self.file_len),
+            bytes_total: Some(self.file_len),
+        };
+        match progress(report) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(RsgError::Cancelled),
+        }
+    }
+
+    /// Record a warning for each coordinate of a trace header which could not be converted, and
+    /// decide whether the trace is kept.
+    fn keep_trace(&mut self, bad_coordinates: Vec<RsgError>, settings: &SegySettings) -> bool {
+        if bad_coordinates.is_empty() {
+            return true;
+        }
+        let skip = settings.get_on_coordinate_parse_error() == CoordErrorPolicy::SkipTrace;
+        let consequence = if skip {
+            "The trace was skipped."
+        } else {
+            "The coordinate was set to zero."
+        };
+        for e in bad_coordinates {
+            self.warnings.push(format!("{} {}", e, consequence));
+        }
+        !skip
+    }
+
+    /// Count the auxiliary traces and decide whether a trace is kept, as given by the
+    /// [`AuxPolicy`].
+    fn keep_aux(&mut self, header: &TraceHeader) -> bool {
+        let aux = header.trace_identification_code.is_auxiliary();
+        self.aux_count += aux as usize;
+        match self.aux_policy {
+            AuxPolicy::Include => true,
+            AuxPolicy::Exclude => !aux,
+            AuxPolicy::Only => aux,
+        }
+    }
+
+    /// Keep a trace as given by the [`HeaderStorage`].
+    fn push_trace(&mut self, trace: Trace) {
+        let header = trace.get_header();
+        if self.aux_policy == AuxPolicy::Include {
+            self.aux_flags
+                .push(header.trace_identification_code.is_auxiliary());
+        }
+        if let Some(key) = self.sort_by.header_value(header) {
+            self.sort_keys.push(key);
+        }
+        if let Some(ref mut cells) = self.derived_cells {
+            if !cells.insert([header.inline_no, header.crossline_no]) {
+                self.duplicate_cells += 1;
             }
         }
-        // The sample count in the binary header can now be adjusted.
+        match self.storage {
+            HeaderStorage::Full => self.traces.full.push(trace),
+            HeaderStorage::Compact => self.traces.compact.push(CompactTrace::new(&trace)),
+            HeaderStorage::Dedup => self.traces.dedup.push(trace),
+        }
+    }
+
+    /// Get the length of a trace header including its extensions and the prefix.
+    fn header_len(&self) -> usize {
+        TRACE_HEADER_LEN * (self.extension_count + 1) + self.prefix_len
+    }
+
+    /// Check whether the scan stopped before the end of the file (eg. because a trace header
+    /// could not be parsed).
+    pub(crate) fn stopped_early(&self) -> bool {
+        self.pointer < self.file_len
+    }
+
+    /// Get the byte range at the start of the data of the first trace which is checked for text
+    /// by [`check_text_in_data`], if there is no prefix in the settings.
+    pub(crate) fn first_data_range(&self) -> Option<std::ops::Range<usize>> {
+        let start = self.first_header + self.header_len();
+        let end = self.file_len.min(start + TEXT_HEADER_LEN);
+        if self.prefix_len == 0 && start < end {
+            Some(start..end)
+        } else {
+            None
+        }
+    }
+
+    /// Finish the scan and get the traces, along with the warnings about any problems with the
+    /// trace headers. The sample count in the binary header is adjusted at this stage.
+    pub(crate) fn finish(
+        mut self,
+        bin_header: &mut BinHeader,
+        settings: &SegySettings,
+    ) -> (ScannedTraces, Vec<String>) {
         bin_header.adjust_sample_count(settings);
-        Ok(traces)
+        if self.duplicate_cells > 0 {
+            self.warnings.push(format!(
+                "{} traces have the same {:?} as an earlier trace, and so the same inline and \
+                crossline numbers. They are kept, but only the last of them can be found by its \
+                inline and crossline numbers.",
+                self.duplicate_cells,
+                settings.get_order_trace_by()
+            ));
+        }
+        if bin_header.no_aux_traces > 0 && self.aux_count == 0 {
+            self.warnings.push(format!(
+                "The binary header gives {} auxiliary traces per record, but no trace has the \
+                trace identification code of an auxiliary trace, so they cannot be told apart \
+                from the other traces.",
+                bin_header.no_aux_traces
+            ));
+        }
+        if !self.sort_keys.is_empty() {
+            self.sort();
+        }
+        self.traces.aux = self
+            .aux_flags
+            .iter()
+            .enumerate()
+            .filter(|(_, aux)| **aux)
+            .map(|(i, _)| i)
+            .collect();
+        self.traces.dedup.finish();
+        (self.traces, self.warnings)
+    }
+
+    /// Sort the traces by their `sort_keys`, keeping traces with the same key in the order of
+    /// the file.
+    fn sort(&mut self) {
+        let keys = &self.sort_keys;
+        sort_by_keys(&mut self.traces.full, keys);
+        sort_by_keys(&mut self.traces.compact, keys);
+        self.traces.dedup.sort_by_keys(keys);
+        sort_by_keys(&mut self.aux_flags, keys);
+
+        let mut sorted = keys.clone();
+        sorted.sort_unstable();
+        let duplicates = sorted.windows(2).filter(|w| w[0] == w[1]).count();
+        if duplicates > 0 {
+            self.warnings.push(format!(
+                "{} traces have the same {:?} as another trace. They are kept in the order of \
+                the file.",
+                duplicates, self.sort_by
+            ));
+        }
+    }
+}
+
+/// Read the sample count from the first trace header extension of a trace whose trace header
+/// gives no sample count. This is zero if the extension was not read.
+fn extended_sample_count(extension: &[u8], bin_header: &BinHeader) -> usize {
+    let range = EXTENDED_SAMPLE_COUNT_BYTE_LOCATION..(EXTENDED_SAMPLE_COUNT_BYTE_LOCATION + 4);
+    let bytes = match extension.get(range) {
+        Some(bytes) => bytes.try_into().expect("The range has four bytes."),
+        None => return 0,
+    };
+    if bin_header.binary_flag_direction_is_le {
+        u32::from_le_bytes(bytes) as usize
+    } else {
+        u32::from_be_bytes(bytes) as usize
     }
 }
 
-/// This function creates a memory map from a file.
-pub(crate) fn map_file_to_memory(file_name: &str) -> Result<(Mmap, std::fs::File), RsgError> {
+/// Sort `items` by the key with the same index, keeping items with the same key in their
+/// order. Empty `items` (ie. a kind of storage which is not used) are left alone.
+pub(crate) fn sort_by_keys<T>(items: &mut Vec<T>, keys: &[i32]) {
+    if items.is_empty() {
+        return;
+    }
+    debug_assert_eq!(items.len(), keys.len());
+    let mut keyed = keys
+        .iter()
+        .cloned()
+        .zip(items.drain(..))
+        .collect::<Vec<_>>();
+    keyed.sort_by_key(|(key, _)| *key);
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+/// This function creates a memory map from a file, unless the file is larger than the budget
+/// or than the address space of the platform. If a `window` size is given (ie. it is not zero)
+/// and the file is larger, only its head is mapped (see [`MappedSegY::head`]), which is at
+/// least `min_head` bytes long. The map is populated unless `population` is
+/// [`MapPopulation::Lazy`] (the locking is left to [`apply_population`]). The length of the file
+/// is returned with the map.
+pub(crate) fn map_file_to_memory(
+    file_name: &str,
+    budget: Option<u64>,
+    window: usize,
+    min_head: usize,
+    population: MapPopulation,
+) -> Result<(Mmap, std::fs::File, usize), RsgError> {
     let segy = std::fs::File::open(file_name).map_err(RsgError::MapFile)?;
-    let map = unsafe { MmapOptions::new().map(&segy).map_err(RsgError::MapFile)? };
-    Ok((map, segy))
+    let len = segy.metadata().map_err(RsgError::MapFile)?.len();
+    let len = check_file_fits(len, budget)?;
+    let mut options = MmapOptions::new();
+    if window > 0 && window < len {
+        options.len(window.max(min_head).min(len));
+    }
+    if population != MapPopulation::Lazy {
+        options.populate();
+    }
+    let map = unsafe { options.map(&segy).map_err(RsgError::MapFile)? };
+    Ok((map, segy, len))
+}
+
+/// Ask for a map to be read in (where `MAP_POPULATE` is not supported), and lock it in memory
+/// if `population` is [`MapPopulation::Lock`]. If the map cannot be locked, because it is
+/// larger than `lock_budget` or the platform refuses, it is only populated, and the reason is
+/// returned as a warning.
+#[cfg(unix)]
+pub(crate) fn apply_population(
+    map: &Mmap,
+    population: MapPopulation,
+    lock_budget: Option<u64>,
+) -> Option<String> {
+    if population == MapPopulation::Lazy || map.is_empty() {
+        return None;
+    }
+    // This is only a hint, so it does not matter if it fails.
+    let _ = map.advise(Advice::WillNeed);
+    if population != MapPopulation::Lock {
+        return None;
+    }
+    match lock_budget {
+        Some(budget) if map.len() as u64 > budget => Some(format!(
+            "The file could not be locked in memory, since the {} bytes of the mapping exceed the \
+            lock budget of {} bytes, so it was only populated.",
+            map.len(),
+            budget
+        )),
+        _ => map.lock().err().map(|e| {
+            format!(
+                "The file could not be locked in memory ({}), so it was only populated.",
+                e
+            )
+        }),
+    }
+}
+
+/// Populating or locking a map is not supported on this platform, so the map is left as it is.
+#[cfg(not(unix))]
+pub(crate) fn apply_population(
+    _map: &Mmap,
+    _population: MapPopulation,
+    _lock_budget: Option<u64>,
+) -> Option<String> {
+    None
+}
+
+/// The window size which is used instead of the one in the settings when the tests are run
+/// with the environment variable `GIGA_SEGY_TEST_MAP_WINDOW`, so that the whole test suite can
+/// be run with a file that is mapped in windows.
+#[cfg(test)]
+fn test_map_window(window: usize) -> usize {
+    match std::env::var("GIGA_SEGY_TEST_MAP_WINDOW") {
+        Ok(size) if window == 0 => size.parse().expect("Invalid GIGA_SEGY_TEST_MAP_WINDOW"),
+        _ => window,
+    }
+}
+
+/// Check that a file of `len` bytes fits in a `usize` (and so in the address space of the
+/// platform), and in the mapping budget if there is one.
+pub(crate) fn check_file_fits(len: u64, budget: Option<u64>) -> Result<usize, RsgError> {
+    match usize::try_from(len) {
+        Ok(l) if !matches!(budget, Some(b) if len > b) => Ok(l),
+        _ => Err(RsgError::FileTooLargeForPlatform { len }),
+    }
+}
+
+/// Find the position of the tape label or text header in a file which begins with `head`. This is
+/// zero unless the settings ask for the text header to be searched for (see
+/// [`SegySettings::set_scan_for_text_header`]).
+pub(crate) fn find_headers_start(head: &[u8], settings: &SegySettings) -> Result<usize, RsgError> {
+    let max_scan = settings.get_scan_for_text_header();
+    if max_scan == 0 {
+        return Ok(0);
+    }
+    let last = head.len().saturating_sub(TEXT_HEADER_LEN + BIN_HEADER_LEN);
+    let found = (0..=max_scan.min(last))
+        .find(|start| looks_like_text_header(&head[*start..]))
+        .map(|start| match start {
+            // The text header after a tape label is read along with the label.
+            TAPE_LABEL_LEN if matches!(has_label(head), Ok(true)) => 0,
+            start => start,
+        });
+    found.ok_or_else(|| RsgError::InvalidHeader {
+        msg: format!(
+            "No text header was found in the first {} bytes of the file.",
+            max_scan
+        ),
+    })
+}
+
+/// Check whether `bytes` begin with something which looks like a text header: It starts with a
+/// `C` and consists of text (including line breaks and zero padding), in ASCII or in EBCDIC.
+fn looks_like_text_header(bytes: &[u8]) -> bool {
+    if bytes.len() < TEXT_HEADER_LEN {
+        return false;
+    }
+    let header = &bytes[..TEXT_HEADER_LEN];
+    (header[0] == b'C' && header.iter().all(|c| is_text(*c)))
+        || (to_ascii(header[0]) == b'C' && header.iter().all(|c| is_text(to_ascii(*c))))
+}
+
+/// Check whether `bytes` consist of text, in ASCII or in EBCDIC, and are not all zero.
+fn is_text_block(bytes: &[u8]) -> bool {
+    bytes.iter().any(|c| *c != 0)
+        && (bytes.iter().all(|c| is_text(*c)) || bytes.iter().all(|c| is_text(to_ascii(*c))))
+}
+
+/// Check whether an ASCII character may be part of a text header (including line breaks and
+/// zero padding).
+fn is_text(c: u8) -> bool {
+    c == b' ' || c == b'\r' || c == b'\n' || c == 0 || c.is_ascii_graphic()
+}
+
+/// Perform a sanity check to make sure that a file of `file_len` bytes, which begins with `head`,
+/// is big enough to be SEG-Y. A file with the text and binary headers but no traces is allowed.
+pub(crate) fn check_file_length(head: &[u8], file_len: usize) -> Result<(), RsgError> {
+    let label_len = if has_label(head)? { TAPE_LABEL_LEN } else { 0 };
+    if file_len < label_len + TEXT_HEADER_LEN + BIN_HEADER_LEN {
+        return Err(RsgError::FileTooShort);
+    }
+    Ok(())
+}
+
+/// Get the tape label from the beginning of the file, if there is one.
+pub(crate) fn get_tape_label(
+    head: &[u8],
+    settings: &SegySettings,
+) -> Result<Option<TapeLabel>, RsgError> {
+    if has_label(head)? {
+        let label = TapeLabel::from_bytes(&head[0..TAPE_LABEL_LEN], settings)?;
+        Ok(Some(label))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Get the text header from the beginning of the file, decoded as set by
+/// [`SegySettings::set_text_header_encoding`], and the encoding which was used.
+pub(crate) fn get_text_header(
+    head: &[u8],
+    settings: &SegySettings,
+) -> Result<(String, TextEncoding, TextHeaderLayout), RsgError> {
+    let start = start_byte(head, 0, TAPE_LABEL_LEN)?;
+    let bytes = &head[start..(start + TEXT_HEADER_LEN)];
+
+    let encoding = match settings.get_text_header_encoding() {
+        TextEncoding::Auto if is_ascii(head, start) => TextEncoding::Ascii,
+        TextEncoding::Auto => TextEncoding::Ebcdic,
+        encoding => encoding,
+    };
+    let header_bytes = match encoding {
+        TextEncoding::Raw => {
+            let header = bytes.iter().map(|b| char::from(*b)).collect();
+            return Ok((header, encoding, TextHeaderLayout::FixedWidth));
+        }
+        TextEncoding::Ebcdic => bytes.iter().map(|c| to_ascii(*c)).collect(),
+        _ => bytes.to_vec(),
+    };
+    let header = String::from_utf8_lossy(truncate_at_zero(&header_bytes));
+    let (header, layout) = text_header_cards(&header);
+    Ok((header, encoding, layout))
+}
+
+/// Bring a decoded text header into cards of eighty characters.
+///
+/// If there is a line feed in the first 400 bytes, the lines are taken to be terminated: The
+/// header is split at each line feed (dropping any carriage returns), and each line is padded
+/// or cut off to eighty characters. Otherwise the header is split into cards by width,
+/// and any carriage returns and line feeds are replaced with spaces.
+fn text_header_cards(header: &str) -> (String, TextHeaderLayout) {
+    let is_terminator = |c: char| c == '\r' || c == '\n';
+    if !header.as_bytes().iter().take(400).any(|b| *b == b'\n') {
+        let header = header.replace(is_terminator, " ");
+        return (header, TextHeaderLayout::FixedWidth);
+    }
+    let mut lines = header.split('\n').collect::<Vec<_>>();
+    // The padding after the last line is not a card of its own.
+    if matches!(lines.last(), Some(line) if line.trim().is_empty()) {
+        lines.pop();
+    }
+    let cards = lines
+        .into_iter()
+        .flat_map(|line| {
+            let card = line.chars().filter(|c| !is_terminator(*c)).take(80);
+            card.chain(std::iter::repeat(' ')).take(80)
+        })
+        .collect();
+    (cards, TextHeaderLayout::LineTerminated)
+}
+
+/// Get the binary header from the beginning of the file.
+pub(crate) fn get_bin_header(head: &[u8], settings: &SegySettings) -> Result<BinHeader, RsgError> {
+    let start = start_byte(head, TEXT_HEADER_LEN, TAPE_LABEL_LEN)?;
+    BinHeader::from_bytes(&head[start..(start + BIN_HEADER_LEN)], settings)
+}
+
+/// Get the bytes of the binary header from the beginning of the file, exactly as they are.
+pub(crate) fn get_raw_bin_header(head: &[u8]) -> Result<[u8; BIN_HEADER_LEN], RsgError> {
+    let start = start_byte(head, TEXT_HEADER_LEN, TAPE_LABEL_LEN)?;
+    Ok(head[start..(start + BIN_HEADER_LEN)].try_into()?)
+}
+
+/// Get `count` extended text headers from the beginning of the file.
+pub(crate) fn get_extended_text_headers(head: &[u8], count: u32) -> Result<Vec<String>, RsgError> {
+    // Shortcut the process if we have nothing to give.
+    if count == 0 {
+        return Ok(Vec::with_capacity(0));
+    }
+
+    let default_start = TEXT_HEADER_LEN + BIN_HEADER_LEN;
+    let start_byte = start_byte(head, default_start, TAPE_LABEL_LEN)?;
+    let count = count as usize;
+
+    // A sanity check to shortcut us if the file is too short.
+    if head.len() < start_byte + count * TEXT_HEADER_LEN {
+        return Err(RsgError::SEGYTooShort);
+    }
+
+    let mut extra_headers = Vec::new();
+    for i in 0..count {
+        let start = start_byte + i * TEXT_HEADER_LEN;
+        let header_bytes = convert_bytes_to_ascii(head, start, TEXT_HEADER_LEN);
+        let header = String::from_utf8_lossy(&header_bytes).to_string();
+        extra_headers.push(header);
+    }
+
+    Ok(extra_headers)
 }
 
-/// A function to determine whether we need to ascify the text.
-fn is_ascii(map: &Mmap, start: usize) -> bool {
+/// Check the number of extended text headers which the binary header gives, for a file whose
+/// headers are `headers_len` bytes long from the start of `head`. The headers must fit into
+/// the file, and there may not be more than the settings allow (see
+/// [`SegySettings::set_max_extended_headers`]).
+///
+/// An implausible count returns an [`RsgError::ImplausibleExtendedHeaderCount`], unless the
+/// headers are to be detected (see [`SegySettings::set_detect_extended_headers`]). Then the
+/// count of `bin_header` is replaced by the number of blocks of text after the binary header,
+/// as far as they are in `head`, and a warning is added to `warnings`.
+pub(crate) fn check_extended_header_count(
+    head: &[u8],
+    headers_len: usize,
+    bin_header: &mut BinHeader,
+    settings: &SegySettings,
+    warnings: &mut Vec<String>,
+) -> Result<(), RsgError> {
+    let count = bin_header.extended_header_count;
+    let start = start_byte(head, TEXT_HEADER_LEN + BIN_HEADER_LEN, TAPE_LABEL_LEN)?;
+    let max = max_extended_header_count(head, headers_len, settings)?;
+    if count <= max {
+        return Ok(());
+    }
+    if !settings.get_detect_extended_headers() {
+        return Err(RsgError::ImplausibleExtendedHeaderCount { count, max });
+    }
+
+    let detected = head[start.min(head.len())..]
+        .chunks_exact(TEXT_HEADER_LEN)
+        .take(max as usize)
+        .take_while(|block| is_text_block(block))
+        .count() as u32;
+    warnings.push(format!(
+        "The binary header gives {} extended text headers, which cannot be right, so {} were \
+        detected instead.",
+        count, detected
+    ));
+    bin_header.extended_header_count = detected;
+    Ok(())
+}
+
+/// Get the largest number of extended text headers which a file can have, whose headers are
+/// `headers_len` bytes long from the start of `head`: As many as fit after the binary header,
+/// but no more than the settings allow.
+pub(crate) fn max_extended_header_count(
+    head: &[u8],
+    headers_len: usize,
+    settings: &SegySettings,
+) -> Result<u32, RsgError> {
+    let start = start_byte(head, TEXT_HEADER_LEN + BIN_HEADER_LEN, TAPE_LABEL_LEN)?;
+    let fitting = headers_len.saturating_sub(start) / TEXT_HEADER_LEN;
+    Ok(u32::try_from(fitting)
+        .unwrap_or(u32::MAX)
+        .min(settings.get_max_extended_headers()))
+}
+
+/// Check that the size of a file of `file_len` bytes, which begins with `head`, fits the sample
+/// format declared in the binary header.
+///
+/// The expected size is calculated from the number of traces and samples in the binary header,
+/// so nothing is checked if either of them is zero, or if the trace format is overridden or
+/// read with a custom decoder.
+/// If the trace data takes up 2, 4 or 8 times more (or less) space than expected, the declared
+/// sample format is assumed to be wrong. Then an [`RsgError::FormatSizeMismatch`] is returned,
+/// unless the file size is trusted, in which case the sample format of `bin_header` is replaced
+/// by the one implied by the file size and a warning is added to `warnings`.
+pub(crate) fn check_format_size(
+    head: &[u8],
+    file_len: usize,
+    bin_header: &mut BinHeader,
+    extended_header_count: usize,
+    settings: &SegySettings,
+    warnings: &mut Vec<String>,
+) -> Result<(), RsgError> {
+    let no_traces = bin_header.no_traces as usize;
+    let no_samples = bin_header.sample_count();
+    if settings.get_override_trace_format().is_some()
+        || custom_decoder(bin_header, settings).is_some()
+        || no_traces == 0
+        || no_samples == 0
+    {
+        return Ok(());
+    }
+
+    let declared = bin_header.sample_format_code;
+    let default_start = bin_header.get_first_trace_offset(extended_header_count);
+    let headers_len = no_traces
+        * (TRACE_HEADER_LEN * (bin_header.max_additional_trace_headers as usize + 1)
+            + settings.get_trace_prefix_bytes());
+    let data_len = match file_len.checked_sub(start_byte(head, default_start, TAPE_LABEL_LEN)?) {
+        Some(l) if l > headers_len => l - headers_len,
+        _ => return Ok(()),
+    };
+    let sample_total = no_traces * no_samples;
+    if data_len == sample_total * declared.datum_byte_length() || data_len % sample_total != 0 {
+        return Ok(());
+    }
+
+    let [small, large] = {
+        let mut sizes = [data_len / sample_total, declared.datum_byte_length()];
+        sizes.sort_unstable();
+        sizes
+    };
+    if small == 0 || large % small != 0 || ![2, 4, 8].contains(&(large / small)) {
+        return Ok(());
+    }
+    let implied_by_size = match format_of_size(declared, data_len / sample_total) {
+        Some(f) => f,
+        None => return Ok(()),
+    };
+    if settings.get_trust_file_size() {
+        bin_header.sample_format_code = implied_by_size;
+        warnings.push(format!(
+            "The sample format was declared as {}, but {} was used because of the file size.",
+            declared, implied_by_size
+        ));
+        Ok(())
+    } else {
+        Err(RsgError::FormatSizeMismatch {
+            declared,
+            implied_by_size,
+        })
+    }
+}
+
+/// If the coordinate format is not given in the settings, take it from the extended text
+/// headers, where `giga_segy_out` records it if it is not the default (see
+/// [`giga_segy_core::stanza::coordinate_format_stanza`]). Whether it was taken from the
+/// extended text headers is returned.
+pub(crate) fn detect_coordinate_format(
+    extended_headers: &[String],
+    settings: &mut SegySettings,
+) -> Result<bool, RsgError> {
+    if settings.get_override_coordinate_format().is_some() {
+        return Ok(false);
+    }
+    let stanzas = stanza::parse_extended_stanzas(extended_headers);
+    match stanza::find_coordinate_format(&stanzas) {
+        Some(format) => settings
+            .set_override_coordinate_format(format)
+            .map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// Check the binary header of a file, whose headers are `headers_len` bytes long from the start
+/// of `head`, before its traces are scanned: Its revision (see [`check_revision`]) and the
+/// size of the file against the declared sample format (see [`check_format_size`]), so that
+/// both may be replaced in `bin_header`. Any warnings about the settings are also added to
+/// `warnings`.
+pub(crate) fn check_before_scan(
+    head: &[u8],
+    headers_len: usize,
+    bin_header: &mut BinHeader,
+    extended_header_count: usize,
+    settings: &SegySettings,
+    warnings: &mut Vec<String>,
+) -> Result<(), RsgError> {
+    check_revision(bin_header, settings, warnings)?;
+    override_warnings(head, bin_header, settings, warnings)?;
+    bidx_warnings(settings, warnings);
+    check_format_size(
+        head,
+        headers_len,
+        bin_header,
+        extended_header_count,
+        settings,
+        warnings,
+    )
+}
+
+/// Check the SEG-Y revision of the binary header. If the settings ask for a strict revision,
+/// an [`RsgError::UnsupportedRevision`] is returned unless the revision is supported (see
+/// [`BinHeader::revision_supported`]). Otherwise a revision whose major number is a printable
+/// ASCII character is replaced with 0.0, and a warning is added to `warnings`.
+pub(crate) fn check_revision(
+    bin_header: &mut BinHeader,
+    settings: &SegySettings,
+    warnings: &mut Vec<String>,
+) -> Result<(), RsgError> {
+    let [major, minor] = bin_header.segy_revision_number;
+    if settings.get_strict_revision() && !bin_header.revision_supported() {
+        return Err(RsgError::UnsupportedRevision { major, minor });
+    }
+    if (0x20..0x7F).contains(&major) {
+        bin_header.segy_revision_number = [0, 0];
+        warnings.push(format!(
+            "The SEG-Y revision bytes {:?} look like text, so the file was read as revision 0.0.",
+            String::from_utf8_lossy(&[major, minor])
+        ));
+    }
+    Ok(())
+}
+
+/// Add a warning for each invalid code in the binary header of the file beginning with `head`
+/// which was worked around because the trace format is overridden. This is the case for the
+/// sample format code and the fixed length trace flag (see [`HeaderFromBytes`] for `BinHeader`).
+pub(crate) fn override_warnings(
+    head: &[u8],
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    warnings: &mut Vec<String>,
+) -> Result<(), RsgError> {
+    let format = match settings.get_override_trace_format() {
+        Some(format) => format,
+        None => return Ok(()),
+    };
+    let code = bin_header.raw_sample_format_code;
+    if SampleFormatCode::new(code).is_err() {
+        warnings.push(format!(
+            "The sample format code {} is not valid, so {} was used as given in the settings.",
+            code, format
+        ));
+    }
+
+    let raw = get_raw_bin_header(head)?;
+    let flag = [raw[302], raw[303]];
+    let flag = if bin_header.binary_flag_direction_is_le {
+        u16::from_le_bytes(flag)
+    } else {
+        u16::from_be_bytes(flag)
+    };
+    if FixedLengthTraces::new(flag).is_err() {
+        warnings.push(format!(
+            "The fixed length trace flag {} is not valid, so the traces are assumed to have variable length.",
+            flag
+        ));
+    }
+    Ok(())
+}
+
+/// Add a warning for each field whose byte index in the settings overlaps other fields of the
+/// trace header (see [`SegySettings::check_bidx_collisions`]), since these are then parsed from
+/// the same bytes.
+pub(crate) fn bidx_warnings(settings: &SegySettings, warnings: &mut Vec<String>) {
+    for collision in settings.check_bidx_collisions() {
+        warnings.push(format!(
+            "{} because of the byte indices of the settings, so both are read from the same bytes.",
+            collision
+        ));
+    }
+}
+
+/// Get the sample format with a datum of `size` bytes which is most similar to `declared`
+/// (ie floats stay floats and unsigned integers stay unsigned).
+fn format_of_size(declared: SampleFormatCode, size: usize) -> Option<SampleFormatCode> {
+    use SampleFormatCode::*;
+    let float = matches!(declared, IbmFloat32 | FixPoint32 | Float32 | Float64);
+    let unsigned = matches!(declared, UInt8 | UInt16 | UInt24 | UInt32 | UInt64);
+    match (size, float, unsigned) {
+        (4, true, _) => Some(Float32),
+        (8, true, _) => Some(Float64),
+        (1, _, true) => Some(UInt8),
+        (2, _, true) => Some(UInt16),
+        (4, _, true) => Some(UInt32),
+        (8, _, true) => Some(UInt64),
+        (1, _, _) => Some(Int8),
+        (2, _, _) => Some(Int16),
+        (4, _, _) => Some(Int32),
+        (8, _, _) => Some(Int64),
+        _ => None,
+    }
+}
+
+/// Check whether the data of the first trace starts with text (at least [`MIN_TEXT_IN_DATA`]
+/// printable ASCII characters), which is a sign that the file was written with a payload between
+/// the trace headers and the data that the settings do not skip. This is only checked if the
+/// traces could not be scanned to the end of the file, since the samples of some files may
+/// happen to look like text.
+pub(crate) fn check_text_in_data(bytes: &[u8]) -> Result<(), RsgError> {
+    let text_len = bytes
+        .iter()
+        .take_while(|c| c.is_ascii_graphic() || c.is_ascii_whitespace())
+        .count();
+    if text_len >= MIN_TEXT_IN_DATA {
+        Err(RsgError::TextInTraceData { trace: 0, text_len })
+    } else {
+        Ok(())
+    }
+}
+
+/// A function to determine whether we need to ascify the text. Carriage returns and line feeds
+/// are allowed in an ASCII header (see [`text_header_cards`]).
+fn is_ascii(map: &[u8], start: usize) -> bool {
     map[start..(start + TEXT_HEADER_LEN)]
         .iter()
-        .all(|c| c.is_ascii() && !c.is_ascii_control())
+        .all(|c| c.is_ascii() && (!c.is_ascii_control() || *c == b'\r' || *c == b'\n'))
 }
 
 /// A way to save LOC when getting the start byte.
-fn start_byte(map: &Mmap, default: usize, extra: usize) -> Result<usize, RsgError> {
+fn start_byte(map: &[u8], default: usize, extra: usize) -> Result<usize, RsgError> {
     if has_label(map)? {
         Ok(default + extra)
     } else {
@@ -256,7 +1491,7 @@ fn start_byte(map: &Mmap, default: usize, extra: usize) -> Result<usize, RsgErro
 }
 
 /// The inner `has_label` function which can be applied to a map before it is turned into `MappedSegY`
-fn has_label(map: &Mmap) -> Result<bool, RsgError> {
+pub(crate) fn has_label(map: &[u8]) -> Result<bool, RsgError> {
     // Sanity check.
     if map.len() <= TAPE_LABEL_LEN {
         return Err(RsgError::FileTooShort);
@@ -280,8 +1515,8 @@ fn has_label(map: &Mmap) -> Result<bool, RsgError> {
 /// NB: This is a copy function.
 /// NB2: Bounds checking is not performed.
 /// NB3: String conversion is not performed here.
-fn convert_bytes_to_ascii(map: &Mmap, start: usize, len: usize) -> Vec<u8> {
-    let mut header_bytes = if is_ascii(map, start) {
+fn convert_bytes_to_ascii(map: &[u8], start: usize, len: usize) -> Vec<u8> {
+    let header_bytes = if is_ascii(map, start) {
         map[start..(start + len)].to_vec()
     } else {
         map[start..(start + len)]
@@ -289,11 +1524,11 @@ fn convert_bytes_to_ascii(map: &Mmap, start: usize, len: usize) -> Vec<u8> {
             .map(|c| to_ascii(*c))
             .collect::<Vec<_>>()
     };
-    // Truncate to valid string.
-    let i = header_bytes
-        .iter()
-        .position(|x| *x == 0)
-        .unwrap_or(header_bytes.len());
-    header_bytes.truncate(i);
-    header_bytes
+    truncate_at_zero(&header_bytes).to_vec()
+}
+
+/// Truncate the bytes of a text header to a valid string, which ends before the first zero.
+fn truncate_at_zero(bytes: &[u8]) -> &[u8] {
+    let i = bytes.iter().position(|x| *x == 0).unwrap_or(bytes.len());
+    &bytes[..i]
 }
@@ -0,0 +1,57 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`TracePosition`], which gives the line numbers and the scaled
+//! ensemble coordinates of a trace (see [`crate::SegyFile::positions_iter`]).
+use crate::compact::TraceKey;
+
+/// The line numbers and the ensemble (CDP) coordinates of a trace, as needed to plot a map of
+/// the traces of a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracePosition {
+    /// The index of the trace in the file.
+    pub index: usize,
+    /// The inline number of the trace.
+    pub inline: i32,
+    /// The crossline number of the trace.
+    pub crossline: i32,
+    /// The X coordinate of the ensemble, multiplied or divided by the coordinate scalar.
+    pub x: f64,
+    /// The Y coordinate of the ensemble, multiplied or divided by the coordinate scalar.
+    pub y: f64,
+}
+
+impl TracePosition {
+    pub(crate) fn new(index: usize, key: TraceKey) -> Self {
+        TracePosition {
+            index,
+            inline: key.inline_no,
+            crossline: key.crossline_no,
+            x: scale(key.x_ensemble, key.coordinate_scalar),
+            y: scale(key.y_ensemble, key.coordinate_scalar),
+        }
+    }
+}
+
+/// Get the bounding box of positions as `[x_min, y_min, x_max, y_max]`, or `None` if there are
+/// no positions.
+pub(crate) fn bounding_box<I: Iterator<Item = TracePosition>>(positions: I) -> Option<[f64; 4]> {
+    positions.fold(None, |bbox, p| {
+        Some(match bbox {
+            None => [p.x, p.y, p.x, p.y],
+            Some([x_min, y_min, x_max, y_max]) => [
+                x_min.min(p.x),
+                y_min.min(p.y),
+                x_max.max(p.x),
+                y_max.max(p.y),
+            ],
+        })
+    })
+}
+
+/// Apply a coordinate scalar as with [`giga_segy_core::TraceHeader::apply_coordinate_scalar`].
+fn scale(value: i32, scalar: i16) -> f64 {
+    match scalar {
+        s if s > 0 => value as f64 * s as f64,
+        s if s < 0 => value as f64 / -(s as f64),
+        _ => value as f64,
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the heuristics which are used to guess where the inline and
+//! crossline numbers are in the trace headers of a file (see [`crate::SegyFile::detect_header_profile`]).
+use std::collections::HashSet;
+
+/// Line numbers above this are not considered plausible.
+const MAX_LINE: i32 = 10_000_000;
+
+/// Score how plausible a sequence of `[inline, crossline]` numbers is, from zero to one, or
+/// give `None` if it is not plausible at all (eg. because a number is zero or negative).
+///
+/// The score is the average of:
+/// - The fraction of pairs of line numbers which are unique.
+/// - The fraction of steps from one trace to the next which go forward, when the traces are
+///   sorted either by inline or by crossline.
+/// - How well the pairs fill the grid of all inlines and crosslines that occur, which rules
+///   out eg. trace sequence numbers (which are unique and sorted, but give a very sparse grid).
+pub(crate) fn line_score(lines: &[[i32; 2]]) -> Option<f64> {
+    if lines.is_empty()
+        || lines
+            .iter()
+            .flatten()
+            .any(|line| !(1..=MAX_LINE).contains(line))
+    {
+        return None;
+    }
+    let distinct = lines.iter().collect::<HashSet<_>>().len();
+    if lines.len() > 1 && distinct == 1 {
+        return None;
+    }
+    let unique = distinct as f64 / lines.len() as f64;
+
+    let steps = lines.len() - 1;
+    let monotone = if steps == 0 {
+        1.
+    } else {
+        let by_inline = lines.windows(2).filter(|w| w[0] < w[1]).count();
+        let by_crossline = lines
+            .windows(2)
+            .filter(|w| [w[0][1], w[0][0]] < [w[1][1], w[1][0]])
+            .count();
+        by_inline.max(by_crossline) as f64 / steps as f64
+    };
+
+    let inlines = lines.iter().map(|l| l[0]).collect::<HashSet<_>>().len();
+    let crosslines = lines.iter().map(|l| l[1]).collect::<HashSet<_>>().len();
+    let fill = distinct as f64 / (inlines * crosslines) as f64;
+
+    Some((unique + monotone + fill) / 3.)
+}
@@ -0,0 +1,173 @@
+//! Property tests which feed arbitrary bytes and settings to the parsers, to check that they
+//! return errors rather than panic whatever the input.
+use crate::convert_headers::{HeaderFromBytes, TraceHeaderFromBytes};
+use crate::SegyFile;
+
+use giga_segy_core::enums::{
+    AuxPolicy, CoordErrorPolicy, EndianDetection, OrderTraceBy, SampleFormatCode,
+};
+use giga_segy_core::{BinHeader, SegySettings, TapeLabel, TraceHeader};
+use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TRACE_HEADER_LEN};
+use giga_segy_out::create_headers::CreateBinHeader;
+
+use proptest::prelude::*;
+
+const TEST_FILE: &str = "../testdata/DutchMiniHead.sgy";
+
+/// Bytes which are around `len` long, or exactly `len` long half of the time.
+fn bytes_around(len: usize) -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), len),
+        prop::collection::vec(any::<u8>(), 0..(len * 2)),
+    ]
+}
+
+fn sample_format() -> impl Strategy<Value = SampleFormatCode> {
+    (1u16..=16).prop_filter_map("Not a sample format code.", |c| {
+        SampleFormatCode::new(c).ok()
+    })
+}
+
+/// Settings as they can be made with the setters, including combinations which do not pass
+/// [`SegySettings::validate`].
+fn settings() -> impl Strategy<Value = SegySettings> {
+    let overrides = (
+        prop::option::of(any::<bool>()),
+        prop::sample::select(vec![
+            EndianDetection::TrustFlag,
+            EndianDetection::Heuristic,
+            EndianDetection::ForceLe,
+            EndianDetection::ForceBe,
+        ]),
+        prop::option::of(sample_format()),
+        prop::option::of(prop::sample::select(vec![
+            SampleFormatCode::Int32,
+            SampleFormatCode::Float32,
+            SampleFormatCode::IbmFloat32,
+        ])),
+        prop::option::of(-3i32..5),
+        prop::option::of(-3i32..5),
+        prop::option::of(-3i32..70),
+        prop::option::of(-1000i16..1000),
+    );
+    let layout = (
+        prop::array::uniform4(0usize..240),
+        prop::sample::select(vec![
+            OrderTraceBy::Default,
+            OrderTraceBy::TraceSequenceOnLine,
+            OrderTraceBy::TraceNo,
+        ]),
+        prop::sample::select(vec![
+            CoordErrorPolicy::Fail,
+            CoordErrorPolicy::ZeroAndWarn,
+            CoordErrorPolicy::SkipTrace,
+        ]),
+        prop::sample::select(vec![
+            AuxPolicy::Include,
+            AuxPolicy::Exclude,
+            AuxPolicy::Only,
+        ]),
+        0usize..3,
+        0usize..300,
+        prop::sample::select(vec![0usize, 1024, 4096]),
+        prop::sample::select(vec![0usize, 512]),
+    );
+    (overrides, layout).prop_map(|(o, l)| {
+        let (le, detection, format, coordinate_format, dim_x, dim_y, dim_z, scaling) = o;
+        let (bidx, order, policy, aux, storage, prefix, window, scan) = l;
+        let mut s = SegySettings::default();
+        if let Some(le) = le {
+            s.set_override_to_le(le);
+        }
+        s.set_endianness_detection(detection);
+        if let Some(format) = format {
+            s.set_override_trace_format(format);
+        }
+        if let Some(format) = coordinate_format {
+            s.set_override_coordinate_format(format).unwrap();
+        }
+        let _ = dim_x.map(|d| s.set_override_dim_x(d));
+        let _ = dim_y.map(|d| s.set_override_dim_y(d));
+        let _ = dim_z.map(|d| s.set_override_dim_z(d));
+        let _ = scaling.map(|c| s.set_override_coordinate_scaling(c as f64));
+        let _ = s.set_inline_no_bidx(bidx[0]);
+        let _ = s.set_crossline_no_bidx(bidx[1]);
+        let _ = s.set_x_ensemble_bidx(bidx[2]);
+        let _ = s.set_y_ensemble_bidx(bidx[3]);
+        s.set_order_trace_by(order);
+        s.set_on_coordinate_parse_error(policy);
+        s.set_aux_trace_policy(aux);
+        s.set_compact_headers(storage == 1);
+        s.set_dedup_headers(storage == 2);
+        s.set_trace_prefix_bytes(prefix);
+        s.set_max_map_window(window);
+        s.set_scan_for_text_header(scan);
+        s
+    })
+}
+
+/// The beginning of the test file with some of its bytes replaced, so that the parsers get past
+/// the first checks.
+fn mangled_file() -> impl Strategy<Value = Vec<u8>> {
+    let file = std::fs::read(TEST_FILE).unwrap();
+    let max_len = file.len().min(3600 + 20 * (TRACE_HEADER_LEN + 200));
+    let changes = prop::collection::vec((0..max_len, any::<u8>()), 0..16);
+    (0..max_len, changes).prop_map(move |(len, changes)| {
+        let mut bytes = file[..max_len].to_vec();
+        for (i, b) in changes {
+            bytes[i] = b;
+        }
+        bytes.truncate(len);
+        bytes
+    })
+}
+
+/// Write `bytes` to a file and read it with `settings`.
+fn open_bytes(bytes: &[u8], settings: SegySettings) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("arbitrary.sgy");
+    std::fs::write(&path, bytes).unwrap();
+    if let Ok(segy) = SegyFile::open(path.to_str().unwrap(), settings) {
+        for i in 0..segy.trace_count() {
+            let _ = segy.get_trace_data_as_f32(i);
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn tape_label_from_arbitrary_bytes(bytes in bytes_around(TAPE_LABEL_LEN)) {
+        let _ = TapeLabel::from_bytes(&bytes, &SegySettings::default());
+    }
+
+    #[test]
+    fn bin_header_from_arbitrary_bytes(bytes in bytes_around(BIN_HEADER_LEN), s in settings()) {
+        let _ = BinHeader::from_bytes(&bytes, &s);
+    }
+
+    #[test]
+    fn trace_header_from_arbitrary_bytes(
+        bytes in bytes_around(TRACE_HEADER_LEN),
+        le in any::<bool>(),
+        idx in any::<usize>(),
+        s in settings(),
+    ) {
+        let mut bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+        bin_header.binary_flag_direction_is_le = le;
+        let _ = TraceHeader::from_bytes(&bytes, &bin_header, &s, idx);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn open_arbitrary_file(bytes in bytes_around(4000), s in settings()) {
+        open_bytes(&bytes, s);
+    }
+
+    #[test]
+    fn open_mangled_file(bytes in mangled_file(), s in settings()) {
+        open_bytes(&bytes, s);
+    }
+}
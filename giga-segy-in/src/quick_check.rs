@@ -0,0 +1,188 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains [`quick_check`], which checks whether the binary header of a file
+//! is plausible without mapping or parsing the rest of the file.
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TEXT_HEADER_LEN, TRACE_HEADER_LEN};
+
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::convert_headers::guess_le;
+use crate::memory_map::has_label;
+
+/// The result of [`quick_check`]: the most important values of the binary header, and the
+/// problems that were found with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickCheckReport {
+    /// The SEG-Y revision as major and minor revision number.
+    pub revision: [u8; 2],
+    /// The sample format, or `None` if the format code is not valid.
+    pub format: Option<SampleFormatCode>,
+    /// The number of samples per trace.
+    pub no_samples: u16,
+    /// The sample interval in microseconds (or Hz, m or ft, depending on the domain).
+    pub sample_interval_us: u16,
+    /// The number of traces given in the binary header, if any. This is `no_traces`, or if it
+    /// is zero, the extended number of traces (bytes 3261-3264) or the number of traces in the
+    /// file (bytes 3513-3520) of SEG-Y rev 2.
+    pub declared_traces: Option<u64>,
+    /// The number of traces implied by the size of the file, if the sample format and the
+    /// number of samples are valid.
+    pub estimated_traces_from_size: Option<u64>,
+    /// Whether the binary header is little endian. This is guessed in the same way as with
+    /// [`giga_segy_core::enums::EndianDetection::Heuristic`].
+    pub is_le: bool,
+    /// A description of each problem that was found. If this is empty, the file is likely to
+    /// open without trouble.
+    pub problems: Vec<String>,
+}
+
+impl QuickCheckReport {
+    /// Check whether no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check the binary header of a SEG-Y file for plausibility, without mapping the file.
+///
+/// Only the text and binary headers (and the tape label, if there is one) are read, along
+/// with the length of the file, so this is much cheaper than opening the file. An error is
+/// only returned if the file cannot be read or is too short to contain the headers. Anything
+/// else that is wrong is described in [`QuickCheckReport::problems`].
+///
+/// NB: The number of traces is estimated on the assumption that all traces have the number of
+/// samples given in the binary header.
+/// ```
+/// use std::env::var;
+/// use std::path::PathBuf;
+/// use giga_segy_in::quick_check;
+///
+/// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// root.pop();
+/// let name = root.join("testdata").join("DutchMiniHead.sgy");
+///
+/// let report = quick_check(&name).unwrap();
+/// assert!(report.is_ok(), "{:?}", report.problems);
+/// ```
+pub fn quick_check<P: AsRef<Path>>(path: P) -> Result<QuickCheckReport, RsgError> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut head = Vec::with_capacity(TAPE_LABEL_LEN + TEXT_HEADER_LEN + BIN_HEADER_LEN);
+    file.take((TAPE_LABEL_LEN + TEXT_HEADER_LEN + BIN_HEADER_LEN) as u64)
+        .read_to_end(&mut head)?;
+
+    let label_len = if has_label(&head)? { TAPE_LABEL_LEN } else { 0 };
+    let bin_start = label_len + TEXT_HEADER_LEN;
+    let bytes = head
+        .get(bin_start..(bin_start + BIN_HEADER_LEN))
+        .ok_or(RsgError::FileTooShort)?;
+
+    let is_le = guess_le(bytes);
+    let u16_at = |i: usize| {
+        let b = bytes[i..(i + 2)].try_into().unwrap();
+        if is_le {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        }
+    };
+    let u32_at = |i: usize| {
+        let b = bytes[i..(i + 4)].try_into().unwrap();
+        if is_le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        }
+    };
+    let u64_at = |i: usize| {
+        let b = bytes[i..(i + 8)].try_into().unwrap();
+        if is_le {
+            u64::from_le_bytes(b)
+        } else {
+            u64::from_be_bytes(b)
+        }
+    };
+
+    let mut problems = Vec::new();
+    let revision = [bytes[300], bytes[301]];
+    if revision[0] > 2 {
+        problems.push(format!(
+            "The SEG-Y revision {}.{} is not recognized.",
+            revision[0], revision[1]
+        ));
+    }
+
+    let format_code = u16_at(24);
+    let format = SampleFormatCode::new(format_code).ok();
+    if format.is_none() {
+        problems.push(format!(
+            "The sample format code {} is not valid.",
+            format_code
+        ));
+    }
+
+    let no_samples = u16_at(20);
+    if no_samples == 0 {
+        problems.push("The number of samples per trace is zero.".to_string());
+    }
+
+    let sample_interval_us = u16_at(16);
+    if !(1..=i16::MAX as u16).contains(&sample_interval_us) {
+        problems.push(format!(
+            "The sample interval of {} is not plausible.",
+            sample_interval_us
+        ));
+    }
+
+    let declared_traces = [u16_at(12) as u64, u32_at(60) as u64, u64_at(312)]
+        .iter()
+        .copied()
+        .find(|n| *n > 0);
+
+    let estimated_traces_from_size = match format {
+        Some(format) if no_samples > 0 => {
+            // This is the same as `BinHeader::get_first_trace_offset`.
+            let default_start = TEXT_HEADER_LEN * (u16_at(304) as usize + 1) + BIN_HEADER_LEN;
+            let first_trace_offset = match usize::try_from(u64_at(320)) {
+                Ok(offset) if offset >= default_start => offset,
+                _ => default_start,
+            };
+            let trace_len = TRACE_HEADER_LEN as u64 * (u32_at(306) as u64 + 1)
+                + no_samples as u64 * format.datum_byte_length() as u64;
+            let data_len = file_len.saturating_sub((label_len + first_trace_offset) as u64);
+            if data_len % trace_len != 0 {
+                problems.push(format!(
+                    "The last trace is incomplete ({} of {} bytes).",
+                    data_len % trace_len,
+                    trace_len
+                ));
+            }
+            Some(data_len / trace_len)
+        }
+        _ => None,
+    };
+
+    match (declared_traces, estimated_traces_from_size) {
+        (_, Some(0)) => problems.push("The file contains no complete traces.".to_string()),
+        (Some(declared), Some(estimated)) if declared != estimated => problems.push(format!(
+            "The binary header declares {} traces, but the file size implies {}.",
+            declared, estimated
+        )),
+        _ => {}
+    }
+
+    Ok(QuickCheckReport {
+        revision,
+        format,
+        no_samples,
+        sample_interval_us,
+        declared_traces,
+        estimated_traces_from_size,
+        is_le,
+        problems,
+    })
+}
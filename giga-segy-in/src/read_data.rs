@@ -1,48 +1,57 @@
 // Copyright (C) 2020 by GiGa infosystems
 //! This submodule deals with reading the actual data in the file once it has been mapped to
 //! memory.
-use giga_segy_core::bitconverter::converter_chooser;
+use giga_segy_core::bitconverter::{converter_chooser, BitConverter};
+use giga_segy_core::decoder::{DatumLen, TraceDecoder};
+use giga_segy_core::enums::SampleFormatCode;
 use giga_segy_core::errors::*;
 use giga_segy_core::BinHeader;
 use giga_segy_core::SegySettings;
-use giga_segy_core::Trace;
+use giga_segy_core::{Trace, TraceHeader};
+use num::FromPrimitive;
 
-use crate::memory_map::MappedSegY;
+use std::borrow::Borrow;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use crate::memory_map::{MapSlice, MappedSegY};
+use crate::SampleFailures;
 
 /// A function to get the bytes of a SEG-Y data trace.
 /// NB: This function does not process the data. It only performs a few sanity checks.
 pub(crate) fn get_trace_data_reference<'a>(
     segy: &'a MappedSegY,
     trace: &Trace,
-) -> Result<&'a [u8], RsgError> {
+) -> Result<MapSlice<'a>, RsgError> {
     let len = trace.len();
     let start = trace.get_start();
 
     // Sanity check.
-    if segy.map.len() < len + start {
+    if segy.len() < len + start {
         return Err(RsgError::ShortSEGY {
-            a: segy.map.len(),
+            a: segy.len(),
             b: len + start,
         });
     }
 
     // Return the bytes as they are.
-    Ok(&segy.map[start..(start + len)])
+    segy.get(start..(start + len))
 }
 
 /// A function to get the bytes of sub-slice SEG-Y data trace.
 /// NB: This function does not process the data.
-/// NB2: This function does not do san-checks. You have been warned.
+/// NB2: This function only checks that the range is in the file. You have been warned.
 pub(crate) fn get_trace_data_slice_reference(
     segy: &MappedSegY,
     range: std::ops::Range<usize>,
-) -> &[u8] {
+) -> Result<MapSlice<'_>, RsgError> {
     // Return the bytes as they are.
-    &segy.map[range]
+    segy.get(range)
 }
 
 /// A function to get the bytes of a SEG-Y data trace.
-/// NB: This function does not process the data. It only performs a few sanity checks.
+/// NB: This function does not process the data apart from taking every `step_by`-th sample
+/// (see [`SegySettings::set_step_by`]). It only performs a few sanity checks.
 pub(crate) fn get_trace_data_as_bytes_unprocessed(
     segy: &MappedSegY,
     trace: &Trace,
@@ -51,28 +60,177 @@ pub(crate) fn get_trace_data_as_bytes_unprocessed(
 ) -> Result<Vec<u8>, RsgError> {
     // Get the slice.
     let data = get_trace_data_reference(segy, trace)?;
+    decimate_trace_bytes(&data, bin_header, settings)
+}
 
+/// Takes the bytes of every `step_by`-th sample of the raw bytes of a trace (see
+/// [`decimate_bytes`]). An error is returned if the samples are packed (see [`datum_byte_length`])
+/// and not all of them are taken.
+pub(crate) fn decimate_trace_bytes(
+    data: &[u8],
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> Result<Vec<u8>, RsgError> {
+    match settings.get_step_by() {
+        1 => Ok(data.to_vec()),
+        step => Ok(decimate_bytes(
+            data,
+            datum_byte_length(bin_header, settings)?,
+            step,
+        )),
+    }
+}
+
+/// Takes the bytes of every `step`-th sample of the raw bytes of a trace, in the same way as
+/// the samples are skipped when the data is converted to f32 values.
+pub(crate) fn decimate_bytes(data: &[u8], datum_byte_length: usize, step: usize) -> Vec<u8> {
     // If we are not skipping values, return the whole trace.
-    if settings.get_step_by() == 1 {
-        Ok(data.to_vec())
+    if step == 1 {
+        data.to_vec()
     // If we are skipping values, return only the bytes which correspond to some kind of values.
     } else {
-        let datum_byte_length = if let Some(f) = settings.get_override_trace_format() {
-            f.datum_byte_length()
-        } else {
-            bin_header.sample_format_code.datum_byte_length()
-        };
         // Break data into chunks the size of the data type and then stitch together only those bits.
-        let res = data
-            .chunks(datum_byte_length)
-            .step_by(settings.get_step_by())
+        data.chunks(datum_byte_length)
+            .step_by(step)
             .flatten()
             .copied()
-            .collect::<Vec<u8>>();
-        Ok(res)
+            .collect::<Vec<u8>>()
     }
 }
 
+/// Get the byte length of a sample, checking for an override of the format and for a custom
+/// decoder in the settings. An error is returned if the samples of a custom decoder are packed,
+/// so that they do not have a byte length.
+pub(crate) fn datum_byte_length(
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> Result<usize, RsgError> {
+    match datum_len(bin_header, settings) {
+        DatumLen::Bytes(n) => Ok(n),
+        DatumLen::Bits(n) => Err(RsgError::SEGYSettingsError {
+            msg: format!(
+                "The samples of sample format code {} are packed with {} bits each, so their \
+                bytes cannot be read one by one.",
+                bin_header.raw_sample_format_code, n
+            ),
+        }),
+    }
+}
+
+/// Get the size of a sample, checking for an override of the format and for a custom decoder
+/// in the settings.
+pub(crate) fn datum_len(bin_header: &BinHeader, settings: &SegySettings) -> DatumLen {
+    if let Some(f) = settings.get_override_trace_format() {
+        DatumLen::Bytes(f.datum_byte_length())
+    } else if let Some(decoder) = custom_decoder(bin_header, settings) {
+        decoder.datum_byte_length()
+    } else {
+        DatumLen::Bytes(bin_header.sample_format_code.datum_byte_length())
+    }
+}
+
+/// Get whether the samples are little endian. This is the byte order of the file, unless it
+/// is swapped for the samples (see [`SegySettings::set_swap_data_byte_order`]).
+pub(crate) fn data_is_le(bin_header: &BinHeader, settings: &SegySettings) -> bool {
+    let le = settings
+        .get_override_to_le()
+        .unwrap_or(bin_header.binary_flag_direction_is_le);
+    le != settings.get_swap_data_byte_order()
+}
+
+/// Get the custom decoder which is registered for the sample format code of the binary header,
+/// unless the trace format is overridden (see [`SegySettings::register_custom_decoder`]).
+pub(crate) fn custom_decoder<'a>(
+    bin_header: &BinHeader,
+    settings: &'a SegySettings,
+) -> Option<&'a Arc<dyn TraceDecoder>> {
+    if settings.get_override_trace_format().is_some() {
+        return None;
+    }
+    settings.get_custom_decoder(bin_header.raw_sample_format_code)
+}
+
+/// Converts the raw bytes of a trace to f32 values with a custom decoder, taking every
+/// `step`-th value. An error is returned if the decoder does not give one value for each
+/// sample.
+fn decode_custom(
+    raw_data: &[u8],
+    decoder: &dyn TraceDecoder,
+    (code, le): (u16, bool),
+    step: usize,
+) -> Result<Vec<f32>, RsgError> {
+    let n_samples = decoder.datum_byte_length().sample_count(raw_data.len());
+    let mut data = Vec::with_capacity(n_samples);
+    decoder.decode(raw_data, n_samples, le, &mut data)?;
+    if data.len() != n_samples {
+        return Err(RsgError::BitConversionError {
+            msg: format!(
+                "The custom decoder for sample format code {} gave {} values for {} samples.",
+                code,
+                data.len(),
+                n_samples
+            ),
+        });
+    }
+    if step > 1 {
+        data = data.into_iter().step_by(step).collect();
+    }
+    Ok(data)
+}
+
+/// Get the factor by which the samples of a trace are multiplied when they are read as f32
+/// values. This is `2^-N` for the trace weighting factor `N` of the trace header if it is to be
+/// applied to the (integer) sample format (see [`SegySettings::set_apply_trace_weighting`]),
+/// multiplied by the user gain (see [`SegySettings::set_user_gain`]). The weighting factor is
+/// read as a signed number, and it is not applied to samples which are read with a custom
+/// decoder.
+pub(crate) fn sample_scale(
+    header: &TraceHeader,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> f32 {
+    let format = settings
+        .get_override_trace_format()
+        .unwrap_or(bin_header.sample_format_code);
+    let mut scale = settings.get_user_gain() as f64;
+    let custom = custom_decoder(bin_header, settings).is_some();
+    if settings.get_apply_trace_weighting() && format.is_integer() && !custom {
+        scale *= 2f64.powi(-(header.trace_weighting_factor as i16 as i32));
+    }
+    scale as f32
+}
+
+/// Multiply the samples by a factor from [`sample_scale`].
+fn apply_scale(mut data: Vec<f32>, scale: f32) -> Vec<f32> {
+    if scale != 1.0 {
+        data.iter_mut().for_each(|v| *v *= scale);
+    }
+    data
+}
+
+/// Get the byte range of the sample with index `idx` of a trace whose data starts at `start`
+/// and is `len` bytes long. The index counts only every `step_by`-th sample (see
+/// [`SegySettings::set_step_by`]), so that it matches the position of the sample in the data
+/// returned by [`get_trace_data_as_f32`].
+fn sample_range(
+    segy: &MappedSegY,
+    [start, len]: [usize; 2],
+    datum_byte_length: usize,
+    step: usize,
+    idx: usize,
+) -> Result<std::ops::Range<usize>, RsgError> {
+    let first_byte = idx
+        .checked_mul(step * datum_byte_length)
+        .and_then(|offset| offset.checked_add(start))
+        .ok_or(RsgError::TracePointOutOfBounds { idx })?;
+    let last_byte = first_byte + datum_byte_length;
+
+    if (last_byte > start + len) || (last_byte > segy.len()) {
+        return Err(RsgError::TracePointOutOfBounds { idx });
+    }
+    Ok(first_byte..last_byte)
+}
+
 /// This function takes the SEG-Y memory map and the processed metadata and returns a vector of
 /// f32 result data.
 pub(crate) fn get_trace_data_as_f32(
@@ -81,16 +239,90 @@ pub(crate) fn get_trace_data_as_f32(
     bin_header: &BinHeader,
     settings: &SegySettings,
 ) -> Result<Vec<f32>, RsgError> {
+    let raw_data = get_trace_data_reference(segy, trace)?;
+    decode_trace_data_as_f32(&raw_data, trace.get_header(), bin_header, settings)
+}
+
+/// Get the samples of a trace with the indices in `range` as f32 values. The indices count only
+/// every `step_by`-th sample, as with [`get_trace_data_as_f32`]. Only the bytes of these samples
+/// are decoded, unless they are read with a custom decoder, which gets the whole trace.
+pub(crate) fn get_trace_data_window_as_f32(
+    segy: &MappedSegY,
+    trace: &Trace,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    range: std::ops::Range<usize>,
+) -> Result<Vec<f32>, RsgError> {
+    if range.is_empty() {
+        return Ok(Vec::new());
+    }
+    if custom_decoder(bin_header, settings).is_some() {
+        let data = get_trace_data_as_f32(segy, trace, bin_header, settings)?;
+        return data
+            .get(range.clone())
+            .map(<[f32]>::to_vec)
+            .ok_or(RsgError::TracePointOutOfBounds { idx: range.end - 1 });
+    }
+
+    let datum_byte_length = datum_byte_length(bin_header, settings)?;
+    let step = settings.get_step_by();
+    // The bytes run from the first sample in the range to the end of the last one, so that
+    // taking every `step`-th sample of them gives the samples in the range.
+    let first = sample_range(
+        segy,
+        [trace.get_start(), trace.len()],
+        datum_byte_length,
+        step,
+        range.start,
+    )?;
+    let last = sample_range(
+        segy,
+        [trace.get_start(), trace.len()],
+        datum_byte_length,
+        step,
+        range.end - 1,
+    )?;
+    let raw_data = get_trace_data_slice_reference(segy, first.start..last.end)?;
+    decode_trace_data_as_f32(&raw_data, trace.get_header(), bin_header, settings)
+}
+
+/// This function takes the raw bytes of a trace and converts them to a vector of f32 values,
+/// scaled as given by [`sample_scale`] for the trace header. It is used by
+/// [`get_trace_data_as_f32`], but can also be used on bytes that were read from the file in
+/// some other manner.
+pub(crate) fn decode_trace_data_as_f32(
+    raw_data: &[u8],
+    header: &TraceHeader,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> Result<Vec<f32>, RsgError> {
+    if let Some(decoder) = custom_decoder(bin_header, settings) {
+        let le = data_is_le(bin_header, settings);
+        let data = decode_custom(
+            raw_data,
+            decoder.as_ref(),
+            (bin_header.raw_sample_format_code, le),
+            settings.get_step_by(),
+        )?;
+        return Ok(apply_scale(
+            data,
+            sample_scale(header, bin_header, settings),
+        ));
+    }
+
     // Format and byte length must be checked against overrides in the setting.
     let format = if let Some(f) = settings.get_override_trace_format() {
         f
     } else {
         bin_header.sample_format_code
     };
-    let raw_data = get_trace_data_reference(segy, trace)?;
 
     let datum_byte_length = format.datum_byte_length();
-    if raw_data.len() % datum_byte_length != 0 {
+    if !raw_data
+        .chunks_exact(datum_byte_length)
+        .remainder()
+        .is_empty()
+    {
         return Err(RsgError::TraceDivisibility {
             a: raw_data.len(),
             b: datum_byte_length,
@@ -99,25 +331,271 @@ pub(crate) fn get_trace_data_as_f32(
     }
 
     // Determine whether we are dealing with LE or BE, checking for override in the `SegySettings`.
-    let le = if let Some(le) = settings.get_override_to_le() {
-        le
+    let le = data_is_le(bin_header, settings);
+    let converter = converter_chooser(bin_header.sample_format_code, le)?;
+    let data = decode_samples(
+        raw_data,
+        datum_byte_length,
+        settings.get_step_by(),
+        (bin_header.sample_format_code, le),
+        converter,
+    )?;
+    Ok(apply_scale(
+        data,
+        sample_scale(header, bin_header, settings),
+    ))
+}
+
+/// This function takes the SEG-Y memory map and the processed metadata and returns the data of
+/// a trace converted to any primitive number type (see [`decode_trace_data_converted`]).
+pub(crate) fn get_trace_data_converted<T: FromPrimitive>(
+    segy: &MappedSegY,
+    trace: &Trace,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    scale: Option<f64>,
+) -> Result<Vec<T>, RsgError> {
+    let raw_data = get_trace_data_reference(segy, trace)?;
+    decode_trace_data_converted(&raw_data, trace.get_header(), bin_header, settings, scale)
+}
+
+/// A sample as it is stored in the file, without loss of precision.
+#[derive(Debug, Clone, Copy)]
+enum NativeSample {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+impl NativeSample {
+    /// Decode a sample of the given format. 24-bit integers are not supported, as with
+    /// [`converter_chooser`], and IBM floats are decoded with the `converter`.
+    fn decode(
+        bytes: &[u8],
+        (format, le): (SampleFormatCode, bool),
+        converter: BitConverter,
+    ) -> Result<Self, RsgError> {
+        macro_rules! read {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().map_err(RsgError::TryFromSlice)?;
+                if le {
+                    <$t>::from_le_bytes(bytes)
+                } else {
+                    <$t>::from_be_bytes(bytes)
+                }
+            }};
+        }
+        let sample = match format {
+            SampleFormatCode::Int8 => NativeSample::Signed(read!(i8) as i64),
+            SampleFormatCode::Int16 => NativeSample::Signed(read!(i16) as i64),
+            SampleFormatCode::Int32 => NativeSample::Signed(read!(i32) as i64),
+            SampleFormatCode::Int64 => NativeSample::Signed(read!(i64)),
+            SampleFormatCode::UInt8 => NativeSample::Unsigned(read!(u8) as u64),
+            SampleFormatCode::UInt16 => NativeSample::Unsigned(read!(u16) as u64),
+            SampleFormatCode::UInt32 => NativeSample::Unsigned(read!(u32) as u64),
+            SampleFormatCode::UInt64 => NativeSample::Unsigned(read!(u64)),
+            SampleFormatCode::Float32 => NativeSample::Float(read!(f32) as f64),
+            SampleFormatCode::Float64 => NativeSample::Float(read!(f64)),
+            SampleFormatCode::FixPoint32 => NativeSample::Float(read!(i32) as f64 / 65536.0),
+            _ => NativeSample::Float(converter(bytes).map_err(RsgError::TryFromSlice)? as f64),
+        };
+        Ok(sample)
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            NativeSample::Signed(v) => v as f64,
+            NativeSample::Unsigned(v) => v as f64,
+            NativeSample::Float(v) => v,
+        }
+    }
+}
+
+/// This function takes the raw bytes of a trace and converts every `step_by`-th sample to `T`.
+///
+/// The samples are multiplied by the factor from [`sample_scale`] and by `scale`, if given. If
+/// the total factor is one, integer samples are converted exactly from their own type, and
+/// otherwise each sample is converted from an [`f64`] (which is truncated towards zero for
+/// integer types, as with [`FromPrimitive::from_f64`]). An [`RsgError::SampleConversion`] is
+/// returned for the first sample which `T` cannot represent.
+pub(crate) fn decode_trace_data_converted<T: FromPrimitive>(
+    raw_data: &[u8],
+    header: &TraceHeader,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    scale: Option<f64>,
+) -> Result<Vec<T>, RsgError> {
+    if custom_decoder(bin_header, settings).is_some() {
+        let factor = scale.unwrap_or(1.);
+        let data = decode_trace_data_as_f32(raw_data, header, bin_header, settings)?;
+        return data
+            .into_iter()
+            .enumerate()
+            .map(|(idx, v)| {
+                let value = v as f64 * factor;
+                T::from_f64(value).ok_or(RsgError::SampleConversion { idx, value })
+            })
+            .collect();
+    }
+    let format = settings
+        .get_override_trace_format()
+        .unwrap_or(bin_header.sample_format_code);
+    let datum_byte_length = format.datum_byte_length();
+    if !raw_data
+        .chunks_exact(datum_byte_length)
+        .remainder()
+        .is_empty()
+    {
+        return Err(RsgError::TraceDivisibility {
+            a: raw_data.len(),
+            b: datum_byte_length,
+            format,
+        });
+    }
+    let le = data_is_le(bin_header, settings);
+    let converter = converter_chooser(format, le)?;
+    let factor = sample_scale(header, bin_header, settings) as f64 * scale.unwrap_or(1.);
+
+    let mut data = Vec::with_capacity(raw_data.len() / datum_byte_length / settings.get_step_by());
+    for (idx, bytes) in raw_data
+        .chunks_exact(datum_byte_length)
+        .step_by(settings.get_step_by())
+        .enumerate()
+    {
+        let sample = NativeSample::decode(bytes, (format, le), converter)?;
+        let value = match sample {
+            NativeSample::Signed(v) if factor == 1. => T::from_i64(v),
+            NativeSample::Unsigned(v) if factor == 1. => T::from_u64(v),
+            _ => T::from_f64(sample.as_f64() * factor),
+        };
+        let value = value.ok_or(RsgError::SampleConversion {
+            idx,
+            value: sample.as_f64() * factor,
+        })?;
+        data.push(value);
+    }
+    Ok(data)
+}
+
+/// This function gets the data of several traces as vectors of f32 values. This is equivalent
+/// to calling [`get_trace_data_as_f32`] for each trace, but the converter is only chosen once.
+pub(crate) fn get_traces_data_as_f32<T: Borrow<Trace>, I: IntoIterator<Item = T>>(
+    segy: &MappedSegY,
+    traces: I,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+) -> Result<Vec<Vec<f32>>, RsgError> {
+    if custom_decoder(bin_header, settings).is_some() {
+        return traces
+            .into_iter()
+            .map(|t| get_trace_data_as_f32(segy, t.borrow(), bin_header, settings))
+            .collect();
+    }
+    let format = if let Some(f) = settings.get_override_trace_format() {
+        f
     } else {
-        bin_header.binary_flag_direction_is_le
+        bin_header.sample_format_code
     };
-    // Step size. The default is 1.
-    let s = settings.get_step_by();
+    let datum_byte_length = format.datum_byte_length();
 
-    // Allocate result vecor.
-    let mut data = Vec::with_capacity(raw_data.len() / datum_byte_length / s);
+    let le = data_is_le(bin_header, settings);
     let converter = converter_chooser(bin_header.sample_format_code, le)?;
 
-    for slice in raw_data.chunks(datum_byte_length).step_by(s) {
+    traces
+        .into_iter()
+        .map(|trace| {
+            let raw_data = get_trace_data_reference(segy, trace.borrow())?;
+            if !raw_data
+                .chunks_exact(datum_byte_length)
+                .remainder()
+                .is_empty()
+            {
+                return Err(RsgError::TraceDivisibility {
+                    a: raw_data.len(),
+                    b: datum_byte_length,
+                    format,
+                });
+            }
+            let data = decode_samples(
+                &raw_data,
+                datum_byte_length,
+                settings.get_step_by(),
+                (bin_header.sample_format_code, le),
+                converter,
+            )?;
+            let scale = sample_scale(trace.borrow().get_header(), bin_header, settings);
+            Ok(apply_scale(data, scale))
+        })
+        .collect()
+}
+
+/// This converts the samples of a whole trace in one pass for the given number of bytes per
+/// sample, with the conversion inlined instead of calling a [`BitConverter`] for each sample
+/// (see [`decode_samples`]).
+macro_rules! fast_decode {
+    ($raw_data:expr, $step:expr, $n:literal, $from_bytes:expr) => {{
+        let mut data = Vec::with_capacity($raw_data.len() / $n / $step);
+        data.extend(
+            $raw_data
+                .chunks_exact($n)
+                .step_by($step)
+                .map(|s| -> f32 { $from_bytes(s.try_into().expect("Chunks are exact.")) }),
+        );
+        data
+    }};
+}
+
+/// Converts the raw bytes to f32 values, taking every `step`-th value. The most common sample
+/// formats (with the byte order given as `le`) are converted by specialized loops, and the
+/// others with the converter. The values are bit for bit the same either way.
+///
+/// NB: The length of the raw data must be a multiple of `datum_byte_length`.
+pub(crate) fn decode_samples(
+    raw_data: &[u8],
+    datum_byte_length: usize,
+    step: usize,
+    (format, le): (SampleFormatCode, bool),
+    converter: BitConverter,
+) -> Result<Vec<f32>, RsgError> {
+    let data = match format {
+        SampleFormatCode::Float32 if le => fast_decode!(raw_data, step, 4, f32::from_le_bytes),
+        SampleFormatCode::Float32 => fast_decode!(raw_data, step, 4, f32::from_be_bytes),
+        SampleFormatCode::Int32 if le => {
+            fast_decode!(raw_data, step, 4, |b| i32::from_le_bytes(b) as f32)
+        }
+        SampleFormatCode::Int32 => {
+            fast_decode!(raw_data, step, 4, |b| i32::from_be_bytes(b) as f32)
+        }
+        SampleFormatCode::Int16 if le => {
+            fast_decode!(raw_data, step, 2, |b| i16::from_le_bytes(b) as f32)
+        }
+        SampleFormatCode::Int16 => {
+            fast_decode!(raw_data, step, 2, |b| i16::from_be_bytes(b) as f32)
+        }
+        SampleFormatCode::Int8 => fast_decode!(raw_data, step, 1, |b| i8::from_be_bytes(b) as f32),
+        _ => return decode_with_converter(raw_data, datum_byte_length, step, converter),
+    };
+    Ok(data)
+}
+
+/// Converts the raw bytes to f32 values with a converter that has already been chosen, taking
+/// every `step`-th value.
+pub(crate) fn decode_with_converter(
+    raw_data: &[u8],
+    datum_byte_length: usize,
+    step: usize,
+    converter: BitConverter,
+) -> Result<Vec<f32>, RsgError> {
+    // Allocate result vecor.
+    let mut data = Vec::with_capacity(raw_data.len() / datum_byte_length / step);
+    for slice in raw_data.chunks(datum_byte_length).step_by(step) {
         data.push(converter(slice).map_err(RsgError::TryFromSlice)?)
     }
     Ok(data)
 }
 
-/// A function to get the bytes of a SEG-Y data trace.
+/// A function to get the bytes of a single data point of a SEG-Y data trace. The index of the
+/// data point counts only every `step_by`-th sample, as with [`sample_range`].
 /// NB: This function does not process the data. It only performs a few sanity checks.
 pub(crate) fn get_trace_data_point_as_bytes_unprocessed(
     segy: &MappedSegY,
@@ -126,25 +604,20 @@ pub(crate) fn get_trace_data_point_as_bytes_unprocessed(
     settings: &SegySettings,
     idx: usize,
 ) -> Result<Vec<u8>, RsgError> {
-    let datum_byte_length = if let Some(f) = settings.get_override_trace_format() {
-        f.datum_byte_length()
-    } else {
-        bin_header.sample_format_code.datum_byte_length()
-    };
-
-    let first_byte = trace.get_start() + idx * datum_byte_length;
-    let last_byte = first_byte + datum_byte_length;
-
-    if (last_byte > trace.get_start() + trace.len()) || (last_byte > segy.map.len()) {
-        return Err(RsgError::TracePointOutOfBounds { idx });
-    }
-    let range = first_byte..last_byte;
-    let data = get_trace_data_slice_reference(segy, range).to_vec();
+    let range = sample_range(
+        segy,
+        [trace.get_start(), trace.len()],
+        datum_byte_length(bin_header, settings)?,
+        settings.get_step_by(),
+        idx,
+    )?;
+    let data = get_trace_data_slice_reference(segy, range)?.to_vec();
     Ok(data)
 }
 
-/// This function takes the SEG-Y memory map and the processed metadata and returns a vector of
-/// f32 result data.
+/// This function takes the SEG-Y memory map and the processed metadata and returns a single
+/// data point of a trace as an f32 value. The index of the data point counts only every
+/// `step_by`-th sample, as with [`sample_range`].
 pub(crate) fn get_trace_data_point_as_f32(
     segy: &MappedSegY,
     trace: &Trace,
@@ -152,31 +625,141 @@ pub(crate) fn get_trace_data_point_as_f32(
     settings: &SegySettings,
     idx: usize,
 ) -> Result<f32, RsgError> {
-    // Determine byte length of a data point.
-    let datum_byte_length = if let Some(f) = settings.get_override_trace_format() {
-        f.datum_byte_length()
-    } else {
-        bin_header.sample_format_code.datum_byte_length()
-    };
+    // A custom decoder can only decode whole traces.
+    if custom_decoder(bin_header, settings).is_some() {
+        let data = get_trace_data_as_f32(segy, trace, bin_header, settings)?;
+        return data
+            .get(idx)
+            .copied()
+            .ok_or(RsgError::TracePointOutOfBounds { idx });
+    }
 
-    // determine byte range of the data point.
-    let first_byte = trace.get_start() + idx * datum_byte_length;
-    let last_byte = first_byte + datum_byte_length;
+    // Determine byte range of the data point (and check it).
+    let range = sample_range(
+        segy,
+        [trace.get_start(), trace.len()],
+        datum_byte_length(bin_header, settings)?,
+        settings.get_step_by(),
+        idx,
+    )?;
 
-    // san check.
-    if (last_byte > trace.get_start() + trace.len()) || (last_byte > segy.map.len()) {
-        return Err(RsgError::TracePointOutOfBounds { idx });
+    // Determine whether we are dealing with LE or BE, checking for override in the `SegySettings`.
+    let le = data_is_le(bin_header, settings);
+
+    let converter = converter_chooser(bin_header.sample_format_code, le)?;
+    let value =
+        converter(&get_trace_data_slice_reference(segy, range)?).map_err(RsgError::TryFromSlice)?;
+    Ok(value * sample_scale(trace.get_header(), bin_header, settings))
+}
+
+/// This function gets a single data point from each of a number of traces, given as pairs of
+/// trace index and sample index, and returns them as f32 values in the order of the input.
+///
+/// The converter is only chosen once and the data points are read in the order of their
+/// position in the file. If `fill_with_nan` is `false`, the first out of range pair causes an
+/// error. Otherwise the value is set to NaN and the position of the pair in the input is
+/// returned, together with the error, as the second element of the output.
+///
+/// The traces are given as a function which gets the start and length of the data of the
+/// trace with a given index and the factor by which its samples are multiplied (see
+/// [`sample_scale`]), or `None` if there is no such trace. The sample indices count only every
+/// `step_by`-th sample, as with [`sample_range`].
+pub(crate) fn get_samples_at<F: Fn(usize) -> Option<(usize, usize, f32)>>(
+    segy: &MappedSegY,
+    traces: F,
+    bin_header: &BinHeader,
+    settings: &SegySettings,
+    indices: &[(usize, usize)],
+    fill_with_nan: bool,
+) -> Result<(Vec<f32>, SampleFailures), RsgError> {
+    if let Some(decoder) = custom_decoder(bin_header, settings) {
+        return get_custom_samples_at(
+            segy,
+            traces,
+            (bin_header, settings, decoder.as_ref()),
+            indices,
+            fill_with_nan,
+        );
     }
-    // Build range.
-    let range = first_byte..last_byte;
 
-    // Determine whether we are dealing with LE or BE, checking for override in the `SegySettings`.
-    let le = if let Some(le) = settings.get_override_to_le() {
-        le
-    } else {
-        bin_header.binary_flag_direction_is_le
-    };
+    // Determine byte length of a data point.
+    let datum_byte_length = datum_byte_length(bin_header, settings)?;
 
+    // Determine whether we are dealing with LE or BE, checking for override in the `SegySettings`.
+    let le = data_is_le(bin_header, settings);
     let converter = converter_chooser(bin_header.sample_format_code, le)?;
-    converter(get_trace_data_slice_reference(segy, range)).map_err(RsgError::TryFromSlice)
+
+    // Find the first byte of each data point (or the reason why there isn't one).
+    let mut failures = Vec::new();
+    let mut positions = Vec::with_capacity(indices.len());
+    for (n, (i, idx)) in indices.iter().copied().enumerate() {
+        let first_byte = match traces(i) {
+            Some((start, len, scale)) => sample_range(
+                segy,
+                [start, len],
+                datum_byte_length,
+                settings.get_step_by(),
+                idx,
+            )
+            .map(|range| (range.start, scale)),
+            None => Err(RsgError::TraceNotFound { i }),
+        };
+        match first_byte {
+            Ok((first_byte, scale)) => positions.push((first_byte, n, scale)),
+            Err(e) if fill_with_nan => failures.push((n, e)),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Read the data in the order in which it is in the file.
+    positions.sort_unstable_by_key(|(first_byte, n, _)| (*first_byte, *n));
+    let mut data = vec![f32::NAN; indices.len()];
+    for (first_byte, n, scale) in positions {
+        let range = first_byte..(first_byte + datum_byte_length);
+        data[n] = converter(&get_trace_data_slice_reference(segy, range)?)
+            .map_err(RsgError::TryFromSlice)?
+            * scale;
+    }
+    Ok((data, failures))
+}
+
+/// This works in the same way as [`get_samples_at`] for samples which are read with a custom
+/// decoder, which can only decode whole traces. The trace of each pair is decoded unless it is
+/// the trace of the pair before.
+fn get_custom_samples_at<F: Fn(usize) -> Option<(usize, usize, f32)>>(
+    segy: &MappedSegY,
+    traces: F,
+    (bin_header, settings, decoder): (&BinHeader, &SegySettings, &dyn TraceDecoder),
+    indices: &[(usize, usize)],
+    fill_with_nan: bool,
+) -> Result<(Vec<f32>, SampleFailures), RsgError> {
+    let le = data_is_le(bin_header, settings);
+    let code = bin_header.raw_sample_format_code;
+    let mut failures = Vec::new();
+    let mut data = vec![f32::NAN; indices.len()];
+    let mut decoded: Option<(usize, Vec<f32>)> = None;
+    for (n, (i, idx)) in indices.iter().copied().enumerate() {
+        let value = match traces(i) {
+            Some((start, len, scale)) => {
+                if !matches!(decoded, Some((j, _)) if j == i) {
+                    let raw_data = get_trace_data_slice_reference(segy, start..(start + len))?;
+                    let samples =
+                        decode_custom(&raw_data, decoder, (code, le), settings.get_step_by())?;
+                    decoded = Some((i, apply_scale(samples, scale)));
+                }
+                let samples = decoded.as_ref().map(|(_, s)| s.as_slice()).unwrap_or(&[]);
+                samples
+                    .get(idx)
+                    .copied()
+                    .ok_or(RsgError::TracePointOutOfBounds { idx })
+            }
+            None => Err(RsgError::TraceNotFound { i }),
+        };
+        match value {
+            Ok(value) => data[n] = value,
+            Err(e) if fill_with_nan => failures.push((n, e)),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((data, failures))
 }
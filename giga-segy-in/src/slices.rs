@@ -0,0 +1,180 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the structures which hold data that has been extracted from
+//! several traces at once, such as a time slice.
+use giga_segy_core::errors::RsgError;
+use std::convert::TryFrom;
+
+/// A horizontal slice through a 3D survey, consisting of the value at a single sample index
+/// in every trace, arranged as a grid of inlines and crosslines.
+///
+/// The data is row-major, with each row corresponding to an inline and each column to a
+/// crossline. Grid cells for which there is no trace are NaN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSlice {
+    /// The sample index of the slice.
+    pub sample_idx: usize,
+    /// The minimum and maximum inline numbers.
+    pub inline_range: [i32; 2],
+    /// The difference in inline number between two neighbouring rows.
+    pub inline_step: i32,
+    /// The minimum and maximum crossline numbers.
+    pub crossline_range: [i32; 2],
+    /// The difference in crossline number between two neighbouring columns.
+    pub crossline_step: i32,
+    /// The values of the slice in row-major order.
+    pub data: Vec<f32>,
+}
+
+impl TimeSlice {
+    /// Get the number of rows (inlines) in the slice.
+    pub fn rows(&self) -> usize {
+        axis_len(self.inline_range, self.inline_step, self.data.is_empty())
+    }
+
+    /// Get the number of columns (crosslines) in the slice.
+    pub fn columns(&self) -> usize {
+        axis_len(
+            self.crossline_range,
+            self.crossline_step,
+            self.data.is_empty(),
+        )
+    }
+
+    /// Get the value for a given inline and crossline number. This returns `None` if the
+    /// position is outside of the grid, and NaN if it is inside the grid, but there is no trace.
+    pub fn get(&self, inline: i32, crossline: i32) -> Option<f32> {
+        let row = axis_position(inline, self.inline_range, self.inline_step)?;
+        let column = axis_position(crossline, self.crossline_range, self.crossline_step)?;
+        self.data.get(row * self.columns() + column).copied()
+    }
+}
+
+/// The kind of line along which a [`Section`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// The section runs along an inline, so the traces are ordered by crossline number.
+    Inline,
+    /// The section runs along a crossline, so the traces are ordered by inline number.
+    Crossline,
+}
+
+/// A vertical section through a 3D survey along a single inline or crossline.
+///
+/// The traces are ordered by the number of the other line, and there is a slot for every
+/// position on the grid of the survey, even if there is no trace at that position. Depending
+/// on how the section was extracted, such a slot is either empty or filled with NaN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Whether this is an inline or a crossline section.
+    pub kind: LineKind,
+    /// The number of the inline or crossline.
+    pub line_no: i32,
+    /// The crossline (for an inline section) or inline (for a crossline section) number of
+    /// each slot.
+    pub line_numbers: Vec<i32>,
+    /// The data of the trace in each slot.
+    pub traces: Vec<Vec<f32>>,
+    /// The sample interval, as given in the binary header.
+    pub sample_interval: u16,
+}
+
+impl Section {
+    /// Get the number of slots in the section.
+    pub fn len(&self) -> usize {
+        self.traces.len()
+    }
+
+    /// Check whether the section has no slots.
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    /// Get the data of the trace at a given crossline (for an inline section) or inline
+    /// (for a crossline section) number.
+    pub fn get(&self, line_no: i32) -> Option<&[f32]> {
+        let i = self.line_numbers.iter().position(|n| *n == line_no)?;
+        self.traces.get(i).map(|t| t.as_slice())
+    }
+}
+
+/// The largest number of cells of a grid of inline and crossline numbers for which a slice or
+/// section is allocated. Larger grids come from implausible line numbers rather than surveys.
+pub(crate) const MAX_GRID_CELLS: u64 = 1 << 28;
+
+/// Get the minimum, maximum and step of a set of line numbers. The step is the greatest
+/// common divisor of the distances between line numbers, so that every line number that is
+/// present falls onto the grid.
+pub(crate) fn line_axis<I: Iterator<Item = i32>>(lines: I) -> ([i32; 2], i32) {
+    let mut lines = lines.collect::<Vec<_>>();
+    lines.sort_unstable();
+    lines.dedup();
+
+    let (min, max) = match (lines.first(), lines.last()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return ([0, 0], 1),
+    };
+    let step = lines
+        .windows(2)
+        .map(|w| i64::from(w[1]) - i64::from(w[0]))
+        .fold(0, gcd)
+        .max(1);
+    ([min, max], fit_step(step))
+}
+
+/// Get the largest divisor of a step which fits into an `i32`. The step can only be larger
+/// when there are just two line numbers, and any divisor of their distance keeps both of them
+/// on the grid.
+fn fit_step(step: i64) -> i32 {
+    if let Ok(step) = i32::try_from(step) {
+        return step;
+    }
+    // The step is less than 2^32, so it either has a factor below 2^16 or is prime.
+    (2..1 << 16)
+        .find(|f| step % f == 0)
+        .and_then(|f| i32::try_from(step / f).ok())
+        .unwrap_or(1)
+}
+
+/// Get the position of a line number on an axis.
+pub(crate) fn axis_position(line: i32, range: [i32; 2], step: i32) -> Option<usize> {
+    let offset = i64::from(line) - i64::from(range[0]);
+    if offset < 0 || line > range[1] || offset % i64::from(step) != 0 {
+        return None;
+    }
+    usize::try_from(offset / i64::from(step)).ok()
+}
+
+/// Get the line number at a position on an axis.
+pub(crate) fn axis_line(position: usize, range: [i32; 2], step: i32) -> i32 {
+    (i64::from(range[0]) + position as i64 * i64::from(step)) as i32
+}
+
+/// Get the number of positions on an axis.
+pub(crate) fn axis_len(range: [i32; 2], step: i32, empty: bool) -> usize {
+    if empty {
+        0
+    } else {
+        ((i64::from(range[1]) - i64::from(range[0])) / i64::from(step)) as usize + 1
+    }
+}
+
+/// Get the number of cells of a grid, or an [`RsgError::GridTooLarge`] if there are more than
+/// [`MAX_GRID_CELLS`].
+pub(crate) fn grid_cells(rows: usize, columns: usize) -> Result<usize, RsgError> {
+    let cells = (rows as u64).saturating_mul(columns as u64);
+    if cells > MAX_GRID_CELLS {
+        return Err(RsgError::GridTooLarge {
+            cells,
+            max: MAX_GRID_CELLS,
+        });
+    }
+    Ok(cells as usize)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule reads Seismic Unix (SU) files with the `su` feature.
+//!
+//! An SU file is a stream of SEG-Y traces without the text and binary headers: Each trace has
+//! the usual 240 byte trace header, which is followed by its samples as IEEE floats in the byte
+//! order of the machine which wrote the file. The trace headers and data are read in the same
+//! way as those of a [`SegyFile`], with a binary header which is made up from the first trace
+//! header (see [`SuFile::get_bin_header`]).
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegyMetadata, SegySettings, Trace, TracesIter};
+use giga_segy_core::{BIN_HEADER_LEN, TRACE_HEADER_LEN};
+
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::ops::ControlFlow;
+
+use crate::convert_headers::HeaderFromBytes;
+use crate::memory_map::MappedSegY;
+use crate::SegyFile;
+
+/// The position of the number of samples (`ns`) in a trace header.
+const NS_OFFSET: usize = 114;
+/// The position of the sample interval (`dt`) in a trace header.
+const DT_OFFSET: usize = 116;
+
+/// A structure which represents a mapped Seismic Unix file.
+///
+/// It gives the trace headers and data in the same way as [`SegyFile`], which it uses to read
+/// the traces. The settings apply to the traces as they do for a SEG-Y file, except that there
+/// are no headers for them to apply to.
+/// ```
+/// use giga_segy_in::su::SuFile;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("one_trace.su");
+///
+/// // A trace header with `ns = 3` and `dt = 4000`, followed by three samples.
+/// let mut bytes = vec![0; 240];
+/// bytes[114..116].copy_from_slice(&3u16.to_ne_bytes());
+/// bytes[116..118].copy_from_slice(&4000u16.to_ne_bytes());
+/// for sample in [1f32, 2., 3.] {
+///     bytes.extend_from_slice(&sample.to_ne_bytes());
+/// }
+/// std::fs::write(&path, bytes).unwrap();
+///
+/// let file = SuFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+/// assert_eq!(file.trace_count(), 1);
+/// assert_eq!(file.get_bin_header().sample_interval, 4000);
+/// assert_eq!(file.get_trace_data_as_f32(0).unwrap(), vec![1., 2., 3.]);
+/// ```
+pub struct SuFile {
+    segy: SegyFile,
+}
+
+impl SuFile {
+    /// Attempts to open an SU file stored in a given location, with a given set of settings.
+    ///
+    /// The samples are read in the byte order of the settings, if they give one (see
+    /// [`SegySettings::set_override_to_le`]). Otherwise the byte order of the machine is used,
+    /// unless the sample count of the first trace only makes sense in the other byte order,
+    /// which is then used with a warning (see [`Self::get_warnings`]). An error is returned if
+    /// the first trace has no samples in either byte order.
+    pub fn open(file_name: &str, settings: SegySettings) -> Result<Self, RsgError> {
+        settings.validate()?;
+        let data = MappedSegY::new_headerless(file_name, &settings)?;
+        let mut warnings = Vec::new();
+        warnings.extend(data.map_warning().map(String::from));
+        let le = match settings.get_override_to_le() {
+            Some(le) => le,
+            None => guess_le(data.head(), data.len(), &mut warnings)?,
+        };
+        let mut bin_header = synthesize_bin_header(data.head(), le, &settings)?;
+        let scanned = data.get_metadata_for_headerless_traces(
+            &mut bin_header,
+            &settings,
+            &mut |_| ControlFlow::Continue(()),
+            &mut warnings,
+        )?;
+        let metadata = SegyMetadata::new(None, String::new(), vec![], bin_header, settings);
+        Ok(SuFile {
+            segy: SegyFile::from_scanned(data, metadata, scanned, warnings),
+        })
+    }
+
+    /// Get a reference to the [`SegySettings`] which were used when opening the file.
+    pub fn get_settings(&self) -> &SegySettings {
+        self.segy.get_settings()
+    }
+
+    /// Get the binary header which stands in for the one that SU files do not have. It gives
+    /// the sample count and sample interval of the first trace, `Float32` samples (unless the
+    /// format is overridden in the settings, see [`SegySettings::set_override_trace_format`])
+    /// and the byte order of the file. All other fields are zero or unknown.
+    pub fn get_bin_header(&self) -> &BinHeader {
+        self.segy.get_bin_header()
+    }
+
+    /// Get whether the file is little endian.
+    pub fn is_le(&self) -> bool {
+        self.get_bin_header().binary_flag_direction_is_le
+    }
+
+    /// Get anything that was not quite right in the file, but could be dealt with when opening
+    /// it (see [`SegyFile::get_warnings`]).
+    pub fn get_warnings(&self) -> &[String] {
+        self.segy.get_warnings()
+    }
+
+    /// Get the number of traces in the file.
+    pub fn trace_count(&self) -> usize {
+        self.segy.trace_count()
+    }
+
+    /// Check whether the file has no traces.
+    pub fn is_empty(&self) -> bool {
+        self.segy.is_empty()
+    }
+
+    /// Get the trace with a given index (see [`SegyFile::get_trace`]).
+    pub fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        self.segy.get_trace(i)
+    }
+
+    /// Iterate through the traces (see [`SegyFile::traces_iter`]).
+    pub fn traces_iter(&self) -> TracesIter<'_, SegyFile> {
+        self.segy.traces_iter()
+    }
+
+    /// Get the trace with a given index in any mode (see [`SegyFile::load_trace`]).
+    pub fn load_trace(&self, i: usize) -> Result<Cow<'_, Trace>, RsgError> {
+        self.segy.load_trace(i)
+    }
+
+    /// Get the bytes of the trace header of the trace with a given index exactly as they are in
+    /// the file.
+    pub fn get_trace_header_bytes(&self, i: usize) -> Result<Cow<'_, [u8]>, RsgError> {
+        self.segy.get_trace_header_bytes(i)
+    }
+
+    /// Get the trace data for a given index as a [`Vec<f32>`] (see
+    /// [`SegyFile::get_trace_data_as_f32`]).
+    pub fn get_trace_data_as_f32(&self, i: usize) -> Result<Vec<f32>, RsgError> {
+        self.segy.get_trace_data_as_f32(i)
+    }
+
+    /// Get the trace data for a given [`Trace`] of this file as a [`Vec<f32>`].
+    pub fn get_trace_data_as_f32_from_trace(&self, t: &Trace) -> Result<Vec<f32>, RsgError> {
+        self.segy.get_trace_data_as_f32_from_trace(t)
+    }
+
+    /// Get the trace data for a given index converted to any primitive number type (see
+    /// [`SegyFile::get_trace_data_as`]).
+    pub fn get_trace_data_as<T: num::FromPrimitive>(
+        &self,
+        i: usize,
+        scale: Option<f64>,
+    ) -> Result<Vec<T>, RsgError> {
+        self.segy.get_trace_data_as(i, scale)
+    }
+
+    /// Get the bytes of the trace data for a given index (see
+    /// [`SegyFile::get_trace_data_as_bytes`]).
+    pub fn get_trace_data_as_bytes(&self, i: usize) -> Result<Vec<u8>, RsgError> {
+        self.segy.get_trace_data_as_bytes(i)
+    }
+}
+
+/// Read the sample count of the first trace in the given byte order.
+fn first_sample_count(head: &[u8], le: bool) -> u16 {
+    let bytes = head[NS_OFFSET..(NS_OFFSET + 2)].try_into().unwrap();
+    if le {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    }
+}
+
+/// Guess the byte order of the file from the sample count of the first trace, which must not
+/// be zero and must fit in the file of `file_len` bytes. The byte order of the machine is
+/// preferred.
+fn guess_le(head: &[u8], file_len: usize, warnings: &mut Vec<String>) -> Result<bool, RsgError> {
+    let fits = |le: bool| {
+        let ns = first_sample_count(head, le) as usize;
+        ns > 0 && TRACE_HEADER_LEN + ns * SampleFormatCode::Float32.datum_byte_length() <= file_len
+    };
+    let native = cfg!(target_endian = "little");
+    if fits(native) {
+        Ok(native)
+    } else if fits(!native) {
+        warnings.push(format!(
+            "The sample count of the first trace only fits in the file as {} endian, which is \
+            not the byte order of this machine.",
+            if native { "big" } else { "little" }
+        ));
+        Ok(!native)
+    } else {
+        Err(RsgError::InvalidHeader {
+            msg: "The first trace of the SU file has no samples in either byte order, or more \
+                than the file holds."
+                .to_string(),
+        })
+    }
+}
+
+/// Make up a binary header from the sample count and sample interval of the first trace.
+fn synthesize_bin_header(
+    head: &[u8],
+    le: bool,
+    settings: &SegySettings,
+) -> Result<BinHeader, RsgError> {
+    let mut bytes = [0; BIN_HEADER_LEN];
+    let u16_to_bytes = if le {
+        u16::to_le_bytes
+    } else {
+        u16::to_be_bytes
+    };
+    // The sample interval and count are in the same byte order as in the trace header.
+    bytes[16..18].copy_from_slice(&head[DT_OFFSET..(DT_OFFSET + 2)]);
+    bytes[20..22].copy_from_slice(&head[NS_OFFSET..(NS_OFFSET + 2)]);
+    bytes[24..26].copy_from_slice(&u16_to_bytes(SampleFormatCode::Float32 as u16));
+    let mut settings = settings.clone();
+    settings.set_override_to_le(le);
+    BinHeader::from_bytes(&bytes, &settings)
+}
@@ -1,49 +1,184 @@
 use crate::memory_map::*;
 
-use giga_segy_core::SegySettings;
+use giga_segy_core::enums::{
+    CoordinateUnits, FixedLengthTraces, MapPopulation, MeasurementSystem, OrderTraceBy,
+    SampleFormatCode, TraceIdCode,
+};
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{AffineTransform, BinHeader, GeoPosition, SegyHandle, SegySettings};
+use giga_segy_core::{Trace, TraceHeader};
+use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+use num::ToPrimitive;
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::fmt::Debug;
+use std::ops::ControlFlow;
+use std::path::Path;
 
 #[cfg(test)]
 // NB: Some tests will only work with a valid SEGY file in the right place.
 const TEST_FILE: &str = "../testdata/DutchMiniHead.sgy";
 
+/// Write a synthetic 3D survey to `path`, with a trace for every combination of the inline and
+/// crossline numbers, except for those given in `holes` (as `[inline, crossline]`).
+/// The value of each sample is `inline * 1000 + crossline + sample_idx / 100`.
+fn write_synthetic_grid(
+    path: &Path,
+    inlines: &[i32],
+    xlines: &[i32],
+    holes: &[[i32; 2]],
+    sample_count: u16,
+) {
+    let bin_header = BinHeader::new(0, 4, sample_count, SampleFormatCode::Float32);
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        inlines,
+        xlines,
+        holes,
+        |_, data| data,
+    );
+}
+
+/// Write a synthetic grid as [`write_synthetic_grid`] does, with the given settings and binary
+/// header, which gives the number of samples. `edit` may change the header of each trace, and
+/// returns the samples which are written in place of the synthetic ones. If the binary header
+/// declares trace header extensions, they hold the line numbers of the trace.
+fn write_edited_grid<T: ToPrimitive + Debug>(
+    path: &Path,
+    settings: SegySettings,
+    bin_header: BinHeader,
+    inlines: &[i32],
+    xlines: &[i32],
+    holes: &[[i32; 2]],
+    mut edit: impl FnMut(&mut TraceHeader, Vec<f32>) -> Vec<T>,
+) {
+    let sample_count = bin_header.no_samples;
+    let extension_len = bin_header.max_additional_trace_headers as usize * 240;
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        path,
+        settings,
+        "C 1 A synthetic grid.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for il in inlines {
+        for xl in xlines {
+            if holes.contains(&[*il, *xl]) {
+                continue;
+            }
+            let mut trace_header = TraceHeader::new_3d(*xl * 10, *il * 10, *il, *xl, 1);
+            trace_header.no_samples_in_trace = sample_count;
+            let data = (0..sample_count)
+                .map(|s| (*il * 1000 + *xl) as f32 + s as f32 / 100.)
+                .collect::<Vec<f32>>();
+            let data = edit(&mut trace_header, data);
+            let extension = (extension_len > 0).then(|| {
+                format!(
+                    "{:<1$}",
+                    format!("Inline {} crossline {}", il, xl),
+                    extension_len
+                )
+            });
+            file.add_trace(trace_header, extension, data)
+                .expect("Could not add trace.");
+        }
+    }
+}
+
+/// A progress callback which does nothing.
+fn no_progress(_: giga_segy_core::SegyProgress) -> ControlFlow<()> {
+    ControlFlow::Continue(())
+}
+
 #[test]
 fn test_map_file_to_memory() {
-    let map = MappedSegY::new(TEST_FILE);
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default());
     assert!(map.is_ok());
     let map = map.unwrap();
-    println!("map len={}", map.map.len());
+    println!("map len={}", map.len());
+}
+
+#[test]
+fn test_map_population() {
+    let read_all = |settings: SegySettings| {
+        let segy = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+        let data = (0..segy.trace_count())
+            .map(|i| segy.get_trace_data_as_f32(i).unwrap())
+            .collect::<Vec<_>>();
+        (data, segy.get_warnings().to_vec())
+    };
+    let (lazy, warnings) = read_all(SegySettings::default());
+    assert!(warnings.is_empty());
+
+    for population in [MapPopulation::Populate, MapPopulation::Lock] {
+        for window in [0, 4096] {
+            let mut settings = SegySettings::default();
+            settings.set_map_population(population);
+            settings.set_max_map_window(window);
+            let (data, warnings) = read_all(settings);
+            assert_eq!(data, lazy);
+            if population == MapPopulation::Populate {
+                assert!(warnings.is_empty());
+            } else {
+                // Whether the file can be locked depends on the limits of the machine.
+                assert!(warnings.len() <= 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_map_population_lock_fails() {
+    let mut settings = SegySettings::default();
+    settings.set_map_population(MapPopulation::Lock);
+    settings.set_lock_budget(Some(1));
+    let segy = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+    let lazy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as_f32(0).unwrap(),
+        lazy.get_trace_data_as_f32(0).unwrap()
+    );
+    if cfg!(unix) {
+        assert_eq!(segy.get_warnings().len(), 1);
+        assert!(segy.get_warnings()[0].contains("lock budget of 1 bytes"));
+    } else {
+        assert!(segy.get_warnings().is_empty());
+    }
 }
 
 #[test]
 fn test_get_tape_label_of_mapped_segy_y() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let label = map.get_tape_label(&s).expect("Should be Ok but isn't.");
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let label = crate::memory_map::get_tape_label(map.head(), &s).expect("Should be Ok but isn't.");
     assert!(label.is_none());
 }
 
 #[test]
 fn test_get_bin_header() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let bin_header = map.get_bin_header(&s);
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let bin_header = crate::memory_map::get_bin_header(map.head(), &s);
     println!("{:?}", bin_header);
     assert!(bin_header.is_ok());
 }
 
 #[test]
 fn test_get_text_header() {
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let text_header = map.get_text_header();
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let text_header = crate::memory_map::get_text_header(map.head(), &SegySettings::default());
     println!("{:?}", text_header);
     assert!(text_header.is_ok());
-    assert_eq!(text_header.unwrap().chars().nth(0), Some('C'));
+    assert_eq!(text_header.unwrap().0.chars().next(), Some('C'));
 }
 
 #[test]
 fn test_get_extended_text_headers() {
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let text_headers = map.get_extended_text_headers(0);
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let text_headers = crate::memory_map::get_extended_text_headers(map.head(), 0);
     println!("{:?}", text_headers);
     assert!(text_headers.is_ok());
     assert!(text_headers.unwrap().is_empty());
@@ -52,18 +187,27 @@ fn test_get_extended_text_headers() {
 #[test]
 fn test_get_trace_headers() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
-    let trace_headers = map.get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s);
+    let trace_headers = map.get_metadata_for_traces(
+        &mut bin_header,
+        extended_headers.len(),
+        &s,
+        &mut no_progress,
+        &mut Vec::new(),
+    );
     if trace_headers.is_err() {
         println!("{:?}", trace_headers);
     }
 
     assert!(trace_headers.is_ok());
-    let trace_headers = trace_headers.unwrap();
+    let scanned = trace_headers.unwrap();
+    assert!(scanned.compact.is_empty());
+    assert_eq!(scanned.dedup.len(), 0);
+    let trace_headers = scanned.full;
     assert_eq!(trace_headers.len(), 2500);
     assert_eq!(trace_headers[0].get_start(), 3840);
     assert_eq!(trace_headers[0].len(), 50 * 4);
@@ -73,14 +217,21 @@ fn test_get_trace_headers() {
 #[test]
 fn test_get_trace_data_as_bytes_unprocessed() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
     let trace_headers = map
-        .get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s)
-        .expect("Could not header the traces.");
+        .get_metadata_for_traces(
+            &mut bin_header,
+            extended_headers.len(),
+            &s,
+            &mut no_progress,
+            &mut Vec::new(),
+        )
+        .expect("Could not header the traces.")
+        .full;
 
     let data = crate::read_data::get_trace_data_as_bytes_unprocessed(
         &map,
@@ -91,21 +242,32 @@ fn test_get_trace_data_as_bytes_unprocessed() {
     .expect("Could not get data.");
 
     assert_eq!(data.len(), trace_headers[0].len());
-    assert_eq!(data[0], map.map[trace_headers[0].get_start()]);
+    assert_eq!(
+        data[0],
+        map.get(trace_headers[0].get_start()..trace_headers[0].get_start() + 1)
+            .unwrap()[0]
+    );
     println!("{:?}", data);
 }
 
 #[test]
 fn test_get_trace_data_as_bytes_unprocessed2() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
     let trace_headers = map
-        .get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s)
-        .expect("Could not header the traces.");
+        .get_metadata_for_traces(
+            &mut bin_header,
+            extended_headers.len(),
+            &s,
+            &mut no_progress,
+            &mut Vec::new(),
+        )
+        .expect("Could not header the traces.")
+        .full;
 
     let data = crate::read_data::get_trace_data_as_bytes_unprocessed(
         &map,
@@ -116,21 +278,32 @@ fn test_get_trace_data_as_bytes_unprocessed2() {
     .expect("Could not get data.");
 
     assert_eq!(data.len(), trace_headers[2499].len());
-    assert_eq!(data[0], map.map[trace_headers[2499].get_start()]);
+    assert_eq!(
+        data[0],
+        map.get(trace_headers[2499].get_start()..trace_headers[2499].get_start() + 1)
+            .unwrap()[0]
+    );
     println!("{:?}", data);
 }
 
 #[test]
 fn test_get_trace_data_point_as_bytes_unprocessed() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
     let trace_headers = map
-        .get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s)
-        .expect("Could not header the traces.");
+        .get_metadata_for_traces(
+            &mut bin_header,
+            extended_headers.len(),
+            &s,
+            &mut no_progress,
+            &mut Vec::new(),
+        )
+        .expect("Could not header the traces.")
+        .full;
 
     let data = crate::read_data::get_trace_data_as_bytes_unprocessed(
         &map,
@@ -157,14 +330,21 @@ fn test_get_trace_data_point_as_bytes_unprocessed() {
 #[test]
 fn test_get_trace_data_as_f32() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
     let trace_headers = map
-        .get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s)
-        .expect("Could not header the traces.");
+        .get_metadata_for_traces(
+            &mut bin_header,
+            extended_headers.len(),
+            &s,
+            &mut no_progress,
+            &mut Vec::new(),
+        )
+        .expect("Could not header the traces.")
+        .full;
 
     let data = crate::read_data::get_trace_data_as_f32(&map, &trace_headers[0], &bin_header, &s)
         .expect("Could not get data.");
@@ -190,14 +370,21 @@ fn test_get_trace_data_as_f32() {
 #[test]
 fn test_get_trace_data_point_as_f32() {
     let s = SegySettings::default();
-    let map = MappedSegY::new(TEST_FILE).expect("Couldn't map SEG-Y");
-    let mut bin_header = map.get_bin_header(&s).expect("Bin header is dead.");
-    let extended_headers = map
-        .get_extended_text_headers(0)
+    let map = MappedSegY::new(TEST_FILE, &SegySettings::default()).expect("Couldn't map SEG-Y");
+    let mut bin_header =
+        crate::memory_map::get_bin_header(map.head(), &s).expect("Bin header is dead.");
+    let extended_headers = crate::memory_map::get_extended_text_headers(map.head(), 0)
         .expect("extended header is dead.");
     let trace_headers = map
-        .get_metadata_for_traces(&mut bin_header, extended_headers.len(), &s)
-        .expect("Could not header the traces.");
+        .get_metadata_for_traces(
+            &mut bin_header,
+            extended_headers.len(),
+            &s,
+            &mut no_progress,
+            &mut Vec::new(),
+        )
+        .expect("Could not header the traces.")
+        .full;
 
     for h in trace_headers.into_iter().take(100) {
         let data = crate::read_data::get_trace_data_as_f32(&map, &h, &bin_header, &s)
@@ -258,3 +445,4724 @@ fn test_get_trace_name() {
     let string = th.get_trace_name();
     assert_eq!(&string, "SEG01234");
 }
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_open_file_matches_sync() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    // The traces are the same whether or not the trace headers are stored compactly.
+    for (compact, dedup) in [(false, false), (true, false), (false, true)] {
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(compact);
+        settings.set_dedup_headers(dedup);
+        let async_segy = crate::AsyncSegyFile::open(TEST_FILE, settings)
+            .await
+            .unwrap();
+
+        assert_eq!(async_segy.get_text_header(), segy.get_text_header());
+        assert_eq!(
+            async_segy.get_text_header_encoding(),
+            segy.get_text_header_encoding()
+        );
+        assert_eq!(async_segy.get_bin_header(), segy.get_bin_header());
+        assert_eq!(async_segy.get_tape_label(), segy.get_tape_label());
+        assert_eq!(async_segy.trace_count(), segy.trace_count());
+        for (a, s) in async_segy.traces_iter().zip(segy.traces_iter()) {
+            assert_eq!(a, s);
+            assert_eq!(matches!(a, Cow::Owned(_)), compact || dedup);
+        }
+        let trace = segy.get_trace(1234).unwrap();
+        let header = trace.get_header();
+        let found = async_segy
+            .get_trace_by_xline_inline(header.crossline_no, header.inline_no)
+            .unwrap();
+        assert_eq!(found, trace);
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_get_trace_data_as_f32_matches_sync() {
+    use futures_util::StreamExt;
+
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let async_segy = crate::AsyncSegyFile::open(TEST_FILE, SegySettings::default())
+        .await
+        .unwrap();
+
+    for i in (0..segy.trace_count()).step_by(100) {
+        let data = async_segy.get_trace_data_as_f32(i).await.unwrap();
+        assert_eq!(data, segy.get_trace_data_as_f32(i).unwrap());
+    }
+
+    let stream = async_segy.traces_stream();
+    futures_util::pin_mut!(stream);
+    let mut count = 0;
+    while let Some(res) = stream.next().await {
+        let (trace, data) = res.unwrap();
+        assert_eq!(data, segy.get_trace_data_as_f32_from_trace(&trace).unwrap());
+        count += 1;
+    }
+    assert_eq!(count, segy.trace_count());
+}
+
+#[test]
+fn test_get_samples_at() {
+    let s = SegySettings::default();
+    let segy = crate::SegyFile::open(TEST_FILE, s).unwrap();
+
+    // A "horizon" that goes up and down, in reverse order of the traces.
+    let indices = (0..segy.trace_count())
+        .rev()
+        .map(|i| (i, i % 7))
+        .collect::<Vec<_>>();
+    let samples = segy
+        .get_samples_at(&indices)
+        .expect("Could not get samples.");
+
+    assert_eq!(samples.len(), indices.len());
+    for (v, (i, idx)) in samples.iter().zip(indices.iter()) {
+        let trace = segy.get_trace(*i).unwrap();
+        let expected = segy
+            .get_trace_data_point_as_f32_from_trace(&trace, *idx)
+            .expect("Could not get data point.");
+        assert_eq!(v.to_bits(), expected.to_bits());
+    }
+}
+
+#[test]
+fn test_get_samples_at_out_of_range() {
+    let s = SegySettings::default();
+    let segy = crate::SegyFile::open(TEST_FILE, s).unwrap();
+    let indices = [(0, 0), (999_999_999, 0), (1, 999_999_999), (2, 1)];
+
+    let err = segy.get_samples_at(&indices).unwrap_err();
+    assert!(matches!(err, RsgError::TraceNotFound { i: 999_999_999 }));
+
+    let (samples, failures) = segy
+        .get_samples_at_or_nan(&indices)
+        .expect("Should fill with NaN.");
+    assert_eq!(samples.len(), 4);
+    assert_eq!(samples[0], segy.get_trace_data_as_f32(0).unwrap()[0]);
+    assert!(samples[1].is_nan());
+    assert!(samples[2].is_nan());
+    assert_eq!(samples[3], segy.get_trace_data_as_f32(2).unwrap()[1]);
+
+    assert_eq!(failures.len(), 2);
+    assert!(matches!(failures[0], (1, RsgError::TraceNotFound { .. })));
+    assert!(matches!(
+        failures[1],
+        (2, RsgError::TracePointOutOfBounds { idx: 999_999_999 })
+    ));
+}
+
+#[test]
+fn test_extract_time_slice() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let inlines = [10, 12, 14, 16];
+    let xlines = [100, 101, 102];
+    write_synthetic_grid(&path, &inlines, &xlines, &[[12, 101]], 20);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let slice = segy
+        .extract_time_slice(5, false)
+        .expect("Could not get slice.");
+
+    assert_eq!(slice.inline_range, [10, 16]);
+    assert_eq!(slice.inline_step, 2);
+    assert_eq!(slice.crossline_range, [100, 102]);
+    assert_eq!(slice.crossline_step, 1);
+    assert_eq!(slice.rows(), 4);
+    assert_eq!(slice.columns(), 3);
+    assert_eq!(slice.data.len(), 12);
+
+    for (r, il) in inlines.iter().enumerate() {
+        for (c, xl) in xlines.iter().enumerate() {
+            let v = slice.data[r * 3 + c];
+            if [*il, *xl] == [12, 101] {
+                assert!(v.is_nan());
+                assert!(slice.get(*il, *xl).unwrap().is_nan());
+            } else {
+                assert_eq!(v, (il * 1000 + xl) as f32 + 0.05);
+                assert_eq!(slice.get(*il, *xl), Some(v));
+            }
+        }
+    }
+    assert_eq!(slice.get(11, 100), None);
+    assert_eq!(slice.get(10, 103), None);
+}
+
+#[test]
+fn test_line_axis_extreme_numbers() {
+    use crate::slices::{axis_len, axis_line, axis_position, line_axis};
+
+    let (range, step) = line_axis([i32::MAX, i32::MIN].iter().copied());
+    assert_eq!(range, [i32::MIN, i32::MAX]);
+    // 2^32 - 1 = 3 * 1431655765.
+    assert_eq!(step, 1_431_655_765);
+    assert_eq!(axis_len(range, step, false), 4);
+    assert_eq!(axis_position(i32::MAX, range, step), Some(3));
+    assert_eq!(axis_line(3, range, step), i32::MAX);
+
+    let (range, step) = line_axis([-2_000_000_000, 2_000_000_000].iter().copied());
+    assert_eq!(step, 2_000_000_000);
+    assert_eq!(axis_len(range, step, false), 3);
+    assert_eq!(axis_position(0, range, step), Some(1));
+
+    let (_, step) = line_axis([i32::MIN, 0, i32::MAX].iter().copied());
+    assert_eq!(step, 1);
+}
+
+#[test]
+fn test_extract_time_slice_grid_too_large() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[0, 1, 100_000], &[0, 1, 10_000], &[], 2);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert!(matches!(
+        segy.extract_time_slice(0, false),
+        Err(RsgError::GridTooLarge {
+            cells: 1_000_110_001,
+            max: 268_435_456
+        })
+    ));
+    assert!(segy.extract_inline_section(0, false).is_ok());
+}
+
+#[test]
+fn test_grid_index() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let inlines = [1001, 1003, 1005, 1007, 1009];
+    let xlines = [10, 13, 16, 19, 22, 25];
+    write_synthetic_grid(&path, &inlines, &xlines, &[[1003, 16]], 2);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let grid = segy.grid_axes();
+    assert_eq!(
+        grid.inline,
+        crate::GridAxis {
+            origin: 1001,
+            increment: 2,
+            len: 5,
+            outliers: 0
+        }
+    );
+    assert_eq!(
+        grid.crossline,
+        crate::GridAxis {
+            origin: 10,
+            increment: 3,
+            len: 6,
+            outliers: 0
+        }
+    );
+
+    for (i_idx, il) in inlines.iter().enumerate() {
+        for (x_idx, xl) in xlines.iter().enumerate() {
+            assert_eq!(segy.grid_index_of(*il, *xl), Some((i_idx, x_idx)));
+            assert_eq!(segy.line_numbers_of(i_idx, x_idx), (*il, *xl));
+            let trace = segy.get_trace_by_grid_index(i_idx, x_idx);
+            if [*il, *xl] == [1003, 16] {
+                assert!(trace.is_none());
+            } else {
+                assert_eq!(lines_of(&trace.unwrap()), [*il, *xl]);
+            }
+        }
+    }
+    assert_eq!(segy.grid_index_of(1002, 10), None);
+    assert_eq!(segy.grid_index_of(1001, 11), None);
+    assert_eq!(segy.grid_index_of(999, 10), None);
+    assert_eq!(segy.grid_index_of(1011, 10), None);
+    assert_eq!(segy.line_numbers_of(5, 6), (1011, 28));
+    assert!(segy.get_trace_by_grid_index(5, 0).is_none());
+    assert!(segy.get_trace_by_grid_index(0, 6).is_none());
+}
+
+#[test]
+fn test_grid_index_outliers() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    // One inline is off the grid of the others, and some crosslines are missing.
+    let mut inlines = (0..20).map(|i| 2001 + 2 * i).collect::<Vec<_>>();
+    inlines.push(2004);
+    let xlines = [-9, -6, 0, 3, 9];
+    write_synthetic_grid(&path, &inlines, &xlines, &[], 2);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let grid = segy.grid_axes();
+    assert_eq!(
+        grid.inline,
+        crate::GridAxis {
+            origin: 2001,
+            increment: 2,
+            len: 20,
+            outliers: 1
+        }
+    );
+    assert_eq!(
+        grid.crossline,
+        crate::GridAxis {
+            origin: -9,
+            increment: 3,
+            len: 7,
+            outliers: 0
+        }
+    );
+    assert_eq!(segy.grid_index_of(2004, 0), None);
+    assert_eq!(segy.grid_index_of(2005, 0), Some((2, 3)));
+    assert!(segy.get_trace_by_grid_index(2, 2).is_none());
+    let trace = segy.get_trace_by_grid_index(19, 6).unwrap();
+    assert_eq!(lines_of(&trace), [2039, 9]);
+
+    // The trace is found however the trace headers are stored.
+    let (mut compact, mut dedup) = (SegySettings::default(), SegySettings::default());
+    compact.set_compact_headers(true);
+    dedup.set_dedup_headers(true);
+    for settings in [compact, dedup] {
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+        assert!(segy.get_trace_by_grid_index(2, 2).is_none());
+        let trace = segy.get_trace_by_grid_index(19, 6).unwrap();
+        assert_eq!(lines_of(&trace), [2039, 9]);
+    }
+}
+
+#[test]
+fn test_grid_axis_infer() {
+    use crate::grid::GridAxis;
+
+    let axis = |lines: &[i32]| {
+        let a = GridAxis::infer(lines.iter().copied());
+        (a.origin, a.increment, a.len, a.outliers)
+    };
+    assert_eq!(axis(&[]), (0, 1, 0, 0));
+    assert_eq!(axis(&[7, 7]), (7, 1, 1, 0));
+    assert_eq!(axis(&[4, 8, 20]), (4, 4, 5, 0));
+    // Too many line numbers are off the most common distance, so all of them are kept.
+    assert_eq!(axis(&[10, 20, 30, 35]), (10, 5, 6, 0));
+    // An outlier below the grid does not move its origin.
+    let mut lines = (0..10).map(|i| 100 + 10 * i).collect::<Vec<_>>();
+    lines.push(93);
+    assert_eq!(axis(&lines), (100, 10, 10, 1));
+    let wide = GridAxis::infer([i32::MIN, i32::MAX].iter().copied());
+    assert_eq!((wide.origin, wide.increment), (i32::MIN, 1));
+}
+
+#[test]
+fn test_extract_time_slice_out_of_range() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2], &[1, 2], &[], 20);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let err = segy.extract_time_slice(20, false).unwrap_err();
+    assert!(matches!(err, RsgError::TracePointOutOfBounds { idx: 20 }));
+
+    let slice = segy
+        .extract_time_slice(20, true)
+        .expect("Should fill with NaN.");
+    assert_eq!(slice.data.len(), 4);
+    assert!(slice.data.iter().all(|v| v.is_nan()));
+}
+
+#[test]
+fn test_extract_sections() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let lines = (1..=10).collect::<Vec<i32>>();
+    write_synthetic_grid(&path, &lines, &lines, &[[3, 7]], 30);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let section = segy.extract_inline_section(4, false).unwrap();
+    assert_eq!(section.kind, crate::LineKind::Inline);
+    assert_eq!(section.line_no, 4);
+    assert_eq!(section.line_numbers, lines);
+    assert_eq!(section.sample_interval, 4);
+    assert_eq!(section.len(), 10);
+    for (xl, trace) in section.line_numbers.iter().zip(section.traces.iter()) {
+        let expected = (0..30)
+            .map(|s| (4000 + xl) as f32 + s as f32 / 100.)
+            .collect::<Vec<f32>>();
+        assert_eq!(trace, &expected);
+    }
+
+    let section = segy.extract_crossline_section(7, false).unwrap();
+    assert_eq!(section.kind, crate::LineKind::Crossline);
+    assert_eq!(section.line_numbers, lines);
+    assert!(section.get(3).unwrap().is_empty());
+    assert_eq!(section.get(5).unwrap()[2], 5007.02);
+
+    let section = segy.extract_crossline_section(7, true).unwrap();
+    let missing = section.get(3).unwrap();
+    assert_eq!(missing.len(), 30);
+    assert!(missing.iter().all(|v| v.is_nan()));
+
+    let err = segy.extract_inline_section(11, false).unwrap_err();
+    assert!(matches!(err, RsgError::LineNotFound { line: 11 }));
+}
+
+/// Get the inline and crossline numbers of a trace.
+fn lines_of(trace: &Trace) -> [i32; 2] {
+    [
+        trace.get_header().inline_no,
+        trace.get_header().crossline_no,
+    ]
+}
+
+#[test]
+fn test_line_key_orientation() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2, 3], &[10, 20], &[[3, 20]], 3);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    assert!(segy.contains_line(2, 10));
+    assert!(!segy.contains_line(10, 2));
+    assert!(!segy.contains_line(3, 20));
+    let trace = segy.get_trace_by_xline_inline(10, 2).unwrap();
+    assert_eq!(trace.get_header().inline_no, 2);
+    assert_eq!(trace.get_header().crossline_no, 10);
+    assert!(segy.get_trace_by_xline_inline(2, 10).is_none());
+
+    let mut lines = segy.lines_iter().collect::<Vec<_>>();
+    lines.sort_unstable();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], crate::LineKey::new(1, 10));
+    assert_eq!(
+        lines[1],
+        crate::LineKey {
+            inline: 1,
+            crossline: 20
+        }
+    );
+    assert_eq!(lines[4], crate::LineKey::new(3, 10));
+
+    let lookup = segy.into_lookup();
+    assert_eq!(lookup.len(), 5);
+    assert_eq!(lookup[&crate::LineKey::new(1, 20)], 1);
+    assert_eq!(lookup[&crate::LineKey::new(3, 10)], 4);
+}
+
+#[test]
+fn test_get_nearest_trace() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let holes = [[12, 102], [14, 102], [14, 103]];
+    write_synthetic_grid(
+        &path,
+        &[10, 12, 14, 16],
+        &[100, 101, 102, 103, 104],
+        &holes,
+        5,
+    );
+
+    // The trace is found however the trace headers are stored.
+    let (mut compact, mut dedup) = (SegySettings::default(), SegySettings::default());
+    compact.set_compact_headers(true);
+    dedup.set_dedup_headers(true);
+    for settings in [SegySettings::default(), compact, dedup] {
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+        let nearest = |xline: i32, inline: i32| {
+            let (trace, distance) = segy.get_nearest_trace(xline, inline).unwrap();
+            (lines_of(&trace), distance)
+        };
+        // An exact match.
+        assert_eq!(nearest(101, 10), ([10, 101], 0));
+        // Ties go to the trace that comes first in the file, on the same inline or on another.
+        assert_eq!(nearest(102, 12), ([12, 101], 1));
+        assert_eq!(nearest(102, 13), ([12, 101], 2));
+        assert_eq!(nearest(103, 15), ([16, 103], 1));
+        assert_eq!(nearest(0, 100), ([16, 100], 84 * 84 + 100 * 100));
+        // The distance saturates instead of overflowing.
+        assert_eq!(nearest(i32::MIN, i32::MAX).1, i64::MAX);
+    }
+}
+
+#[test]
+fn test_get_traces_in_rect() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let holes = [[12, 102], [14, 102], [14, 103]];
+    write_synthetic_grid(
+        &path,
+        &[10, 12, 14, 16],
+        &[100, 101, 102, 103, 104],
+        &holes,
+        5,
+    );
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let in_rect = |inline_range: [i32; 2], xline_range: [i32; 2]| {
+        segy.get_traces_in_rect(inline_range, xline_range)
+            .into_iter()
+            .map(|i| lines_of(&segy.get_trace(i).unwrap()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        in_rect([11, 14], [101, 103]),
+        vec![[12, 101], [12, 103], [14, 101]]
+    );
+    assert_eq!(in_rect([16, 16], [104, 200]), vec![[16, 104]]);
+    assert!(in_rect([11, 11], [100, 104]).is_empty());
+    assert!(in_rect([14, 10], [100, 104]).is_empty());
+    assert_eq!(
+        segy.get_traces_in_rect([i32::MIN, i32::MAX], [i32::MIN, i32::MAX]),
+        (0..17).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_diff_files() {
+    use crate::compare::{diff, DiffOptions};
+
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path_a = dir.path().join("a.sgy");
+    let path_b = dir.path().join("b.sgy");
+    write_synthetic_grid(&path_a, &[1, 2, 3], &[1, 2, 3], &[], 10);
+
+    // Patch a header field of the third trace and a sample of the fifth.
+    let mut bytes = std::fs::read(&path_a).unwrap();
+    let a = crate::SegyFile::open(path_a.to_str().unwrap(), SegySettings::default()).unwrap();
+    let header_start = a.get_trace(2).unwrap().get_start() - 240;
+    bytes[header_start..(header_start + 4)].copy_from_slice(&77i32.to_be_bytes());
+    let sample_start = a.get_trace(4).unwrap().get_start() + 3 * 4;
+    bytes[sample_start..(sample_start + 4)].copy_from_slice(&(-1.5f32).to_be_bytes());
+    std::fs::write(&path_b, &bytes).unwrap();
+    let b = crate::SegyFile::open(path_b.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let same = diff(&a, &a, DiffOptions::default()).unwrap();
+    assert!(same.is_empty());
+
+    let d = diff(&a, &b, DiffOptions::default()).unwrap();
+    assert!(!d.is_empty());
+    assert!(d.bin_header.is_empty());
+    assert_eq!(d.trace_count, None);
+    assert_eq!(d.trace_headers.len(), 1);
+    assert_eq!(d.trace_headers[0].trace, 2);
+    assert_eq!(d.trace_headers[0].fields.len(), 1);
+    assert_eq!(d.trace_headers[0].fields[0].field, "trace_sequence_on_line");
+    assert_eq!(d.trace_headers[0].fields[0].b, "77");
+    assert_eq!(d.trace_data.len(), 1);
+    assert_eq!(d.trace_data[0].trace, 4);
+    assert_eq!(d.trace_data[0].sample_counts, [10, 10]);
+    // Trace 4 is inline 2, crossline 2, and the sample was 2002.03.
+    assert!((d.trace_data[0].max_diff - 2003.53).abs() < 0.01);
+
+    // Ignoring the header field and raising the tolerance hides both differences.
+    let opts = DiffOptions {
+        tolerance: 3000.,
+        ignore_trace_header_fields: vec!["trace_sequence_on_line".to_string()],
+        ..Default::default()
+    };
+    assert!(diff(&a, &b, opts).unwrap().is_empty());
+
+    // A field name which is not in the header is an error rather than ignored silently.
+    let opts = DiffOptions {
+        ignore_trace_header_fields: vec!["trace_sequence".to_string()],
+        ..Default::default()
+    };
+    let err = diff(&a, &b, opts).unwrap_err();
+    assert!(matches!(err, RsgError::SEGYSettingsError { .. }));
+    assert!(err.to_string().contains("\"trace_sequence\""));
+    let opts = DiffOptions {
+        ignore_bin_header_fields: vec!["raw_sample_format_code".to_string()],
+        ..Default::default()
+    };
+    assert!(diff(&a, &a, opts).unwrap().is_empty());
+}
+
+#[test]
+fn test_diff_raw_sample_format_code() {
+    use crate::compare::{diff, DiffOptions};
+
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path_a = dir.path().join("a.sgy");
+    let path_b = dir.path().join("b.sgy");
+    write_synthetic_grid(&path_a, &[1], &[1, 2], &[], 10);
+    // The same file, but declared as Int32 and read as Float32.
+    let mut bytes = std::fs::read(&path_a).unwrap();
+    bytes[3224..3226].copy_from_slice(&2u16.to_be_bytes());
+    std::fs::write(&path_b, &bytes).unwrap();
+
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_format(SampleFormatCode::Float32);
+    let a = crate::SegyFile::open(path_a.to_str().unwrap(), settings.clone()).unwrap();
+    let b = crate::SegyFile::open(path_b.to_str().unwrap(), settings).unwrap();
+    assert_eq!(
+        a.get_bin_header().sample_format_code,
+        b.get_bin_header().sample_format_code
+    );
+
+    let d = diff(&a, &b, DiffOptions::default()).unwrap();
+    assert_eq!(d.bin_header.len(), 1);
+    assert_eq!(d.bin_header[0].field, "raw_sample_format_code");
+    assert_eq!((&d.bin_header[0].a[..], &d.bin_header[0].b[..]), ("5", "2"));
+    assert!(d.trace_headers.is_empty());
+    assert!(d.trace_data.is_empty());
+}
+
+#[test]
+fn test_trace_sample_count() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1], &[1, 2], &[], 10);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_sample_count(0), Some(10));
+    assert_eq!(segy.trace_sample_count(2), None);
+
+    for (step, expected) in [(2, 5), (3, 4), (4, 3), (10, 1), (11, 1)] {
+        let mut settings = SegySettings::default();
+        settings.set_step_by(step);
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+        let data = segy.get_trace_data_as_f32(1).unwrap();
+        assert_eq!(segy.trace_sample_count(1), Some(expected));
+        assert_eq!(data.len(), expected);
+    }
+}
+
+#[test]
+fn test_step_by_consistent_accessors() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1], &[1, 2], &[], 11);
+    let name = path.to_str().unwrap();
+
+    // The number of samples is always rounded up.
+    for (step, expected) in [(1, 11), (2, 6), (5, 3)] {
+        let mut settings = SegySettings::default();
+        settings.set_step_by(step);
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+
+        for i in 0..segy.trace_count() {
+            let trace = segy.get_trace(i).unwrap();
+            let floats = segy.get_trace_data_as_f32(i).unwrap();
+            let bytes = segy.get_trace_data_as_bytes(i).unwrap();
+            assert_eq!(floats.len(), expected, "step {}", step);
+            assert_eq!(bytes.len() / 4, expected, "step {}", step);
+            assert_eq!(
+                segy.get_trace_data_as_f32_from_trace(&trace).unwrap(),
+                floats
+            );
+            assert_eq!(
+                segy.get_trace_data_as_bytes_from_trace(&trace).unwrap(),
+                bytes
+            );
+            assert_eq!(segy.get_trace_data_as_f32_muted(i).unwrap(), floats);
+            assert_eq!(segy.trace_sample_count(i), Some(expected));
+            assert_eq!(segy.time_axis(i).unwrap().len(), expected);
+
+            for k in 0..expected {
+                let value = (1000 + 1 + i) as f32 + (k * step) as f32 / 100.;
+                let datum = &bytes[(k * 4)..(k * 4 + 4)];
+                assert_eq!(floats[k], value);
+                assert_eq!(f32::from_be_bytes(datum.try_into().unwrap()), value);
+                assert_eq!(
+                    segy.get_trace_data_point_as_f32_from_trace(&trace, k)
+                        .unwrap(),
+                    value
+                );
+                assert_eq!(
+                    segy.get_trace_data_point_as_bytes_from_trace(&trace, k)
+                        .unwrap(),
+                    datum
+                );
+                assert_eq!(segy.get_samples_at(&[(i, k)]).unwrap(), [value]);
+            }
+
+            assert!(matches!(
+                segy.get_trace_data_point_as_f32_from_trace(&trace, expected),
+                Err(RsgError::TracePointOutOfBounds { idx }) if idx == expected
+            ));
+            assert!(matches!(
+                segy.get_trace_data_point_as_bytes_from_trace(&trace, expected),
+                Err(RsgError::TracePointOutOfBounds { idx }) if idx == expected
+            ));
+            assert!(segy.get_samples_at(&[(i, expected)]).is_err());
+        }
+
+        let slice = segy.extract_time_slice(expected - 1, false).unwrap();
+        assert_eq!(slice.data[0], 1001. + ((expected - 1) * step) as f32 / 100.);
+        assert!(segy.extract_time_slice(expected, false).is_err());
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_step_by_matches_sync() {
+    let mut settings = SegySettings::default();
+    settings.set_step_by(5);
+    let segy = crate::SegyFile::open(TEST_FILE, settings.clone()).unwrap();
+    let async_segy = crate::AsyncSegyFile::open(TEST_FILE, settings)
+        .await
+        .unwrap();
+
+    let trace = segy.get_trace(3).unwrap();
+    let bytes = async_segy
+        .get_trace_data_as_bytes_from_trace(&trace)
+        .await
+        .unwrap();
+    let floats = async_segy.get_trace_data_as_f32(3).await.unwrap();
+    assert_eq!(bytes, segy.get_trace_data_as_bytes(3).unwrap());
+    assert_eq!(floats, segy.get_trace_data_as_f32(3).unwrap());
+    assert_eq!(bytes.len() / 4, floats.len());
+}
+
+#[test]
+fn test_parallel_reads() {
+    for compact in [false, true] {
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(compact);
+        let segy = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+        let sequential = (0..segy.trace_count())
+            .map(|i| segy.get_trace_data_as_f32(i).unwrap())
+            .collect::<Vec<_>>();
+
+        // Each thread reads every fourth trace, and all of them look up traces by position.
+        let threads = 4;
+        let parallel = std::thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|t| {
+                    let segy = &segy;
+                    scope.spawn(move || {
+                        let all = [i32::MIN, i32::MAX];
+                        assert!(!segy.get_traces_in_rect(all, all).is_empty());
+                        (t..segy.trace_count())
+                            .step_by(threads)
+                            .map(|i| (i, segy.get_trace_data_as_f32(i).unwrap()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(parallel.len(), sequential.len());
+        for (i, data) in parallel {
+            assert_eq!(data, sequential[i], "trace {}", i);
+        }
+    }
+}
+
+#[test]
+fn test_detect_header_profile() {
+    use giga_segy_core::HeaderProfile;
+
+    // A grid with the line numbers where older Petrel exports put them.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("petrel.sgy");
+    let mut settings = SegySettings::default();
+    settings.apply_profile(HeaderProfile::PetrelLegacy).unwrap();
+    // The line numbers take the place of the field record and ensemble numbers.
+    settings.set_allow_bidx_collisions(true);
+    let bin_header = BinHeader::new(12, 4000, 5, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        settings.clone(),
+        "C 1 A grid with Petrel line numbers.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for il in 101..104 {
+        for xl in 201..205 {
+            let mut trace_header = TraceHeader::new_3d(xl * 10, il * 10, il, xl, 1);
+            trace_header.trace_sequence_in_file = (il - 101) * 4 + xl - 200;
+            file.add_trace(trace_header, None, vec![il as f32; 5])
+                .unwrap();
+        }
+    }
+    drop(file);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.detect_header_profile(100),
+        Some(HeaderProfile::PetrelLegacy)
+    );
+    assert_eq!(
+        segy.detect_header_profile(2),
+        Some(HeaderProfile::PetrelLegacy)
+    );
+    assert_eq!(segy.detect_header_profile(0), None);
+    assert!(segy.get_trace_by_xline_inline(202, 101).is_none());
+
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(
+        segy.get_warnings(),
+        [
+            "inline_no at bytes 9-12 overlaps field_record_no because of the byte indices of the settings, so both are read from the same bytes.",
+            "crossline_no at bytes 21-24 overlaps ensemble_no because of the byte indices of the settings, so both are read from the same bytes.",
+        ]
+    );
+    let trace = segy.get_trace_by_xline_inline(202, 101).unwrap();
+    assert_eq!(trace.get_header().field_record_no, 101);
+    assert_eq!(trace.get_header().inline_no, 101);
+    assert_eq!(trace.get_header().crossline_no, 202);
+    assert_eq!(trace.get_header().x_ensemble, 2020);
+    assert_eq!(
+        segy.get_trace_data_as_f32_from_trace(&trace).unwrap(),
+        [101.; 5]
+    );
+
+    // Files with the standard positions are recognized as such.
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2, 3], &[1, 2], &[[2, 2]], 3);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.detect_header_profile(10),
+        Some(HeaderProfile::Standard)
+    );
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.detect_header_profile(100),
+        Some(HeaderProfile::Standard)
+    );
+}
+
+#[test]
+fn test_line_score() {
+    use crate::profile::line_score;
+
+    let grid = [[1, 1], [1, 2], [2, 1], [2, 2]];
+    assert_eq!(line_score(&grid), Some(1.));
+    assert_eq!(line_score(&[[5, 7]]), Some(1.));
+    assert_eq!(line_score(&[]), None);
+    assert_eq!(line_score(&[[1, 1], [0, 2]]), None);
+    assert_eq!(line_score(&[[3, 3], [3, 3]]), None);
+
+    // Trace sequence numbers with a line number are sorted and unique, but fill no grid.
+    let sequence = [[1, 1], [2, 1], [3, 2], [4, 2]];
+    assert!(line_score(&sequence).unwrap() < line_score(&grid).unwrap());
+    let shuffled = [[2, 1], [1, 2], [2, 2], [1, 1]];
+    assert!(line_score(&shuffled).unwrap() < 1.);
+}
+
+#[test]
+fn test_trace_ranges() {
+    let tr = Trace::new(TraceHeader::default(), 3840, 200);
+    assert_eq!(tr.get_start(), 3840);
+    assert_eq!(tr.end(), 4040);
+    assert_eq!(tr.data_range(), 3840..4040);
+    assert_eq!(tr.sample_count(SampleFormatCode::Float64), 25);
+    assert_eq!(tr.sample_count(SampleFormatCode::Int16), 100);
+    assert_eq!(tr.sample_count(SampleFormatCode::UInt8), 200);
+}
+
+#[test]
+fn test_trace_sample_count_odd_length() {
+    let tr = Trace::new(TraceHeader::default(), 0, 7);
+    assert_eq!(tr.sample_count(SampleFormatCode::Int24), 2);
+    assert_eq!(tr.sample_count(SampleFormatCode::Int32), 1);
+    assert_eq!(tr.sample_count(SampleFormatCode::Int64), 0);
+
+    let empty = Trace::new(TraceHeader::default(), 100, 0);
+    assert_eq!(empty.data_range(), 100..100);
+    assert_eq!(empty.sample_count(SampleFormatCode::Float32), 0);
+}
+
+#[test]
+fn test_trace_header_mut() {
+    let mut tr = Trace::new(TraceHeader::default(), 0, 4);
+    tr.header_mut().inline_no = 42;
+    assert_eq!(tr.get_header().inline_no, 42);
+    assert_eq!(tr.len(), 4);
+}
+
+/// Write a small file in the given byte order, with the byte order flag of the binary header
+/// zeroed out.
+fn write_unflagged_file(path: &Path, le: bool) {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut settings = SegySettings::default();
+    settings.set_override_to_le(le);
+    let bin_header = BinHeader::new(0, 4000, 50, SampleFormatCode::Float32);
+    write_edited_grid(
+        path,
+        settings,
+        bin_header,
+        &[1],
+        &[0, 1, 2],
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            (0..50).map(|s| (i * 100 + s) as f32).collect::<Vec<f32>>()
+        },
+    );
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+    file.seek(SeekFrom::Start(3296)).unwrap();
+    file.write_all(&[0; 4]).unwrap();
+}
+
+#[test]
+fn test_endianness_heuristic() {
+    use giga_segy_core::enums::EndianDetection;
+
+    let dir = tempfile::tempdir().unwrap();
+    for le in [true, false] {
+        let path = dir.path().join(format!("unflagged_{}.sgy", le));
+        write_unflagged_file(&path, le);
+        let name = path.to_str().unwrap();
+
+        let mut settings = SegySettings::default();
+        settings.set_endianness_detection(EndianDetection::Heuristic);
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+        let bin_header = segy.get_bin_header();
+        assert_eq!(bin_header.binary_flag_direction_is_le, le);
+        assert_eq!(bin_header.sample_interval, 4000);
+        assert_eq!(bin_header.no_samples, 50);
+        assert_eq!(segy.trace_count(), 3);
+        let data = segy.get_trace_data_as_f32(2).unwrap();
+        assert_eq!(data[..3], [200., 201., 202.]);
+
+        // Without the heuristic, the flag is trusted and the file is taken to be BE.
+        let segy = crate::SegyFile::open(name, SegySettings::default());
+        assert_eq!(segy.is_ok(), !le);
+    }
+}
+
+#[test]
+fn test_endianness_forced() {
+    use giga_segy_core::enums::EndianDetection;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("unflagged.sgy");
+    write_unflagged_file(&path, true);
+    let name = path.to_str().unwrap();
+
+    let mut settings = SegySettings::default();
+    settings.set_endianness_detection(EndianDetection::ForceLe);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert!(segy.get_bin_header().binary_flag_direction_is_le);
+
+    let mut settings = SegySettings::default();
+    settings.set_endianness_detection(EndianDetection::ForceBe);
+    assert!(crate::SegyFile::open(name, settings).is_err());
+
+    // An explicit override beats the detection mode.
+    let mut settings = SegySettings::default();
+    settings.set_endianness_detection(EndianDetection::ForceBe);
+    settings.set_override_to_le(true);
+    assert!(crate::SegyFile::open(name, settings).is_ok());
+}
+
+/// Build a copy of the metadata of an open file, since the metadata itself is not accessible.
+fn metadata_of(segy: &crate::SegyFile) -> giga_segy_core::SegyMetadata<SegySettings> {
+    giga_segy_core::SegyMetadata::new(
+        segy.get_tape_label().clone(),
+        segy.get_text_header().to_string(),
+        segy.get_extended_headers().to_vec(),
+        segy.get_bin_header().clone(),
+        segy.get_settings().clone(),
+    )
+}
+
+#[test]
+fn test_metadata_take_text_header() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut metadata = metadata_of(&segy);
+    let text_header = metadata.take_text_header();
+    assert_eq!(text_header, segy.get_text_header());
+    assert!(metadata.get_text_header().is_empty());
+    assert_eq!(metadata.take_text_header(), "");
+    // The file itself is unaffected.
+    assert_eq!(segy.get_text_header(), text_header);
+    assert!(segy.get_trace_data_as_f32(0).is_ok());
+}
+
+#[test]
+fn test_metadata_take_extended_headers() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut metadata = metadata_of(&segy);
+    metadata.extended_headers = vec!["A".repeat(3200), "B".repeat(3200)];
+    let extended_headers = metadata.take_extended_headers();
+    assert_eq!(extended_headers.len(), 2);
+    assert_eq!(extended_headers[1], "B".repeat(3200));
+    assert!(metadata.get_extended_headers().is_empty());
+    assert_eq!(metadata.get_text_header(), segy.get_text_header());
+}
+
+#[test]
+fn test_metadata_into_bin_header() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let bin_header = metadata_of(&segy).into_bin_header();
+    assert_eq!(&bin_header, segy.get_bin_header());
+}
+
+#[test]
+fn test_metadata_map_settings() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let metadata = metadata_of(&segy).map_settings(|s| (s.get_step_by(), "extra"));
+    assert_eq!(metadata.get_settings(), &(1, "extra"));
+    assert_eq!(metadata.get_text_header(), segy.get_text_header());
+    assert_eq!(metadata.get_bin_header(), segy.get_bin_header());
+    assert_eq!(metadata.get_tape_label(), segy.get_tape_label());
+}
+
+#[test]
+fn test_time_axis() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("intervals.sgy");
+    let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Traces with different sample intervals.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    // The first trace uses the interval of the binary header, the second has its own.
+    let mut trace_header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+    trace_header.no_samples_in_trace = 5;
+    file.add_trace(trace_header.clone(), None, vec![0f32; 5])
+        .unwrap();
+    trace_header.sample_interval_of_trace = 2000;
+    trace_header.delay_recording_time = 100;
+    file.add_trace(trace_header, None, vec![0f32; 5]).unwrap();
+    drop(file);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.effective_sample_interval(0), 4000.);
+    assert_eq!(segy.effective_sample_interval(1), 2000.);
+    assert_eq!(segy.effective_sample_interval(2), 4000.);
+    assert_eq!(segy.time_axis(0).unwrap(), [0., 4., 8., 12., 16.]);
+    assert_eq!(segy.time_axis(1).unwrap(), [100., 102., 104., 106., 108.]);
+    assert!(matches!(
+        segy.time_axis(2),
+        Err(RsgError::TraceNotFound { i: 2 })
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.set_step_by(2);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(segy.time_axis(1).unwrap(), [100., 104., 108.]);
+    assert_eq!(
+        segy.time_axis(1).unwrap().len(),
+        segy.get_trace_data_as_f32(1).unwrap().len()
+    );
+}
+
+#[test]
+fn test_trace_data_window_ms() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("window.sgy");
+    let bin_header = BinHeader::new(0, 2000, 20, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A trace with a delay.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    // The samples are at 100, 102, ..., 138 ms.
+    let mut trace_header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+    trace_header.no_samples_in_trace = 20;
+    trace_header.delay_recording_time = 100;
+    let data = (0..20).map(|s| s as f32).collect::<Vec<_>>();
+    file.add_trace(trace_header, None, data).unwrap();
+    drop(file);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_time_range(0).unwrap(), (100., 138.));
+    assert!(matches!(
+        segy.trace_time_range(1),
+        Err(RsgError::TraceNotFound { i: 1 })
+    ));
+    // The ends of the window are included, and the samples between them are found.
+    let window = |start, end| segy.get_trace_data_window_ms(0, start, end);
+    assert_eq!(window(104., 110.).unwrap(), [2., 3., 4., 5.]);
+    assert_eq!(window(103., 109.).unwrap(), [2., 3., 4.]);
+    assert_eq!(window(106., 106.).unwrap(), [3.]);
+    assert!(window(106.5, 107.5).unwrap().is_empty());
+    // The window is clamped to the trace.
+    assert_eq!(window(0., 103.).unwrap(), [0., 1.]);
+    assert_eq!(window(134., 1000.).unwrap(), [17., 18., 19.]);
+    assert!(window(0., 99.).unwrap().is_empty());
+    assert!(window(140., 150.).unwrap().is_empty());
+    assert!(matches!(
+        window(110., 104.),
+        Err(RsgError::SEGYSettingsError { .. })
+    ));
+    assert!(matches!(
+        window(f64::NAN, 104.),
+        Err(RsgError::SEGYSettingsError { .. })
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.set_strict_time_window(true);
+    let segy = crate::SegyFile::open(name, settings.clone()).unwrap();
+    assert_eq!(
+        segy.get_trace_data_window_ms(0, 100., 138.).unwrap().len(),
+        20
+    );
+    let e = segy.get_trace_data_window_ms(0, 0., 99.).unwrap_err();
+    assert!(matches!(
+        e,
+        RsgError::TimeWindowOutOfBounds {
+            trace: 0,
+            window: [w0, w1],
+            extent: [e0, e1],
+        } if [w0, w1, e0, e1] == [0., 99., 100., 138.]
+    ));
+    assert!(segy.get_trace_data_window_ms(0, 130., 139.).is_err());
+
+    // The window counts only the samples that are read.
+    settings.set_strict_time_window(false);
+    settings.set_step_by(3);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(segy.trace_time_range(0).unwrap(), (100., 136.));
+    assert_eq!(
+        segy.get_trace_data_window_ms(0, 104., 120.).unwrap(),
+        [3., 6., 9.]
+    );
+
+    // Without a sample interval the window cannot be found.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[3216..3218].copy_from_slice(&[0, 0]);
+    std::fs::write(&path, bytes).unwrap();
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert!(matches!(
+        segy.get_trace_data_window_ms(0, 100., 110.),
+        Err(RsgError::InvalidHeader { .. })
+    ));
+}
+
+#[test]
+fn test_metadata_equality() {
+    let first = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let second = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    assert_eq!(first.get_trace(3), second.get_trace(3));
+    assert_ne!(first.get_trace(3), second.get_trace(4));
+    let trace = first.get_trace(3).unwrap();
+    let rebuilt = Trace::new_with_extensions(
+        trace.get_header().clone(),
+        trace.get_start(),
+        trace.len(),
+        trace.extension_count(),
+    );
+    assert_eq!(*trace, rebuilt);
+    let traces = first
+        .traces_iter()
+        .chain(second.traces_iter())
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(traces.len(), first.trace_count());
+
+    let (first, second) = (first.into_metadata(), second.into_metadata());
+    assert_eq!(first, second);
+    let mut settings = SegySettings::default();
+    settings.set_step_by(2);
+    let third = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+    assert_ne!(first, third.into_metadata());
+}
+
+/// Write a synthetic grid with `junk` in front of it.
+fn write_grid_after_junk(dir: &Path, junk: &[u8]) -> (String, String) {
+    let path = dir.join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2, 3], &[1, 2], &[], 20);
+    let mut bytes = junk.to_vec();
+    bytes.extend(std::fs::read(&path).unwrap());
+    let junk_path = dir.join("junk.sgy");
+    std::fs::write(&junk_path, bytes).unwrap();
+    let name = |p: &Path| p.to_str().unwrap().to_string();
+    (name(&path), name(&junk_path))
+}
+
+#[test]
+fn test_scan_for_text_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let (path, junk_path) = write_grid_after_junk(dir.path(), &[0xFF; 512]);
+    let grid = crate::SegyFile::open(&path, SegySettings::default()).unwrap();
+    assert_eq!(grid.get_text_header_offset(), 0);
+
+    // The junk is taken for the headers unless the text header is searched for.
+    assert!(crate::SegyFile::open(&junk_path, SegySettings::default()).is_err());
+
+    let mut settings = SegySettings::default();
+    settings.set_scan_for_text_header(1024);
+    let segy = crate::SegyFile::open(&junk_path, settings.clone()).unwrap();
+    assert_eq!(segy.get_text_header_offset(), 512);
+    assert_eq!(segy.get_text_header(), grid.get_text_header());
+    assert_eq!(segy.get_bin_header(), grid.get_bin_header());
+    assert_eq!(segy.get_text_header_bytes(), grid.get_text_header_bytes());
+    assert_eq!(segy.trace_count(), grid.trace_count());
+    for i in 0..grid.trace_count() {
+        assert_eq!(
+            segy.get_trace(i).unwrap().get_start(),
+            grid.get_trace(i).unwrap().get_start() + 512
+        );
+        assert_eq!(
+            segy.get_trace_data_as_f32(i).unwrap(),
+            grid.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+
+    // The same when only the beginning of the file is mapped at first.
+    settings.set_max_map_window(1024);
+    let windowed = crate::SegyFile::open(&junk_path, settings).unwrap();
+    assert_eq!(windowed.get_text_header_offset(), 512);
+    assert_eq!(
+        windowed.get_trace_data_as_f32(5).unwrap(),
+        grid.get_trace_data_as_f32(5).unwrap()
+    );
+
+    // The text header is not found if it is too far in.
+    let mut settings = SegySettings::default();
+    settings.set_scan_for_text_header(256);
+    assert!(matches!(
+        crate::SegyFile::open(&junk_path, settings),
+        Err(RsgError::InvalidHeader { .. })
+    ));
+
+    // A file which starts with its text header (in EBCDIC here) is read as usual.
+    let mut settings = SegySettings::default();
+    settings.set_scan_for_text_header(1024);
+    let segy = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+    assert_eq!(segy.get_text_header_offset(), 0);
+    assert_eq!(
+        segy.into_metadata().get_text_header(),
+        crate::SegyFile::open(TEST_FILE, SegySettings::default())
+            .unwrap()
+            .get_text_header()
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_scan_for_text_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let (path, junk_path) = write_grid_after_junk(dir.path(), &[0xFF; 512]);
+    let grid = crate::SegyFile::open(&path, SegySettings::default()).unwrap();
+    let mut settings = SegySettings::default();
+    settings.set_scan_for_text_header(1024);
+    let segy = crate::AsyncSegyFile::open(&junk_path, settings)
+        .await
+        .unwrap();
+    assert_eq!(segy.get_text_header(), grid.get_text_header());
+    assert_eq!(segy.get_bin_header(), grid.get_bin_header());
+    assert_eq!(segy.trace_count(), grid.trace_count());
+    for i in 0..grid.trace_count() {
+        assert_eq!(
+            segy.get_trace_data_as_f32(i).await.unwrap(),
+            grid.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+}
+
+/// Write a synthetic grid with two extended text headers, whose count in the binary header is
+/// given as `count_bytes`. Return the paths of the grid without and with the extended headers.
+fn write_grid_with_extended_headers(dir: &Path, count_bytes: [u8; 2]) -> (String, String) {
+    let path = dir.join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2, 3], &[1, 2], &[], 20);
+    let grid = std::fs::read(&path).unwrap();
+    let mut bytes = grid[..3600].to_vec();
+    bytes[3504..3506].copy_from_slice(&count_bytes);
+    for text in ["((SEG: Observer Notes))", "((SEG: EndText))"] {
+        let mut header = text.as_bytes().to_vec();
+        header.resize(3200, b' ');
+        bytes.extend(header);
+    }
+    bytes.extend(&grid[3600..]);
+    let extended_path = dir.join("extended.sgy");
+    std::fs::write(&extended_path, bytes).unwrap();
+    let name = |p: &Path| p.to_str().unwrap().to_string();
+    (name(&path), name(&extended_path))
+}
+
+#[test]
+fn test_implausible_extended_header_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let (path, extended_path) = write_grid_with_extended_headers(dir.path(), [0x20, 0x20]);
+    let grid = crate::SegyFile::open(&path, SegySettings::default()).unwrap();
+
+    // Two spaces give a count of 8224, but only the two headers and the six traces fit.
+    let fitting = (std::fs::metadata(&extended_path).unwrap().len() as u32 - 3600) / 3200;
+    assert_eq!(fitting, 2);
+    let e = crate::SegyFile::open(&extended_path, SegySettings::default())
+        .err()
+        .unwrap();
+    assert!(
+        matches!(
+            e,
+            RsgError::ImplausibleExtendedHeaderCount {
+                count: 8224,
+                max: 2
+            }
+        ),
+        "{:?}",
+        e
+    );
+
+    let mut settings = SegySettings::default();
+    settings.set_detect_extended_headers(true);
+    let segy = crate::SegyFile::open(&extended_path, settings.clone()).unwrap();
+    assert_eq!(segy.get_bin_header().extended_header_count, 2);
+    assert_eq!(segy.get_extended_headers().len(), 2);
+    assert!(segy.get_extended_headers()[1].starts_with("((SEG: EndText))"));
+    assert!(segy.get_warnings().iter().any(|w| w.contains("8224")));
+    assert_eq!(segy.trace_count(), grid.trace_count());
+    for i in 0..grid.trace_count() {
+        assert_eq!(
+            segy.get_trace_data_as_f32(i).unwrap(),
+            grid.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+
+    // A bogus count in a file without extended headers is detected as zero.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[3504..3506].copy_from_slice(&[0x40, 0x40]);
+    std::fs::write(&path, bytes).unwrap();
+    assert!(crate::SegyFile::open(&path, SegySettings::default()).is_err());
+    let segy = crate::SegyFile::open(&path, settings).unwrap();
+    assert_eq!(segy.get_bin_header().extended_header_count, 0);
+    assert_eq!(segy.trace_count(), grid.trace_count());
+}
+
+#[test]
+fn test_max_extended_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    let (_, extended_path) = write_grid_with_extended_headers(dir.path(), [0, 2]);
+    let segy = crate::SegyFile::open(&extended_path, SegySettings::default()).unwrap();
+    assert_eq!(segy.get_extended_headers().len(), 2);
+    assert!(segy.get_warnings().is_empty());
+
+    let mut settings = SegySettings::default();
+    settings.set_max_extended_headers(1);
+    let e = crate::SegyFile::open(&extended_path, settings)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        e,
+        RsgError::ImplausibleExtendedHeaderCount { count: 2, max: 1 }
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_implausible_extended_header_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let (path, extended_path) = write_grid_with_extended_headers(dir.path(), [0x20, 0x20]);
+    let grid = crate::SegyFile::open(&path, SegySettings::default()).unwrap();
+    let e = crate::AsyncSegyFile::open(&extended_path, SegySettings::default())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        e,
+        RsgError::ImplausibleExtendedHeaderCount {
+            count: 8224,
+            max: 2
+        }
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.set_detect_extended_headers(true);
+    let segy = crate::AsyncSegyFile::open(&extended_path, settings)
+        .await
+        .unwrap();
+    assert_eq!(segy.get_extended_headers().len(), 2);
+    assert_eq!(segy.trace_count(), grid.trace_count());
+    for i in 0..grid.trace_count() {
+        assert_eq!(
+            segy.get_trace_data_as_f32(i).await.unwrap(),
+            grid.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_into_parts() {
+    let open = || crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let lookup = open().into_lookup();
+    let parts = open().into_parts(true);
+    let (tape_label, text_header, extended_headers, bin_header, traces) = open().deconstruct();
+    assert_eq!(parts.tape_label, tape_label);
+    assert_eq!(parts.text_header, text_header);
+    assert_eq!(parts.extended_headers, extended_headers);
+    assert_eq!(parts.bin_header, bin_header);
+    assert_eq!(parts.traces, traces);
+    assert_eq!(parts.lookup, Some(lookup));
+    assert_eq!(open().into_parts(false).lookup, None);
+
+    let metadata = open().into_metadata();
+    let parts = metadata.clone().into_parts();
+    let (tape_label, text_header, extended_headers, bin_header, settings) = metadata.deconstruct();
+    assert_eq!(parts.tape_label, tape_label);
+    assert_eq!(parts.text_header, text_header);
+    assert_eq!(parts.extended_headers, extended_headers);
+    assert_eq!(parts.bin_header, bin_header);
+    assert_eq!(parts.settings, settings);
+}
+
+#[test]
+fn test_trace_header_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("extensions.sgy");
+    let mut bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    bin_header.max_additional_trace_headers = 1;
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Traces with one trace header extension each.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for i in 0..4 {
+        let mut trace_header = TraceHeader::new_3d(0, 0, 1, i, 1);
+        trace_header.no_samples_in_trace = 10;
+        let extension = format!("{:<232}SEG00001", format!("Extension of trace {}", i));
+        let data = (0..10).map(|s| (i * 10 + s) as f32).collect::<Vec<_>>();
+        file.add_trace(trace_header, Some(extension), data).unwrap();
+    }
+    drop(file);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_bin_header().max_additional_trace_headers, 1);
+    assert_eq!(segy.trace_count(), 4);
+    for i in 0..4 {
+        let trace = segy.get_trace(i).unwrap();
+        assert_eq!(trace.extension_count(), 1);
+        assert_eq!(trace.get_header().crossline_no, i as i32);
+
+        let extension = segy.get_trace_extension_bytes(i).unwrap();
+        assert_eq!(extension.len(), 240);
+        assert!(extension.starts_with(format!("Extension of trace {}", i).as_bytes()));
+        assert!(extension.ends_with(b"SEG00001"));
+
+        let data = segy.get_trace_data_as_f32(i).unwrap();
+        let expected = (0..10).map(|s| (i * 10 + s) as f32).collect::<Vec<_>>();
+        assert_eq!(data, expected);
+    }
+    assert!(segy.get_trace_extension_bytes(4).is_none());
+}
+
+#[test]
+fn test_text_header_lines() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let lines = segy.text_header_lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 40);
+    assert!(lines.iter().all(|l| l.len() == 80));
+    assert_eq!(lines, segy.get_text_header_lines());
+    assert!(segy.text_header_line(1).unwrap().starts_with("C 1"));
+    assert_eq!(segy.text_header_line(40), Some(lines[39]));
+    assert_eq!(segy.text_header_line(0), None);
+    assert_eq!(segy.text_header_line(41), None);
+}
+
+#[test]
+fn test_text_header_lines_multibyte() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut metadata = metadata_of(&segy);
+    let mut text_header = "C 1 Grüße".to_string();
+    text_header.push_str(&" ".repeat(71));
+    text_header.push_str(&"x".repeat(3120));
+    metadata.text_header = text_header.clone();
+
+    let lines = metadata.text_header_lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 40);
+    assert_eq!(lines[0].chars().count(), 80);
+    assert_eq!(lines[0].len(), 82);
+    assert!(lines[0].starts_with("C 1 Grüße"));
+    assert_eq!(metadata.line(2), Some("x".repeat(80).as_str()));
+    assert_eq!(lines.concat(), text_header);
+    assert_eq!(metadata.get_text_header_lines(), lines);
+}
+
+#[test]
+fn test_text_header_trimmed() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let trimmed = segy.get_text_header_lines_trimmed();
+    assert_eq!(trimmed.len(), 40);
+    for (trimmed, raw) in trimmed.iter().zip(segy.text_header_lines()) {
+        assert_eq!(trimmed, raw.trim_end());
+    }
+    assert!(trimmed[0].starts_with("C 1"));
+    assert!(!trimmed[0].ends_with(' '));
+    let joined = segy.get_text_header_trimmed();
+    assert!(joined.starts_with(&format!("{}\n", trimmed[0])));
+    assert!(joined.lines().count() <= 40);
+    assert!(!segy.text_header_is_blank());
+}
+
+#[test]
+fn test_text_header_is_blank() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut metadata = metadata_of(&segy);
+
+    metadata.text_header = " ".repeat(3200);
+    assert!(metadata.text_header_is_blank());
+    assert_eq!(metadata.get_text_header_trimmed(), "");
+    assert!(metadata
+        .get_text_header_lines_trimmed()
+        .iter()
+        .all(|l| l.is_empty()));
+
+    // A skeleton header, which only has the card numbers.
+    let skeleton = (1..=40)
+        .map(|i| format!("{:<80}", format!("C{:2}", i)))
+        .collect::<String>();
+    metadata.text_header = skeleton.clone();
+    assert!(metadata.text_header_is_blank());
+    assert_eq!(metadata.get_text_header_lines_trimmed()[0], "C 1");
+    assert_eq!(metadata.get_text_header_trimmed().lines().count(), 40);
+
+    metadata.text_header = skeleton.replacen("C 5", "C 5 CLIENT", 1);
+    assert!(!metadata.text_header_is_blank());
+}
+
+#[cfg(feature = "to_json")]
+#[test]
+fn test_metadata_to_json() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut metadata = metadata_of(&segy);
+    let text_header = format!("{:<80}{:<3120}", "C 1 CLIENT: GIGA", "C 2");
+    metadata.text_header = text_header.clone();
+
+    let json = metadata.to_json(false).unwrap();
+    assert!(json.contains(r#""text_header":"C 1 CLIENT: GIGA\nC 2","#));
+    assert!(!json.contains("raw_text_header"));
+    assert!(json.contains(&format!(
+        r#""sample_interval":{},"#,
+        segy.get_bin_header().sample_interval
+    )));
+
+    let json = metadata.to_json(true).unwrap();
+    assert!(json.contains(&format!(r#""raw_text_header":"{}","#, text_header)));
+}
+
+#[test]
+fn test_open_with_progress() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    let lines = (1..=30).collect::<Vec<_>>();
+    write_synthetic_grid(&path, &lines, &lines, &[], 5);
+
+    let mut reports = Vec::new();
+    let segy =
+        crate::SegyFile::open_with_progress(path.to_str().unwrap(), SegySettings::default(), |p| {
+            reports.push(p);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(segy.trace_count(), 900);
+
+    assert_eq!(reports[0].phase, giga_segy_core::ProgressPhase::Headers);
+    let scan = reports
+        .iter()
+        .filter(|p| p.phase == giga_segy_core::ProgressPhase::TraceScan)
+        .collect::<Vec<_>>();
+    assert!(scan.len() > 2);
+    assert!(scan
+        .windows(2)
+        .all(|w| w[0].traces_done <= w[1].traces_done));
+    assert!(scan.windows(2).all(|w| w[0].bytes_done <= w[1].bytes_done));
+    assert_eq!(scan.last().unwrap().traces_done, 900);
+}
+
+#[test]
+fn test_open_with_progress_cancelled() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[1, 2, 3], &[1, 2, 3], &[], 5);
+
+    let result =
+        crate::SegyFile::open_with_progress(path.to_str().unwrap(), SegySettings::default(), |p| {
+            if p.traces_done >= 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+    assert!(matches!(result, Err(RsgError::Cancelled)));
+}
+
+/// Write a file of four traces of Float32 data, whose binary header claims that the data is
+/// Float64, so that the file is exactly half as long as it should be.
+fn write_half_size_file(path: &Path) {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let bin_header = BinHeader::new(4, 4000, 10, SampleFormatCode::Float32);
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &[0, 1, 2, 3],
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            (0..10).map(|s| (i * 100 + s) as f32).collect::<Vec<f32>>()
+        },
+    );
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+    file.seek(SeekFrom::Start(3224)).unwrap();
+    file.write_all(&6u16.to_be_bytes()).unwrap();
+}
+
+#[test]
+fn test_format_size_mismatch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("half_size.sgy");
+    write_half_size_file(&path);
+
+    let res = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default());
+    match res {
+        Err(RsgError::FormatSizeMismatch {
+            declared,
+            implied_by_size,
+        }) => {
+            assert_eq!(declared, SampleFormatCode::Float64);
+            assert_eq!(implied_by_size, SampleFormatCode::Float32);
+        }
+        _ => panic!("Expected a format size mismatch, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_format_size_mismatch_trusted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("half_size.sgy");
+    write_half_size_file(&path);
+
+    let mut settings = SegySettings::default();
+    settings.set_trust_file_size(true);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(
+        segy.get_bin_header().sample_format_code,
+        SampleFormatCode::Float32
+    );
+    assert_eq!(segy.get_warnings().len(), 1);
+    assert_eq!(segy.trace_count(), 4);
+    let data = segy.get_trace_data_as_f32(3).unwrap();
+    assert_eq!(data.len(), 10);
+    assert_eq!(data[..3], [300., 301., 302.]);
+
+    // Overriding the trace format takes precedence over the file size.
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_format(SampleFormatCode::Float32);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert!(segy.get_warnings().is_empty());
+
+    // A file which is consistent gives no warnings.
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    assert!(segy.get_warnings().is_empty());
+}
+
+#[test]
+fn test_effective_parameters() {
+    use crate::Provenance;
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let params = segy.effective_parameters().clone();
+    let bin_header = segy.get_bin_header();
+    assert_ne!(params.is_le.source, Provenance::Override);
+    assert_eq!(params.is_le.value, bin_header.binary_flag_direction_is_le);
+    assert_eq!(params.sample_format.source, Provenance::File);
+    assert_eq!(params.sample_format.value, bin_header.sample_format_code);
+    assert_eq!(params.coordinate_format.source, Provenance::Fallback);
+    assert_eq!(params.coordinate_format.value, SampleFormatCode::Int32);
+    assert_eq!(params.coordinate_units.source, Provenance::File);
+    assert_eq!(params.coordinate_scaling.source, Provenance::File);
+    assert_eq!(params.coordinate_scaling.value, None);
+    assert_eq!(params.trace_id_code.value, None);
+    assert_eq!(params.no_samples.source, Provenance::File);
+    assert_eq!(params.no_samples.value, bin_header.no_samples as usize);
+    assert_eq!(params.inline_no_bidx.source, Provenance::Fallback);
+    assert_eq!(
+        params.inline_no_bidx.value,
+        giga_segy_core::INLINE_BYTE_LOCATION
+    );
+
+    let mut settings = SegySettings::default();
+    settings.set_override_to_le(bin_header.binary_flag_direction_is_le);
+    settings.set_override_trace_format(bin_header.sample_format_code);
+    settings
+        .set_override_coordinate_format(SampleFormatCode::Float32)
+        .unwrap();
+    settings.set_override_coordinate_scaling(-100.).unwrap();
+    settings.set_override_sample_interval(2000.);
+    settings.set_override_dim_z(10).unwrap();
+    settings.set_inline_no_bidx(8).unwrap();
+    let segy = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+    let overridden = segy.effective_parameters();
+    assert_eq!(overridden.is_le.value, params.is_le.value);
+    assert_eq!(overridden.is_le.source, Provenance::Override);
+    assert_eq!(overridden.sample_format.value, params.sample_format.value);
+    assert_eq!(overridden.sample_format.source, Provenance::Override);
+    assert_eq!(
+        overridden.coordinate_format.value,
+        SampleFormatCode::Float32
+    );
+    assert_eq!(overridden.coordinate_format.source, Provenance::Override);
+    assert_eq!(overridden.coordinate_scaling.value, Some(-100.));
+    assert_eq!(overridden.coordinate_scaling.source, Provenance::Override);
+    assert_eq!(overridden.sample_interval.value, 2000.);
+    assert_eq!(overridden.sample_interval.source, Provenance::Override);
+    assert_eq!(overridden.no_samples.value, 10);
+    assert_eq!(overridden.no_samples.source, Provenance::Override);
+    assert_eq!(overridden.inline_no_bidx.value, 8);
+    assert_eq!(overridden.inline_no_bidx.source, Provenance::Override);
+    assert_eq!(overridden.crossline_no_bidx.source, Provenance::Fallback);
+}
+
+#[test]
+fn test_effective_parameters_fallback() {
+    use crate::Provenance;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("half_size.sgy");
+    write_half_size_file(&path);
+
+    // The sample format of the binary header is replaced because of the size of the file.
+    let mut settings = SegySettings::default();
+    settings.set_trust_file_size(true);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let params = segy.effective_parameters();
+    assert_eq!(params.sample_format.value, SampleFormatCode::Float32);
+    assert_eq!(params.sample_format.source, Provenance::Fallback);
+    // `giga_segy_out` always writes the byte order flag.
+    assert_eq!(params.is_le.source, Provenance::File);
+
+    // Forcing the byte order counts as an override.
+    let mut settings = SegySettings::default();
+    settings.set_trust_file_size(true);
+    settings.set_endianness_detection(giga_segy_core::enums::EndianDetection::ForceBe);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert!(!segy.effective_parameters().is_le.value);
+    assert_eq!(
+        segy.effective_parameters().is_le.source,
+        Provenance::Override
+    );
+}
+
+#[test]
+fn test_fix_point_32_file() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fix_point.sgy");
+
+    // The writer cannot write fixed point samples, so the samples are written as 32-bit
+    // integers with the right bit patterns, and the sample format is patched afterwards.
+    let bin_header = BinHeader::new(2, 4000, 4, SampleFormatCode::Int32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A legacy file with fixed point samples.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for i in 0..2 {
+        let mut trace_header = TraceHeader::new_3d(0, 0, 1, i, 1);
+        trace_header.no_samples_in_trace = 4;
+        // 1.0, -0.5, 1.5 and i.
+        let data = vec![
+            0x0001_0000u32 as i32,
+            0xFFFF_8000u32 as i32,
+            0x0001_8000,
+            i << 16,
+        ];
+        file.add_trace_lossless(trace_header, None, data)
+            .expect("Could not add trace.");
+    }
+    drop(file);
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.seek(SeekFrom::Start(3224)).unwrap();
+    file.write_all(&4u16.to_be_bytes()).unwrap();
+    drop(file);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_bin_header().sample_format_code,
+        SampleFormatCode::FixPoint32
+    );
+    assert_eq!(
+        segy.get_trace_data_as_f32(0).unwrap(),
+        vec![1.0, -0.5, 1.5, 0.0]
+    );
+    assert_eq!(
+        segy.get_trace_data_as_f32(1).unwrap(),
+        vec![1.0, -0.5, 1.5, 1.0]
+    );
+}
+
+/// Write a file with float coordinates, where the ensemble x coordinate of the fourth trace
+/// is NaN, which cannot be converted to an integer.
+fn write_nan_coordinate_file(path: &Path) -> SegySettings {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut settings = SegySettings::default();
+    settings
+        .set_override_coordinate_format(SampleFormatCode::Float32)
+        .unwrap();
+    let bin_header = BinHeader::new(5, 4000, 10, SampleFormatCode::Float32);
+    let xlines = [0, 1, 2, 3, 4];
+    write_edited_grid(
+        path,
+        settings.clone(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, data| {
+            header.y_ensemble = header.crossline_no * 20;
+            data
+        },
+    );
+
+    let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+    let trace_len = giga_segy_core::TRACE_HEADER_LEN + 10 * 4;
+    // The traces come after the extended header which records the coordinate format.
+    let head_len = 3600 + giga_segy_core::TEXT_HEADER_LEN;
+    let x_ensemble = head_len + 3 * trace_len + giga_segy_core::CDPX_BYTE_LOCATION;
+    file.seek(SeekFrom::Start(x_ensemble as u64)).unwrap();
+    file.write_all(&f32::NAN.to_be_bytes()).unwrap();
+    settings
+}
+
+#[test]
+fn test_coordinate_parse_error_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nan_coordinate.sgy");
+    let settings = write_nan_coordinate_file(&path);
+
+    match crate::SegyFile::open(path.to_str().unwrap(), settings) {
+        Err(RsgError::CoordinateParse { trace, offset, .. }) => {
+            assert_eq!(trace, 3);
+            assert_eq!(offset, giga_segy_core::CDPX_BYTE_LOCATION);
+        }
+        res => panic!("Expected a coordinate error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_coordinate_parse_error_zero_and_warn() {
+    use giga_segy_core::enums::CoordErrorPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nan_coordinate.sgy");
+    let mut settings = write_nan_coordinate_file(&path);
+    settings.set_on_coordinate_parse_error(CoordErrorPolicy::ZeroAndWarn);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.trace_count(), 5);
+    assert_eq!(segy.get_warnings().len(), 1);
+    let headers = segy
+        .traces_iter()
+        .map(|t| t.get_header().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(headers[3].x_ensemble, 0);
+    assert_eq!(headers[3].y_ensemble, 60);
+    assert_eq!(headers[4].x_ensemble, 40);
+}
+
+#[test]
+fn test_coordinate_parse_error_skip_trace() {
+    use giga_segy_core::enums::CoordErrorPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nan_coordinate.sgy");
+    let mut settings = write_nan_coordinate_file(&path);
+    settings.set_on_coordinate_parse_error(CoordErrorPolicy::SkipTrace);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.trace_count(), 4);
+    assert_eq!(segy.get_warnings().len(), 1);
+    let crosslines = segy
+        .traces_iter()
+        .map(|t| t.get_header().crossline_no)
+        .collect::<Vec<_>>();
+    assert_eq!(crosslines, vec![0, 1, 2, 4]);
+}
+
+/// Write a file with a trace for each of the given trace identification codes, with the
+/// headers set up for the given vertical axis.
+fn write_axis_file(path: &Path, axis: giga_segy_core::VerticalAxis, codes: &[TraceIdCode]) {
+    let mut bin_header = BinHeader::new(0, 0, 10, SampleFormatCode::Float32);
+    bin_header.set_vertical_axis(axis).unwrap();
+    let xlines = (0..codes.len() as i32).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, data| {
+            header.set_vertical_axis(axis).unwrap();
+            header.trace_identification_code = codes[header.crossline_no as usize];
+            data
+        },
+    );
+}
+
+#[test]
+fn test_vertical_axis_time() {
+    use giga_segy_core::VerticalAxis;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("time.sgy");
+    let axis = VerticalAxis::TimeMs { interval: 2.0 };
+    let codes = [
+        TraceIdCode::TimeDomainSeismic,
+        TraceIdCode::TimeDomainSeismic,
+        TraceIdCode::Dead,
+    ];
+    write_axis_file(&path, axis, &codes);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.vertical_axis(), axis);
+    assert_eq!(segy.get_bin_header().sample_interval, 2000);
+}
+
+#[test]
+fn test_vertical_axis_depth() {
+    use giga_segy_core::VerticalAxis;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("depth.sgy");
+    let axis = VerticalAxis::DepthMeters { interval: 5.0 };
+    write_axis_file(&path, axis, &[TraceIdCode::DepthDomainSeismic; 3]);
+
+    let name = path.to_str().unwrap();
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.vertical_axis(), axis);
+    assert_eq!(
+        segy.get_bin_header().measurement_system,
+        MeasurementSystem::Meters
+    );
+
+    // The overrides take precedence over the headers.
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_depth_units(MeasurementSystem::Feet);
+    let segy = crate::SegyFile::open(name, settings.clone()).unwrap();
+    assert_eq!(
+        segy.vertical_axis(),
+        VerticalAxis::DepthFeet { interval: 5.0 }
+    );
+    settings.set_override_trace_id_code(TraceIdCode::TimeDomainSeismic);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(segy.vertical_axis(), VerticalAxis::TimeMs { interval: 5.0 });
+}
+
+#[test]
+fn test_vertical_axis_depth_by_override() {
+    use giga_segy_core::VerticalAxis;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("unknown.sgy");
+    write_axis_file(&path, VerticalAxis::Unknown, &[TraceIdCode::Unknown; 2]);
+
+    let name = path.to_str().unwrap();
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.vertical_axis(), VerticalAxis::Unknown);
+
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_id_code(TraceIdCode::DepthDomainSeismic);
+    settings.set_override_sample_interval(10_000.0);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(
+        segy.vertical_axis(),
+        VerticalAxis::DepthMeters { interval: 10.0 }
+    );
+}
+
+#[test]
+fn test_vertical_axis_mixed() {
+    use giga_segy_core::VerticalAxis;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mixed.sgy");
+    let codes = [
+        TraceIdCode::TimeDomainSeismic,
+        TraceIdCode::DepthDomainSeismic,
+        TraceIdCode::TimeDomainSeismic,
+        TraceIdCode::DepthDomainSeismic,
+        TraceIdCode::Dead,
+    ];
+    write_axis_file(&path, VerticalAxis::TimeMs { interval: 4.0 }, &codes);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.vertical_axis(), VerticalAxis::Unknown);
+    assert_eq!(
+        segy.trace_id_code_counts(),
+        vec![
+            (TraceIdCode::TimeDomainSeismic, 2),
+            (TraceIdCode::DepthDomainSeismic, 2),
+            (TraceIdCode::Dead, 1),
+        ]
+    );
+}
+
+/// Write a file with a trace for each of the given mute windows (as `[start, end]` in
+/// milliseconds) and delay recording times. Each trace has 20 samples with the value
+/// `sample_idx + 1`, and the sample interval in the binary header is `interval`.
+fn write_muted_file(path: &Path, interval: u16, traces: &[([u16; 2], u16)]) {
+    let bin_header = BinHeader::new(0, interval, 20, SampleFormatCode::Float32);
+    let xlines = (0..traces.len() as i32).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, _| {
+            let ([start, end], delay) = traces[header.crossline_no as usize];
+            header.mute_time_start = start;
+            header.mute_time_end = end;
+            header.delay_recording_time = delay;
+            (1..=20).map(|s| s as f32).collect::<Vec<f32>>()
+        },
+    );
+}
+
+#[test]
+fn test_get_trace_data_as_f32_muted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("muted.sgy");
+    let traces = [
+        ([0, 0], 0),
+        ([8, 20], 0),
+        ([0, 110], 100),
+        ([40, 1000], 0),
+        ([0, 1000], 0),
+    ];
+    write_muted_file(&path, 4000, &traces);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let muted = |i| {
+        let data = segy.get_trace_data_as_f32_muted(i).unwrap();
+        assert_eq!(data.len(), 20);
+        (0..20).filter(|&k| data[k] == 0.).collect::<Vec<usize>>()
+    };
+
+    // No mute.
+    assert_eq!(muted(0), Vec::<usize>::new());
+    assert_eq!(
+        segy.get_trace_data_as_f32_muted(0).unwrap(),
+        segy.get_trace_data_as_f32(0).unwrap()
+    );
+    // Samples at 8, 12 and 16 ms.
+    assert_eq!(muted(1), vec![2, 3, 4]);
+    let data = segy.get_trace_data_as_f32_muted(1).unwrap();
+    assert_eq!(data[1], 2.);
+    assert_eq!(data[5], 6.);
+    // Samples at 100, 104 and 108 ms.
+    assert_eq!(muted(2), vec![0, 1, 2]);
+    // The mute goes past the end of the trace.
+    assert_eq!(muted(3), (10..20).collect::<Vec<_>>());
+    assert_eq!(muted(4), (0..20).collect::<Vec<_>>());
+
+    let trace = segy.get_trace(1).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as_f32_muted_from_trace(&trace).unwrap(),
+        segy.get_trace_data_as_f32_muted(1).unwrap()
+    );
+    assert!(matches!(
+        segy.get_trace_data_as_f32_muted(5),
+        Err(RsgError::TraceNotFound { i: 5 })
+    ));
+}
+
+#[test]
+fn test_get_trace_data_as_f32_muted_with_nan() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("muted.sgy");
+    write_muted_file(&path, 4000, &[([8, 20], 0)]);
+
+    let mut settings = SegySettings::default();
+    settings.set_mute_with_nan(true);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let data = segy.get_trace_data_as_f32_muted(0).unwrap();
+    let nans = (0..20).filter(|&k| data[k].is_nan()).collect::<Vec<_>>();
+    assert_eq!(nans, vec![2, 3, 4]);
+    assert_eq!(data[0], 1.);
+}
+
+#[test]
+fn test_get_trace_data_as_f32_muted_no_interval() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("muted.sgy");
+    write_muted_file(&path, 0, &[([0, 0], 0), ([8, 20], 0)]);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert!(segy.get_trace_data_as_f32_muted(0).is_ok());
+    assert!(matches!(
+        segy.get_trace_data_as_f32_muted(1),
+        Err(RsgError::InvalidHeader { .. })
+    ));
+}
+
+#[test]
+fn test_raw_header_bytes() {
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let text_header = segy.get_text_header_bytes();
+    assert_eq!(text_header.len(), 3200);
+    let bin_header = segy.get_bin_header_bytes();
+    assert_eq!(bin_header.len(), 400);
+    assert_eq!(
+        segy.metadata.get_raw_bin_header().unwrap()[..],
+        bin_header[..]
+    );
+
+    let on_disk = std::fs::read(TEST_FILE).unwrap();
+    assert_eq!(text_header, &on_disk[..3200]);
+    assert_eq!(bin_header, &on_disk[3200..3600]);
+
+    let trace = segy.get_trace(1).unwrap();
+    let trace_header = segy.get_trace_header_bytes(1).unwrap();
+    assert_eq!(trace_header.len(), 240);
+    assert_eq!(
+        trace_header,
+        &on_disk[(trace.get_start() - 240)..trace.get_start()]
+    );
+    assert!(matches!(
+        segy.get_trace_header_bytes(segy.trace_count()),
+        Err(RsgError::TraceNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_transcoding_keeps_raw_bin_header() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.sgy");
+    write_synthetic_grid(&input, &[1, 2], &[1, 2], &[], 5);
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&input)
+        .unwrap();
+    file.seek(SeekFrom::Start(3400)).unwrap();
+    file.write_all(&[0xAB]).unwrap();
+    drop(file);
+
+    let segy = crate::SegyFile::open(input.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_bin_header_bytes()[200], 0xAB);
+
+    // Transcode the file to 64 bit floats.
+    let output = dir.path().join("output.sgy");
+    let mut bin_header = segy.get_bin_header().clone();
+    bin_header.sample_format_code = SampleFormatCode::Float64;
+    let mut out = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &output,
+        SegySettings::default(),
+        segy.get_text_header().to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    out.set_raw_bin_header(*segy.metadata.get_raw_bin_header().unwrap())
+        .unwrap();
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let data = segy.get_trace_data_as_f32(i).unwrap();
+        out.add_trace(trace.get_header().clone(), None, data)
+            .unwrap();
+    }
+    drop(out);
+
+    let transcoded =
+        crate::SegyFile::open(output.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(transcoded.get_bin_header_bytes()[200], 0xAB);
+    assert_eq!(
+        transcoded.get_bin_header().sample_format_code,
+        SampleFormatCode::Float64
+    );
+    assert_eq!(transcoded.trace_count(), 4);
+    assert_eq!(
+        transcoded.get_trace_data_as_f32(3).unwrap(),
+        segy.get_trace_data_as_f32(3).unwrap()
+    );
+}
+
+/// A summary of a SEG-Y file, which can be made from both a file that is read and a file
+/// that is written.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    trace_count: usize,
+    no_samples: u16,
+    first_line: String,
+    inline_range: Option<[i32; 2]>,
+}
+
+fn summarize(h: &impl SegyHandle) -> Summary {
+    let inline_range = h.traces_iter().fold(None, |r: Option<[i32; 2]>, t| {
+        let i = t.get_header().inline_no;
+        match r {
+            Some([min, max]) => Some([min.min(i), max.max(i)]),
+            None => Some([i, i]),
+        }
+    });
+    Summary {
+        trace_count: h.trace_count(),
+        no_samples: h.get_bin_header().no_samples,
+        first_line: h
+            .get_text_header()
+            .chars()
+            .take(80)
+            .collect::<String>()
+            .trim_end()
+            .to_string(),
+        inline_range,
+    }
+}
+
+#[test]
+fn test_segy_handle() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("handle.sgy");
+
+    let bin_header = BinHeader::new(6, 4000, 10, SampleFormatCode::Float32);
+    let mut written = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file for any handle.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for i in 0..6 {
+        let mut trace_header = TraceHeader::new_3d(0, 0, 10 + i / 2, i % 2, 1);
+        trace_header.no_samples_in_trace = 10;
+        written
+            .add_trace(trace_header, None, vec![i as f32; 10])
+            .expect("Could not add trace.");
+    }
+
+    let expected = Summary {
+        trace_count: 6,
+        no_samples: 10,
+        first_line: "C 1 A file for any handle.".to_string(),
+        inline_range: Some([10, 12]),
+    };
+    assert_eq!(summarize(&written), expected);
+    drop(written);
+
+    let read = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(summarize(&read), expected);
+}
+
+#[test]
+fn test_data_digests() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("digest.sgy");
+
+    let bin_header = BinHeader::new(3, 4000, 10, SampleFormatCode::Float32);
+    let mut written = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file with digests.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for i in 0..3 {
+        let mut trace_header = TraceHeader::new_3d(0, 0, 1, i, 1);
+        trace_header.no_samples_in_trace = 10;
+        let data = (0..10).map(|s| (i * 10 + s) as f32).collect::<Vec<f32>>();
+        written
+            .add_trace(trace_header, None, data)
+            .expect("Could not add trace.");
+    }
+    let trace_digests = written.written_trace_digests().to_vec();
+    let file_digest = written.digest_written_traces();
+    drop(written);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    for (i, d) in trace_digests.iter().enumerate() {
+        assert_eq!(segy.trace_data_digest(i).unwrap(), *d);
+    }
+    assert_eq!(segy.file_data_digest().unwrap(), file_digest);
+    assert!(segy.trace_data_digest(3).is_err());
+    let trace_1 = segy.get_trace(1).unwrap().into_owned();
+    drop(segy);
+
+    // Patching a trace header does not change the digests.
+    let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    let header_start = trace_1.get_start() - giga_segy_core::TRACE_HEADER_LEN;
+    file.seek(SeekFrom::Start(header_start as u64)).unwrap();
+    file.write_all(&[0x12, 0x34, 0x56, 0x78]).unwrap();
+    drop(file);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_trace(1)
+            .unwrap()
+            .get_header()
+            .trace_sequence_on_line,
+        0x12345678
+    );
+    assert_eq!(segy.file_data_digest().unwrap(), file_digest);
+    drop(segy);
+
+    // Flipping a byte of the data does.
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[trace_1.get_start() + 5] ^= 0x01;
+    std::fs::write(&path, bytes).unwrap();
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_data_digest(0).unwrap(), trace_digests[0]);
+    assert_ne!(segy.trace_data_digest(1).unwrap(), trace_digests[1]);
+    assert_eq!(segy.trace_data_digest(2).unwrap(), trace_digests[2]);
+    assert_ne!(segy.file_data_digest().unwrap(), file_digest);
+}
+
+#[test]
+fn test_first_trace_offset() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("default_offset.sgy");
+    let shifted = dir.path().join("shifted.sgy");
+
+    let bin_header = BinHeader::new(3, 4000, 10, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file with a gap before the first trace.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.");
+    for i in 0..3 {
+        let mut trace_header = TraceHeader::new_3d(0, 0, 1, i, 1);
+        trace_header.no_samples_in_trace = 10;
+        let data = (0..10).map(|s| (i * 10 + s) as f32).collect::<Vec<f32>>();
+        file.add_trace(trace_header, None, data)
+            .expect("Could not add trace.");
+    }
+    drop(file);
+
+    // Insert a gap of 1000 bytes of rubbish between the headers and the first trace, and give
+    // the offset of the first trace in the binary header.
+    let bytes = std::fs::read(&path).unwrap();
+    let mut shifted_bytes = bytes[..3600].to_vec();
+    shifted_bytes[3520..3528].copy_from_slice(&4600u64.to_be_bytes());
+    shifted_bytes.extend_from_slice(&[0xAB; 1000]);
+    shifted_bytes.extend_from_slice(&bytes[3600..]);
+    std::fs::write(&shifted, shifted_bytes).unwrap();
+
+    let original = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let segy = crate::SegyFile::open(shifted.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_bin_header().first_trace_offset, 4600);
+    assert_eq!(segy.trace_count(), 3);
+    assert_eq!(segy.get_trace(0).unwrap().get_start(), 4600 + 240);
+    for i in 0..3 {
+        assert_eq!(
+            segy.get_trace(i).unwrap().get_header(),
+            original.get_trace(i).unwrap().get_header()
+        );
+        assert_eq!(
+            segy.get_trace_data_as_f32(i).unwrap(),
+            original.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+
+    drop(original);
+
+    // An offset which would place the first trace within the headers is ignored.
+    let mut bytes = bytes;
+    bytes[3520..3528].copy_from_slice(&100u64.to_be_bytes());
+    std::fs::write(&path, bytes).unwrap();
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_count(), 3);
+    assert_eq!(segy.get_trace(0).unwrap().get_start(), 3600 + 240);
+}
+
+#[test]
+fn test_quick_check() {
+    let report = crate::quick_check(TEST_FILE).unwrap();
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let bin_header = segy.get_bin_header();
+
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.format, Some(bin_header.sample_format_code));
+    assert_eq!(report.no_samples, bin_header.no_samples);
+    assert_eq!(report.sample_interval_us, bin_header.sample_interval);
+    assert_eq!(report.revision, bin_header.segy_revision_number);
+    assert_eq!(report.is_le, bin_header.binary_flag_direction_is_le);
+    assert_eq!(
+        report.estimated_traces_from_size,
+        Some(segy.trace_count() as u64)
+    );
+}
+
+#[test]
+fn test_quick_check_truncated() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("truncated.sgy");
+    let mut bytes = std::fs::read(TEST_FILE).unwrap();
+    let full = crate::quick_check(TEST_FILE).unwrap();
+    bytes.truncate(bytes.len() - 100);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let report = crate::quick_check(&path).unwrap();
+    let estimated = full.estimated_traces_from_size.unwrap() - 1;
+    assert_eq!(report.estimated_traces_from_size, Some(estimated));
+    assert!(report.problems[0].starts_with("The last trace is incomplete"));
+    if let Some(declared) = report.declared_traces {
+        assert_eq!(
+            report.problems[1],
+            format!(
+                "The binary header declares {} traces, but the file size implies {}.",
+                declared, estimated
+            )
+        );
+    }
+
+    // Without a complete binary header there is nothing to check.
+    bytes.truncate(3500);
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        crate::quick_check(&path),
+        Err(RsgError::FileTooShort)
+    ));
+}
+
+#[test]
+fn test_quick_check_bad_header() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("bad.sgy");
+    let mut bytes = std::fs::read(TEST_FILE).unwrap();
+    let le = crate::quick_check(TEST_FILE).unwrap().is_le;
+    let write_u16 = |bytes: &mut Vec<u8>, at: usize, v: u16| {
+        let b = if le { v.to_le_bytes() } else { v.to_be_bytes() };
+        bytes[at..at + 2].copy_from_slice(&b);
+    };
+    // The sample interval, the sample format and the revision.
+    write_u16(&mut bytes, 3216, 0);
+    write_u16(&mut bytes, 3224, 99);
+    bytes[3500] = 7;
+    std::fs::write(&path, &bytes).unwrap();
+
+    let report = crate::quick_check(&path).unwrap();
+    assert_eq!(report.format, None);
+    assert_eq!(report.estimated_traces_from_size, None);
+    assert_eq!(
+        report.problems,
+        vec![
+            "The SEG-Y revision 7.0 is not recognized.".to_string(),
+            "The sample format code 99 is not valid.".to_string(),
+            "The sample interval of 0 is not plausible.".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_open_corrupt_format_with_override() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("corrupt.sgy");
+    write_synthetic_grid(&path, &[1, 2], &[1, 2, 3], &[], 5);
+    // Corrupt the sample format code and the fixed length trace flag (big endian).
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[3224..3226].copy_from_slice(&13u16.to_be_bytes());
+    bytes[3502..3504].copy_from_slice(&7u16.to_be_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let name = path.to_str().unwrap();
+
+    let res = crate::SegyFile::open(name, SegySettings::default());
+    assert!(matches!(res, Err(RsgError::ParseEnum { code: 13, .. })));
+
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_format(SampleFormatCode::Float32);
+    let file = crate::SegyFile::open(name, settings).unwrap();
+    let bin_header = file.get_bin_header();
+    assert_eq!(bin_header.sample_format_code, SampleFormatCode::Float32);
+    assert_eq!(bin_header.raw_sample_format_code, 13);
+    assert_eq!(bin_header.fixed_length_trace_flag, FixedLengthTraces::No);
+    assert_eq!(file.trace_count(), 6);
+    assert_eq!(
+        file.get_trace_data_as_f32_from_trace(&file.get_trace(4).unwrap())
+            .unwrap(),
+        vec![2002.0, 2002.01, 2002.02, 2002.03, 2002.04]
+    );
+    assert_eq!(
+        file.get_warnings(),
+        &[
+            "The sample format code 13 is not valid, so Float32 was used as given in the settings."
+                .to_string(),
+            "The fixed length trace flag 7 is not valid, so the traces are assumed to have variable length."
+                .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_raw_sample_format_code() {
+    let file = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let bin_header = file.get_bin_header();
+    assert_eq!(
+        bin_header.raw_sample_format_code,
+        bin_header.sample_format_code as u16
+    );
+    assert!(file.get_warnings().is_empty());
+}
+
+#[test]
+fn test_compact_headers() {
+    let full = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut settings = SegySettings::default();
+    settings.set_compact_headers(true);
+    let compact = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+
+    assert!(!full.has_compact_headers());
+    assert!(compact.has_compact_headers());
+    assert_eq!(compact.trace_count(), full.trace_count());
+    // The traces are parsed from the file when they are asked for.
+    assert!(compact.traces_iter().eq(full.traces_iter()));
+    assert!(matches!(compact.get_trace(0), Some(Cow::Owned(_))));
+    assert!(matches!(full.get_trace(0), Some(Cow::Borrowed(_))));
+    assert_eq!(compact.get_trace(full.trace_count()), None);
+    for key in full.lines_iter() {
+        let trace = compact.get_trace_by_xline_inline(key.crossline, key.inline);
+        assert_eq!(lines_of(&trace.unwrap()), [key.inline, key.crossline]);
+    }
+    assert_eq!(summarize(&compact), summarize(&full));
+    assert_eq!(SegyHandle::trace_count(&compact), full.trace_count());
+    assert_eq!(SegyHandle::get_trace(&compact, 3), full.get_trace(3));
+
+    for i in 0..full.trace_count() {
+        let trace = compact.load_trace(i).unwrap();
+        assert_eq!(trace, full.get_trace(i).unwrap());
+        assert_eq!(full.load_trace(i).unwrap(), trace);
+        assert_eq!(compact.get_trace_key(i), full.get_trace_key(i));
+        assert_eq!(
+            compact.get_trace_header_bytes(i).unwrap(),
+            full.get_trace_header_bytes(i).unwrap()
+        );
+        assert_eq!(
+            compact.get_trace_data_as_f32(i).unwrap(),
+            full.get_trace_data_as_f32(i).unwrap()
+        );
+        assert_eq!(compact.trace_sample_count(i), full.trace_sample_count(i));
+    }
+    let last = full.trace_count();
+    assert!(matches!(
+        compact.load_trace(last),
+        Err(RsgError::TraceNotFound { i }) if i == last
+    ));
+    assert_eq!(compact.get_trace_key(last), None);
+
+    // The lookup is a hash map, so which of several traces on the same line is found varies.
+    let inlines = |f: &crate::SegyFile| {
+        f.get_trace_idx_for_inline_min_max()
+            .map(|idx| idx.map(|i| f.get_trace_key(i).unwrap().inline_no))
+    };
+    assert_eq!(inlines(&compact), inlines(&full));
+    let xlines = |f: &crate::SegyFile| {
+        f.get_trace_idx_for_crossline_min_max()
+            .map(|idx| idx.map(|i| f.get_trace_key(i).unwrap().crossline_no))
+    };
+    assert_eq!(xlines(&compact), xlines(&full));
+    assert_eq!(
+        compact.get_trace_idx_for_x_ensemble_min_max(),
+        full.get_trace_idx_for_x_ensemble_min_max()
+    );
+    assert_eq!(
+        compact.get_trace_idx_for_y_ensemble_min_max(),
+        full.get_trace_idx_for_y_ensemble_min_max()
+    );
+    assert_eq!(compact.trace_id_code_counts(), full.trace_id_code_counts());
+    assert_eq!(
+        compact.file_data_digest().unwrap(),
+        full.file_data_digest().unwrap()
+    );
+
+    let indices = [(0, 0), (3, 7), (last - 1, 2)];
+    assert_eq!(
+        compact.get_samples_at(&indices).unwrap(),
+        full.get_samples_at(&indices).unwrap()
+    );
+    let inline = full.get_trace(0).unwrap().get_header().inline_no;
+    assert_eq!(
+        compact.extract_inline_section(inline, true).unwrap(),
+        full.extract_inline_section(inline, true).unwrap()
+    );
+    assert_eq!(
+        compact.extract_time_slice(1, true).unwrap(),
+        full.extract_time_slice(1, true).unwrap()
+    );
+    assert!(crate::compare::diff(&full, &compact, Default::default())
+        .unwrap()
+        .is_empty());
+}
+
+/// Check that a file with deduplicated trace headers gives the same traces as the full one.
+fn assert_same_traces(full: &crate::SegyFile, dedup: &crate::SegyFile) {
+    assert!(dedup.has_dedup_headers());
+    assert_eq!(dedup.trace_count(), full.trace_count());
+    // The traces are put back together when they are asked for.
+    assert!(dedup.traces_iter().eq(full.traces_iter()));
+    assert!(matches!(dedup.get_trace(0), Some(Cow::Owned(_))));
+    assert_eq!(dedup.get_trace(full.trace_count()), None);
+    for key in full.lines_iter() {
+        let trace = dedup.get_trace_by_xline_inline(key.crossline, key.inline);
+        assert_eq!(lines_of(&trace.unwrap()), [key.inline, key.crossline]);
+    }
+    assert_eq!(summarize(dedup), summarize(full));
+    assert_eq!(SegyHandle::trace_count(dedup), full.trace_count());
+    assert_eq!(SegyHandle::get_trace(dedup, 0), full.get_trace(0));
+    for i in 0..full.trace_count() {
+        // This compares every field of the trace header.
+        assert_eq!(dedup.load_trace(i).unwrap(), full.get_trace(i).unwrap());
+        assert_eq!(dedup.get_trace_key(i), full.get_trace_key(i));
+        assert_eq!(
+            dedup.get_trace_header_bytes(i).unwrap(),
+            full.get_trace_header_bytes(i).unwrap()
+        );
+        assert_eq!(
+            dedup.get_trace_data_as_f32(i).unwrap(),
+            full.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+    let last = full.trace_count();
+    assert!(matches!(
+        dedup.load_trace(last),
+        Err(RsgError::TraceNotFound { i }) if i == last
+    ));
+    assert_eq!(dedup.get_trace_key(last), None);
+    assert_eq!(dedup.lookup, full.lookup);
+    assert!(crate::compare::diff(full, dedup, Default::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_dedup_headers() {
+    let full = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    let mut settings = SegySettings::default();
+    settings.set_dedup_headers(true);
+    let dedup = crate::SegyFile::open(TEST_FILE, settings).unwrap();
+    assert_same_traces(&full, &dedup);
+    assert_eq!(full.distinct_header_count(), None);
+    assert!(dedup.distinct_header_count().unwrap() >= 1);
+
+    let mut settings = SegySettings::default();
+    settings.set_dedup_headers(true);
+    settings.set_compact_headers(true);
+    assert!(matches!(
+        crate::SegyFile::open(TEST_FILE, settings),
+        Err(RsgError::SEGYSettingsError { .. })
+    ));
+}
+
+#[test]
+fn test_dedup_headers_memory() {
+    // Every trace header is the same apart from the line numbers and coordinates.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("uniform.sgy");
+    let lines = (1..=40).collect::<Vec<i32>>();
+    write_synthetic_grid(&path, &lines, &lines, &[], 4);
+    let path = path.to_str().unwrap();
+
+    let full = crate::SegyFile::open(path, SegySettings::default()).unwrap();
+    let mut settings = SegySettings::default();
+    settings.set_dedup_headers(true);
+    let dedup = crate::SegyFile::open(path, settings).unwrap();
+    assert_same_traces(&full, &dedup);
+    assert_eq!(dedup.distinct_header_count(), Some(1));
+
+    // See benches/dedup_headers.rs for the sizes in a larger file.
+    let (full_size, dedup_size) = (full.trace_memory_size(), dedup.trace_memory_size());
+    assert!(full_size >= full.trace_count() * std::mem::size_of::<TraceHeader>());
+    assert!(dedup_size * 2 < full_size);
+}
+
+/// Write a 2D line with a trace at each of the given ensemble positions (in centimetres, with a
+/// coordinate scalar of -100).
+fn write_2d_line(path: &Path, positions: &[[i32; 2]]) {
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    let xlines = (0..positions.len() as i32).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            let [x, y] = positions[i as usize];
+            *header = TraceHeader::new_2d(x, y, -100);
+            vec![i as f32; 3]
+        },
+    );
+}
+
+#[test]
+fn test_order_traces_by_line_distance() {
+    // A gently curved line: 50 traces on an arc of 60 degrees with a radius of 1 km.
+    let arc = (0..50)
+        .map(|k| {
+            let angle = (120. - 60. * k as f64 / 49.).to_radians();
+            let x = 500_000. + 1000. * angle.cos();
+            let y = 6_000_000. + 1000. * angle.sin();
+            [(x * 100.).round() as i32, (y * 100.).round() as i32]
+        })
+        .collect::<Vec<_>>();
+    // The traces are written in a shuffled order.
+    let shuffled = (0..50).map(|i| (i * 17 + 3) % 50).collect::<Vec<usize>>();
+    let positions = shuffled.iter().map(|k| arc[*k]).collect::<Vec<_>>();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crooked.sgy");
+    write_2d_line(&path, &positions);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    // The arc runs from west to east, and so does the line: Its first trace is further west
+    // than the last.
+    let geometry = segy.line_geometry().unwrap();
+    let chord = 2000. * 30f64.to_radians().sin();
+    assert!((geometry.length - chord).abs() < 1., "{:?}", geometry);
+    assert!(geometry.direction[0] > 0.99 && geometry.direction[1].abs() < 1e-3);
+    assert!((geometry.origin[0] - (500_000. - chord / 2.)).abs() < 1.);
+    let norm = geometry.direction[0].hypot(geometry.direction[1]);
+    assert!((norm - 1.).abs() < 1e-12);
+
+    let order = segy.order_traces_by_line_distance();
+    let along_arc = order.iter().map(|i| shuffled[*i]).collect::<Vec<_>>();
+    assert_eq!(along_arc, (0..50).collect::<Vec<_>>());
+
+    // The same in compact mode.
+    let mut settings = SegySettings::default();
+    settings.set_compact_headers(true);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.line_geometry(), Some(geometry));
+    assert_eq!(segy.order_traces_by_line_distance(), order);
+}
+
+#[test]
+fn test_line_geometry_degenerate() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let path = dir.path().join("single.sgy");
+    write_2d_line(&path, &[[100, 200]]);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.line_geometry(), None);
+    assert_eq!(segy.order_traces_by_line_distance(), vec![0]);
+
+    let path = dir.path().join("same.sgy");
+    write_2d_line(&path, &[[100, 200]; 4]);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.line_geometry(), None);
+    assert_eq!(segy.order_traces_by_line_distance(), vec![0, 1, 2, 3]);
+
+    // A straight line with some traces at the same position keeps their order.
+    let path = dir.path().join("straight.sgy");
+    write_2d_line(&path, &[[0, 300], [0, 100], [0, 200], [0, 100]]);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let geometry = segy.line_geometry().unwrap();
+    assert_eq!(geometry.length, 2.);
+    assert!(geometry.origin[0].abs() < 1e-12 && geometry.origin[1] == 3.);
+    assert_eq!(segy.order_traces_by_line_distance(), vec![0, 2, 1, 3]);
+}
+
+fn geographic_header(units: CoordinateUnits, scalar: i16, x: i32, y: i32) -> TraceHeader {
+    let mut header = TraceHeader::new_2d(0, 0, scalar);
+    header.coordinate_units = units;
+    header.source_x = x;
+    header.source_y = y;
+    header.receiver_group_x = x;
+    header.receiver_group_y = y;
+    header
+}
+
+fn assert_position(position: Option<GeoPosition>, longitude: f64, latitude: f64) {
+    let position = position.unwrap();
+    assert!(
+        (position.longitude - longitude).abs() < 1e-9,
+        "{:?}",
+        position
+    );
+    assert!(
+        (position.latitude - latitude).abs() < 1e-9,
+        "{:?}",
+        position
+    );
+}
+
+#[test]
+fn test_geographic_position_seconds_of_arc() {
+    // 10°45'22.5" = 38722.5", and -59°54'50.2" = -215690.2".
+    let header = geographic_header(CoordinateUnits::SecondsOfArc, -10, 387225, -2156902);
+    let expected = (10.75625, -(59. + 54. / 60. + 50.2 / 3600.));
+    assert_position(header.geographic_source_position(), expected.0, expected.1);
+    assert_position(header.geographic_group_position(), expected.0, expected.1);
+
+    // A positive scalar multiplies: 3600" * 10 = 10°.
+    let header = geographic_header(CoordinateUnits::SecondsOfArc, 10, 3600, -7200);
+    assert_position(header.geographic_source_position(), 10., -20.);
+}
+
+#[test]
+fn test_geographic_position_decimal_degrees() {
+    let header = geographic_header(CoordinateUnits::DegreesDecimal, -10000, -1234567, 456789);
+    assert_position(header.geographic_source_position(), -123.4567, 45.6789);
+    // A scalar of zero is taken as one.
+    let header = geographic_header(CoordinateUnits::DegreesDecimal, 0, 180, -90);
+    assert_position(header.geographic_source_position(), 180., -90.);
+}
+
+#[test]
+fn test_geographic_position_dms() {
+    // 10°45'22.50"E and 59°54'50.20"N packed as DDDMMSS.ss with a scalar of -100.
+    let header = geographic_header(
+        CoordinateUnits::DegreesMinutesSeconds,
+        -100,
+        10452250,
+        59545020,
+    );
+    assert_position(
+        header.geographic_source_position(),
+        10. + 45. / 60. + 22.5 / 3600.,
+        59. + 54. / 60. + 50.2 / 3600.,
+    );
+    // 43°12'45"W and 0°00'30.00"S, where the sign applies to the whole value.
+    let header = geographic_header(
+        CoordinateUnits::DegreesMinutesSeconds,
+        -100,
+        -43124500,
+        -3000,
+    );
+    assert_position(header.geographic_source_position(), -43.2125, -30. / 3600.);
+    // Without a scalar: 179°59'59"E, 1°01'01"N.
+    let header = geographic_header(CoordinateUnits::DegreesMinutesSeconds, 1, 1795959, 10101);
+    assert_position(
+        header.geographic_source_position(),
+        179. + 59. / 60. + 59. / 3600.,
+        1. + 1. / 60. + 1. / 3600.,
+    );
+
+    // 60 minutes or seconds are not valid.
+    let header = geographic_header(CoordinateUnits::DegreesMinutesSeconds, 1, 106000, 0);
+    assert_eq!(header.geographic_source_position(), None);
+    let header = geographic_header(CoordinateUnits::DegreesMinutesSeconds, -100, 0, 1596000);
+    assert_eq!(header.geographic_source_position(), None);
+}
+
+#[test]
+fn test_geographic_position_none() {
+    for units in [
+        CoordinateUnits::Unspecified,
+        CoordinateUnits::Length,
+        CoordinateUnits::Invalid,
+    ] {
+        let header = geographic_header(units, -100, 1000, 2000);
+        assert_eq!(header.geographic_source_position(), None);
+        assert_eq!(header.geographic_group_position(), None);
+        // The raw values are kept.
+        assert_eq!([header.source_x, header.source_y], [1000, 2000]);
+    }
+    // Out of range.
+    let header = geographic_header(CoordinateUnits::DegreesDecimal, 1, 181, 0);
+    assert_eq!(header.geographic_source_position(), None);
+    let header = geographic_header(CoordinateUnits::SecondsOfArc, 1, 0, -90 * 3600 - 1);
+    assert_eq!(header.geographic_source_position(), None);
+}
+
+#[test]
+fn test_segy_file_geographic_source_position() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("geographic.sgy");
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A line in geographic coordinates.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    let header = geographic_header(
+        CoordinateUnits::DegreesMinutesSeconds,
+        -100,
+        10452250,
+        59545020,
+    );
+    file.add_trace(header, None, vec![0f32; 3]).unwrap();
+    drop(file);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_position(
+        segy.geographic_source_position(0),
+        10.75625,
+        59. + 54. / 60. + 50.2 / 3600.,
+    );
+    assert_eq!(segy.geographic_source_position(1), None);
+
+    // With an override, the coordinates are taken to be lengths.
+    let mut settings = SegySettings::default();
+    settings.set_override_coordinate_units(MeasurementSystem::Meters);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.geographic_source_position(0), None);
+    assert_eq!(segy.load_trace(0).unwrap().get_header().source_x, 10452250);
+}
+
+#[test]
+fn test_mapping_budget() {
+    let len = std::fs::metadata(TEST_FILE).unwrap().len();
+    let mut settings = SegySettings::default();
+    settings.set_mapping_budget(Some(len - 1));
+    let err = match crate::SegyFile::open(TEST_FILE, settings.clone()) {
+        Err(e) => e,
+        Ok(_) => panic!("The file is larger than the budget."),
+    };
+    assert!(matches!(err, RsgError::FileTooLargeForPlatform { len: l } if l == len));
+    assert_eq!(err.code(), 30);
+
+    settings.set_mapping_budget(Some(len));
+    assert!(crate::SegyFile::open(TEST_FILE, settings).is_ok());
+
+    assert_eq!(check_file_fits(len, None).unwrap(), len as usize);
+    #[cfg(target_pointer_width = "32")]
+    assert!(matches!(
+        check_file_fits(6_000_000_000, None),
+        Err(RsgError::FileTooLargeForPlatform { len: 6_000_000_000 })
+    ));
+}
+
+/// Write a file of traces of different lengths (some of which are larger than a page) with one
+/// trace header extension each.
+fn write_windowed_file(path: &Path) {
+    let mut bin_header = BinHeader::new(0, 4000, 0, SampleFormatCode::Float32);
+    bin_header.max_additional_trace_headers = 1;
+    let xlines = (0..40).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, _| {
+            let i = header.crossline_no as usize;
+            let len = 100 + (i * 397) % 2500;
+            header.x_ensemble = i as i32;
+            header.y_ensemble = -(i as i32);
+            header.no_samples_in_trace = len as u16;
+            (0..len).map(|s| (s * 40 + i) as f32).collect::<Vec<_>>()
+        },
+    );
+}
+
+#[test]
+fn test_max_map_window() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("windowed.sgy");
+    write_windowed_file(&path);
+    let path = path.to_str().unwrap();
+
+    let full = crate::SegyFile::open(path, SegySettings::default()).unwrap();
+    for compact in [false, true] {
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(compact);
+        settings.set_max_map_window(4096);
+        let windowed = crate::SegyFile::open(path, settings).unwrap();
+
+        assert_eq!(windowed.trace_count(), 40);
+        assert_eq!(windowed.get_text_header(), full.get_text_header());
+        assert_eq!(
+            windowed.get_text_header_bytes(),
+            full.get_text_header_bytes()
+        );
+        assert_eq!(windowed.get_bin_header_bytes(), full.get_bin_header_bytes());
+        assert_eq!(
+            windowed.file_data_digest().unwrap(),
+            full.file_data_digest().unwrap()
+        );
+        // Go back and forth, so that the windows are both reused and mapped again.
+        for i in (0..40).chain((0..40).rev()).step_by(3) {
+            assert_eq!(windowed.load_trace(i).unwrap(), full.load_trace(i).unwrap());
+            assert_eq!(
+                windowed.get_trace_header_bytes(i).unwrap(),
+                full.get_trace_header_bytes(i).unwrap()
+            );
+            assert_eq!(
+                windowed.get_trace_extension_bytes(i).unwrap(),
+                full.get_trace_extension_bytes(i).unwrap()
+            );
+            assert_eq!(
+                windowed.get_trace_data_as_f32(i).unwrap(),
+                full.get_trace_data_as_f32(i).unwrap()
+            );
+            assert_eq!(
+                windowed.get_trace_data_as_bytes(i).unwrap(),
+                full.get_trace_data_as_bytes(i).unwrap()
+            );
+        }
+        let points = (0..40).map(|i| (39 - i, i * 2)).collect::<Vec<_>>();
+        assert_eq!(
+            windowed.get_samples_at(&points).unwrap(),
+            full.get_samples_at(&points).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_max_map_window_header_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("windowed.sgy");
+    write_windowed_file(&path);
+    let path = path.to_str().unwrap();
+
+    let full = crate::SegyFile::open(path, SegySettings::default()).unwrap();
+    let mut settings = SegySettings::default();
+    settings.set_max_map_window(4096);
+    let windowed = crate::SegyFile::open(path, settings).unwrap();
+    assert!(std::fs::metadata(path).unwrap().len() > 40 * 4096);
+
+    // The bytes of every trace are held at once, but only a few windows stay mapped.
+    let headers = (0..40)
+        .map(|i| windowed.get_trace_header_bytes(i).unwrap())
+        .collect::<Vec<_>>();
+    let extensions = (0..40)
+        .map(|i| windowed.get_trace_extension_bytes(i).unwrap())
+        .collect::<Vec<_>>();
+    assert!(windowed.data.mapped_window_count() <= crate::memory_map::CACHED_WINDOWS);
+    for i in 0..40 {
+        assert_eq!(headers[i], full.get_trace_header_bytes(i).unwrap());
+        assert_eq!(extensions[i], full.get_trace_extension_bytes(i).unwrap());
+    }
+    // Only the bytes in the head of the file are borrowed.
+    assert!(matches!(headers[39], Cow::Owned(_)));
+    assert!(matches!(
+        full.get_trace_header_bytes(0).unwrap(),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_ignores_mapping_budget() {
+    let mut settings = SegySettings::default();
+    settings.set_mapping_budget(Some(1000));
+    let segy = crate::AsyncSegyFile::open(TEST_FILE, settings)
+        .await
+        .unwrap();
+    assert!(segy.trace_count() > 0);
+}
+
+#[test]
+fn test_trace_positions() {
+    // The positions which the writer records are those at which the traces are read.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("positions.sgy");
+    let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Trace positions.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for i in 0..3 {
+        let header = TraceHeader::new_3d(0, 0, 1, i + 1, 1);
+        file.add_trace(header, None, vec![i as f32; 5 + i as usize])
+            .unwrap();
+    }
+    let written = file.traces.clone();
+    let coordinates = (0..3)
+        .map(|i| file.lookup[&i].start_byte)
+        .collect::<Vec<_>>();
+    drop(file);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(coordinates, [3600, 3600 + 240 + 20, 3600 + 2 * 240 + 44]);
+    for (w, r) in written.iter().zip(segy.traces_iter()) {
+        assert_eq!(w.get_start(), r.get_start());
+        assert_eq!(w.len(), r.len());
+        assert_eq!(r.get_start() as u64, r.get_start_u64());
+        assert_eq!(r.len() as u64, r.len_u64());
+        assert_eq!(r.end() as u64, r.end_u64());
+    }
+}
+
+/// Write a file with 16-bit integer samples, where the trace on crossline `i` has a trace
+/// weighting factor of `i` and the samples `0, 8, 16, ..`.
+fn write_weighted_file(path: &Path) {
+    let bin_header = BinHeader::new(0, 4000, 6, SampleFormatCode::Int16);
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &[0, 1, 2, 3],
+        &[],
+        |header, _| {
+            header.trace_weighting_factor = header.crossline_no as u16;
+            (0..6).map(|s| s * 8).collect::<Vec<i16>>()
+        },
+    );
+}
+
+#[test]
+fn test_trace_weighting() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("weighted.sgy");
+    write_weighted_file(&path);
+    let raw = vec![0., 8., 16., 24., 32., 40.];
+    let weighted = vec![0., 1., 2., 3., 4., 5.];
+
+    // The weighting factor is not applied by default.
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_trace_data_as_f32(3).unwrap(), raw);
+
+    let mut settings = SegySettings::default();
+    settings.set_apply_trace_weighting(true);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings.clone()).unwrap();
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), raw);
+    assert_eq!(segy.get_trace_data_as_f32(3).unwrap(), weighted);
+    assert_eq!(segy.get_trace_data_as_f32(1).unwrap()[1], 4.);
+    assert_eq!(segy.get_trace_data_as_f32_muted(3).unwrap(), weighted);
+    let trace = segy.load_trace(3).unwrap();
+    assert_eq!(
+        segy.get_trace_data_point_as_f32_from_trace(&trace, 2)
+            .unwrap(),
+        2.
+    );
+    assert_eq!(
+        segy.get_samples_at(&[(3, 5), (0, 5), (2, 1)]).unwrap(),
+        vec![5., 40., 2.]
+    );
+    let section = segy.extract_inline_section(1, false).unwrap();
+    assert_eq!(section.traces[3], weighted);
+    assert_eq!(section.traces[0], raw);
+
+    // The bytes are those of the file.
+    let bytes = segy.get_trace_data_as_bytes(3).unwrap();
+    assert_eq!(bytes[2..4], 8i16.to_be_bytes());
+
+    // The weighting works together with `step_by`.
+    settings.set_step_by(2);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.get_trace_data_as_f32(3).unwrap(), vec![0., 2., 4.]);
+    assert_eq!(segy.get_samples_at(&[(3, 1)]).unwrap(), vec![2.]);
+}
+
+#[test]
+fn test_user_gain() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("weighted.sgy");
+    write_weighted_file(&path);
+
+    // The gain is applied on top of the weighting.
+    let mut settings = SegySettings::default();
+    settings.set_apply_trace_weighting(true);
+    settings.set_user_gain(-0.5);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as_f32(3).unwrap(),
+        vec![0., -0.5, -1., -1.5, -2., -2.5]
+    );
+    assert_eq!(segy.get_samples_at(&[(2, 4)]).unwrap(), vec![-4.]);
+
+    // The gain also applies to floating point samples, for which the weighting factor is
+    // ignored.
+    let path = dir.path().join("float.sgy");
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A weighted float file.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    let mut trace_header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+    trace_header.trace_weighting_factor = 4;
+    file.add_trace(trace_header, None, vec![1., 2., 3.])
+        .unwrap();
+    drop(file);
+
+    let mut settings = SegySettings::default();
+    settings.set_apply_trace_weighting(true);
+    settings.set_user_gain(2.);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), vec![2., 4., 6.]);
+    let trace = segy.load_trace(0).unwrap();
+    assert_eq!(
+        segy.get_trace_data_point_as_f32_from_trace(&trace, 1)
+            .unwrap(),
+        4.
+    );
+}
+
+#[test]
+fn test_export_flat_f32() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.sgy");
+    let lines = (1..=10).collect::<Vec<i32>>();
+    write_synthetic_grid(&path, &lines, &lines, &[[3, 7]], 30);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let value_at =
+        |out: &[u8], n: usize| f32::from_le_bytes(out[n * 4..n * 4 + 4].try_into().unwrap());
+
+    let mut out = Vec::new();
+    let shape = segy
+        .export_flat_f32(&mut out, crate::CubeOrder::TraceMajor)
+        .unwrap();
+    assert_eq!(
+        shape,
+        crate::CubeShape::Traces {
+            traces: 99,
+            samples: 30
+        }
+    );
+    assert_eq!(out.len(), 99 * 30 * 4);
+    // The hole shifts the traces after it.
+    assert_eq!(value_at(&out, 0), 1001.);
+    assert_eq!(value_at(&out, 26 * 30 + 5), 3008.05);
+    assert_eq!(value_at(&out, 98 * 30 + 29), 10010.29);
+
+    let mut out = Vec::new();
+    let shape = segy
+        .export_flat_f32(&mut out, crate::CubeOrder::Grid)
+        .unwrap();
+    assert_eq!(shape.dims(), vec![10, 10, 30]);
+    assert_eq!(out.len(), shape.value_count() * 4);
+    let cell = |il: usize, xl: usize, s: usize| ((il - 1) * 10 + xl - 1) * 30 + s;
+    assert_eq!(value_at(&out, cell(2, 5, 3)), 2005.03);
+    assert!(value_at(&out, cell(3, 7, 0)).is_nan());
+    assert!(value_at(&out, cell(3, 7, 29)).is_nan());
+    assert_eq!(value_at(&out, cell(3, 8, 0)), 3008.);
+    assert_eq!(value_at(&out, cell(10, 10, 29)), 10010.29);
+
+    // The export follows the `step_by` setting.
+    let mut settings = SegySettings::default();
+    settings.set_step_by(4);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let mut out = Vec::new();
+    let shape = segy
+        .export_flat_f32(&mut out, crate::CubeOrder::Grid)
+        .unwrap();
+    assert_eq!(shape.dims(), vec![10, 10, 8]);
+    assert_eq!(value_at(&out, 8 + 1), 1002.04);
+}
+
+#[cfg(feature = "npy")]
+#[test]
+fn test_export_npy() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.sgy");
+    let lines = (1..=10).collect::<Vec<i32>>();
+    write_synthetic_grid(&path, &lines, &lines, &[[3, 7]], 30);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let mut out = Vec::new();
+    segy.export_npy(&mut out, crate::CubeOrder::Grid).unwrap();
+    assert_eq!(&out[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([out[8], out[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&out[10..10 + header_len]).unwrap();
+    assert_eq!(
+        header.trim_end(),
+        "{'descr': '<f4', 'fortran_order': False, 'shape': (10, 10, 30,), }"
+    );
+    assert!(header.ends_with('\n'));
+
+    let data = &out[10 + header_len..];
+    assert_eq!(data.len(), 10 * 10 * 30 * 4);
+    let value_at = |n: usize| f32::from_le_bytes(data[n * 4..n * 4 + 4].try_into().unwrap());
+    assert_eq!(value_at((4 * 10 + 1) * 30 + 2), 5002.02);
+    assert!(value_at((2 * 10 + 6) * 30).is_nan());
+
+    let mut out = Vec::new();
+    segy.export_npy(&mut out, crate::CubeOrder::TraceMajor)
+        .unwrap();
+    let header_len = u16::from_le_bytes([out[8], out[9]]) as usize;
+    let header = std::str::from_utf8(&out[10..10 + header_len]).unwrap();
+    assert!(header.contains("'shape': (99, 30,)"));
+    assert_eq!(out.len(), 10 + header_len + 99 * 30 * 4);
+}
+
+#[test]
+fn test_read_all_traces_f32() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.sgy");
+    let lines = (1..=10).collect::<Vec<i32>>();
+    write_synthetic_grid(&path, &lines, &lines, &[[3, 7]], 30);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let matrix = segy.read_all_traces_f32(99 * 30 * 4, false).unwrap();
+    assert_eq!(matrix.shape(), [99, 30]);
+    assert_eq!(matrix.strides(), [30, 1]);
+    assert_eq!(matrix.as_slice().len(), 99 * 30);
+    for i in 0..99 {
+        assert_eq!(
+            matrix.row(i).unwrap(),
+            &segy.get_trace_data_as_f32(i).unwrap()[..]
+        );
+    }
+    assert_eq!(matrix.row(99), None);
+    assert_eq!(matrix.as_slice()[26 * 30 + 5], 3008.05);
+
+    // Nothing is read if the matrix does not fit in the budget.
+    let err = segy
+        .read_all_traces_f32(99 * 30 * 4 - 1, false)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::AllocationOverBudget {
+            bytes: 11880,
+            budget: 11879
+        }
+    ));
+
+    #[cfg(feature = "ndarray")]
+    {
+        let array = matrix.clone().into_ndarray();
+        assert_eq!(array.shape(), &[99, 30]);
+        assert_eq!(array[[26, 5]], 3008.05);
+    }
+    assert_eq!(matrix.into_vec().len(), 99 * 30);
+}
+
+#[test]
+fn test_read_all_traces_f32_variable_length() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("variable.sgy");
+    let bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file with variable length traces.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for (i, no_samples) in [10, 8, 12].iter().enumerate() {
+        let trace_header = TraceHeader::new_3d(0, 0, 1, i as i32, 1);
+        let data = (0..*no_samples).map(|s| (i * 100 + s) as f32).collect();
+        file.add_trace(trace_header, None, data).unwrap();
+    }
+    drop(file);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let err = segy.read_all_traces_f32(usize::MAX, false).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::RaggedTraces {
+            trace: 1,
+            samples: 8,
+            expected: 10
+        }
+    ));
+    assert_eq!(err.trace_index(), Some(1));
+
+    let matrix = segy.read_all_traces_f32(usize::MAX, true).unwrap();
+    assert_eq!(matrix.shape(), [3, 12]);
+    let row = matrix.row(1).unwrap();
+    assert_eq!(&row[..8], &segy.get_trace_data_as_f32(1).unwrap()[..]);
+    assert!(row[8..].iter().all(|v| v.is_nan()));
+    assert!(matrix.row(0).unwrap()[10..].iter().all(|v| v.is_nan()));
+    assert_eq!(
+        matrix.row(2).unwrap(),
+        &segy.get_trace_data_as_f32(2).unwrap()[..]
+    );
+}
+
+/// Write a file with four traces in the way that older versions of `giga_segy_out` did when
+/// they were given an extended header for each trace without any trace header extensions in the
+/// binary header: the text is put between the header and the data of each trace.
+fn write_prefixed_file(path: &Path, prefix: &str) {
+    let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &[0, 1, 2, 3],
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            (0..5).map(|s| (i * 10 + s) as f32).collect::<Vec<f32>>()
+        },
+    );
+
+    let bytes = std::fs::read(path).unwrap();
+    let (head, traces) = bytes.split_at(3600);
+    let mut prefixed = head.to_vec();
+    for trace in traces.chunks(240 + 5 * 4) {
+        prefixed.extend_from_slice(&trace[..240]);
+        prefixed.extend_from_slice(prefix.as_bytes());
+        prefixed.extend_from_slice(&trace[240..]);
+    }
+    std::fs::write(path, prefixed).unwrap();
+}
+
+#[test]
+fn test_text_in_trace_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.sgy");
+    let prefix = "An extended header of the trace.";
+    write_prefixed_file(&path, prefix);
+
+    let err = match crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()) {
+        Err(e) => e,
+        Ok(_) => panic!("The traces should not be found."),
+    };
+    assert!(matches!(
+        err,
+        RsgError::TextInTraceData { trace: 0, text_len } if text_len == prefix.len()
+    ));
+    assert_eq!(err.code(), 32);
+}
+
+#[test]
+fn test_trace_prefix_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.sgy");
+    let prefix = "An extended header of the trace.";
+    write_prefixed_file(&path, prefix);
+
+    for storage in 0..3 {
+        let mut settings = SegySettings::default();
+        settings.set_trace_prefix_bytes(prefix.len());
+        settings.set_compact_headers(storage == 1);
+        settings.set_dedup_headers(storage == 2);
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+        assert_eq!(segy.trace_count(), 4);
+        for i in 0..4 {
+            let trace = segy.load_trace(i).unwrap();
+            assert_eq!(trace.prefix_len(), prefix.len());
+            assert_eq!(trace.get_header().crossline_no, i as i32);
+            let expected = (0..5).map(|s| (i * 10 + s) as f32).collect::<Vec<f32>>();
+            assert_eq!(segy.get_trace_data_as_f32(i).unwrap(), expected);
+            assert!(segy.get_trace_extension_bytes(i).unwrap().is_empty());
+            let header_bytes = segy.get_trace_header_bytes(i).unwrap();
+            assert_eq!(header_bytes[192..196], (i as i32).to_be_bytes());
+        }
+    }
+}
+
+#[test]
+fn test_text_like_samples() {
+    // Samples which look like text are not a problem if the traces can be read to the end.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("letters.sgy");
+    let bin_header = BinHeader::new(0, 4000, 26, SampleFormatCode::Int8);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file with samples that look like text.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for i in 0..3 {
+        let data = (b'A'..=b'Z').map(|c| c as i8).collect::<Vec<i8>>();
+        file.add_trace_lossless(TraceHeader::new_3d(0, 0, 1, i, 1), None, data)
+            .unwrap();
+    }
+    drop(file);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_count(), 3);
+    assert_eq!(segy.get_trace_data_as_f32(2).unwrap()[0], 65.);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_text_in_trace_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("prefixed.sgy");
+    let prefix = "An extended header of the trace.";
+    write_prefixed_file(&path, prefix);
+
+    let res = crate::AsyncSegyFile::open(path.to_str().unwrap(), SegySettings::default()).await;
+    assert!(matches!(
+        res.map(|_| ()),
+        Err(RsgError::TextInTraceData { trace: 0, .. })
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.set_trace_prefix_bytes(prefix.len());
+    let segy = crate::AsyncSegyFile::open(path.to_str().unwrap(), settings)
+        .await
+        .unwrap();
+    assert_eq!(segy.trace_count(), 4);
+    assert_eq!(
+        segy.get_trace_data_as_f32(3).await.unwrap(),
+        vec![30., 31., 32., 33., 34.]
+    );
+}
+
+#[test]
+fn test_fast_decode_matches_converter() {
+    use giga_segy_core::bitconverter::converter_chooser;
+
+    // A xorshift generator, so that the bytes are the same in every run.
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let mut bytes = (0..4096)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect::<Vec<u8>>();
+    // NaN with a payload, signalling NaN, infinity, negative zero and a subnormal.
+    for (n, v) in [
+        0x7FC0_1234u32,
+        0x7F80_0001,
+        0x7F80_0000,
+        0x8000_0000,
+        0x0000_0001,
+    ]
+    .iter()
+    .enumerate()
+    {
+        bytes[n * 4..n * 4 + 4].copy_from_slice(&v.to_be_bytes());
+        bytes[20 + n * 4..24 + n * 4].copy_from_slice(&v.to_le_bytes());
+    }
+
+    for format in [
+        SampleFormatCode::Float32,
+        SampleFormatCode::Int32,
+        SampleFormatCode::Int16,
+        SampleFormatCode::Int8,
+        SampleFormatCode::IbmFloat32,
+        SampleFormatCode::UInt16,
+    ]
+    .iter()
+    {
+        for le in [true, false].iter() {
+            let converter = converter_chooser(*format, *le).unwrap();
+            let n = format.datum_byte_length();
+            for step in 1..4 {
+                for len in [0, n, 3 * n, bytes.len()].iter() {
+                    let raw = &bytes[..*len];
+                    let fast =
+                        crate::read_data::decode_samples(raw, n, step, (*format, *le), converter)
+                            .unwrap();
+                    let generic =
+                        crate::read_data::decode_with_converter(raw, n, step, converter).unwrap();
+                    assert_eq!(
+                        fast.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+                        generic.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+                        "{:?} le={} step={} len={}",
+                        format,
+                        le,
+                        step,
+                        len
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Write a small file with the given bytes as its text header.
+fn write_text_header_file(path: &Path, text_header: &[u8]) {
+    let _ = std::fs::remove_file(path);
+    write_synthetic_grid(path, &[1], &[1, 2], &[], 3);
+    let mut bytes = std::fs::read(path).unwrap();
+    bytes[..3200].copy_from_slice(text_header);
+    std::fs::write(path, bytes).unwrap();
+}
+
+/// Get 40 cards, each with its number and a short text, but without padding.
+fn short_cards() -> Vec<String> {
+    (1..=40)
+        .map(|n| format!("C{:>2} Card number {}", n, n))
+        .collect()
+}
+
+/// Convert upper case letters, digits, spaces and full stops from ASCII to EBCDIC.
+fn to_ebcdic(c: u8) -> u8 {
+    match c {
+        b'A'..=b'I' => c - b'A' + 0xC1,
+        b'J'..=b'R' => c - b'J' + 0xD1,
+        b'S'..=b'Z' => c - b'S' + 0xE2,
+        b'0'..=b'9' => c - b'0' + 0xF0,
+        b'.' => 0x4B,
+        _ => 0x40,
+    }
+}
+
+#[test]
+fn test_text_header_encoding() {
+    use giga_segy_core::enums::TextEncoding::*;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("text_header.sgy");
+    let open = |encoding| {
+        let mut settings = SegySettings::default();
+        settings.set_text_header_encoding(encoding);
+        crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap()
+    };
+
+    let mut ascii = format!("{:<3200}", "C 1 A header in ASCII.").into_bytes();
+    write_text_header_file(&path, &ascii);
+    for (setting, detected) in [(Auto, Ascii), (Ascii, Ascii), (Raw, Raw)] {
+        let segy = open(setting);
+        assert_eq!(segy.get_text_header_encoding(), detected);
+        assert_eq!(segy.get_text_header().as_bytes(), &ascii[..]);
+    }
+    let segy = open(Ebcdic);
+    assert_eq!(segy.get_text_header_encoding(), Ebcdic);
+    assert_ne!(segy.get_text_header().as_bytes(), &ascii[..]);
+
+    let text = format!("{:<3200}", "C 1 AN EBCDIC HEADER.");
+    let ebcdic = text.bytes().map(to_ebcdic).collect::<Vec<_>>();
+    write_text_header_file(&path, &ebcdic);
+    for setting in [Auto, Ebcdic] {
+        let segy = open(setting);
+        assert_eq!(segy.get_text_header_encoding(), Ebcdic);
+        assert_eq!(segy.get_text_header(), text);
+    }
+    assert!(!open(Ascii).get_text_header().starts_with("C 1"));
+    let segy = open(Raw);
+    assert_eq!(segy.get_text_header_bytes(), &ebcdic[..]);
+    let raw = segy.get_text_header().chars().map(|c| c as u32);
+    assert!(raw.eq(ebcdic.iter().map(|b| *b as u32)));
+
+    // A byte which is neither printable ASCII nor sensible EBCDIC.
+    ascii[100] = 0xFF;
+    write_text_header_file(&path, &ascii);
+    let segy = open(Auto);
+    assert_eq!(segy.get_text_header_encoding(), Ebcdic);
+    assert_eq!(segy.get_text_header_bytes(), &ascii[..]);
+
+    let segy = open(Ascii);
+    assert_eq!(segy.get_text_header_encoding(), Ascii);
+    let text = segy.get_text_header();
+    assert!(text.starts_with("C 1 A header in ASCII."));
+    assert_eq!(text.chars().nth(100), Some(char::REPLACEMENT_CHARACTER));
+    assert_eq!(text.chars().count(), 3200);
+
+    let segy = open(Raw);
+    let text = segy.get_text_header();
+    assert_eq!(text.chars().nth(100), Some('\u{FF}'));
+    assert!(text
+        .chars()
+        .map(|c| c as u32)
+        .eq(ascii.iter().map(|b| *b as u32)));
+    assert_eq!(segy.text_header_lines().count(), 40);
+}
+
+/// Write a file whose traces have the given trace numbers, with the inline number 1, the
+/// position of the trace in the file as the crossline number and as the value of its samples.
+fn write_trace_nos(path: &Path, trace_nos: &[i32]) {
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    let xlines = (0..trace_nos.len() as i32).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            header.trace_no = trace_nos[i as usize];
+            vec![i as f32; 3]
+        },
+    );
+}
+
+#[test]
+fn test_sort_traces_by() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shuffled.sgy");
+    write_trace_nos(&path, &[5, 2, 4, 2, 1, 3]);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let trace_nos = (0..6)
+        .map(|i| segy.load_trace(i).unwrap().get_header().trace_no)
+        .collect::<Vec<_>>();
+    assert_eq!(trace_nos, vec![5, 2, 4, 2, 1, 3]);
+    assert!(segy.get_warnings().is_empty());
+
+    for storage in 0..3 {
+        let mut settings = SegySettings::default();
+        settings.set_sort_traces_by(OrderTraceBy::TraceNo);
+        settings.set_compact_headers(storage == 1);
+        settings.set_dedup_headers(storage == 2);
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+        assert_eq!(segy.trace_count(), 6);
+
+        let traces = (0..6).map(|i| segy.load_trace(i).unwrap().into_owned());
+        let (trace_nos, positions): (Vec<_>, Vec<_>) = traces
+            .map(|t| (t.get_header().trace_no, t.get_header().crossline_no))
+            .unzip();
+        assert_eq!(trace_nos, vec![1, 2, 2, 3, 4, 5]);
+        // The two traces with the same number are kept in the order of the file.
+        assert_eq!(positions, vec![4, 1, 3, 5, 2, 0]);
+        for (i, position) in positions.iter().enumerate() {
+            assert_eq!(
+                segy.get_trace_data_as_f32(i).unwrap(),
+                vec![*position as f32; 3]
+            );
+            let found = segy.get_traces_in_rect([1, 1], [*position, *position]);
+            assert_eq!(found, vec![i]);
+        }
+        assert_eq!(segy.get_warnings().len(), 1);
+        assert!(segy.get_warnings()[0].contains("TraceNo"));
+    }
+}
+
+#[test]
+fn test_order_trace_by_duplicates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shuffled.sgy");
+    write_trace_nos(&path, &[5, 2, 4, 2, 1, 3]);
+
+    for compact in [false, true] {
+        let mut settings = SegySettings::default();
+        settings.set_order_trace_by(OrderTraceBy::TraceNo);
+        settings.set_override_dim_x(3).unwrap();
+        settings.set_compact_headers(compact);
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+
+        // Both traces with the trace number 2 are kept, in the same cell.
+        assert_eq!(segy.trace_count(), 6);
+        let cells = (0..6)
+            .map(|i| {
+                let trace = segy.load_trace(i).unwrap();
+                [
+                    trace.get_header().inline_no,
+                    trace.get_header().crossline_no,
+                ]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cells, vec![[1, 2], [0, 2], [1, 1], [0, 2], [0, 1], [1, 0]]);
+        assert_eq!(segy.get_warnings().len(), 1);
+        assert!(segy.get_warnings()[0].contains("1 traces have the same TraceNo"));
+        // The later of the two is found by its inline and crossline numbers.
+        let found = segy.get_traces_in_rect([0, 0], [2, 2]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(segy.get_trace_data_as_f32(found[0]).unwrap()[0], 3.);
+    }
+}
+
+#[test]
+fn test_ensemble_xy_from_source_xy() {
+    // Shot gathers, where only the source and receiver coordinates are set.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots.sgy");
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Shot gathers.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for i in 0..4 {
+        let mut trace_header = TraceHeader::new_2d(0, 0, 1);
+        trace_header.source_x = 1000 + i;
+        trace_header.source_y = 2000 + i;
+        trace_header.receiver_group_x = 3000 + i;
+        trace_header.receiver_group_y = 4000 + i;
+        file.add_trace(trace_header, None, vec![0.; 3]).unwrap();
+    }
+    drop(file);
+    let name = path.to_str().unwrap();
+
+    let mut settings = SegySettings::default();
+    settings.set_ensemble_xy_to_source_xy();
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(segy.get_warnings().len(), 2);
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let header = trace.get_header();
+        assert_eq!(
+            [header.x_ensemble, header.y_ensemble],
+            [1000, 2000].map(|v| v + i as i32)
+        );
+        assert_eq!(
+            [header.source_x, header.source_y],
+            [1000, 2000].map(|v| v + i as i32)
+        );
+    }
+
+    let mut settings = SegySettings::default();
+    settings.set_ensemble_xy_to_receiver_xy();
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let header = trace.get_header();
+        assert_eq!(
+            [header.x_ensemble, header.y_ensemble],
+            [3000, 4000].map(|v| v + i as i32)
+        );
+    }
+}
+
+/// Write a file with valid headers and no traces.
+fn write_empty_file(path: &Path) {
+    write_synthetic_grid(path, &[], &[], &[], 10);
+}
+
+#[test]
+fn test_zero_traces() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("empty.sgy");
+    write_empty_file(&path);
+    let name = path.to_str().unwrap();
+
+    for storage in 0..3 {
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(storage == 1);
+        settings.set_dedup_headers(storage == 2);
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+        assert_eq!(segy.trace_count(), 0);
+        assert!(segy.is_empty());
+        assert!(segy.get_warnings().is_empty());
+        assert!(segy.get_text_header().starts_with("C 1 A synthetic grid."));
+
+        assert!(segy.get_trace_idx_for_inline_min_max().is_none());
+        assert!(segy.get_trace_idx_for_crossline_min_max().is_none());
+        assert!(segy.get_trace_idx_for_x_ensemble_min_max().is_none());
+        assert!(segy.get_trace_idx_for_y_ensemble_min_max().is_none());
+        assert!(segy.traces_iter().next().is_none());
+        assert!(segy.get_trace(0).is_none());
+        assert!(segy.get_trace_key(0).is_none());
+        assert!(segy.trace_sample_count(0).is_none());
+        assert!(segy.get_trace_by_xline_inline(0, 0).is_none());
+        assert!(segy.get_nearest_trace(0, 0).is_none());
+        assert!(segy
+            .get_traces_in_rect([i32::MIN, i32::MAX], [i32::MIN, i32::MAX])
+            .is_empty());
+        assert!(segy.line_geometry().is_none());
+        assert!(segy.order_traces_by_line_distance().is_empty());
+        assert!(segy.trace_id_code_counts().is_empty());
+        assert!(segy.load_trace(0).is_err());
+        assert!(segy.get_trace_data_as_f32(0).is_err());
+        assert!(segy.get_samples_at(&[]).unwrap().is_empty());
+        segy.file_data_digest().unwrap();
+
+        let parts = segy.into_parts(true);
+        assert!(parts.text_header.starts_with("C 1 A synthetic grid."));
+        assert!(parts.extended_headers.is_empty());
+        assert_eq!(parts.bin_header.no_traces, 0);
+        assert!(parts.traces.is_empty());
+        assert_eq!(parts.lookup, Some(Default::default()));
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_zero_traces() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("empty.sgy");
+    write_empty_file(&path);
+
+    let segy = crate::AsyncSegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .await
+        .unwrap();
+    assert_eq!(segy.trace_count(), 0);
+    assert!(segy.is_empty());
+    assert!(segy.get_trace(0).is_none());
+    assert!(segy.get_trace_data_as_f32(0).await.is_err());
+}
+
+/// Write a file with four traces on a local grid, with the given coordinate scalar. The
+/// ensemble, source and receiver group coordinates of trace `i` are `(i, 2i)`, `(i + 1, 0)`
+/// and `(0, i + 1)` before scaling.
+fn write_local_grid(path: &Path, scalar: i16) {
+    let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &[0, 1, 2, 3],
+        &[],
+        |header, data| {
+            let i = header.crossline_no;
+            header.x_ensemble = i;
+            header.y_ensemble = 2 * i;
+            header.coordinate_scalar = scalar;
+            header.source_x = i + 1;
+            header.receiver_group_y = i + 1;
+            data
+        },
+    );
+}
+
+/// Check the scaled ensemble, source and receiver group coordinates of a trace header.
+fn assert_scaled_coordinates(h: &TraceHeader, expected: [f64; 6]) {
+    let scaled = [
+        h.x_ensemble,
+        h.y_ensemble,
+        h.source_x,
+        h.source_y,
+        h.receiver_group_x,
+        h.receiver_group_y,
+    ]
+    .map(|c| h.apply_coordinate_scalar(c));
+    for (value, expected) in scaled.iter().zip(expected) {
+        assert!(
+            (value - expected).abs() < 1e-6,
+            "{:?} {:?}",
+            scaled,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_coordinate_transform_translation() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("local.sgy");
+    write_local_grid(&path, -10);
+    let name = path.to_str().unwrap();
+
+    for compact in [false, true] {
+        let mut settings = SegySettings::default();
+        settings.set_compact_headers(compact);
+        settings.set_coordinate_transform(Some(AffineTransform::translation(500_000., 6e6)));
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+        for i in 0..4 {
+            let header = segy.load_trace(i).unwrap().get_header().clone();
+            let c = i as f64 / 10.;
+            assert_scaled_coordinates(
+                &header,
+                [
+                    500_000. + c,
+                    6e6 + 2. * c,
+                    500_000.1 + c,
+                    6e6,
+                    500_000.,
+                    6_000_000.1 + c,
+                ],
+            );
+            assert_eq!(segy.get_trace_key(i).unwrap().x_ensemble, header.x_ensemble);
+
+            let raw = segy.load_untransformed_trace_header(i).unwrap();
+            assert_scaled_coordinates(&raw, [c, 2. * c, c + 0.1, 0., 0., c + 0.1]);
+            assert_eq!(raw.inline_no, header.inline_no);
+            assert_eq!(raw.crossline_no, header.crossline_no);
+        }
+        assert!(segy.get_trace_by_xline_inline(3, 1).is_some());
+    }
+}
+
+#[test]
+fn test_coordinate_transform_rotation_and_scale() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("local.sgy");
+    write_local_grid(&path, -100);
+    let name = path.to_str().unwrap();
+
+    // Rotate by 90 degrees anticlockwise, double the size and shift.
+    let transform = AffineTransform::new([[0., -2., 100.], [2., 0., -50.]]);
+    let mut settings = SegySettings::default();
+    settings.set_coordinate_transform(Some(transform));
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let c = i as f64 / 100.;
+        let h = trace.get_header();
+        assert_eq!(h.coordinate_scalar, -100);
+        assert_eq!(
+            [h.x_ensemble, h.y_ensemble],
+            [10_000 - 4 * i as i32, -5000 + 2 * i as i32]
+        );
+        assert_scaled_coordinates(
+            h,
+            [
+                100. - 4. * c,
+                -50. + 2. * c,
+                100.,
+                -50. + 2. * (c + 0.01),
+                100. - 2. * (c + 0.01),
+                -50.,
+            ],
+        );
+    }
+
+    // The override of the coordinate scalar is applied before the transform.
+    let mut settings = SegySettings::default();
+    settings.set_override_coordinate_scaling(-10.).unwrap();
+    settings.set_coordinate_transform(Some(transform));
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    let trace = segy.get_trace(2).unwrap();
+    let h = trace.get_header();
+    assert_eq!([h.x_ensemble, h.y_ensemble], [1000 - 8, -500 + 4]);
+}
+
+#[test]
+fn test_coordinate_transform_overflow() {
+    use giga_segy_core::enums::CoordErrorPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("local.sgy");
+    write_local_grid(&path, -1000);
+    let name = path.to_str().unwrap();
+
+    // 6e6 with a scalar of -1000 does not fit in an i32.
+    let transform = AffineTransform::translation(0., 6e6);
+    let mut settings = SegySettings::default();
+    settings.set_coordinate_transform(Some(transform));
+    match crate::SegyFile::open(name, settings.clone()) {
+        Err(RsgError::CoordinateParse { trace, offset, .. }) => {
+            assert_eq!([trace, offset], [0, 184])
+        }
+        _ => panic!("The coordinate should not fit."),
+    }
+
+    settings.set_on_coordinate_parse_error(CoordErrorPolicy::ZeroAndWarn);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    let trace = segy.get_trace(1).unwrap();
+    let h = trace.get_header();
+    assert_eq!([h.x_ensemble, h.y_ensemble], [1, 0]);
+    assert_eq!([h.source_x, h.source_y], [2, 0]);
+    assert_eq!(segy.get_warnings().len(), 12);
+}
+
+/// Write three shots, each with a time break and a sweep before four seismic traces, and the
+/// given number of auxiliary traces in the binary header. The trace numbers count down from
+/// 18 and the samples are the index of the trace in the file.
+fn write_shots_with_aux(path: &Path, no_aux_traces: u16, aux_codes: [TraceIdCode; 2]) {
+    let mut bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    bin_header.no_aux_traces = no_aux_traces;
+    let xlines = (0..18).collect::<Vec<_>>();
+    write_edited_grid(
+        path,
+        SegySettings::default(),
+        bin_header,
+        &[1],
+        &xlines,
+        &[],
+        |header, _| {
+            let i = header.crossline_no;
+            *header = TraceHeader::new_2d(0, 0, 1);
+            header.field_record_no = i / 6 + 1;
+            header.trace_no = 18 - i;
+            header.trace_identification_code = match i % 6 {
+                0 => aux_codes[0],
+                1 => aux_codes[1],
+                _ => TraceIdCode::TimeDomainSeismic,
+            };
+            vec![i as f32; 3]
+        },
+    );
+}
+
+#[test]
+fn test_aux_trace_policy() {
+    use giga_segy_core::enums::AuxPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots.sgy");
+    let aux_codes = [TraceIdCode::TimeBreak, TraceIdCode::Sweep];
+    write_shots_with_aux(&path, 2, aux_codes);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(segy.trace_count(), 18);
+    assert_eq!(segy.aux_trace_indices(), &[0, 1, 6, 7, 12, 13]);
+    assert!(segy.get_warnings().is_empty(), "{:?}", segy.get_warnings());
+
+    let mut settings = SegySettings::default();
+    settings.set_aux_trace_policy(AuxPolicy::Exclude);
+    let segy = crate::SegyFile::open(name, settings.clone()).unwrap();
+    assert_eq!(segy.trace_count(), 12);
+    assert!(segy.aux_trace_indices().is_empty());
+    let ids = segy
+        .traces_iter()
+        .map(|t| t.get_header().trace_identification_code)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![TraceIdCode::TimeDomainSeismic; 12]);
+    assert_eq!(segy.get_trace_data_as_f32(4).unwrap(), vec![8.; 3]);
+
+    settings.set_aux_trace_policy(AuxPolicy::Only);
+    let segy = crate::SegyFile::open(name, settings.clone()).unwrap();
+    assert_eq!(segy.trace_count(), 6);
+    assert!(segy.aux_trace_indices().is_empty());
+    let records = segy
+        .traces_iter()
+        .map(|t| t.get_header().field_record_no)
+        .collect::<Vec<_>>();
+    assert_eq!(records, vec![1, 1, 2, 2, 3, 3]);
+    assert!(segy
+        .traces_iter()
+        .all(|t| t.get_header().trace_identification_code.is_auxiliary()));
+
+    // The same traces are found with compact headers.
+    settings.set_compact_headers(true);
+    let segy = crate::SegyFile::open(name, settings).unwrap();
+    assert_eq!(segy.trace_count(), 6);
+    assert_eq!(segy.get_trace_data_as_f32(3).unwrap(), vec![7.; 3]);
+}
+
+#[test]
+fn test_aux_trace_indices_sorted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots.sgy");
+    write_shots_with_aux(&path, 2, [TraceIdCode::TimeBreak, TraceIdCode::Sweep]);
+
+    // Sorting by the trace number reverses the traces, and the indices follow them.
+    let mut settings = SegySettings::default();
+    settings.set_sort_traces_by(OrderTraceBy::TraceNo);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.aux_trace_indices(), &[4, 5, 10, 11, 16, 17]);
+    for i in segy.aux_trace_indices() {
+        let trace = segy.get_trace(*i).unwrap();
+        let header = trace.get_header();
+        assert!(header.trace_identification_code.is_auxiliary());
+    }
+}
+
+#[test]
+fn test_aux_traces_not_recognised() {
+    use giga_segy_core::enums::AuxPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots.sgy");
+    let aux_codes = [TraceIdCode::Unknown, TraceIdCode::Unknown];
+    write_shots_with_aux(&path, 2, aux_codes);
+
+    let mut settings = SegySettings::default();
+    settings.set_aux_trace_policy(AuxPolicy::Exclude);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(segy.trace_count(), 18);
+    assert_eq!(segy.get_warnings().len(), 1);
+    assert!(segy.get_warnings()[0].contains("2 auxiliary traces per record"));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_aux_trace_indices() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("shots.sgy");
+    write_shots_with_aux(
+        &path,
+        2,
+        [TraceIdCode::Uphole, TraceIdCode::VibratorReference],
+    );
+
+    let segy = crate::AsyncSegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .await
+        .unwrap();
+    assert_eq!(segy.trace_count(), 18);
+    assert_eq!(segy.aux_trace_indices(), &[0, 1, 6, 7, 12, 13]);
+}
+
+#[test]
+fn test_synthesized_line_numbers() {
+    use giga_segy_core::enums::LineNumberingSource;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.sgy");
+    write_synthetic_grid(&path, &[10, 11, 12], &[20, 21, 22, 23, 24], &[], 4);
+    let name = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(name, SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.line_numbering_source(),
+        LineNumberingSource::TraceHeaders {
+            inline_no_bidx: 188,
+            crossline_no_bidx: 192,
+        }
+    );
+    for trace in segy.traces_iter() {
+        assert!(!trace.line_numbers_synthesized());
+        assert_eq!(trace.original_inline_no(), None);
+        assert_eq!(trace.original_crossline_no(), None);
+    }
+
+    let mut settings = SegySettings::default();
+    settings.set_override_dim_x(5).unwrap();
+    for storage in 0..3 {
+        let mut settings = settings.clone();
+        settings.set_compact_headers(storage == 1);
+        settings.set_dedup_headers(storage == 2);
+        let segy = crate::SegyFile::open(name, settings).unwrap();
+        assert_eq!(
+            segy.line_numbering_source(),
+            LineNumberingSource::Synthesized {
+                dim_x: 5,
+                order_trace_by: OrderTraceBy::Default,
+            }
+        );
+        assert_eq!(segy.trace_count(), 15);
+        // The headers of compact traces are parsed again, so the traces are loaded.
+        for i in 0..segy.trace_count() {
+            let trace = segy.load_trace(i).unwrap();
+            let header = trace.get_header();
+            assert_eq!(
+                [header.inline_no, header.crossline_no],
+                [i / 5, i % 5].map(|x| x as i32)
+            );
+            assert!(trace.line_numbers_synthesized());
+            assert_eq!(trace.original_inline_no(), Some(10 + i as i32 / 5));
+            assert_eq!(trace.original_crossline_no(), Some(20 + i as i32 % 5));
+        }
+    }
+}
+
+#[test]
+fn test_text_header_with_crlf() {
+    use giga_segy_core::enums::TextHeaderLayout;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crlf.sgy");
+    let cards = short_cards();
+    let text = format!("{:<3200}", cards.join("\r\n") + "\r\n");
+    write_text_header_file(&path, text.as_bytes());
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_text_header_layout(),
+        TextHeaderLayout::LineTerminated
+    );
+    let lines = segy.get_text_header_lines();
+    assert_eq!(lines.len(), 40);
+    for (line, card) in lines.iter().zip(&cards) {
+        assert_eq!(line, &format!("{:<80}", card));
+    }
+    assert_eq!(
+        segy.text_header_line(12).unwrap().trim_end(),
+        "C12 Card number 12"
+    );
+    assert!(!segy.get_text_header().contains(['\r', '\n']));
+
+    // A line which is too long is cut off, and the bytes in the file are unchanged.
+    let mut cards = short_cards();
+    cards[2] = format!("C 3 {}", "X".repeat(100));
+    let text = format!("{:<3200}", cards.join("\n"));
+    write_text_header_file(&path, text.as_bytes());
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_text_header_layout(),
+        TextHeaderLayout::LineTerminated
+    );
+    assert_eq!(segy.get_text_header_lines().len(), 40);
+    assert_eq!(segy.text_header_line(3).unwrap(), &cards[2][..80]);
+    assert_eq!(
+        segy.text_header_line(4).unwrap().trim_end(),
+        "C 4 Card number 4"
+    );
+    assert_eq!(segy.get_text_header_bytes(), text.as_bytes());
+}
+
+#[test]
+fn test_text_header_with_stray_line_feed() {
+    use giga_segy_core::enums::{TextEncoding, TextHeaderLayout};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stray.sgy");
+    let cards = short_cards();
+    let mut text = cards
+        .iter()
+        .map(|card| format!("{:<80}", card))
+        .collect::<String>()
+        .into_bytes();
+    // A line feed in the middle of card 12, well after the first 400 bytes.
+    text[11 * 80 + 30] = b'\n';
+    write_text_header_file(&path, &text);
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_text_header_encoding(), TextEncoding::Ascii);
+    assert_eq!(segy.get_text_header_layout(), TextHeaderLayout::FixedWidth);
+    let lines = segy.get_text_header_lines();
+    assert_eq!(lines.len(), 40);
+    assert_eq!(lines[11], format!("{:<80}", cards[11]));
+    assert_eq!(lines[12], format!("{:<80}", cards[12]));
+    assert_eq!(segy.get_text_header().len(), 3200);
+
+    let segy = crate::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    assert_eq!(segy.get_text_header_layout(), TextHeaderLayout::FixedWidth);
+}
+
+#[test]
+fn test_trace_name_byte_order() {
+    let dir = tempfile::tempdir().unwrap();
+    for le in [false, true] {
+        let path = dir.path().join(format!("names_{}.sgy", le));
+        let mut bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+        bin_header.binary_flag_direction_is_le = le;
+        let mut settings = SegySettings::default();
+        settings.set_override_to_le(le);
+        let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+            &path,
+            settings,
+            "C 1 Named traces.".to_string(),
+            bin_header,
+            None,
+        )
+        .unwrap();
+        let mut trace_header = TraceHeader::new_2d(0, 0, 1);
+        trace_header.trace_name = *b"SEG00001";
+        file.add_trace(trace_header, None, vec![0.; 3]).unwrap();
+        drop(file);
+
+        // The name is in the natural order in the file, whatever the byte order.
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[3600 + 232..3600 + 240], b"SEG00001");
+
+        let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+        let trace = segy.get_trace(0).unwrap();
+        let header = trace.get_header();
+        assert_eq!(&header.trace_name, b"SEG00001");
+        assert_eq!(header.get_trace_name(), "SEG00001");
+    }
+}
+
+#[test]
+fn test_read_trace_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("names.sgy");
+    write_synthetic_grid(&path, &[1], &[1, 2, 3], &[], 3);
+    // Names as other software writes them, in ASCII and EBCDIC, and one which was reversed by
+    // an older version of this crate.
+    let ebcdic = b"SEG00002".map(to_ebcdic);
+    let mut bytes = std::fs::read(&path).unwrap();
+    let trace_len = 240 + 3 * 4;
+    for (i, name) in [*b"SEG00001", ebcdic, *b"30000GES"].iter().enumerate() {
+        let start = 3600 + i * trace_len + 232;
+        bytes[start..(start + 8)].copy_from_slice(name);
+    }
+    std::fs::write(&path, bytes).unwrap();
+
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let names = segy
+        .traces_iter()
+        .map(|t| t.get_header().get_trace_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["SEG00001", "SEG00002", "SEG00003"]);
+    assert_eq!(&segy.get_trace(1).unwrap().get_header().trace_name, &ebcdic);
+}
+
+/// Write a file with one trace of the given samples in the given format.
+fn write_samples<T: ToPrimitive + Debug + Clone>(
+    path: &Path,
+    format: SampleFormatCode,
+    data: Vec<T>,
+) {
+    let bin_header = BinHeader::new(0, 4000, data.len() as u16, format);
+    let settings = SegySettings::default();
+    write_edited_grid(path, settings, bin_header, &[1], &[1], &[], |_, _| {
+        data.clone()
+    });
+}
+
+#[test]
+fn test_get_trace_data_as_scaled() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("float.sgy");
+    write_samples(
+        &path,
+        SampleFormatCode::Float32,
+        vec![0.5f32, -1.25, 3.999, 100.],
+    );
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    // The values are truncated towards zero.
+    assert_eq!(
+        segy.get_trace_data_as::<i16>(0, None).unwrap(),
+        vec![0, -1, 3, 100]
+    );
+    assert_eq!(
+        segy.get_trace_data_as::<i16>(0, Some(100.)).unwrap(),
+        vec![50, -125, 399, 10_000]
+    );
+    assert_eq!(
+        segy.get_trace_data_as::<f64>(0, Some(2.)).unwrap(),
+        vec![1., -2.5, 3.999f32 as f64 * 2., 200.]
+    );
+
+    // The scale is applied on top of the user gain.
+    let mut settings = SegySettings::default();
+    settings.set_user_gain(2.);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as::<i32>(0, Some(10.)).unwrap(),
+        vec![10, -25, 79, 2000]
+    );
+}
+
+#[test]
+fn test_get_trace_data_as_out_of_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("float.sgy");
+    write_samples(
+        &path,
+        SampleFormatCode::Float32,
+        vec![1f32, 200., 400., -1.],
+    );
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+
+    let e = segy.get_trace_data_as::<i16>(0, Some(100.)).unwrap_err();
+    assert!(
+        matches!(e, RsgError::SampleConversion { idx: 2, value } if value == 40_000.),
+        "{:?}",
+        e
+    );
+    let e = segy.get_trace_data_as::<u8>(0, None).unwrap_err();
+    assert!(
+        matches!(e, RsgError::SampleConversion { idx: 2, .. }),
+        "{:?}",
+        e
+    );
+    let e = segy.get_trace_data_as::<i16>(999, None).unwrap_err();
+    assert!(matches!(e, RsgError::TraceNotFound { i: 999 }));
+
+    // The index counts only every `step_by`-th sample.
+    let mut settings = SegySettings::default();
+    settings.set_step_by(2);
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let e = segy.get_trace_data_as::<u8>(0, Some(1.)).unwrap_err();
+    assert!(
+        matches!(e, RsgError::SampleConversion { idx: 1, .. }),
+        "{:?}",
+        e
+    );
+}
+
+#[test]
+fn test_get_trace_data_as_exact() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("int16.sgy");
+    write_samples(
+        &path,
+        SampleFormatCode::Int16,
+        vec![i16::MIN, -1, 0, i16::MAX],
+    );
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as::<i16>(0, None).unwrap(),
+        vec![i16::MIN, -1, 0, i16::MAX]
+    );
+    assert_eq!(
+        segy.get_trace_data_as::<i64>(0, Some(2.)).unwrap(),
+        vec![-65536, -2, 0, 65534]
+    );
+
+    // Values which an f64 cannot represent are kept as they are.
+    let path = dir.path().join("int64.sgy");
+    let large = vec![i64::MAX, i64::MIN + 1, (1 << 53) + 1];
+    write_samples(&path, SampleFormatCode::Int64, large.clone());
+    let segy = crate::SegyFile::open(path.to_str().unwrap(), SegySettings::default()).unwrap();
+    assert_eq!(segy.get_trace_data_as::<i64>(0, None).unwrap(), large);
+    assert_eq!(
+        segy.get_trace_data_as::<i128>(0, Some(1.)).unwrap()[2],
+        (1 << 53) + 1
+    );
+    assert!(matches!(
+        segy.get_trace_data_as::<u64>(0, None),
+        Err(RsgError::SampleConversion { idx: 1, .. })
+    ));
+}
+
+#[test]
+fn test_strict_revision() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("revision.sgy");
+    write_synthetic_grid(&path, &[1, 2], &[1, 2], &[], 10);
+    let open = |revision: [u8; 2], strict: bool| {
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[3500..3502].copy_from_slice(&revision);
+        std::fs::write(&path, bytes).unwrap();
+        let mut settings = SegySettings::default();
+        settings.set_strict_revision(strict);
+        crate::SegyFile::open(path.to_str().unwrap(), settings)
+    };
+
+    for strict in [false, true] {
+        let segy = open([2, 0], strict).unwrap();
+        assert_eq!(segy.get_bin_header().segy_revision_number, [2, 0]);
+        assert!(segy.get_bin_header().revision_supported());
+        assert!(segy.get_warnings().is_empty());
+    }
+
+    let segy = open([3, 0], false).unwrap();
+    assert_eq!(segy.get_bin_header().segy_revision_number, [3, 0]);
+    assert!(!segy.get_bin_header().revision_supported());
+    assert_eq!(segy.trace_count(), 4);
+    assert!(matches!(
+        open([3, 0], true),
+        Err(RsgError::UnsupportedRevision { major: 3, minor: 0 })
+    ));
+
+    // "1." is text, not a revision number.
+    let segy = open([0x31, 0x2E], false).unwrap();
+    assert_eq!(segy.get_bin_header().segy_revision_number, [0, 0]);
+    assert_eq!(segy.get_bin_header_bytes()[300..302], [0x31, 0x2E]);
+    assert_eq!(segy.get_warnings().len(), 1);
+    assert!(segy.get_warnings()[0].contains("\"1.\""));
+    assert_eq!(segy.get_trace_data_as_f32(3).unwrap()[0], 2002.);
+    assert!(matches!(
+        open([0x31, 0x2E], true),
+        Err(RsgError::UnsupportedRevision {
+            major: 0x31,
+            minor: 0x2E
+        })
+    ));
+}
+
+#[test]
+fn test_custom_decoder() {
+    use giga_segy_core::decoder::{DatumLen, TraceDecoder};
+    use std::sync::Arc;
+
+    /// Float32 samples, stored with the sign flipped.
+    struct Negated;
+
+    impl TraceDecoder for Negated {
+        fn datum_byte_length(&self) -> DatumLen {
+            DatumLen::Bytes(4)
+        }
+
+        fn decode(
+            &self,
+            bytes: &[u8],
+            n_samples: usize,
+            le: bool,
+            out: &mut Vec<f32>,
+        ) -> Result<(), RsgError> {
+            out.extend(bytes.chunks_exact(4).take(n_samples).map(|b| {
+                let b = b.try_into().unwrap();
+                -if le {
+                    f32::from_le_bytes(b)
+                } else {
+                    f32::from_be_bytes(b)
+                }
+            }));
+            Ok(())
+        }
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("custom.sgy");
+    write_synthetic_grid(&path, &[1, 2], &[1, 2, 3], &[], 10);
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[3224..3226].copy_from_slice(&200u16.to_be_bytes());
+    std::fs::write(&path, bytes).unwrap();
+    let path = path.to_str().unwrap();
+
+    assert!(matches!(
+        crate::SegyFile::open(path, SegySettings::default()),
+        Err(RsgError::ParseEnum { .. })
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.register_custom_decoder(200, Arc::new(Negated));
+    let segy = crate::SegyFile::open(path, settings.clone()).unwrap();
+    assert_eq!(segy.trace_count(), 6);
+    assert_eq!(segy.get_bin_header().raw_sample_format_code, 200);
+    assert_eq!(segy.trace_sample_count(0), Some(10));
+    assert_eq!(
+        segy.effective_parameters().sample_format.source,
+        giga_segy_core::effective::Provenance::Override
+    );
+
+    // Trace 4 is inline 2, crossline 2.
+    let expected = (0..10)
+        .map(|s| -2002. - s as f32 / 100.)
+        .collect::<Vec<_>>();
+    assert_eq!(segy.get_trace_data_as_bytes(4).unwrap().len(), 40);
+    assert_eq!(segy.get_trace_data_as_f32(4).unwrap(), expected);
+    assert_eq!(
+        segy.get_trace_data_as::<f64>(4, Some(2.)).unwrap(),
+        expected.iter().map(|v| *v as f64 * 2.).collect::<Vec<_>>()
+    );
+    let trace = segy.get_trace(4).unwrap();
+    assert_eq!(
+        segy.get_trace_data_point_as_f32_from_trace(&trace, 3)
+            .unwrap(),
+        expected[3]
+    );
+    assert!(matches!(
+        segy.get_trace_data_point_as_f32_from_trace(&trace, 10),
+        Err(RsgError::TracePointOutOfBounds { idx: 10 })
+    ));
+    assert_eq!(
+        segy.get_samples_at(&[(4, 0), (4, 9), (5, 1)]).unwrap(),
+        vec![
+            expected[0],
+            expected[9],
+            segy.get_trace_data_as_f32(5).unwrap()[1]
+        ]
+    );
+
+    settings.set_step_by(3);
+    let segy = crate::SegyFile::open(path, settings).unwrap();
+    assert_eq!(segy.trace_sample_count(4), Some(4));
+    assert_eq!(
+        segy.get_trace_data_as_f32(4).unwrap(),
+        expected.into_iter().step_by(3).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_detect_float_byte_order_issue() {
+    use crate::FloatOrder;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("swapped.sgy");
+    write_synthetic_grid(&path, &[1, 2], &[1, 2, 3], &[], 10);
+    let good = std::fs::read(&path).unwrap();
+    let path = path.to_str().unwrap();
+    // Change the samples of each trace (after the headers) with `f`.
+    let rewrite = |f: &dyn Fn(&mut [u8])| {
+        let mut bytes = good.clone();
+        for trace in bytes[3600..].chunks_exact_mut(240 + 40) {
+            f(&mut trace[240..]);
+        }
+        std::fs::write(path, bytes).unwrap();
+    };
+    let swap = |data: &mut [u8]| data.chunks_exact_mut(4).for_each(|s| s.reverse());
+    let open = |swap: bool| {
+        let mut settings = SegySettings::default();
+        settings.set_swap_data_byte_order(swap);
+        crate::SegyFile::open(path, settings).unwrap()
+    };
+
+    let segy = open(false);
+    let diagnosis = segy.detect_float_byte_order_issue(10);
+    assert_eq!(diagnosis.format, SampleFormatCode::Float32);
+    assert_eq!(diagnosis.traces, 6);
+    assert_eq!(diagnosis.as_read.count, 60);
+    assert_eq!(diagnosis.as_read.plausible, 1.);
+    assert_eq!(diagnosis.verdict, FloatOrder::AsRead);
+    let expected = segy.get_trace_data_as_f32(4).unwrap();
+
+    // The headers are big endian, but the samples little endian.
+    rewrite(&swap);
+    let segy = open(false);
+    let diagnosis = segy.detect_float_byte_order_issue(3);
+    assert_eq!(diagnosis.traces, 3);
+    assert!(diagnosis.is_swapped());
+    assert!(diagnosis.as_read.plausible < 0.5);
+    assert_ne!(segy.get_trace_data_as_f32(4).unwrap(), expected);
+    let segy = open(true);
+    assert_eq!(
+        segy.detect_float_byte_order_issue(3).verdict,
+        FloatOrder::AsRead
+    );
+    assert_eq!(segy.get_trace_data_as_f32(4).unwrap(), expected);
+    assert_eq!(segy.get_trace_data_as::<f64>(4, None).unwrap()[0], 2002.);
+    assert_eq!(segy.get_samples_at(&[(4, 0)]).unwrap(), vec![2002.]);
+
+    // IBM floats, with the values 256 to 265 in every trace.
+    let ibm = |data: &mut [u8]| {
+        for (n, s) in data.chunks_exact_mut(4).enumerate() {
+            s.copy_from_slice(&(0x4300_0000u32 | ((256 + n as u32) << 12)).to_be_bytes());
+        }
+    };
+    let mut good = good.clone();
+    good[3224..3226].copy_from_slice(&1u16.to_be_bytes());
+    let rewrite = |f: &dyn Fn(&mut [u8])| {
+        let mut bytes = good.clone();
+        for trace in bytes[3600..].chunks_exact_mut(240 + 40) {
+            f(&mut trace[240..]);
+        }
+        std::fs::write(path, bytes).unwrap();
+    };
+    let expected = (256..266).map(|v| v as f32).collect::<Vec<_>>();
+    rewrite(&ibm);
+    let segy = open(false);
+    let diagnosis = segy.detect_float_byte_order_issue(10);
+    assert_eq!(diagnosis.format, SampleFormatCode::IbmFloat32);
+    assert_eq!(diagnosis.verdict, FloatOrder::AsRead);
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), expected);
+
+    rewrite(&|data| {
+        ibm(data);
+        swap(data);
+    });
+    let segy = open(false);
+    assert!(segy.detect_float_byte_order_issue(10).is_swapped());
+    let segy = open(true);
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), expected);
+
+    // Integer samples are not checked.
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_format(SampleFormatCode::Int32);
+    let segy = crate::SegyFile::open(path, settings).unwrap();
+    let diagnosis = segy.detect_float_byte_order_issue(10);
+    assert_eq!(diagnosis.verdict, FloatOrder::NotApplicable);
+    assert_eq!(diagnosis.traces, 0);
+}
+
+#[test]
+fn test_positions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("positions.sgy");
+    let bin_header = BinHeader::new(0, 4, 5, SampleFormatCode::Float32);
+    let mut file = giga_segy_out::SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Coordinates in centimetres.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    for (n, [il, xl]) in [[1, 1], [1, 2], [2, 1], [2, 2], [3, 5]].iter().enumerate() {
+        let x = 100_000 + n as i32 * 1234;
+        let y = 50_000 - n as i32 * 567;
+        let mut header = TraceHeader::new_3d(x, y, *il, *xl, -100);
+        header.no_samples_in_trace = 5;
+        file.add_trace(header, None, vec![0.; 5]).unwrap();
+    }
+    file.finalize().unwrap();
+    let path = path.to_str().unwrap();
+
+    let segy = crate::SegyFile::open(path, SegySettings::default()).unwrap();
+    let positions = segy.positions_to_vec();
+    assert_eq!(positions.len(), 5);
+    assert_eq!(
+        positions[4],
+        crate::TracePosition {
+            index: 4,
+            inline: 3,
+            crossline: 5,
+            x: 1049.36,
+            y: 477.32,
+        }
+    );
+    assert_eq!(positions[1].x, 1012.34);
+    assert_eq!(positions, segy.positions_iter().collect::<Vec<_>>());
+
+    // The bounding box matches the traces with the smallest and largest coordinates.
+    let [x_min, x_max] = segy.get_trace_idx_for_x_ensemble_min_max().unwrap();
+    let [y_min, y_max] = segy.get_trace_idx_for_y_ensemble_min_max().unwrap();
+    assert_eq!(
+        segy.positions_bounding_box(),
+        Some([
+            positions[x_min].x,
+            positions[y_min].y,
+            positions[x_max].x,
+            positions[y_max].y
+        ])
+    );
+    assert_eq!(
+        segy.positions_bounding_box(),
+        Some([1000., 477.32, 1049.36, 500.])
+    );
+
+    // The positions are the same whichever way the headers are stored.
+    let mut settings = SegySettings::default();
+    settings.set_compact_headers(true);
+    let compact = crate::SegyFile::open(path, settings).unwrap();
+    assert_eq!(compact.positions_to_vec(), positions);
+    let mut settings = SegySettings::default();
+    settings.set_dedup_headers(true);
+    let dedup = crate::SegyFile::open(path, settings).unwrap();
+    assert_eq!(dedup.positions_to_vec(), positions);
+
+    // An overridden scalar is used instead of the one of the trace headers.
+    let mut settings = SegySettings::default();
+    settings.set_override_coordinate_scaling(10.).unwrap();
+    let segy = crate::SegyFile::open(path, settings).unwrap();
+    assert_eq!(
+        segy.positions_bounding_box(),
+        Some([1_000_000., 477_320., 1_049_360., 500_000.])
+    );
+}
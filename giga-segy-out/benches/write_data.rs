@@ -0,0 +1,69 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! Benchmarks for the conversion of trace data when it is written, for the sample formats which
+//! are converted in bulk and for one which is converted sample by sample.
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+use giga_segy_out::write_data::LosslessWriteableSegyData;
+use giga_segy_out::SegyFile;
+use num::FromPrimitive;
+use std::path::Path;
+
+const SAMPLES: usize = 10_000;
+const TRACES: usize = 10;
+
+/// Create a new file for the traces, replacing the one of the last iteration.
+fn create(path: &Path, format: SampleFormatCode) -> SegyFile<SegySettings> {
+    let _ = std::fs::remove_file(path);
+    let bin_header = BinHeader::new(0, 4000, SAMPLES as u16, format);
+    SegyFile::<SegySettings>::create_file(
+        path,
+        SegySettings::default(),
+        "C 1 A benchmark.".to_string(),
+        bin_header,
+        None,
+    )
+    .expect("Could not create file.")
+}
+
+fn bench_format<T: LosslessWriteableSegyData + FromPrimitive + Clone>(
+    c: &mut Criterion,
+    format: SampleFormatCode,
+) {
+    let dir = tempfile::tempdir().expect("Couldn't get tempdir.");
+    let path = dir.path().join("bench.sgy");
+    let data = (0..SAMPLES)
+        .map(|i| T::from_usize(i % 100).expect("Fits."))
+        .collect::<Vec<T>>();
+
+    let mut group = c.benchmark_group("add_trace_lossless");
+    group.throughput(Throughput::Elements((SAMPLES * TRACES) as u64));
+    group.bench_function(format!("{:?}", format), |b| {
+        b.iter_batched(
+            || create(&path, format),
+            |mut file| {
+                for i in 0..TRACES {
+                    let header = TraceHeader::new_3d(0, 0, 1, i as i32, 1);
+                    file.add_trace_lossless(header, None, data.clone())
+                        .expect("Could not add trace.");
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn write_data(c: &mut Criterion) {
+    bench_format::<i8>(c, SampleFormatCode::Int8);
+    bench_format::<u8>(c, SampleFormatCode::UInt8);
+    bench_format::<i16>(c, SampleFormatCode::Int16);
+    bench_format::<i32>(c, SampleFormatCode::Int32);
+    bench_format::<f32>(c, SampleFormatCode::Float32);
+    // This format is still converted sample by sample.
+    bench_format::<f64>(c, SampleFormatCode::Float64);
+}
+
+criterion_group!(benches, write_data);
+criterion_main!(benches);
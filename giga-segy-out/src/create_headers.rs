@@ -5,7 +5,10 @@
 //! that implementing the traits here for new types and structures will probably not be too
 //! useful unless [`crate::write_headers::SegyHeaderToBytes`] is also implemented for the structure.
 use giga_segy_core::enums::*;
-use giga_segy_core::{BinHeader, TapeLabel, TraceHeader};
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, ReadableTapeLabel, TapeLabel, TraceHeader};
+
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::SegyHeaderToBytes;
 
@@ -24,6 +27,13 @@ pub trait CreateBinHeader: SegyHeaderToBytes {
     /// Set the measurement system.
     fn set_measurement_system(&mut self, measurement_system: MeasurementSystem);
 
+    /// Set the sample interval and, for depth data, the measurement system from the vertical
+    /// axis. The interval is written in microseconds, millimetres or thousandths of a foot.
+    /// Nothing is changed for [`VerticalAxis::Unknown`].
+    ///
+    /// An error is returned if the interval cannot be written to the header.
+    fn set_vertical_axis(&mut self, axis: VerticalAxis) -> Result<(), RsgError>;
+
     /// Set binary direction as LE.
     fn switch_binary_flag_to_le(&mut self);
 }
@@ -49,6 +59,28 @@ pub trait CreateTraceHeader {
         xline_no: i32,
         coordinate_scalar: i16,
     ) -> Self;
+
+    /// Set the trace identification code (time or depth domain seismic) and the sample
+    /// interval of the trace from the vertical axis, in the same way as
+    /// [`CreateBinHeader::set_vertical_axis`]. For [`VerticalAxis::Unknown`], the code is set
+    /// to [`TraceIdCode::Unknown`] and the sample interval is left as it is.
+    /// ```
+    /// use giga_segy_core::{BinHeader, TraceHeader};
+    /// use giga_segy_core::enums::*;
+    /// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+    ///
+    /// let axis = VerticalAxis::DepthFeet { interval: 2.5 };
+    /// let mut bin_header = BinHeader::new(0, 0, 100, SampleFormatCode::Float32);
+    /// bin_header.set_vertical_axis(axis).unwrap();
+    /// assert_eq!(bin_header.sample_interval, 2500);
+    /// assert_eq!(bin_header.measurement_system, MeasurementSystem::Feet);
+    ///
+    /// let mut trace_header = TraceHeader::new_2d(0, 0, 1);
+    /// trace_header.set_vertical_axis(axis).unwrap();
+    /// assert_eq!(trace_header.trace_identification_code, TraceIdCode::DepthDomainSeismic);
+    /// assert_eq!(trace_header.sample_interval_of_trace, 2500);
+    /// ```
+    fn set_vertical_axis(&mut self, axis: VerticalAxis) -> Result<(), RsgError>;
 }
 
 /// Creates an empty tape label.
@@ -72,6 +104,121 @@ pub fn create_tape_label() -> TapeLabel {
     }
 }
 
+/// Builds a [`TapeLabel`] from strings, which are checked and padded with spaces when the
+/// label is built (see [`ReadableTapeLabel::to_tape_label`]).
+///
+/// By default the label is for storage unit "1" of SEG-Y rev 2 ("SY2.0"), with the "RECORD"
+/// structure, binding edition "BXXX", a maximum block size of [`u32::MAX`] and today's date
+/// (in the DD-MMM-YYYY format). All other fields are blank.
+/// ```
+/// use giga_segy_out::create_headers::TapeLabelBuilder;
+///
+/// let label = TapeLabelBuilder::new()
+///     .producing_organisation_code("GIGA")
+///     .external_label("SURVEY-42")
+///     .build()
+///     .unwrap();
+/// assert_eq!(&label.segy_revision_no, b"SY2.0");
+/// assert_eq!(&label.external_label, b"SURVEY-42   ");
+///
+/// assert!(TapeLabelBuilder::new().serial_number("1234567890123").build().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapeLabelBuilder {
+    label: ReadableTapeLabel,
+}
+
+impl Default for TapeLabelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates the setters of [`TapeLabelBuilder`].
+macro_rules! tape_label_setters {
+    ($($field:ident),+ $(,)?) => {
+        $(
+            #[doc = concat!("Set [`TapeLabel::", stringify!($field), "`].")]
+            pub fn $field(mut self, value: &str) -> Self {
+                self.label.$field = value.to_string();
+                self
+            }
+        )+
+    };
+}
+
+impl TapeLabelBuilder {
+    /// Create a builder with the default values.
+    pub fn new() -> Self {
+        TapeLabelBuilder {
+            label: ReadableTapeLabel {
+                storage_unit_seq_no: String::from("1"),
+                segy_revision_no: String::from("SY2.0"),
+                storage_unit_structure: String::from("RECORD"),
+                binding_number: String::from("BXXX"),
+                max_block_size: u32::MAX,
+                producing_organisation_code: String::new(),
+                creation_date: current_date(),
+                serial_number: String::new(),
+                external_label: String::new(),
+                recording_entity: String::new(),
+                extra: String::new(),
+            },
+        }
+    }
+
+    tape_label_setters!(
+        storage_unit_seq_no,
+        segy_revision_no,
+        storage_unit_structure,
+        binding_number,
+        producing_organisation_code,
+        creation_date,
+        serial_number,
+        external_label,
+        recording_entity,
+        extra,
+    );
+
+    /// Set [`TapeLabel::max_block_size`].
+    pub fn max_block_size(mut self, value: u32) -> Self {
+        self.label.max_block_size = value;
+        self
+    }
+
+    /// Build the tape label. This fails if any of the fields is too long or not ASCII.
+    pub fn build(&self) -> Result<TapeLabel, RsgError> {
+        self.label.to_tape_label()
+    }
+}
+
+/// Get today's date (UTC) in the DD-MMM-YYYY format of the tape label.
+fn current_date() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400);
+    let (year, month, day) = civil_from_days(days);
+    const MONTHS: [&str; 12] = [
+        "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+    ];
+    format!("{:02}-{}-{:04}", day, MONTHS[month as usize - 1], year)
+}
+
+/// Convert a number of days since 1970-01-01 to a year, month and day (see
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>).
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
 impl CreateBinHeader for BinHeader {
     fn default() -> Self {
         create_default_bin_header()
@@ -88,6 +235,7 @@ impl CreateBinHeader for BinHeader {
         header.sample_interval = sample_interval;
         header.no_samples = no_samples;
         header.sample_format_code = sample_format_code;
+        header.raw_sample_format_code = sample_format_code as u16;
         header
     }
 
@@ -95,11 +243,42 @@ impl CreateBinHeader for BinHeader {
         self.measurement_system = measurement_system;
     }
 
+    fn set_vertical_axis(&mut self, axis: VerticalAxis) -> Result<(), RsgError> {
+        if let Some(interval) = header_sample_interval(axis, "sample_interval")? {
+            self.sample_interval = interval;
+        }
+        match axis {
+            VerticalAxis::DepthMeters { .. } => self.measurement_system = MeasurementSystem::Meters,
+            VerticalAxis::DepthFeet { .. } => self.measurement_system = MeasurementSystem::Feet,
+            VerticalAxis::TimeMs { .. } | VerticalAxis::Unknown => {}
+        }
+        Ok(())
+    }
+
     fn switch_binary_flag_to_le(&mut self) {
         self.binary_flag_direction_is_le = true;
     }
 }
 
+/// Get the sample interval of a vertical axis as it is written to the headers (in microseconds,
+/// millimetres or thousandths of a foot), or `None` if the axis is unknown.
+fn header_sample_interval(
+    axis: VerticalAxis,
+    field: &'static str,
+) -> Result<Option<u16>, RsgError> {
+    match axis.interval() {
+        None => Ok(None),
+        Some(interval) => {
+            let interval = (interval * 1000.0).round();
+            if interval.is_finite() && (0.0..=u16::MAX as f64).contains(&interval) {
+                Ok(Some(interval as u16))
+            } else {
+                Err(RsgError::HeaderFieldEncode { field })
+            }
+        }
+    }
+}
+
 /// This creates a default instance of `BinHeader`
 ///
 /// NB: This is not default in accordance with the standard.
@@ -116,6 +295,7 @@ fn create_default_bin_header() -> BinHeader {
         no_samples: 0,
         no_samples_original: 0,
         sample_format_code: SampleFormatCode::Float32,
+        raw_sample_format_code: SampleFormatCode::Float32 as u16,
         ensemble_fold: 0,
         sorting_code: TraceSortingCode::Unknown,
         vertical_sum: 0,
@@ -133,11 +313,22 @@ fn create_default_bin_header() -> BinHeader {
         measurement_system: MeasurementSystem::Unspecified,
         impulse_signal_polarity: ImpulseSignalPolarity::Unspecified,
         vibratory_polarity_code: VibratoryPolarityCode::Unspecified,
+        extended_no_traces: 0,
+        extended_no_aux_traces: 0,
+        extended_no_samples: 0,
+        extended_sample_interval: 0.,
+        extended_sample_interval_original: 0.,
+        extended_no_samples_original: 0,
+        extended_ensemble_fold: 0,
         // Combines minor and major revision code.
         segy_revision_number: [2, 0],
         fixed_length_trace_flag: FixedLengthTraces::No,
         extended_header_count: 0,
+        max_additional_trace_headers: 0,
         time_basis_code: TimeBasisCode::Unspecified,
+        no_traces_in_file: 0,
+        first_trace_offset: 0,
+        no_trailer_stanzas: 0,
         binary_flag_direction_is_le: false,
     }
 }
@@ -170,6 +361,20 @@ impl CreateTraceHeader for TraceHeader {
         header.coordinate_scalar = coordinate_scalar;
         header
     }
+
+    fn set_vertical_axis(&mut self, axis: VerticalAxis) -> Result<(), RsgError> {
+        if let Some(interval) = header_sample_interval(axis, "sample_interval_of_trace")? {
+            self.sample_interval_of_trace = interval;
+        }
+        self.trace_identification_code = match axis {
+            VerticalAxis::TimeMs { .. } => TraceIdCode::TimeDomainSeismic,
+            VerticalAxis::DepthMeters { .. } | VerticalAxis::DepthFeet { .. } => {
+                TraceIdCode::DepthDomainSeismic
+            }
+            VerticalAxis::Unknown => TraceIdCode::Unknown,
+        };
+        Ok(())
+    }
 }
 
 /// Creates an empty instance of `TraceHeader`.
@@ -346,4 +551,12 @@ mod tests {
         assert_eq!(header.y_ensemble, 0);
         assert_eq!(header.coordinate_scalar, 0);
     }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_243), (2022, 9, 8));
+        assert_eq!(civil_from_days(20_742), (2026, 10, 16));
+    }
 }
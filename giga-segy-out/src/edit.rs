@@ -0,0 +1,450 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows the trace headers of an existing SEG-Y file to be edited, while
+//! leaving everything else untouched. The text header and some fields of the binary header
+//! can also be patched in place, without copying the file.
+//!
+//! It uses `giga_segy_in` to parse the file, and thus requires the `edit` feature.
+use giga_segy_core::enums::{AuxPolicy, OrderTraceBy};
+use giga_segy_core::errors::*;
+use giga_segy_core::progress::{ProgressPhase, SegyProgress, PROGRESS_INTERVAL};
+use giga_segy_core::TEXT_HEADER_LEN;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader, TRACE_HEADER_LEN};
+use giga_segy_in::SegyFile as InputSegyFile;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::write_headers::{th_as_bytes_with_settings, write_text_header};
+use crate::{open_output, EXTENDED_NO_TRACES_OFFSET, NO_TRACES_IN_FILE_OFFSET, NO_TRACES_OFFSET};
+
+/// The fields of the binary header which can be patched in place with
+/// [`patch_bin_header_field`]. These are only informative, so changing them cannot change how
+/// the rest of the file is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinField {
+    /// The job identification number (bytes 3201-3204).
+    JobId,
+    /// The line number (bytes 3205-3208).
+    LineNumber,
+    /// The reel number (bytes 3209-3212).
+    ReelNumber,
+}
+
+impl BinField {
+    /// Get the position of the field in the binary header.
+    fn offset(self) -> usize {
+        match self {
+            BinField::JobId => 0,
+            BinField::LineNumber => 4,
+            BinField::ReelNumber => 8,
+        }
+    }
+}
+
+/// Copy a SEG-Y file from `input` to `output`, passing every trace header through `f`
+/// along with the index of the trace.
+///
+/// The file is copied as a stream. Only the 240 byte trace headers are parsed, and only the
+/// fields which `f` changes are written over the bytes of the trace header. Everything else
+/// (the tape label, the text and binary headers, the extended headers, any trace header
+/// extensions, the trace data and the fields which are not changed) is copied byte for byte.
+/// Thus trace data in formats that cannot be written by this crate (eg. IBM floats) is
+/// preserved exactly.
+///
+/// The `settings` are used both for parsing and for writing the trace headers, so the byte
+/// indices of inline and crossline numbers and the coordinate format are kept consistent. The
+/// trace headers are given to `f` as they are stored, so the sort order, the auxiliary trace
+/// policy and the coordinate transform of the settings are ignored. Traces that are not read
+/// because of the settings (eg. because they are out of bounds) are copied as they are.
+///
+/// NB: The output file must not exist yet. If an error happens once it has been created, the
+/// partially written output is removed.
+/// ```
+/// # use std::env::var;
+/// # use std::path::PathBuf;
+/// use giga_segy_out::edit::rewrite_trace_headers;
+///
+/// # let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// # root.pop();
+/// let input = root.join("testdata").join("DutchMiniHead.sgy");
+/// let dir = tempfile::tempdir().unwrap();
+/// let output = dir.path().join("shifted.sgy");
+///
+/// rewrite_trace_headers(&input, &output, Default::default(), |_, mut header| {
+///     header.source_x += 1000;
+///     header
+/// })
+/// .unwrap();
+/// ```
+pub fn rewrite_trace_headers<P, Q, F>(
+    input: P,
+    output: Q,
+    settings: SegySettings,
+    mut f: F,
+) -> Result<(), RsgError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(usize, TraceHeader) -> TraceHeader,
+{
+    let input = input.as_ref();
+    let output = output.as_ref();
+    let segy = open_input(input, full_trace_settings(settings))?;
+
+    let mut file = open_output(output, false)?;
+    let result = rewrite_into(&segy, input, &mut file, &mut f);
+    remove_if_failed(result, output)
+}
+
+/// Copy `segy` (read from `input`) to `file`, with the trace headers passed through `f`.
+fn rewrite_into(
+    segy: &InputSegyFile,
+    input: &Path,
+    file: &mut File,
+    f: &mut dyn FnMut(usize, TraceHeader) -> TraceHeader,
+) -> Result<(), RsgError> {
+    let bin_header = segy.get_bin_header();
+    let settings = segy.get_settings();
+
+    // The trace headers are replaced in the order in which they are in the file.
+    let mut order = (0..segy.trace_count())
+        .map(|i| {
+            Ok((
+                segy.load_trace(i)?.extension_range().start - TRACE_HEADER_LEN,
+                i,
+            ))
+        })
+        .collect::<Result<Vec<_>, RsgError>>()?;
+    order.sort_unstable();
+
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(file);
+    let mut position = 0;
+    let mut header_bytes = vec![0; TRACE_HEADER_LEN];
+    for (header_start, i) in order {
+        // Copy everything up to the trace header, then replace the header itself.
+        copy_bytes(&mut reader, &mut writer, (header_start - position) as u64)?;
+        reader.read_exact(&mut header_bytes)?;
+
+        let old_header = segy.load_trace(i)?.get_header().clone();
+        let new_header = f(i, old_header.clone());
+        patch_trace_header(
+            &mut header_bytes,
+            &old_header,
+            &new_header,
+            settings,
+            bin_header,
+        )?;
+        writer.write_all(&header_bytes)?;
+        position = header_start + TRACE_HEADER_LEN;
+    }
+    // Whatever is left after the last trace header (including the data of the last trace).
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the fields of `new` which differ from `old` over the trace header bytes `raw`, from
+/// which `old` was parsed. Only the bytes whose encodings in `old` and `new` differ are
+/// changed, so the bytes which are not part of any field, and the values which were changed
+/// when they were read (eg. synthesized line numbers), are kept as they are in the file.
+pub(crate) fn patch_trace_header(
+    raw: &mut [u8],
+    old: &TraceHeader,
+    new: &TraceHeader,
+    settings: &SegySettings,
+    bin_header: &BinHeader,
+) -> Result<(), RsgError> {
+    if old == new {
+        return Ok(());
+    }
+    let old_bytes = th_as_bytes_with_settings(old, settings, bin_header)?;
+    let new_bytes = th_as_bytes_with_settings(new, settings, bin_header)?;
+    for ((byte, o), n) in raw.iter_mut().zip(old_bytes).zip(new_bytes) {
+        if o != n {
+            *byte = n;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the text header of an existing SEG-Y file in place, leaving the rest of the file
+/// untouched.
+///
+/// The new text header is written as it is (as with [`crate::SegyFile::create_file`]) and
+/// padded with spaces to 3200 bytes, after the tape label if there is one. It can be brought
+/// into card image form first with [`crate::text_header::normalize_text_header`]. The file is
+/// synced to the disk before returning.
+///
+/// An error is returned if the new text header is longer than 3200 bytes, or if the file
+/// cannot be read as SEG-Y with the default settings, in which case nothing is written.
+/// ```
+/// # use std::env::var;
+/// # use std::path::PathBuf;
+/// use giga_segy_out::edit::patch_text_header;
+///
+/// # let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// # root.pop();
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("copy.sgy");
+/// std::fs::copy(root.join("testdata").join("DutchMiniHead.sgy"), &path).unwrap();
+///
+/// patch_text_header(&path, "C 1 REPROCESSED 2026").unwrap();
+/// ```
+pub fn patch_text_header<P: AsRef<Path>>(path: P, new_header: &str) -> Result<(), RsgError> {
+    let path = path.as_ref();
+    if new_header.len() > TEXT_HEADER_LEN {
+        let msg = format!("Invalid TextHeader: Too long: {}", new_header.len());
+        return Err(RsgError::InvalidHeader { msg });
+    }
+    let text_header_offset = open_input(path, SegySettings::default())?.get_text_header_offset();
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(text_header_offset as u64))?;
+    write_text_header(new_header, &mut file)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Set a field of the binary header of an existing SEG-Y file in place, leaving the rest of
+/// the file untouched. Only the fields of [`BinField`] can be set, and the value is written in
+/// the byte order of the file. The file is synced to the disk before returning.
+///
+/// An error is returned if the file cannot be read as SEG-Y with the default settings, in
+/// which case nothing is written.
+/// ```
+/// # use std::env::var;
+/// # use std::path::PathBuf;
+/// use giga_segy_out::edit::{patch_bin_header_field, BinField};
+///
+/// # let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// # root.pop();
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("copy.sgy");
+/// std::fs::copy(root.join("testdata").join("DutchMiniHead.sgy"), &path).unwrap();
+///
+/// patch_bin_header_field(&path, BinField::ReelNumber, 7).unwrap();
+/// ```
+pub fn patch_bin_header_field<P: AsRef<Path>>(
+    path: P,
+    field: BinField,
+    value: u32,
+) -> Result<(), RsgError> {
+    let path = path.as_ref();
+    let (text_header_offset, le) = {
+        let segy = open_input(path, SegySettings::default())?;
+        (
+            segy.get_text_header_offset(),
+            segy.get_bin_header().binary_flag_direction_is_le,
+        )
+    };
+    let bytes = if le {
+        value.to_le_bytes()
+    } else {
+        value.to_be_bytes()
+    };
+
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    file.seek(SeekFrom::Start(
+        (text_header_offset + TEXT_HEADER_LEN + field.offset()) as u64,
+    ))?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Open a file with `giga_segy_in`.
+pub(crate) fn open_input(path: &Path, settings: SegySettings) -> Result<InputSegyFile, RsgError> {
+    let name = path.to_str().ok_or_else(|| {
+        let msg = format!("Input path is not valid unicode: {:?}", path);
+        RsgError::StdIoError(io::Error::new(io::ErrorKind::InvalidInput, msg))
+    })?;
+    InputSegyFile::open(name, settings)
+}
+
+/// Change the settings used to read a file so that the traces are read as they are stored: In
+/// the order of the file, all of them (including the auxiliary traces), with every sample (ie.
+/// `step_by` is one), without the override of the sample count, without a coordinate transform
+/// and without anything that changes the samples when they are read (the gain, the trace
+/// weighting and the swapped byte order of the data). Then the data of a trace can be copied
+/// together with its header.
+pub(crate) fn full_trace_settings(mut settings: SegySettings) -> SegySettings {
+    settings.set_step_by(1);
+    settings.clear_override_dim_z();
+    settings.set_sort_traces_by(OrderTraceBy::Default);
+    settings.set_coordinate_transform(None);
+    settings.set_aux_trace_policy(AuxPolicy::Include);
+    settings.set_user_gain(1.0);
+    settings.set_apply_trace_weighting(false);
+    settings.set_swap_data_byte_order(false);
+    settings
+}
+
+/// Get the length of everything that comes before the first trace header, which is to say the
+/// tape label, the text header, the binary header and the extended headers (and anything
+/// before the text header or between these and the first trace, if the file has it).
+pub(crate) fn head_len(segy: &InputSegyFile) -> usize {
+    let ext_count = segy.get_extended_headers().len();
+    segy.get_text_header_offset() + segy.get_bin_header().get_first_trace_offset(ext_count)
+}
+
+/// Overwrite a two byte field of the binary header in a file which was copied from `segy`.
+/// The `offset` is the position of the field in the binary header (eg. 12 for `no_traces`).
+pub(crate) fn patch_bin_header_u16(
+    file: &mut File,
+    segy: &InputSegyFile,
+    offset: usize,
+    value: u16,
+) -> Result<(), RsgError> {
+    let bytes = if segy.get_bin_header().binary_flag_direction_is_le {
+        value.to_le_bytes()
+    } else {
+        value.to_be_bytes()
+    };
+    patch_bin_header_bytes(file, segy, offset, &bytes)
+}
+
+/// Overwrite a four byte field of the binary header in a file which was copied from `segy`, in
+/// the byte order of `segy`.
+pub(crate) fn patch_bin_header_u32(
+    file: &mut File,
+    segy: &InputSegyFile,
+    offset: usize,
+    value: u32,
+) -> Result<(), RsgError> {
+    let bytes = if segy.get_bin_header().binary_flag_direction_is_le {
+        value.to_le_bytes()
+    } else {
+        value.to_be_bytes()
+    };
+    patch_bin_header_bytes(file, segy, offset, &bytes)
+}
+
+/// Overwrite the number of traces in the binary header of a file which was copied from `segy`
+/// (see [`crate::trace_count_fields`]). As in [`crate::SegyFile::finalize`], the number of
+/// traces in the file is only written for SEG-Y revision 2 or later.
+pub(crate) fn patch_trace_count(
+    file: &mut File,
+    segy: &InputSegyFile,
+    count: usize,
+) -> Result<(), RsgError> {
+    let (no_traces, extended, in_file) = crate::trace_count_fields(count)?;
+    patch_bin_header_u16(file, segy, NO_TRACES_OFFSET, no_traces)?;
+    patch_bin_header_u32(file, segy, EXTENDED_NO_TRACES_OFFSET, extended)?;
+    if segy.get_bin_header().segy_revision_number[0] < 2 {
+        return Ok(());
+    }
+    let bytes = if segy.get_bin_header().binary_flag_direction_is_le {
+        in_file.to_le_bytes()
+    } else {
+        in_file.to_be_bytes()
+    };
+    patch_bin_header_bytes(file, segy, NO_TRACES_IN_FILE_OFFSET, &bytes)
+}
+
+/// Overwrite bytes of the binary header in a file which was copied from `segy`, starting at
+/// `offset` in the binary header.
+pub(crate) fn patch_bin_header_bytes(
+    file: &mut File,
+    segy: &InputSegyFile,
+    offset: usize,
+    bytes: &[u8],
+) -> Result<(), RsgError> {
+    file.seek(SeekFrom::Start(
+        (segy.get_text_header_offset() + TEXT_HEADER_LEN + offset) as u64,
+    ))?;
+    file.write_all(bytes)?;
+    Ok(())
+}
+
+/// Keeps track of how many traces have been written by one of the editing utilities and
+/// reports it to a progress callback every [`PROGRESS_INTERVAL`] traces.
+pub(crate) struct WriteProgress<'a> {
+    progress: &'a mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+    traces_total: usize,
+    traces_done: usize,
+    bytes_done: usize,
+    next_report: usize,
+}
+
+impl<'a> WriteProgress<'a> {
+    pub(crate) fn new(
+        progress: &'a mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+        traces_total: usize,
+    ) -> Self {
+        WriteProgress {
+            progress,
+            traces_total,
+            traces_done: 0,
+            bytes_done: 0,
+            next_report: PROGRESS_INTERVAL,
+        }
+    }
+
+    /// Count a trace of `bytes` bytes (including its headers) which has been written.
+    pub(crate) fn trace_written(&mut self, bytes: usize) -> Result<(), RsgError> {
+        self.traces_done += 1;
+        self.bytes_done += bytes;
+        if self.traces_done >= self.next_report {
+            self.next_report += PROGRESS_INTERVAL;
+            self.report()?;
+        }
+        Ok(())
+    }
+
+    /// Report the current progress.
+    pub(crate) fn report(&mut self) -> Result<(), RsgError> {
+        let report = SegyProgress {
+            phase: ProgressPhase::Writing,
+            traces_done: self.traces_done,
+            traces_total: Some(self.traces_total),
+            bytes_done: self.bytes_done,
+            bytes_total: None,
+        };
+        match (self.progress)(report) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(RsgError::Cancelled),
+        }
+    }
+}
+
+/// If an operation was cancelled, remove the partially written output.
+pub(crate) fn remove_if_cancelled<T>(
+    result: Result<T, RsgError>,
+    output: &Path,
+) -> Result<T, RsgError> {
+    if let Err(RsgError::Cancelled) = result {
+        std::fs::remove_file(output)?;
+    }
+    result
+}
+
+/// If an operation failed after the output was created, remove the partially written output.
+/// The error of the operation is returned even if the output can not be removed.
+pub(crate) fn remove_if_failed<T>(
+    result: Result<T, RsgError>,
+    output: &Path,
+) -> Result<T, RsgError> {
+    if result.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+/// Copy exactly `len` bytes from the reader to the writer.
+pub(crate) fn copy_bytes<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    len: u64,
+) -> Result<(), RsgError> {
+    let copied = io::copy(&mut reader.take(len), writer)?;
+    if copied != len {
+        return Err(RsgError::ShortSEGY {
+            a: copied as usize,
+            b: len as usize,
+        });
+    }
+    Ok(())
+}
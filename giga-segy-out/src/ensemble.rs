@@ -0,0 +1,173 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`EnsembleWriter`], which helps with writing pre-stack data
+//! ensemble by ensemble (eg. CDP gathers), while keeping the ensemble numbering consistent.
+use giga_segy_core::errors::*;
+use giga_segy_core::{Trace, TraceHeader};
+use num::ToPrimitive;
+
+use std::fmt::Debug;
+
+use crate::settings::SegyWriteSettings;
+use crate::write_data::LosslessWriteableSegyData;
+use crate::SegyFile;
+
+/// The position of `ensemble_fold` in the binary header.
+const ENSEMBLE_FOLD_OFFSET: usize = 26;
+
+/// The ensemble which is currently being written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpenEnsemble {
+    ensemble_no: i32,
+    x: i32,
+    y: i32,
+    fold: u16,
+}
+
+/// A wrapper around a [`SegyFile`] which writes traces ensemble by ensemble.
+///
+/// Each trace that is added gets the `ensemble_no` and the ensemble coordinates of the
+/// current ensemble, and `trace_no_in_ensemble` is counted up from one. When the writer is
+/// finalized, the largest fold of all ensembles is written to `ensemble_fold` in the binary
+/// header.
+/// ```
+/// use giga_segy_out::ensemble::EnsembleWriter;
+/// use giga_segy_out::SegyFile;
+/// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+/// use giga_segy_core::enums::*;
+/// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+///
+/// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+/// let path = dir.path().join("gathers.sgy");
+/// let bin_header = BinHeader::new(0, 4000, 50, SampleFormatCode::Float32);
+///
+/// let mut file = SegyFile::<SegySettings>::create_file(
+///     path,
+///     Default::default(),
+///     "C 1 CDP gathers".to_string(),
+///     bin_header,
+///     None,
+/// ).unwrap();
+///
+/// let mut writer = EnsembleWriter::new(&mut file);
+/// for cdp in 1..=3 {
+///     writer.start_ensemble(cdp, cdp * 25, 0);
+///     for offset in 0..cdp {
+///         let mut header = TraceHeader::new_2d(0, 0, 1);
+///         header.source_to_receiver_distance = offset * 100;
+///         writer.add_trace_to_ensemble(header, vec![0f32; 50]).unwrap();
+///     }
+/// }
+/// assert_eq!(writer.finalize().unwrap(), 3);
+/// assert_eq!(file.metadata.get_bin_header().ensemble_fold, 3);
+/// ```
+pub struct EnsembleWriter<'a, S: SegyWriteSettings> {
+    segy: &'a mut SegyFile<S>,
+    current: Option<OpenEnsemble>,
+    max_fold: u16,
+}
+
+impl<'a, S: SegyWriteSettings> EnsembleWriter<'a, S> {
+    /// Create a new writer, which adds traces to the end of `segy`.
+    pub fn new(segy: &'a mut SegyFile<S>) -> Self {
+        EnsembleWriter {
+            segy,
+            current: None,
+            max_fold: 0,
+        }
+    }
+
+    /// Start a new ensemble with the given number and ensemble coordinates. If an ensemble is
+    /// still open, it is finished first.
+    pub fn start_ensemble(&mut self, ensemble_no: i32, x: i32, y: i32) {
+        self.finish_ensemble();
+        self.current = Some(OpenEnsemble {
+            ensemble_no,
+            x,
+            y,
+            fold: 0,
+        });
+    }
+
+    /// Add a trace to the current ensemble, converting the data like [`SegyFile::add_trace`].
+    ///
+    /// The `ensemble_no`, `trace_no_in_ensemble`, `x_ensemble` and `y_ensemble` of the header
+    /// are overwritten, while all other fields are kept as they are. An error is returned if
+    /// no ensemble has been started.
+    pub fn add_trace_to_ensemble<T: ToPrimitive + Debug>(
+        &mut self,
+        header_template: TraceHeader,
+        data: Vec<T>,
+    ) -> Result<&Trace, RsgError> {
+        let header = self.next_header(header_template)?;
+        let trace = self.segy.add_trace(header, None, data)?;
+        Self::count_trace(&mut self.current);
+        Ok(trace)
+    }
+
+    /// Add a trace to the current ensemble without lossy conversion of the data, like
+    /// [`SegyFile::add_trace_lossless`]. Otherwise this works in the same way as
+    /// [`EnsembleWriter::add_trace_to_ensemble`].
+    pub fn add_trace_to_ensemble_lossless<T: LosslessWriteableSegyData>(
+        &mut self,
+        header_template: TraceHeader,
+        data: Vec<T>,
+    ) -> Result<&Trace, RsgError> {
+        let header = self.next_header(header_template)?;
+        let trace = self.segy.add_trace_lossless(header, None, data)?;
+        Self::count_trace(&mut self.current);
+        Ok(trace)
+    }
+
+    /// Finish the current ensemble and return its fold, or `None` if no ensemble is open.
+    ///
+    /// NB: Nothing is written to the file here, since the trace headers are already complete.
+    pub fn finish_ensemble(&mut self) -> Option<u16> {
+        let finished = self.current.take()?;
+        self.max_fold = self.max_fold.max(finished.fold);
+        Some(finished.fold)
+    }
+
+    /// Get the largest fold of the ensembles that have been finished so far.
+    pub fn max_fold(&self) -> u16 {
+        self.max_fold
+    }
+
+    /// Finish the current ensemble and write the largest fold of all ensembles to the binary
+    /// header (both in the file and in the metadata of the [`SegyFile`]). The largest fold is
+    /// returned.
+    pub fn finalize(mut self) -> Result<u16, RsgError> {
+        self.finish_ensemble();
+        let fold = self.max_fold;
+
+        let bin_header = &mut self.segy.metadata.bin_header;
+        bin_header.ensemble_fold = fold;
+        let bytes = if bin_header.binary_flag_direction_is_le {
+            fold.to_le_bytes()
+        } else {
+            fold.to_be_bytes()
+        };
+        self.segy
+            .patch_bin_header_bytes(ENSEMBLE_FOLD_OFFSET, &bytes)?;
+        Ok(fold)
+    }
+
+    /// Fill in the ensemble fields of a trace header for the next trace of the current
+    /// ensemble.
+    fn next_header(&self, mut header: TraceHeader) -> Result<TraceHeader, RsgError> {
+        let current = self.current.ok_or_else(|| RsgError::SEGYSettingsError {
+            msg: "A trace cannot be added before an ensemble is started".to_string(),
+        })?;
+        header.ensemble_no = current.ensemble_no;
+        header.trace_no_in_ensemble = current.fold as i32 + 1;
+        header.x_ensemble = current.x;
+        header.y_ensemble = current.y;
+        Ok(header)
+    }
+
+    /// Count a trace which has been written to the current ensemble.
+    fn count_trace(current: &mut Option<OpenEnsemble>) {
+        if let Some(ref mut c) = current {
+            c.fold = c.fold.saturating_add(1);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that a file which is created atomically only appears under its final name
+//! once it is finalized.
+use super::{numbered_traces, write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::{partial_path, DurabilityOptions, SegyFile};
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Create a file atomically and add `count` traces of ten samples to it.
+fn write_atomic(
+    path: &Path,
+    durability: DurabilityOptions,
+    count: usize,
+) -> Result<SegyFile<SegySettings>, RsgError> {
+    write_fixture(
+        path,
+        CreateMode::Atomic(durability),
+        "C 1 A file which is written atomically.",
+        SegySettings::default(),
+        BinHeader::new(0, 4000, 10, SampleFormatCode::Float32),
+        numbered_traces(count),
+    )
+}
+
+#[test]
+fn atomic_drop_without_finalize() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("killed.sgy");
+    let file = write_atomic(&path, DurabilityOptions::default(), 3).unwrap();
+    assert!(!path.exists());
+    drop(file);
+    assert!(!path.exists());
+    assert!(partial_path(&path).exists());
+
+    let durability = DurabilityOptions {
+        delete_partial_on_drop: true,
+        ..Default::default()
+    };
+    let path = dir.path().join("cleaned_up.sgy");
+    let file = write_atomic(&path, durability, 3).unwrap();
+    assert!(partial_path(&path).exists());
+    drop(file);
+    assert!(!path.exists());
+    assert!(!partial_path(&path).exists());
+}
+
+#[test]
+fn atomic_finalize() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("finished.sgy");
+    let durability = DurabilityOptions {
+        sync_on_finalize: true,
+        delete_partial_on_drop: true,
+    };
+    let mut file = write_atomic(&path, durability, 3).unwrap();
+    assert_eq!(file.finalize().unwrap(), 3);
+    assert!(path.exists());
+    assert!(!partial_path(&path).exists());
+
+    // Traces added after finalizing go to the final file, which is not deleted on drop.
+    file.add_trace(TraceHeader::new_3d(0, 0, 1, 3, 1), None, vec![3f32; 10])
+        .unwrap();
+    assert_eq!(file.finalize().unwrap(), 4);
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 4);
+    assert_eq!(segy.get_bin_header().no_traces, 4);
+    for i in 0..4 {
+        assert_eq!(segy.get_trace_data_as_f32(i).unwrap(), vec![i as f32; 10]);
+    }
+}
+
+#[test]
+fn atomic_existing_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("existing.sgy");
+    std::fs::write(&path, b"not a SEG-Y file").unwrap();
+    let e = write_atomic(&path, DurabilityOptions::default(), 0)
+        .err()
+        .unwrap();
+    assert!(matches!(e, RsgError::OutputExists { path: ref p } if p == &path));
+    assert!(!partial_path(&path).exists());
+    assert_eq!(std::fs::read(&path).unwrap(), b"not a SEG-Y file");
+
+    // A partial file which is left over is not overwritten either.
+    let path = dir.path().join("left_over.sgy");
+    drop(write_atomic(&path, DurabilityOptions::default(), 1).unwrap());
+    assert!(write_atomic(&path, DurabilityOptions::default(), 0).is_err());
+    assert!(!path.exists());
+}
@@ -0,0 +1,264 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we rewrite the trace headers of an existing file (or patch its text and binary
+//! headers in place) and check that nothing else has changed.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::edit::{patch_bin_header_field, patch_text_header, rewrite_trace_headers, BinField};
+use crate::merge::{merge_files, MergeOptions};
+use giga_segy_core::enums::{AuxPolicy, OrderTraceBy, SampleFormatCode, TraceIdCode};
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{AffineTransform, BinHeader, SegySettings, TraceHeader, TAPE_LABEL_LEN};
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+const TEST_FILE: &str = "../testdata/DutchMiniHead.sgy";
+
+#[test]
+fn rewrite_inline_numbers() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("patched.sgy");
+
+    rewrite_trace_headers(
+        TEST_FILE,
+        &path,
+        SegySettings::default(),
+        |i, mut header| {
+            header.inline_no += 1000 + i as i32;
+            header
+        },
+    )
+    .expect("Could not rewrite headers");
+
+    let original = std::fs::read(TEST_FILE).unwrap();
+    let patched = std::fs::read(&path).unwrap();
+    assert_eq!(original.len(), patched.len());
+
+    let in_original =
+        giga_segy_in::SegyFile::open(TEST_FILE, SegySettings::default()).expect("Could not open");
+    let in_patched = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+
+    // The tape label, text header and binary header are untouched.
+    let first_trace = in_original.get_trace(0).unwrap().get_start() - 240;
+    assert_eq!(original[..first_trace], patched[..first_trace]);
+    assert_eq!(in_original.get_bin_header(), in_patched.get_bin_header());
+    assert_eq!(in_original.trace_count(), in_patched.trace_count());
+
+    for (i, (o, p)) in in_original
+        .traces_iter()
+        .zip(in_patched.traces_iter())
+        .enumerate()
+    {
+        // The trace data is byte-identical.
+        let data = o.get_start()..(o.get_start() + o.len());
+        assert_eq!(o.get_start(), p.get_start());
+        assert_eq!(original[data.clone()], patched[data]);
+
+        // Only the inline number has changed in the trace header.
+        let mut expected = o.get_header().clone();
+        expected.inline_no += 1000 + i as i32;
+        assert_eq!(p.get_header(), &expected);
+    }
+}
+
+/// Write a file whose traces are numbered backwards, with an auxiliary trace and a trace
+/// weighting factor, so that the settings of [`reading_settings`] change how it is read.
+fn write_shot(path: &Path) {
+    let traces = (0..6).map(|i| {
+        let mut header = TraceHeader::new_3d(1000 + i, 2000 - i, 1, i, -10);
+        header.no_samples_in_trace = 8;
+        header.trace_no = 6 - i;
+        header.trace_weighting_factor = 2;
+        if i == 2 {
+            header.trace_identification_code = TraceIdCode::TimeBreak;
+        }
+        let data = (0..8).map(|s| (i * 100 + s) as i16).collect::<Vec<_>>();
+        (header, data)
+    });
+    let mut file = write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A shot to be copied as it is.",
+        SegySettings::default(),
+        BinHeader::new(6, 4000, 8, SampleFormatCode::Int16),
+        traces,
+    )
+    .expect("Could not create file.");
+    file.finalize().expect("Could not finalize.");
+}
+
+/// Settings which change the order, the number, the coordinates and the samples of the traces
+/// when they are read.
+fn reading_settings() -> SegySettings {
+    let mut settings = SegySettings::default();
+    settings.set_sort_traces_by(OrderTraceBy::TraceNo);
+    settings.set_coordinate_transform(Some(AffineTransform::translation(500_000., 0.)));
+    settings.set_aux_trace_policy(AuxPolicy::Exclude);
+    settings.set_user_gain(2.);
+    settings.set_apply_trace_weighting(true);
+    settings.set_swap_data_byte_order(true);
+    settings.set_step_by(2);
+    settings
+}
+
+#[test]
+fn copies_ignore_reading_settings() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("shot.sgy");
+    write_shot(&input);
+    let original = std::fs::read(&input).unwrap();
+
+    let rewritten = dir.path().join("rewritten.sgy");
+    rewrite_trace_headers(&input, &rewritten, reading_settings(), |_, h| h).unwrap();
+    assert_eq!(std::fs::read(&rewritten).unwrap(), original);
+
+    let merged = dir.path().join("merged.sgy");
+    let opts = MergeOptions {
+        settings: reading_settings(),
+        ..Default::default()
+    };
+    let report = merge_files(&[&input], &merged, opts).unwrap();
+    assert_eq!(report.trace_counts, vec![6]);
+    assert_eq!(std::fs::read(&merged).unwrap(), original);
+}
+
+#[test]
+fn rewrite_keeps_values_which_were_changed_when_read() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("shot.sgy");
+    let output = dir.path().join("rewritten.sgy");
+    write_shot(&input);
+
+    // The line numbers are synthesized from the index of the trace.
+    let mut settings = SegySettings::default();
+    settings.set_override_dim_x(3).unwrap();
+    rewrite_trace_headers(&input, &output, settings, |_, mut header| {
+        header.source_x += 7;
+        header
+    })
+    .unwrap();
+
+    let original = giga_segy_in::SegyFile::open(input.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    let rewritten = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(rewritten.trace_count(), 6);
+    for (o, r) in original.traces_iter().zip(rewritten.traces_iter()) {
+        let mut expected = o.get_header().clone();
+        expected.source_x += 7;
+        assert_eq!(r.get_header(), &expected);
+    }
+}
+
+#[test]
+fn rewrite_removes_output_on_error() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("shot.sgy");
+    let output = dir.path().join("rewritten.sgy");
+    write_shot(&input);
+
+    let result = rewrite_trace_headers(&input, &output, SegySettings::default(), |i, mut h| {
+        if i == 3 {
+            h.trace_identification_code = TraceIdCode::Invalid;
+        }
+        h
+    });
+    assert!(matches!(result, Err(RsgError::HeaderFieldEncode { .. })));
+    assert!(!output.exists());
+}
+
+/// Copy the test file to `path`, and get the position of its text header.
+fn copy_test_file(path: &Path) -> usize {
+    std::fs::copy(TEST_FILE, path).expect("Could not copy the test file.");
+    let segy = giga_segy_in::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+    segy.get_tape_label().as_ref().map_or(0, |_| TAPE_LABEL_LEN)
+}
+
+/// Hash a file, leaving out the bytes in `skip`.
+fn hash_except(path: &Path, skip: std::ops::Range<usize>) -> u64 {
+    let bytes = std::fs::read(path).unwrap();
+    let mut hasher = DefaultHasher::new();
+    bytes[..skip.start].hash(&mut hasher);
+    bytes[skip.end..].hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn patch_text_header_in_place() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("patched.sgy");
+    let start = copy_test_file(&path);
+    let text_header = start..(start + 3200);
+    let before = hash_except(&path, text_header.clone());
+
+    let new_header = format!("{:<80}{:<80}", "C 1 REPROCESSED", "C 2 WITH A NEW GAIN");
+    patch_text_header(&path, &new_header).expect("Could not patch the text header.");
+    assert_eq!(hash_except(&path, text_header.clone()), before);
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().len(),
+        std::fs::metadata(TEST_FILE).unwrap().len()
+    );
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(
+        segy.text_header_line(1).unwrap().trim_end(),
+        "C 1 REPROCESSED"
+    );
+    assert_eq!(
+        segy.text_header_line(2).unwrap().trim_end(),
+        "C 2 WITH A NEW GAIN"
+    );
+    assert!(segy.get_text_header()[160..].trim().is_empty());
+
+    // A text header which is too long is not written.
+    let written = std::fs::read(&path).unwrap();
+    assert!(matches!(
+        patch_text_header(&path, &"C".repeat(3201)),
+        Err(RsgError::InvalidHeader { .. })
+    ));
+    assert_eq!(std::fs::read(&path).unwrap(), written);
+}
+
+#[test]
+fn patch_bin_header_field_in_place() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("patched.sgy");
+    let start = copy_test_file(&path) + 3200;
+    let original = giga_segy_in::SegyFile::open(TEST_FILE, SegySettings::default()).unwrap();
+
+    for (field, offset, value) in [
+        (BinField::JobId, 0, 17),
+        (BinField::LineNumber, 4, 4242),
+        (BinField::ReelNumber, 8, u32::MAX),
+    ]
+    .iter()
+    {
+        let range = (start + offset)..(start + offset + 4);
+        let before = hash_except(&path, range.clone());
+        patch_bin_header_field(&path, *field, *value).expect("Could not patch the field.");
+        assert_eq!(hash_except(&path, range), before);
+    }
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    let mut expected = original.get_bin_header().clone();
+    expected.job_id = 17;
+    expected.line_number = 4242;
+    expected.reel_number = u32::MAX;
+    assert_eq!(segy.get_bin_header(), &expected);
+}
+
+#[test]
+fn patch_refuses_non_segy() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("notes.txt");
+    let contents = b"Not a SEG-Y file at all.".repeat(200);
+    std::fs::write(&path, &contents).unwrap();
+
+    assert!(patch_text_header(&path, "C 1 HELLO").is_err());
+    assert!(patch_bin_header_field(&path, BinField::JobId, 1).is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), contents);
+}
@@ -0,0 +1,137 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we write pre-stack data with the `EnsembleWriter` and check the ensemble numbering
+//! with `giga_segy_in`.
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::ensemble::EnsembleWriter;
+use crate::SegyFile;
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+
+#[test]
+fn ensembles_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("gathers.sgy");
+    let bin_header = BinHeader::new(0, 4000, 20, SampleFormatCode::Int16);
+    let mut file = SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 Three CDP gathers.".to_string(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+
+    let folds = [(101, 3), (102, 5), (103, 2)];
+    let mut writer = EnsembleWriter::new(&mut file);
+    for (cdp, fold) in folds.iter() {
+        writer.start_ensemble(*cdp, cdp * 10, cdp * 20);
+        for offset in 0..*fold {
+            let mut header = TraceHeader::new_2d(0, 0, 1);
+            header.source_to_receiver_distance = offset * 50;
+            header.no_samples_in_trace = 20;
+            // Values in the template are overwritten.
+            header.trace_no_in_ensemble = 99;
+            if offset % 2 == 0 {
+                writer
+                    .add_trace_to_ensemble(header, vec![*cdp as f32; 20])
+                    .unwrap();
+            } else {
+                writer
+                    .add_trace_to_ensemble_lossless(header, vec![*cdp as i16; 20])
+                    .unwrap();
+            }
+        }
+    }
+    // The last ensemble is still open, so it is not counted yet.
+    assert_eq!(writer.max_fold(), 5);
+    assert_eq!(writer.finalize().unwrap(), 5);
+
+    // Traces can still be added afterwards.
+    let mut header = TraceHeader::new_2d(0, 0, 1);
+    header.ensemble_no = 104;
+    header.no_samples_in_trace = 20;
+    file.add_trace(header, None, vec![0f32; 20]).unwrap();
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+    assert_eq!(segy.get_bin_header().ensemble_fold, 5);
+    assert_eq!(segy.trace_count(), 11);
+
+    let mut traces = segy.traces_iter();
+    for (cdp, fold) in folds.iter() {
+        for offset in 0..*fold {
+            let trace = traces.next().unwrap();
+            let header = trace.get_header();
+            assert_eq!(header.ensemble_no, *cdp);
+            assert_eq!(header.trace_no_in_ensemble, offset + 1);
+            assert_eq!(header.x_ensemble, cdp * 10);
+            assert_eq!(header.y_ensemble, cdp * 20);
+            assert_eq!(header.source_to_receiver_distance, offset * 50);
+            let data = segy.get_trace_data_as_f32_from_trace(&trace).unwrap();
+            assert_eq!(data, vec![*cdp as f32; 20]);
+        }
+    }
+    assert_eq!(traces.next().unwrap().get_header().ensemble_no, 104);
+}
+
+#[test]
+fn ensemble_fold_in_raw_bin_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("raw.sgy");
+    let bin_header = BinHeader::new(0, 4000, 2, SampleFormatCode::Float32);
+    let mut file = SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        String::new(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+    file.set_raw_bin_header([0; 400]).unwrap();
+
+    let mut writer = EnsembleWriter::new(&mut file);
+    writer.start_ensemble(1, 0, 0);
+    for _ in 0..3 {
+        let header = TraceHeader::new_2d(0, 0, 1);
+        writer.add_trace_to_ensemble(header, vec![0f32; 2]).unwrap();
+    }
+    assert_eq!(writer.finalize().unwrap(), 3);
+
+    // The file and the raw binary header agree.
+    let raw = file.metadata.get_raw_bin_header().unwrap();
+    assert_eq!(raw[26..28], [0, 3]);
+    drop(file);
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[3226..3228], [0, 3]);
+}
+
+#[test]
+fn ensemble_must_be_started() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("no_ensemble.sgy");
+    let bin_header = BinHeader::new(0, 4000, 20, SampleFormatCode::Float32);
+    let mut file = SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        String::new(),
+        bin_header,
+        None,
+    )
+    .unwrap();
+
+    let mut writer = EnsembleWriter::new(&mut file);
+    let header = TraceHeader::new_2d(0, 0, 1);
+    let err = writer
+        .add_trace_to_ensemble(header, vec![0f32; 20])
+        .unwrap_err();
+    assert!(matches!(err, RsgError::SEGYSettingsError { .. }));
+    assert_eq!(writer.finish_ensemble(), None);
+
+    writer.start_ensemble(1, 0, 0);
+    writer
+        .add_trace_to_ensemble(TraceHeader::new_2d(0, 0, 1), vec![0f32; 20])
+        .unwrap();
+    assert_eq!(writer.finish_ensemble(), Some(1));
+}
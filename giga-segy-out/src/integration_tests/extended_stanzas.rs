@@ -0,0 +1,141 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we write the stanzas of extended text headers to a file and check that they are
+//! read back by `giga_segy_in`, including the record of the format of the coordinates.
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::{MetadataOverrides, SegyFile};
+
+use giga_segy_core::enums::*;
+use giga_segy_core::stanza::Stanza;
+use giga_segy_core::{BinHeader, SegyMetadata, SegySettings, TraceHeader};
+
+#[test]
+fn extended_stanzas_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stanzas.sgy");
+
+    // The first stanza does not fit into one extended header.
+    let mut location = Stanza::new("SEG: Location Data ver 1.0");
+    for i in 0..45 {
+        location
+            .entries
+            .push((format!("Node {}", i), format!("{} {}", i * 25, i * 50)));
+    }
+    let mut notes = Stanza::new("Company: Acquisition Notes");
+    notes
+        .entries
+        .push(("Vessel".to_string(), "Research Vessel".to_string()));
+
+    let mut extended_headers = vec![format!("{:<3200}", "C 1 Free-form notes.")];
+    extended_headers.extend(location.to_header_records());
+    extended_headers.extend(notes.to_header_records());
+    extended_headers.extend(Stanza::new("SEG: EndText").to_header_records());
+    assert_eq!(extended_headers.len(), 5);
+
+    let metadata = SegyMetadata::new(
+        None,
+        "C 1 A file with extended headers.".to_string(),
+        extended_headers,
+        BinHeader::new(1, 4000, 3, SampleFormatCode::Float32),
+        SegySettings::default(),
+    );
+    let mut file =
+        SegyFile::create_file_from_metadata(&path, metadata, MetadataOverrides::default()).unwrap();
+    file.add_trace(
+        TraceHeader::new_3d(0, 0, 1, 1, 1),
+        None,
+        vec![1.0f32, 2.0, 3.0],
+    )
+    .unwrap();
+    drop(file);
+
+    let input = giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+    assert_eq!(input.get_extended_headers().len(), 5);
+    assert_eq!(input.extended_stanzas(), vec![location, notes]);
+    assert_eq!(input.get_trace_data_as_f32(0).unwrap(), vec![1.0, 2.0, 3.0]);
+}
+
+/// Write a file with two traces, with the given coordinate format and extended headers.
+fn write_with_coordinates(
+    path: &std::path::Path,
+    format: Option<SampleFormatCode>,
+    extended_headers: Vec<String>,
+) -> SampleFormatCode {
+    let mut settings = SegySettings::default();
+    if let Some(format) = format {
+        settings.set_override_coordinate_format(format).unwrap();
+    }
+    let metadata = SegyMetadata::new(
+        None,
+        "C 1 A file with float coordinates.".to_string(),
+        extended_headers,
+        BinHeader::new(2, 4000, 3, SampleFormatCode::Float32),
+        settings,
+    );
+    let mut file =
+        SegyFile::create_file_from_metadata(path, metadata, MetadataOverrides::default()).unwrap();
+    for i in 0..2 {
+        let header = TraceHeader::new_3d(123_456 + i, 654_321 - i, 1, i, 1);
+        file.add_trace(header, None, vec![1.0f32, 2.0, 3.0])
+            .unwrap();
+    }
+    file.coordinate_encoding()
+}
+
+#[test]
+fn coordinate_format_is_detected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("float_coordinates.sgy");
+    let end = Stanza::new("SEG: EndText").to_header_records();
+    let encoding = write_with_coordinates(&path, Some(SampleFormatCode::Float32), end.clone());
+    assert_eq!(encoding, SampleFormatCode::Float32);
+
+    // The note comes before the end of the stanzas.
+    let input = giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+    assert_eq!(input.get_bin_header().extended_header_count, 2);
+    assert_eq!(input.get_extended_headers()[1], end[0]);
+    assert_eq!(input.coordinate_encoding(), SampleFormatCode::Float32);
+    for i in 0..2 {
+        let trace = input.get_trace(i as usize).unwrap();
+        let header = trace.get_header();
+        assert_eq!(header.x_ensemble, 123_456 + i);
+        assert_eq!(header.y_ensemble, 654_321 - i);
+    }
+
+    // Settings which give the format take precedence.
+    let mut settings = SegySettings::default();
+    settings
+        .set_override_coordinate_format(SampleFormatCode::Int32)
+        .unwrap();
+    let input = giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    assert_eq!(input.coordinate_encoding(), SampleFormatCode::Int32);
+    assert_ne!(input.get_trace(0).unwrap().get_header().x_ensemble, 123_456);
+
+    // A file which is derived from this one does not get a second note.
+    let derived = dir.path().join("derived.sgy");
+    let headers = input.get_extended_headers().to_vec();
+    write_with_coordinates(&derived, Some(SampleFormatCode::UInt32), headers);
+    let input =
+        giga_segy_in::SegyFile::open(derived.to_str().unwrap(), Default::default()).unwrap();
+    assert_eq!(input.get_extended_headers().len(), 2);
+    assert_eq!(input.coordinate_encoding(), SampleFormatCode::UInt32);
+    assert_eq!(input.get_trace(1).unwrap().get_header().x_ensemble, 123_457);
+}
+
+#[test]
+fn default_coordinate_format_is_not_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("int_coordinates.sgy");
+    for format in [None, Some(SampleFormatCode::Int32)] {
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            write_with_coordinates(&path, format, vec![]),
+            SampleFormatCode::Int32
+        );
+        let input =
+            giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+        assert!(input.get_extended_headers().is_empty());
+        assert_eq!(input.get_bin_header().extended_header_count, 0);
+        assert_eq!(input.coordinate_encoding(), SampleFormatCode::Int32);
+        assert_eq!(input.get_trace(0).unwrap().get_header().x_ensemble, 123_456);
+    }
+}
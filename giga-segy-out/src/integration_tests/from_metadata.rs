@@ -0,0 +1,123 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we read a file, create a derived file from its metadata and check that the headers
+//! are the same, except for those that were overridden.
+use crate::{MetadataOverrides, SegyFile};
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::SegySettings;
+
+const TEST_FILE: &str = "../testdata/DutchMiniHead.sgy";
+
+#[test]
+fn create_derived_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("derived.sgy");
+
+    let original =
+        giga_segy_in::SegyFile::open(TEST_FILE, SegySettings::default()).expect("Could not open");
+    let traces = [3, 7]
+        .iter()
+        .map(|i| {
+            let header = original.get_trace(*i).unwrap().get_header().clone();
+            let data = original.get_trace_data_as_f32(*i).unwrap();
+            (header, data)
+        })
+        .collect::<Vec<_>>();
+    let original_bin_header = original.get_bin_header().clone();
+    let original_text_header = original.get_text_header().to_string();
+
+    let overrides = MetadataOverrides {
+        sample_format: Some(SampleFormatCode::Float32),
+        no_traces: Some(2),
+        clear_extended_headers: true,
+    };
+    let mut derived =
+        SegyFile::create_file_from_metadata(&path, original.into_metadata(), overrides)
+            .expect("Could not create file");
+    for (header, data) in traces.iter() {
+        derived
+            .add_trace(header.clone(), None, data.clone())
+            .expect("Could not add trace");
+    }
+    drop(derived);
+
+    let derived = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    let mut expected = original_bin_header;
+    expected.sample_format_code = SampleFormatCode::Float32;
+    expected.no_traces = 2;
+    expected.extended_header_count = 0;
+    assert_eq!(derived.get_bin_header(), &expected);
+    assert_eq!(derived.get_text_header(), original_text_header);
+    assert!(derived.get_extended_headers().is_empty());
+
+    assert_eq!(derived.trace_count(), 2);
+    for (i, (header, data)) in traces.iter().enumerate() {
+        assert_eq!(derived.get_trace(i).unwrap().get_header(), header);
+        assert_eq!(&derived.get_trace_data_as_f32(i).unwrap(), data);
+    }
+
+    // The unassigned bytes of the binary header are kept.
+    let original = std::fs::read(TEST_FILE).unwrap();
+    let derived = std::fs::read(&path).unwrap();
+    assert_eq!(original[3600 - 100..3600], derived[3600 - 100..3600]);
+}
+
+#[test]
+fn keep_rev2_bin_header_fields() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let rev2_path = dir.path().join("rev2.sgy");
+    let path = dir.path().join("derived.sgy");
+
+    // Fill in the extended fields of SEG-Y rev 2 and the time basis code.
+    let le = giga_segy_in::SegyFile::open(TEST_FILE, SegySettings::default())
+        .unwrap()
+        .get_bin_header()
+        .binary_flag_direction_is_le;
+    let u32_to_b = if le {
+        u32::to_le_bytes
+    } else {
+        u32::to_be_bytes
+    };
+    let u64_to_b = if le {
+        u64::to_le_bytes
+    } else {
+        u64::to_be_bytes
+    };
+    let mut bytes = std::fs::read(TEST_FILE).unwrap();
+    bytes[3260..3264].copy_from_slice(&u32_to_b(48));
+    bytes[3264..3268].copy_from_slice(&u32_to_b(2));
+    bytes[3268..3272].copy_from_slice(&u32_to_b(70_000));
+    bytes[3280..3288].copy_from_slice(&u64_to_b(250.5f64.to_bits()));
+    bytes[3288..3292].copy_from_slice(&u32_to_b(140_000));
+    bytes[3292..3296].copy_from_slice(&u32_to_b(96));
+    bytes[3510..3512].copy_from_slice(&(if le { [4, 0] } else { [0, 4] }));
+    std::fs::write(&rev2_path, &bytes).unwrap();
+
+    let original = giga_segy_in::SegyFile::open(rev2_path.to_str().unwrap(), Default::default())
+        .expect("Could not open");
+    let bin_header = original.get_bin_header();
+    assert_eq!(bin_header.extended_no_traces, 48);
+    assert_eq!(bin_header.extended_no_aux_traces, 2);
+    assert_eq!(bin_header.extended_no_samples, 70_000);
+    assert_eq!(bin_header.extended_sample_interval_original, 250.5);
+    assert_eq!(bin_header.extended_no_samples_original, 140_000);
+    assert_eq!(bin_header.extended_ensemble_fold, 96);
+    let header = original.get_trace(0).unwrap().get_header().clone();
+    let data = original.get_trace_data_as_f32(0).unwrap();
+
+    // Without the raw binary header, the fields must be written from the `BinHeader`.
+    let mut metadata = original.into_metadata();
+    metadata.raw_bin_header = None;
+    let mut derived =
+        SegyFile::create_file_from_metadata(&path, metadata, MetadataOverrides::default())
+            .expect("Could not create file");
+    derived.add_trace(header, None, data).unwrap();
+    drop(derived);
+
+    let derived = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[3260..3296], derived[3260..3296]);
+    assert_eq!(bytes[3500..3512], derived[3500..3512]);
+    let reread = giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default())
+        .expect("Could not reopen");
+    assert_eq!(reread.get_bin_header().extended_no_samples, 70_000);
+}
@@ -0,0 +1,394 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that headers which cannot be written produce an error instead of a panic,
+//! and that headers which can be written are read back unchanged, with every field where
+//! [`giga_segy_core::layout`] says it is.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::write_headers::{th_as_bytes_with_settings, SegyHeaderToBytes};
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::layout::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use giga_segy_in::convert_headers::{HeaderFromBytes, TraceHeaderFromBytes};
+
+#[test]
+fn bin_header_invalid_enum() {
+    let mut bin_header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+    bin_header.taper_type = TaperType::Invalid;
+    let err = bin_header.as_bytes().unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::HeaderFieldEncode {
+            field: "taper_type"
+        }
+    ));
+    assert!(err.to_string().contains("taper_type"));
+}
+
+#[test]
+fn trace_header_invalid_enum() {
+    let bin_header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+    let settings = SegySettings::default();
+
+    let mut trace_header = TraceHeader::new_3d(1, 2, 3, 4, 1);
+    trace_header.trace_identification_code = TraceIdCode::Invalid;
+    let err = th_as_bytes_with_settings(&trace_header, &settings, &bin_header).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::HeaderFieldEncode {
+            field: "trace_identification_code"
+        }
+    ));
+
+    let mut trace_header = TraceHeader::new_3d(1, 2, 3, 4, 1);
+    trace_header.source_measurement_unit = SourceMeasurementUnit::Invalid;
+    let err = th_as_bytes_with_settings(&trace_header, &settings, &bin_header).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::HeaderFieldEncode {
+            field: "source_measurement_unit"
+        }
+    ));
+}
+
+#[test]
+fn headers_roundtrip() {
+    let settings = SegySettings::default();
+    let mut bin_header = BinHeader::new(7, 4000, 10, SampleFormatCode::Int16);
+    bin_header.sorting_code = TraceSortingCode::Other;
+    bin_header.measurement_system = MeasurementSystem::Meters;
+    let bytes = bin_header.as_bytes().unwrap();
+    assert_eq!(bytes.len(), 400);
+    // The sample format code is at bytes 3225-3226 (big endian).
+    assert_eq!(bytes[24..26], [0, 3]);
+    assert_eq!(
+        BinHeader::from_bytes(&bytes, &settings).unwrap(),
+        bin_header
+    );
+
+    let mut trace_header = TraceHeader::new_3d(100, -200, 3, 4, -10);
+    trace_header.trace_identification_code = TraceIdCode::Other;
+    trace_header.trace_value_measurement_unit = TraceValueUnit::Volts;
+    trace_header.source_type = SourceType::VibratoryVertical;
+    trace_header.low_cut_frequency = 5;
+    trace_header.high_cut_slope = 6;
+    trace_header.day_of_year = 300;
+    trace_header.source_measurement_exponent = 2;
+    let bytes = th_as_bytes_with_settings(&trace_header, &settings, &bin_header).unwrap();
+    assert_eq!(bytes.len(), 240);
+    assert_eq!(bytes[28..30], (-1i16).to_be_bytes());
+    assert_eq!(bytes[180..184], 100i32.to_be_bytes());
+    assert_eq!(bytes[184..188], (-200i32).to_be_bytes());
+    let read = TraceHeader::from_bytes(&bytes, &bin_header, &settings, 0).unwrap();
+    assert_eq!(read, trace_header);
+}
+
+#[test]
+fn bin_header_sweep_frequency_end() {
+    let mut bin_header = BinHeader::new(7, 4000, 10, SampleFormatCode::Int16);
+    bin_header.sweep_frequency_start = 10;
+    bin_header.sweep_frequency_end = 80;
+    let bytes = bin_header.as_bytes().unwrap();
+    // Bytes 3233-3236 hold the start and end frequencies of the sweep. Earlier versions
+    // wrote the start frequency twice, so this is the one change to the bytes of the header.
+    assert_eq!(bytes[32..36], [0, 10, 0, 80]);
+    let read = BinHeader::from_bytes(&bytes, &SegySettings::default()).unwrap();
+    assert_eq!(read.sweep_frequency_start, 10);
+    assert_eq!(read.sweep_frequency_end, 80);
+}
+
+#[test]
+fn rev2_bin_header_fields_roundtrip() {
+    let mut bin_header = BinHeader::new(7, 4000, 10, SampleFormatCode::Int16);
+    bin_header.no_traces_in_file = 5_000_000_000;
+    bin_header.first_trace_offset = 8192;
+    bin_header.no_trailer_stanzas = -1;
+    for le in [false, true].iter() {
+        bin_header.binary_flag_direction_is_le = *le;
+        let bytes = bin_header.as_bytes().unwrap();
+        let (in_file, offset, stanzas) = if *le {
+            (
+                5_000_000_000u64.to_le_bytes(),
+                8192u64.to_le_bytes(),
+                (-1i32).to_le_bytes(),
+            )
+        } else {
+            (
+                5_000_000_000u64.to_be_bytes(),
+                8192u64.to_be_bytes(),
+                (-1i32).to_be_bytes(),
+            )
+        };
+        assert_eq!(bytes[312..320], in_file);
+        assert_eq!(bytes[320..328], offset);
+        assert_eq!(bytes[328..332], stanzas);
+        assert!(bytes[332..].iter().all(|b| *b == 0));
+        assert_eq!(
+            BinHeader::from_bytes(&bytes, &SegySettings::default()).unwrap(),
+            bin_header
+        );
+    }
+}
+
+/// Make big endian header bytes where each field of the layout (except raw bytes) holds a
+/// different value: its position in the layout plus one, or `1` for enums so that the code is
+/// valid. The expected value of each field is returned as well.
+fn bytes_from_layout(layout: &[FieldSpec], len: usize) -> (Vec<u8>, Vec<Option<FieldValue>>) {
+    let mut bytes = vec![0; len];
+    let mut values = Vec::new();
+    for (k, field) in layout.iter().enumerate() {
+        let v = if field.kind == FieldKind::Enum {
+            1
+        } else {
+            k + 1
+        };
+        let encoded = (v as u64).to_be_bytes();
+        let width = field.range.len();
+        if field.kind != FieldKind::Bytes {
+            bytes[field.range.clone()].copy_from_slice(&encoded[(8 - width)..]);
+        }
+        values.push(match field.kind {
+            FieldKind::I32 | FieldKind::Coordinate => Some(FieldValue::I32(v as i32)),
+            FieldKind::U16 => Some(FieldValue::U16(v as u16)),
+            FieldKind::I16 => Some(FieldValue::I16(v as i16)),
+            FieldKind::Enum => Some(FieldValue::Enum(v as i32)),
+            _ => None,
+        });
+    }
+    (bytes, values)
+}
+
+#[test]
+fn trace_header_matches_layout() {
+    let settings = SegySettings::default();
+    let bin_header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+    let (bytes, values) = bytes_from_layout(trace_header_layout(), 240);
+
+    let header = TraceHeader::from_bytes(&bytes, &bin_header, &settings, 0).unwrap();
+    for (field, value) in trace_header_layout().iter().zip(values) {
+        assert_eq!(
+            header.get_field_by_name(field.name),
+            value,
+            "{}",
+            field.name
+        );
+    }
+    assert_eq!(
+        header.get_field_by_name("inline_no"),
+        Some(FieldValue::I32(header.inline_no))
+    );
+
+    let written = th_as_bytes_with_settings(&header, &settings, &bin_header).unwrap();
+    for field in trace_header_layout() {
+        assert_eq!(
+            written[field.range.clone()],
+            bytes[field.range.clone()],
+            "{}",
+            field.name
+        );
+    }
+}
+
+#[test]
+fn bin_header_matches_layout() {
+    let (mut bytes, _) = bytes_from_layout(bin_header_layout(), 400);
+    // A valid fixed length trace flag.
+    bytes[303] = 1;
+    let bin_header = BinHeader::from_bytes(&bytes, &SegySettings::default()).unwrap();
+
+    let written = bin_header.as_bytes().unwrap();
+    for field in bin_header_layout() {
+        if field.kind != FieldKind::Bytes {
+            assert_eq!(
+                written[field.range.clone()],
+                bytes[field.range.clone()],
+                "{}",
+                field.name
+            );
+        }
+    }
+}
+
+#[test]
+fn sample_interval_roundtrip() {
+    let settings = SegySettings::default();
+    let expected = [
+        (2000., 2000, 0.),
+        (65535., 65535, 0.),
+        (100_000., 0, 100_000.),
+    ];
+    for (interval, short, extended) in expected.iter() {
+        let mut bin_header = BinHeader::new(1, 0, 10, SampleFormatCode::Float32);
+        bin_header.try_set_sample_interval_us(*interval).unwrap();
+        assert_eq!(bin_header.sample_interval, *short);
+        assert_eq!(bin_header.extended_sample_interval, *extended);
+        assert_eq!(bin_header.sample_interval_us(), *interval);
+        assert!(bin_header
+            .to_string()
+            .contains(&format!("sample interval: {}\n", interval)));
+
+        let bytes = bin_header.as_bytes().unwrap();
+        assert_eq!(bytes[72..80], extended.to_be_bytes());
+        let read = BinHeader::from_bytes(&bytes, &settings).unwrap();
+        assert_eq!(read.sample_interval_us(), *interval);
+        assert_eq!(read, bin_header);
+    }
+
+    // A fractional interval keeps a rounded value in the old field.
+    let mut bin_header = BinHeader::new(1, 0, 10, SampleFormatCode::Float32);
+    bin_header.try_set_sample_interval_us(312.5).unwrap();
+    assert_eq!(bin_header.sample_interval, 313);
+    assert_eq!(bin_header.sample_interval_us(), 312.5);
+
+    // Before rev 2 there is no extended sample interval, neither to write nor to read.
+    bin_header.segy_revision_number = [1, 0];
+    assert_eq!(bin_header.sample_interval_us(), 313.);
+    let err = bin_header.try_set_sample_interval_us(100_000.).unwrap_err();
+    assert!(matches!(err, RsgError::InvalidHeader { .. }));
+    assert_eq!(bin_header.sample_interval, 313);
+    assert!(bin_header.try_set_sample_interval_us(65535.).is_ok());
+    assert!(bin_header.try_set_sample_interval_us(-1.).is_err());
+    assert!(bin_header.try_set_sample_interval_us(f64::NAN).is_err());
+    assert_eq!(bin_header.sample_interval_us(), 65535.);
+}
+
+/// Create a file with one trace with the given settings.
+fn create_with_settings(
+    path: &std::path::Path,
+    settings: SegySettings,
+    header: TraceHeader,
+) -> Result<(), RsgError> {
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 Custom byte indices.",
+        settings,
+        BinHeader::new(1, 4000, 2, SampleFormatCode::Float32),
+        [(header, vec![1f32, 2.])],
+    )?;
+    Ok(())
+}
+
+#[test]
+fn bidx_collisions_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut header = TraceHeader::new_3d(1000, 2000, 3, 4, 1);
+    header.source_x = 555;
+
+    // The X coordinate of the ensemble would overwrite the X coordinate of the source.
+    let mut settings = SegySettings::default();
+    settings.set_x_ensemble_bidx(72).unwrap();
+    let path = dir.path().join("refused.sgy");
+    let err = create_with_settings(&path, settings.clone(), header.clone()).unwrap_err();
+    assert!(matches!(err, RsgError::SEGYSettingsError { .. }));
+    assert!(
+        err.to_string()
+            .contains("x_ensemble at bytes 73-76 overlaps source_x"),
+        "{}",
+        err
+    );
+    assert!(!path.exists());
+
+    // Unless this is allowed.
+    settings.set_allow_bidx_collisions(true);
+    let path = dir.path().join("allowed.sgy");
+    create_with_settings(&path, settings.clone(), header.clone()).unwrap();
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let trace = segy.get_trace(0).unwrap();
+    let read = trace.get_header();
+    assert_eq!(read.x_ensemble, 1000);
+    assert_eq!(read.source_x, 1000);
+    assert_eq!(segy.get_warnings().len(), 1);
+}
+
+#[test]
+fn bidx_without_collisions_are_written() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut header = TraceHeader::new_3d(1000, 2000, 3, 4, 1);
+    header.source_x = 555;
+
+    // The inline and crossline numbers swap places.
+    let mut settings = SegySettings::default();
+    settings.set_inline_no_bidx(192).unwrap();
+    settings.set_crossline_no_bidx(188).unwrap();
+    assert!(settings.check_bidx_collisions().is_empty());
+    let path = dir.path().join("swapped.sgy");
+    create_with_settings(&path, settings.clone(), header).unwrap();
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).unwrap();
+    let trace = segy.get_trace(0).unwrap();
+    let read = trace.get_header();
+    assert_eq!([read.inline_no, read.crossline_no], [3, 4]);
+    assert_eq!(read.source_x, 555);
+    assert!(segy.get_warnings().is_empty());
+    assert_eq!(
+        segy.get_trace_header_bytes(0).unwrap()[188..192],
+        4i32.to_be_bytes()
+    );
+}
+
+#[test]
+fn shot_point_scalar_roundtrip() {
+    let settings = SegySettings::default();
+    let bin_header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+    let mut trace_header = TraceHeader::new_2d(0, 0, 1);
+    trace_header.shot_point_no = 1015;
+    trace_header.shot_point_scalar = -10;
+    let bytes = th_as_bytes_with_settings(&trace_header, &settings, &bin_header).unwrap();
+    assert_eq!(bytes[200..202], (-10i16).to_be_bytes());
+
+    let read = TraceHeader::from_bytes(&bytes, &bin_header, &settings, 0).unwrap();
+    assert_eq!(read.shot_point_scalar, -10);
+    assert_eq!(read.scaled_shot_point(), 101.5);
+    assert!(read.to_string().contains("scaled shot point: 101.5\n"));
+
+    trace_header.shot_point_no = -7;
+    trace_header.shot_point_scalar = -4;
+    assert_eq!(trace_header.scaled_shot_point(), -1.75);
+    trace_header.shot_point_scalar = 0;
+    assert_eq!(trace_header.scaled_shot_point(), -7.);
+    trace_header.shot_point_scalar = 1;
+    assert_eq!(trace_header.scaled_shot_point(), -7.);
+    trace_header.shot_point_scalar = 100;
+    assert_eq!(trace_header.scaled_shot_point(), -700.);
+}
+
+/// Get the names of the fields of a structure from its pretty `Debug` form.
+fn debug_field_names<T: std::fmt::Debug>(value: &T) -> Vec<String> {
+    format!("{:#?}", value)
+        .lines()
+        .filter_map(|l| l.strip_prefix("    "))
+        .filter(|l| !l.starts_with(' '))
+        .filter_map(|l| l.split(": ").next())
+        .filter(|l| l.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn every_field_can_be_formatted_by_name() {
+    let bin_header = BinHeader::new(1, 4000, 10, SampleFormatCode::Float32);
+    let names = debug_field_names(&bin_header);
+    assert!(names.len() > 40);
+    for name in names.iter() {
+        assert!(bin_header.format_field_by_name(name).is_some(), "{}", name);
+    }
+    let listed = bin_header_layout().len() + BIN_HEADER_EXTRA_FIELDS.len();
+    assert_eq!(names.len(), listed);
+    assert_eq!(
+        bin_header.format_field_by_name("raw_sample_format_code"),
+        Some("5".to_string())
+    );
+
+    let trace_header = TraceHeader::new_3d(1000, 2000, 12, 34, 1);
+    let names = debug_field_names(&trace_header);
+    for name in names.iter() {
+        assert!(
+            trace_header.format_field_by_name(name).is_some(),
+            "{}",
+            name
+        );
+    }
+    assert_eq!(names.len(), trace_header_layout().len());
+}
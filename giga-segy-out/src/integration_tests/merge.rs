@@ -0,0 +1,201 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we merge two small files and read the result back.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::merge::{merge_files, MergeOptions};
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Write a file with a single inline of `count` traces. The value of each sample is
+/// `inline * 100 + crossline + sample_idx`.
+fn write_line(path: &Path, inline: i32, count: i32, format: SampleFormatCode) {
+    let traces = (0..count).map(|xl| {
+        let mut trace_header = TraceHeader::new_3d(xl, inline, inline, xl, 1);
+        trace_header.no_samples_in_trace = 10;
+        trace_header.trace_sequence_in_file = xl + 1;
+        let data = (0..10).map(|s| inline * 100 + xl + s).collect::<Vec<i32>>();
+        (trace_header, data)
+    });
+    write_fixture(
+        path,
+        CreateMode::New,
+        &format!("C 1 Sail line {}", inline),
+        SegySettings::default(),
+        BinHeader::new(count as u16, 4000, 10, format),
+        traces,
+    )
+    .expect("Could not create file.");
+}
+
+#[test]
+fn merge_two_lines() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let a = dir.path().join("a.sgy");
+    let b = dir.path().join("b.sgy");
+    let merged = dir.path().join("merged.sgy");
+    write_line(&a, 1, 5, SampleFormatCode::Float32);
+    write_line(&b, 2, 3, SampleFormatCode::Float32);
+
+    let opts = MergeOptions {
+        renumber_traces: true,
+        ..Default::default()
+    };
+    let report = merge_files(&[&a, &b], &merged, opts).expect("Could not merge");
+    assert_eq!(report.trace_counts, vec![5, 3]);
+    assert!(report.discrepancies.is_empty());
+
+    let segy = giga_segy_in::SegyFile::open(merged.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert!(segy.get_text_header().starts_with("C 1 Sail line 1"));
+    assert_eq!(segy.get_bin_header().no_traces, 8);
+    assert_eq!(segy.trace_count(), 8);
+
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let header = trace.get_header();
+        let (inline, xl) = if i < 5 {
+            (1, i as i32)
+        } else {
+            (2, i as i32 - 5)
+        };
+        assert_eq!(header.inline_no, inline);
+        assert_eq!(header.crossline_no, xl);
+        assert_eq!(header.trace_sequence_in_file, i as i32 + 1);
+
+        let data = segy.get_trace_data_as_f32_from_trace(&trace).unwrap();
+        let expected = (0..10)
+            .map(|s| (inline * 100 + xl + s) as f32)
+            .collect::<Vec<f32>>();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn merge_different_formats() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let a = dir.path().join("a.sgy");
+    let b = dir.path().join("b.sgy");
+    write_line(&a, 1, 2, SampleFormatCode::Float32);
+    write_line(&b, 2, 2, SampleFormatCode::Int16);
+
+    let strict = dir.path().join("strict.sgy");
+    let err = merge_files(&[&a, &b], &strict, MergeOptions::default()).unwrap_err();
+    assert!(matches!(err, RsgError::InvalidHeader { .. }));
+    assert!(!strict.exists());
+
+    let lenient = dir.path().join("lenient.sgy");
+    let opts = MergeOptions {
+        strict_format: false,
+        ..Default::default()
+    };
+    let report = merge_files(&[&a, &b], &lenient, opts).expect("Could not merge");
+    assert_eq!(report.discrepancies.len(), 1);
+    assert_eq!(report.discrepancies[0].input, 1);
+
+    let segy = giga_segy_in::SegyFile::open(lenient.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(
+        segy.get_bin_header().sample_format_code,
+        SampleFormatCode::Float32
+    );
+    let data = segy.get_trace_data_as_f32(3).unwrap();
+    assert_eq!(
+        data,
+        (0..10).map(|s| (201 + s) as f32).collect::<Vec<f32>>()
+    );
+}
+
+#[test]
+fn merge_ignores_step_by_and_sample_override() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let a = dir.path().join("a.sgy");
+    let b = dir.path().join("b.sgy");
+    let merged = dir.path().join("merged.sgy");
+    write_line(&a, 1, 2, SampleFormatCode::Float32);
+    write_line(&b, 2, 2, SampleFormatCode::Float32);
+
+    let mut settings = SegySettings::default();
+    settings.set_step_by(3);
+    settings.set_override_dim_z(4).unwrap();
+    let opts = MergeOptions {
+        settings,
+        ..Default::default()
+    };
+    merge_files(&[&a, &b], &merged, opts).expect("Could not merge");
+
+    let segy = giga_segy_in::SegyFile::open(merged.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(segy.trace_count(), 4);
+    for (i, trace) in segy.traces_iter().enumerate() {
+        assert_eq!(trace.get_header().no_samples_in_trace, 10);
+        let (inline, xl) = (i as i32 / 2 + 1, i as i32 % 2);
+        let data = segy.get_trace_data_as_f32_from_trace(&trace).unwrap();
+        let expected = (0..10)
+            .map(|s| (inline * 100 + xl + s) as f32)
+            .collect::<Vec<f32>>();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn merge_more_than_u16_max_traces() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let a = dir.path().join("a.sgy");
+    let b = dir.path().join("b.sgy");
+    let merged = dir.path().join("merged.sgy");
+    write_line(&a, 1, 33_000, SampleFormatCode::Float32);
+    write_line(&b, 2, 33_000, SampleFormatCode::Float32);
+
+    let report = merge_files(&[&a, &b], &merged, MergeOptions::default()).expect("Could not merge");
+    assert_eq!(report.total_trace_count(), 66_000);
+    assert!(report.discrepancies.is_empty());
+
+    let segy = giga_segy_in::SegyFile::open(merged.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    let bin_header = segy.get_bin_header();
+    assert_eq!(bin_header.no_traces, 0);
+    assert_eq!(bin_header.extended_no_traces, 66_000);
+    assert_eq!(bin_header.no_traces_in_file, 66_000);
+    assert_eq!(segy.trace_count(), 66_000);
+}
+
+#[test]
+fn merge_keeps_headers_and_data_as_stored() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let a = dir.path().join("a.sgy");
+    let b = dir.path().join("b.sgy");
+    let merged = dir.path().join("merged.sgy");
+    write_line(&a, 1, 4, SampleFormatCode::Float32);
+    write_line(&b, 2, 4, SampleFormatCode::Int16);
+
+    // Neither the gain (on the converted traces) nor the synthesized lines may leak into
+    // the output.
+    let mut settings = SegySettings::default();
+    settings.set_user_gain(3.);
+    settings.set_override_dim_x(2).unwrap();
+    let opts = MergeOptions {
+        settings,
+        strict_format: false,
+        renumber_traces: true,
+        ..Default::default()
+    };
+    merge_files(&[&a, &b], &merged, opts).expect("Could not merge");
+
+    let segy = giga_segy_in::SegyFile::open(merged.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(segy.trace_count(), 8);
+    for (i, trace) in segy.traces_iter().enumerate() {
+        let header = trace.get_header();
+        let (inline, xl) = (i as i32 / 4 + 1, i as i32 % 4);
+        assert_eq!(header.inline_no, inline);
+        assert_eq!(header.crossline_no, xl);
+        assert_eq!(header.trace_sequence_in_file, i as i32 + 1);
+        let data = segy.get_trace_data_as_f32_from_trace(&trace).unwrap();
+        let expected = (0..10)
+            .map(|s| (inline * 100 + xl + s) as f32)
+            .collect::<Vec<f32>>();
+        assert_eq!(data, expected);
+    }
+}
@@ -1,12 +1,12 @@
 // Copyright (C) 2022 by GiGa infosystems
 //! This module uses `rust_segy_input` and `rust_segy_output` to make roundtrip
 //! tests and observe whether the crates work in a compatible manner. They
-//! perform tests with two simplified geometry types, a trace based `Survey`
-//! type and 3D voxet type.
+//! perform tests with a simplified trace based `Survey` type, and with the
+//! 3D voxets of [`crate::voxet`].
 use crate::create_headers::CreateBinHeader;
 use crate::create_headers::CreateTraceHeader;
 use crate::utils::CoordinateScalar;
-use crate::SegyFile;
+use crate::{DurabilityOptions, SegyFile};
 
 use giga_segy_core::enums::*;
 use giga_segy_core::errors::*;
@@ -15,7 +15,32 @@ use num::ToPrimitive;
 use std::fmt::Debug;
 use std::path::Path;
 
+mod durability;
+#[cfg(feature = "edit")]
+mod edit;
+mod ensemble;
+mod extended_stanzas;
+mod from_metadata;
+mod header_encoding;
+#[cfg(feature = "edit")]
+mod merge;
+mod overwrite;
+mod provenance;
+#[cfg(feature = "edit")]
+mod repair;
+#[cfg(feature = "edit")]
+mod resample;
+mod sample_count;
+mod scaled;
+mod su;
+#[cfg(feature = "edit")]
+mod subset;
 mod survey_roundtrip;
+mod tape_label;
+mod text_header;
+mod trace_count;
+mod trace_extensions;
+mod voxet;
 
 /// An XYZ point.
 #[derive(Clone, Debug, PartialEq)]
@@ -130,7 +155,7 @@ impl Survey {
         let mut data = Vec::with_capacity(segy_file.trace_count());
         for trace in segy_file.traces_iter() {
             // Data and header.
-            let trace_data = segy_file.get_trace_data_as_f32_from_trace(trace)?;
+            let trace_data = segy_file.get_trace_data_as_f32_from_trace(&trace)?;
             let header = trace.get_header();
 
             // Converter from ensemble, height and coordinates. Our measurement system
@@ -171,93 +196,56 @@ impl Survey {
     }
 }
 
-/// The data contained in a single XY layer of a Voxet dataset.
-/// X is the fast axis and Z is the slow axis.
-type LayerData<T> = Vec<T>;
-
-struct Size {
-    x: i32,
-    y: i32,
-    z: i32,
-}
-
-/// The structure represent a simple SEGY compatible voxet.
-#[allow(dead_code)]
-struct Voxet<T: ToPrimitive + Debug> {
-    name: String,
-    description: String,
-    size: Size,
-    start_pt: Pt,
-    end_pt: Pt,
-    data: Vec<LayerData<T>>,
+/// How [`write_fixture`] creates a file.
+#[derive(Clone, Copy, Debug)]
+enum CreateMode {
+    /// The file must not exist yet (see [`SegyFile::create_file`]).
+    New,
+    /// An existing file is replaced (see [`SegyFile::create_file_overwrite`]).
+    Overwrite,
+    /// The file only appears under its name once it is finalized (see
+    /// [`SegyFile::create_file_atomic`]).
+    Atomic(DurabilityOptions),
 }
 
-#[allow(dead_code)]
-impl<T: ToPrimitive + Debug + Copy> Voxet<T> {
-    fn write(&self, path: &Path, written_sample_format: SampleFormatCode) -> Result<(), RsgError> {
-        let final_name = path.join(&self.name).with_extension("sgy");
-
-        let no_traces = (self.size.x * self.size.y) as u16;
-        let no_samples = (self.size.z) as u16;
-        let sample_interval = ((self.end_pt.z - self.start_pt.z) / no_samples as f32) as u16;
-        let mut bin_header = BinHeader::new(
-            no_traces,
-            sample_interval,
-            no_samples,
-            written_sample_format,
-        );
-        bin_header.set_measurement_system(MeasurementSystem::Meters);
-
-        let mut segy_file = SegyFile::create_file(
-            final_name,
-            SegySettings::default(),
-            self.description.to_string(),
-            bin_header,
-            None,
-        )?;
-
-        let theoretical_size = (self.size.x * self.size.y) as usize;
-
-        for i in 0..theoretical_size {
-            if self.data[i].len() != theoretical_size {
-                return Err(RsgError::SEGYSettingsError {
-                    msg: "Corrupt Voxet with different sized layers.".to_string(),
-                });
-            }
-
-            // Get data coordinates.
-            let x_row = i as i32 % self.size.x;
-            let y_row = i as i32 / self.size.x;
-            let x_ensemble =
-                self.start_pt.x + x_row as f32 * (self.end_pt.x - self.end_pt.x) as f32;
-            let y_ensemble =
-                self.start_pt.y + y_row as f32 * (self.end_pt.y - self.end_pt.y) as f32;
-
-            // Convert ensemble into a SEGY writeable format.
-            let scale_converter = CoordinateScalar::from_multiplier(100.).unwrap();
-            let x_ensemble = scale_converter.scale_to_i32(x_ensemble).unwrap();
-            let y_ensemble = scale_converter.scale_to_i32(y_ensemble).unwrap();
-            let elevation = scale_converter.scale_to_i32(self.start_pt.z).unwrap();
-            let coord_scalar = scale_converter.writeable_scalar();
-
-            // Create trace header. (This is a cat)
-            let mut trace_header =
-                TraceHeader::new_3d(x_ensemble, y_ensemble, x_row, y_row, coord_scalar);
-            trace_header.trace_sequence_in_file = i as i32;
-            trace_header.trace_no = i as i32;
-            trace_header.trace_sequence_on_line = x_row;
-            trace_header.datum_elevation_of_source = elevation;
-            trace_header.elevation_scalar = coord_scalar;
-
-            let trace_data = self.data.iter().map(|v| v[i]).collect::<Vec<_>>();
-
-            // Write data to file.
-            segy_file.add_trace(trace_header, None, trace_data)?;
+/// Create a file for a test with the given text header, settings and binary header, and add a
+/// trace for each trace header and its samples in `traces`. The file is not finalized, so that
+/// more can be done with it first.
+fn write_fixture<T, I>(
+    path: &Path,
+    mode: CreateMode,
+    text_header: &str,
+    settings: SegySettings,
+    bin_header: BinHeader,
+    traces: I,
+) -> Result<SegyFile<SegySettings>, RsgError>
+where
+    T: ToPrimitive + Debug,
+    I: IntoIterator<Item = (TraceHeader, Vec<T>)>,
+{
+    let text_header = text_header.to_string();
+    let mut file = match mode {
+        CreateMode::New => SegyFile::create_file(path, settings, text_header, bin_header, None)?,
+        CreateMode::Overwrite => {
+            SegyFile::create_file_overwrite(path, settings, text_header, bin_header, None)?
+        }
+        CreateMode::Atomic(durability) => {
+            SegyFile::create_file_atomic(path, settings, text_header, bin_header, None, durability)?
         }
-        Ok(())
+    };
+    for (header, data) in traces {
+        file.add_trace(header, None, data)?;
     }
+    Ok(file)
+}
 
-    fn read(_path: &Path) -> Result<Self, RsgError> {
-        unimplemented!();
-    }
+/// Get `count` traces of ten samples for [`write_fixture`]. The crossline number and every
+/// sample of each trace is the index of the trace.
+fn numbered_traces(count: usize) -> impl Iterator<Item = (TraceHeader, Vec<f32>)> {
+    (0..count).map(|i| {
+        (
+            TraceHeader::new_3d(0, 0, 1, i as i32, 1),
+            vec![i as f32; 10],
+        )
+    })
 }
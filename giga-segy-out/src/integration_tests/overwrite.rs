@@ -0,0 +1,73 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that an existing file is only overwritten when this is asked for, and that
+//! nothing of it is left behind the new file.
+use super::{numbered_traces, write_fixture, CreateMode};
+use crate::create_headers::CreateBinHeader;
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings};
+use giga_segy_core::{BIN_HEADER_LEN, TEXT_HEADER_LEN, TRACE_HEADER_LEN};
+use std::path::Path;
+
+/// Write a file with `count` traces of ten samples, overwriting `path` if `overwrite` is true.
+fn write(path: &Path, count: usize, overwrite: bool) -> Result<(), RsgError> {
+    let mode = if overwrite {
+        CreateMode::Overwrite
+    } else {
+        CreateMode::New
+    };
+    write_fixture(
+        path,
+        mode,
+        "C 1 A file which is written again.",
+        SegySettings::default(),
+        BinHeader::new(0, 4000, 10, SampleFormatCode::Float32),
+        numbered_traces(count),
+    )?;
+    Ok(())
+}
+
+#[test]
+fn create_file_existing() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("existing.sgy");
+    std::fs::write(&path, b"not a SEG-Y file").unwrap();
+    let e = write(&path, 1, false).unwrap_err();
+    assert!(matches!(e, RsgError::OutputExists { path: ref p } if p == &path));
+    assert_eq!(e.code(), 40);
+    assert_eq!(
+        std::io::Error::from(e).kind(),
+        std::io::ErrorKind::AlreadyExists
+    );
+    assert_eq!(std::fs::read(&path).unwrap(), b"not a SEG-Y file");
+}
+
+#[test]
+fn create_file_overwrite_new_path() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("new.sgy");
+    write(&path, 2, true).unwrap();
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 2);
+}
+
+#[test]
+fn create_file_overwrite_leaves_no_stale_tail() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("rerun.sgy");
+    write(&path, 20, false).unwrap();
+    write(&path, 3, true).unwrap();
+
+    let trace_len = TRACE_HEADER_LEN + 10 * 4;
+    let len = std::fs::metadata(&path).unwrap().len() as usize;
+    assert_eq!(len, TEXT_HEADER_LEN + BIN_HEADER_LEN + 3 * trace_len);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 3);
+    for i in 0..3 {
+        assert_eq!(segy.get_trace_data_as_f32(i).unwrap(), vec![i as f32; 10]);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that finalizing a file records how it was written in a blank card of the text
+//! header, and leaves the rest of the text header as it was.
+use super::{numbered_traces, write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::text_header::{normalize_text_header_lines, utc_date, TextHeaderOptions};
+use crate::SegyFile;
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Write a file with `count` traces of ten samples and finalize it.
+fn write(path: &Path, text_header: &str, append: bool, count: usize) -> SegyFile<SegySettings> {
+    let mut settings = SegySettings::default();
+    settings.set_append_provenance_card(append);
+    let mut file = write_fixture(
+        path,
+        CreateMode::New,
+        text_header,
+        settings,
+        BinHeader::new(0, 4000, 10, SampleFormatCode::Int32),
+        numbered_traces(count),
+    )
+    .unwrap();
+    file.finalize().unwrap();
+    file
+}
+
+/// Get the text header of a file as it was written.
+fn text_header_bytes(path: &Path) -> Vec<u8> {
+    std::fs::read(path).unwrap()[..3200].to_vec()
+}
+
+/// Check that a card records a file with `traces` traces of 32-bit integers.
+fn check_card(card: &str, n: usize, traces: usize) {
+    let prefix = format!(
+        "C{} written by giga-segy-out v{} on ",
+        n,
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(card.starts_with(&prefix), "{}", card);
+    let date = &card[prefix.len()..(prefix.len() + 10)];
+    assert!(date.chars().enumerate().all(|(i, c)| if i == 4 || i == 7 {
+        c == '-'
+    } else {
+        c.is_ascii_digit()
+    }));
+    let rest = format!(", format 2, {} traces", traces);
+    assert_eq!(card[(prefix.len() + 10)..].trim_end(), rest);
+    assert_eq!(card.len(), 80);
+}
+
+#[test]
+fn provenance_card_in_last_blank_card() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("provenance.sgy");
+    let mut lines = vec!["C 1 A survey"; 38];
+    lines.push("");
+    lines.push("C40 END EBCDIC");
+    let original = normalize_text_header_lines(&lines, TextHeaderOptions::default()).unwrap();
+
+    let mut file = write(&path, &original, true, 3);
+    assert_eq!(file.provenance_card(), Some(39));
+    let written = text_header_bytes(&path);
+    let card = std::str::from_utf8(&written[3040..3120]).unwrap();
+    check_card(card, 39, 3);
+    assert_eq!(&written[..3040], &original.as_bytes()[..3040]);
+    assert_eq!(&written[3120..], &original.as_bytes()[3120..]);
+    assert_eq!(file.metadata.get_text_header().as_bytes(), &written[..]);
+
+    // Finalizing again updates the same card.
+    file.add_trace(TraceHeader::new_3d(0, 0, 1, 3, 1), None, vec![3; 10])
+        .unwrap();
+    file.finalize().unwrap();
+    assert_eq!(file.provenance_card(), Some(39));
+    let written = text_header_bytes(&path);
+    check_card(std::str::from_utf8(&written[3040..3120]).unwrap(), 39, 4);
+    assert_eq!(&written[3120..], &original.as_bytes()[3120..]);
+    drop(file);
+
+    // The reader sees the card.
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    check_card(&segy.get_text_header_lines()[38], 39, 4);
+    assert_eq!(segy.trace_count(), 4);
+}
+
+#[test]
+fn provenance_card_after_short_text_header() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("short.sgy");
+    let file = write(&path, "C 1 A short text header", true, 2);
+    assert_eq!(file.provenance_card(), Some(40));
+    let written = text_header_bytes(&path);
+    assert!(written.starts_with(b"C 1 A short text header "));
+    assert!(written[23..3120].iter().all(|b| *b == b' '));
+    check_card(std::str::from_utf8(&written[3120..]).unwrap(), 40, 2);
+}
+
+#[test]
+fn provenance_card_full_text_header() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("full.sgy");
+    let options = TextHeaderOptions {
+        number_lines: true,
+        ..Default::default()
+    };
+    let original = normalize_text_header_lines(&["A full text header"; 40], options).unwrap();
+    let file = write(&path, &original, true, 2);
+    assert_eq!(file.provenance_card(), None);
+    assert_eq!(file.metadata.get_text_header(), original);
+    assert_eq!(text_header_bytes(&path), original.as_bytes());
+
+    // Nothing is written unless it is asked for.
+    let path = dir.path().join("not_asked.sgy");
+    let file = write(&path, "C 1 A short text header", false, 2);
+    assert_eq!(file.provenance_card(), None);
+    assert!(text_header_bytes(&path)[23..].iter().all(|b| *b == b' '));
+}
+
+#[test]
+fn provenance_card_date() {
+    assert_eq!(utc_date(0), "1970-01-01");
+    assert_eq!(utc_date(951_782_400), "2000-02-29");
+    assert_eq!(utc_date(1_709_251_199), "2024-02-29");
+    assert_eq!(utc_date(1_735_689_600), "2025-01-01");
+}
@@ -0,0 +1,148 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we break files in the ways that are common in the wild, repair them, and check that the
+//! repaired files can be read with the default settings.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::repair::{normalize, RepairPolicy};
+
+use giga_segy_core::enums::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// The traces of the fixtures: Five traces with 50 samples each.
+fn trace_data(i: usize) -> Vec<f32> {
+    (0..50).map(|s| (i * 1000 + s) as f32).collect()
+}
+
+/// Write a file with the traces of [`trace_data`] in the given byte order.
+fn write_clean(path: &Path, le: bool) {
+    let mut bin_header = BinHeader::new(0, 4000, 50, SampleFormatCode::Float32);
+    bin_header.binary_flag_direction_is_le = le;
+    let traces = (0..5).map(|i| {
+        let mut header = TraceHeader::new_2d(i as i32 * 10, 0, 1);
+        header.no_samples_in_trace = 50;
+        (header, trace_data(i))
+    });
+    let mut file = write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A file to break and repair",
+        SegySettings::default(),
+        bin_header,
+        traces,
+    )
+    .expect("Could not create file.");
+    file.finalize().expect("Could not finalize.");
+}
+
+/// Overwrite some bytes of a file.
+fn patch(path: &Path, at: usize, bytes: &[u8]) {
+    let mut file = std::fs::read(path).unwrap();
+    file[at..(at + bytes.len())].copy_from_slice(bytes);
+    std::fs::write(path, file).unwrap();
+}
+
+/// Open the repaired file with the default settings and check its traces.
+fn check_repaired(path: &Path) -> giga_segy_in::SegyFile {
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("The repaired file could not be opened.");
+    assert_eq!(segy.trace_count(), 5);
+    assert_eq!(segy.get_bin_header().no_traces, 5);
+    for i in 0..5 {
+        assert_eq!(
+            segy.get_trace(i).unwrap().get_header().x_ensemble,
+            i as i32 * 10
+        );
+        assert_eq!(segy.get_trace_data_as_f32(i).unwrap(), trace_data(i));
+    }
+    segy
+}
+
+#[test]
+fn repair_wrong_trace_count() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let broken = dir.path().join("broken.sgy");
+    let repaired = dir.path().join("repaired.sgy");
+    write_clean(&broken, false);
+    patch(&broken, 3212, &7u16.to_be_bytes());
+
+    let report = normalize(&broken, &repaired, RepairPolicy::default()).unwrap();
+    assert_eq!(report.trace_count, 5);
+    assert!(!report.is_clean());
+    let fix = report
+        .corrections
+        .iter()
+        .find(|c| c.field == "no_traces")
+        .expect("The trace count was not corrected.");
+    assert_eq!((fix.trace, &*fix.before, &*fix.after), (None, "7", "5"));
+    assert!(report.corrections.iter().all(|c| c.trace.is_none()));
+
+    check_repaired(&repaired);
+    // Everything but the trace count is copied as it was.
+    let (broken, repaired) = (
+        std::fs::read(broken).unwrap(),
+        std::fs::read(repaired).unwrap(),
+    );
+    assert_eq!(broken.len(), repaired.len());
+    let differ = (0..broken.len())
+        .filter(|i| broken[*i] != repaired[*i])
+        .collect::<Vec<_>>();
+    assert_eq!(differ, vec![3213]);
+}
+
+#[test]
+fn repair_missing_endian_stamp() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let broken = dir.path().join("broken.sgy");
+    let repaired = dir.path().join("repaired.sgy");
+    write_clean(&broken, true);
+    patch(&broken, 3296, &[0; 4]);
+
+    // Without the stamp the file is read as big endian, which does not make sense.
+    let res = giga_segy_in::SegyFile::open(broken.to_str().unwrap(), SegySettings::default());
+    assert!(res.map_or(true, |f| f.get_trace_data_as_f32(0).ok()
+        != Some(trace_data(0))));
+
+    let report = normalize(&broken, &repaired, RepairPolicy::default()).unwrap();
+    let fix = report
+        .corrections
+        .iter()
+        .find(|c| c.field == "binary_flag_direction_is_le")
+        .expect("The byte order flag was not written.");
+    assert_eq!(fix.trace, None);
+    assert_ne!(fix.before, fix.after);
+
+    let segy = check_repaired(&repaired);
+    assert!(segy.get_bin_header().binary_flag_direction_is_le);
+    assert_eq!(std::fs::read(&repaired).unwrap()[3296..3300], [1, 2, 3, 4]);
+}
+
+#[test]
+fn repair_clean_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let clean = dir.path().join("clean.sgy");
+    let copy = dir.path().join("copy.sgy");
+    write_clean(&clean, false);
+
+    let report = normalize(&clean, &copy, RepairPolicy::default()).unwrap();
+    assert!(report.is_clean(), "{:?}", report.corrections);
+    assert_eq!(std::fs::read(clean).unwrap(), std::fs::read(copy).unwrap());
+}
+
+#[test]
+fn repair_ignores_reading_settings() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let clean = dir.path().join("clean.sgy");
+    let copy = dir.path().join("copy.sgy");
+    write_clean(&clean, false);
+
+    // Every trace is copied in the order of the file, so a clean file is still copied as it is.
+    let mut policy = RepairPolicy::default();
+    policy.settings.set_step_by(2);
+    policy.settings.set_override_dim_z(10).unwrap();
+    policy.settings.set_user_gain(2.);
+    let report = normalize(&clean, &copy, policy).unwrap();
+    assert_eq!(report.trace_count, 5);
+    assert!(report.is_clean(), "{:?}", report.corrections);
+    assert_eq!(std::fs::read(clean).unwrap(), std::fs::read(copy).unwrap());
+}
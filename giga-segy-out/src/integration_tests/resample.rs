@@ -0,0 +1,285 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we decimate files with and without a low-pass filter, and read the results back.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::edit::remove_if_failed;
+use crate::resample::decimate;
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Write a file with one trace for each of the given lengths and a sample interval of 2 ms.
+/// The value of each sample is `trace * 100_000_000 + sample_idx`, which needs all 32 bits.
+fn write_int32(path: &Path, lengths: &[u16]) {
+    let traces = lengths.iter().enumerate().map(|(i, len)| {
+        let mut trace_header = TraceHeader::new_2d(i as i32, 0, 1);
+        trace_header.no_samples_in_trace = *len;
+        trace_header.sample_interval_of_trace = 2000;
+        let data = (0..*len as i32)
+            .map(|s| (i as i32 + 1) * 100_000_000 + s)
+            .collect::<Vec<i32>>();
+        (trace_header, data)
+    });
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 Traces to decimate",
+        SegySettings::default(),
+        BinHeader::new(0, 2000, lengths[0], SampleFormatCode::Int32),
+        traces,
+    )
+    .expect("Could not create file.");
+}
+
+/// Write a file with a Float32 trace for each of the given data.
+fn write_float32(path: &Path, traces: &[Vec<f32>]) {
+    let len = traces[0].len() as u16;
+    let traces = traces.iter().map(|data| {
+        let mut trace_header = TraceHeader::new_2d(0, 0, 1);
+        trace_header.no_samples_in_trace = len;
+        (trace_header, data.clone())
+    });
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 Traces to filter",
+        SegySettings::default(),
+        BinHeader::new(0, 1000, len, SampleFormatCode::Float32),
+        traces,
+    )
+    .expect("Could not create file.");
+}
+
+fn open(path: &Path) -> giga_segy_in::SegyFile {
+    giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open")
+}
+
+#[test]
+fn decimate_strided() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    write_int32(&input, &[20; 4]);
+
+    for factor in [1, 2, 3, 4] {
+        let output = dir.path().join(format!("decimated_{}.sgy", factor));
+        let report = decimate(&input, &output, factor, false, SegySettings::default()).unwrap();
+        assert_eq!(report.trace_count, 4);
+        let truncated = if 20 % factor == 0 {
+            vec![]
+        } else {
+            vec![0, 1, 2, 3]
+        };
+        assert_eq!(report.truncated, truncated);
+
+        let original = open(&input);
+        let decimated = open(&output);
+        assert_eq!(decimated.get_text_header(), original.get_text_header());
+        let bin_header = decimated.get_bin_header();
+        assert_eq!(bin_header.sample_interval as usize, 2000 * factor);
+        assert_eq!(bin_header.no_samples as usize, 20 / factor);
+        assert_eq!(bin_header.sample_format_code, SampleFormatCode::Int32);
+        assert_eq!(decimated.trace_count(), 4);
+
+        for (a, b) in original.traces_iter().zip(decimated.traces_iter()) {
+            let mut expected = a.get_header().clone();
+            expected.no_samples_in_trace = (20 / factor) as u16;
+            expected.sample_interval_of_trace = (2000 * factor) as u16;
+            assert_eq!(b.get_header(), &expected);
+
+            let data = original.get_trace_data_as_bytes_from_trace(&a).unwrap();
+            let strided = data
+                .chunks_exact(4)
+                .step_by(factor)
+                .take(20 / factor)
+                .flatten()
+                .cloned()
+                .collect::<Vec<u8>>();
+            assert_eq!(
+                decimated.get_trace_data_as_bytes_from_trace(&b).unwrap(),
+                strided
+            );
+        }
+    }
+}
+
+#[test]
+fn decimate_variable_length() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    let output = dir.path().join("decimated.sgy");
+    write_int32(&input, &[12, 7, 9, 3, 1]);
+
+    let report = decimate(&input, &output, 3, false, SegySettings::default()).unwrap();
+    assert_eq!(report.truncated, vec![1, 4]);
+
+    let decimated = open(&output);
+    assert_eq!(decimated.get_bin_header().no_samples, 4);
+    assert_eq!(decimated.get_bin_header().sample_interval, 6000);
+    let lengths = decimated
+        .traces_iter()
+        .map(|t| t.get_header().no_samples_in_trace)
+        .collect::<Vec<_>>();
+    assert_eq!(lengths, vec![4, 2, 3, 1, 0]);
+    for (i, len) in lengths.into_iter().enumerate() {
+        let data = decimated.get_trace_data_as_f32(i).unwrap();
+        let expected = (0..len as i32)
+            .map(|s| ((i as i32 + 1) * 100_000_000 + s * 3) as f32)
+            .collect::<Vec<_>>();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn decimate_antialias() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    let output = dir.path().join("decimated.sgy");
+    let len = 200;
+    let constant = vec![5f32; len];
+    // Above the Nyquist frequency of the output, so it should be (nearly) removed.
+    let high = (0..len)
+        .map(|s| if s % 2 == 0 { 1. } else { -1. })
+        .collect::<Vec<f32>>();
+    // Well below the Nyquist frequency of the output, so it should be kept.
+    let low = (0..len)
+        .map(|s| (s as f32 * std::f32::consts::PI / 40.).sin())
+        .collect::<Vec<f32>>();
+    write_float32(&input, &[constant, high, low.clone()]);
+
+    let report = decimate(&input, &output, 4, true, SegySettings::default()).unwrap();
+    assert_eq!(report.trace_count, 3);
+    assert!(report.truncated.is_empty());
+
+    let decimated = open(&output);
+    assert_eq!(decimated.get_bin_header().sample_interval, 4000);
+    assert_eq!(decimated.get_bin_header().no_samples, 50);
+
+    let data = decimated.get_trace_data_as_f32(0).unwrap();
+    assert_eq!(data.len(), 50);
+    assert!(data.iter().all(|v| (v - 5.).abs() < 1e-5), "{:?}", data);
+
+    let data = decimated.get_trace_data_as_f32(1).unwrap();
+    // Away from the ends of the trace, where the filter is cut off.
+    assert!(data[5..45].iter().all(|v| v.abs() < 1e-3), "{:?}", data);
+
+    let data = decimated.get_trace_data_as_f32(2).unwrap();
+    for (k, v) in data.iter().enumerate().skip(5).take(40) {
+        assert!((v - low[k * 4]).abs() < 0.05, "{} {} {}", k, v, low[k * 4]);
+    }
+}
+
+#[test]
+fn decimate_factor_limits() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    let output = dir.path().join("decimated.sgy");
+    write_int32(&input, &[10; 2]);
+
+    let result = decimate(&input, &output, 0, false, SegySettings::default());
+    assert!(matches!(result, Err(RsgError::SEGYSettingsError { .. })));
+    assert!(!output.exists());
+
+    // 2 ms times 40 does not fit in the sample interval fields, so the extended sample
+    // interval of the binary header is used and the trace headers have none.
+    let report = decimate(&input, &output, 40, false, SegySettings::default()).unwrap();
+    assert_eq!(report.truncated, vec![0, 1]);
+    let decimated = open(&output);
+    let bin_header = decimated.get_bin_header();
+    assert_eq!(bin_header.sample_interval, 0);
+    assert_eq!(bin_header.extended_sample_interval, 80_000.);
+    assert_eq!(bin_header.sample_interval_us(), 80_000.);
+    assert_eq!(bin_header.no_samples, 0);
+    for trace in decimated.traces_iter() {
+        assert_eq!(trace.get_header().sample_interval_of_trace, 0);
+        assert_eq!(trace.get_header().no_samples_in_trace, 0);
+    }
+}
+
+#[test]
+fn decimate_ibm_antialias() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    let output = dir.path().join("decimated.sgy");
+    write_float32(&input, &[vec![5f32; 40], vec![-0.25f32; 40]]);
+    // The samples are now read as IBM floats, which can not be written by `add_trace`.
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&input)
+        .unwrap();
+    file.seek(SeekFrom::Start(3200 + 24)).unwrap();
+    file.write_all(&1u16.to_be_bytes()).unwrap();
+    drop(file);
+
+    decimate(&input, &output, 2, true, SegySettings::default()).unwrap();
+    let original = open(&input);
+    let decimated = open(&output);
+    assert_eq!(
+        decimated.get_bin_header().sample_format_code,
+        SampleFormatCode::IbmFloat32
+    );
+    for i in 0..2 {
+        // The traces are constant, so the filter keeps their value.
+        let value = original.get_trace_data_as_f32(i).unwrap()[0];
+        let data = decimated.get_trace_data_as_f32(i).unwrap();
+        assert_eq!(data.len(), 20);
+        assert!(
+            data.iter().all(|v| (v - value).abs() <= value.abs() * 1e-5),
+            "{} {:?}",
+            value,
+            data
+        );
+    }
+}
+
+#[test]
+fn decimate_with_format_override() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("input.sgy");
+    let output = dir.path().join("decimated.sgy");
+    let data = (0..40).map(|s| s as f32 / 4.).collect::<Vec<_>>();
+    write_float32(&input, &[data.clone(), vec![3f32; 40]]);
+    // The sample format code is corrupt, so the file can only be read with an override.
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&input)
+        .unwrap();
+    file.seek(SeekFrom::Start(3200 + 24)).unwrap();
+    file.write_all(&0u16.to_be_bytes()).unwrap();
+    drop(file);
+    let mut settings = SegySettings::default();
+    settings.set_override_trace_format(SampleFormatCode::Float32);
+
+    for antialias in [false, true] {
+        let _ = std::fs::remove_file(&output);
+        decimate(&input, &output, 2, antialias, settings.clone()).unwrap();
+        // The output has the format of the override, and can be read without it.
+        let decimated = open(&output);
+        assert_eq!(
+            decimated.get_bin_header().sample_format_code,
+            SampleFormatCode::Float32
+        );
+        assert_eq!(decimated.get_trace_data_as_f32(1).unwrap(), vec![3f32; 20]);
+        if !antialias {
+            let strided = data.iter().step_by(2).cloned().collect::<Vec<_>>();
+            assert_eq!(decimated.get_trace_data_as_f32(0).unwrap(), strided);
+        }
+    }
+}
+
+#[test]
+fn remove_output_if_failed() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let output = dir.path().join("partial.sgy");
+    std::fs::write(&output, b"partial").unwrap();
+    let result = remove_if_failed::<()>(Err(RsgError::Cancelled), &output);
+    assert!(matches!(result, Err(RsgError::Cancelled)));
+    assert!(!output.exists());
+
+    std::fs::write(&output, b"complete").unwrap();
+    remove_if_failed(Ok(()), &output).unwrap();
+    assert!(output.exists());
+}
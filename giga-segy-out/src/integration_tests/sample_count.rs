@@ -0,0 +1,396 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that the number of samples of each trace that is written fits the headers, so
+//! that the file can be read again.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::{SegyFile, SegyHandle};
+use giga_segy_core::enums::{FixedLengthTraces, SampleFormatCode};
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Create a file with 10 samples per trace in the binary header.
+fn create(path: &Path, fixed: bool, settings: SegySettings) -> SegyFile<SegySettings> {
+    let mut bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+    if fixed {
+        bin_header.fixed_length_trace_flag = FixedLengthTraces::Yes;
+    }
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 Sample counts.",
+        settings,
+        bin_header,
+        Vec::<(TraceHeader, Vec<f32>)>::new(),
+    )
+    .expect("Could not create file.")
+}
+
+/// Add a trace with `no_samples` samples and `no_samples_in_trace` in the trace header.
+fn add(
+    file: &mut SegyFile<SegySettings>,
+    no_samples: usize,
+    no_samples_in_trace: u16,
+) -> Result<(), RsgError> {
+    let mut header = TraceHeader::new_2d(0, 0, 1);
+    header.no_samples_in_trace = no_samples_in_trace;
+    file.add_trace_lossless(header, None, vec![1f32; no_samples])?;
+    Ok(())
+}
+
+/// Read the sample counts in the trace headers of a file.
+fn header_sample_counts(path: &Path) -> Vec<u16> {
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    segy.traces_iter()
+        .map(|t| t.get_header().no_samples_in_trace)
+        .collect()
+}
+
+/// Read the sample counts of the traces of a file.
+fn sample_counts(path: &Path) -> Vec<usize> {
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    (0..segy.trace_count())
+        .map(|i| segy.get_trace_data_as_f32(i).unwrap().len())
+        .collect()
+}
+
+#[test]
+fn fixed_length_match() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("fixed.sgy");
+    let mut file = create(&path, true, SegySettings::default());
+    add(&mut file, 10, 0).unwrap();
+    add(&mut file, 10, 10).unwrap();
+    // A trace header which claims to have no samples is filled in.
+    assert_eq!(
+        file.get_trace(0).unwrap().get_header().no_samples_in_trace,
+        10
+    );
+    drop(file);
+    assert_eq!(sample_counts(&path), vec![10, 10]);
+    assert_eq!(header_sample_counts(&path), vec![10, 10]);
+}
+
+#[test]
+fn fixed_length_mismatch() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("fixed.sgy");
+    let mut file = create(&path, true, SegySettings::default());
+    add(&mut file, 10, 10).unwrap();
+    // Matching the trace header is not enough.
+    let err = add(&mut file, 8, 8).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::FixedLengthMismatch {
+            trace: 1,
+            l_data: 8,
+            l_bin: 10
+        }
+    ));
+    assert_eq!(err.trace_index(), Some(1));
+}
+
+#[test]
+fn variable_length_match() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("variable.sgy");
+    let mut file = create(&path, false, SegySettings::default());
+    add(&mut file, 10, 10).unwrap();
+    add(&mut file, 8, 8).unwrap();
+    // A missing sample count is filled in.
+    add(&mut file, 12, 0).unwrap();
+    assert_eq!(
+        file.get_trace(2).unwrap().get_header().no_samples_in_trace,
+        12
+    );
+    drop(file);
+    assert_eq!(sample_counts(&path), vec![10, 8, 12]);
+    assert_eq!(header_sample_counts(&path), vec![10, 8, 12]);
+}
+
+#[test]
+fn variable_length_mismatch() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("variable.sgy");
+    let mut file = create(&path, false, SegySettings::default());
+    // Matching the binary header is not enough.
+    let err = add(&mut file, 10, 8).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::TraceSampleCountMismatch {
+            trace: 0,
+            l_data: 10,
+            l_trace: 8
+        }
+    ));
+
+    let mut settings = SegySettings::default();
+    settings.set_strict_sample_count(true);
+    let mut file = create(&dir.path().join("strict.sgy"), false, settings);
+    let err = add(&mut file, 10, 0).unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::TraceSampleCountMismatch {
+            trace: 0,
+            l_data: 10,
+            l_trace: 0
+        }
+    ));
+}
+
+/// Create a file for traces of `no_samples` samples, with one trace header extension unless the
+/// traces have a fixed length.
+fn create_long(path: &Path, fixed: bool, revision: u8, no_samples: u32) -> SegyFile<SegySettings> {
+    let mut bin_header = BinHeader::new(0, 4000, 0, SampleFormatCode::Float32);
+    bin_header.segy_revision_number = [revision, 0];
+    if fixed {
+        bin_header.fixed_length_trace_flag = FixedLengthTraces::Yes;
+        bin_header.extended_no_samples = no_samples;
+    } else {
+        bin_header.max_additional_trace_headers = 1;
+    }
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 Long traces.",
+        SegySettings::default(),
+        bin_header,
+        Vec::<(TraceHeader, Vec<f32>)>::new(),
+    )
+    .expect("Could not create file.")
+}
+
+#[test]
+fn long_traces_rev2() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let data = (0..100_000).map(|s| s as f32).collect::<Vec<_>>();
+    for fixed in [true, false] {
+        let path = dir.path().join(format!("long_{}.sgy", fixed));
+        let mut file = create_long(&path, fixed, 2, 100_000);
+        for i in 0..2 {
+            let header = TraceHeader::new_3d(0, 0, 1, i, 1);
+            file.add_trace(header, None, data.clone()).unwrap();
+        }
+        // The sample count does not fit in the trace header.
+        assert_eq!(
+            file.get_trace(0).unwrap().get_header().no_samples_in_trace,
+            0
+        );
+        drop(file);
+
+        let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+            .expect("Could not open");
+        assert_eq!(segy.trace_count(), 2);
+        assert_eq!(segy.get_trace_data_as_f32(1).unwrap(), data);
+        if !fixed {
+            let extension = segy.get_trace_extension_bytes(0).unwrap();
+            assert_eq!(extension[136..140], 100_000u32.to_be_bytes());
+        }
+    }
+}
+
+#[test]
+fn long_traces_refused() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+
+    // Before rev 2 the sample count must fit in 16 bits.
+    let mut file = create_long(&dir.path().join("rev1.sgy"), false, 1, 0);
+    let err = file
+        .add_trace(header.clone(), Some(" ".repeat(240)), vec![1f32; 100_000])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::LongDataVector {
+            l_data: 100_000,
+            max: 65535,
+            ..
+        }
+    ));
+    assert!(err.to_string().contains("not SEG-Y rev 2"));
+    file.add_trace(header.clone(), None, vec![1f32; 65535])
+        .unwrap();
+
+    // A variable length trace needs a trace header extension for its sample count.
+    let mut file = create(
+        &dir.path().join("no_extension.sgy"),
+        false,
+        Default::default(),
+    );
+    let err = file
+        .add_trace(header, None, vec![1f32; 100_000])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::LongDataVector {
+            l_data: 100_000,
+            max: 65535,
+            ..
+        }
+    ));
+    assert!(err.to_string().contains("trace header extension"));
+}
+
+#[test]
+fn samples_above_i16_max() {
+    // 60 000 samples still fit in the 16 bit header fields, but not in an i16.
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("60k.sgy");
+    let data = |i: i32| {
+        (0..60_000)
+            .map(|s| (i * 100_000 + s) as f32)
+            .collect::<Vec<_>>()
+    };
+    let mut bin_header = BinHeader::new(3, 1000, 60_000, SampleFormatCode::Float32);
+    bin_header.fixed_length_trace_flag = FixedLengthTraces::Yes;
+    let traces = (0..3).map(|i| (TraceHeader::new_3d(0, 0, 1, i, 1), data(i)));
+    write_fixture(
+        &path,
+        CreateMode::New,
+        "C 1 60 000 samples per trace.",
+        SegySettings::default(),
+        bin_header,
+        traces,
+    )
+    .expect("Could not create file.");
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.get_bin_header().sample_count(), 60_000);
+    assert_eq!(segy.trace_count(), 3);
+    for i in 0..3 {
+        assert_eq!(segy.trace_sample_count(i as usize), Some(60_000));
+        assert_eq!(header_sample_counts(&path)[i as usize], 60_000);
+        assert_eq!(segy.get_trace_data_as_f32(i as usize).unwrap(), data(i));
+        let trace = segy.get_trace(i as usize).unwrap();
+        for idx in [0, 32_767, 32_768, 59_999] {
+            let point = segy.get_trace_data_point_as_f32_from_trace(&trace, idx);
+            assert_eq!(point.unwrap(), (i * 100_000 + idx as i32) as f32);
+        }
+        assert!(segy
+            .get_trace_data_point_as_f32_from_trace(&trace, 60_000)
+            .is_err());
+    }
+
+    let mut settings = SegySettings::default();
+    settings.set_step_by(7);
+    let segy =
+        giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).expect("Could not open");
+    let stepped = segy.get_trace_data_as_f32(2).unwrap();
+    assert_eq!(stepped.len(), 8572);
+    assert_eq!(stepped, data(2).into_iter().step_by(7).collect::<Vec<_>>());
+    let trace = segy.get_trace(2).unwrap();
+    let point = segy.get_trace_data_point_as_f32_from_trace(&trace, 8571);
+    assert_eq!(point.unwrap(), (200_000 + 8571 * 7) as f32);
+}
+
+#[test]
+fn override_dim_z_above_u16_max() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("long.sgy");
+    let data = (0..100_000).map(|s| s as f32).collect::<Vec<_>>();
+    let mut file = create_long(&path, true, 2, 100_000);
+    file.add_trace(TraceHeader::new_3d(0, 0, 1, 1, 1), None, data.clone())
+        .unwrap();
+    drop(file);
+
+    // The override is kept in the extended sample count, rather than wrapping around.
+    let mut settings = SegySettings::default();
+    settings.set_override_dim_z(70_000).unwrap();
+    let segy =
+        giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).expect("Could not open");
+    let bin_header = segy.get_bin_header();
+    assert_eq!(bin_header.no_samples, 0);
+    assert_eq!(bin_header.sample_count(), 70_000);
+    assert_eq!(segy.trace_sample_count(0), Some(70_000));
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), data[..70_000]);
+}
+
+#[cfg(feature = "edit")]
+#[test]
+fn crop_long_traces() {
+    use crate::subset::crop_with_window;
+
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("long.sgy");
+    let data = (0..100_000).map(|s| s as f32).collect::<Vec<_>>();
+    let mut file = create_long(&input, false, 2, 100_000);
+    file.add_trace(TraceHeader::new_3d(0, 0, 1, 1, 1), None, data.clone())
+        .unwrap();
+    drop(file);
+
+    // The window is still too long for the trace header, so its length goes to the extension.
+    let output = dir.path().join("cropped.sgy");
+    crop_with_window(
+        &input,
+        &output,
+        [1, 1],
+        [1, 1],
+        10..80_000,
+        SegySettings::default(),
+    )
+    .unwrap();
+    let segy = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(
+        segy.get_trace(0).unwrap().get_header().no_samples_in_trace,
+        0
+    );
+    assert_eq!(segy.trace_sample_count(0), Some(79_990));
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), data[10..80_000]);
+}
+
+#[cfg(feature = "edit")]
+#[test]
+fn decimate_long_traces() {
+    use crate::resample::decimate;
+    use std::convert::TryFrom;
+
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let data = (0..100_000).map(|s| s as f32).collect::<Vec<_>>();
+    for fixed in [true, false] {
+        let input = dir.path().join(format!("long_{}.sgy", fixed));
+        let mut file = create_long(&input, fixed, 2, 100_000);
+        file.add_trace(TraceHeader::new_3d(0, 0, 1, 1, 1), None, data.clone())
+            .unwrap();
+        drop(file);
+
+        for (factor, antialias) in [(2, false), (2, true), (1, true)] {
+            let output = dir
+                .path()
+                .join(format!("decimated_{}_{}.sgy", fixed, factor));
+            let _ = std::fs::remove_file(&output);
+            decimate(&input, &output, factor, antialias, SegySettings::default()).unwrap();
+
+            let segy =
+                giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+                    .expect("Could not open");
+            let count = 100_000 / factor;
+            // Counts which fit go to the headers, and the rest to the extended fields. The
+            // binary header only has a count for fixed length traces.
+            let fits = u16::try_from(count).unwrap_or(0);
+            let bin_header = segy.get_bin_header();
+            if fixed {
+                assert_eq!(bin_header.sample_count(), count);
+                assert_eq!(bin_header.no_samples, fits);
+            } else {
+                assert_eq!(bin_header.sample_count(), 0);
+            }
+            let trace = segy.get_trace(0).unwrap();
+            let header = trace.get_header();
+            assert_eq!(header.no_samples_in_trace, fits);
+            assert_eq!(segy.trace_sample_count(0), Some(count));
+            if !fixed {
+                // The count which the input had in the extension is updated.
+                let extension = segy.get_trace_extension_bytes(0).unwrap();
+                assert_eq!(extension[136..140], (count as u32).to_be_bytes());
+            }
+            if !antialias {
+                let expected = data.iter().step_by(factor).cloned().collect::<Vec<_>>();
+                assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), expected);
+            }
+        }
+    }
+}
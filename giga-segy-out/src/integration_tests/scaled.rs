@@ -0,0 +1,167 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that samples which are scaled to fit an integer format with
+//! [`SegyFile::add_trace_scaled`] can be restored when the file is read.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::write_data::ScaleMode;
+use crate::SegyFile;
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+const SAMPLES: usize = 11;
+
+/// The samples of trace `i`, which are well beyond the range of `i16`.
+fn samples(i: usize) -> Vec<f32> {
+    (0..SAMPLES)
+        .map(|s| (s as f32 - 5.) * 12_345.678 * (i + 1) as f32)
+        .collect()
+}
+
+fn create(path: &Path, format: SampleFormatCode) -> SegyFile<SegySettings> {
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A file with scaled samples.",
+        SegySettings::default(),
+        BinHeader::new(0, 4000, SAMPLES as u16, format),
+        Vec::<(TraceHeader, Vec<f32>)>::new(),
+    )
+    .expect("Could not create file.")
+}
+
+/// Write three traces with a given scaling, and return the factor of each trace, as read
+/// from its trace weighting factor.
+fn write_scaled(path: &Path, mode: ScaleMode) -> Vec<f64> {
+    let mut file = create(path, SampleFormatCode::Int16);
+    let mut factors = Vec::new();
+    for i in 0..3 {
+        let header = TraceHeader::new_3d(0, 0, 1, i as i32, 1);
+        let trace = file
+            .add_trace_scaled(header, None, samples(i), mode)
+            .expect("Could not add trace.");
+        let weighting = trace.get_header().trace_weighting_factor as i16;
+        factors.push(2f64.powi(weighting as i32));
+    }
+    file.finalize().unwrap();
+    factors
+}
+
+fn open_weighted(path: &Path) -> giga_segy_in::SegyFile {
+    let mut settings = SegySettings::default();
+    settings.set_apply_trace_weighting(true);
+    giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings).expect("Could not open file.")
+}
+
+/// Check that the samples which are read (and divided by `extra`) are within the quantization
+/// error of those which were written.
+fn check_restored(file: &giga_segy_in::SegyFile, factors: &[f64], extra: f64) {
+    for (i, factor) in factors.iter().enumerate() {
+        let read = file.get_trace_data_as_f32(i).unwrap();
+        for (read, written) in read.iter().zip(samples(i)) {
+            let error = (*read as f64 / extra - written as f64).abs();
+            let quantum = 0.5 / (factor * extra);
+            assert!(
+                error <= quantum + written.abs() as f64 * 1e-6,
+                "trace {}: {} read as {} (factor {})",
+                i,
+                written,
+                read,
+                factor * extra,
+            );
+        }
+    }
+}
+
+#[test]
+fn scaled_auto_per_trace() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("per_trace.sgy");
+    let factors = write_scaled(&path, ScaleMode::AutoPerTrace);
+    // The largest samples are 61728, 123457 and 185185.
+    assert_eq!(factors, vec![0.5, 0.25, 0.125]);
+    check_restored(&open_weighted(&path), &factors, 1.);
+}
+
+#[test]
+fn scaled_auto_per_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("per_file.sgy");
+    let peak = samples(2).iter().fold(0f64, |p, v| p.max(v.abs() as f64));
+    let factors = write_scaled(&path, ScaleMode::AutoPerFile(peak));
+    assert_eq!(factors, vec![0.125; 3]);
+    check_restored(&open_weighted(&path), &factors, 1.);
+}
+
+#[test]
+fn scaled_fixed_power_of_two() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("fixed.sgy");
+    let factors = write_scaled(&path, ScaleMode::Fixed(0.125));
+    assert_eq!(factors, vec![0.125; 3]);
+    check_restored(&open_weighted(&path), &factors, 1.);
+}
+
+#[test]
+fn scaled_fixed_with_note() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("noted.sgy");
+    let factors = write_scaled(&path, ScaleMode::Fixed(0.1));
+    assert_eq!(factors, vec![1.; 3]);
+
+    let file = open_weighted(&path);
+    let note = &file.get_text_header()[3120..];
+    assert_eq!(
+        note.trim_end(),
+        "C40 UNWEIGHTED SAMPLES ARE MULTIPLIED BY 1E-1"
+    );
+    assert!(file
+        .get_text_header()
+        .starts_with("C 1 A file with scaled samples."));
+    check_restored(&file, &factors, 0.1);
+}
+
+#[test]
+fn scaled_rounds_half_to_even() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("rounded.sgy");
+    let mut file = create(&path, SampleFormatCode::Int16);
+    let data = vec![0.5f64, 1.5, 2.5, -0.5, -1.5, -2.5, 2.4, 2.6, -2.6, 0., 7.];
+    let header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+    file.add_trace_scaled(header, None, data, ScaleMode::Fixed(1.))
+        .unwrap();
+    file.finalize().unwrap();
+
+    let read = open_weighted(&path).get_trace_data_as_f32(0).unwrap();
+    assert_eq!(read, vec![0., 2., 2., 0., -2., -2., 2., 3., -3., 0., 7.]);
+}
+
+#[test]
+fn scaled_errors() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+
+    let mut file = create(&dir.path().join("errors.sgy"), SampleFormatCode::Int16);
+    // The samples do not fit without being scaled down.
+    let result = file.add_trace_scaled(header.clone(), None, samples(0), ScaleMode::Fixed(1.));
+    assert!(matches!(result, Err(RsgError::BitConversionError { .. })));
+    for mode in [
+        ScaleMode::Fixed(0.),
+        ScaleMode::Fixed(f64::NAN),
+        ScaleMode::AutoPerFile(-1.),
+    ] {
+        let result = file.add_trace_scaled(header.clone(), None, samples(0), mode);
+        assert!(matches!(result, Err(RsgError::SEGYSettingsError { .. })));
+    }
+    // Only one factor which is not a power of two can be noted.
+    file.add_trace_scaled(header.clone(), None, samples(0), ScaleMode::Fixed(0.1))
+        .unwrap();
+    let result = file.add_trace_scaled(header.clone(), None, samples(0), ScaleMode::Fixed(0.2));
+    assert!(matches!(result, Err(RsgError::SEGYSettingsError { .. })));
+    assert_eq!(file.traces.len(), 1);
+
+    let mut file = create(&dir.path().join("float.sgy"), SampleFormatCode::Float32);
+    let result = file.add_trace_scaled(header, None, samples(0), ScaleMode::AutoPerTrace);
+    assert!(matches!(result, Err(RsgError::SEGYSettingsError { .. })));
+}
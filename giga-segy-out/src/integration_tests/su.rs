@@ -0,0 +1,190 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we read Seismic Unix files with `giga_segy_in` and write them back, and write SU files
+//! from SEG-Y files.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::su::SuWriter;
+
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use giga_segy_in::su::SuFile;
+use std::path::Path;
+
+/// Make the bytes of an SU file by hand, with `ns` samples per trace, in the byte order of the
+/// machine.
+fn su_bytes(trace_count: i32, ns: u16) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for i in 0..trace_count {
+        let mut header = vec![0; 240];
+        header[0..4].copy_from_slice(&(i + 1).to_ne_bytes()); // tracl
+        header[8..12].copy_from_slice(&7i32.to_ne_bytes()); // fldr
+        header[20..24].copy_from_slice(&(100 + i).to_ne_bytes()); // cdp
+        header[70..72].copy_from_slice(&(-10i16).to_ne_bytes()); // scalco
+        header[72..76].copy_from_slice(&(5000 + 250 * i).to_ne_bytes()); // sx
+        header[114..116].copy_from_slice(&ns.to_ne_bytes());
+        header[116..118].copy_from_slice(&2000u16.to_ne_bytes());
+        bytes.extend_from_slice(&header);
+        for s in 0..ns {
+            bytes.extend_from_slice(&(i as f32 * 10. + s as f32 / 4.).to_ne_bytes());
+        }
+    }
+    bytes
+}
+
+fn open_su(path: &Path, settings: SegySettings) -> SuFile {
+    SuFile::open(path.to_str().unwrap(), settings).expect("Could not open")
+}
+
+/// Write all of the traces of an SU file to a new one.
+fn copy_su(input: &SuFile, output: &Path, settings: SegySettings) {
+    let mut writer = SuWriter::create(output, settings).unwrap();
+    for i in 0..input.trace_count() {
+        let trace = input.load_trace(i).unwrap();
+        let data = input.get_trace_data_as_f32(i).unwrap();
+        writer.add_trace(trace.get_header().clone(), &data).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), input.trace_count());
+}
+
+#[test]
+fn su_roundtrip() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let original = dir.path().join("original.su");
+    let copy = dir.path().join("copy.su");
+    let bytes = su_bytes(4, 30);
+    std::fs::write(&original, &bytes).unwrap();
+
+    let su = open_su(&original, SegySettings::default());
+    assert!(su.get_warnings().is_empty(), "{:?}", su.get_warnings());
+    assert_eq!(su.is_le(), cfg!(target_endian = "little"));
+    assert_eq!(su.trace_count(), 4);
+    let bin_header = su.get_bin_header();
+    assert_eq!(bin_header.no_samples, 30);
+    assert_eq!(bin_header.sample_interval, 2000);
+    assert_eq!(bin_header.sample_format_code, SampleFormatCode::Float32);
+
+    let trace = su.get_trace(2).unwrap();
+    let header = trace.get_header();
+    assert_eq!(header.trace_sequence_on_line, 3);
+    assert_eq!(header.field_record_no, 7);
+    assert_eq!(header.ensemble_no, 102);
+    assert_eq!(header.coordinate_scalar, -10);
+    assert_eq!(header.source_x, 5500);
+    assert_eq!(su.get_trace_header_bytes(2).unwrap(), &bytes[720..960]);
+    let data = su.get_trace_data_as_f32(2).unwrap();
+    assert_eq!(data.len(), 30);
+    assert_eq!(data[0], 20.);
+    assert_eq!(data[29], 27.25);
+
+    copy_su(&su, &copy, SegySettings::default());
+    assert_eq!(std::fs::read(&copy).unwrap(), bytes);
+}
+
+#[test]
+fn su_foreign_byte_order() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let original = dir.path().join("original.su");
+    let swapped = dir.path().join("swapped.su");
+    std::fs::write(&original, su_bytes(3, 12)).unwrap();
+    let su = open_su(&original, SegySettings::default());
+
+    let foreign = !cfg!(target_endian = "little");
+    let mut settings = SegySettings::default();
+    settings.set_override_to_le(foreign);
+    copy_su(&su, &swapped, settings.clone());
+
+    // The byte order is found from the sample count of the first trace.
+    let read = open_su(&swapped, SegySettings::default());
+    assert_eq!(read.is_le(), foreign);
+    assert_eq!(read.get_warnings().len(), 1);
+    assert_eq!(read.get_bin_header().no_samples, 12);
+    for i in 0..3 {
+        assert_eq!(
+            read.load_trace(i).unwrap().get_header(),
+            su.load_trace(i).unwrap().get_header()
+        );
+        assert_eq!(
+            read.get_trace_data_as_f32(i).unwrap(),
+            su.get_trace_data_as_f32(i).unwrap()
+        );
+    }
+    let read = open_su(&swapped, settings);
+    assert!(read.get_warnings().is_empty());
+    assert_eq!(read.get_trace_data_as_f32(1).unwrap()[4], 11.);
+}
+
+#[test]
+fn su_from_segy() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let segy_path = dir.path().join("source.sgy");
+    let su_path = dir.path().join("converted.su");
+
+    let traces = (0..5).map(|i| {
+        let mut header = TraceHeader::new_3d(1000 + i, 2000 - i, 10, 20 + i, -100);
+        header.sample_interval_of_trace = 4000;
+        let data = (0..20).map(|s| (i * 100 + s) as f32).collect::<Vec<_>>();
+        (header, data)
+    });
+    let mut file = write_fixture(
+        &segy_path,
+        CreateMode::New,
+        "C 1 A file to convert to SU.",
+        SegySettings::default(),
+        BinHeader::new(0, 4000, 20, SampleFormatCode::Float32),
+        traces,
+    )
+    .unwrap();
+    file.finalize().unwrap();
+    drop(file);
+
+    let segy =
+        giga_segy_in::SegyFile::open(segy_path.to_str().unwrap(), SegySettings::default()).unwrap();
+    let mut writer = SuWriter::create(&su_path, SegySettings::default()).unwrap();
+    for trace in segy.traces_iter() {
+        let data = segy.get_trace_data_as_f32_from_trace(&trace).unwrap();
+        writer.add_trace(trace.get_header().clone(), &data).unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(
+        std::fs::metadata(&su_path).unwrap().len(),
+        5 * (240 + 20 * 4)
+    );
+
+    let su = open_su(&su_path, SegySettings::default());
+    assert_eq!(su.trace_count(), 5);
+    assert_eq!(su.get_bin_header().sample_interval, 4000);
+    assert_eq!(su.get_bin_header().no_samples, 20);
+    for (a, b) in segy.traces_iter().zip(su.traces_iter()) {
+        assert_eq!(a.get_header(), b.get_header());
+        assert_eq!(
+            segy.get_trace_data_as_f32_from_trace(&a).unwrap(),
+            su.get_trace_data_as_f32_from_trace(&b).unwrap()
+        );
+    }
+}
+
+#[test]
+fn su_errors() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("no_samples.su");
+    std::fs::write(&path, su_bytes(2, 0)).unwrap();
+    let res = SuFile::open(path.to_str().unwrap(), SegySettings::default());
+    assert!(matches!(res, Err(RsgError::InvalidHeader { .. })));
+
+    let path = dir.path().join("short.su");
+    std::fs::write(&path, [0; 100]).unwrap();
+    let res = SuFile::open(path.to_str().unwrap(), SegySettings::default());
+    assert!(matches!(res, Err(RsgError::FileTooShort)));
+
+    let path = dir.path().join("mismatch.su");
+    let mut writer = SuWriter::create(&path, SegySettings::default()).unwrap();
+    let mut header = TraceHeader::new_2d(0, 0, 1);
+    header.no_samples_in_trace = 5;
+    let res = writer.add_trace(header, &[0.; 4]);
+    assert!(matches!(
+        res,
+        Err(RsgError::TraceSampleCountMismatch { trace: 0, .. })
+    ));
+    assert!(SuWriter::create(&path, SegySettings::default()).is_err());
+}
@@ -0,0 +1,250 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we crop a small survey, both horizontally and vertically, and read the result back.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::subset::{crop, crop_with_progress, crop_with_window};
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::io::{Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Write a 10x10 survey with inlines and crosslines numbered from 1 to 10 and 20 samples
+/// per trace. The value of each sample is `inline * 1000 + crossline * 10 + sample_idx`.
+fn write_survey(path: &Path) {
+    write_survey_with_interval(path, 2000);
+}
+
+/// Write the survey of [`write_survey`] with a sample interval of `interval` microseconds.
+fn write_survey_with_interval(path: &Path, interval: u16) {
+    let traces = (1..=10).flat_map(|il| {
+        (1..=10).map(move |xl| {
+            let mut trace_header = TraceHeader::new_3d(xl, il, il, xl, 1);
+            trace_header.no_samples_in_trace = 20;
+            let data = (0..20)
+                .map(|s| (il * 1000 + xl * 10 + s) as f32)
+                .collect::<Vec<f32>>();
+            (trace_header, data)
+        })
+    });
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A 10x10 survey",
+        SegySettings::default(),
+        BinHeader::new(100, interval, 20, SampleFormatCode::Float32),
+        traces,
+    )
+    .expect("Could not create file.");
+}
+
+#[test]
+fn crop_survey() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    let output = dir.path().join("cropped.sgy");
+    write_survey(&input);
+
+    let count = crop(&input, &output, [3, 6], [5, 8], SegySettings::default()).unwrap();
+    assert_eq!(count, 16);
+
+    let original = giga_segy_in::SegyFile::open(input.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    let cropped = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(cropped.get_text_header(), original.get_text_header());
+    assert_eq!(cropped.get_bin_header().no_traces, 16);
+    assert_eq!(cropped.trace_count(), 16);
+
+    for trace in cropped.traces_iter() {
+        let header = trace.get_header();
+        assert!((3..=6).contains(&header.inline_no));
+        assert!((5..=8).contains(&header.crossline_no));
+        let source = original
+            .get_trace_by_xline_inline(header.crossline_no, header.inline_no)
+            .unwrap();
+        assert_eq!(header, source.get_header());
+        assert_eq!(
+            cropped.get_trace_data_as_bytes_from_trace(&trace).unwrap(),
+            original
+                .get_trace_data_as_bytes_from_trace(&source)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn crop_survey_vertically() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    let output = dir.path().join("cropped.sgy");
+    write_survey(&input);
+
+    let count = crop_with_window(
+        &input,
+        &output,
+        [1, 10],
+        [2, 2],
+        5..12,
+        SegySettings::default(),
+    )
+    .unwrap();
+    assert_eq!(count, 10);
+
+    let cropped = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(cropped.get_bin_header().no_traces, 10);
+    assert_eq!(cropped.get_bin_header().no_samples, 7);
+    assert_eq!(cropped.trace_count(), 10);
+
+    for (i, trace) in cropped.traces_iter().enumerate() {
+        let header = trace.get_header();
+        let il = i as i32 + 1;
+        assert_eq!([header.inline_no, header.crossline_no], [il, 2]);
+        assert_eq!(header.no_samples_in_trace, 7);
+        // 5 samples at 2ms.
+        assert_eq!(header.delay_recording_time, 10);
+
+        let data = cropped.get_trace_data_as_f32_from_trace(&trace).unwrap();
+        let expected = (5..12)
+            .map(|s| (il * 1000 + 20 + s) as f32)
+            .collect::<Vec<f32>>();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn crop_survey_cancelled() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    let output = dir.path().join("cropped.sgy");
+    write_survey(&input);
+
+    let mut last_done = 0;
+    let result = crop_with_progress(
+        &input,
+        &output,
+        [1, 10],
+        [1, 10],
+        None,
+        SegySettings::default(),
+        |p| {
+            last_done = p.traces_done;
+            ControlFlow::Break(())
+        },
+    );
+    assert!(matches!(result, Err(RsgError::Cancelled)));
+    assert_eq!(last_done, 100);
+    assert!(!output.exists());
+}
+
+#[test]
+fn crop_ibm_survey_vertically() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    let output = dir.path().join("cropped.sgy");
+    write_survey(&input);
+    // The samples are now read as IBM floats, which can not be written from f32 values.
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&input)
+        .unwrap();
+    file.seek(SeekFrom::Start(3200 + 24)).unwrap();
+    file.write_all(&1u16.to_be_bytes()).unwrap();
+    drop(file);
+
+    let count = crop_with_window(
+        &input,
+        &output,
+        [1, 10],
+        [2, 2],
+        5..12,
+        SegySettings::default(),
+    )
+    .unwrap();
+    assert_eq!(count, 10);
+
+    let original = giga_segy_in::SegyFile::open(input.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    let cropped = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    assert_eq!(
+        cropped.get_bin_header().sample_format_code,
+        SampleFormatCode::IbmFloat32
+    );
+    for trace in cropped.traces_iter() {
+        let header = trace.get_header();
+        let source = original
+            .get_trace_by_xline_inline(header.crossline_no, header.inline_no)
+            .unwrap();
+        let bytes = original
+            .get_trace_data_as_bytes_from_trace(&source)
+            .unwrap();
+        assert_eq!(
+            cropped.get_trace_data_as_bytes_from_trace(&trace).unwrap(),
+            bytes[(5 * 4)..(12 * 4)].to_vec()
+        );
+    }
+}
+
+#[test]
+fn crop_window_with_fractional_interval() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    let output = dir.path().join("cropped.sgy");
+    write_survey_with_interval(&input, 2500);
+
+    crop_with_window(
+        &input,
+        &output,
+        [1, 1],
+        [1, 1],
+        3..10,
+        SegySettings::default(),
+    )
+    .unwrap();
+    let cropped = giga_segy_in::SegyFile::open(output.to_str().unwrap(), SegySettings::default())
+        .expect("Could not reopen");
+    // 3 samples at 2.5ms is 7.5ms, which is rounded rather than cut off.
+    assert_eq!(
+        cropped
+            .load_trace(0)
+            .unwrap()
+            .get_header()
+            .delay_recording_time,
+        8
+    );
+}
+
+#[test]
+fn crop_ignores_step_by_and_sample_override() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let input = dir.path().join("survey.sgy");
+    write_survey(&input);
+    let mut settings = SegySettings::default();
+    settings.set_step_by(3);
+    settings.set_override_dim_z(4).unwrap();
+
+    let whole = dir.path().join("whole.sgy");
+    crop(&input, &whole, [1, 2], [1, 2], settings.clone()).unwrap();
+    let window = dir.path().join("window.sgy");
+    crop_with_window(&input, &window, [1, 2], [1, 2], 5..12, settings).unwrap();
+
+    for (path, samples) in [(whole, 0..20), (window, 5..12)] {
+        let cropped = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+            .expect("Could not reopen");
+        assert_eq!(cropped.trace_count(), 4);
+        for trace in cropped.traces_iter() {
+            let header = trace.get_header();
+            assert_eq!(header.no_samples_in_trace as usize, samples.len());
+            let data = cropped.get_trace_data_as_f32_from_trace(&trace).unwrap();
+            let expected = samples
+                .clone()
+                .map(|s| (header.inline_no * 1000 + header.crossline_no * 10 + s) as f32)
+                .collect::<Vec<f32>>();
+            assert_eq!(data, expected);
+        }
+    }
+}
@@ -307,6 +307,8 @@ fn read_write_survey_line_bidx_shifts() {
     let mut settings = SegySettings::default();
     settings.set_inline_no_bidx(100).expect("It's fine.");
     settings.set_crossline_no_bidx(104).expect("It's fine.");
+    // These overwrite other fields of the trace header, which must be allowed.
+    settings.set_allow_bidx_collisions(true);
 
     test_write_survey_inner(survey, settings, true, true, Sac::Float32, 100.);
 }
@@ -323,6 +325,8 @@ fn read_write_survey_ensemble_bidx_shifts() {
     let mut settings = SegySettings::default();
     settings.set_x_ensemble_bidx(100).expect("It's fine.");
     settings.set_y_ensemble_bidx(104).expect("It's fine.");
+    // These overwrite other fields of the trace header, which must be allowed.
+    settings.set_allow_bidx_collisions(true);
 
     test_write_survey_inner(survey, settings, true, true, Sac::Float32, 100.);
 }
@@ -343,6 +347,8 @@ fn header_test_bidx_shifts() {
     settings.set_y_ensemble_bidx(100).expect("It's fine.");
     settings.set_inline_no_bidx(104).expect("It's fine.");
     settings.set_crossline_no_bidx(108).expect("It's fine.");
+    // These overwrite other fields of the trace header, which must be allowed.
+    settings.set_allow_bidx_collisions(true);
 
     let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
     {
@@ -461,3 +467,47 @@ fn read_write_survey_check_headers_default_u16() {
     let sample_format = Sac::UInt16;
     header_tests_inner(settings, sample_format);
 }
+
+/// Write the same survey as LE and as BE and check that both are read identically.
+#[test]
+fn read_write_survey_le_and_be() {
+    let survey = create_survey(
+        "my_survey",
+        "I like surveys, and this one is pretty nifty I think.",
+        5.,
+        generate_coords(10, 10),
+        200,
+    );
+
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let mut read_surveys = Vec::new();
+    for (le, flag) in [(true, [1, 2, 3, 4]), (false, [4, 3, 2, 1])].iter() {
+        let path = dir.path().join(if *le { "le" } else { "be" });
+        std::fs::create_dir(&path).expect("Couldn't create dir.");
+        let final_name = path.join(&survey.name).with_extension("sgy");
+        let path_str = final_name.to_str().expect("Couldn't string the path.");
+
+        let mut settings = SegySettings::default();
+        settings.set_override_to_le(*le);
+        let written = survey
+            .write(&path, Sac::Float32, settings, 100.)
+            .expect("Could not write survey");
+        assert_eq!(
+            written
+                .metadata
+                .get_bin_header()
+                .binary_flag_direction_is_le,
+            *le
+        );
+
+        let bytes = std::fs::read(&final_name).expect("Could not read file back.");
+        assert_eq!(&bytes[3296..3300], flag);
+
+        // The endianness should be detected without the override.
+        let read = Survey::read(path_str, "my_survey", SegySettings::default())
+            .expect("Couldn't read SEGY survey.");
+        check(&read, &survey, true, true);
+        read_surveys.push(read);
+    }
+    assert_eq!(read_surveys[0], read_surveys[1]);
+}
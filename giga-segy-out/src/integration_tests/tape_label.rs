@@ -0,0 +1,146 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that tape labels are written where the standard puts each field, and that
+//! they are read back unchanged.
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader, TapeLabelBuilder};
+use crate::write_headers::SegyHeaderToBytes;
+use crate::SegyFile;
+
+use giga_segy_in::convert_headers::HeaderFromBytes;
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader, TAPE_LABEL_LEN};
+
+fn test_label() -> TapeLabelBuilder {
+    TapeLabelBuilder::new()
+        .storage_unit_seq_no("7")
+        .max_block_size(32768)
+        .producing_organisation_code("GIGA")
+        .creation_date("08-SEP-2022")
+        .serial_number("SN-0001")
+        .external_label("SURVEY-42")
+        .recording_entity("Research Vessel")
+        .extra("user data")
+}
+
+#[test]
+fn tape_label_layout() {
+    let bytes = test_label().build().unwrap().as_bytes().unwrap();
+    assert_eq!(bytes.len(), TAPE_LABEL_LEN);
+    assert_eq!(&bytes[0..4], b"7   ");
+    assert_eq!(&bytes[4..9], b"SY2.0");
+    assert_eq!(&bytes[9..15], b"RECORD");
+    assert_eq!(&bytes[15..19], b"BXXX");
+    assert_eq!(&bytes[19..29], b"     32768");
+    assert_eq!(&bytes[29..39], b"GIGA      ");
+    assert_eq!(&bytes[39..50], b"08-SEP-2022");
+    assert_eq!(&bytes[50..62], b"SN-0001     ");
+    assert_eq!(&bytes[62..68], b"      ");
+    assert_eq!(&bytes[68..80], b"SURVEY-42   ");
+    assert_eq!(&bytes[80..104], b"Research Vessel         ");
+    assert_eq!(&bytes[104..118], b"user data     ");
+    assert_eq!(&bytes[118..128], b"          ");
+}
+
+#[test]
+fn tape_label_too_long() {
+    let err = test_label().recording_entity(&"x".repeat(25)).build();
+    match err {
+        Err(RsgError::InvalidHeader { msg }) => assert!(msg.contains("recording_entity")),
+        e => panic!("Unexpected result: {:?}", e),
+    }
+    let err = test_label().creation_date("8. Sept. 2022").build();
+    match err {
+        Err(RsgError::InvalidHeader { msg }) => assert!(msg.contains("creation_date")),
+        e => panic!("Unexpected result: {:?}", e),
+    }
+    assert!(test_label().extra("Grüße").build().is_err());
+}
+
+#[test]
+fn tape_label_default_date() {
+    let label = TapeLabelBuilder::new().build().unwrap().to_readable();
+    let date = label.creation_date.as_bytes();
+    assert_eq!(date.len(), 11);
+    assert!(date[..2].iter().all(u8::is_ascii_digit));
+    assert_eq!([date[2], date[6]], [b'-', b'-']);
+    assert!(date[3..6].iter().all(u8::is_ascii_uppercase));
+    assert!(date[7..].iter().all(u8::is_ascii_digit));
+}
+
+#[test]
+fn tape_label_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("labelled.sgy");
+    let label = test_label().build().unwrap();
+
+    let bin_header = BinHeader::new(2, 4000, 3, SampleFormatCode::Float32);
+    let mut file = SegyFile::<SegySettings>::create_file(
+        &path,
+        SegySettings::default(),
+        "C 1 A file with a tape label.".to_string(),
+        bin_header,
+        Some(label.clone()),
+    )
+    .unwrap();
+    for i in 0..2 {
+        let header = TraceHeader::new_3d(i * 10, 0, i, 1, 1);
+        file.add_trace(header, None, vec![1.0f32, 2.0, 3.0])
+            .unwrap();
+    }
+    drop(file);
+
+    let input = giga_segy_in::SegyFile::open(path.to_str().unwrap(), Default::default()).unwrap();
+    assert_eq!(input.get_tape_label().as_ref(), Some(&label));
+    assert_eq!(input.get_readable_tape_label(), Some(label.to_readable()));
+    assert_eq!(label.to_readable().to_tape_label().unwrap(), label,);
+    assert_eq!(input.trace_count(), 2);
+    assert_eq!(
+        input
+            .get_trace_data_as_f32_from_trace(&input.get_trace(1).unwrap())
+            .unwrap(),
+        vec![1.0, 2.0, 3.0]
+    );
+}
+
+/// Check that a tape label which is read from `bytes` is written back to the same bytes.
+fn assert_bytes_roundtrip(bytes: &[u8]) {
+    let label = giga_segy_core::TapeLabel::from_bytes(bytes, &SegySettings::default()).unwrap();
+    assert_eq!(label.as_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn tape_label_bytes_roundtrip() {
+    let bytes = test_label().build().unwrap().as_bytes().unwrap();
+    assert_bytes_roundtrip(&bytes);
+
+    // Every field is filled to its full length, and the block size has all ten digits.
+    let mut bytes = Vec::with_capacity(TAPE_LABEL_LEN);
+    bytes.extend_from_slice(b"9999");
+    bytes.extend_from_slice(b"SY2.1");
+    bytes.extend_from_slice(b"RECORD");
+    bytes.extend_from_slice(b"B123");
+    bytes.extend_from_slice(u32::MAX.to_string().as_bytes());
+    bytes.extend_from_slice(b"ORGANISATN");
+    bytes.extend_from_slice(b"31-DEC-2099");
+    bytes.extend_from_slice(b"SERIAL-NO-12");
+    bytes.extend_from_slice(b"      ");
+    bytes.extend_from_slice(b"EXTERNAL-LBL");
+    bytes.extend_from_slice(b"RECORDING-ENTITY-NAME-24");
+    bytes.extend_from_slice(b"USER-DEFINED14");
+    bytes.extend_from_slice(b"          ");
+    assert_eq!(bytes.len(), TAPE_LABEL_LEN);
+    assert_bytes_roundtrip(&bytes);
+}
+
+#[test]
+fn tape_label_wrong_length() {
+    let bytes = test_label().build().unwrap().as_bytes().unwrap();
+    let settings = SegySettings::default();
+    for len in [0, 68, TAPE_LABEL_LEN - 1] {
+        match giga_segy_core::TapeLabel::from_bytes(&bytes[..len], &settings) {
+            Err(RsgError::InvalidHeader { msg }) => assert!(msg.contains("length")),
+            e => panic!("Unexpected result: {:?}", e),
+        }
+    }
+}
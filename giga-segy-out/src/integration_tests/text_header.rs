@@ -0,0 +1,150 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that text headers are brought into card image form, and that this happens
+//! when a file is created with a strict text header.
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::text_header::*;
+use crate::SegyFile;
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+
+#[test]
+fn text_header_with_newlines() {
+    let header = normalize_text_header("First line\r\nSecond\tline\n\nFourth line\u{7}").unwrap();
+    assert_eq!(header.len(), 3200);
+    assert!(header.is_ascii());
+    let lines = header
+        .as_bytes()
+        .chunks(80)
+        .map(|l| std::str::from_utf8(l).unwrap().trim_end())
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 40);
+    assert_eq!(lines[..4], ["First line", "Second line", "", "Fourth line"]);
+    assert!(lines[4..].iter().all(|l| l.is_empty()));
+}
+
+#[test]
+fn text_header_raw() {
+    let raw = "x".repeat(100);
+    let header = normalize_text_header(&raw).unwrap();
+    assert_eq!(&header[..100], raw);
+    assert!(header[100..].chars().all(|c| c == ' '));
+
+    let err = normalize_text_header(&"x".repeat(3201)).unwrap_err();
+    assert!(matches!(err, RsgError::InvalidHeader { .. }));
+}
+
+#[test]
+fn text_header_line_too_long() {
+    let input = format!("A short line\n{}", "y".repeat(81));
+    let err = normalize_text_header(&input).unwrap_err();
+    assert!(err.to_string().contains("Line 2 is too long"));
+
+    // With numbering, only 76 characters are left for the content.
+    let opts = TextHeaderOptions {
+        number_lines: true,
+        ..Default::default()
+    };
+    assert!(normalize_text_header_lines(&["z".repeat(76)], opts).is_ok());
+    assert!(normalize_text_header_lines(&["z".repeat(77)], opts).is_err());
+}
+
+#[test]
+fn text_header_too_many_lines() {
+    let lines = (0..41).map(|i| format!("Line {}", i)).collect::<Vec<_>>();
+    let err = normalize_text_header_lines(&lines, Default::default()).unwrap_err();
+    assert!(err.to_string().contains("Too many lines: 41"));
+    assert!(normalize_text_header_lines(&lines[..40], Default::default()).is_ok());
+}
+
+#[test]
+fn text_header_numbering() {
+    let opts = TextHeaderOptions {
+        number_lines: true,
+        ..Default::default()
+    };
+    let header = normalize_text_header_lines(&["Survey", "Client"], opts).unwrap();
+    assert_eq!(header[..80].trim_end(), "C 1 Survey");
+    assert_eq!(header[80..160].trim_end(), "C 2 Client");
+    assert_eq!(header[3120..].trim_end(), "C40");
+}
+
+#[test]
+fn text_header_renumbering() {
+    let opts = TextHeaderOptions {
+        number_lines: true,
+        ..Default::default()
+    };
+    let lines = [
+        "C 1 Survey",
+        "C02 Client",
+        "C3 Contractor",
+        "C 9",
+        "C 3D survey",
+        "CDP bins",
+        &format!("C 7 {}", "z".repeat(76)),
+    ];
+    let header = normalize_text_header_lines(&lines, opts).unwrap();
+    let cards = header
+        .as_bytes()
+        .chunks(80)
+        .map(|c| std::str::from_utf8(c).unwrap().trim_end())
+        .collect::<Vec<_>>();
+    assert_eq!(cards[0], "C 1 Survey");
+    assert_eq!(cards[1], "C 2 Client");
+    assert_eq!(cards[2], "C 3 Contractor");
+    assert_eq!(cards[3], "C 4");
+    assert_eq!(cards[4], "C 5 C 3D survey");
+    assert_eq!(cards[5], "C 6 CDP bins");
+    assert_eq!(cards[6], format!("C 7 {}", "z".repeat(76)));
+
+    // A raw header which is already numbered is split into cards of 80 characters.
+    let raw = (1..=40)
+        .map(|i| format!("{:<80}", format!("C{:2} Card {}", i, i)))
+        .collect::<String>();
+    let header = normalize_text_header_with(&raw, opts).unwrap();
+    assert_eq!(header, raw);
+}
+
+#[test]
+fn text_header_non_ascii() {
+    let err = normalize_text_header("Schöne Grüße").unwrap_err();
+    assert!(matches!(err, RsgError::InvalidHeader { .. }));
+
+    let opts = TextHeaderOptions {
+        transliterate: true,
+        ..Default::default()
+    };
+    let header = normalize_text_header_with("Schöne Grüße 東", opts).unwrap();
+    assert_eq!(header[..80].trim_end(), "Schone Gruse ?");
+}
+
+#[test]
+fn create_file_with_strict_text_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin_header = BinHeader::new(0, 4000, 10, SampleFormatCode::Float32);
+
+    let mut settings = SegySettings::default();
+    settings.set_strict_text_header(true);
+    let path = dir.path().join("strict.sgy");
+    let mut file = SegyFile::create_file(
+        &path,
+        settings.clone(),
+        "C 1 Line one\nC 2 Line two".to_string(),
+        bin_header.clone(),
+        None,
+    )
+    .unwrap();
+    file.add_trace(TraceHeader::new_2d(0, 0, 1), None, vec![0f32; 10])
+        .unwrap();
+    drop(file);
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), settings.clone()).unwrap();
+    let lines = segy.get_text_header_lines();
+    assert_eq!(lines[0].trim_end(), "C 1 Line one");
+    assert_eq!(lines[1].trim_end(), "C 2 Line two");
+
+    let path = dir.path().join("invalid.sgy");
+    let res = SegyFile::create_file(&path, settings, "Ünïcödé".to_string(), bin_header, None);
+    assert!(res.is_err());
+}
@@ -0,0 +1,207 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that the number of traces in the binary header is consistent with the traces
+//! that were written, even if there are more traces than it can count, and that traces are
+//! numbered when the settings ask for it.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::SegyFile;
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Create a file with one sample per trace and add `count` traces to it.
+fn write_tiny_traces(
+    path: &Path,
+    settings: SegySettings,
+    count: usize,
+) -> Result<SegyFile<SegySettings>, RsgError> {
+    let traces = (0..count).map(|i| {
+        let mut header = TraceHeader::new_2d(i as i32, 0, 1);
+        header.no_samples_in_trace = 1;
+        (header, vec![(i % 100) as i8])
+    });
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A file with many tiny traces.",
+        settings,
+        BinHeader::new(0, 4000, 1, SampleFormatCode::Int8),
+        traces,
+    )
+}
+
+#[test]
+fn finalize_small_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("small.sgy");
+
+    let mut file = write_tiny_traces(&path, SegySettings::default(), 10).unwrap();
+    assert_eq!(file.finalize().unwrap(), 10);
+    assert_eq!(file.metadata.get_bin_header().no_traces, 10);
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.get_bin_header().no_traces, 10);
+    assert_eq!(segy.trace_count(), 10);
+}
+
+#[test]
+fn finalize_updates_raw_bin_header() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("raw.sgy");
+
+    let mut file = write_tiny_traces(&path, SegySettings::default(), 10).unwrap();
+    file.set_raw_bin_header([0xAA; 400]).unwrap();
+    assert_eq!(file.finalize().unwrap(), 10);
+    let raw = *file.metadata.get_raw_bin_header().unwrap();
+    drop(file);
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(raw[12..14], [0, 10]);
+    assert_eq!(raw[60..64], [0; 4]);
+    assert_eq!(raw[312..320], 10u64.to_be_bytes());
+    for range in [12..14, 60..64, 312..320] {
+        assert_eq!(
+            raw[range.clone()],
+            bytes[(3200 + range.start)..(3200 + range.end)]
+        );
+    }
+    // The bytes which do not belong to a field are kept.
+    assert_eq!(raw[200], 0xAA);
+}
+
+#[test]
+fn finalize_keeps_no_traces_in_file_before_rev_2() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("rev1.sgy");
+
+    let mut file = write_tiny_traces(&path, SegySettings::default(), 10).unwrap();
+    // SEG-Y revision 1.0 has no number of traces in the file.
+    file.metadata.bin_header.segy_revision_number = [1, 0];
+    file.set_raw_bin_header([0xAA; 400]).unwrap();
+    let before = std::fs::read(&path).unwrap()[3512..3520].to_vec();
+    assert_eq!(file.finalize().unwrap(), 10);
+    assert_eq!(file.metadata.get_bin_header().no_traces_in_file, 0);
+    assert_eq!(
+        file.metadata.get_raw_bin_header().unwrap()[312..320],
+        [0xAA; 8]
+    );
+    drop(file);
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[3212..3214], [0, 10]);
+    assert_eq!(bytes[3512..3520], before[..]);
+}
+
+#[test]
+fn too_many_traces() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("too_many.sgy");
+
+    let res = write_tiny_traces(&path, SegySettings::default(), u16::MAX as usize + 1);
+    assert!(matches!(res, Err(RsgError::TooManyTraces { max: 65535 })));
+}
+
+#[test]
+fn extended_trace_count() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("extended.sgy");
+
+    let mut settings = SegySettings::default();
+    settings.set_extended_trace_count(true);
+    let mut file = write_tiny_traces(&path, settings, 70_000).unwrap();
+    assert_eq!(file.finalize().unwrap(), 70_000);
+    assert_eq!(file.metadata.get_bin_header().no_traces, 0);
+    drop(file);
+
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes[3212..3214], [0, 0]);
+    assert_eq!(bytes[3260..3264], 70_000u32.to_be_bytes());
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 70_000);
+    let last = segy.get_trace(69_999).unwrap();
+    assert_eq!(last.get_header().x_ensemble, 69_999);
+    assert_eq!(segy.get_trace_data_as_f32(69_999).unwrap(), vec![99.]);
+}
+
+#[test]
+fn auto_sequence() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("sequence.sgy");
+
+    let mut settings = SegySettings::default();
+    settings.set_auto_sequence(true);
+    let mut file = write_tiny_traces(&path, settings, 5).unwrap();
+    // Numbers which are set explicitly are kept.
+    let mut header = TraceHeader::new_2d(5, 0, 1);
+    header.no_samples_in_trace = 1;
+    header.trace_no = 42;
+    file.add_trace_lossless(header, None, vec![5i8]).unwrap();
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    let numbers = segy
+        .traces_iter()
+        .map(|t| {
+            let h = t.get_header();
+            (
+                h.trace_sequence_on_line,
+                h.trace_sequence_in_file,
+                h.trace_no,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        numbers,
+        vec![
+            (1, 1, 1),
+            (2, 2, 2),
+            (3, 3, 3),
+            (4, 4, 4),
+            (5, 5, 5),
+            (6, 6, 42)
+        ]
+    );
+}
+
+#[test]
+fn no_auto_sequence_by_default() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("no_sequence.sgy");
+
+    drop(write_tiny_traces(&path, SegySettings::default(), 3).unwrap());
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert!(segy
+        .traces_iter()
+        .all(|t| t.get_header().trace_sequence_in_file == 0 && t.get_header().trace_no == 0));
+}
+
+#[test]
+fn finalize_empty_file() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("empty.sgy");
+
+    let mut file = write_tiny_traces(&path, SegySettings::default(), 0).unwrap();
+    assert_eq!(file.finalize().unwrap(), 0);
+    assert_eq!(file.metadata.get_bin_header().no_traces, 0);
+    drop(file);
+    let len = std::fs::metadata(&path).unwrap().len() as usize;
+    assert_eq!(
+        len,
+        giga_segy_core::TEXT_HEADER_LEN + giga_segy_core::BIN_HEADER_LEN
+    );
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.get_bin_header().no_traces, 0);
+    assert_eq!(segy.get_bin_header().no_traces_in_file, 0);
+    assert_eq!(segy.trace_count(), 0);
+    assert!(segy.is_empty());
+    assert!(segy.get_warnings().is_empty());
+}
@@ -0,0 +1,108 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we check that the extended header which is written with each trace is refused unless it
+//! fills the trace header extensions of the binary header, so that readers find the trace data.
+use super::{write_fixture, CreateMode};
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::SegyFile;
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+use std::path::Path;
+
+/// Create a file with three samples per trace and the given number of trace header extensions.
+fn create_with_extensions(path: &Path, extensions: u32) -> SegyFile<SegySettings> {
+    let mut bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Float32);
+    bin_header.max_additional_trace_headers = extensions;
+    write_fixture(
+        path,
+        CreateMode::New,
+        "C 1 A file with trace header extensions.",
+        SegySettings::default(),
+        bin_header,
+        Vec::<(TraceHeader, Vec<f32>)>::new(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn extended_header_without_extensions_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("refused.sgy");
+    let mut file = create_with_extensions(&path, 0);
+    let header = TraceHeader::new_3d(0, 0, 1, 1, 1);
+
+    let err = file
+        .add_trace(header.clone(), Some("A note".to_string()), vec![1f32; 3])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::TraceExtensionLength {
+            trace: 0,
+            len: 6,
+            expected: 0
+        }
+    ));
+    assert_eq!(err.code(), 31);
+
+    // Nothing was written, so the next trace is still the first one.
+    file.add_trace(header, Some(String::new()), vec![1f32; 3])
+        .unwrap();
+    assert_eq!(file.traces.len(), 1);
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 1);
+    assert_eq!(segy.get_trace_data_as_f32(0).unwrap(), vec![1.; 3]);
+}
+
+#[test]
+fn extended_header_must_fill_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("extensions.sgy");
+    let mut file = create_with_extensions(&path, 1);
+
+    let short = "x".repeat(200);
+    let err = file
+        .add_trace(
+            TraceHeader::new_3d(0, 0, 1, 1, 1),
+            Some(short),
+            vec![1f32; 3],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RsgError::TraceExtensionLength {
+            len: 200,
+            expected: 240,
+            ..
+        }
+    ));
+
+    // A missing extended header leaves the extensions empty.
+    for i in 0..3 {
+        let extension = match i {
+            1 => None,
+            _ => Some(format!("{:<240}", format!("Extension {}", i))),
+        };
+        file.add_trace(
+            TraceHeader::new_3d(0, 0, 1, i, 1),
+            extension,
+            vec![i as f32; 3],
+        )
+        .unwrap();
+    }
+    drop(file);
+
+    let segy = giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open");
+    assert_eq!(segy.trace_count(), 3);
+    for i in 0..3 {
+        assert_eq!(segy.get_trace_data_as_f32(i).unwrap(), vec![i as f32; 3]);
+    }
+    assert!(segy
+        .get_trace_extension_bytes(0)
+        .unwrap()
+        .starts_with(b"Extension 0"));
+    assert_eq!(segy.get_trace_extension_bytes(1).unwrap(), &[0; 240][..]);
+}
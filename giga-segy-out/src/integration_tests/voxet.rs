@@ -0,0 +1,189 @@
+// Copyright (C) 2022 by GiGa infosystems.
+//! Here we write voxets with the `VoxetWriter` and read them back, checking the geometry of the
+//! grid and the data.
+use crate::voxet::VoxetWriter;
+
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::RsgError;
+use giga_segy_core::SegySettings;
+use std::path::Path;
+
+const DIMS: [usize; 3] = [4, 5, 10];
+const ORIGIN: [f64; 3] = [1000.5, 2000.25, 0.];
+
+/// A grid which is rotated by 30 degrees, with 10 m between inlines and 25 m between crosslines.
+fn axes() -> ([f64; 3], [f64; 3]) {
+    let (sin, cos) = 30f64.to_radians().sin_cos();
+    ([10. * cos, 10. * sin, 0.], [-25. * sin, 25. * cos, 0.])
+}
+
+fn create(path: &Path) -> VoxetWriter {
+    let (u, v) = axes();
+    VoxetWriter::new(path, DIMS, ORIGIN, u, v, 2000., SampleFormatCode::Float32).unwrap()
+}
+
+fn column(i: usize, j: usize) -> Vec<f32> {
+    (0..DIMS[2])
+        .map(|k| (i * 100 + j * 10 + k) as f32)
+        .collect()
+}
+
+fn open(path: &Path) -> giga_segy_in::SegyFile {
+    giga_segy_in::SegyFile::open(path.to_str().unwrap(), SegySettings::default())
+        .expect("Could not open")
+}
+
+#[test]
+fn voxet_roundtrip() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("voxet.sgy");
+    let mut writer = create(&path);
+    // The columns do not have to be written in order.
+    for j in (0..DIMS[1]).rev() {
+        for i in 0..DIMS[0] {
+            writer.write_column(i, j, &column(i, j)).unwrap();
+        }
+    }
+    assert_eq!(writer.written_count(), 20);
+    assert_eq!(writer.finish(false).unwrap(), 0);
+    drop(writer);
+
+    let segy = open(&path);
+    let bin_header = segy.get_bin_header();
+    assert_eq!(bin_header.no_traces, 20);
+    assert_eq!(bin_header.no_samples, 10);
+    assert_eq!(bin_header.sample_interval, 2000);
+    assert_eq!(segy.trace_count(), 20);
+
+    let (u, v) = axes();
+    for i in 0..DIMS[0] {
+        for j in 0..DIMS[1] {
+            let trace = segy
+                .get_trace_by_xline_inline(j as i32, i as i32)
+                .expect("Every cell has a trace.");
+            let header = trace.get_header();
+            assert_eq!(header.coordinate_scalar, -10000);
+            assert_eq!(header.trace_identification_code, TraceIdCode::Unknown);
+            assert_eq!(header.no_samples_in_trace, 10);
+            let x = header.apply_coordinate_scalar(header.x_ensemble);
+            let y = header.apply_coordinate_scalar(header.y_ensemble);
+            let expected_x = ORIGIN[0] + i as f64 * u[0] + j as f64 * v[0];
+            let expected_y = ORIGIN[1] + i as f64 * u[1] + j as f64 * v[1];
+            // The coordinates are stored as 32 bit floats, like the samples.
+            assert!((x - expected_x).abs() <= 1e-3, "{} {}", x, expected_x);
+            assert!((y - expected_y).abs() <= 1e-3, "{} {}", y, expected_y);
+            assert_eq!(
+                segy.get_trace_data_as_f32_from_trace(&trace).unwrap(),
+                column(i, j)
+            );
+        }
+    }
+}
+
+#[test]
+fn voxet_missing_cells() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("voxet.sgy");
+    let mut writer = create(&path);
+    for i in 0..DIMS[0] {
+        for j in 0..DIMS[1] {
+            if (i, j) != (0, 3) && i != 2 {
+                writer.write_column(i, j, &column(i, j)).unwrap();
+            }
+        }
+    }
+
+    match writer.finish(false) {
+        Err(RsgError::MissingCells { count, first }) => {
+            assert_eq!(count, 6);
+            assert_eq!(first, [0, 3]);
+        }
+        r => panic!("Expected missing cells, not {:?}", r),
+    }
+    // The missing columns can still be written.
+    writer.write_column(0, 3, &column(0, 3)).unwrap();
+    assert_eq!(writer.finish(true).unwrap(), 5);
+    drop(writer);
+
+    let segy = open(&path);
+    assert_eq!(segy.trace_count(), 20);
+    assert_eq!(segy.get_bin_header().no_traces, 20);
+    for j in 0..DIMS[1] {
+        let trace = segy.get_trace_by_xline_inline(j as i32, 2).unwrap();
+        assert_eq!(
+            trace.get_header().trace_identification_code,
+            TraceIdCode::Dead
+        );
+        assert_eq!(
+            segy.get_trace_data_as_f32_from_trace(&trace).unwrap(),
+            vec![0.; 10]
+        );
+    }
+    let trace = segy.get_trace_by_xline_inline(3, 0).unwrap();
+    assert_eq!(
+        segy.get_trace_data_as_f32_from_trace(&trace).unwrap(),
+        column(0, 3)
+    );
+}
+
+#[test]
+fn voxet_invalid_columns() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let path = dir.path().join("voxet.sgy");
+    let mut writer = create(&path);
+
+    let res = writer.write_column(4, 0, &column(0, 0));
+    assert!(matches!(
+        res,
+        Err(RsgError::InvalidCell { cell: [4, 0], .. })
+    ));
+    writer.write_column(1, 1, &column(1, 1)).unwrap();
+    let res = writer.write_column(1, 1, &column(1, 1));
+    assert!(matches!(
+        res,
+        Err(RsgError::InvalidCell { cell: [1, 1], .. })
+    ));
+    // The wrong number of samples.
+    assert!(writer.write_column(1, 2, &[0.; 9]).is_err());
+    assert_eq!(writer.written_count(), 1);
+    writer.write_column(1, 2, &column(1, 2)).unwrap();
+    assert_eq!(writer.written_count(), 2);
+}
+
+#[test]
+fn voxet_coordinate_scalar() {
+    let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    let (u, v) = axes();
+    let float32 = SampleFormatCode::Float32;
+
+    let res = VoxetWriter::new(
+        dir.path().join("a.sgy"),
+        [0, 5, 10],
+        ORIGIN,
+        u,
+        v,
+        2000.,
+        float32,
+    );
+    assert!(matches!(res, Err(RsgError::SEGYSettingsError { .. })));
+
+    // UTM coordinates need a less precise scalar.
+    let path = dir.path().join("utm.sgy");
+    let origin = [500_000.123, 6_000_000.456, 0.];
+    let mut writer = VoxetWriter::new(&path, DIMS, origin, u, v, 2000., float32).unwrap();
+    writer.finish(true).unwrap();
+    drop(writer);
+    let segy = open(&path);
+    let trace = segy.get_trace_by_xline_inline(0, 0).unwrap();
+    let header = trace.get_header();
+    assert_eq!(header.coordinate_scalar, -100);
+    // The coordinates are stored as 32 bit floats, like the samples.
+    let expected = [50_000_012f32 as i32, 600_000_046f32 as i32];
+    assert_eq!([header.x_ensemble, header.y_ensemble], expected);
+
+    let far = [1e14, 0., 0.];
+    let res = VoxetWriter::new(dir.path().join("b.sgy"), DIMS, far, u, v, 2000., float32);
+    assert!(matches!(res, Err(RsgError::SEGYSettingsError { .. })));
+    // The file is not created.
+    assert!(!dir.path().join("b.sgy").exists());
+}
@@ -8,57 +8,93 @@
 //! (from January 2017).
 //!
 //! This library is not designed for editing of SEG-Y files, although it can theoretically be accomplished
-//! with the clever use of `giga_segy_in`. However we do not recommend this.
+//! with the clever use of `giga_segy_in`. However we do not recommend this. The one exception is the
+//! rewriting of trace headers, the merging of files, the cropping of files and their decimation, which
+//! are supported by the [`edit`], [`merge`], [`subset`] and [`resample`] modules if the `edit` feature
+//! is enabled.
+//!
+//! Seismic Unix files, which consist of the traces without the text and binary headers, can be
+//! written with the [`su`] module.
 #![allow(clippy::derive_partial_eq_without_eq)]
 extern crate fnv;
 extern crate giga_segy_core;
 extern crate num;
 #[macro_use]
 extern crate tinyvec;
-#[cfg(test)]
+#[cfg(any(test, feature = "edit"))]
 extern crate giga_segy_in;
 #[cfg(test)]
 extern crate tempfile;
 
 pub mod create_headers;
+#[cfg(feature = "edit")]
+pub mod edit;
+pub mod ensemble;
 #[cfg(test)]
 mod integration_tests;
+#[cfg(feature = "edit")]
+pub mod merge;
+#[cfg(feature = "edit")]
+pub mod repair;
+#[cfg(feature = "edit")]
+pub mod resample;
 mod settings;
+pub mod su;
+#[cfg(feature = "edit")]
+pub mod subset;
+pub mod text_header;
 pub mod utils;
+pub mod voxet;
 pub mod write_data;
 pub mod write_headers;
 
 pub use giga_segy_core::enums;
 pub use giga_segy_core::errors::*;
 pub use giga_segy_core::header_structs::*;
-pub use giga_segy_core::{SegyMetadata, SegySettings, Trace};
+pub use giga_segy_core::{SegyHandle, SegyMetadata, SegySettings, Trace};
+
+use giga_segy_core::layout;
+use giga_segy_core::settings::check_coordinate_format;
+use giga_segy_core::stanza;
+use giga_segy_core::EXTENDED_SAMPLE_COUNT_BYTE_LOCATION;
+use giga_segy_core::{BIN_HEADER_LEN, TAPE_LABEL_LEN, TEXT_HEADER_LEN, TRACE_HEADER_LEN};
 
 use num::ToPrimitive;
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
 use crate::settings::SegyWriteSettings;
 use crate::write_data::LosslessWriteableSegyData;
 use crate::write_headers::SegyHeaderToBytes;
 
+/// The position of `no_traces` in the binary header.
+pub(crate) const NO_TRACES_OFFSET: usize = 12;
+/// The position of the extended number of data traces (SEG-Y rev 2) in the binary header.
+pub(crate) const EXTENDED_NO_TRACES_OFFSET: usize = 60;
+/// The position of `no_traces_in_file` in the binary header.
+pub(crate) const NO_TRACES_IN_FILE_OFFSET: usize = 312;
+
 /// This structure gives several different ways of looking at trace coordinates.
 /// It is created once a trace has been written and moved into the [`SegyFile`]
 /// lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TraceCoordinates {
     /// Index of a trace in the order it is added to the file.
     pub idx: usize,
     /// The start byte of the trace, including the header.
-    pub start_byte: usize,
+    pub start_byte: u64,
     /// The start byt of the trace data.
-    pub data_start_byte: usize,
+    pub data_start_byte: u64,
     /// Overall length with all headers.
-    pub byte_len: usize,
+    pub byte_len: u64,
 }
 
 impl TraceCoordinates {
-    fn new(idx: usize, start: usize, ds: usize, overall_len: usize) -> Self {
+    fn new(idx: usize, start: u64, ds: u64, overall_len: u64) -> Self {
         Self {
             idx,
             start_byte: start,
@@ -68,6 +104,118 @@ impl TraceCoordinates {
     }
 }
 
+/// Changes that are made to the headers by [`SegyFile::create_file_from_metadata`] before they
+/// are written.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MetadataOverrides {
+    /// The sample format of the new file, if it differs from that of the metadata.
+    pub sample_format: Option<enums::SampleFormatCode>,
+    /// The number of traces (`no_traces` in the binary header) that the new file is expected
+    /// to have, if it differs from that of the metadata.
+    pub no_traces: Option<u16>,
+    /// If this is set, the extended text headers are not written.
+    pub clear_extended_headers: bool,
+}
+
+/// How a file which is created with [`SegyFile::create_file_atomic`] is finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DurabilityOptions {
+    /// If this is set, [`SegyFile::finalize`] calls [`File::sync_all`] before the file is
+    /// renamed, so that the data is on disk before the file appears under its final name.
+    pub sync_on_finalize: bool,
+    /// If this is set, the partial file is deleted if the [`SegyFile`] is dropped before
+    /// [`SegyFile::finalize`] is called. Otherwise it is left as it is.
+    pub delete_partial_on_drop: bool,
+}
+
+/// Get the path which a file is written to by [`SegyFile::create_file_atomic`] until it is
+/// finalized: The path with `.partial` appended (eg. `survey.sgy.partial`).
+pub fn partial_path<T: AsRef<Path>>(file_name: T) -> PathBuf {
+    let mut partial = file_name.as_ref().as_os_str().to_os_string();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+/// Open the file which is written to. If `overwrite` is true, an existing file is truncated,
+/// and otherwise [`RsgError::OutputExists`] is returned for it.
+fn open_output(path: &Path, overwrite: bool) -> Result<File, RsgError> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => RsgError::OutputExists {
+            path: path.to_path_buf(),
+        },
+        _ => RsgError::StdIoError(e),
+    })
+}
+
+/// Set `extended_header_count` in the binary header to the number of extended headers.
+fn set_extended_header_count<S>(metadata: &mut SegyMetadata<S>) -> Result<(), RsgError> {
+    let count = metadata.extended_headers.len();
+    metadata.bin_header.extended_header_count = u32::try_from(count).map_err(|_| {
+        let msg = format!("Too many extended headers: {}", count);
+        RsgError::InvalidHeader { msg }
+    })?;
+    Ok(())
+}
+
+/// Refuse byte indices which would make the inline and crossline numbers or the ensemble
+/// coordinates overwrite other fields of the trace header, unless the settings allow it.
+fn check_bidx_collisions<S: SegyWriteSettings>(settings: &S) -> Result<(), RsgError> {
+    let collisions = layout::find_bidx_collisions([
+        settings.get_inline_no_bidx(),
+        settings.get_crossline_no_bidx(),
+        settings.get_x_ensemble_bidx(),
+        settings.get_y_ensemble_bidx(),
+    ]);
+    if collisions.is_empty() || settings.get_allow_bidx_collisions() {
+        return Ok(());
+    }
+    let list = collisions
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join("; ");
+    let msg = format!(
+        "The byte indices would overwrite other fields of the trace header ({}). This must be allowed in the settings.",
+        list
+    );
+    Err(RsgError::SEGYSettingsError { msg })
+}
+
+/// Record a format of the coordinates which is not the default in the extended text headers,
+/// before the `((SEG: EndText))` stanza if there is one. A record of the format which is
+/// already there (eg. because the headers come from a file that was read) is replaced.
+/// Returns whether the extended headers were changed.
+fn record_coordinate_format(
+    extended_headers: &mut Vec<String>,
+    format: Option<enums::SampleFormatCode>,
+) -> bool {
+    let is_record = |header: &String| {
+        let stanzas = stanza::parse_extended_stanzas(std::slice::from_ref(header));
+        matches!(&stanzas[..], [s] if s.name == stanza::COORDINATE_FORMAT_STANZA)
+    };
+    let count = extended_headers.len();
+    extended_headers.retain(|h| !is_record(h));
+    let mut changed = extended_headers.len() != count;
+
+    if let Some(format) = format.filter(|f| *f != enums::SampleFormatCode::Int32) {
+        let end = extended_headers
+            .iter()
+            .position(|h| h.to_ascii_uppercase().contains("((SEG: ENDTEXT))"))
+            .unwrap_or(extended_headers.len());
+        let records = stanza::coordinate_format_stanza(format).to_header_records();
+        extended_headers.splice(end..end, records);
+        changed = true;
+    }
+    changed
+}
+
 /// A structure which represents a mapped SEG-Y file. This represents a writeable SEG-Y.
 pub struct SegyFile<S: SegyWriteSettings> {
     /// Metadata, including headers and settings used for creating and writing this file.
@@ -79,11 +227,43 @@ pub struct SegyFile<S: SegyWriteSettings> {
     pub lookup: fnv::FnvHashMap<usize, TraceCoordinates>,
     /// The file which the SEG-Y is being written to.
     pub file: File,
+    /// The digests of the data of the traces written, in the order they were written.
+    data_digests: Vec<u64>,
+    /// The partial and final paths of a file which was created with
+    /// [`SegyFile::create_file_atomic`] and has not been finalized yet.
+    pending_rename: Option<[PathBuf; 2]>,
+    durability: DurabilityOptions,
+    /// The factor which has been noted in the text header by [`SegyFile::add_trace_scaled`].
+    scale_note: Option<f64>,
+    /// The card of the text header in which the last call of [`SegyFile::finalize`] recorded
+    /// how the file was written.
+    provenance_card: Option<usize>,
 }
 
 impl<S: SegyWriteSettings> SegyFile<S> {
     /// Create a file and return the handle to a writeable file. Traces can then be added
     /// one by one.
+    ///
+    /// The file is written as little endian if `bin_header.binary_flag_direction_is_le` is set,
+    /// and as big endian otherwise. If the settings contain an endianness override
+    /// (see [`SegySettings::set_override_to_le`]), it takes precedence and the binary
+    /// header is adjusted accordingly, so that the headers and the trace data always agree.
+    ///
+    /// If the settings ask for a strict text header (see [`SegySettings::set_strict_text_header`]),
+    /// the text header is normalized with [`text_header::normalize_text_header`] first.
+    ///
+    /// If the coordinates are not written as `Int32` (see [`Self::coordinate_encoding`]), the
+    /// format is recorded in an extended text header, and `extended_header_count` is set
+    /// accordingly. An error is returned before anything is written if the coordinate format
+    /// is not four bytes long.
+    ///
+    /// [`RsgError::OutputExists`] is returned if `file_name` already exists (see
+    /// [`SegyFile::create_file_overwrite`] to replace it).
+    ///
+    /// An error is also returned if the byte indices of the settings make the inline and
+    /// crossline numbers or the ensemble coordinates overlap other fields of the trace header
+    /// (see [`SegySettings::check_bidx_collisions`]), unless this is allowed with
+    /// [`SegySettings::set_allow_bidx_collisions`].
     /// ```
     /// use giga_segy_out::SegyFile;
     /// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
@@ -98,9 +278,13 @@ impl<S: SegyWriteSettings> SegyFile<S> {
     /// // The number of samples in either the binary or trace header must equal data vector length.
     /// bin_header.no_samples = 50;
     ///
+    /// // Number the traces in the order in which they are added.
+    /// let mut settings = SegySettings::default();
+    /// settings.set_auto_sequence(true);
+    ///
     /// let mut file = SegyFile::<SegySettings>::create_file(
     ///     path,
-    ///     Default::default(),
+    ///     settings,
     ///     std::iter::repeat('x').take(3200).collect::<String>(),
     ///     bin_header,
     ///     None,
@@ -119,30 +303,457 @@ impl<S: SegyWriteSettings> SegyFile<S> {
         bin_header: BinHeader,
         tape_label: Option<TapeLabel>,
     ) -> Result<Self, RsgError> {
-        let mut file = OpenOptions::new()
-            .write(true)
-            .read(true)
-            .create_new(true)
-            .open(file_name)?;
+        let metadata = SegyMetadata::new(tape_label, text_header, vec![], bin_header, settings);
+        Self::create_file_with_headers(file_name, metadata, false)
+    }
+
+    /// Create a file as with [`SegyFile::create_file`], but overwrite `file_name` if it already
+    /// exists instead of returning [`RsgError::OutputExists`].
+    ///
+    /// An existing file is truncated before the headers are written, so nothing of it is left
+    /// behind the new traces, even if it was longer.
+    /// ```
+    /// use giga_segy_out::SegyFile;
+    /// use giga_segy_core::{BinHeader, RsgError, SegySettings, TraceHeader};
+    /// use giga_segy_core::enums::*;
+    /// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+    ///
+    /// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    /// let path = dir.path().join("rerun.sgy");
+    /// std::fs::write(&path, b"An older version of the file").unwrap();
+    /// let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    ///
+    /// let e = SegyFile::<SegySettings>::create_file(
+    ///     &path,
+    ///     Default::default(),
+    ///     "C 1 A file which is written again".to_string(),
+    ///     bin_header.clone(),
+    ///     None,
+    /// ).err().unwrap();
+    /// assert!(matches!(e, RsgError::OutputExists { .. }));
+    ///
+    /// let mut file = SegyFile::<SegySettings>::create_file_overwrite(
+    ///     &path,
+    ///     Default::default(),
+    ///     "C 1 A file which is written again".to_string(),
+    ///     bin_header,
+    ///     None,
+    /// ).unwrap();
+    /// file.add_trace(TraceHeader::new_2d(0, 0, 1), None, vec![1f32; 5]).unwrap();
+    /// ```
+    pub fn create_file_overwrite<T: AsRef<Path>>(
+        file_name: T,
+        settings: S,
+        text_header: String,
+        bin_header: BinHeader,
+        tape_label: Option<TapeLabel>,
+    ) -> Result<Self, RsgError> {
+        let metadata = SegyMetadata::new(tape_label, text_header, vec![], bin_header, settings);
+        Self::create_file_with_headers(file_name, metadata, true)
+    }
+
+    /// Create a file as with [`SegyFile::create_file`], but write it to a partial file next to
+    /// `file_name` (see [`partial_path`]), which is only renamed to `file_name` when
+    /// [`SegyFile::finalize`] is called. This way a file which is not finished (eg. because
+    /// the process was killed) never appears under its final name.
+    ///
+    /// An error is returned if `file_name` or the partial file already exist. After the first
+    /// call of [`SegyFile::finalize`], the file keeps its final name, so traces which are added
+    /// afterwards are written to it directly.
+    /// ```
+    /// use giga_segy_out::{partial_path, DurabilityOptions, SegyFile};
+    /// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+    /// use giga_segy_core::enums::*;
+    /// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+    ///
+    /// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    /// let path = dir.path().join("atomic.sgy");
+    /// let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    ///
+    /// let mut file = SegyFile::<SegySettings>::create_file_atomic(
+    ///     &path,
+    ///     Default::default(),
+    ///     "C 1 A file which appears when it is finished".to_string(),
+    ///     bin_header,
+    ///     None,
+    ///     DurabilityOptions { sync_on_finalize: true, ..Default::default() },
+    /// ).unwrap();
+    /// file.add_trace(TraceHeader::new_2d(0, 0, 1), None, vec![1f32; 5]).unwrap();
+    /// assert!(!path.exists());
+    ///
+    /// file.finalize().unwrap();
+    /// assert!(path.exists());
+    /// assert!(!partial_path(&path).exists());
+    /// ```
+    pub fn create_file_atomic<T: AsRef<Path>>(
+        file_name: T,
+        settings: S,
+        text_header: String,
+        bin_header: BinHeader,
+        tape_label: Option<TapeLabel>,
+        durability: DurabilityOptions,
+    ) -> Result<Self, RsgError> {
+        let file_name = file_name.as_ref().to_path_buf();
+        if file_name.exists() {
+            return Err(RsgError::OutputExists { path: file_name });
+        }
+        let partial = partial_path(&file_name);
+        let metadata = SegyMetadata::new(tape_label, text_header, vec![], bin_header, settings);
+        let mut segy = Self::create_file_with_headers(&partial, metadata, false)?;
+        segy.pending_rename = Some([partial, file_name]);
+        segy.durability = durability;
+        Ok(segy)
+    }
+
+    /// Create a file with the headers of `metadata` (eg. the metadata of a file that was read
+    /// with `giga_segy_in`), after applying the `overrides`. Traces can then be added one by one.
+    ///
+    /// Unlike [`SegyFile::create_file`], the extended text headers of the metadata are written
+    /// as well (unless they are cleared), and `extended_header_count` in the binary header is set
+    /// to their number. If the metadata has a raw binary header, the bytes that do not belong to
+    /// any field of [`BinHeader`] are kept (see [`SegyFile::set_raw_bin_header`]).
+    /// ```
+    /// use std::env::var;
+    /// use std::path::PathBuf;
+    /// use giga_segy_out::{MetadataOverrides, SegyFile};
+    /// use giga_segy_core::enums::SampleFormatCode;
+    ///
+    /// let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+    /// root.pop();
+    /// let name = root.join("testdata").join("DutchMiniHead.sgy");
+    /// let input = giga_segy_in::SegyFile::open(name.to_str().unwrap(), Default::default()).unwrap();
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let overrides = MetadataOverrides {
+    ///     sample_format: Some(SampleFormatCode::Float32),
+    ///     ..Default::default()
+    /// };
+    /// let file = SegyFile::create_file_from_metadata(
+    ///     dir.path().join("derived.sgy"),
+    ///     input.into_metadata(),
+    ///     overrides,
+    /// ).unwrap();
+    /// assert_eq!(
+    ///     file.metadata.get_bin_header().sample_format_code,
+    ///     SampleFormatCode::Float32
+    /// );
+    /// ```
+    pub fn create_file_from_metadata<T: AsRef<Path>>(
+        file_name: T,
+        metadata: SegyMetadata<S>,
+        overrides: MetadataOverrides,
+    ) -> Result<Self, RsgError> {
+        let mut metadata = metadata;
+        if let Some(format) = overrides.sample_format {
+            metadata.bin_header.sample_format_code = format;
+        }
+        if let Some(no_traces) = overrides.no_traces {
+            metadata.bin_header.no_traces = no_traces;
+        }
+        if overrides.clear_extended_headers {
+            metadata.extended_headers.clear();
+        }
+        set_extended_header_count(&mut metadata)?;
+        Self::create_file_with_headers(file_name, metadata, false)
+    }
+
+    /// Create a file and write all of the headers in `metadata` to it. An existing file is
+    /// truncated if `overwrite` is true, and an error otherwise.
+    fn create_file_with_headers<T: AsRef<Path>>(
+        file_name: T,
+        metadata: SegyMetadata<S>,
+        overwrite: bool,
+    ) -> Result<Self, RsgError> {
+        let mut metadata = metadata;
+        metadata.settings.validate()?;
+        check_bidx_collisions(&metadata.settings)?;
+        let coordinate_format = metadata.settings.get_override_coordinate_format();
+        if let Some(format) = coordinate_format {
+            check_coordinate_format(format)?;
+        }
+        if record_coordinate_format(&mut metadata.extended_headers, coordinate_format) {
+            set_extended_header_count(&mut metadata)?;
+        }
+        if let Some(le) = metadata.settings.get_override_to_le() {
+            metadata.bin_header.binary_flag_direction_is_le = le;
+        }
+        // The traces directly follow the headers, so an offset of the first trace which was
+        // given (eg. by a file that was read) must be corrected.
+        if metadata.bin_header.first_trace_offset != 0 {
+            let ext_len = metadata.extended_headers.len() * TEXT_HEADER_LEN;
+            metadata.bin_header.first_trace_offset =
+                (TEXT_HEADER_LEN + BIN_HEADER_LEN + ext_len) as u64;
+        }
+        if metadata.settings.get_strict_text_header() {
+            metadata.text_header =
+                crate::text_header::normalize_text_header(&metadata.text_header)?;
+        }
 
-        if let Some(ref tl) = tape_label {
+        let mut file = open_output(file_name.as_ref(), overwrite)?;
+
+        if let Some(ref tl) = metadata.tape_label {
             file.write_all(&tl.as_bytes()?)?;
         }
 
-        crate::write_headers::write_text_header(&text_header, &mut file)?;
+        crate::write_headers::write_text_header(&metadata.text_header, &mut file)?;
+
+        file.write_all(&write_headers::bin_header_as_bytes_with_raw(
+            &metadata.bin_header,
+            metadata.raw_bin_header.as_ref(),
+        )?)?;
 
-        file.write_all(&bin_header.as_bytes()?)?;
+        for header in metadata.extended_headers.iter() {
+            crate::write_headers::write_text_header(header, &mut file)?;
+        }
 
         Ok(SegyFile {
-            metadata: SegyMetadata::new(tape_label, text_header, vec![], bin_header, settings),
+            metadata,
             traces: Vec::new(),
             lookup: fnv::FnvHashMap::default(),
             file,
+            data_digests: Vec::new(),
+            pending_rename: None,
+            scale_note: None,
+            provenance_card: None,
+            durability: DurabilityOptions::default(),
         })
     }
 
+    /// Get the format in which the coordinates of the trace headers are written. This is the
+    /// override of the settings (see [`SegySettings::set_override_coordinate_format`]), or
+    /// `Int32` as in the standard.
+    ///
+    /// If it is not `Int32`, the format is recorded in the extended text headers (see
+    /// [`giga_segy_core::stanza::coordinate_format_stanza`]) when the file is created, so that
+    /// `giga_segy_in` reads the coordinates correctly without the same settings.
+    pub fn coordinate_encoding(&self) -> enums::SampleFormatCode {
+        self.metadata
+            .settings
+            .get_override_coordinate_format()
+            .unwrap_or(enums::SampleFormatCode::Int32)
+    }
+
+    /// Take the bytes of the binary header which do not belong to any field of [`BinHeader`]
+    /// (eg. the unassigned bytes 3261-3500) from `raw`, and write the binary header to the file
+    /// again. This preserves these bytes when a file is transcoded, using the raw binary header
+    /// that was read (see [`SegyMetadata::get_raw_bin_header`]).
+    /// ```
+    /// use giga_segy_out::SegyFile;
+    /// use giga_segy_core::{BinHeader, SegySettings};
+    /// use giga_segy_core::enums::*;
+    /// use giga_segy_out::create_headers::CreateBinHeader;
+    ///
+    /// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    /// let path = dir.path().join("raw.sgy");
+    /// let bin_header = BinHeader::new(0, 4000, 50, SampleFormatCode::Float32);
+    ///
+    /// let mut file = SegyFile::<SegySettings>::create_file(
+    ///     &path,
+    ///     Default::default(),
+    ///     "C 1 A file with a vendor specific binary header".to_string(),
+    ///     bin_header,
+    ///     None,
+    /// ).unwrap();
+    /// let mut raw = [0; 400];
+    /// raw[200] = 42;
+    /// file.set_raw_bin_header(raw).unwrap();
+    /// drop(file);
+    ///
+    /// let bytes = std::fs::read(&path).unwrap();
+    /// assert_eq!(bytes[3400], 42);
+    /// // Bytes which belong to a field of the binary header are not taken from the raw header.
+    /// assert_eq!(bytes[3224..3226], [0, 5]);
+    /// ```
+    pub fn set_raw_bin_header(&mut self, raw: [u8; BIN_HEADER_LEN]) -> Result<(), RsgError> {
+        let bytes =
+            write_headers::bin_header_as_bytes_with_raw(&self.metadata.bin_header, Some(&raw))?;
+        let tape_label_len = self
+            .metadata
+            .get_tape_label()
+            .as_ref()
+            .map_or(0, |_| TAPE_LABEL_LEN);
+        self.file
+            .seek(SeekFrom::Start((tape_label_len + TEXT_HEADER_LEN) as u64))?;
+        self.file.write_all(&bytes)?;
+        // Traces must still be appended to the end.
+        self.file.seek(SeekFrom::End(0))?;
+        self.metadata.raw_bin_header = Some(raw);
+        Ok(())
+    }
+
+    /// Write the number of traces that have been added to the binary header (both in the file
+    /// and in the metadata), so that the headers are consistent with the traces. The number
+    /// of traces is returned. It is written to `no_traces`, and to `no_traces_in_file` if the
+    /// binary header is SEG-Y revision 2 or later (older revisions leave bytes 3313-3320 alone).
+    ///
+    /// If there are more than 65535 traces (which requires
+    /// [`SegySettings::set_extended_trace_count`]), `no_traces` is set to zero and the number
+    /// of traces is written to the extended number of data traces (bytes 3261-3264) instead.
+    ///
+    /// More traces may still be added afterwards, but then this must be called again.
+    ///
+    /// If the settings ask for it (see [`SegySettings::set_append_provenance_card`]), a card
+    /// which records how the file was written is added to the text header as well (see
+    /// [`SegyFile::provenance_card`]).
+    ///
+    /// If the file was created with [`SegyFile::create_file_atomic`], the file is synced if the
+    /// [`DurabilityOptions`] ask for it, and the partial file is renamed to its final name.
+    pub fn finalize(&mut self) -> Result<usize, RsgError> {
+        let count = self.traces.len();
+        let (no_traces, extended, _) = trace_count_fields(count)?;
+
+        let le = self.metadata.bin_header.binary_flag_direction_is_le;
+        let (no_traces_bytes, extended_bytes, in_file_bytes) = if le {
+            let in_file = (count as u64).to_le_bytes();
+            (no_traces.to_le_bytes(), extended.to_le_bytes(), in_file)
+        } else {
+            let in_file = (count as u64).to_be_bytes();
+            (no_traces.to_be_bytes(), extended.to_be_bytes(), in_file)
+        };
+        self.patch_bin_header_bytes(NO_TRACES_OFFSET, &no_traces_bytes)?;
+        self.patch_bin_header_bytes(EXTENDED_NO_TRACES_OFFSET, &extended_bytes)?;
+        let in_file = self.metadata.bin_header.segy_revision_number[0] >= 2;
+        if in_file {
+            self.patch_bin_header_bytes(NO_TRACES_IN_FILE_OFFSET, &in_file_bytes)?;
+        }
+        if self.metadata.settings.get_append_provenance_card() {
+            self.write_provenance_card(count)?;
+        }
+        self.file.flush()?;
+        // Further traces must still be appended to the end.
+        self.file.seek(SeekFrom::End(0))?;
+
+        self.metadata.bin_header.no_traces = no_traces;
+        self.metadata.bin_header.extended_no_traces = extended;
+        if in_file {
+            self.metadata.bin_header.no_traces_in_file = count as u64;
+        }
+
+        if self.durability.sync_on_finalize {
+            self.file.sync_all()?;
+        }
+        if let Some([partial, final_name]) = self.pending_rename.take() {
+            std::fs::rename(partial, final_name)?;
+        }
+        Ok(count)
+    }
+
+    /// Overwrite bytes of the binary header, starting at `offset` in the binary header, both
+    /// in the file and in the raw binary header of the metadata (if there is one). Further
+    /// traces are still appended to the end of the file.
+    pub(crate) fn patch_bin_header_bytes(
+        &mut self,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), RsgError> {
+        let bin_header_start = self
+            .metadata
+            .get_tape_label()
+            .as_ref()
+            .map_or(0, |_| TAPE_LABEL_LEN)
+            + TEXT_HEADER_LEN;
+        self.file
+            .seek(SeekFrom::Start((bin_header_start + offset) as u64))?;
+        self.file.write_all(bytes)?;
+        self.file.seek(SeekFrom::End(0))?;
+        if let Some(ref mut raw) = self.metadata.raw_bin_header {
+            raw[offset..(offset + bytes.len())].copy_from_slice(bytes);
+        }
+        Ok(())
+    }
+
+    /// Get the card of the text header (counting from one) in which [`SegyFile::finalize`]
+    /// recorded how the file was written (see [`SegySettings::set_append_provenance_card`]).
+    ///
+    /// This is `None` if the file was not finalized with the setting, or if the text header
+    /// had no blank card, in which case it was left as it was.
+    /// ```
+    /// use giga_segy_out::SegyFile;
+    /// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+    ///
+    /// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    /// let mut settings = SegySettings::default();
+    /// settings.set_append_provenance_card(true);
+    /// let bin_header = BinHeader::new(0, 4000, 5, SampleFormatCode::Float32);
+    ///
+    /// let mut file = SegyFile::create_file(
+    ///     dir.path().join("provenance.sgy"),
+    ///     settings,
+    ///     "C 1 A file which records how it was written".to_string(),
+    ///     bin_header,
+    ///     None,
+    /// ).unwrap();
+    /// file.add_trace(TraceHeader::new_2d(0, 0, 1), None, vec![1f32; 5]).unwrap();
+    /// file.finalize().unwrap();
+    ///
+    /// assert_eq!(file.provenance_card(), Some(40));
+    /// let card = &file.metadata.get_text_header()[3120..3200];
+    /// assert!(card.starts_with("C40 written by giga-segy-out v"));
+    /// assert!(card.trim_end().ends_with(", format 5, 1 traces"));
+    /// ```
+    pub fn provenance_card(&self) -> Option<usize> {
+        self.provenance_card
+    }
+
+    /// Record how the file was written in a blank card of the text header (or the card in
+    /// which this was recorded before), both in the file and in the metadata.
+    fn write_provenance_card(&mut self, count: usize) -> Result<(), RsgError> {
+        let text_header = &mut self.metadata.text_header;
+        self.provenance_card = text_header::find_provenance_card(text_header.as_bytes());
+        let n = match self.provenance_card {
+            Some(n) => n,
+            None => return Ok(()),
+        };
+        let format = self.metadata.bin_header.sample_format_code as u16;
+        let card = text_header::provenance_card(n, format, count, std::time::SystemTime::now());
+        text_header::replace_card(text_header, n, &card);
+
+        let text_header_start = self
+            .metadata
+            .get_tape_label()
+            .as_ref()
+            .map_or(0, |_| TAPE_LABEL_LEN);
+        self.file.seek(SeekFrom::Start(
+            (text_header_start + (n - 1) * card.len()) as u64,
+        ))?;
+        self.file.write_all(card.as_bytes())?;
+        Ok(())
+    }
+
+    /// Get the digests (see [`giga_segy_core::digest::digest_bytes`]) of the data of each trace
+    /// written, computed from the bytes that were written to the file. These are the same as
+    /// the digests that `giga_segy_in` computes when the file is read.
+    pub fn written_trace_digests(&self) -> &[u64] {
+        &self.data_digests
+    }
+
+    /// Get a digest of the data of all traces written, which combines the digests of the
+    /// traces with [`giga_segy_core::digest::combine_digests`]. This is the same as the digest
+    /// that `giga_segy_in` computes for the whole file when it is read.
+    pub fn digest_written_traces(&self) -> u64 {
+        giga_segy_core::digest::combine_digests(self.data_digests.iter().copied())
+    }
+
     /// This function will add a trace to the file being written. It will try to convert
     /// The data to the desired [`enums::SampleFormatCode`], which can result in loss of precision.
+    ///
+    /// If the settings ask for it (see [`SegySettings::set_auto_sequence`]), the sequence
+    /// numbers of the trace header which are zero are set to the position of the trace, counting
+    /// from one.
+    ///
+    /// The `extended_header` is written between the trace header and the data as the trace
+    /// header extensions of SEG-Y rev 2, so it must have 240 bytes for each extension that the
+    /// binary header declares (`max_additional_trace_headers`), or an
+    /// [`RsgError::TraceExtensionLength`] is returned. If it is `None`, the extensions are
+    /// filled with zeros. This is so that readers can find the data of each trace.
+    ///
+    /// A trace may have up to 65535 samples, or up to [`u32::MAX`] samples if the binary header
+    /// is SEG-Y rev 2 (`segy_revision_number`), and an [`RsgError::LongDataVector`] is returned
+    /// otherwise. The sample count of a longer trace is written to bytes 137-140 of its first
+    /// trace header extension, so a variable length trace needs one. Fixed length traces need
+    /// the sample count in [`BinHeader::extended_no_samples`] instead.
     pub fn add_trace<T: ToPrimitive + Debug>(
         &mut self,
         trace_header: TraceHeader,
@@ -169,6 +780,8 @@ impl<S: SegyWriteSettings> SegyFile<S> {
     /// conversions that are not lossless. The out of the box implementation,
     /// however, only allows conversion between types where conversion is
     /// theoretically lossless.
+    ///
+    /// The `extended_header` must fit the trace header extensions, as with [`Self::add_trace`].
     /// ```
     /// use giga_segy_out::SegyFile;
     /// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
@@ -230,11 +843,149 @@ impl<S: SegyWriteSettings> SegyFile<S> {
             write_data::convert_data_losslessly,
         )
     }
+
+    /// Add a trace to a file with an integer sample format, scaling the samples so that they
+    /// make good use of the range of the format (see [`write_data::ScaleMode`]). The scaled
+    /// samples are rounded to the nearest integer, and to the even one if they are halfway
+    /// between two. An error is returned if the sample format is not an integer format, or if
+    /// the scaled samples do not fit in it.
+    ///
+    /// If the factor is a power of two `2^N`, `trace_weighting_factor` of the trace header is
+    /// set to `N`, so that `giga_segy_in` can restore the samples (see
+    /// [`SegySettings::set_apply_trace_weighting`]). Otherwise the weighting factor is set to
+    /// zero, and the last card of the text header is replaced by a note such as
+    /// `C40 UNWEIGHTED SAMPLES ARE MULTIPLIED BY 1.5E0`. Since there is only one note, all
+    /// traces which are scaled by a factor that is not a power of two must use the same one.
+    ///
+    /// The `extended_header` is written as with [`Self::add_trace`].
+    /// ```
+    /// use giga_segy_out::SegyFile;
+    /// use giga_segy_out::write_data::ScaleMode;
+    /// use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+    /// use giga_segy_core::enums::SampleFormatCode;
+    /// use giga_segy_out::create_headers::{CreateBinHeader, CreateTraceHeader};
+    ///
+    /// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+    /// let path = dir.path().join("scaled.sgy");
+    ///
+    /// let bin_header = BinHeader::new(0, 4000, 3, SampleFormatCode::Int16);
+    /// let mut file = SegyFile::create_file(
+    ///     path, SegySettings::default(), String::new(), bin_header, None,
+    /// ).unwrap();
+    ///
+    /// let data = vec![100_000f32, -50_000., 1.];
+    /// let trace = file
+    ///     .add_trace_scaled(TraceHeader::new_2d(0, 0, 1), None, data, ScaleMode::AutoPerTrace)
+    ///     .unwrap();
+    /// // The samples were divided by four, which is `2^-2`.
+    /// assert_eq!(trace.get_header().trace_weighting_factor as i16, -2);
+    /// ```
+    pub fn add_trace_scaled<T: ToPrimitive>(
+        &mut self,
+        mut trace_header: TraceHeader,
+        extended_header: Option<String>,
+        data: Vec<T>,
+        scaling: write_data::ScaleMode,
+    ) -> Result<&Trace, RsgError> {
+        let (format, _) = write_data::get_format_and_le(self.metadata.get_bin_header());
+        let (scaled, applied) = write_data::scale_samples(&data, format, scaling)?;
+        match applied {
+            write_data::AppliedScale::Weighting(n) => {
+                trace_header.trace_weighting_factor = n as u16;
+            }
+            write_data::AppliedScale::Other(factor) => {
+                self.note_scale_factor(factor)?;
+                trace_header.trace_weighting_factor = 0;
+            }
+        }
+        write_trace_internal(
+            self,
+            trace_header,
+            extended_header,
+            scaled,
+            write_data::convert_data,
+        )
+    }
+
+    /// Note a factor by which the samples were scaled in the last card of the text header,
+    /// both in the file and in the metadata (see [`Self::add_trace_scaled`]).
+    fn note_scale_factor(&mut self, factor: f64) -> Result<(), RsgError> {
+        match self.scale_note {
+            Some(noted) if noted == factor => return Ok(()),
+            Some(noted) => {
+                return Err(RsgError::SEGYSettingsError {
+                    msg: format!(
+                        "The samples cannot be scaled by {}, since the text header notes that \
+                        they are scaled by {}.",
+                        factor, noted
+                    ),
+                })
+            }
+            None => {}
+        }
+        const CARD_LEN: usize = 80;
+        let card_start = TEXT_HEADER_LEN - CARD_LEN;
+        let note = format!(
+            "{:<width$}",
+            format!("C40 UNWEIGHTED SAMPLES ARE MULTIPLIED BY {:E}", factor),
+            width = CARD_LEN
+        );
+
+        let text_header = &mut self.metadata.text_header;
+        let mut end = text_header.len().min(card_start);
+        while !text_header.is_char_boundary(end) {
+            end -= 1;
+        }
+        text_header.truncate(end);
+        text_header.push_str(&" ".repeat(card_start - end));
+        text_header.push_str(&note);
+
+        let text_header_start = self
+            .metadata
+            .get_tape_label()
+            .as_ref()
+            .map_or(0, |_| TAPE_LABEL_LEN);
+        self.file
+            .seek(SeekFrom::Start((text_header_start + card_start) as u64))?;
+        self.file.write_all(note.as_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.scale_note = Some(factor);
+        Ok(())
+    }
+}
+
+impl<S: SegyWriteSettings> Drop for SegyFile<S> {
+    /// A file which was created with [`SegyFile::create_file_atomic`] and not finalized is
+    /// left under its partial name, or deleted if the [`DurabilityOptions`] ask for it.
+    fn drop(&mut self) {
+        if let Some([partial, _]) = self.pending_rename.take() {
+            if self.durability.delete_partial_on_drop {
+                // There is nothing to be done if this fails.
+                let _ = std::fs::remove_file(partial);
+            }
+        }
+    }
+}
+
+impl<S: SegyWriteSettings> SegyHandle for SegyFile<S> {
+    type Settings = S;
+
+    fn get_metadata(&self) -> &SegyMetadata<S> {
+        &self.metadata
+    }
+
+    fn trace_count(&self) -> usize {
+        self.traces.len()
+    }
+
+    fn get_trace(&self, i: usize) -> Option<Cow<'_, Trace>> {
+        self.traces.get(i).map(Cow::Borrowed)
+    }
 }
 
 fn write_trace_internal<T, S>(
     segy: &mut SegyFile<S>,
-    trace_header: TraceHeader,
+    mut trace_header: TraceHeader,
     extended_header: Option<String>,
     data: Vec<T>,
     write_fn: fn(Vec<T>, &BinHeader) -> Result<Vec<u8>, RsgError>,
@@ -245,25 +996,45 @@ where
 {
     // Get some parameters for construction of byte coordinates.
     let idx = segy.traces.len();
-    let start = segy.traces.last().map(|t| t.get_start()).unwrap_or(0);
-    let length = segy.lookup.get(&start).map(|g| g.byte_len).unwrap_or(0);
-    let new_start = start + length;
+    if idx >= u16::MAX as usize && !segy.metadata.get_settings().get_extended_trace_count() {
+        return Err(RsgError::TooManyTraces {
+            max: u16::MAX as usize,
+        });
+    }
+    if segy.metadata.get_settings().get_auto_sequence() {
+        fill_sequence_numbers(&mut trace_header, idx);
+    }
+    // Positions are kept as `u64`, so that they do not overflow on 32-bit targets.
+    let new_start = segy.file.stream_position()?;
 
     // A sanity check to make sure that if we are given the wrong number of data, we return an error.
     let data_len = data.len();
     let bin_header = segy.metadata.get_bin_header();
-    let bin_sample_count = bin_header.no_samples;
-    let th_sample_count = trace_header.no_samples_in_trace;
-
-    if data.len() > u16::MAX as usize {
-        return Err(RsgError::LongDataVector { l_data: data.len() });
-    } else if (data_len as u16 != bin_sample_count) && (data_len as u16 != th_sample_count) {
-        return Err(RsgError::BadDataVector {
-            l_data: data_len as u16,
-            l_bin: bin_sample_count,
-            l_trace: th_sample_count,
+    check_data_len(bin_header, data_len)?;
+    check_sample_count(
+        &mut trace_header,
+        bin_header,
+        data_len,
+        idx,
+        segy.metadata.get_settings().get_strict_sample_count(),
+    )?;
+
+    // The extensions must have the length that readers expect from the binary header.
+    let expected = TRACE_HEADER_LEN * bin_header.max_additional_trace_headers as usize;
+    let mut extension = match extended_header {
+        Some(e) => e.into_bytes(),
+        None => vec![0; expected],
+    };
+    if extension.len() != expected {
+        return Err(RsgError::TraceExtensionLength {
+            trace: idx,
+            len: extension.len(),
+            expected,
         });
     }
+    if data_len > u16::MAX as usize {
+        write_extended_sample_count(&mut extension, bin_header, data_len)?;
+    }
 
     let header_bytes = write_headers::th_as_bytes_with_settings(
         &trace_header,
@@ -273,20 +1044,146 @@ where
     let mut length = header_bytes.len();
     segy.file.write_all(&header_bytes)?;
 
-    if let Some(extra) = extended_header {
-        let bytes = extra.as_bytes();
-        length += bytes.len();
-        segy.file.write_all(bytes)?;
-    }
+    length += extension.len();
+    segy.file.write_all(&extension)?;
 
     let data = write_fn(data, bin_header)?;
-    length += data_len;
+    let data_start = new_start + length as u64;
+    length += data.len();
     segy.file.write_all(&data)?;
+    segy.data_digests
+        .push(giga_segy_core::digest::digest_bytes(&data));
 
-    let new_coordinates = TraceCoordinates::new(idx, new_start, data_len, length);
+    let new_coordinates = TraceCoordinates::new(idx, new_start, data_start, length as u64);
     segy.traces
-        .push(Trace::new(trace_header, new_start, data_len));
+        .push(Trace::new_u64(trace_header, data_start, data.len() as u64));
     segy.lookup.insert(idx, new_coordinates);
 
     Ok(segy.traces.last().expect("Just added."))
 }
+
+/// Check that the headers can count the samples of a trace. Before SEG-Y rev 2 the sample
+/// counts are 16 bit numbers, while SEG-Y rev 2 has 32 bit sample counts in the binary header
+/// and the first trace header extension.
+fn check_data_len(bin_header: &BinHeader, data_len: usize) -> Result<(), RsgError> {
+    let (max, reason) = if bin_header.segy_revision_number[0] < 2 {
+        (
+            u16::MAX as usize,
+            "the file is not SEG-Y rev 2, which has extended sample counts",
+        )
+    } else {
+        (
+            u32::MAX as usize,
+            "the extended sample counts of SEG-Y rev 2 are 32 bit numbers",
+        )
+    };
+    if data_len > max {
+        return Err(RsgError::LongDataVector {
+            l_data: data_len,
+            max,
+            reason,
+        });
+    }
+    Ok(())
+}
+
+/// Write the sample count of a trace which is too long for its trace header to the first trace
+/// header extension (SEG-Y rev 2). Fixed length traces may do without it, since the binary
+/// header gives their sample count.
+fn write_extended_sample_count(
+    extension: &mut [u8],
+    bin_header: &BinHeader,
+    data_len: usize,
+) -> Result<(), RsgError> {
+    let location = EXTENDED_SAMPLE_COUNT_BYTE_LOCATION..(EXTENDED_SAMPLE_COUNT_BYTE_LOCATION + 4);
+    let bytes = match extension.get_mut(location) {
+        Some(bytes) => bytes,
+        None if bin_header.fixed_length_trace_flag.yes() => return Ok(()),
+        None => {
+            return Err(RsgError::LongDataVector {
+                l_data: data_len,
+                max: u16::MAX as usize,
+                reason: "the sample count of a longer variable length trace is written to its \
+                    first trace header extension, and the binary header declares none",
+            })
+        }
+    };
+    let count = data_len as u32;
+    if bin_header.binary_flag_direction_is_le {
+        bytes.copy_from_slice(&count.to_le_bytes());
+    } else {
+        bytes.copy_from_slice(&count.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// Check that the number of samples of a trace fits the headers, so that a reader can find the
+/// traces that follow it. If the binary header declares fixed length traces, the data must have
+/// the number of samples given there (see [`BinHeader::sample_count`]), and a zero in the trace
+/// header is filled in, since some readers skip traces which claim to have no samples.
+/// Otherwise it must have the number of samples given in the trace header, which is filled in
+/// if it is zero (unless this is `strict`).
+///
+/// A sample count which does not fit in the trace header must be left at zero there (even if
+/// this is `strict`), and it is written to the first trace header extension instead (see
+/// [`write_extended_sample_count`]).
+fn check_sample_count(
+    trace_header: &mut TraceHeader,
+    bin_header: &BinHeader,
+    data_len: usize,
+    idx: usize,
+    strict: bool,
+) -> Result<(), RsgError> {
+    let fill = u16::try_from(data_len).unwrap_or(0);
+    if bin_header.fixed_length_trace_flag.yes() {
+        if data_len != bin_header.sample_count() {
+            return Err(RsgError::FixedLengthMismatch {
+                trace: idx,
+                l_data: data_len,
+                l_bin: bin_header.sample_count() as u32,
+            });
+        }
+        if trace_header.no_samples_in_trace == 0 {
+            trace_header.no_samples_in_trace = fill;
+        }
+    } else if trace_header.no_samples_in_trace == 0 && (!strict || data_len > u16::MAX as usize) {
+        trace_header.no_samples_in_trace = fill;
+    } else if data_len != trace_header.no_samples_in_trace as usize {
+        return Err(RsgError::TraceSampleCountMismatch {
+            trace: idx,
+            l_data: data_len,
+            l_trace: trace_header.no_samples_in_trace,
+        });
+    }
+    Ok(())
+}
+
+/// Get the values of `no_traces`, of the extended number of data traces and of
+/// `no_traces_in_file` in the binary header of a file with `count` traces. If there are more
+/// than 65535 traces, `no_traces` is zero and the number is given by the extended field.
+pub(crate) fn trace_count_fields(count: usize) -> Result<(u16, u32, u64), RsgError> {
+    match u16::try_from(count) {
+        Ok(no_traces) => Ok((no_traces, 0, count as u64)),
+        Err(_) => {
+            let extended = u32::try_from(count).map_err(|_| RsgError::TooManyTraces {
+                max: u32::MAX as usize,
+            })?;
+            Ok((0, extended, count as u64))
+        }
+    }
+}
+
+/// Fill in the sequence numbers of a trace header which are left at zero with the position of
+/// the trace in the file (counting from one).
+fn fill_sequence_numbers(trace_header: &mut TraceHeader, idx: usize) {
+    let sequence = i32::try_from(idx + 1).unwrap_or(i32::MAX);
+    for field in [
+        &mut trace_header.trace_sequence_on_line,
+        &mut trace_header.trace_sequence_in_file,
+        &mut trace_header.trace_no,
+    ] {
+        if *field == 0 {
+            *field = sequence;
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows several SEG-Y files (eg. the sail lines of a survey) to be merged
+//! into a single file.
+//!
+//! It uses `giga_segy_in` to parse the files, and thus requires the `edit` feature.
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegyProgress, SegySettings, TRACE_HEADER_LEN};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::edit::{copy_bytes, full_trace_settings, head_len, open_input, patch_trace_count};
+use crate::edit::{patch_trace_header, remove_if_cancelled, WriteProgress};
+use crate::open_output;
+use crate::write_data::{convert_data, get_format_and_le};
+use crate::write_headers::th_as_bytes_with_settings;
+
+/// The options for [`merge_files`].
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    /// The settings used to read the input files. These are also used to write the trace
+    /// headers of inputs whose byte order differs from the first input. The traces are always read whole and as they are stored, so `step_by` is taken
+    /// to be one, and an override of the sample count, the sort order, the auxiliary trace
+    /// policy, the coordinate transform and the gain (and the like) are ignored.
+    pub settings: SegySettings,
+    /// If this is set, all inputs must have the same sample format and endianness as the
+    /// first input. Otherwise, the data of the traces which differ is converted to the format
+    /// of the first input. The default is `true`.
+    pub strict_format: bool,
+    /// If this is set, all inputs must have the same sample count and sample interval in the
+    /// binary header as the first input. Otherwise, any differences are listed in the
+    /// [`MergeReport`]. The default is `true`.
+    pub strict_sampling: bool,
+    /// If this is set, the `trace_sequence_in_file` of the traces is renumbered, starting
+    /// from one. The default is `false`.
+    pub renumber_traces: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        MergeOptions {
+            settings: SegySettings::default(),
+            strict_format: true,
+            strict_sampling: true,
+            renumber_traces: false,
+        }
+    }
+}
+
+/// A difference between an input and the first input, which was tolerated during the merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeDiscrepancy {
+    /// The index of the input in the list of inputs.
+    pub input: usize,
+    /// A description of the difference.
+    pub msg: String,
+}
+
+/// A summary of the merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeReport {
+    /// The number of traces copied from each input, in the order of the inputs.
+    pub trace_counts: Vec<usize>,
+    /// The differences between inputs which were tolerated.
+    pub discrepancies: Vec<MergeDiscrepancy>,
+}
+
+impl MergeReport {
+    /// Get the total number of traces in the merged file.
+    pub fn total_trace_count(&self) -> usize {
+        self.trace_counts.iter().sum()
+    }
+}
+
+/// Merge several SEG-Y files into a new file at `output`.
+///
+/// The tape label, text header, binary header and extended headers of the first input are
+/// copied to the output, and the number of traces in the binary header is set to the total
+/// number of traces (in the extended number of data traces if there are more than 65535, as
+/// with [`crate::SegyFile::finalize`]). Then the traces of every input are appended in the
+/// order of the inputs. Trace data is copied byte for byte if the sample format and
+/// endianness of the input match the first input, and is converted otherwise (if allowed by
+/// the [`MergeOptions`]).
+///
+/// NB: If the sample counts of the inputs are allowed to differ, the sample count must be set
+/// in the trace headers, since the binary header of the first input is used for all traces.
+///
+/// NB2: The output file must not exist yet.
+pub fn merge_files(
+    inputs: &[&Path],
+    output: &Path,
+    opts: MergeOptions,
+) -> Result<MergeReport, RsgError> {
+    merge_files_with_progress(inputs, output, opts, |_| ControlFlow::Continue(()))
+}
+
+/// This works in the same way as [`merge_files`], but reports the progress to a callback every
+/// few hundred traces. If the callback returns `ControlFlow::Break(())`, the merge is stopped,
+/// the partially written output is removed and [`RsgError::Cancelled`] is returned.
+pub fn merge_files_with_progress<F>(
+    inputs: &[&Path],
+    output: &Path,
+    opts: MergeOptions,
+    mut progress: F,
+) -> Result<MergeReport, RsgError>
+where
+    F: FnMut(SegyProgress) -> ControlFlow<()>,
+{
+    remove_if_cancelled(merge_inner(inputs, output, opts, &mut progress), output)
+}
+
+fn merge_inner(
+    inputs: &[&Path],
+    output: &Path,
+    mut opts: MergeOptions,
+    progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+) -> Result<MergeReport, RsgError> {
+    opts.settings = full_trace_settings(opts.settings);
+    let (first_path, rest) = inputs
+        .split_first()
+        .ok_or_else(|| RsgError::SEGYSettingsError {
+            msg: "There must be at least one input file to merge".to_string(),
+        })?;
+    let first = open_input(first_path, opts.settings.clone())?;
+    let out_bin_header = first.get_bin_header().clone();
+    let (out_format, out_le) = get_format_and_le(&out_bin_header);
+
+    // Check the inputs before anything is written.
+    let mut report = MergeReport {
+        trace_counts: Vec::with_capacity(inputs.len()),
+        discrepancies: Vec::new(),
+    };
+    let mut others = Vec::with_capacity(rest.len());
+    for (i, path) in rest.iter().enumerate() {
+        let segy = open_input(path, opts.settings.clone())?;
+        compare_bin_headers(
+            i + 1,
+            &out_bin_header,
+            segy.get_bin_header(),
+            &opts,
+            &mut report,
+        )?;
+        others.push(segy);
+    }
+
+    let mut file = open_output(output, false)?;
+    let mut writer = BufWriter::new(&mut file);
+
+    // Copy the headers of the first file as they are.
+    let mut head = File::open(first_path)?;
+    copy_bytes(&mut head, &mut writer, head_len(&first) as u64)?;
+
+    let trace_total = first.trace_count() + others.iter().map(|s| s.trace_count()).sum::<usize>();
+    let mut progress = WriteProgress::new(progress, trace_total);
+    let mut sequence = 1;
+    for segy in std::iter::once(&first).chain(others.iter()) {
+        let (format, le) = get_format_and_le(segy.get_bin_header());
+        let same_format = format == out_format && le == out_le;
+
+        for i in 0..segy.trace_count() {
+            let trace = segy.load_trace(i)?;
+            let mut header = trace.get_header().clone();
+            if let Some(inline_no) = trace.original_inline_no() {
+                header.inline_no = inline_no;
+            }
+            if let Some(crossline_no) = trace.original_crossline_no() {
+                header.crossline_no = crossline_no;
+            }
+            let old_header = header.clone();
+            if opts.renumber_traces {
+                header.trace_sequence_in_file = sequence;
+                sequence += 1;
+            }
+            // The raw header is kept where the byte order allows it, so that fields which are
+            // not parsed (or not parsed as they are stored) survive the merge.
+            if le == out_le {
+                let mut raw = segy.get_trace_header_bytes(i)?.into_owned();
+                patch_trace_header(
+                    &mut raw,
+                    &old_header,
+                    &header,
+                    &opts.settings,
+                    &out_bin_header,
+                )?;
+                writer.write_all(&raw)?;
+            } else {
+                writer.write_all(&th_as_bytes_with_settings(
+                    &header,
+                    &opts.settings,
+                    &out_bin_header,
+                )?)?;
+            }
+            let extension = segy.get_trace_extension_bytes(i).unwrap_or_default();
+            writer.write_all(&extension)?;
+
+            let data = if same_format {
+                segy.get_trace_data_as_bytes_from_trace(&trace)?
+            } else {
+                let data = segy.get_trace_data_as_f32_from_trace(&trace)?;
+                convert_data(data, &out_bin_header)?
+            };
+            writer.write_all(&data)?;
+            progress.trace_written(TRACE_HEADER_LEN + extension.len() + data.len())?;
+        }
+        report.trace_counts.push(segy.trace_count());
+    }
+    progress.report()?;
+    writer.flush()?;
+    drop(writer);
+
+    // Finally, fix the number of traces in the binary header.
+    patch_trace_count(&mut file, &first, report.total_trace_count())?;
+
+    Ok(report)
+}
+
+/// Compare the binary header of an input with that of the first input. Depending on the
+/// options, differences either cause an error or are added to the report.
+fn compare_bin_headers(
+    input: usize,
+    first: &BinHeader,
+    other: &BinHeader,
+    opts: &MergeOptions,
+    report: &mut MergeReport,
+) -> Result<(), RsgError> {
+    let (first_format, first_le) = get_format_and_le(first);
+    let (format, le) = get_format_and_le(other);
+
+    let mut check = |same: bool, strict: bool, what: &str, a: String, b: String| {
+        if same {
+            return Ok(());
+        }
+        let msg = format!(
+            "Input {} has {} {}, but the first input has {}",
+            input, what, b, a
+        );
+        if strict {
+            Err(RsgError::InvalidHeader { msg })
+        } else {
+            report.discrepancies.push(MergeDiscrepancy { input, msg });
+            Ok(())
+        }
+    };
+
+    check(
+        format == first_format,
+        opts.strict_format,
+        "sample format",
+        first_format.to_string(),
+        format.to_string(),
+    )?;
+    check(
+        le == first_le,
+        opts.strict_format,
+        "little endian flag",
+        first_le.to_string(),
+        le.to_string(),
+    )?;
+    // A different number of trace header extensions can not be tolerated, since they are
+    // copied as they are.
+    check(
+        other.max_additional_trace_headers == first.max_additional_trace_headers,
+        true,
+        "trace header extension count",
+        first.max_additional_trace_headers.to_string(),
+        other.max_additional_trace_headers.to_string(),
+    )?;
+    check(
+        other.no_samples == first.no_samples,
+        opts.strict_sampling,
+        "sample count",
+        first.no_samples.to_string(),
+        other.no_samples.to_string(),
+    )?;
+    check(
+        other.sample_interval_us() == first.sample_interval_us(),
+        opts.strict_sampling,
+        "sample interval",
+        first.sample_interval_us().to_string(),
+        other.sample_interval_us().to_string(),
+    )
+}
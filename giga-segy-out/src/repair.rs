@@ -0,0 +1,338 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule writes a standard conforming copy of a malformed SEG-Y file, with the fields
+//! of the binary header which do not fit the traces corrected (see [`normalize`]).
+//!
+//! It uses `giga_segy_in` to parse the file, and thus requires the `edit` feature.
+use giga_segy_core::enums::*;
+use giga_segy_core::errors::*;
+use giga_segy_core::layout::{bin_header_layout, FieldKind};
+use giga_segy_core::standard_locations::NO_SAMPLES_IN_TRACE;
+use giga_segy_core::{BinHeader, SegySettings};
+use giga_segy_core::{BIN_HEADER_LEN, TEXT_HEADER_LEN, TRACE_HEADER_LEN};
+use giga_segy_in::SegyFile as InputSegyFile;
+
+use std::convert::{TryFrom, TryInto};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::edit::{copy_bytes, full_trace_settings, head_len, open_input, remove_if_failed};
+use crate::write_headers::bin_header_as_bytes_with_raw;
+use crate::{open_output, trace_count_fields};
+
+/// The options for [`normalize`].
+#[derive(Debug, Clone)]
+pub struct RepairPolicy {
+    /// The settings used to read the input. By default these are as tolerant as possible: The
+    /// byte order is detected heuristically if the binary header does not give it, the size of
+    /// the file is trusted over the sample format, and coordinates which cannot be parsed are
+    /// set to zero. The traces are always copied whole and in the order of the file, so
+    /// `step_by`, an override of the sample count, the sort order, the auxiliary trace policy,
+    /// the coordinate transform and the gain (and the like) are ignored.
+    pub settings: SegySettings,
+    /// If this is set, the trace counts of the binary header are set to the number of traces
+    /// which were found. The default is `true`.
+    pub fix_trace_count: bool,
+    /// If this is set and all traces have the same number of samples, that number is written
+    /// to the binary header. The sample count of each trace header which does not match its
+    /// trace is corrected (if it fits). The default is `true`.
+    pub fix_sample_counts: bool,
+    /// If this is set, enum codes of the binary header which are not valid are set to zero.
+    /// Otherwise they cannot be written, and an error is returned. The default is `true`.
+    pub fix_enum_codes: bool,
+}
+
+impl Default for RepairPolicy {
+    fn default() -> Self {
+        let mut settings = SegySettings::default();
+        settings.set_endianness_detection(EndianDetection::Heuristic);
+        settings.set_trust_file_size(true);
+        settings.set_on_coordinate_parse_error(CoordErrorPolicy::ZeroAndWarn);
+        RepairPolicy {
+            settings,
+            fix_trace_count: true,
+            fix_sample_counts: true,
+            fix_enum_codes: true,
+        }
+    }
+}
+
+/// A value which was corrected by [`normalize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairCorrection {
+    /// The index of the trace whose header was corrected, or `None` for the binary header.
+    pub trace: Option<usize>,
+    /// The name of the field (see [`giga_segy_core::layout`]).
+    pub field: &'static str,
+    /// The value in the input.
+    pub before: String,
+    /// The value in the output.
+    pub after: String,
+}
+
+/// A summary of the corrections which were made by [`normalize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of traces written.
+    pub trace_count: usize,
+    /// Every value which was corrected, with the fields of the binary header first.
+    pub corrections: Vec<RepairCorrection>,
+    /// The warnings which were recorded when the input was read (see
+    /// `giga_segy_in::SegyFile::get_warnings`).
+    pub warnings: Vec<String>,
+}
+
+impl RepairReport {
+    /// Check whether nothing had to be corrected.
+    pub fn is_clean(&self) -> bool {
+        self.corrections.is_empty()
+    }
+}
+
+/// Write a standard conforming copy of `input` to `output`, and list what was corrected.
+///
+/// The input is read with the settings of the [`RepairPolicy`], which are as tolerant as
+/// possible by default. Then the binary header is written again in the byte order that the
+/// input was read in, which also writes the byte order flag (bytes 3297-3300), with the sample
+/// format that the input was read with. The trace counts, the sample counts and invalid enum
+/// codes are corrected as the policy asks for. Every field of the binary header whose bytes
+/// change is listed in the [`RepairReport`] with its value before and after.
+///
+/// The text header, the extended headers and the traces (including their trace header
+/// extensions) are copied byte for byte, except for the sample counts of the trace headers.
+/// Anything between the trace headers and the data (see
+/// [`SegySettings::set_trace_prefix_bytes`]) and anything after the last trace is left out.
+///
+/// An error is returned if the input cannot be read even with the settings of the policy. If
+/// the copy fails after the output was created, the partially written output is removed.
+///
+/// NB: The output file must not exist yet.
+/// ```
+/// # use std::env::var;
+/// # use std::path::PathBuf;
+/// use giga_segy_out::repair::normalize;
+///
+/// # let mut root = var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap();
+/// # root.pop();
+/// let input = root.join("testdata").join("DutchMiniHead.sgy");
+/// let dir = tempfile::tempdir().unwrap();
+/// let output = dir.path().join("normalized.sgy");
+///
+/// let report = normalize(&input, &output, Default::default()).unwrap();
+/// for c in report.corrections.iter() {
+///     println!("{}: {} -> {}", c.field, c.before, c.after);
+/// }
+/// ```
+pub fn normalize<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    policy: RepairPolicy,
+) -> Result<RepairReport, RsgError> {
+    let input = input.as_ref();
+    let output = output.as_ref();
+    let segy = open_input(input, full_trace_settings(policy.settings.clone()))?;
+    let raw: [u8; BIN_HEADER_LEN] = segy.get_bin_header_bytes().try_into()?;
+    let le = segy.get_bin_header().binary_flag_direction_is_le;
+
+    let mut bin_header = segy.get_bin_header().clone();
+    if policy.fix_trace_count {
+        set_trace_count(&mut bin_header, segy.trace_count())?;
+    }
+    let sample_counts = (0..segy.trace_count())
+        .map(|i| segy.trace_sample_count(i).unwrap_or(0))
+        .collect::<Vec<_>>();
+    if policy.fix_sample_counts {
+        if let Some(first) = sample_counts.first() {
+            if sample_counts.iter().all(|c| c == first) {
+                set_sample_count(&mut bin_header, *first);
+            }
+        }
+    }
+    if policy.fix_enum_codes {
+        fix_enum_codes(&mut bin_header);
+    }
+    let new_raw = bin_header_as_bytes_with_raw(&bin_header, Some(&raw))?;
+    let mut corrections = bin_header_corrections(&raw, &new_raw, le);
+
+    let mut file = open_output(output, false)?;
+    let result = normalize_into(
+        &segy,
+        input,
+        &mut file,
+        &new_raw,
+        &sample_counts,
+        &policy,
+        &mut corrections,
+    );
+    remove_if_failed(result, output)?;
+
+    Ok(RepairReport {
+        trace_count: segy.trace_count(),
+        corrections,
+        warnings: segy.get_warnings().to_vec(),
+    })
+}
+
+/// Copy `segy` (read from `input`) to `file` with the binary header replaced by `new_raw`, and
+/// correct the sample counts of the trace headers if the policy asks for it.
+fn normalize_into(
+    segy: &InputSegyFile,
+    input: &Path,
+    file: &mut File,
+    new_raw: &[u8],
+    sample_counts: &[usize],
+    policy: &RepairPolicy,
+    corrections: &mut Vec<RepairCorrection>,
+) -> Result<(), RsgError> {
+    let le = segy.get_bin_header().binary_flag_direction_is_le;
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(file);
+    let bin_header_start = segy.get_text_header_offset() + TEXT_HEADER_LEN;
+    copy_bytes(&mut reader, &mut writer, bin_header_start as u64)?;
+    writer.write_all(new_raw)?;
+    reader.seek(SeekFrom::Current(BIN_HEADER_LEN as i64))?;
+    copy_bytes(
+        &mut reader,
+        &mut writer,
+        (head_len(segy) - bin_header_start - BIN_HEADER_LEN) as u64,
+    )?;
+
+    let mut headers = Vec::new();
+    for (i, sample_count) in sample_counts.iter().enumerate() {
+        let trace = segy.load_trace(i)?;
+        let header_start = trace.extension_range().start - TRACE_HEADER_LEN;
+        headers.resize(trace.extension_range().end - header_start, 0);
+        reader.seek(SeekFrom::Start(header_start as u64))?;
+        reader.read_exact(&mut headers)?;
+        if policy.fix_sample_counts {
+            if let Some(c) = fix_trace_sample_count(&mut headers, *sample_count, le) {
+                corrections.push(RepairCorrection {
+                    trace: Some(i),
+                    ..c
+                });
+            }
+        }
+        writer.write_all(&headers)?;
+        reader.seek(SeekFrom::Start(trace.get_start() as u64))?;
+        copy_bytes(&mut reader, &mut writer, trace.len() as u64)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Set the trace counts of the binary header in the same way as [`crate::SegyFile::finalize`].
+fn set_trace_count(bin_header: &mut BinHeader, count: usize) -> Result<(), RsgError> {
+    let (no_traces, extended, in_file) = trace_count_fields(count)?;
+    bin_header.no_traces = no_traces;
+    bin_header.extended_no_traces = extended;
+    if bin_header.segy_revision_number[0] >= 2 {
+        bin_header.no_traces_in_file = in_file;
+    }
+    Ok(())
+}
+
+/// Set the sample count of the binary header, using the extended sample count of SEG-Y rev 2
+/// if it does not fit in [`BinHeader::no_samples`].
+fn set_sample_count(bin_header: &mut BinHeader, count: usize) {
+    if bin_header.sample_count() == count {
+        return;
+    }
+    match u16::try_from(count) {
+        Ok(no_samples) => {
+            bin_header.no_samples = no_samples;
+            bin_header.extended_no_samples = 0;
+        }
+        Err(_) => {
+            bin_header.no_samples = 0;
+            bin_header.extended_no_samples = u32::try_from(count).unwrap_or(u32::MAX);
+        }
+    }
+}
+
+/// Set the enum codes of the binary header which are not valid to zero.
+fn fix_enum_codes(bin_header: &mut BinHeader) {
+    macro_rules! fix {
+        ($($field:ident: $enum:ident),+ $(,)?) => {
+            $(
+                if bin_header.$field == $enum::Invalid {
+                    bin_header.$field = $enum::new(0);
+                }
+            )+
+        };
+    }
+    fix!(
+        sorting_code: TraceSortingCode,
+        sweep_type: SweepTypeCode,
+        taper_type: TaperType,
+        correlated_traces: CorrelatedDataTraces,
+        binary_gain_recovered: BinaryGainRecovered,
+        amplitude_recovery_method: AmplitudeRecoveryMethod,
+        measurement_system: MeasurementSystem,
+        impulse_signal_polarity: ImpulseSignalPolarity,
+        vibratory_polarity_code: VibratoryPolarityCode,
+        time_basis_code: TimeBasisCode,
+    );
+}
+
+/// List the fields of the binary header whose bytes differ between `before` and `after`.
+fn bin_header_corrections(before: &[u8], after: &[u8], le: bool) -> Vec<RepairCorrection> {
+    bin_header_layout()
+        .iter()
+        .filter(|f| before[f.range.clone()] != after[f.range.clone()])
+        .map(|f| RepairCorrection {
+            trace: None,
+            field: f.name,
+            before: field_value(&before[f.range.clone()], f.kind, le),
+            after: field_value(&after[f.range.clone()], f.kind, le),
+        })
+        .collect()
+}
+
+/// Set the sample count of a trace header (given as bytes) to the number of samples of its
+/// trace if it differs and fits, and return the correction.
+fn fix_trace_sample_count(header: &mut [u8], count: usize, le: bool) -> Option<RepairCorrection> {
+    let range = NO_SAMPLES_IN_TRACE..(NO_SAMPLES_IN_TRACE + 2);
+    let before = header[range.clone()].to_vec();
+    let count = u16::try_from(count).ok()?;
+    let after = if le {
+        count.to_le_bytes()
+    } else {
+        count.to_be_bytes()
+    };
+    if before == after {
+        return None;
+    }
+    header[range].copy_from_slice(&after);
+    Some(RepairCorrection {
+        trace: None,
+        field: "no_samples_in_trace",
+        before: field_value(&before, FieldKind::U16, le),
+        after: count.to_string(),
+    })
+}
+
+/// Show the value of a header field, given its bytes.
+fn field_value(bytes: &[u8], kind: FieldKind, le: bool) -> String {
+    macro_rules! read {
+        ($t:ty) => {{
+            let bytes = bytes
+                .try_into()
+                .expect("The field has the size of its kind.");
+            let value = if le {
+                <$t>::from_le_bytes(bytes)
+            } else {
+                <$t>::from_be_bytes(bytes)
+            };
+            value.to_string()
+        }};
+    }
+    match (kind, bytes.len()) {
+        (FieldKind::I16, _) | (FieldKind::Enum, 2) => read!(i16),
+        (FieldKind::U16, _) => read!(u16),
+        (FieldKind::I32, _) | (FieldKind::Coordinate, _) => read!(i32),
+        (FieldKind::U32, _) => read!(u32),
+        (FieldKind::U64, _) => read!(u64),
+        (FieldKind::F64, _) => read!(f64),
+        _ => format!("{:?}", bytes),
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows a SEG-Y file to be copied with fewer samples per trace, by keeping
+//! only every `factor`-th sample (eg. to turn a 2 ms volume into a 4 ms volume).
+//!
+//! It uses `giga_segy_in` to parse the file, and thus requires the `edit` feature.
+use giga_segy_core::bitconverter::decode_samples;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings};
+use giga_segy_in::SegyFile as InputSegyFile;
+
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::edit::remove_if_failed;
+use crate::edit::{copy_bytes, full_trace_settings, head_len, open_input};
+use crate::edit::{patch_bin_header_bytes, patch_bin_header_u16, patch_bin_header_u32};
+use crate::write_data::encode_samples;
+use crate::write_headers::th_as_bytes_with_settings;
+use crate::{open_output, write_extended_sample_count};
+
+/// A summary of the decimation of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecimationReport {
+    /// The number of traces written.
+    pub trace_count: usize,
+    /// The indices of the traces whose sample count was not a multiple of the factor. The
+    /// samples after the last whole multiple were dropped from these traces.
+    pub truncated: Vec<usize>,
+}
+
+/// Write `input` to `output` with only every `factor`-th sample of each trace, starting with
+/// the first.
+///
+/// The sample interval in the binary header and in each trace header is multiplied by the
+/// factor, and the sample counts are divided by it. If a trace has a sample count which is
+/// not a multiple of the factor, its last samples are dropped so that every sample of the
+/// output stands for `factor` samples of the input, and the trace is listed in the
+/// [`DecimationReport`]. Traces of different lengths are each decimated on their own. Sample
+/// counts which no longer fit in the binary header or a trace header go to the extended fields
+/// of SEG-Y rev 2 (bytes 3269-3272 and the first trace header extension), which are also
+/// updated wherever the input used them.
+///
+/// If `antialias` is not set, the bytes of the samples which are kept are copied as they are,
+/// so that the values are exact in any sample format. Otherwise the traces are first passed
+/// through a low-pass filter (a Hamming windowed sinc with a cutoff at the new Nyquist
+/// frequency, renormalised at the ends of the trace), and the filtered data is converted
+/// back to the sample format of the input. The samples are read from the file as they are
+/// (without any gain or trace weighting of the `settings`), and IBM floats, 24-bit integers
+/// and 32-bit fixed point numbers are written as well, with the integers rounded to the
+/// nearest value that the format can hold.
+///
+/// The `settings` are used to read the input and to write the trace headers, except that
+/// `step_by` is always taken to be one, and an override of the sample count, the sort order,
+/// the auxiliary trace policy and the coordinate transform are ignored. If they override the
+/// sample format (see [`SegySettings::set_override_trace_format`]), the samples are taken to be
+/// in that format, and its code is written to the binary header of the output. The rest of the
+/// headers and any trace header extensions are copied as they are.
+///
+/// An error is returned if the factor is zero, or if the new sample interval can not be
+/// written to the binary header (see [`giga_segy_core::BinHeader::try_set_sample_interval_us`]).
+/// The sample interval of a trace header is set to zero if it no longer fits. If an error
+/// happens once the output has been created, the partially written output is removed.
+///
+/// NB: The output file must not exist yet.
+pub fn decimate<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    factor: usize,
+    antialias: bool,
+    settings: SegySettings,
+) -> Result<DecimationReport, RsgError> {
+    if factor == 0 {
+        let msg = "The decimation factor must be larger than zero.".to_string();
+        return Err(RsgError::SEGYSettingsError { msg });
+    }
+    let input = input.as_ref();
+    let output = output.as_ref();
+    let segy = open_input(input, full_trace_settings(settings))?;
+    let bin_header = segy.get_bin_header();
+
+    let mut new_bin_header = bin_header.clone();
+    // The count came from a `u16` or a `u32`, so the smaller one still fits.
+    let new_count = bin_header.sample_count() / factor;
+    new_bin_header.no_samples = u16::try_from(new_count).unwrap_or(0);
+    if bin_header.extended_no_samples > 0 || new_bin_header.no_samples as usize != new_count {
+        new_bin_header.extended_no_samples = new_count as u32;
+    }
+    new_bin_header.try_set_sample_interval_us(bin_header.sample_interval_us() * factor as f64)?;
+
+    let mut file = open_output(output, false)?;
+    let result = decimate_into(&segy, input, &mut file, factor, antialias, &new_bin_header);
+    remove_if_failed(result, output)
+}
+
+/// Write the decimated headers and traces of `segy` (read from `input`) to `file`, and patch the
+/// binary header to match `new_bin_header`.
+fn decimate_into(
+    segy: &InputSegyFile,
+    input: &Path,
+    file: &mut File,
+    factor: usize,
+    antialias: bool,
+    new_bin_header: &BinHeader,
+) -> Result<DecimationReport, RsgError> {
+    let bin_header = segy.get_bin_header();
+    // The samples are in the format of the override, if there is one, whatever the code in the
+    // binary header says.
+    let format = segy
+        .get_settings()
+        .get_override_trace_format()
+        .unwrap_or(bin_header.sample_format_code);
+    let le = bin_header.binary_flag_direction_is_le;
+    let datum_len = format.datum_byte_length();
+
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(&mut *file);
+
+    copy_bytes(&mut reader, &mut writer, head_len(segy) as u64)?;
+
+    let filter = if antialias {
+        low_pass_filter(factor)
+    } else {
+        Vec::new()
+    };
+    let mut truncated = Vec::new();
+    for i in 0..segy.trace_count() {
+        let trace = segy.load_trace(i)?;
+        let sample_count = trace.len() / datum_len;
+        let new_count = sample_count / factor;
+        if new_count * factor != sample_count {
+            truncated.push(i);
+        }
+
+        let mut bytes = vec![0; sample_count * datum_len];
+        reader.seek(SeekFrom::Start(trace.get_start() as u64))?;
+        reader.read_exact(&mut bytes)?;
+        let data = if antialias {
+            let samples = decode_samples(&bytes, format, le)?;
+            encode_samples(&apply_filter(&samples, &filter, factor), format, le)?
+        } else {
+            bytes
+                .chunks_exact(datum_len * factor)
+                .flat_map(|samples| &samples[..datum_len])
+                .cloned()
+                .collect()
+        };
+
+        // A count which does not fit in the trace header is written to its first extension,
+        // as is any count which the input had there.
+        let mut header = trace.get_header().clone();
+        let count_in_extension = header.no_samples_in_trace == 0;
+        header.no_samples_in_trace = u16::try_from(new_count).unwrap_or(0);
+        header.sample_interval_of_trace = (header.sample_interval_of_trace as usize)
+            .checked_mul(factor)
+            .filter(|interval| *interval <= u16::MAX as usize)
+            .unwrap_or(0) as u16;
+        writer.write_all(&th_as_bytes_with_settings(
+            &header,
+            segy.get_settings(),
+            bin_header,
+        )?)?;
+        let mut extension = vec![0; trace.extension_range().len()];
+        reader.seek(SeekFrom::Start(trace.extension_range().start as u64))?;
+        reader.read_exact(&mut extension)?;
+        if (header.no_samples_in_trace == 0 && new_count > 0)
+            || (count_in_extension && sample_count > 0)
+        {
+            write_extended_sample_count(&mut extension, new_bin_header, new_count)?;
+        }
+        writer.write_all(&extension)?;
+        writer.write_all(&data)?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    patch_bin_header_u16(file, segy, 16, new_bin_header.sample_interval)?;
+    patch_bin_header_u16(file, segy, 20, new_bin_header.no_samples)?;
+    if bin_header.raw_sample_format_code != format as u16 {
+        patch_bin_header_u16(file, segy, 24, format as u16)?;
+    }
+    if new_bin_header.extended_no_samples != bin_header.extended_no_samples {
+        patch_bin_header_u32(file, segy, 68, new_bin_header.extended_no_samples)?;
+    }
+    if new_bin_header.extended_sample_interval != bin_header.extended_sample_interval {
+        let bits = new_bin_header.extended_sample_interval.to_bits();
+        let bytes = if le {
+            bits.to_le_bytes()
+        } else {
+            bits.to_be_bytes()
+        };
+        patch_bin_header_bytes(file, segy, 72, &bytes)?;
+    }
+    Ok(DecimationReport {
+        trace_count: segy.trace_count(),
+        truncated,
+    })
+}
+
+/// Get the taps of a low-pass filter for decimation by `factor`: A sinc with a cutoff at
+/// `0.5 / factor` cycles per sample, with a Hamming window over `4 * factor` samples on each
+/// side. The taps are normalised to a sum of one.
+fn low_pass_filter(factor: usize) -> Vec<f64> {
+    let half = 4 * factor as i64;
+    let cutoff = 0.5 / factor as f64;
+    let taps = (-half..=half)
+        .map(|j| {
+            let x = 2. * cutoff * j as f64;
+            let sinc = if j == 0 {
+                1.
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let window = 0.54 + 0.46 * (PI * j as f64 / half as f64).cos();
+            sinc * window
+        })
+        .collect::<Vec<_>>();
+    let sum = taps.iter().sum::<f64>();
+    taps.into_iter().map(|t| t / sum).collect()
+}
+
+/// Filter the samples and keep every `factor`-th one. At the ends of the trace, where part of
+/// the filter falls outside of it, the taps which remain are scaled back to a sum of one.
+fn apply_filter(samples: &[f32], filter: &[f64], factor: usize) -> Vec<f32> {
+    let half = filter.len() / 2;
+    (0..samples.len() / factor)
+        .map(|k| {
+            let centre = k * factor;
+            let first = half.saturating_sub(centre);
+            let last = filter.len().min(samples.len() + half - centre);
+            let (mut value, mut weight) = (0., 0.);
+            for (tap, sample) in filter[first..last]
+                .iter()
+                .zip(&samples[(centre + first - half)..])
+            {
+                value += tap * *sample as f64;
+                weight += tap;
+            }
+            (value / weight) as f32
+        })
+        .collect()
+}
@@ -16,16 +16,27 @@ use giga_segy_core::SegySettings;
 ///
 /// 3) inline and crossline no bidx
 ///
+/// 4) the endianness of the file (which takes precedence over the `BinHeader`).
+///
 /// Other settings cannot be overridden when creating a segy file, because
 /// the API user has direct access to the `BinHeader` and `TraceHeader` fields
 /// when creating them, thus over-riding them seems counterintuitive (why override
 /// something that you had the chance to set yourself a few lines earlier?).
 ///
 /// NB2: Changing the settings for byte indices will the TraceHeader in ways that
-/// Will override other settings without compensating for the changes.
+/// Will override other settings without compensating for the changes. Because of this, a file
+/// is only created with byte indices which overlap other fields if this is allowed (see
+/// [`SegyWriteSettings::get_allow_bidx_collisions`]).
 pub trait SegyWriteSettings {
     fn get_override_coordinate_format(&self) -> Option<SampleFormatCode>;
 
+    /// Get the endianness override, if any. `Some(true)` means that the file is written as
+    /// little endian and `Some(false)` means that it is written as big endian. The default
+    /// implementation returns `None`, so the endianness of the `BinHeader` is used.
+    fn get_override_to_le(&self) -> Option<bool> {
+        None
+    }
+
     /// Get the byte index of the inline number.
     fn get_inline_no_bidx(&self) -> usize;
 
@@ -53,6 +64,56 @@ pub trait SegyWriteSettings {
     /// Sets the trace format to the input. NB: This may return an error if the format code is for
     /// a format which is not four bytes long (because that would raise more questions than it answers).
     fn set_override_coordinate_format(&mut self, format: SampleFormatCode) -> Result<(), RsgError>;
+
+    /// Sets whether the file is written as little endian (`true`) or big endian (`false`).
+    /// This has no default implementation, so that a setting cannot be lost silently.
+    fn set_override_to_le(&mut self, le: bool);
+
+    /// Get whether the text header is normalized with
+    /// [`crate::text_header::normalize_text_header`] before it is written. The default
+    /// implementation returns `false`.
+    fn get_strict_text_header(&self) -> bool {
+        false
+    }
+
+    /// Get whether more than 65535 traces may be written to a file (see
+    /// [`SegySettings::set_extended_trace_count`]). The default implementation returns `false`.
+    fn get_extended_trace_count(&self) -> bool {
+        false
+    }
+
+    /// Get whether the sequence numbers of the trace headers are filled in when they are zero
+    /// (see [`SegySettings::set_auto_sequence`]). The default implementation returns `false`.
+    fn get_auto_sequence(&self) -> bool {
+        false
+    }
+
+    /// Get whether a trace header without a sample count is an error in files with variable
+    /// length traces (see [`SegySettings::set_strict_sample_count`]). The default
+    /// implementation returns `false`.
+    fn get_strict_sample_count(&self) -> bool {
+        false
+    }
+
+    /// Get whether a file may be created with byte indices which overlap other fields of the
+    /// trace header (see [`SegySettings::set_allow_bidx_collisions`]). The default
+    /// implementation returns `false`.
+    fn get_allow_bidx_collisions(&self) -> bool {
+        false
+    }
+
+    /// Get whether a card which records how the file was written is added to the text header
+    /// when the file is finalized (see [`SegySettings::set_append_provenance_card`]). The
+    /// default implementation returns `false`.
+    fn get_append_provenance_card(&self) -> bool {
+        false
+    }
+
+    /// Check that the settings are consistent. This is called when a file is created. The
+    /// default implementation accepts any settings.
+    fn validate(&self) -> Result<(), RsgError> {
+        Ok(())
+    }
 }
 
 impl SegyWriteSettings for SegySettings {
@@ -60,6 +121,10 @@ impl SegyWriteSettings for SegySettings {
         self.get_override_coordinate_format()
     }
 
+    fn get_override_to_le(&self) -> Option<bool> {
+        self.get_override_to_le()
+    }
+
     fn get_inline_no_bidx(&self) -> usize {
         self.get_inline_no_bidx()
     }
@@ -95,4 +160,36 @@ impl SegyWriteSettings for SegySettings {
     fn set_override_coordinate_format(&mut self, format: SampleFormatCode) -> Result<(), RsgError> {
         self.set_override_coordinate_format(format)
     }
+
+    fn set_override_to_le(&mut self, le: bool) {
+        self.set_override_to_le(le)
+    }
+
+    fn get_strict_text_header(&self) -> bool {
+        self.get_strict_text_header()
+    }
+
+    fn get_extended_trace_count(&self) -> bool {
+        self.get_extended_trace_count()
+    }
+
+    fn get_auto_sequence(&self) -> bool {
+        self.get_auto_sequence()
+    }
+
+    fn get_strict_sample_count(&self) -> bool {
+        self.get_strict_sample_count()
+    }
+
+    fn get_allow_bidx_collisions(&self) -> bool {
+        self.get_allow_bidx_collisions()
+    }
+
+    fn get_append_provenance_card(&self) -> bool {
+        self.get_append_provenance_card()
+    }
+
+    fn validate(&self) -> Result<(), RsgError> {
+        self.validate()
+    }
 }
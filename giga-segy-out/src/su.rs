@@ -0,0 +1,115 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`SuWriter`], which writes Seismic Unix (SU) files: Streams of
+//! SEG-Y traces without the text and binary headers, with IEEE float samples.
+use giga_segy_core::enums::SampleFormatCode;
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, TraceHeader};
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::create_headers::CreateBinHeader;
+use crate::settings::SegyWriteSettings;
+use crate::write_data::convert_data;
+use crate::write_headers::th_as_bytes_with_settings;
+use crate::{check_bidx_collisions, check_sample_count, fill_sequence_numbers, open_output};
+
+/// Writes the traces of an SU file one by one.
+///
+/// The trace headers are written as by [`crate::SegyFile`] with the same settings, and the
+/// samples are written as `Float32`. The file is written in the byte order of the settings (see
+/// [`giga_segy_core::SegySettings::set_override_to_le`]), or else in that of the machine, as
+/// is usual for SU files. Since there is no binary header, the sample count and sample interval
+/// of each trace must be given in its trace header (a sample count of zero is filled in unless
+/// the settings ask for a strict sample count).
+/// ```
+/// use giga_segy_out::su::SuWriter;
+/// use giga_segy_out::create_headers::CreateTraceHeader;
+/// use giga_segy_core::{SegySettings, TraceHeader};
+///
+/// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+/// let path = dir.path().join("traces.su");
+///
+/// let mut writer = SuWriter::create(&path, SegySettings::default()).unwrap();
+/// for i in 0..3 {
+///     let mut header = TraceHeader::new_2d(i * 100, 0, 1);
+///     header.sample_interval_of_trace = 4000;
+///     writer.add_trace(header, &[i as f32; 25]).unwrap();
+/// }
+/// assert_eq!(writer.finish().unwrap(), 3);
+/// assert_eq!(std::fs::metadata(&path).unwrap().len(), 3 * (240 + 25 * 4));
+/// ```
+pub struct SuWriter<S: SegyWriteSettings> {
+    file: BufWriter<File>,
+    settings: S,
+    /// The binary header which gives the sample format and byte order to the functions which
+    /// convert the headers and data. It is not written.
+    bin_header: BinHeader,
+    trace_count: usize,
+}
+
+impl<S: SegyWriteSettings> SuWriter<S> {
+    /// Create the file. An error is returned if it already exists, or if the settings are not
+    /// valid for writing (as with [`crate::SegyFile::create_file`]).
+    pub fn create<P: AsRef<Path>>(path: P, settings: S) -> Result<Self, RsgError> {
+        settings.validate()?;
+        check_bidx_collisions(&settings)?;
+        let mut bin_header = BinHeader::new(0, 0, 0, SampleFormatCode::Float32);
+        bin_header.binary_flag_direction_is_le = settings
+            .get_override_to_le()
+            .unwrap_or(cfg!(target_endian = "little"));
+        let file = open_output(path.as_ref(), false)?;
+        Ok(SuWriter {
+            file: BufWriter::new(file),
+            settings,
+            bin_header,
+            trace_count: 0,
+        })
+    }
+
+    /// Write a trace to the end of the file. An error is returned if the number of samples
+    /// does not match the trace header, or if there are more than 65535 samples.
+    pub fn add_trace(&mut self, mut header: TraceHeader, data: &[f32]) -> Result<(), RsgError> {
+        let idx = self.trace_count;
+        if data.len() > u16::MAX as usize {
+            return Err(RsgError::LongDataVector {
+                l_data: data.len(),
+                max: u16::MAX as usize,
+                reason: "SU files only have the 16 bit sample count of the trace header",
+            });
+        }
+        if self.settings.get_auto_sequence() {
+            fill_sequence_numbers(&mut header, idx);
+        }
+        check_sample_count(
+            &mut header,
+            &self.bin_header,
+            data.len(),
+            idx,
+            self.settings.get_strict_sample_count(),
+        )?;
+        let header_bytes = th_as_bytes_with_settings(&header, &self.settings, &self.bin_header)?;
+        self.file.write_all(&header_bytes)?;
+        self.file
+            .write_all(&convert_data(data.to_vec(), &self.bin_header)?)?;
+        self.trace_count += 1;
+        Ok(())
+    }
+
+    /// Get whether the file is written as little endian.
+    pub fn is_le(&self) -> bool {
+        self.bin_header.binary_flag_direction_is_le
+    }
+
+    /// Get the number of traces which have been written so far.
+    pub fn trace_count(&self) -> usize {
+        self.trace_count
+    }
+
+    /// Write everything which is still buffered to the file, and return the number of traces.
+    pub fn finish(mut self) -> Result<usize, RsgError> {
+        self.file.flush()?;
+        Ok(self.trace_count)
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows a subset of a SEG-Y file, given by a range of inlines and crosslines
+//! (and optionally a window of samples), to be written to a new file.
+//!
+//! It uses `giga_segy_in` to parse the file, and thus requires the `edit` feature.
+use giga_segy_core::errors::*;
+use giga_segy_core::{SegyProgress, SegySettings, TraceHeader, TRACE_HEADER_LEN};
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
+
+use crate::edit::{copy_bytes, full_trace_settings, head_len, open_input};
+use crate::edit::{patch_bin_header_u16, patch_bin_header_u32, patch_trace_count};
+use crate::edit::{remove_if_cancelled, WriteProgress};
+use crate::write_headers::th_as_bytes_with_settings;
+use crate::{open_output, write_extended_sample_count};
+
+/// Write the traces of `input` whose inline and crossline numbers are within the given
+/// (inclusive) ranges to `output`, and return the number of traces written.
+///
+/// The headers of the input are copied, with the number of traces in the binary header set to
+/// the number of traces written (in the extended number of data traces if there are more than
+/// 65535, as with [`crate::SegyFile::finalize`]). The traces themselves, including their
+/// headers and any trace header extensions, are copied byte for byte.
+///
+/// The `settings` are used to read the input. Any inline and crossline bounds that they
+/// contain are replaced by the given ranges. The traces are always copied whole and in the
+/// order of the file, so `step_by` is taken to be one, and an override of the sample count, the
+/// sort order and the auxiliary trace policy are ignored.
+///
+/// NB: The output file must not exist yet.
+pub fn crop<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    inline_range: [i32; 2],
+    crossline_range: [i32; 2],
+    settings: SegySettings,
+) -> Result<usize, RsgError> {
+    crop_with_progress(
+        input,
+        output,
+        inline_range,
+        crossline_range,
+        None,
+        settings,
+        |_| ControlFlow::Continue(()),
+    )
+}
+
+/// This works in the same way as [`crop`], but only the samples within `sample_window` are
+/// kept in each trace.
+///
+/// The bytes of the samples in the window are copied as they are, so this works for any sample
+/// format, and the sample counts in the binary header and the trace headers are adjusted. The
+/// delay recording time of each trace is moved to the start of the window (rounded to the
+/// nearest value that the header can hold, since it is given in whole milliseconds times the
+/// time scalar of the header). Traces that are shorter than the window are truncated at their
+/// end.
+///
+/// A window of more than [`u16::MAX`] samples is only possible with SEG-Y rev 2, where the
+/// sample count goes to the extended fields (and, for traces of variable length, to the first
+/// trace header extension).
+pub fn crop_with_window<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    inline_range: [i32; 2],
+    crossline_range: [i32; 2],
+    sample_window: Range<usize>,
+    settings: SegySettings,
+) -> Result<usize, RsgError> {
+    crop_with_progress(
+        input,
+        output,
+        inline_range,
+        crossline_range,
+        Some(sample_window),
+        settings,
+        |_| ControlFlow::Continue(()),
+    )
+}
+
+/// This works in the same way as [`crop`] (or [`crop_with_window`] if a `sample_window` is
+/// given), but reports the progress to a callback every few hundred traces. If the callback
+/// returns `ControlFlow::Break(())`, cropping is stopped, the partially written output is
+/// removed and [`RsgError::Cancelled`] is returned.
+pub fn crop_with_progress<P, Q, F>(
+    input: P,
+    output: Q,
+    inline_range: [i32; 2],
+    crossline_range: [i32; 2],
+    sample_window: Option<Range<usize>>,
+    settings: SegySettings,
+    mut progress: F,
+) -> Result<usize, RsgError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(SegyProgress) -> ControlFlow<()>,
+{
+    let output = output.as_ref();
+    let result = crop_inner(
+        input.as_ref(),
+        output,
+        inline_range,
+        crossline_range,
+        sample_window,
+        settings,
+        &mut progress,
+    );
+    remove_if_cancelled(result, output)
+}
+
+fn crop_inner(
+    input: &Path,
+    output: &Path,
+    inline_range: [i32; 2],
+    crossline_range: [i32; 2],
+    sample_window: Option<Range<usize>>,
+    mut settings: SegySettings,
+    progress: &mut dyn FnMut(SegyProgress) -> ControlFlow<()>,
+) -> Result<usize, RsgError> {
+    if let Some(ref w) = sample_window {
+        if w.start >= w.end {
+            let msg = format!("Invalid sample window: {:?}", w);
+            return Err(RsgError::SEGYSettingsError { msg });
+        }
+    }
+
+    settings.set_inlne_min_max(inline_range);
+    settings.set_crossline_min_max(crossline_range);
+    let segy = open_input(input, full_trace_settings(settings))?;
+    let bin_header = segy.get_bin_header();
+    let datum_len = bin_header.sample_format_code.datum_byte_length();
+
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut file = open_output(output, false)?;
+    let mut writer = BufWriter::new(&mut file);
+
+    copy_bytes(&mut reader, &mut writer, head_len(&segy) as u64)?;
+
+    let mut progress = WriteProgress::new(progress, segy.trace_count());
+    for i in 0..segy.trace_count() {
+        let trace = segy.load_trace(i)?;
+        let header_start = trace.extension_range().start - TRACE_HEADER_LEN;
+        match sample_window {
+            None => {
+                // The headers and the data are copied separately, so that a prefix (see
+                // `SegySettings::set_trace_prefix_bytes`) is left out.
+                let headers_end = trace.extension_range().end;
+                reader.seek(SeekFrom::Start(header_start as u64))?;
+                copy_bytes(
+                    &mut reader,
+                    &mut writer,
+                    (headers_end - header_start) as u64,
+                )?;
+                reader.seek(SeekFrom::Start(trace.get_start() as u64))?;
+                copy_bytes(&mut reader, &mut writer, trace.len() as u64)?;
+                progress.trace_written(headers_end - header_start + trace.len())?;
+            }
+            Some(ref w) => {
+                let sample_count = trace.len() / datum_len;
+                let window = w.start.min(sample_count)..w.end.min(sample_count);
+                let mut data = vec![0; window.len() * datum_len];
+                reader.seek(SeekFrom::Start(
+                    (trace.get_start() + window.start * datum_len) as u64,
+                ))?;
+                reader.read_exact(&mut data)?;
+
+                // A count which does not fit in the trace header is written to its first
+                // extension.
+                let mut header = trace.get_header().clone();
+                header.no_samples_in_trace = u16::try_from(window.len()).unwrap_or(0);
+                let interval_us = match header.sample_interval_of_trace {
+                    0 => bin_header.sample_interval_us(),
+                    interval => interval as f64,
+                };
+                header.delay_recording_time =
+                    shifted_delay(&header, window.start as f64 * interval_us);
+
+                writer.write_all(&th_as_bytes_with_settings(
+                    &header,
+                    segy.get_settings(),
+                    bin_header,
+                )?)?;
+                let mut extension = vec![0; trace.extension_range().len()];
+                reader.seek(SeekFrom::Start(trace.extension_range().start as u64))?;
+                reader.read_exact(&mut extension)?;
+                if header.no_samples_in_trace == 0 && !window.is_empty() {
+                    write_extended_sample_count(&mut extension, bin_header, window.len())?;
+                }
+                writer.write_all(&extension)?;
+                writer.write_all(&data)?;
+                progress.trace_written(trace.extension_range().end - header_start + data.len())?;
+            }
+        }
+    }
+    progress.report()?;
+    writer.flush()?;
+    drop(writer);
+
+    let count = segy.trace_count();
+    patch_trace_count(&mut file, &segy, count)?;
+    if let Some(w) = sample_window {
+        let no_samples = bin_header.sample_count().clamp(w.start, w.end) - w.start;
+        patch_bin_header_u16(&mut file, &segy, 20, u16::try_from(no_samples).unwrap_or(0))?;
+        if bin_header.extended_no_samples > 0 || no_samples > u16::MAX as usize {
+            patch_bin_header_u32(&mut file, &segy, 68, no_samples as u32)?;
+        }
+    }
+    Ok(count)
+}
+
+/// Get the delay recording time of a trace header after moving it by `shift_us` microseconds.
+/// The delay is given in milliseconds, multiplied by the time scalar of the header (bytes
+/// 215-216, where a negative scalar is a divisor and zero counts as one), so the delay is
+/// rounded to the nearest value that can be written with the same scalar.
+fn shifted_delay(header: &TraceHeader, shift_us: f64) -> u16 {
+    let scale = match header.time_scalar_trace_header as i16 {
+        s if s > 0 => s as f64,
+        s if s < 0 => 1. / -(s as f64),
+        _ => 1.,
+    };
+    let delay_ms = header.delay_recording_time as f64 * scale + shift_us / 1000.;
+    (delay_ms / scale).round().clamp(0., u16::MAX as f64) as u16
+}
@@ -0,0 +1,242 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule allows text headers to be brought into the card image form of the SEG-Y
+//! standard: forty lines of eighty ASCII characters each.
+use giga_segy_core::errors::*;
+use giga_segy_core::TEXT_HEADER_LEN;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The length of a line (card image) in the text header.
+const LINE_LEN: usize = 80;
+/// The number of lines in the text header.
+const LINE_COUNT: usize = TEXT_HEADER_LEN / LINE_LEN;
+
+/// The options for [`normalize_text_header_with`] and [`normalize_text_header_lines`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextHeaderOptions {
+    /// If this is set, each line is prefixed with `C`, the line number (right aligned in two
+    /// columns) and a space, eg. `"C 1 "`. Lines then have 76 columns for their content. A
+    /// line which already starts with such a prefix (also in the form `"C01 "` or `"C1 "`) is
+    /// renumbered rather than numbered twice.
+    pub number_lines: bool,
+    /// If this is set, accented latin letters are replaced by their unaccented form and any
+    /// other non-ASCII characters by `?`. Otherwise non-ASCII characters cause an error.
+    pub transliterate: bool,
+}
+
+/// Normalize a text header with the default [`TextHeaderOptions`]. See
+/// [`normalize_text_header_with`].
+/// ```
+/// use giga_segy_out::text_header::normalize_text_header;
+///
+/// let header = normalize_text_header("C 1 First line\nC 2 Second line").unwrap();
+/// assert_eq!(header.len(), 3200);
+/// assert_eq!(header[..80].trim_end(), "C 1 First line");
+/// assert_eq!(header[80..160].trim_end(), "C 2 Second line");
+/// ```
+pub fn normalize_text_header(input: &str) -> Result<String, RsgError> {
+    normalize_text_header_with(input, TextHeaderOptions::default())
+}
+
+/// Bring a text header into card image form, returning a string of exactly 3200 ASCII
+/// characters.
+///
+/// If the input contains line breaks, each line of the input becomes one line of the text
+/// header. Otherwise the input is taken to be a raw text header and is split into lines of
+/// eighty characters. Tabs are replaced by spaces, and other control characters are removed.
+/// Each line is padded to eighty columns and the header is padded to forty lines.
+///
+/// An error is returned if a line is too long, if there are more than forty lines, or if the
+/// input contains non-ASCII characters (unless these are transliterated).
+pub fn normalize_text_header_with(
+    input: &str,
+    opts: TextHeaderOptions,
+) -> Result<String, RsgError> {
+    if input.contains('\n') {
+        let lines = input.lines().collect::<Vec<_>>();
+        normalize_text_header_lines(&lines, opts)
+    } else {
+        let cleaned = clean_line(input, opts)?;
+        // A raw header which is already numbered consists of whole cards.
+        let width = if opts.number_lines && strip_card_prefix(&cleaned).len() == cleaned.len() {
+            LINE_LEN - 4
+        } else {
+            LINE_LEN
+        };
+        let chars = cleaned.chars().collect::<Vec<_>>();
+        let lines = chars
+            .chunks(width)
+            .map(|c| c.iter().collect::<String>())
+            .collect::<Vec<_>>();
+        normalize_text_header_lines(&lines, opts)
+    }
+}
+
+/// Bring a text header, given as a list of lines, into card image form. See
+/// [`normalize_text_header_with`].
+pub fn normalize_text_header_lines<S: AsRef<str>>(
+    lines: &[S],
+    opts: TextHeaderOptions,
+) -> Result<String, RsgError> {
+    if lines.len() > LINE_COUNT {
+        let msg = format!(
+            "Invalid TextHeader: Too many lines: {} (at most {} are allowed)",
+            lines.len(),
+            LINE_COUNT
+        );
+        return Err(RsgError::InvalidHeader { msg });
+    }
+
+    let mut output = String::with_capacity(TEXT_HEADER_LEN);
+    for i in 0..LINE_COUNT {
+        let mut line = if opts.number_lines {
+            format!("C{:2} ", i + 1)
+        } else {
+            String::new()
+        };
+        if let Some(content) = lines.get(i) {
+            let content = clean_line(content.as_ref(), opts)?;
+            if opts.number_lines {
+                line.push_str(strip_card_prefix(&content));
+            } else {
+                line.push_str(&content);
+            }
+        }
+
+        if line.len() > LINE_LEN {
+            let msg = format!(
+                "Invalid TextHeader: Line {} is too long: {} (at most {} characters are allowed)",
+                i + 1,
+                line.len(),
+                LINE_LEN
+            );
+            return Err(RsgError::InvalidHeader { msg });
+        }
+        output.push_str(&format!("{:<width$}", line, width = LINE_LEN));
+    }
+    Ok(output)
+}
+
+/// Remove control characters from a line and deal with non-ASCII characters.
+fn clean_line(line: &str, opts: TextHeaderOptions) -> Result<String, RsgError> {
+    let mut output = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '\t' => output.push(' '),
+            c if c.is_control() => {}
+            c if c.is_ascii() => output.push(c),
+            c if opts.transliterate => output.push(transliterate(c)),
+            c => {
+                let msg = format!("Invalid TextHeader: Non-ASCII character: {:?}", c);
+                return Err(RsgError::InvalidHeader { msg });
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Strip the card prefix (`C`, a number of one or two digits, which may be padded with a space,
+/// and a space) from the start of a line. Other lines are returned unchanged.
+fn strip_card_prefix(line: &str) -> &str {
+    let rest = match line.strip_prefix('C') {
+        Some(rest) => rest.strip_prefix(' ').unwrap_or(rest),
+        None => return line,
+    };
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 || digits > 2 {
+        return line;
+    }
+    match &rest[digits..] {
+        "" => "",
+        rest => rest.strip_prefix(' ').unwrap_or(line),
+    }
+}
+
+/// Replace a non-ASCII character with a single ASCII character.
+fn transliterate(c: char) -> char {
+    match c {
+        'À'..='Å' => 'A',
+        'Ç' => 'C',
+        'È'..='Ë' => 'E',
+        'Ì'..='Ï' => 'I',
+        'Ñ' => 'N',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'Ù'..='Ü' => 'U',
+        'Ý' => 'Y',
+        'à'..='å' => 'a',
+        'ç' => 'c',
+        'è'..='ë' => 'e',
+        'ì'..='ï' => 'i',
+        'ñ' => 'n',
+        'ò'..='ö' | 'ø' => 'o',
+        'ù'..='ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ß' => 's',
+        '\u{a0}' => ' ',
+        _ => '?',
+    }
+}
+
+/// The text of the card which records how a file was written (see
+/// [`crate::SegyFile::provenance_card`]), after the card number.
+const PROVENANCE_PREFIX: &str = "written by giga-segy-out";
+
+/// Find the card (counting from one) in which to record how a file was written: The card which
+/// already records it, or otherwise the last blank card, searching from C40 backwards. Cards
+/// beyond the end of `text_header` are blank.
+pub(crate) fn find_provenance_card(text_header: &[u8]) -> Option<usize> {
+    let card = |n: usize| {
+        let start = ((n - 1) * LINE_LEN).min(text_header.len());
+        let end = (n * LINE_LEN).min(text_header.len());
+        &text_header[start..end]
+    };
+    let is_provenance = |c: &[u8]| c.len() > 4 && c[4..].starts_with(PROVENANCE_PREFIX.as_bytes());
+    let is_blank = |c: &[u8]| c.iter().all(|b| *b == b' ');
+    let cards = || (1..=LINE_COUNT).rev();
+    cards()
+        .find(|n| is_provenance(card(*n)))
+        .or_else(|| cards().find(|n| is_blank(card(*n))))
+}
+
+/// Make the card `n` (counting from one) which records how a file with `traces` traces of the
+/// sample format `format` was written, at the time `now`.
+pub(crate) fn provenance_card(n: usize, format: u16, traces: usize, now: SystemTime) -> String {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let card = format!(
+        "C{:2} {} v{} on {}, format {}, {} traces",
+        n,
+        PROVENANCE_PREFIX,
+        env!("CARGO_PKG_VERSION"),
+        utc_date(secs),
+        format,
+        traces
+    );
+    format!("{:<width$.width$}", card, width = LINE_LEN)
+}
+
+/// Replace the card `n` (counting from one) of a text header, which must be blank or consist
+/// of ASCII characters, by `card`. The text header is padded with spaces if it ends before the
+/// card.
+pub(crate) fn replace_card(text_header: &mut String, n: usize, card: &str) {
+    let range = ((n - 1) * LINE_LEN)..(n * LINE_LEN);
+    if text_header.len() < range.end {
+        let padding = range.end - text_header.len();
+        text_header.push_str(&" ".repeat(padding));
+    }
+    text_header.replace_range(range, card);
+}
+
+/// Get the date (as `YYYY-MM-DD`, in UTC) of a time given in seconds since the Unix epoch.
+pub(crate) fn utc_date(secs: u64) -> String {
+    // This is the `civil_from_days` algorithm of Howard Hinnant.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,253 @@
+// Copyright (C) 2022 by GiGa infosystems
+//! This submodule contains the [`VoxetWriter`], which writes a regular 3D grid (a voxet) column
+//! by column, and fills in the line numbers and coordinates of the trace headers from the
+//! geometry of the grid.
+use giga_segy_core::enums::{SampleFormatCode, TraceIdCode};
+use giga_segy_core::errors::*;
+use giga_segy_core::{BinHeader, SegySettings, TraceHeader};
+
+use std::convert::TryFrom;
+use std::path::Path;
+
+use crate::create_headers::{CreateBinHeader, CreateTraceHeader};
+use crate::SegyFile;
+
+/// The coordinate scalars which are tried for the ensemble coordinates, from the most to the
+/// least precise.
+const SCALARS: [i16; 9] = [-10000, -1000, -100, -10, 1, 10, 100, 1000, 10000];
+
+/// Writes a regular grid of `dims[0]` inlines by `dims[1]` crosslines, with `dims[2]` samples
+/// in each column, to a SEG-Y file.
+///
+/// The column `(i, j)` gets the inline number `i` and the crossline number `j`, and its ensemble
+/// coordinates are the X and Y components of `origin + i * u + j * v`. These are written with
+/// the most precise coordinate scalar (from `-10000` to `10000`) for which all of the grid fits.
+/// The columns may be written in any order, and each one is written to the end of the file,
+/// so the inline and crossline numbers (or the lookup of `giga_segy_in`) must be used to find
+/// them again.
+///
+/// The geometry is also kept in the settings of the [`SegyFile`] (see
+/// [`SegySettings::set_origin`], [`SegySettings::set_override_u`] and
+/// [`SegySettings::set_override_v`]).
+/// ```
+/// use giga_segy_out::voxet::VoxetWriter;
+/// use giga_segy_core::enums::SampleFormatCode;
+///
+/// let dir = tempfile::tempdir().expect("Couldn't get tempfile.");
+/// let mut writer = VoxetWriter::new(
+///     dir.path().join("voxet.sgy"),
+///     [2, 3, 10],
+///     [500_000., 6_000_000., 0.],
+///     [12.5, 0., 0.],
+///     [0., 25., 0.],
+///     4000.,
+///     SampleFormatCode::Float32,
+/// )
+/// .unwrap();
+/// writer.write_column(1, 2, &[1.; 10]).unwrap();
+/// // The other cells are filled with dead traces.
+/// assert_eq!(writer.finish(true).unwrap(), 5);
+/// ```
+pub struct VoxetWriter {
+    segy: SegyFile<SegySettings>,
+    dims: [usize; 3],
+    origin: [f64; 3],
+    u: [f64; 3],
+    v: [f64; 3],
+    coordinate_scalar: i16,
+    written: Vec<bool>,
+}
+
+impl VoxetWriter {
+    /// Create the file for a grid with the given dimensions (inlines, crosslines and samples)
+    /// and geometry. The `sample_interval` is given in microseconds (or millimetres, or
+    /// thousandths of a foot, as in the binary header), and the samples are written in the
+    /// given format.
+    ///
+    /// An error is returned if one of the dimensions is zero, if there are more than 65535
+    /// samples, or if the coordinates of the grid are too large for any coordinate scalar.
+    ///
+    /// NB: As with any [`SegyFile`], the coordinates are written in the sample format (see
+    /// [`SegySettings::set_override_coordinate_format`]), so they lose precision with a 32 bit
+    /// floating point format if they are large.
+    ///
+    /// NB: If there are more than 65535 columns, the extended trace count of SEG-Y rev 2 is used
+    /// (see [`SegySettings::set_extended_trace_count`]).
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        dims: [usize; 3],
+        origin: [f64; 3],
+        u: [f64; 3],
+        v: [f64; 3],
+        sample_interval: f64,
+        format: SampleFormatCode,
+    ) -> Result<Self, RsgError> {
+        let error = |msg: String| Err(RsgError::SEGYSettingsError { msg });
+        if dims.contains(&0) {
+            return error(format!(
+                "The dimensions of a voxet ({:?}) cannot be zero.",
+                dims
+            ));
+        }
+        let no_samples = match u16::try_from(dims[2]) {
+            Ok(no_samples) => no_samples,
+            Err(_) => return error(format!("A voxet cannot have {} samples.", dims[2])),
+        };
+        let cells = match dims[0].checked_mul(dims[1]) {
+            Some(cells) => cells,
+            None => return error(format!("A voxet cannot have {:?} columns.", &dims[..2])),
+        };
+
+        // This is checked before the file is created, so that no file is left behind.
+        let coordinate_scalar = match grid_scalar(dims, [origin, u, v]) {
+            Some(scalar) => scalar,
+            None => return error("The coordinates of the voxet are too large.".to_string()),
+        };
+
+        let mut bin_header = BinHeader::new(0, 0, no_samples, format);
+        bin_header.try_set_sample_interval_us(sample_interval)?;
+        let mut writer = VoxetWriter {
+            segy: SegyFile::create_file(
+                path,
+                SegySettings::default(),
+                format!(
+                    "C 1 A voxet of {} inlines, {} crosslines and {} samples.",
+                    dims[0], dims[1], dims[2]
+                ),
+                bin_header,
+                None,
+            )?,
+            dims,
+            origin,
+            u,
+            v,
+            coordinate_scalar,
+            written: vec![false; cells],
+        };
+
+        let settings = &mut writer.segy.metadata.settings;
+        settings.set_origin(origin);
+        settings.set_override_u(u);
+        settings.set_override_v(v);
+        settings.set_override_sample_interval(sample_interval);
+        settings.set_extended_trace_count(cells > u16::MAX as usize);
+        Ok(writer)
+    }
+
+    /// Write the samples of the column `(i, j)`. An error is returned if the cell is outside of
+    /// the grid or already has a trace, and if the number of samples is not `dims[2]`.
+    pub fn write_column(&mut self, i: usize, j: usize, data: &[f32]) -> Result<(), RsgError> {
+        let cell = self.cell_index(i, j)?;
+        let header = self.header(i, j, TraceIdCode::Unknown)?;
+        self.segy.add_trace(header, None, data.to_vec())?;
+        self.written[cell] = true;
+        Ok(())
+    }
+
+    /// Get the number of columns which have been written so far.
+    pub fn written_count(&self) -> usize {
+        self.written.iter().filter(|w| **w).count()
+    }
+
+    /// Finish the file, and write the number of traces to the binary header (see
+    /// [`SegyFile::finalize`]).
+    ///
+    /// If some columns have not been written, they are filled with zeros and marked as dead
+    /// traces ([`TraceIdCode::Dead`]) if `fill_missing` is set. Otherwise an
+    /// [`RsgError::MissingCells`] is returned and nothing is written, so that the missing
+    /// columns can still be written before this is called again. The number of columns which
+    /// were filled is returned.
+    pub fn finish(&mut self, fill_missing: bool) -> Result<usize, RsgError> {
+        let missing = self
+            .written
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| !**w)
+            .map(|(cell, _)| [cell / self.dims[1], cell % self.dims[1]])
+            .collect::<Vec<_>>();
+        if let (Some(first), false) = (missing.first(), fill_missing) {
+            return Err(RsgError::MissingCells {
+                count: missing.len(),
+                first: *first,
+            });
+        }
+        let zeros = vec![0f32; self.dims[2]];
+        for [i, j] in missing.iter() {
+            let header = self.header(*i, *j, TraceIdCode::Dead)?;
+            self.segy.add_trace(header, None, zeros.clone())?;
+            self.written[i * self.dims[1] + j] = true;
+        }
+        self.segy.finalize()?;
+        Ok(missing.len())
+    }
+
+    /// Get the index of a cell which can still be written.
+    fn cell_index(&self, i: usize, j: usize) -> Result<usize, RsgError> {
+        let invalid = |msg: String| RsgError::InvalidCell { cell: [i, j], msg };
+        if i >= self.dims[0] || j >= self.dims[1] {
+            return Err(invalid(format!(
+                "The grid has {} inlines and {} crosslines.",
+                self.dims[0], self.dims[1]
+            )));
+        }
+        let cell = i * self.dims[1] + j;
+        if self.written[cell] {
+            return Err(invalid("The cell already has a trace.".to_string()));
+        }
+        Ok(cell)
+    }
+
+    /// Get the X and Y coordinates of the column `(i, j)`.
+    fn position(&self, i: usize, j: usize) -> [f64; 2] {
+        grid_position([self.origin, self.u, self.v], i, j)
+    }
+
+    /// Create the trace header of the column `(i, j)`.
+    fn header(&self, i: usize, j: usize, id: TraceIdCode) -> Result<TraceHeader, RsgError> {
+        let [x, y] = self.position(i, j);
+        let mut header = TraceHeader::new_3d(0, 0, i as i32, j as i32, self.coordinate_scalar);
+        header.x_ensemble =
+            header
+                .remove_coordinate_scalar(x)
+                .ok_or(RsgError::HeaderFieldEncode {
+                    field: "x_ensemble",
+                })?;
+        header.y_ensemble =
+            header
+                .remove_coordinate_scalar(y)
+                .ok_or(RsgError::HeaderFieldEncode {
+                    field: "y_ensemble",
+                })?;
+        let cell = (i * self.dims[1] + j) as i32;
+        header.trace_sequence_in_file = self.segy.traces.len() as i32 + 1;
+        header.trace_sequence_on_line = j as i32 + 1;
+        header.ensemble_no = cell + 1;
+        header.trace_no = cell + 1;
+        header.trace_identification_code = id;
+        header.no_samples_in_trace = u16::try_from(self.dims[2]).unwrap_or(0);
+        header.sample_interval_of_trace = self.segy.metadata.bin_header.sample_interval;
+        Ok(header)
+    }
+}
+
+/// Get the X and Y coordinates of the column `(i, j)` of a grid with the given origin and
+/// axes (`[origin, u, v]`).
+fn grid_position(geometry: [[f64; 3]; 3], i: usize, j: usize) -> [f64; 2] {
+    let [origin, u, v] = geometry;
+    let c = |k: usize| origin[k] + i as f64 * u[k] + j as f64 * v[k];
+    [c(0), c(1)]
+}
+
+/// Find the most precise of the [`SCALARS`] with which the coordinates of all columns of a grid
+/// fit in the trace header. It is enough to check the corners of the grid.
+fn grid_scalar(dims: [usize; 3], geometry: [[f64; 3]; 3]) -> Option<i16> {
+    let [last_i, last_j] = [dims[0] - 1, dims[1] - 1];
+    let corners = [[0, 0], [last_i, 0], [0, last_j], [last_i, last_j]];
+    SCALARS.iter().copied().find(|scalar| {
+        let header = TraceHeader::new_2d(0, 0, *scalar);
+        corners
+            .iter()
+            .flat_map(|[i, j]| grid_position(geometry, *i, *j))
+            .all(|c| header.remove_coordinate_scalar(c).is_some())
+    })
+}
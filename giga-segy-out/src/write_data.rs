@@ -61,20 +61,75 @@ pub(crate) type BitConverter<T> = fn(T) -> Result<TVu8, RsgError>;
 macro_rules! make_converter {
     ($to_number:expr, $to_bytes:expr) => {{
         fn x<T: ToPrimitive + Debug>(x: T) -> Result<TVu8, RsgError> {
-            let x = $to_number(&x).ok_or_else(|| RsgError::BitConversionError {
-                msg: format!("Cannot convert {:?} to bytes", x),
-            })?;
+            let x = $to_number(&x).ok_or_else(|| conversion_error(&x))?;
             Ok(TinyVec::from(&$to_bytes(x)[..]))
         }
         x
     }};
 }
 
+/// This converts a whole slice of data at once, writing the bytes of each sample straight into
+/// the output (see [`convert_data_bulk`]).
+macro_rules! bulk_convert {
+    ($data:expr, $to_number:path, $ty:ty, $to_bytes:ident) => {{
+        let mut output = Vec::with_capacity($data.len() * mem::size_of::<$ty>());
+        for x in $data.iter() {
+            match $to_number(x) {
+                Some(v) => output.extend_from_slice(&<$ty>::$to_bytes(v)),
+                None => return Some(Err(conversion_error(x))),
+            }
+        }
+        Ok(output)
+    }};
+}
+
+fn conversion_error<T: Debug>(x: &T) -> RsgError {
+    RsgError::BitConversionError {
+        msg: format!("Cannot convert {:?} to bytes", x),
+    }
+}
+
 pub(crate) fn get_format_and_le(bh: &BinHeader) -> (SampleFormatCode, bool) {
     (bh.sample_format_code, bh.binary_flag_direction_is_le)
 }
 
-fn convert_data_inner<T: ToPrimitive + Debug>(
+pub(crate) fn convert_data_inner<T: ToPrimitive + Debug>(
+    data: Vec<T>,
+    coord_format: SampleFormatCode,
+    le: bool,
+) -> Result<Vec<u8>, RsgError> {
+    match convert_data_bulk(&data, coord_format, le) {
+        Some(output) => output,
+        None => convert_data_per_sample(data, coord_format, le),
+    }
+}
+
+/// Convert the data for the most common sample formats in one pass, without going through a
+/// [`BitConverter`] (and a [`TVu8`]) for each sample. This gives `None` for the other formats,
+/// which are converted with [`convert_data_per_sample`]. The bytes are the same either way.
+fn convert_data_bulk<T: ToPrimitive + Debug>(
+    data: &[T],
+    format: SampleFormatCode,
+    le: bool,
+) -> Option<Result<Vec<u8>, RsgError>> {
+    let output = match format {
+        Int8 => bulk_convert!(data, ToPrimitive::to_i8, i8, to_be_bytes),
+        UInt8 => bulk_convert!(data, ToPrimitive::to_u8, u8, to_be_bytes),
+        Int16 if le => bulk_convert!(data, ToPrimitive::to_i16, i16, to_le_bytes),
+        Int16 => bulk_convert!(data, ToPrimitive::to_i16, i16, to_be_bytes),
+        UInt16 if le => bulk_convert!(data, ToPrimitive::to_u16, u16, to_le_bytes),
+        UInt16 => bulk_convert!(data, ToPrimitive::to_u16, u16, to_be_bytes),
+        Int32 if le => bulk_convert!(data, ToPrimitive::to_i32, i32, to_le_bytes),
+        Int32 => bulk_convert!(data, ToPrimitive::to_i32, i32, to_be_bytes),
+        Float32 if le => bulk_convert!(data, ToPrimitive::to_f32, f32, to_le_bytes),
+        Float32 => bulk_convert!(data, ToPrimitive::to_f32, f32, to_be_bytes),
+        _ => return None,
+    };
+    Some(output)
+}
+
+/// Convert the data one sample at a time with the converter from [`converter_chooser`].
+fn convert_data_per_sample<T: ToPrimitive + Debug>(
     data: Vec<T>,
     coord_format: SampleFormatCode,
     le: bool,
@@ -170,6 +225,95 @@ pub(crate) fn converter_chooser<T: ToPrimitive + Debug>(
     Ok(f)
 }
 
+/// Encode f32 values in a sample format. Unlike [`convert_data`], this also writes IBM floats,
+/// 24-bit integers and 32-bit fixed point numbers (with 16 fractional bits, as they are read
+/// by `giga_segy_core`). These integers are rounded to the nearest value, and saturate at the
+/// limits of the format. Only the editing functions (with the `edit` feature) need this.
+#[cfg(feature = "edit")]
+pub(crate) fn encode_samples(
+    samples: &[f32],
+    format: SampleFormatCode,
+    le: bool,
+) -> Result<Vec<u8>, RsgError> {
+    let mut output = Vec::with_capacity(samples.len() * format.datum_byte_length());
+    match format {
+        IbmFloat32 => {
+            for bits in samples.iter().map(|v| f32_to_ibm(*v)) {
+                let bytes = if le {
+                    bits.to_le_bytes()
+                } else {
+                    bits.to_be_bytes()
+                };
+                output.extend_from_slice(&bytes);
+            }
+        }
+        Int24 | UInt24 => {
+            let [min, max] = if format == Int24 {
+                [-(1 << 23) as f64, ((1 << 23) - 1) as f64]
+            } else {
+                [0., ((1 << 24) - 1) as f64]
+            };
+            for v in samples {
+                let bytes = ((*v as f64).round().clamp(min, max) as i32).to_be_bytes();
+                if le {
+                    output.extend_from_slice(&[bytes[3], bytes[2], bytes[1]]);
+                } else {
+                    output.extend_from_slice(&bytes[1..]);
+                }
+            }
+        }
+        FixPoint32 => {
+            for v in samples {
+                let value = (*v as f64 * 65536.)
+                    .round()
+                    .clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+                let bytes = if le {
+                    value.to_le_bytes()
+                } else {
+                    value.to_be_bytes()
+                };
+                output.extend_from_slice(&bytes);
+            }
+        }
+        _ => return convert_data_inner(samples.to_vec(), format, le),
+    }
+    Ok(output)
+}
+
+/// Get the bits of the IBM float which is nearest to `value`. NaN is written as zero, and the
+/// infinities as the largest IBM floats.
+#[cfg(feature = "edit")]
+fn f32_to_ibm(value: f32) -> u32 {
+    let sign = if value.is_sign_negative() {
+        0x8000_0000
+    } else {
+        0
+    };
+    if value == 0. || value.is_nan() {
+        return 0;
+    } else if value.is_infinite() {
+        return sign | 0x7FFF_FFFF;
+    }
+    // The value is `fraction * 16^(exponent - 64)`, with `1/16 <= fraction < 1`. Scaling by
+    // powers of 16 is exact, and every f32 is well within the range of IBM floats.
+    let mut fraction = (value as f64).abs();
+    let mut exponent = 64;
+    while fraction >= 1. {
+        fraction /= 16.;
+        exponent += 1;
+    }
+    while fraction < 1. / 16. {
+        fraction *= 16.;
+        exponent -= 1;
+    }
+    let mut mantissa = (fraction * (1 << 24) as f64).round() as u32;
+    if mantissa == 1 << 24 {
+        mantissa >>= 4;
+        exponent += 1;
+    }
+    sign | (exponent << 24) | mantissa
+}
+
 impl LosslessWriteableSegyData for f32 {
     /// Returns true for `Float64` and `Float32`.
     fn is_lossless_to(f: SampleFormatCode) -> bool {
@@ -220,16 +364,19 @@ impl LosslessWriteableSegyData for u64 {
 }
 
 impl LosslessWriteableSegyData for u32 {
-    /// Returns true for `UInt64`, `UInt32` and `Int64`.
+    /// Returns true for `UInt64`, `UInt32`, `Int64` and `Float64`.
     fn is_lossless_to(f: SampleFormatCode) -> bool {
-        matches!(f, UInt32 | UInt64 | Int64)
+        matches!(f, UInt32 | UInt64 | Int64 | Float64)
     }
 }
 
 impl LosslessWriteableSegyData for u16 {
-    /// Returns true for `UInt64`, `UInt32`, `UInt16`, `Int64` and `Float64`.
+    /// Returns true for `UInt64`, `UInt32`, `UInt16`, `Int64`, `Int32`, `Float64` and `Float32`.
     fn is_lossless_to(f: SampleFormatCode) -> bool {
-        matches!(f, UInt16 | UInt32 | UInt64 | Int32 | Int64 | Float64)
+        matches!(
+            f,
+            UInt16 | UInt32 | UInt64 | Int32 | Int64 | Float32 | Float64
+        )
     }
 }
 
@@ -243,6 +390,140 @@ impl LosslessWriteableSegyData for u8 {
     }
 }
 
+/// How [`crate::SegyFile::add_trace_scaled`] scales the samples of a trace before they are
+/// rounded to an integer sample format.
+///
+/// The samples are multiplied by a factor, and this factor is recorded in the file so that
+/// the samples can be restored: If it is a power of two `2^N`, `N` is written to the
+/// `trace_weighting_factor` of the trace header, as the standard intends (readers divide the
+/// samples by `2^N` again, see [`giga_segy_core::SegySettings::set_apply_trace_weighting`]).
+/// Negative values of `N`, which are needed for samples beyond the range of the format, are
+/// written as two's complement. Other factors are noted in the text header instead (see
+/// [`crate::SegyFile::add_trace_scaled`]).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleMode {
+    /// Multiply the samples by a given factor, which must be positive. Samples which do not fit
+    /// in the sample format after this are an error.
+    Fixed(f64),
+    /// Multiply the samples of each trace by the largest power of two for which they all fit
+    /// in the sample format.
+    AutoPerTrace,
+    /// Multiply the samples of all traces by the same power of two: The largest one for which
+    /// the given value (which should be the largest absolute sample value of the whole file,
+    /// computed beforehand) fits in the sample format.
+    AutoPerFile(f64),
+}
+
+/// The factor which was applied by [`scale_samples`], in the way it is recorded in the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AppliedScale {
+    /// The trace weighting factor `N` of a factor `2^N`.
+    Weighting(i16),
+    /// A factor which is not a power of two.
+    Other(f64),
+}
+
+/// Get the largest absolute value that can be written in an integer sample format. This is
+/// `None` for the float formats.
+fn integer_limit(format: SampleFormatCode) -> Option<f64> {
+    let limit = match format {
+        Int8 => i8::MAX as f64,
+        UInt8 => u8::MAX as f64,
+        Int16 => i16::MAX as f64,
+        UInt16 => u16::MAX as f64,
+        Int24 => ((1 << 23) - 1) as f64,
+        UInt24 => ((1 << 24) - 1) as f64,
+        Int32 | FixPoint32 => i32::MAX as f64,
+        UInt32 => u32::MAX as f64,
+        // `i64::MAX` and `u64::MAX` are rounded up as floats, so stay a power of two below.
+        Int64 => 2f64.powi(62),
+        UInt64 => 2f64.powi(63),
+        IbmFloat32 | Float32 | Float64 => return None,
+    };
+    Some(limit)
+}
+
+/// Get the exponent of the largest power of two by which `peak` can be multiplied without
+/// going beyond `limit`.
+fn weighting_for_peak(peak: f64, limit: f64) -> i16 {
+    if peak == 0.0 || !peak.is_finite() {
+        return 0;
+    }
+    let clamp = |n: f64| n.max(i16::MIN as f64).min(i16::MAX as f64) as i16;
+    // The logarithm may be off by one either way, which is corrected here.
+    let mut n = clamp((limit / peak).log2().floor());
+    while n > i16::MIN && peak * 2f64.powi(n as i32) > limit {
+        n -= 1;
+    }
+    while n < i16::MAX && peak * 2f64.powi(n as i32 + 1) <= limit {
+        n += 1;
+    }
+    n
+}
+
+/// Round to the nearest integer, and to the even one if there are two.
+pub(crate) fn round_half_even(x: f64) -> f64 {
+    let rounded = x.round();
+    if (rounded - x).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        rounded - x.signum()
+    } else {
+        rounded
+    }
+}
+
+/// Scale and round the samples of a trace for an integer sample format (see [`ScaleMode`]).
+pub(crate) fn scale_samples<T: ToPrimitive>(
+    data: &[T],
+    format: SampleFormatCode,
+    mode: ScaleMode,
+) -> Result<(Vec<f64>, AppliedScale), RsgError> {
+    let invalid = |msg: String| Err(RsgError::SEGYSettingsError { msg });
+    let limit = match integer_limit(format) {
+        Some(limit) => limit,
+        None => return invalid(format!("Samples cannot be scaled for {:?}.", format)),
+    };
+    let data = data
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            v.to_f64().ok_or_else(|| RsgError::BitConversionError {
+                msg: format!("Sample {} cannot be converted to a float.", i),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let applied = match mode {
+        ScaleMode::Fixed(factor) if !(factor.is_finite() && factor > 0.0) => {
+            return invalid(format!("Cannot scale samples by {}.", factor));
+        }
+        ScaleMode::Fixed(factor) => {
+            let n = factor.log2().round();
+            if n.abs() <= i16::MAX as f64 && 2f64.powi(n as i32) == factor {
+                AppliedScale::Weighting(n as i16)
+            } else {
+                AppliedScale::Other(factor)
+            }
+        }
+        ScaleMode::AutoPerTrace => {
+            let peak = data.iter().fold(0f64, |peak, v| peak.max(v.abs()));
+            AppliedScale::Weighting(weighting_for_peak(peak, limit))
+        }
+        ScaleMode::AutoPerFile(peak) if !(peak.is_finite() && peak >= 0.0) => {
+            return invalid(format!("{} is not a valid peak sample value.", peak));
+        }
+        ScaleMode::AutoPerFile(peak) => AppliedScale::Weighting(weighting_for_peak(peak, limit)),
+    };
+    let factor = match applied {
+        AppliedScale::Weighting(n) => 2f64.powi(n as i32),
+        AppliedScale::Other(factor) => factor,
+    };
+    let scaled = data
+        .into_iter()
+        .map(|v| round_half_even(v * factor))
+        .collect();
+    Ok((scaled, applied))
+}
+
 #[cfg(test)]
 /// NB: for now, due to the lare number of combos we mostly don't test conversions
 /// outside of 32 and 64 bit types.
@@ -256,6 +537,7 @@ mod tests {
     use crate::create_headers::CreateBinHeader;
 
     use num::{FromPrimitive, ToPrimitive};
+    use std::convert::TryInto;
     use std::fmt::Debug;
 
     fn make_bit_converter<T: ToPrimitive + Debug>() {
@@ -457,7 +739,7 @@ mod tests {
     #[test]
     fn convert_u32_to_f64_ok() {
         let con = u32::to_f64;
-        let is_err = Result::is_err;
+        let is_err = Result::is_ok;
         make_convert_test!(u32, f64, false, Float64, con, is_err);
         make_convert_test!(u32, f64, true, Float64, con, is_err);
     }
@@ -477,4 +759,288 @@ mod tests {
         make_convert_test!(i64, f64, false, Float64, con, is_err);
         make_convert_test!(i64, f64, true, Float64, con, is_err);
     }
+
+    /// The formats which can be written.
+    const WRITEABLE: [SampleFormatCode; 10] = [
+        Int8, Int16, Int32, Int64, UInt8, UInt16, UInt32, UInt64, Float32, Float64,
+    ];
+
+    /// Integers at the edges of what the formats can hold exactly. Those which a type cannot
+    /// hold are left out when checking that type.
+    const EDGES: [i128; 27] = [
+        0,
+        1,
+        -1,
+        i8::MIN as i128,
+        i8::MAX as i128,
+        u8::MAX as i128,
+        i16::MIN as i128,
+        i16::MAX as i128,
+        u16::MAX as i128,
+        (1 << 24) - 1,
+        1 << 24,
+        (1 << 24) + 1,
+        -(1 << 24) + 1,
+        -(1 << 24),
+        -(1 << 24) - 1,
+        i32::MIN as i128,
+        i32::MAX as i128,
+        u32::MAX as i128,
+        (1 << 53) - 1,
+        1 << 53,
+        (1 << 53) + 1,
+        -(1 << 53) + 1,
+        -(1 << 53),
+        -(1 << 53) - 1,
+        i64::MIN as i128,
+        i64::MAX as i128,
+        u64::MAX as i128,
+    ];
+
+    /// Read a value which was written in a format back as a `T`.
+    fn read_back<T: FromPrimitive>(bytes: &[u8], f: SampleFormatCode, le: bool) -> Option<T> {
+        macro_rules! read {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().ok()?;
+                if le {
+                    <$t>::from_le_bytes(bytes)
+                } else {
+                    <$t>::from_be_bytes(bytes)
+                }
+            }};
+        }
+        match f {
+            Int8 => T::from_i8(read!(i8)),
+            Int16 => T::from_i16(read!(i16)),
+            Int32 => T::from_i32(read!(i32)),
+            Int64 => T::from_i64(read!(i64)),
+            UInt8 => T::from_u8(read!(u8)),
+            UInt16 => T::from_u16(read!(u16)),
+            UInt32 => T::from_u32(read!(u32)),
+            UInt64 => T::from_u64(read!(u64)),
+            Float32 => T::from_f32(read!(f32)),
+            Float64 => T::from_f64(read!(f64)),
+            _ => None,
+        }
+    }
+
+    /// Check that `T` is claimed to be lossless for exactly those formats for which every
+    /// value (the edges, the minimum and maximum of `T` and the `extra` values) is read back
+    /// unchanged after it was written.
+    fn check_lossless_matrix<T>(extra: &[T])
+    where
+        T: LosslessWriteableSegyData + FromPrimitive + num::Bounded + PartialEq + Copy,
+    {
+        let mut values = vec![T::min_value(), T::max_value()];
+        values.extend(EDGES.iter().filter_map(|v| T::from_i128(*v)));
+        values.extend_from_slice(extra);
+
+        for f in WRITEABLE.iter() {
+            for le in [false, true].iter() {
+                let converter = converter_chooser::<T>(*f, *le).expect("Writeable");
+                let exact = values.iter().all(|v| {
+                    let bytes = converter(*v).ok();
+                    bytes.and_then(|b| read_back::<T>(&b, *f, *le)) == Some(*v)
+                });
+                assert_eq!(
+                    exact,
+                    T::is_lossless_to(*f),
+                    "{} as {:?}",
+                    std::any::type_name::<T>(),
+                    f
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lossless_matrix_is_exact() {
+        check_lossless_matrix::<u8>(&[]);
+        check_lossless_matrix::<u16>(&[]);
+        check_lossless_matrix::<u32>(&[]);
+        check_lossless_matrix::<u64>(&[]);
+        check_lossless_matrix::<i8>(&[]);
+        check_lossless_matrix::<i16>(&[]);
+        check_lossless_matrix::<i32>(&[]);
+        check_lossless_matrix::<i64>(&[]);
+        check_lossless_matrix::<f32>(&[0.5, -0.1, f32::MIN_POSITIVE, f32::EPSILON]);
+        check_lossless_matrix::<f64>(&[0.5, -0.1, f64::MIN_POSITIVE, f64::EPSILON]);
+    }
+
+    /// Check that the bulk converters give the same bytes (or errors) as the per-sample
+    /// converters, for every sample format and byte order, for the whole data and for each value.
+    fn check_bulk_matches_per_sample<T: ToPrimitive + FromPrimitive + Debug + Clone>() {
+        let values = [
+            0.,
+            1.,
+            -1.,
+            1.5,
+            -2.75,
+            127.,
+            128.,
+            255.,
+            256.,
+            -128.,
+            -129.,
+            32767.,
+            32768.,
+            65535.,
+            65536.,
+            -32769.,
+            2147483648.,
+            1e10,
+            -1e10,
+            f64::NAN,
+            f64::INFINITY,
+        ];
+        let values = values
+            .iter()
+            .filter_map(|v| T::from_f64(*v))
+            .collect::<Vec<T>>();
+        let mut cases = values.iter().map(|v| vec![v.clone()]).collect::<Vec<_>>();
+        cases.push(values.clone());
+        cases.push(Vec::new());
+
+        for le in [true, false].iter() {
+            for f in [
+                Int8, UInt8, Int16, UInt16, Int32, Float32, Float64, Int64, UInt32, UInt64, Int24,
+                IbmFloat32,
+            ]
+            .iter()
+            {
+                for data in cases.iter() {
+                    let bulk = match convert_data_bulk(data, *f, *le) {
+                        Some(bulk) => bulk,
+                        None => {
+                            assert!(!matches!(
+                                f,
+                                Int8 | UInt8 | Int16 | UInt16 | Int32 | Float32
+                            ));
+                            continue;
+                        }
+                    };
+                    let per_sample = convert_data_per_sample(data.clone(), *f, *le);
+                    let name = std::any::type_name::<T>();
+                    match (bulk, per_sample) {
+                        (Ok(a), Ok(b)) => assert_eq!(a, b, "{} {:?} as {:?}", name, data, f),
+                        (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+                        (a, b) => panic!("{} {:?} as {:?}: {:?} vs {:?}", name, data, f, a, b),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bulk_converters_match_per_sample() {
+        check_bulk_matches_per_sample::<u8>();
+        check_bulk_matches_per_sample::<u16>();
+        check_bulk_matches_per_sample::<u32>();
+        check_bulk_matches_per_sample::<u64>();
+        check_bulk_matches_per_sample::<i8>();
+        check_bulk_matches_per_sample::<i16>();
+        check_bulk_matches_per_sample::<i32>();
+        check_bulk_matches_per_sample::<i64>();
+        check_bulk_matches_per_sample::<f32>();
+        check_bulk_matches_per_sample::<f64>();
+    }
+
+    #[test]
+    fn round_half_even_ties() {
+        let rounded = [-2.5, -1.5, -0.5, 0.5, 1.5, 2.5, 2.4, 2.6, -2.6]
+            .iter()
+            .map(|x| round_half_even(*x))
+            .collect::<Vec<_>>();
+        assert_eq!(rounded, vec![-2., -2., 0., 0., 2., 2., 2., 3., -3.]);
+    }
+
+    #[test]
+    fn weighting_for_peak_fits() {
+        assert_eq!(weighting_for_peak(32767., 32767.), 0);
+        assert_eq!(weighting_for_peak(32768., 32767.), -1);
+        assert_eq!(weighting_for_peak(100_000., 32767.), -2);
+        assert_eq!(weighting_for_peak(1., 32767.), 14);
+        assert_eq!(weighting_for_peak(0., 32767.), 0);
+    }
+
+    /// The codecs which only the editing functions use.
+    #[cfg(feature = "edit")]
+    mod codecs {
+        use super::*;
+        use giga_segy_core::bitconverter::decode_samples;
+
+        #[test]
+        fn ibm_round_trip() {
+            let converter =
+                giga_segy_core::bitconverter::converter_chooser(IbmFloat32, false).unwrap();
+            for value in [1., -1., 0.5, 118.625, -118.625, 1e-30, 3.4e38, 0.1, 1. / 3.] {
+                let bits = f32_to_ibm(value);
+                let back = converter(&bits.to_be_bytes()).unwrap();
+                assert!(
+                    (back - value).abs() <= value.abs() * 1e-6,
+                    "{} {}",
+                    value,
+                    back
+                );
+            }
+            // The example from the IBM documentation.
+            assert_eq!(f32_to_ibm(-118.625), 0xC276_A000);
+            assert_eq!(f32_to_ibm(0.), 0);
+            assert_eq!(f32_to_ibm(f32::NAN), 0);
+            assert_eq!(f32_to_ibm(f32::NEG_INFINITY), 0xFFFF_FFFF);
+
+            let bytes = encode_samples(&[-118.625], IbmFloat32, true).unwrap();
+            assert_eq!(bytes, 0xC276_A000u32.to_le_bytes());
+        }
+
+        #[test]
+        fn int24_round_trip() {
+            for le in [false, true] {
+                let samples = [0., 1., -1., 8_388_607., -8_388_608., 1e9, -1e9, 2.4, -2.6];
+                let bytes = encode_samples(&samples, Int24, le).unwrap();
+                assert_eq!(bytes.len(), samples.len() * 3);
+                let decoded = decode_samples(&bytes, Int24, le).unwrap();
+                let expected = [
+                    0.,
+                    1.,
+                    -1.,
+                    8_388_607.,
+                    -8_388_608.,
+                    8_388_607.,
+                    -8_388_608.,
+                    2.,
+                    -3.,
+                ];
+                assert_eq!(decoded, expected);
+
+                let bytes = encode_samples(&samples, UInt24, le).unwrap();
+                let decoded = decode_samples(&bytes, UInt24, le).unwrap();
+                let expected = [0., 1., 0., 8_388_607., 0., 16_777_215., 0., 2., 0.];
+                assert_eq!(decoded, expected);
+            }
+            let bytes = encode_samples(&[-2.], Int24, false).unwrap();
+            assert_eq!(bytes, vec![0xFF, 0xFF, 0xFE]);
+        }
+
+        #[test]
+        fn fix_point_round_trip() {
+            let samples = [0., 1.5, -2.25, 1e9];
+            let bytes = encode_samples(&samples, FixPoint32, true).unwrap();
+            let decoded = decode_samples(&bytes, FixPoint32, true).unwrap();
+            assert_eq!(decoded, [0., 1.5, -2.25, 32768.]);
+        }
+
+        #[test]
+        fn encode_other_formats_as_written() {
+            let samples = vec![1.5f32, -2., 100.];
+            for format in [Float32, Float64, Int16, Int32] {
+                for le in [false, true] {
+                    assert_eq!(
+                        encode_samples(&samples, format, le).unwrap(),
+                        convert_data_inner(samples.clone(), format, le).unwrap()
+                    );
+                }
+            }
+        }
+    }
 }
@@ -2,16 +2,41 @@
 //! This submodule exists for converting headers to bytes and writing them.
 use giga_segy_core::enums::*;
 use giga_segy_core::errors::*;
-use giga_segy_core::{BinHeader, TapeLabel, TraceHeader};
+use giga_segy_core::{BinHeader, TapeLabel, TraceHeader, BIN_HEADER_LEN, TAPE_LABEL_LEN};
 use num::ToPrimitive;
 // use rust_segy_input::{BIN_HEADER_LEN, TRACE_HEADER_LEN};
 
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::Write;
+use std::ops::Range;
 
 use crate::settings::SegyWriteSettings;
 use crate::write_data;
 
+/// Converts an enum in a header to the numeric code which is written to the file. If this is
+/// not possible (eg. because the value is the `Invalid` variant, which does not correspond to any
+/// valid code), an error which names the field is returned.
+macro_rules! enum_code {
+    ($header:expr, $field:ident, $conv:ident) => {
+        $header.$field.$conv().ok_or(RsgError::HeaderFieldEncode {
+            field: stringify!($field),
+        })?
+    };
+    ($header:expr, $field:ident, $conv:ident, $invalid:path) => {
+        if $header.$field == $invalid {
+            return Err(RsgError::HeaderFieldEncode {
+                field: stringify!($field),
+            });
+        } else {
+            enum_code!($header, $field, $conv)
+        }
+    };
+}
+
+/// The byte ranges of the binary header which do not belong to any field of [`BinHeader`].
+const BIN_HEADER_UNASSIGNED: [Range<usize>; 2] = [100..300, 332..400];
+
 pub trait SegyHeaderToBytes {
     fn as_bytes(&self) -> Result<Vec<u8>, RsgError>;
 }
@@ -26,10 +51,11 @@ impl SegyHeaderToBytes for TapeLabel {
             return Err(RsgError::InvalidHeader { msg });
         }
 
-        let mut max_block_size = vec![0; ml as usize];
+        // The number is right-justified, and the reserved bytes are left blank.
+        let mut max_block_size = vec![b' '; ml as usize];
         max_block_size.extend_from_slice(mbs_string.as_bytes());
 
-        let mut output = Vec::with_capacity(128);
+        let mut output = Vec::with_capacity(TAPE_LABEL_LEN);
         output.extend_from_slice(&self.storage_unit_seq_no);
         output.extend_from_slice(&self.segy_revision_no);
         output.extend_from_slice(&self.storage_unit_structure);
@@ -38,9 +64,16 @@ impl SegyHeaderToBytes for TapeLabel {
         output.extend_from_slice(&self.producing_organisation_code);
         output.extend_from_slice(&self.creation_date);
         output.extend_from_slice(&self.serial_number);
+        debug_assert_eq!(output.len(), 62);
+
+        output.extend_from_slice(&[b' '; 6]); // 63-68 (reserved)
         output.extend_from_slice(&self.external_label);
         output.extend_from_slice(&self.recording_entity);
         output.extend_from_slice(&self.extra);
+        debug_assert_eq!(output.len(), 118);
+
+        output.extend_from_slice(&[b' '; 10]); // 119-128 (reserved)
+        debug_assert_eq!(output.len(), TAPE_LABEL_LEN);
         Ok(output)
     }
 }
@@ -63,6 +96,12 @@ pub(crate) fn write_text_header(header: &str, file: &mut File) -> Result<(), Rsg
 }
 
 impl SegyHeaderToBytes for BinHeader {
+    /// Encode the binary header. Fields which cannot be encoded give an
+    /// [`RsgError::HeaderFieldEncode`] instead of a panic.
+    ///
+    /// NB: Unlike earlier versions, which wrote the start frequency of the sweep to both
+    /// bytes 3233-3234 and 3235-3236, this writes `sweep_frequency_end` to bytes 3235-3236.
+    /// Apart from that, the output is the same as before.
     fn as_bytes(&self) -> Result<Vec<u8>, RsgError> {
         let le = self.binary_flag_direction_is_le;
 
@@ -81,19 +120,55 @@ impl SegyHeaderToBytes for BinHeader {
         } else {
             u32::to_be_bytes
         };
+        let i32_to_b = if le {
+            i32::to_le_bytes
+        } else {
+            i32::to_be_bytes
+        };
+        let u64_to_b = if le {
+            u64::to_le_bytes
+        } else {
+            u64::to_be_bytes
+        };
 
-        let sample_format_code = self.sample_format_code.to_u16().unwrap();
-        let sorting_code = self.sorting_code.to_i16().unwrap();
-        let sweep_type = self.sweep_type.to_u16().unwrap();
-        let taper_type = self.taper_type.to_u16().unwrap();
-        let correlated_traces = self.correlated_traces.to_u16().unwrap();
-        let binary_gain_recovered = self.binary_gain_recovered.to_u16().unwrap();
-        let amplitude_recovery_method = self.amplitude_recovery_method.to_u16().unwrap();
-        let measurement_system = self.measurement_system.to_u16().unwrap();
-        let impulse_signal_polarity = self.impulse_signal_polarity.to_u16().unwrap();
-        let vibratory_polarity_code = self.vibratory_polarity_code.to_u16().unwrap();
-        let fixed_length_trace_flag = self.fixed_length_trace_flag.to_u16().unwrap();
-        let time_basis_code = self.time_basis_code.to_u16().unwrap();
+        let sample_format_code = enum_code!(self, sample_format_code, to_u16);
+        let sorting_code = enum_code!(self, sorting_code, to_i16, TraceSortingCode::Invalid);
+        let sweep_type = enum_code!(self, sweep_type, to_u16, SweepTypeCode::Invalid);
+        let taper_type = enum_code!(self, taper_type, to_u16, TaperType::Invalid);
+        let correlated_traces = enum_code!(
+            self,
+            correlated_traces,
+            to_u16,
+            CorrelatedDataTraces::Invalid
+        );
+        let binary_gain_recovered = enum_code!(
+            self,
+            binary_gain_recovered,
+            to_u16,
+            BinaryGainRecovered::Invalid
+        );
+        let amplitude_recovery_method = enum_code!(
+            self,
+            amplitude_recovery_method,
+            to_u16,
+            AmplitudeRecoveryMethod::Invalid
+        );
+        let measurement_system =
+            enum_code!(self, measurement_system, to_u16, MeasurementSystem::Invalid);
+        let impulse_signal_polarity = enum_code!(
+            self,
+            impulse_signal_polarity,
+            to_u16,
+            ImpulseSignalPolarity::Invalid
+        );
+        let vibratory_polarity_code = enum_code!(
+            self,
+            vibratory_polarity_code,
+            to_u16,
+            VibratoryPolarityCode::Invalid
+        );
+        let fixed_length_trace_flag = enum_code!(self, fixed_length_trace_flag, to_u16);
+        let time_basis_code = enum_code!(self, time_basis_code, to_u16, TimeBasisCode::Invalid);
         let binary_flag_direction_is_le = if le { [1, 2, 3, 4] } else { [4, 3, 2, 1] };
 
         let mut output = Vec::with_capacity(400);
@@ -110,8 +185,8 @@ impl SegyHeaderToBytes for BinHeader {
         output.extend_from_slice(&u16_to_b(self.ensemble_fold)); // 27-28
         output.extend_from_slice(&i16_to_b(sorting_code)); // 29-30 !!NB: i16!!
         output.extend_from_slice(&u16_to_b(self.vertical_sum)); // 31-32
-        output.extend_from_slice(&u16_to_b(self.sweep_frequency_start));
-        output.extend_from_slice(&u16_to_b(self.sweep_frequency_start));
+        output.extend_from_slice(&u16_to_b(self.sweep_frequency_start)); // 33-34
+        output.extend_from_slice(&u16_to_b(self.sweep_frequency_end)); // 35-36
         output.extend_from_slice(&u16_to_b(self.sweep_length)); // 37-38
         output.extend_from_slice(&u16_to_b(sweep_type)); // 39-40
         output.extend_from_slice(&u16_to_b(self.sweep_channel_trace_no));
@@ -124,29 +199,58 @@ impl SegyHeaderToBytes for BinHeader {
         output.extend_from_slice(&u16_to_b(measurement_system)); // 55-56
         output.extend_from_slice(&u16_to_b(impulse_signal_polarity));
         output.extend_from_slice(&u16_to_b(vibratory_polarity_code));
-        // NB: We use only the first 60 bytes and:
-        // [96..100] for the directionality (97-100)
-        // [300-312] for stuff (301-312)
-        // So we must write 36 empty bytes, and then our 4, and then another 200.
-        output.extend_from_slice(&[0; 36]); // 97-100
-        output.extend_from_slice(&binary_flag_direction_is_le);
-
-        output.extend_from_slice(&[0; 200]); // 97-100
+        // The extended (SEG-Y rev 2) fields.
+        output.extend_from_slice(&u32_to_b(self.extended_no_traces)); // 61-64
+        output.extend_from_slice(&u32_to_b(self.extended_no_aux_traces)); // 65-68
+        output.extend_from_slice(&u32_to_b(self.extended_no_samples)); // 69-72
+        output.extend_from_slice(&u64_to_b(self.extended_sample_interval.to_bits())); // 73-80
+        output.extend_from_slice(&u64_to_b(self.extended_sample_interval_original.to_bits())); // 81-88
+        output.extend_from_slice(&u32_to_b(self.extended_no_samples_original)); // 89-92
+        output.extend_from_slice(&u32_to_b(self.extended_ensemble_fold)); // 93-96
+        output.extend_from_slice(&binary_flag_direction_is_le); // 97-100
+
+        // NB: Bytes 101-300 are unassigned.
+        output.extend_from_slice(&[0; 200]);
         debug_assert_eq!(output.len(), 300);
 
         output.extend_from_slice(&self.segy_revision_number); // 301-302
         output.extend_from_slice(&u16_to_b(fixed_length_trace_flag));
-        output.extend_from_slice(&[0, 0]); // 305-306
-        output.extend_from_slice(&u32_to_b(self.extended_header_count));
+        let extended_header_count =
+            u16::try_from(self.extended_header_count).map_err(|_| RsgError::HeaderFieldEncode {
+                field: "extended_header_count",
+            })?;
+        output.extend_from_slice(&u16_to_b(extended_header_count)); // 305-306
+        output.extend_from_slice(&u32_to_b(self.max_additional_trace_headers)); // 307-310
         output.extend_from_slice(&u16_to_b(time_basis_code)); // 311-312
         debug_assert_eq!(output.len(), 312);
 
-        output.extend_from_slice(&[0; 88]);
+        output.extend_from_slice(&u64_to_b(self.no_traces_in_file)); // 313-320
+        output.extend_from_slice(&u64_to_b(self.first_trace_offset)); // 321-328
+        output.extend_from_slice(&i32_to_b(self.no_trailer_stanzas)); // 329-332
+        debug_assert_eq!(output.len(), 332);
+
+        output.extend_from_slice(&[0; 68]);
         debug_assert_eq!(output.len(), 400);
         Ok(output)
     }
 }
 
+/// Convert a binary header to bytes in the same way as [`SegyHeaderToBytes::as_bytes`], but
+/// take the bytes which do not belong to any field of [`BinHeader`] from `raw` (eg. the binary
+/// header of the file which is being copied) instead of leaving them zeroed.
+pub fn bin_header_as_bytes_with_raw(
+    bin_header: &BinHeader,
+    raw: Option<&[u8; BIN_HEADER_LEN]>,
+) -> Result<Vec<u8>, RsgError> {
+    let mut output = bin_header.as_bytes()?;
+    if let Some(raw) = raw {
+        for range in BIN_HEADER_UNASSIGNED.iter().cloned() {
+            output[range.clone()].copy_from_slice(&raw[range]);
+        }
+    }
+    Ok(output)
+}
+
 /// Convert the trace header to bytes.
 ///
 /// * We cannot use [`crate::write_headers::SegyHeaderToBytes`] as we also need values
@@ -155,14 +259,14 @@ impl SegyHeaderToBytes for BinHeader {
 /// * Because of how variable byte positions work, it is possible to use settings to assign
 /// overlapping byte positions or overwrite other fields in the trace header when overriding
 /// byte indices using the settings (if the given settings `S` support overriding byte indices).
-/// Care is therefore advised.
+/// Care is therefore advised. The fields which are overwritten can be found with
+/// [`giga_segy_core::layout::find_bidx_collisions`], and [`crate::SegyFile`] refuses to create
+/// a file with such settings unless they allow it.
 pub fn th_as_bytes_with_settings<S: SegyWriteSettings>(
     trace_header: &TraceHeader,
     settings: &S,
     bin_header: &BinHeader,
 ) -> Result<Vec<u8>, RsgError> {
-    use std::convert::TryInto;
-
     let mut output = vec![0; 240];
 
     let le = bin_header.binary_flag_direction_is_le;
@@ -200,159 +304,174 @@ pub fn th_as_bytes_with_settings<S: SegyWriteSettings>(
         Ok([x[0], x[1], x[2], x[3]])
     };
 
-    let b0_4 = i32_to_b(trace_header.trace_sequence_on_line.to_i32().unwrap());
-    let b4_8 = i32_to_b(trace_header.trace_sequence_in_file.to_i32().unwrap());
-    let b8_12 = i32_to_b(trace_header.field_record_no.to_i32().unwrap());
-    let b12_16 = i32_to_b(trace_header.trace_no.to_i32().unwrap());
-    let b16_20 = i32_to_b(trace_header.energy_source_point_no.to_i32().unwrap());
-    let b20_24 = i32_to_b(trace_header.ensemble_no.to_i32().unwrap());
-    let b24_28 = i32_to_b(trace_header.trace_no_in_ensemble.to_i32().unwrap());
-    let b28_30 = i16_to_b(trace_header.trace_identification_code.to_i16().unwrap());
-    let b30_32 = u16_to_b(trace_header.no_v_summed_traces.to_u16().unwrap());
-    let b32_34 = u16_to_b(trace_header.no_h_stacked_traces.to_u16().unwrap());
-    let b34_36 = u16_to_b(trace_header.data_use.to_u16().unwrap());
+    let b0_4 = i32_to_b(trace_header.trace_sequence_on_line);
+    let b4_8 = i32_to_b(trace_header.trace_sequence_in_file);
+    let b8_12 = i32_to_b(trace_header.field_record_no);
+    let b12_16 = i32_to_b(trace_header.trace_no);
+    let b16_20 = i32_to_b(trace_header.energy_source_point_no);
+    let b20_24 = i32_to_b(trace_header.ensemble_no);
+    let b24_28 = i32_to_b(trace_header.trace_no_in_ensemble);
+    let b28_30 = i16_to_b(enum_code!(
+        trace_header,
+        trace_identification_code,
+        to_i16,
+        TraceIdCode::Invalid
+    ));
+    let b30_32 = u16_to_b(trace_header.no_v_summed_traces);
+    let b32_34 = u16_to_b(trace_header.no_h_stacked_traces);
+    let b34_36 = u16_to_b(enum_code!(trace_header, data_use, to_u16, DataUse::Invalid));
 
     //// These are coordinates. They use the `coord_byter`.
-    let b36_40 = coord_byter(trace_header.source_to_receiver_distance.to_i32().unwrap())?;
-    let b40_44 = coord_byter(trace_header.elevation_of_receiver_group.to_i32().unwrap())?;
-    let b44_48 = coord_byter(trace_header.surface_elevation_of_source.to_i32().unwrap())?;
-    let b48_52 = coord_byter(trace_header.source_depth.to_i32().unwrap())?;
-    let b52_56 = coord_byter(
-        trace_header
-            .datum_elevation_of_receiver_group
-            .to_i32()
-            .unwrap(),
-    )?;
-    let b56_60 = coord_byter(trace_header.datum_elevation_of_source.to_i32().unwrap())?;
-    let b60_64 = coord_byter(trace_header.water_column_height_at_source.to_i32().unwrap())?;
-    let b64_68 = coord_byter(trace_header.water_column_height_at_group.to_i32().unwrap())?;
+    let b36_40 = coord_byter(trace_header.source_to_receiver_distance)?;
+    let b40_44 = coord_byter(trace_header.elevation_of_receiver_group)?;
+    let b44_48 = coord_byter(trace_header.surface_elevation_of_source)?;
+    let b48_52 = coord_byter(trace_header.source_depth)?;
+    let b52_56 = coord_byter(trace_header.datum_elevation_of_receiver_group)?;
+    let b56_60 = coord_byter(trace_header.datum_elevation_of_source)?;
+    let b60_64 = coord_byter(trace_header.water_column_height_at_source)?;
+    let b64_68 = coord_byter(trace_header.water_column_height_at_group)?;
     //////////////////////////////////////
 
-    let b68_70 = i16_to_b(trace_header.elevation_scalar.to_i16().unwrap());
+    let b68_70 = i16_to_b(trace_header.elevation_scalar);
     let b70_72 = i16_to_b(trace_header.coordinate_scalar);
     //// These are coordinates. They use the `coord_byter`.
-    let b72_76 = coord_byter(trace_header.source_x.to_i32().unwrap())?;
-    let b76_80 = coord_byter(trace_header.source_y.to_i32().unwrap())?;
-    let b80_84 = coord_byter(trace_header.receiver_group_x.to_i32().unwrap())?;
-    let b84_88 = coord_byter(trace_header.receiver_group_y.to_i32().unwrap())?;
+    let b72_76 = coord_byter(trace_header.source_x)?;
+    let b76_80 = coord_byter(trace_header.source_y)?;
+    let b80_84 = coord_byter(trace_header.receiver_group_x)?;
+    let b84_88 = coord_byter(trace_header.receiver_group_y)?;
     //////////////////////////////////
-    let b88_90 = u16_to_b(trace_header.coordinate_units.to_u16().unwrap());
-    let b90_92 = u16_to_b(trace_header.weathing_velocity.to_u16().unwrap());
-    let b92_94 = u16_to_b(trace_header.sub_weathering_velocity.to_u16().unwrap());
-    let b94_96 = u16_to_b(trace_header.uphole_time_at_source.to_u16().unwrap());
-    let b96_98 = u16_to_b(trace_header.uphole_time_at_group.to_u16().unwrap());
-    let b98_100 = u16_to_b(trace_header.source_static_correction.to_u16().unwrap());
-
-    let b100_102 = u16_to_b(trace_header.group_static_correction.to_u16().unwrap());
-    let b102_104 = u16_to_b(trace_header.total_static_applied.to_u16().unwrap());
-    let b104_106 = u16_to_b(trace_header.lag_time_a.to_u16().unwrap());
-    let b106_108 = u16_to_b(trace_header.lag_time_b.to_u16().unwrap());
-    let b108_110 = u16_to_b(trace_header.delay_recording_time.to_u16().unwrap());
-    let b110_112 = u16_to_b(trace_header.mute_time_start.to_u16().unwrap());
-    let b112_114 = u16_to_b(trace_header.mute_time_end.to_u16().unwrap());
-    let b114_116 = u16_to_b(trace_header.no_samples_in_trace.to_u16().unwrap());
-    let b116_118 = u16_to_b(trace_header.sample_interval_of_trace.to_u16().unwrap());
-    let b118_120 = u16_to_b(trace_header.gain_type.to_u16().unwrap());
-
-    let b120_122 = u16_to_b(trace_header.instrument_gain_constant.to_u16().unwrap());
-    let b122_124 = u16_to_b(trace_header.instrument_initial_gain.to_u16().unwrap());
-    let b124_126 = u16_to_b(trace_header.correlated.to_u16().unwrap());
-    let b126_128 = u16_to_b(trace_header.sweep_frequency_at_start.to_u16().unwrap());
-    let b128_130 = u16_to_b(trace_header.sweep_frequency_at_end.to_u16().unwrap());
-    let b130_132 = u16_to_b(trace_header.sweep_length.to_u16().unwrap());
-    let b132_134 = u16_to_b(trace_header.sweep_type.to_u16().unwrap());
-    let b134_136 = u16_to_b(
-        trace_header
-            .sweep_trace_taper_length_at_start
-            .to_u16()
-            .unwrap(),
-    );
-    let b136_138 = u16_to_b(
-        trace_header
-            .sweep_trace_taper_length_at_end
-            .to_u16()
-            .unwrap(),
-    );
-    let b138_140 = u16_to_b(trace_header.taper_type.to_u16().unwrap());
-
-    let b140_142 = u16_to_b(trace_header.alias_filter_frequency.to_u16().unwrap());
-    let b142_144 = u16_to_b(trace_header.alias_filter_slope.to_u16().unwrap());
-    let b144_146 = u16_to_b(trace_header.notch_filter_frequency.to_u16().unwrap());
-    let b146_148 = u16_to_b(trace_header.notch_filter_slope.to_u16().unwrap());
-    let b148_150 = u16_to_b(trace_header.low_cut_frequency.to_u16().unwrap());
-    let b150_152 = u16_to_b(trace_header.high_cut_frequency.to_u16().unwrap());
-    let b152_154 = u16_to_b(trace_header.low_cut_slope.to_u16().unwrap());
-    let b154_156 = u16_to_b(trace_header.low_cut_frequency.to_u16().unwrap());
-    let b156_158 = u16_to_b(trace_header.year_recorded.to_u16().unwrap());
-    let b158_160 = u16_to_b(trace_header.day_of_year.to_u16().unwrap());
-
-    let b160_162 = u16_to_b(trace_header.hour_of_day.to_u16().unwrap());
-    let b162_164 = u16_to_b(trace_header.minute_of_hour.to_u16().unwrap());
-    let b164_166 = u16_to_b(trace_header.second_of_minute.to_u16().unwrap());
-    let b166_168 = u16_to_b(trace_header.time_base_code.to_u16().unwrap());
-    let b168_170 = u16_to_b(trace_header.trace_weighting_factor.to_u16().unwrap());
-    let b170_172 = u16_to_b(
-        trace_header
-            .geophone_group_number_roll_pos1
-            .to_u16()
-            .unwrap(),
-    );
-    let b172_174 = u16_to_b(
-        trace_header
-            .geophone_group_number_first_trace_orig_field
-            .to_u16()
-            .unwrap(),
-    );
-    let b174_176 = u16_to_b(
-        trace_header
-            .geophone_group_number_last_trace_orig_field
-            .to_u16()
-            .unwrap(),
-    );
-    let b176_178 = u16_to_b(trace_header.gap_size.to_u16().unwrap());
-    let b178_180 = u16_to_b(trace_header.over_travel.to_u16().unwrap());
+    let b88_90 = u16_to_b(enum_code!(
+        trace_header,
+        coordinate_units,
+        to_u16,
+        CoordinateUnits::Invalid
+    ));
+    let b90_92 = u16_to_b(trace_header.weathing_velocity);
+    let b92_94 = u16_to_b(trace_header.sub_weathering_velocity);
+    let b94_96 = u16_to_b(trace_header.uphole_time_at_source);
+    let b96_98 = u16_to_b(trace_header.uphole_time_at_group);
+    let b98_100 = u16_to_b(trace_header.source_static_correction);
+
+    let b100_102 = u16_to_b(trace_header.group_static_correction);
+    let b102_104 = u16_to_b(trace_header.total_static_applied);
+    let b104_106 = u16_to_b(trace_header.lag_time_a);
+    let b106_108 = u16_to_b(trace_header.lag_time_b);
+    let b108_110 = u16_to_b(trace_header.delay_recording_time);
+    let b110_112 = u16_to_b(trace_header.mute_time_start);
+    let b112_114 = u16_to_b(trace_header.mute_time_end);
+    let b114_116 = u16_to_b(trace_header.no_samples_in_trace);
+    let b116_118 = u16_to_b(trace_header.sample_interval_of_trace);
+    let b118_120 = u16_to_b(enum_code!(
+        trace_header,
+        gain_type,
+        to_u16,
+        GainType::Invalid
+    ));
+
+    let b120_122 = u16_to_b(trace_header.instrument_gain_constant);
+    let b122_124 = u16_to_b(trace_header.instrument_initial_gain);
+    let b124_126 = u16_to_b(enum_code!(
+        trace_header,
+        correlated,
+        to_u16,
+        Correlated::Invalid
+    ));
+    let b126_128 = u16_to_b(trace_header.sweep_frequency_at_start);
+    let b128_130 = u16_to_b(trace_header.sweep_frequency_at_end);
+    let b130_132 = u16_to_b(trace_header.sweep_length);
+    let b132_134 = u16_to_b(enum_code!(
+        trace_header,
+        sweep_type,
+        to_u16,
+        SweepType::Invalid
+    ));
+    let b134_136 = u16_to_b(trace_header.sweep_trace_taper_length_at_start);
+    let b136_138 = u16_to_b(trace_header.sweep_trace_taper_length_at_end);
+    let b138_140 = u16_to_b(enum_code!(
+        trace_header,
+        taper_type,
+        to_u16,
+        TaperType::Invalid
+    ));
+
+    let b140_142 = u16_to_b(trace_header.alias_filter_frequency);
+    let b142_144 = u16_to_b(trace_header.alias_filter_slope);
+    let b144_146 = u16_to_b(trace_header.notch_filter_frequency);
+    let b146_148 = u16_to_b(trace_header.notch_filter_slope);
+    let b148_150 = u16_to_b(trace_header.low_cut_frequency);
+    let b150_152 = u16_to_b(trace_header.high_cut_frequency);
+    let b152_154 = u16_to_b(trace_header.low_cut_slope);
+    let b154_156 = u16_to_b(trace_header.high_cut_slope);
+    let b156_158 = u16_to_b(trace_header.year_recorded);
+    let b158_160 = u16_to_b(trace_header.day_of_year);
+
+    let b160_162 = u16_to_b(trace_header.hour_of_day);
+    let b162_164 = u16_to_b(trace_header.minute_of_hour);
+    let b164_166 = u16_to_b(trace_header.second_of_minute);
+    let b166_168 = u16_to_b(enum_code!(
+        trace_header,
+        time_base_code,
+        to_u16,
+        TimeBasisCode::Invalid
+    ));
+    let b168_170 = u16_to_b(trace_header.trace_weighting_factor);
+    let b170_172 = u16_to_b(trace_header.geophone_group_number_roll_pos1);
+    let b172_174 = u16_to_b(trace_header.geophone_group_number_first_trace_orig_field);
+    let b174_176 = u16_to_b(trace_header.geophone_group_number_last_trace_orig_field);
+    let b176_178 = u16_to_b(trace_header.gap_size);
+    let b178_180 = u16_to_b(enum_code!(
+        trace_header,
+        over_travel,
+        to_u16,
+        OverTravel::Invalid
+    ));
 
     // REDO WITH COORDINATE PARSER!!!!!!!
     let x_ensemble_bytes = coord_byter(trace_header.x_ensemble)?;
     let y_ensemble_bytes = coord_byter(trace_header.y_ensemble)?;
     //////////////////////////////////////////////
 
-    let inline_no_bytes = i32_to_b(trace_header.inline_no.to_i32().unwrap());
-    let xline_no_bytes = i32_to_b(trace_header.crossline_no.to_i32().unwrap());
-
-    let b196_200 = i32_to_b(trace_header.shot_point_no.to_i32().unwrap());
-    let b200_202 = u16_to_b(trace_header.shot_point_scalar.to_u16().unwrap());
-    let b202_204 = i16_to_b(trace_header.trace_value_measurement_unit.to_i16().unwrap());
-    let b204_208 = i32_to_b(
-        trace_header
-            .transduction_constant_mantissa
-            .to_i32()
-            .unwrap(),
-    );
-    let b208_210 = u16_to_b(trace_header.transduction_constant_power.to_u16().unwrap());
-    let b210_212 = i16_to_b(trace_header.transduction_units.to_i16().unwrap());
-    let b212_214 = u16_to_b(trace_header.trace_identifier.to_u16().unwrap());
-    let b214_216 = u16_to_b(trace_header.time_scalar_trace_header.to_u16().unwrap());
-    let b216_218 = i16_to_b(trace_header.source_type.to_i16().unwrap());
-    let b218_220 = u16_to_b(trace_header.source_energy_direction_v.to_u16().unwrap());
-
-    let b220_222 = u16_to_b(trace_header.source_energy_direction_il.to_u16().unwrap());
-    let b222_224 = u16_to_b(trace_header.source_energy_direction_xl.to_u16().unwrap());
-    let b224_228 = i32_to_b(trace_header.source_measurement_mantissa.to_i32().unwrap());
-    let b228_230 = u16_to_b(trace_header.transduction_constant_power.to_u16().unwrap());
-    let b230_232 = i16_to_b(trace_header.source_measurement_unit.to_i16().unwrap());
-    let b232_240 = if le {
-        trace_header.trace_name
-    } else {
-        trace_header
-            .trace_name
-            .iter()
-            .copied()
-            .rev()
-            .collect::<Vec<u8>>()
-            .try_into()
-            .expect("The arrays are the same length")
-    };
+    let inline_no_bytes = i32_to_b(trace_header.inline_no);
+    let xline_no_bytes = i32_to_b(trace_header.crossline_no);
+
+    let b196_200 = i32_to_b(trace_header.shot_point_no);
+    let b200_202 = i16_to_b(trace_header.shot_point_scalar);
+    let b202_204 = i16_to_b(enum_code!(
+        trace_header,
+        trace_value_measurement_unit,
+        to_i16,
+        TraceValueUnit::Invalid
+    ));
+    let b204_208 = i32_to_b(trace_header.transduction_constant_mantissa);
+    let b208_210 = u16_to_b(trace_header.transduction_constant_power);
+    let b210_212 = i16_to_b(enum_code!(
+        trace_header,
+        transduction_units,
+        to_i16,
+        TransductionUnits::Invalid
+    ));
+    let b212_214 = u16_to_b(trace_header.trace_identifier);
+    let b214_216 = u16_to_b(trace_header.time_scalar_trace_header);
+    let b216_218 = i16_to_b(enum_code!(
+        trace_header,
+        source_type,
+        to_i16,
+        SourceType::Invalid
+    ));
+    let b218_220 = u16_to_b(trace_header.source_energy_direction_v);
+
+    let b220_222 = u16_to_b(trace_header.source_energy_direction_il);
+    let b222_224 = u16_to_b(trace_header.source_energy_direction_xl);
+    let b224_228 = i32_to_b(trace_header.source_measurement_mantissa);
+    let b228_230 = u16_to_b(trace_header.source_measurement_exponent);
+    let b230_232 = i16_to_b(enum_code!(
+        trace_header,
+        source_measurement_unit,
+        to_i16,
+        SourceMeasurementUnit::Invalid
+    ));
+    // The trace name is a string of characters, so it has no byte order.
+    let b232_240 = trace_header.trace_name;
 
     array_cpy(&mut output, &b0_4, 0);
     array_cpy(&mut output, &b4_8, 4);
@@ -419,7 +538,7 @@ pub fn th_as_bytes_with_settings<S: SegyWriteSettings>(
     array_cpy(&mut output, &b152_154, 152);
     array_cpy(&mut output, &b154_156, 154);
     array_cpy(&mut output, &b156_158, 156);
-    array_cpy(&mut output, &b158_160, 150);
+    array_cpy(&mut output, &b158_160, 158);
     array_cpy(&mut output, &b160_162, 160);
 
     array_cpy(&mut output, &b162_164, 162);